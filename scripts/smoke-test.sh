@@ -31,6 +31,10 @@ echo "Building with duckdb feature (no tui)..."
 cargo build --no-default-features --features duckdb --bin slack-utils-duckdb
 echo "Building with server feature (no tui)..."
 cargo build --no-default-features --features server --bin slack-archive-server
+echo "Building server with the duckdb SQL endpoint (no tui)..."
+cargo build --no-default-features --features server,duckdb --bin slack-archive-server
+echo "Building with the datafusion sql subcommand..."
+cargo build --features tui,datafusion
 
 echo ""
 echo "=== Running tests (features tested separately) ==="
@@ -40,6 +44,10 @@ echo "Testing with duckdb feature (no tui)..."
 cargo test --no-default-features --features duckdb
 echo "Testing with server feature (no tui)..."
 cargo test --no-default-features --features server
+echo "Testing server with duckdb (no tui)..."
+cargo test --no-default-features --features server,duckdb --bin slack-archive-server
+echo "Testing with datafusion..."
+cargo test --features tui,datafusion
 
 echo ""
 echo "=== Running clippy (features checked separately) ==="
@@ -49,6 +57,10 @@ echo "Clippy with duckdb feature (no tui)..."
 cargo clippy --no-default-features --features duckdb
 echo "Clippy with server feature (no tui)..."
 cargo clippy --no-default-features --features server
+echo "Clippy server with duckdb (no tui)..."
+cargo clippy --no-default-features --features server,duckdb --bin slack-archive-server
+echo "Clippy with datafusion..."
+cargo clippy --features tui,datafusion
 
 echo ""
 echo "=== Testing slack-utils --help ==="
@@ -60,14 +72,40 @@ cargo run -- ui --help
 cargo run -- export-conversations --help
 cargo run -- export-conversations-week --help
 cargo run -- archive-range --help
+cargo run -- archive-extract --help
+cargo run -- archive-compact --help
+cargo run -- archive-vacuum --help
+cargo run -- archive-info --help
+cargo run -- archive-sync --help
+cargo run -- archive-daemon --help
+cargo run -- live-archive --help
+cargo run -- post-message --help
+cargo run -- digest --help
+cargo run -- encrypt-settings --help
 cargo run -- export-users --help
 cargo run -- export-channels --help
 cargo run -- download-attachments --help
 cargo run -- export-markdown --help
 cargo run -- export-emojis --help
+cargo run -- export-emoji-pack --help
+cargo run -- stats --help
+cargo run -- activity --help
+cargo run -- links --help
+cargo run -- diff --help
+cargo run -- merge --help
+cargo run -- backfill-threads --help
+cargo run -- convert-to --help
+cargo run -- export-corpus --help
+cargo run -- split --help
+cargo run -- validate --help
 cargo run -- export-index --help
 cargo run -- import-index-meilisearch --help
 cargo run -- query-meilisearch --help
+cargo run -- index-tantivy --help
+cargo run -- query-tantivy --help
+cargo run -- summarize --help
+cargo run -- build-site --help
+cargo run -- md-to-html --help
 
 echo ""
 echo "=== Testing slack-utils-duckdb --help ==="
@@ -122,6 +160,11 @@ cat > "$TEMP_DIR/selected-conversations.json" << 'SELECTED_EOF'
 ]
 SELECTED_EOF
 
+echo ""
+echo "=== Testing download-attachments --dry-run with fixtures ==="
+cargo run -- download-attachments --input "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/attachments-dry" --dry-run \
+    | grep -q '^Dry run:' && test ! -e "$TEMP_DIR/attachments-dry" && echo "download-attachments --dry-run: OK"
+
 echo ""
 echo "=== Testing export-index with fixtures ==="
 cargo run -- export-index \
@@ -129,7 +172,91 @@ cargo run -- export-index \
     --users "$TEMP_DIR/users.json" \
     --channels "$TEMP_DIR/channels.json" \
     --output "$TEMP_DIR/conversation-index.json"
-test -f "$TEMP_DIR/conversation-index.json" && echo "export-index: OK"
+test -f "$TEMP_DIR/conversation-index.json" && grep -q '"has_attachments"' "$TEMP_DIR/conversation-index.json" && echo "export-index: OK"
+
+cargo run -- export-index \
+    --conversations "$TEMP_DIR/conversations.json" \
+    --users "$TEMP_DIR/users.json" \
+    --channels "$TEMP_DIR/channels.json" \
+    --output "$TEMP_DIR/conversation-index.sqlite" \
+    --format sqlite-fts
+test -s "$TEMP_DIR/conversation-index.sqlite" && echo "export-index (sqlite-fts): OK"
+
+echo ""
+echo "=== Testing stats with fixtures ==="
+cargo run -- stats \
+    --conversations "$TEMP_DIR/conversations.json" \
+    --users "$TEMP_DIR/users.json" \
+    --output "$TEMP_DIR/stats" \
+    --report "$TEMP_DIR/stats.md"
+grep -q '"by_hour"' "$TEMP_DIR/stats.json" && grep -q 'testuser' "$TEMP_DIR/stats.md" && echo "stats: OK"
+cargo run -- stats --conversations "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/stats" --format csv
+test -f "$TEMP_DIR/stats-channels.csv" && grep -q '^C001,general,1' "$TEMP_DIR/stats-channels.csv" && echo "stats (csv): OK"
+cargo run -- stats --conversations "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/stats" --json 2>/dev/null \
+    | grep -q '"command":"stats","ok":true' && echo "--json: OK"
+
+echo ""
+echo "=== Testing activity with fixtures ==="
+cargo run -- activity \
+    --conversations "$TEMP_DIR/conversations.json" \
+    --output "$TEMP_DIR/activity.json" \
+    --svg "$TEMP_DIR/heatmap.svg"
+grep -q '"heatmap"' "$TEMP_DIR/activity.json" && grep -q '<svg' "$TEMP_DIR/heatmap.svg" && echo "activity: OK"
+
+echo ""
+echo "=== Testing links with fixtures ==="
+cargo run -- links --conversations "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/links.json" --report "$TEMP_DIR/links.md"
+grep -q '"domains"' "$TEMP_DIR/links.json" && test -f "$TEMP_DIR/links.md" && echo "links: OK"
+
+echo ""
+echo "=== Testing diff with fixtures ==="
+cargo run -- diff --old "$TEMP_DIR/conversations.json" --new "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/diff.json" --report "$TEMP_DIR/diff.md"
+grep -q '"added": \[\]' "$TEMP_DIR/diff.json" && test -f "$TEMP_DIR/diff.md" && echo "diff: OK"
+
+echo ""
+echo "=== Testing merge with fixtures ==="
+cargo run -- merge --inputs "$TEMP_DIR/conversations.json" "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/merged.json"
+cargo run -- diff --old "$TEMP_DIR/conversations.json" --new "$TEMP_DIR/merged.json" --output "$TEMP_DIR/merge-diff.json" | grep -q "No changes" && echo "merge: OK"
+
+echo ""
+echo "=== Testing export-corpus with fixtures ==="
+cargo run -- export-corpus --conversations "$TEMP_DIR/conversations.json" --anonymize --output "$TEMP_DIR/corpus.jsonl"
+grep -q '"participants":\["user1"' "$TEMP_DIR/corpus.jsonl" && echo "export-corpus: OK"
+
+echo ""
+echo "=== Testing split with fixtures ==="
+cargo run -- split --conversations "$TEMP_DIR/conversations.json" --by channel --output "$TEMP_DIR/split-channel"
+test -f "$TEMP_DIR/split-channel/general.json" && echo "split by channel: OK"
+cargo run -- split --conversations "$TEMP_DIR/conversations.json" --by month --output "$TEMP_DIR/split-month"
+test -f "$TEMP_DIR/split-month/2023-11.json" && echo "split by month: OK"
+
+echo ""
+echo "=== Testing convert-to with fixtures ==="
+cargo run -- convert-to --target matrix --conversations "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/matrix"
+grep -q '"m.room.message"' "$TEMP_DIR/matrix/general.json" && echo "convert-to matrix: OK"
+cargo run -- convert-to --target discord --conversations "$TEMP_DIR/conversations.json" --output "$TEMP_DIR/discord"
+grep -q '"GuildTextChat"' "$TEMP_DIR/discord/general.json" && echo "convert-to discord: OK"
+cargo run -- convert-to --target mattermost --conversations "$TEMP_DIR/conversations.json" --users "$TEMP_DIR/users.json" --channels "$TEMP_DIR/channels.json" --output "$TEMP_DIR/mattermost"
+grep -q '"username":"testuser"' "$TEMP_DIR/mattermost/mattermost-import.jsonl" && echo "convert-to mattermost: OK"
+
+echo ""
+echo "=== Testing backfill-threads with fixtures ==="
+cargo run -- backfill-threads --conversations "$TEMP_DIR/conversations.json" | grep -q "No threads with missing replies" && echo "backfill-threads: OK"
+
+echo ""
+echo "=== Testing validate with fixtures ==="
+cargo run -- validate --conversations "$TEMP_DIR/conversations.json" && echo "validate: OK"
+echo '[{"id": "U1"}, {"id": "U1", "name": "dup"}]' > "$TEMP_DIR/bad-users.json"
+if cargo run -- validate --users "$TEMP_DIR/bad-users.json"; then
+    echo "validate should fail on bad users" && exit 1
+fi
+echo "validate failure: OK"
+
+echo ""
+echo "=== Testing index-tantivy / query-tantivy with fixtures ==="
+cargo run -- index-tantivy --input "$TEMP_DIR/conversation-index.json" --output "$TEMP_DIR/tantivy-index"
+test -f "$TEMP_DIR/tantivy-index/meta.json" && echo "index-tantivy: OK"
+cargo run -- query-tantivy "Hello" --index "$TEMP_DIR/tantivy-index" | grep -q "#general" && echo "query-tantivy: OK"
 
 echo ""
 echo "=== Testing export-markdown with fixtures ==="
@@ -140,6 +267,37 @@ cargo run -- export-markdown \
     --output "$TEMP_DIR/output.md"
 test -f "$TEMP_DIR/output.md" && echo "export-markdown: OK"
 
+echo ""
+echo "=== Testing md-to-html with fixtures ==="
+cargo run -- md-to-html "$TEMP_DIR/output.md" --output "$TEMP_DIR/output.html"
+test -f "$TEMP_DIR/output.html" && echo "md-to-html: OK"
+
+mkdir -p "$TEMP_DIR/md-in/nested"
+cp "$TEMP_DIR/output.md" "$TEMP_DIR/md-in/nested/output.md"
+echo "[nested](nested/output.md)" > "$TEMP_DIR/md-in/index.md"
+cargo run -- md-to-html --input-dir "$TEMP_DIR/md-in" --output-dir "$TEMP_DIR/md-out"
+test -f "$TEMP_DIR/md-out/nested/output.html" && grep -q 'nested/output.html' "$TEMP_DIR/md-out/index.html" && echo "md-to-html (dir): OK"
+
+echo "body { font-family: sans-serif; }" > "$TEMP_DIR/style.css"
+cargo run -- md-to-html "$TEMP_DIR/output.md" --output "$TEMP_DIR/page.html" --css "$TEMP_DIR/style.css"
+grep -q '<!DOCTYPE html>' "$TEMP_DIR/page.html" && grep -q 'font-family' "$TEMP_DIR/page.html" && echo "md-to-html (page): OK"
+
+printf '```rust\nfn main() {}\n```\n' > "$TEMP_DIR/code.md"
+cargo run -- md-to-html "$TEMP_DIR/code.md" --highlight
+grep -q '<span style=' "$TEMP_DIR/code.html" && echo "md-to-html (highlight): OK"
+
+cargo run -- export-markdown -c - -u "$TEMP_DIR/users.json" --channels "$TEMP_DIR/channels.json" -o - < "$TEMP_DIR/selected-conversations.json" \
+    | cargo run -- md-to-html - > "$TEMP_DIR/piped.html"
+grep -q '<h' "$TEMP_DIR/piped.html" && echo "export-markdown | md-to-html (stdio): OK"
+
+echo ""
+echo "=== Testing export-emoji-pack with fixtures ==="
+mkdir -p "$TEMP_DIR/emoji-images"
+printf 'GIF89a' > "$TEMP_DIR/emoji-images/party.gif"
+echo '{"party": "https://emoji.slack-edge.com/T1/party/abc.gif", "tada": "alias:party"}' > "$TEMP_DIR/emoji-list.json"
+cargo run -- export-emoji-pack --emojis "$TEMP_DIR/emoji-list.json" --folder "$TEMP_DIR/emoji-images" --output "$TEMP_DIR/emoji-pack"
+test -f "$TEMP_DIR/emoji-pack/party.gif" && grep -q '"tada"' "$TEMP_DIR/emoji-pack/emojipack.yaml" && echo "export-emoji-pack: OK"
+
 echo ""
 echo "=== Testing commands that require SLACK_TOKEN ==="
 
@@ -152,9 +310,15 @@ if [ -n "$SLACK_TOKEN" ]; then
     cargo run -- export-channels --output "$TEMP_DIR/channels-export"
     test -f "$TEMP_DIR/channels-export.json" && echo "export-channels: OK"
 
+    cargo run -- export-channels --output "$TEMP_DIR/channels-logged" --log-file "$TEMP_DIR/slack-utils.log"
+    grep -q 'conversations.list' "$TEMP_DIR/slack-utils.log" && echo "export-channels (--log-file): OK"
+
     cargo run -- export-conversations --output "$TEMP_DIR/conv-export"
     test -f "$TEMP_DIR/conv-export.json" && echo "export-conversations: OK"
 
+    cargo run -- export-conversations --output "$TEMP_DIR/conv-dry" --dry-run | grep -q '^Dry run:' \
+        && test ! -e "$TEMP_DIR/conv-dry.json" && echo "export-conversations --dry-run: OK"
+
     cargo run -- export-conversations-week --output "$TEMP_DIR/conv-week-export"
     test -f "$TEMP_DIR/conv-week-export.json" && echo "export-conversations-week: OK"
 
@@ -175,6 +339,40 @@ if [ -n "$SLACK_TOKEN" ]; then
         --output "$TEMP_DIR/archive"
     test -d "$TEMP_DIR/archive" && echo "archive-range: OK"
 
+    # The weeks just archived are listed as skipped
+    cargo run -- archive-range \
+        --from-year "$FROM_YEAR" --from-week "$FROM_WEEK" \
+        --to-year "$CURRENT_YEAR" --to-week "$CURRENT_WEEK" \
+        --output "$TEMP_DIR/archive" --dry-run | grep -q 'already archived' && echo "archive-range --dry-run: OK"
+
+    # The archive keeps its week layout; asking for another one fails
+    if cargo run -- archive-range \
+        --from-year "$FROM_YEAR" --from-week "$FROM_WEEK" \
+        --output "$TEMP_DIR/archive" --partition-by channel --dry-run 2>/dev/null; then
+        echo "archive-range --partition-by: FAILED (mixed layouts accepted)"
+        exit 1
+    fi
+    echo "archive-range --partition-by: OK"
+
+    if find "$TEMP_DIR/archive" -name '*.parquet' | grep -q .; then
+        cargo run -- archive-info --archive "$TEMP_DIR/archive" --files | grep -q 'Written by: slack-utils' && echo "archive-info: OK"
+        cargo run -- archive-compact --archive "$TEMP_DIR/archive" --by month --output "$TEMP_DIR/archive-monthly"
+        find "$TEMP_DIR/archive-monthly" -path '*month=*' -name threads.parquet | grep -q . && echo "archive-compact: OK"
+        ARCHIVED_WEEK=$(find "$TEMP_DIR/archive" -name threads.parquet | head -1 | sed -E 's#.*/year=([0-9]+)/week=([0-9]+)/.*#\1/W\2#')
+        cargo run -- stats --conversations "archive:$TEMP_DIR/archive/$ARCHIVED_WEEK" --output "$TEMP_DIR/archive-stats"
+        test -f "$TEMP_DIR/archive-stats.json" && echo "archive: input: OK"
+    fi
+
+    cargo run -- build-site --archive "$TEMP_DIR/archive" --users "$TEMP_DIR/users.json" --channels "$TEMP_DIR/channels.json" --output "$TEMP_DIR/site"
+    test -f "$TEMP_DIR/site/index.html" && test -f "$TEMP_DIR/site/search.html" && echo "build-site: OK"
+    cargo run -- build-site --archive "$TEMP_DIR/archive" --users "$TEMP_DIR/users.json" --channels "$TEMP_DIR/channels.json" --output "$TEMP_DIR/site-quiet" --quiet > "$TEMP_DIR/quiet.out"
+    ! grep -q '^  \[' "$TEMP_DIR/quiet.out" && test -f "$TEMP_DIR/site-quiet/index.html" && echo "--quiet: OK"
+
+    # A local destination exercises the same checksum comparison as a bucket
+    cargo run -- archive-sync --archive "$TEMP_DIR/archive" --users "$TEMP_DIR/users.json" --dest "$TEMP_DIR/archive-copy" --dry-run
+    cargo run -- archive-sync --archive "$TEMP_DIR/archive" --users "$TEMP_DIR/users.json" --dest "$TEMP_DIR/archive-copy"
+    test -f "$TEMP_DIR/archive-copy/sync-manifest.json" && test -f "$TEMP_DIR/archive-copy/users.json" && echo "archive-sync: OK"
+
     # download-attachments needs a conversations file with actual attachments, skip for now
     echo "download-attachments: SKIPPED (requires conversations with attachments)"
 else
@@ -185,6 +383,8 @@ else
     echo "  - export-conversations-week: SKIPPED"
     echo "  - export-emojis: SKIPPED"
     echo "  - archive-range: SKIPPED"
+    echo "  - build-site: SKIPPED"
+    echo "  - archive-sync: SKIPPED"
     echo "  - download-attachments: SKIPPED"
 fi
 
@@ -212,5 +412,16 @@ else
     echo "conversations directory not found, skipping"
 fi
 
+echo ""
+echo "=== Testing slack-utils sql (datafusion) ==="
+cargo run --features tui,datafusion -- sql --help
+
+if [ -d "conversations" ]; then
+    cargo run --features tui,datafusion -- sql "SELECT COUNT(*) FROM messages" --format csv
+    echo "sql conversations parquet: OK"
+else
+    echo "conversations directory not found, skipping"
+fi
+
 echo ""
 echo "=== All smoke tests passed ==="
@@ -1,24 +1,31 @@
+use std::collections::HashSet;
 use std::path::Path;
 use std::sync::mpsc;
 use std::thread;
 
-use ratatui::widgets::ListState;
+use chrono::NaiveDate;
+use ratatui::widgets::{ListState, TableState};
+use tokio_util::sync::CancellationToken;
 
+use crate::emojis::load_emoji_catalog;
 use crate::error::AppError;
 use crate::formatter::MarkdownExportOptions;
-use crate::index::export_conversations_to_index_with_progress;
-use crate::markdown::export_conversations_to_markdown_with_options;
+use crate::index::{export_conversations_to_index_with_progress, IndexFormat};
+use crate::markdown::{export_conversation_values_to_markdown, export_conversations_to_markdown_with_options};
 use crate::meilisearch::import_index_to_meilisearch;
+use crate::pipeline::{run_pipeline, MeilisearchTarget, PipelineOptions};
 use crate::settings::Settings;
 use crate::slack;
 use crate::ui::types::{
-    ArchiveRangeField, AsyncResult, ChannelSelection, ConvExportField, ConvExportWeekField,
-    ExportResult, ExportTask, MenuItem, Screen,
+    ArchiveRangeField, AsyncResult, BulkToggle, ChannelSelection, ConvExportField, ConvExportWeekField,
+    DownloadReport, EditableChannelList, EmojiBrowser, ExportResult, ExportTask, LocalSearchSource, MenuItem,
+    OverwriteChoice, PipelineField, PipelineTarget, PipelineTargets, Screen, SearchLocalField, SelectionPicker, TaskLog,
+    UndoStack,
 };
-use crate::widgets::TextInput;
+use crate::widgets::{DatePicker, TextInput};
 use crate::{
     current_iso_week, default_from_date, default_to_date, parse_date, week_to_date_range,
-    SlackApiCallbacks, CHANNELS_FILE,
+    ExportProgress, ProgressEvent, SlackApiCallbacks, TransferProgress, CHANNELS_FILE,
 };
 
 pub struct App {
@@ -27,16 +34,52 @@ pub struct App {
     pub should_quit: bool,
     pub token: String,
     pub async_result_rx: Option<mpsc::Receiver<AsyncResult>>,
-    pub progress_rx: Option<mpsc::Receiver<(usize, usize, String)>>,
+    /// Progress events of the running task
+    pub events_rx: Option<mpsc::Receiver<ProgressEvent>>,
+    /// Nested progress of a running conversation export
+    pub export_progress: Option<ExportProgress>,
+    /// Latest byte progress of a running download
+    pub transfer_progress: Option<TransferProgress>,
+    /// Activity of background tasks, shown in the log pane
+    pub log: TaskLog,
+    /// Candidates of the last ambiguous path completion, shown until the next key
+    pub path_candidates: Vec<String>,
+    /// Position in the focused field's history while cycling with Up/Down,
+    /// and the text it had before
+    pub history_cursor: Option<(usize, String)>,
+    /// Calendar popup for the focused date or week fields, owns the keys while open
+    pub date_picker: Option<DatePicker>,
+    /// Markdown file written by the running or last task, offered for preview
+    /// on its success screen
+    pub markdown_output: Option<String>,
+    /// Edits of the conversation editor session, for Ctrl+Z/Ctrl+Y
+    pub edit_undo: UndoStack<EditableChannelList>,
+    /// Open bulk enable/disable prompt of the conversation editor
+    pub bulk_toggle: Option<BulkToggle>,
+    /// Saved channel selections popup of the focused channel list
+    pub selection_picker: Option<SelectionPicker>,
+    /// Set by Esc on the loading screen to stop the running task
+    pub cancel: Option<CancellationToken>,
     pub settings: Settings,
+    /// Runtime the background tasks' requests run on
+    pub runtime: tokio::runtime::Handle,
 }
 
 impl App {
-    pub fn new(token: String) -> Self {
+    pub fn new(token: String, runtime: tokio::runtime::Handle) -> Self {
         let mut menu_state = ListState::default();
         menu_state.select(Some(0));
 
-        let settings = Settings::load().unwrap_or_default();
+        let settings = Settings::load_for_profile().unwrap_or_default();
+        let mut log = TaskLog::default();
+        if !settings.locked_secrets.is_empty() {
+            let fields: Vec<&str> = settings.locked_secrets.keys().map(String::as_str).collect();
+            log.push(&format!(
+                "{} in settings.toml stay encrypted: {} is unset or wrong",
+                fields.join(", "),
+                crate::secrets::PASSPHRASE_ENV
+            ));
+        }
 
         Self {
             screen: Screen::MainMenu,
@@ -44,8 +87,20 @@ impl App {
             should_quit: false,
             token,
             async_result_rx: None,
-            progress_rx: None,
+            events_rx: None,
+            export_progress: None,
+            transfer_progress: None,
+            log,
+            path_candidates: Vec::new(),
+            history_cursor: None,
+            date_picker: None,
+            markdown_output: None,
+            edit_undo: UndoStack::new(),
+            bulk_toggle: None,
+            selection_picker: None,
+            cancel: None,
             settings,
+            runtime,
         }
     }
 
@@ -84,34 +139,136 @@ impl App {
         MenuItem::all()[idx]
     }
 
+    /// Start `task` from the current form screen: missing input files show an
+    /// error and existing output files ask for confirmation first
+    pub fn run_task(&mut self, mut task: ExportTask, message: String) {
+        self.remember_values(&task.history_values());
+
+        if let Some(missing) = task.input_paths().into_iter().find(|p| !Path::new(p).is_file()) {
+            self.screen = Screen::Error {
+                message: AppError::MissingInput(missing.to_string()).to_string(),
+            };
+            return;
+        }
+
+        let existing: Vec<String> = task
+            .output_paths_mut()
+            .into_iter()
+            .filter(|p| Path::new(p.as_str()).is_file())
+            .map(|p| p.clone())
+            .collect();
+        if existing.is_empty() {
+            self.screen = Screen::Loading { message, progress: None };
+            self.start_task(task);
+        } else {
+            let previous = Box::new(std::mem::replace(&mut self.screen, Screen::MainMenu));
+            self.screen = Screen::ConfirmOverwrite {
+                task,
+                message,
+                existing,
+                previous,
+            };
+        }
+    }
+
+    /// Answer the overwrite confirmation: start the task as is, start it with
+    /// timestamped names for the existing outputs, or go back to the form
+    pub fn confirm_overwrite(&mut self, choice: OverwriteChoice) {
+        let screen = std::mem::replace(&mut self.screen, Screen::MainMenu);
+        let Screen::ConfirmOverwrite {
+            mut task,
+            message,
+            previous,
+            ..
+        } = screen
+        else {
+            self.screen = screen;
+            return;
+        };
+
+        match choice {
+            OverwriteChoice::Cancel => self.screen = *previous,
+            OverwriteChoice::Overwrite | OverwriteChoice::Timestamp => {
+                if choice == OverwriteChoice::Timestamp {
+                    let now = chrono::Local::now().naive_local();
+                    for path in task.output_paths_mut() {
+                        if Path::new(path.as_str()).is_file() {
+                            *path = crate::timestamped_path(path, now);
+                        }
+                    }
+                }
+                self.screen = Screen::Loading { message, progress: None };
+                self.start_task(task);
+            }
+        }
+    }
+
+    /// Add submitted field values to their history and persist it
+    pub fn remember_values(&mut self, values: &[(&str, String)]) {
+        for (key, value) in values {
+            self.settings.remember(key, value);
+        }
+        let _ = self.settings.save();
+    }
+
+    /// Replace the focused field with an older (`older`) or newer entry of
+    /// its history; past the newest entry the text typed before comes back
+    pub fn cycle_history(&mut self, older: bool) {
+        let Some((key, field)) = self.screen.active_text_field() else {
+            return;
+        };
+        let values = self.settings.history(key);
+        let (pos, draft) = match self.history_cursor.take() {
+            Some((pos, draft)) => (Some(pos), draft),
+            None => (None, field.text().to_string()),
+        };
+        let next = match (pos, older) {
+            (None, true) if !values.is_empty() => Some(0),
+            (Some(pos), true) => Some((pos + 1).min(values.len().saturating_sub(1))),
+            (Some(pos), false) => pos.checked_sub(1),
+            (None, _) => return,
+        };
+        match next.and_then(|pos| values.get(pos).map(|value| (pos, value))) {
+            Some((pos, value)) => {
+                field.set_text(value.clone());
+                self.history_cursor = Some((pos, draft));
+            }
+            None => field.set_text(draft),
+        }
+    }
+
     pub fn start_task(&mut self, task: ExportTask) {
+        self.markdown_output = match &task {
+            ExportTask::MarkdownExport { output_path, .. } => Some(output_path.clone()),
+            _ => None,
+        };
+
         let (tx, rx) = mpsc::channel();
         self.async_result_rx = Some(rx);
 
+        let cancel = CancellationToken::new();
+        self.cancel = task.is_cancellable().then(|| cancel.clone());
+
         let token = self.token.clone();
 
-        let (progress_tx, progress_rx) = mpsc::channel();
-        self.progress_rx = Some(progress_rx);
+        let (events, events_rx) = mpsc::channel();
+        self.events_rx = Some(events_rx);
+        self.export_progress = None;
+        self.transfer_progress = None;
 
+        // The task gets a thread of its own since the local conversions are
+        // blocking and their progress callbacks aren't `Send`; its async
+        // parts run on the shared runtime
+        let rt = self.runtime.clone();
         thread::spawn(move || {
-            let rt = match tokio::runtime::Runtime::new() {
-                Ok(rt) => rt,
-                Err(e) => {
-                    let _ = tx.send(AsyncResult::ExportComplete(Err(format!(
-                        "Failed to create async runtime: {}",
-                        e
-                    ))));
-                    return;
-                }
-            };
             match task {
                 ExportTask::Users { output_path, format } => {
                     let result = rt.block_on(async {
                         let count = slack::export_users(&token, Path::new(&output_path), format).await?;
                         Ok::<_, AppError>(format!("Exported {} users to {}", count, output_path))
                     });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
+                    let _ = tx.send(export_complete(
+                        result.map(|msg| ExportResult { message: msg, details: None }),
                     ));
                 }
                 ExportTask::Channels { output_path, format } => {
@@ -119,8 +276,8 @@ impl App {
                         let count = slack::export_channels(&token, Path::new(&output_path), format).await?;
                         Ok::<_, AppError>(format!("Exported {} channels to {}", count, output_path))
                     });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
+                    let _ = tx.send(export_complete(
+                        result.map(|msg| ExportResult { message: msg, details: None }),
                     ));
                 }
                 ExportTask::Conversations {
@@ -130,20 +287,7 @@ impl App {
                     selected_channels,
                     format,
                 } => {
-                    let progress_tx_clone = progress_tx.clone();
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx_clone.send((current, total, name.to_string()));
-                    };
-                    let rate_limit_callback = |wait_secs: u64, attempt: u32, max: u32| {
-                        let _ = progress_tx.send((
-                            attempt as usize,
-                            max as usize,
-                            format!("Rate limited, waiting {}s...", wait_secs),
-                        ));
-                    };
-                    let callbacks = SlackApiCallbacks::new()
-                        .with_progress(&progress_callback)
-                        .with_rate_limit(&rate_limit_callback);
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
                     let result = rt.block_on(async {
                         let from = parse_date(&from_date)?;
                         let to = parse_date(&to_date)?;
@@ -159,8 +303,8 @@ impl App {
                         .await?;
                         Ok::<_, AppError>(format!("Exported {} messages to {}", count, output_path))
                     });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
+                    let _ = tx.send(export_complete(
+                        result.map(|msg| ExportResult { message: msg, details: None }),
                     ));
                 }
                 ExportTask::ConversationsWeek {
@@ -170,20 +314,7 @@ impl App {
                     selected_channels,
                     format,
                 } => {
-                    let progress_tx_clone = progress_tx.clone();
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx_clone.send((current, total, name.to_string()));
-                    };
-                    let rate_limit_callback = |wait_secs: u64, attempt: u32, max: u32| {
-                        let _ = progress_tx.send((
-                            attempt as usize,
-                            max as usize,
-                            format!("Rate limited, waiting {}s...", wait_secs),
-                        ));
-                    };
-                    let callbacks = SlackApiCallbacks::new()
-                        .with_progress(&progress_callback)
-                        .with_rate_limit(&rate_limit_callback);
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
                     let result = rt.block_on(async {
                         let (from, to) = week_to_date_range(year, week)?;
                         let count = slack::export_conversations(
@@ -201,8 +332,8 @@ impl App {
                             count, year, week, output_path
                         ))
                     });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
+                    let _ = tx.send(export_complete(
+                        result.map(|msg| ExportResult { message: msg, details: None }),
                     ));
                 }
                 ExportTask::ArchiveRange {
@@ -212,20 +343,7 @@ impl App {
                     to_week,
                     output_path,
                 } => {
-                    let progress_tx_clone = progress_tx.clone();
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx_clone.send((current, total, name.to_string()));
-                    };
-                    let rate_limit_callback = |wait_secs: u64, attempt: u32, max: u32| {
-                        let _ = progress_tx.send((
-                            attempt as usize,
-                            max as usize,
-                            format!("Rate limited, waiting {}s...", wait_secs),
-                        ));
-                    };
-                    let callbacks = SlackApiCallbacks::new()
-                        .with_progress(&progress_callback)
-                        .with_rate_limit(&rate_limit_callback);
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
                     let result = rt.block_on(async {
                         let r = slack::archive_range(
                             &token,
@@ -242,57 +360,81 @@ impl App {
                             r.total_messages, r.weeks_processed, r.weeks_skipped, output_path
                         ))
                     });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
+                    let _ = tx.send(export_complete(
+                        result.map(|msg| ExportResult { message: msg, details: None }),
                     ));
                 }
+                ExportTask::Pipeline(options) => {
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
+                    let result = run_pipeline(&token, &options, &rt, callbacks);
+                    let _ = tx.send(export_complete(result.map(|lines| ExportResult {
+                        message: format!("Pipeline finished\n{}", lines.join("\n")),
+                        details: None,
+                    })));
+                }
                 ExportTask::DownloadAttachments {
                     conversations_path,
                     output_path,
+                    only,
+                    options,
                 } => {
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx.send((current, total, name.to_string()));
-                    };
-                    let result = slack::download_attachments(
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
+                    let result = rt.block_on(slack::download_attachments(
                         &token,
                         &conversations_path,
                         Path::new(&output_path),
-                        Some(&progress_callback),
-                    );
-                    let msg = match result {
-                        Ok(r) => Ok(ExportResult {
-                            message: format!(
-                                "Downloaded {} files to {} ({} skipped, {} failed)",
-                                r.downloaded, output_path, r.skipped, r.failed
-                            ),
-                            details: None,
-                        }),
-                        Err(e) => Err(e.to_string()),
-                    };
-                    let _ = tx.send(AsyncResult::ExportComplete(msg));
+                        only.as_ref(),
+                        &options,
+                        callbacks,
+                    ));
+                    let _ = tx.send(download_complete(result.map(|r| DownloadReport {
+                        message: format!(
+                            "Downloaded {} files to {} ({} skipped, {} over budget, {} failed)",
+                            r.downloaded, output_path, r.skipped, r.over_budget, r.failed
+                        ),
+                        retry: ExportTask::DownloadAttachments {
+                            conversations_path: conversations_path.clone(),
+                            output_path: output_path.clone(),
+                            only: Some(r.failures.iter().map(|f| f.item.clone()).collect()),
+                            options,
+                        },
+                        failures: r.failures,
+                    })));
                 }
                 ExportTask::MarkdownExport {
                     conversations_path,
+                    conversations,
                     users_path,
                     channels_path,
                     output_path,
                     formatter_script,
                     backslash_line_breaks,
                 } => {
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx.send((current, total, name.to_string()));
+                    let callbacks = SlackApiCallbacks::new().with_events(&events);
+                    let progress_callback = |current: usize, total: usize, name: &str| {
+                        callbacks.report_progress(current, total, name);
                     };
                     let options = MarkdownExportOptions::new()
                         .with_formatter_script(formatter_script)
                         .with_backslash_line_breaks(backslash_line_breaks);
-                    let result = export_conversations_to_markdown_with_options(
-                        &conversations_path,
-                        &users_path,
-                        &channels_path,
-                        &output_path,
-                        Some(&progress_callback),
-                        &options,
-                    );
+                    let result = match conversations {
+                        Some(conversations) => export_conversation_values_to_markdown(
+                            &conversations,
+                            &users_path,
+                            &channels_path,
+                            &output_path,
+                            Some(&progress_callback),
+                            &options,
+                        ),
+                        None => export_conversations_to_markdown_with_options(
+                            &conversations_path,
+                            &users_path,
+                            &channels_path,
+                            &output_path,
+                            Some(&progress_callback),
+                            &options,
+                        ),
+                    };
                     let msg = match result {
                         Ok((count, stats)) => {
                             let mut lines = vec![
@@ -324,26 +466,28 @@ impl App {
                 ExportTask::ExportEmojis {
                     output_path,
                     emojis_folder,
+                    only,
                 } => {
-                    let progress_callback = |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx.send((current, total, name.to_string()));
-                    };
-                    let result = rt.block_on(async {
-                        let r = slack::fetch_emojis(
-                            &token,
-                            Path::new(&output_path),
-                            Path::new(&emojis_folder),
-                            Some(&progress_callback),
-                        )
-                        .await?;
-                        Ok::<_, AppError>(format!(
+                    let callbacks = SlackApiCallbacks::new().with_events(&events).with_cancel(&cancel);
+                    let result = rt.block_on(slack::fetch_emojis(
+                        &token,
+                        Path::new(&output_path),
+                        Path::new(&emojis_folder),
+                        only.as_ref(),
+                        callbacks,
+                    ));
+                    let _ = tx.send(download_complete(result.map(|r| DownloadReport {
+                        message: format!(
                             "Fetched {} emojis to {} ({} downloaded, {} skipped, {} failed)",
                             r.total, output_path, r.downloaded, r.skipped, r.failed
-                        ))
-                    });
-                    let _ = tx.send(AsyncResult::ExportComplete(
-                        result.map(|msg| ExportResult { message: msg, details: None }).map_err(|e| e.to_string()),
-                    ));
+                        ),
+                        retry: ExportTask::ExportEmojis {
+                            output_path: output_path.clone(),
+                            emojis_folder: emojis_folder.clone(),
+                            only: Some(r.failures.iter().map(|f| f.item.clone()).collect()),
+                        },
+                        failures: r.failures,
+                    })));
                 }
                 ExportTask::ExportIndex {
                     conversations_path,
@@ -351,14 +495,16 @@ impl App {
                     channels_path,
                     output_path,
                 } => {
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx.send((current, total, name.to_string()));
+                    let callbacks = SlackApiCallbacks::new().with_events(&events);
+                    let progress_callback = |current: usize, total: usize, name: &str| {
+                        callbacks.report_progress(current, total, name);
                     };
                     let result = export_conversations_to_index_with_progress(
                         &conversations_path,
                         &users_path,
                         &channels_path,
                         &output_path,
+                        IndexFormat::from_output_path(&output_path),
                         Some(&progress_callback),
                     );
                     let msg = match result {
@@ -377,8 +523,9 @@ impl App {
                     index_name,
                     clear,
                 } => {
-                    let progress_callback = move |current: usize, total: usize, name: &str| {
-                        let _ = progress_tx.send((current, total, name.to_string()));
+                    let callbacks = SlackApiCallbacks::new().with_events(&events);
+                    let progress_callback = |current: usize, total: usize, name: &str| {
+                        callbacks.report_progress(current, total, name);
                     };
                     let result = rt.block_on(async {
                         import_index_to_meilisearch(
@@ -412,18 +559,54 @@ impl App {
             && let Ok(result) = rx.try_recv()
         {
             self.async_result_rx = None;
-            self.progress_rx = None;
+            self.check_progress();
+            self.events_rx = None;
+            self.export_progress = None;
+            self.transfer_progress = None;
+            self.cancel = None;
             match result {
                 AsyncResult::ExportComplete(Ok(export_result)) => {
+                    self.log.push(&format!("Done: {}", export_result.message.replace('\n', " ")));
                     self.screen = Screen::Success {
                         message: export_result.message,
                         details: export_result.details,
                         details_scroll: 0,
                     };
                 }
-                AsyncResult::ExportComplete(Err(msg)) => {
+                AsyncResult::DownloadComplete(Ok(report)) => {
+                    self.log.push(&format!("Done: {}", report.message));
+                    for failure in &report.failures {
+                        self.log.push(&format!("Failed: {}", failure.error));
+                    }
+                    self.screen = if report.failures.is_empty() {
+                        Screen::Success {
+                            message: report.message,
+                            details: None,
+                            details_scroll: 0,
+                        }
+                    } else {
+                        let mut table_state = TableState::default();
+                        table_state.select(Some(0));
+                        Screen::DownloadResults {
+                            message: report.message,
+                            failures: report.failures,
+                            retry: report.retry,
+                            table_state,
+                        }
+                    };
+                }
+                AsyncResult::DownloadComplete(Err(msg)) | AsyncResult::ExportComplete(Err(msg)) => {
+                    self.log.push(&format!("Failed: {}", msg));
                     self.screen = Screen::Error { message: msg };
                 }
+                AsyncResult::ExportCancelled(summary) => {
+                    self.log.push(&format!("Cancelled: {}", summary));
+                    self.screen = Screen::Success {
+                        message: format!("Cancelled\n{}", summary),
+                        details: None,
+                        details_scroll: 0,
+                    };
+                }
                 AsyncResult::ChannelsLoaded(Ok(channels)) => {
                     if let Screen::ExportConversations {
                         channel_selection,
@@ -471,25 +654,214 @@ impl App {
                 AsyncResult::MdToHtmlResult(Err(msg)) => {
                     self.screen = Screen::Error { message: msg };
                 }
+                AsyncResult::LocalIndexLoaded(result) => {
+                    if let Screen::SearchLocal {
+                        source,
+                        loading,
+                        error,
+                        active_field,
+                        ..
+                    } = &mut self.screen
+                    {
+                        *loading = false;
+                        match result {
+                            Ok(loaded) => {
+                                *source = Some(loaded);
+                                *error = None;
+                                *active_field = SearchLocalField::Query;
+                            }
+                            Err(msg) => *error = Some(msg),
+                        }
+                    }
+                }
             }
         }
     }
 
     pub fn check_progress(&mut self) {
-        if let Some(rx) = &self.progress_rx {
-            let mut latest = None;
-            while let Ok(progress) = rx.try_recv() {
-                latest = Some(progress);
-            }
-            if let Some(progress) = latest
-                && let Screen::Loading {
-                    progress: screen_progress,
-                    ..
-                } = &mut self.screen
-            {
-                *screen_progress = Some(progress);
+        let Some(rx) = &self.events_rx else {
+            return;
+        };
+        let mut status = None;
+        for event in rx.try_iter() {
+            match event {
+                ProgressEvent::Status { current, total, message } => status = Some((current, total, message)),
+                ProgressEvent::RateLimited {
+                    wait_secs,
+                    attempt,
+                    max_attempts,
+                } => {
+                    let message = format!("Rate limited, waiting {}s...", wait_secs);
+                    self.log.push(&format!("{} (attempt {}/{})", message, attempt, max_attempts));
+                    status = Some((attempt as usize, max_attempts as usize, message));
+                }
+                ProgressEvent::BytesDownloaded(progress) => self.transfer_progress = Some(progress),
+                ProgressEvent::Log(line) => self.log.push(&line),
+                export_event => {
+                    self.export_progress.get_or_insert_default().apply(&export_event);
+                }
             }
         }
+        if let Some(progress) = status
+            && let Screen::Loading {
+                progress: screen_progress,
+                ..
+            } = &mut self.screen
+        {
+            *screen_progress = Some(progress);
+        }
+    }
+
+    /// Show `path` rendered as markdown, going back to `previous` (or the
+    /// main menu) on Esc
+    pub fn open_markdown_preview(&mut self, path: &str, previous: Option<Box<Screen>>) {
+        self.screen = match std::fs::read_to_string(path) {
+            Ok(content) => Screen::MarkdownPreview {
+                path: path.to_string(),
+                content,
+                scroll: 0,
+                previous,
+            },
+            Err(e) => Screen::Error {
+                message: AppError::ReadFile {
+                    path: path.to_string(),
+                    source: e,
+                }
+                .to_string(),
+            },
+        };
+    }
+
+    /// Run the download task of the results screen again for its failed items
+    pub fn retry_failed_downloads(&mut self) {
+        let Screen::DownloadResults { failures, retry, .. } = &self.screen else {
+            return;
+        };
+        let message = format!("Retrying {} failed items...", failures.len());
+        let task = retry.clone();
+        self.screen = Screen::Loading { message, progress: None };
+        self.start_task(task);
+    }
+
+    /// Open the emoji browser on the files of the last emoji export
+    pub fn open_emoji_browser(&mut self) {
+        let s = &self.settings.export_emojis;
+        let output_path = if s.output_path.is_empty() { "emojis.json" } else { &s.output_path };
+        let emojis_folder = if s.emojis_folder.is_empty() { "emojis" } else { &s.emojis_folder };
+
+        self.screen = match load_emoji_catalog(output_path, Path::new(emojis_folder)) {
+            Ok(entries) => Screen::EmojiBrowser {
+                browser: EmojiBrowser::new(entries),
+                source: format!("{} + {}/", output_path, emojis_folder),
+            },
+            Err(e) => Screen::Error { message: e.to_string() },
+        };
+    }
+
+    pub fn open_search_local(&mut self) {
+        let index_path = if self.settings.search_local.index_path.is_empty() {
+            "conversation-index.json".to_string()
+        } else {
+            self.settings.search_local.index_path.clone()
+        };
+        let exists = Path::new(&index_path).exists();
+
+        self.screen = Screen::SearchLocal {
+            index_path: TextInput::new(index_path),
+            query: TextInput::new(String::new()),
+            active_field: SearchLocalField::IndexPath,
+            source: None,
+            loading: false,
+            results: Vec::new(),
+            result_state: ListState::default(),
+            error: None,
+        };
+        if exists {
+            self.load_local_index();
+        }
+    }
+
+    /// Load the index of the local search screen off the UI thread
+    pub fn load_local_index(&mut self) {
+        let Screen::SearchLocal {
+            index_path,
+            source,
+            loading,
+            results,
+            error,
+            ..
+        } = &mut self.screen
+        else {
+            return;
+        };
+        let path = index_path.text().to_string();
+        *source = None;
+        *loading = true;
+        *error = None;
+        results.clear();
+
+        let (tx, rx) = mpsc::channel();
+        self.async_result_rx = Some(rx);
+        self.save_search_local_settings(&path);
+
+        thread::spawn(move || {
+            let result = LocalSearchSource::load(&path).map_err(|e| e.to_string());
+            let _ = tx.send(AsyncResult::LocalIndexLoaded(result));
+        });
+    }
+
+    pub fn open_pipeline(&mut self) {
+        let channel_selection = slack::load_channels_from_file(Path::new(CHANNELS_FILE))
+            .ok()
+            .map(|channels| ChannelSelection::new(channels, Some(self.settings.selected_channels_set())));
+
+        self.screen = Screen::Pipeline {
+            from_date: TextInput::new(default_from_date().format("%Y-%m-%d").to_string()),
+            to_date: TextInput::new(default_to_date().format("%Y-%m-%d").to_string()),
+            active_field: PipelineField::FromDate,
+            targets: PipelineTargets {
+                enabled: vec![PipelineTarget::Markdown, PipelineTarget::Index],
+                cursor: 0,
+            },
+            channel_selection,
+        };
+    }
+
+    /// Pipeline options for the given dates, channels and targets; paths and
+    /// the Meilisearch server come from the settings of the individual screens
+    pub fn pipeline_options(
+        &self,
+        from_date: NaiveDate,
+        to_date: NaiveDate,
+        selected_channels: HashSet<String>,
+        targets: &PipelineTargets,
+    ) -> PipelineOptions {
+        fn or_default(value: &str, default: &str) -> String {
+            if value.is_empty() { default } else { value }.to_string()
+        }
+        let s = &self.settings;
+        let enabled = |target| targets.is_enabled(target).then_some(());
+
+        PipelineOptions {
+            from_date,
+            to_date,
+            selected_channels: Some(selected_channels),
+            conversations_path: or_default(&s.fetch_conversations.output_path, "conversations.json"),
+            users_path: or_default(&s.fetch_users.output_path, "users.json"),
+            channels_path: or_default(&s.fetch_channels.output_path, CHANNELS_FILE),
+            attachments_dir: enabled(PipelineTarget::Attachments)
+                .map(|_| or_default(&s.download_attachments.output_path, "attachments")),
+            download_options: s.download_attachments.download_options(),
+            markdown_path: enabled(PipelineTarget::Markdown)
+                .map(|_| or_default(&s.markdown_export.output_path, "conversations.md")),
+            index_path: enabled(PipelineTarget::Index)
+                .map(|_| or_default(&s.export_index.output_path, "conversation-index.json")),
+            meilisearch: enabled(PipelineTarget::Meilisearch).map(|_| MeilisearchTarget {
+                url: or_default(&s.meilisearch.url, "http://localhost:7700"),
+                api_key: s.meilisearch.api_key.clone(),
+                index_name: or_default(&s.meilisearch.index_name, "slack"),
+            }),
+        }
     }
 
     pub fn open_export_conversations(&mut self) {
@@ -564,6 +936,16 @@ impl App {
         let _ = self.settings.save();
     }
 
+    pub fn save_search_local_settings(&mut self, index_path: &str) {
+        self.settings.search_local.index_path = index_path.to_string();
+        let _ = self.settings.save();
+    }
+
+    pub fn save_tantivy_settings(&mut self, index_dir: &str) {
+        self.settings.tantivy.index_dir = index_dir.to_string();
+        let _ = self.settings.save();
+    }
+
     pub fn save_fetch_users_settings(&mut self, output_path: &str) {
         self.settings.fetch_users.output_path = output_path.to_string();
         let _ = self.settings.save();
@@ -655,3 +1037,18 @@ impl App {
         let _ = self.settings.save();
     }
 }
+
+/// Result message for a finished task; cancelled tasks report what they got done
+fn export_complete(result: crate::Result<ExportResult>) -> AsyncResult {
+    match result {
+        Err(AppError::Cancelled(summary)) => AsyncResult::ExportCancelled(summary),
+        result => AsyncResult::ExportComplete(result.map_err(|e| e.to_string())),
+    }
+}
+
+fn download_complete(result: crate::Result<DownloadReport>) -> AsyncResult {
+    match result {
+        Err(AppError::Cancelled(summary)) => AsyncResult::ExportCancelled(summary),
+        result => AsyncResult::DownloadComplete(result.map(Box::new).map_err(|e| e.to_string())),
+    }
+}
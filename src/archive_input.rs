@@ -0,0 +1,133 @@
+//! The parquet archive as command input: `archive:2024/W07` stands for a
+//! week of the archive wherever a conversations JSON file is read, and
+//! `users.parquet` and `channels.parquet` can replace their JSON exports
+
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::extract::extract_week;
+use crate::parquet::{read_channels_parquet, read_users_parquet};
+
+/// Prefix of an archive week given as a conversations path
+pub const ARCHIVE_INPUT_PREFIX: &str = "archive:";
+
+/// Archive directory of an `archive:` path that names none
+pub const DEFAULT_ARCHIVE_DIR: &str = "conversations";
+
+/// A week of a parquet archive, `archive:[DIR/]YYYY/Www`
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchiveWeek {
+    pub archive: PathBuf,
+    pub year: i32,
+    pub week: u32,
+}
+
+impl ArchiveWeek {
+    /// The week `path` names, `None` when it is a plain path
+    pub fn parse(path: &str) -> Option<Result<Self>> {
+        let spec = path.strip_prefix(ARCHIVE_INPUT_PREFIX)?;
+        let invalid = || AppError::InvalidFormat(format!("{} (expected {}[DIR/]YYYY/Www)", path, ARCHIVE_INPUT_PREFIX));
+        let mut parts = spec.trim_end_matches('/').rsplitn(3, '/');
+        let (Some(week), Some(year)) = (parts.next(), parts.next()) else {
+            return Some(Err(invalid()));
+        };
+        let archive = parts.next().unwrap_or(DEFAULT_ARCHIVE_DIR);
+        let week = week.strip_prefix(['W', 'w']).and_then(|w| w.parse().ok());
+        let (Ok(year), Some(week)) = (year.parse(), week) else {
+            return Some(Err(invalid()));
+        };
+        Some(Ok(ArchiveWeek {
+            archive: PathBuf::from(archive),
+            year,
+            week,
+        }))
+    }
+
+    /// The week's conversations, as export-conversations writes them
+    pub fn conversations(&self) -> Result<Vec<Value>> {
+        Ok(extract_week(&self.archive, self.year, self.week)?.conversations)
+    }
+}
+
+fn is_parquet(path: &str) -> bool {
+    Path::new(path).extension().is_some_and(|e| e.eq_ignore_ascii_case("parquet"))
+}
+
+/// Conversations of a JSON file or an `archive:` week
+pub fn load_conversations(path: &str) -> Result<Vec<Value>> {
+    match ArchiveWeek::parse(path) {
+        Some(week) => week?.conversations(),
+        None => crate::load_json_file(path),
+    }
+}
+
+/// Users of a JSON export or a `users.parquet` file
+pub fn load_users(path: &str) -> Result<Vec<Value>> {
+    if is_parquet(path) {
+        read_users_parquet(Path::new(path))
+    } else {
+        crate::load_json_file(path)
+    }
+}
+
+/// Channels of a JSON export or a `channels.parquet` file
+pub fn load_channels(path: &str) -> Result<Vec<Value>> {
+    if is_parquet(path) {
+        read_channels_parquet(Path::new(path))
+    } else {
+        crate::load_json_file(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_parse_archive_week() {
+        let week = |archive: &str, year, week| ArchiveWeek {
+            archive: PathBuf::from(archive),
+            year,
+            week,
+        };
+        assert_eq!(ArchiveWeek::parse("archive:2024/W07").unwrap().unwrap(), week("conversations", 2024, 7));
+        assert_eq!(ArchiveWeek::parse("archive:backup/slack/2023/w52").unwrap().unwrap(), week("backup/slack", 2023, 52));
+        assert_eq!(ArchiveWeek::parse("archive:/srv/archive/2024/W01/").unwrap().unwrap(), week("/srv/archive", 2024, 1));
+        assert!(ArchiveWeek::parse("conversations.json").is_none());
+        for spec in ["archive:2024", "archive:2024/07", "archive:W07/2024", "archive:"] {
+            assert!(ArchiveWeek::parse(spec).unwrap().is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_load_from_archive() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let archive = dir.path().join("conversations");
+        // 2024-01-02, ISO week 1
+        let conversation = json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1704196800.000100", "user": "U1", "text": "hello"}]
+        });
+        crate::parquet::write_conversations_parquet(&archive, &[conversation]).unwrap();
+        let users = dir.path().join("users.parquet");
+        crate::parquet::write_users_parquet(&users, &[json!({"id": "U1", "name": "ana"})]).unwrap();
+
+        let spec = format!("{}{}/2024/W01", ARCHIVE_INPUT_PREFIX, archive.display());
+        let conversations = load_conversations(&spec).unwrap();
+        assert_eq!(conversations.len(), 1);
+        assert_eq!(conversations[0]["channel_id"], "C1");
+        assert_eq!(conversations[0]["messages"][0]["text"], "hello");
+        assert!(load_conversations(&format!("{}{}/2024/W02", ARCHIVE_INPUT_PREFIX, archive.display())).is_err());
+        // Commands reading a path get the week as a JSON file
+        let input = crate::stdio::InputPath::new(&spec).unwrap();
+        let spooled: Vec<Value> = crate::load_json_file(input.path()).unwrap();
+        assert_eq!(spooled, conversations);
+
+        let users = load_users(&users.display().to_string()).unwrap();
+        assert_eq!(users[0]["name"], "ana");
+    }
+}
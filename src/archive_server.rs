@@ -4,12 +4,26 @@
 //! parquet files from a Slack archive. The logic is separated from HTTP endpoints
 //! to enable testing without starting a server.
 
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::ops::Bound;
 use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use chrono::{Datelike, NaiveDate};
 use serde::{Deserialize, Serialize};
 
-use crate::{AppError, Result};
+use slack_morphism::prelude::{SlackChannelId, SlackUserId};
+
+use crate::index::render_message_body;
+use crate::md_to_html::{convert_md_to_html, MdToHtmlOptions};
+use crate::parquet::{
+    count_messages_by_channel, read_messages_pages, read_parquet_as_json, record_batch_to_json,
+    record_batch_to_parquet_bytes, week_threads_files, JsonRow, MessageCounts, MessagePage, MessagePageBatch,
+};
+use crate::slack_render::SlackReferences;
+use crate::{week_to_date_range, AppError, ProgressEvent, Result, SlackApiCallbacks};
 
 /// Server configuration
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -20,6 +34,49 @@ pub struct ServerConfig {
     pub port: u16,
     /// Path to static assets directory to serve
     pub static_assets: Option<String>,
+    /// Serve a Swagger UI for the OpenAPI document at /api/docs
+    #[serde(default)]
+    pub swagger_ui: bool,
+    /// Response compression negotiated via Accept-Encoding
+    #[serde(default)]
+    pub compression: CompressionConfig,
+}
+
+/// Response compression settings; all encodings are enabled by default
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CompressionConfig {
+    /// Set to false to never compress responses
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub gzip: bool,
+    #[serde(default = "default_true")]
+    pub deflate: bool,
+    #[serde(default = "default_true")]
+    pub zstd: bool,
+    /// Responses smaller than this many bytes are sent uncompressed
+    #[serde(default = "default_compression_min_size")]
+    pub min_size: u16,
+}
+
+impl Default for CompressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            gzip: true,
+            deflate: true,
+            zstd: true,
+            min_size: default_compression_min_size(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_compression_min_size() -> u16 {
+    1024
 }
 
 /// Slack archive configuration
@@ -51,6 +108,182 @@ pub struct Config {
     /// Optional meilisearch configuration for search functionality
     #[serde(default)]
     pub meilisearch: Option<MeilisearchConfig>,
+    /// Optional authentication; without it the archive is served unauthenticated
+    #[serde(default)]
+    pub auth: Option<AuthConfig>,
+    /// Optional CORS policy; without it browsers only allow same-origin requests
+    #[serde(default)]
+    pub cors: Option<CorsConfig>,
+    /// Optional per-client rate limiting
+    #[serde(default, rename = "rate-limit")]
+    pub rate_limit: Option<RateLimitConfig>,
+    /// Optional attachment and emoji folders
+    #[serde(default)]
+    pub media: MediaConfig,
+    /// Additional archives served under `/{name}/…`
+    #[serde(default)]
+    pub archives: Vec<NamedArchiveConfig>,
+    /// Optional SQL endpoint (needs the duckdb feature)
+    #[serde(default)]
+    pub sql: Option<SqlConfig>,
+    /// Optional live tail of new messages over Socket Mode
+    #[serde(default)]
+    pub live: Option<LiveConfig>,
+    /// Optional Events API ingestion at /slack/events
+    #[serde(default)]
+    pub events: Option<EventsConfig>,
+}
+
+/// Slack Events API ingestion at /slack/events, for installations Slack can
+/// reach at a public URL; message events are staged and compacted into the
+/// default archive
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct EventsConfig {
+    /// Signing secret of the Slack app, used to verify each request
+    #[serde(rename = "signing-secret")]
+    pub signing_secret: String,
+    /// Directory for staged events (default: `live-staging` in the archive's base path)
+    #[serde(default)]
+    pub staging: Option<String>,
+    /// Seconds between compactions of staged events into the archive
+    #[serde(default = "default_events_compact_every", rename = "compact-every")]
+    pub compact_every: u64,
+}
+
+fn default_events_compact_every() -> u64 {
+    300
+}
+
+impl EventsConfig {
+    /// Staging directory for the archive at `base_path`
+    pub fn staging_dir(&self, base_path: &str) -> PathBuf {
+        match &self.staging {
+            Some(staging) => PathBuf::from(staging),
+            None => Path::new(base_path).join("live-staging"),
+        }
+    }
+}
+
+/// Live tail of new messages at /archive/live, fed by a Slack Socket Mode connection
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct LiveConfig {
+    /// App-level token (`xapp-…`) with the `connections:write` scope
+    #[serde(rename = "app-token")]
+    pub app_token: String,
+    /// Messages kept for slow WebSocket clients before they skip ahead
+    #[serde(default = "default_live_buffer")]
+    pub buffer: usize,
+}
+
+fn default_live_buffer() -> usize {
+    256
+}
+
+/// Read-only SQL queries over the archive's parquet files at /api/query
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct SqlConfig {
+    /// Maximum number of rows returned by a query
+    #[serde(default = "default_sql_max_rows", rename = "max-rows")]
+    pub max_rows: usize,
+    /// Seconds a query may run before it is interrupted
+    #[serde(default = "default_sql_timeout_secs", rename = "timeout-secs")]
+    pub timeout_secs: u64,
+    /// Accept arbitrary SELECT queries, not just the named ones
+    #[serde(default, rename = "ad-hoc")]
+    pub ad_hoc: bool,
+    /// Allowlisted queries by name
+    #[serde(default)]
+    pub queries: HashMap<String, String>,
+}
+
+fn default_sql_max_rows() -> usize {
+    1000
+}
+
+fn default_sql_timeout_secs() -> u64 {
+    30
+}
+
+impl SqlConfig {
+    /// SQL to run for a request naming an allowlisted query or carrying its own SQL
+    pub fn resolve<'a>(&'a self, name: Option<&str>, sql: Option<&'a str>) -> Result<&'a str> {
+        match (name, sql) {
+            (Some(name), None) => self.queries.get(name).map(String::as_str).ok_or_else(|| {
+                AppError::InvalidQuery(format!("unknown query {:?}", name))
+            }),
+            (None, Some(sql)) if self.ad_hoc => Ok(sql),
+            (None, Some(_)) => Err(AppError::InvalidQuery(
+                "ad-hoc queries are disabled; use a named query".to_string(),
+            )),
+            _ => Err(AppError::InvalidQuery(
+                "expected either a query name or sql".to_string(),
+            )),
+        }
+    }
+}
+
+/// An extra archive (e.g. another workspace) served under `/{name}/…`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct NamedArchiveConfig {
+    /// Route prefix; ASCII letters, digits, `-` and `_`
+    pub name: String,
+    /// Base path where users.parquet, channels.parquet, and conversations/ are located
+    pub base_path: String,
+    /// Search index for this archive; archives don't share the top-level one
+    #[serde(default)]
+    pub meilisearch: Option<MeilisearchConfig>,
+    /// Attachment and emoji folders for this archive
+    #[serde(default)]
+    pub media: MediaConfig,
+}
+
+/// Top-level path segments that can't be used as archive names
+const RESERVED_ARCHIVE_NAMES: &[&str] = &["api", "archive", "admin", "slack"];
+
+/// CORS configuration for browser clients hosted on other origins
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CorsConfig {
+    /// Allowed origins (e.g. "https://viewer.example.com"), or "*" for any
+    #[serde(rename = "allowed-origins")]
+    pub allowed_origins: Vec<String>,
+    #[serde(default = "default_cors_methods", rename = "allowed-methods")]
+    pub allowed_methods: Vec<String>,
+    #[serde(default = "default_cors_headers", rename = "allowed-headers")]
+    pub allowed_headers: Vec<String>,
+    /// Response headers readable by browser scripts
+    #[serde(default = "default_cors_expose_headers", rename = "expose-headers")]
+    pub expose_headers: Vec<String>,
+}
+
+fn default_cors_methods() -> Vec<String> {
+    ["GET", "HEAD", "POST", "OPTIONS"].map(String::from).to_vec()
+}
+
+fn default_cors_headers() -> Vec<String> {
+    [
+        "Authorization",
+        "Content-Type",
+        "X-API-Key",
+        "Range",
+        "If-None-Match",
+        "If-Modified-Since",
+        "If-Range",
+    ]
+    .map(String::from)
+    .to_vec()
+}
+
+fn default_cors_expose_headers() -> Vec<String> {
+    ["X-Total-Count", "ETag", "Last-Modified", "Content-Range", "Accept-Ranges"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl CorsConfig {
+    /// Whether any origin is allowed
+    pub fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|o| o == "*")
+    }
 }
 
 impl Config {
@@ -60,12 +293,547 @@ impl Config {
             path: path.display().to_string(),
             source: e,
         })?;
-        toml::from_str(&content).map_err(|e| AppError::TomlParse(e.to_string()))
+        let config: Self =
+            toml::from_str(&content).map_err(|e| AppError::TomlParse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Settings that differ from `new` but only take effect after a restart;
+    /// everything else is applied when the config is reloaded
+    pub fn restart_required(&self, new: &Config) -> Vec<&'static str> {
+        let mut settings = Vec::new();
+        if self.server.host != new.server.host {
+            settings.push("server.host");
+        }
+        if self.server.port != new.server.port {
+            settings.push("server.port");
+        }
+        if self.live != new.live {
+            settings.push("live");
+        }
+        if self.events != new.events {
+            settings.push("events");
+        }
+        settings
+    }
+
+    /// Check that named archives can be mounted side by side
+    pub fn validate(&self) -> Result<()> {
+        let mut seen = std::collections::HashSet::new();
+        for archive in &self.archives {
+            let name = archive.name.as_str();
+            let valid = !name.is_empty()
+                && name
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+            if !valid {
+                return Err(AppError::InvalidConfig(format!(
+                    "archive name {:?} must only contain letters, digits, '-' and '_'",
+                    name
+                )));
+            }
+            if RESERVED_ARCHIVE_NAMES.contains(&name) {
+                return Err(AppError::InvalidConfig(format!(
+                    "archive name {:?} is reserved",
+                    name
+                )));
+            }
+            if !seen.insert(name) {
+                return Err(AppError::InvalidConfig(format!(
+                    "archive name {:?} is used more than once",
+                    name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Access level granted by a credential
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    /// Read archive data
+    #[default]
+    Read,
+    /// Read archive data and use admin routes
+    Admin,
+}
+
+/// API key accepted via `Authorization: Bearer <key>` or `X-API-Key: <key>`
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct ApiKeyConfig {
+    pub key: String,
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// User accepted via HTTP basic auth
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct BasicAuthUser {
+    pub username: String,
+    pub password: String,
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// Authentication configuration; when present, every route requires
+/// credentials unless it is listed in `public-routes`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct AuthConfig {
+    #[serde(default, rename = "api-keys")]
+    pub api_keys: Vec<ApiKeyConfig>,
+    #[serde(default)]
+    pub users: Vec<BasicAuthUser>,
+    /// Route prefixes served without credentials (e.g. "/assets" for static
+    /// files); "/" opens only the root page, not every route
+    #[serde(default, rename = "public-routes")]
+    pub public_routes: Vec<String>,
+    /// Route prefixes that require the admin role
+    #[serde(default = "default_admin_routes", rename = "admin-routes")]
+    pub admin_routes: Vec<String>,
+}
+
+/// Prefix of the server's own admin endpoints, which always need the admin role
+pub const ADMIN_PREFIX: &str = "/admin";
+
+/// Events API endpoint; Slack can't send credentials, so it is always public
+/// and requests are checked against the signing secret instead
+pub const SLACK_EVENTS_PATH: &str = "/slack/events";
+
+fn default_admin_routes() -> Vec<String> {
+    vec![ADMIN_PREFIX.to_string()]
+}
+
+/// Credentials presented by a request
+#[derive(Debug, Clone, PartialEq)]
+pub enum Credentials<'a> {
+    ApiKey(&'a str),
+    Basic { username: &'a str, password: &'a str },
+}
+
+/// Outcome of checking a request against the auth configuration
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AuthDecision {
+    Allowed,
+    /// Missing or invalid credentials
+    Unauthorized,
+    /// Valid credentials without the required role
+    Forbidden,
+}
+
+impl AuthConfig {
+    /// Role needed to access `path`, or `None` for public routes
+    pub fn required_role(&self, path: &str) -> Option<Role> {
+        if route_matches(path, ADMIN_PREFIX)
+            || self.admin_routes.iter().any(|prefix| route_matches(path, prefix))
+        {
+            Some(Role::Admin)
+        } else if path == SLACK_EVENTS_PATH
+            || self.public_routes.iter().any(|prefix| route_matches(path, prefix))
+        {
+            None
+        } else {
+            Some(Role::Read)
+        }
+    }
+
+    /// Role granted by `credentials`, if they are valid
+    pub fn authenticate(&self, credentials: &Credentials) -> Option<Role> {
+        match credentials {
+            Credentials::ApiKey(key) => self
+                .api_keys
+                .iter()
+                .find(|k| constant_time_eq(k.key.as_bytes(), key.as_bytes()))
+                .map(|k| k.role),
+            Credentials::Basic { username, password } => self
+                .users
+                .iter()
+                .find(|u| {
+                    // Evaluate both comparisons so timing doesn't reveal valid usernames
+                    let user_ok = constant_time_eq(u.username.as_bytes(), username.as_bytes());
+                    let password_ok = constant_time_eq(u.password.as_bytes(), password.as_bytes());
+                    user_ok & password_ok
+                })
+                .map(|u| u.role),
+        }
+    }
+
+    /// Decide whether a request for `path` with `credentials` may proceed
+    pub fn authorize(&self, path: &str, credentials: Option<&Credentials>) -> AuthDecision {
+        let Some(required) = self.required_role(path) else {
+            return AuthDecision::Allowed;
+        };
+        match credentials.and_then(|c| self.authenticate(c)) {
+            None => AuthDecision::Unauthorized,
+            Some(role) if role >= required => AuthDecision::Allowed,
+            Some(_) => AuthDecision::Forbidden,
+        }
+    }
+}
+
+/// Whether `path` is `prefix` or below it ("/api" matches "/api/users", not "/apix").
+/// "/" matches only the root itself, so listing it can't open up every route.
+fn route_matches(path: &str, prefix: &str) -> bool {
+    let prefix = prefix.trim_end_matches('/');
+    if prefix.is_empty() {
+        return path == "/";
+    }
+    path == prefix
+        || path
+            .strip_prefix(prefix)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Compare secrets without exiting early on the first differing byte
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// Rate limiting configuration; each client gets a token bucket that refills
+/// at `requests-per-minute` and holds up to `burst` requests
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct RateLimitConfig {
+    /// Sustained requests per minute for each client IP
+    #[serde(rename = "requests-per-minute")]
+    pub requests_per_minute: u32,
+    /// Requests allowed in a burst (default: requests-per-minute)
+    #[serde(default)]
+    pub burst: Option<u32>,
+    /// Requests per minute for each API key or basic-auth user
+    /// (default: requests-per-minute)
+    #[serde(default, rename = "key-requests-per-minute")]
+    pub key_requests_per_minute: Option<u32>,
+    /// Take the client IP from `X-Forwarded-For` (only behind a trusted proxy)
+    #[serde(default, rename = "trust-forwarded-for")]
+    pub trust_forwarded_for: bool,
+}
+
+/// Identity a request is rate limited by
+#[derive(Debug, Clone, PartialEq)]
+pub enum RateLimitKey<'a> {
+    Ip(IpAddr),
+    /// API key or basic-auth username
+    Credential(&'a str),
+}
+
+impl<'a> RateLimitKey<'a> {
+    /// The credentials' own bucket once `auth` accepts them; the client's
+    /// IP otherwise, so made-up keys and password guesses share its limit
+    pub fn for_request(credentials: Option<&Credentials<'a>>, auth: Option<&AuthConfig>, ip: IpAddr) -> Self {
+        match (credentials, auth) {
+            (Some(credentials), Some(auth)) if auth.authenticate(credentials).is_some() => match credentials {
+                Credentials::ApiKey(key) => RateLimitKey::Credential(key),
+                Credentials::Basic { username, .. } => RateLimitKey::Credential(username),
+            },
+            _ => RateLimitKey::Ip(ip),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Buckets kept before idle (full) ones are dropped
+const MAX_IDLE_BUCKETS: usize = 10_000;
+
+/// Per-client token bucket rate limiter
+#[derive(Debug)]
+pub struct RateLimiter {
+    config: RateLimitConfig,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Self {
+        Self {
+            config,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &RateLimitConfig {
+        &self.config
+    }
+
+    /// Take one request from the client's bucket.
+    ///
+    /// Returns how long to wait before retrying when the bucket is empty.
+    pub fn check(&self, key: &RateLimitKey, now: Instant) -> std::result::Result<(), Duration> {
+        let (bucket_key, per_minute) = match key {
+            RateLimitKey::Ip(ip) => (format!("ip:{}", ip), self.config.requests_per_minute),
+            RateLimitKey::Credential(credential) => (
+                format!("key:{}", credential),
+                self.config
+                    .key_requests_per_minute
+                    .unwrap_or(self.config.requests_per_minute),
+            ),
+        };
+        let per_second = f64::from(per_minute.max(1)) / 60.0;
+        let capacity = f64::from(self.config.burst.unwrap_or(per_minute).max(1));
+
+        // A poisoned lock only means another request panicked mid-update;
+        // the buckets are still usable
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        if buckets.len() > MAX_IDLE_BUCKETS {
+            buckets.retain(|_, b| {
+                b.tokens + now.saturating_duration_since(b.updated).as_secs_f64() * per_second
+                    < capacity
+            });
+        }
+
+        let bucket = buckets.entry(bucket_key).or_insert(TokenBucket {
+            tokens: capacity,
+            updated: now,
+        });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * per_second).min(capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / per_second))
+        }
+    }
+}
+
+/// Weeks to fetch from Slack, as accepted by `POST /admin/archive-range`
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct ArchiveRangeRequest {
+    pub from_year: i32,
+    pub from_week: u32,
+    /// Last year to archive (default: from_year)
+    #[serde(default)]
+    pub to_year: Option<i32>,
+    /// Last week to archive (default: from_week)
+    #[serde(default)]
+    pub to_week: Option<u32>,
+}
+
+impl ArchiveRangeRequest {
+    /// Resolve the inclusive range, rejecting weeks that don't exist
+    pub fn weeks(&self) -> Result<(YearWeek, YearWeek)> {
+        let from = YearWeek::new(self.from_year, self.from_week);
+        let to = YearWeek::new(
+            self.to_year.unwrap_or(self.from_year),
+            self.to_week.unwrap_or(self.from_week),
+        );
+        let (start, _) = week_to_date_range(from.year, from.week)?;
+        let (end, _) = week_to_date_range(to.year, to.week)?;
+        if end < start {
+            return Err(AppError::InvalidDate(format!(
+                "{}-W{:02} is before {}-W{:02}",
+                to.year, to.week, from.year, from.week
+            )));
+        }
+        Ok((from, to))
+    }
+}
+
+/// Lifecycle of a background job started from the admin API
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+}
+
+/// Progress and outcome of a background job
+#[derive(Debug, Clone, Deserialize, Serialize, utoipa::ToSchema)]
+pub struct JobStatus {
+    pub id: String,
+    /// What the job does (e.g. "archive-range")
+    pub kind: String,
+    pub state: JobState,
+    /// Progress as reported by the running task
+    pub current: usize,
+    pub total: usize,
+    pub message: String,
+    /// Summary of a completed job
+    #[schema(value_type = Option<Object>)]
+    pub result: Option<serde_json::Value>,
+    /// Error of a failed job
+    pub error: Option<String>,
+    /// RFC 3339 timestamps
+    pub started_at: String,
+    pub finished_at: Option<String>,
+}
+
+/// Finished jobs kept for polling; older ones are dropped
+const MAX_FINISHED_JOBS: usize = 100;
+
+/// In-memory registry of admin jobs, shared between handlers and workers
+#[derive(Debug, Default)]
+pub struct JobRegistry {
+    jobs: Mutex<HashMap<String, JobStatus>>,
+}
+
+impl JobRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a running job; `None` if a job of the same kind is still running,
+    /// since two archive runs would write the same parquet files
+    pub fn start(&self, kind: &str) -> Option<JobStatus> {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if jobs
+            .values()
+            .any(|job| job.kind == kind && job.state == JobState::Running)
+        {
+            return None;
+        }
+
+        let mut finished: Vec<_> = jobs
+            .values()
+            .filter(|job| job.state != JobState::Running)
+            .map(|job| (job.started_at.clone(), job.id.clone()))
+            .collect();
+        if finished.len() >= MAX_FINISHED_JOBS {
+            finished.sort();
+            for (_, id) in finished.iter().take(finished.len() + 1 - MAX_FINISHED_JOBS) {
+                jobs.remove(id);
+            }
+        }
+
+        let job = JobStatus {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            state: JobState::Running,
+            current: 0,
+            total: 0,
+            message: "Starting...".to_string(),
+            result: None,
+            error: None,
+            started_at: chrono::Utc::now().to_rfc3339(),
+            finished_at: None,
+        };
+        jobs.insert(job.id.clone(), job.clone());
+        Some(job)
+    }
+
+    /// Current status of a job
+    pub fn get(&self, id: &str) -> Option<JobStatus> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        jobs.get(id).cloned()
+    }
+
+    /// All known jobs, most recent first
+    pub fn list(&self) -> Vec<JobStatus> {
+        let jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        let mut list: Vec<_> = jobs.values().cloned().collect();
+        list.sort_by(|a, b| b.started_at.cmp(&a.started_at));
+        list
+    }
+
+    /// Record progress reported by a running job
+    pub fn progress(&self, id: &str, current: usize, total: usize, message: &str) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(job) = jobs.get_mut(id) {
+            job.current = current;
+            job.total = total;
+            job.message = message.to_string();
+        }
     }
+
+    /// Mark a job completed with a summary, or failed with its error
+    pub fn finish(&self, id: &str, outcome: Result<serde_json::Value>) {
+        let mut jobs = self.jobs.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(job) = jobs.get_mut(id) {
+            match outcome {
+                Ok(result) => {
+                    job.state = JobState::Completed;
+                    job.result = Some(result);
+                }
+                Err(e) => {
+                    job.state = JobState::Failed;
+                    job.error = Some(e.to_string());
+                }
+            }
+            job.finished_at = Some(chrono::Utc::now().to_rfc3339());
+        }
+    }
+}
+
+/// Run `archive_range` for a registered job, recording its progress.
+///
+/// Blocks the calling thread on `runtime`; run it via `spawn_blocking`. The
+/// task's status events are recorded from another thread while it runs.
+pub fn run_archive_range_job(
+    jobs: &JobRegistry,
+    id: &str,
+    token: &str,
+    (from, to): (YearWeek, YearWeek),
+    output: &Path,
+    runtime: &tokio::runtime::Handle,
+) {
+    let (events, received) = std::sync::mpsc::channel();
+    let outcome = std::thread::scope(|scope| {
+        scope.spawn(move || {
+            for event in received {
+                if let ProgressEvent::Status { current, total, message } = event {
+                    jobs.progress(id, current, total, &message);
+                }
+            }
+        });
+        let outcome = runtime.block_on(crate::slack::archive_range(
+            token,
+            from.year,
+            from.week,
+            to.year,
+            to.week,
+            output,
+            SlackApiCallbacks::new().with_events(&events),
+        ));
+        // Ends the recording thread
+        drop(events);
+        outcome
+    });
+    jobs.finish(
+        id,
+        outcome.map(|result| {
+            serde_json::json!({
+                "total_messages": result.total_messages,
+                "weeks_processed": result.weeks_processed,
+                "weeks_skipped": result.weeks_skipped,
+            })
+        }),
+    );
+}
+
+/// Server log output format
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogFormat {
+    /// Human-readable lines
+    #[default]
+    Text,
+    /// One JSON object per line, for log shippers
+    Json,
+}
+
+/// Install the global tracing subscriber; `RUST_LOG` overrides the default
+/// `info` level (e.g. `RUST_LOG=debug` also lists the endpoints at startup)
+pub fn init_tracing(format: LogFormat) {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    // Ignore the error if a subscriber is already installed (e.g. in tests)
+    let _ = match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().flatten_event(true).try_init(),
+    };
 }
 
 /// Represents a year/week pair for thread partitions
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct YearWeek {
     pub year: i32,
     pub week: u32,
@@ -77,10 +845,86 @@ impl YearWeek {
     }
 }
 
+/// A page of a threads partition encoded as parquet
+#[derive(Debug)]
+pub struct ThreadsPage {
+    /// Parquet file contents holding the selected messages
+    pub parquet: Vec<u8>,
+    /// Number of messages matching the channel filter (before offset/limit)
+    pub total: usize,
+}
+
+/// A page of a threads partition as JSON rows
+#[derive(Debug)]
+pub struct ThreadsJsonPage {
+    pub rows: Vec<JsonRow>,
+    /// Number of messages matching the channel filter (before offset/limit)
+    pub total: usize,
+}
+
+/// A message rendered to HTML for the built-in web UI
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct RenderedMessage {
+    pub ts: String,
+    pub user: Option<String>,
+    /// Display name resolved from users.parquet, falling back to the user ID
+    pub user_name: Option<String>,
+    pub channel_name: Option<String>,
+    pub date: Option<String>,
+    pub thread_ts: Option<String>,
+    pub is_reply: bool,
+    /// Message blocks rendered through the markdown pipeline
+    pub html: String,
+}
+
+/// A page of a threads partition rendered to HTML
+#[derive(Debug)]
+pub struct ThreadsHtmlPage {
+    pub messages: Vec<RenderedMessage>,
+    /// Number of messages matching the channel filter (before offset/limit)
+    pub total: usize,
+}
+
+/// Message counts of one week partition
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct WeekStats {
+    pub year: i32,
+    pub week: u32,
+    pub messages: usize,
+    /// Size of the week's threads.parquet files
+    pub bytes: u64,
+    /// Messages per channel name
+    pub channels: BTreeMap<String, usize>,
+}
+
+/// Activity summary of a whole archive
+#[derive(Debug, Clone, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct ArchiveStats {
+    pub total_messages: usize,
+    /// Size of all parquet files, including users and channels
+    pub total_bytes: u64,
+    pub first_week: Option<YearWeek>,
+    pub last_week: Option<YearWeek>,
+    /// Messages per channel name across all weeks
+    pub channels: BTreeMap<String, usize>,
+    /// One entry per partition, oldest first
+    pub weeks: Vec<WeekStats>,
+}
+
+/// Message counts of a threads file with the fingerprint they were computed from
+#[derive(Debug)]
+struct CachedCounts {
+    len: u64,
+    modified: Option<SystemTime>,
+    counts: MessageCounts,
+}
+
 /// Archive service providing access to parquet files
 #[derive(Debug, Clone)]
 pub struct ArchiveService {
     base_path: PathBuf,
+    /// Per-file counts, recomputed only when a file's size or mtime changes
+    stats_cache: Arc<Mutex<HashMap<PathBuf, CachedCounts>>>,
 }
 
 impl ArchiveService {
@@ -88,6 +932,7 @@ impl ArchiveService {
     pub fn new(base_path: impl Into<PathBuf>) -> Self {
         Self {
             base_path: base_path.into(),
+            stats_cache: Arc::default(),
         }
     }
 
@@ -101,15 +946,38 @@ impl ArchiveService {
         self.base_path.join("channels.parquet")
     }
 
-    /// Get the path to a threads parquet file for a specific year/week
-    pub fn threads_path(&self, year: i32, week: u32) -> PathBuf {
-        self.base_path
-            .join("conversations")
+    /// Get the path to the conversations folder written by archive-range
+    pub fn conversations_path(&self) -> PathBuf {
+        self.base_path.join("conversations")
+    }
+
+    /// Get the path to a year/week partition directory
+    pub fn week_path(&self, year: i32, week: u32) -> PathBuf {
+        self.conversations_path()
             .join(format!("year={}", year))
             .join(format!("week={:02}", week))
+    }
+
+    /// Get the path to a threads parquet file for a specific year/week, in
+    /// archives partitioned by week
+    pub fn threads_path(&self, year: i32, week: u32) -> PathBuf {
+        self.week_path(year, week).join("threads.parquet")
+    }
+
+    /// Get the path to a channel's threads parquet file for a specific
+    /// year/week, in archives partitioned by channel
+    pub fn channel_threads_path(&self, year: i32, week: u32, channel_id: &str) -> PathBuf {
+        self.week_path(year, week)
+            .join(format!("channel={}", channel_id))
             .join("threads.parquet")
     }
 
+    /// The threads files of a year/week, whichever way the archive is
+    /// partitioned; empty when the week is not archived
+    pub fn threads_files(&self, year: i32, week: u32) -> Vec<PathBuf> {
+        week_threads_files(&self.week_path(year, week))
+    }
+
     /// Check if the users parquet file exists
     pub fn users_exists(&self) -> bool {
         self.users_path().exists()
@@ -120,9 +988,9 @@ impl ArchiveService {
         self.channels_path().exists()
     }
 
-    /// Check if a threads parquet file exists for the given year/week
+    /// Check if threads parquet files exist for the given year/week
     pub fn threads_exists(&self, year: i32, week: u32) -> bool {
-        self.threads_path(year, week).exists()
+        !self.threads_files(year, week).is_empty()
     }
 
     /// Get all year/week partitions that have existing parquet files within a date range.
@@ -167,10 +1035,410 @@ impl ArchiveService {
         Ok(result)
     }
 
+    /// Message counts per week and channel for every partition.
+    ///
+    /// Only threads files changed since the last call are read again, and
+    /// then only their `channel_name` column.
+    pub fn stats(&self) -> Result<ArchiveStats> {
+        let mut partitions = Vec::new();
+        for year_entry in read_partition_dir(&self.conversations_path(), "year=")? {
+            let (year_path, year) = year_entry;
+            let Ok(year) = year.parse::<i32>() else { continue };
+            for (week_path, week) in read_partition_dir(&year_path, "week=")? {
+                let Ok(week) = week.parse::<u32>() else { continue };
+                let files = week_threads_files(&week_path);
+                if !files.is_empty() {
+                    partitions.push((year, week, files));
+                }
+            }
+        }
+        partitions.sort_by_key(|(year, week, _)| (*year, *week));
+
+        let mut cache = self.stats_cache.lock().unwrap_or_else(|e| e.into_inner());
+        cache.retain(|path, _| partitions.iter().any(|(_, _, files)| files.contains(path)));
+
+        let mut weeks = Vec::with_capacity(partitions.len());
+        for (year, week, files) in partitions {
+            let mut stats = WeekStats {
+                year,
+                week,
+                messages: 0,
+                bytes: 0,
+                channels: BTreeMap::new(),
+            };
+            for file in files {
+                let metadata = std::fs::metadata(&file).map_err(|e| AppError::ReadFile {
+                    path: file.display().to_string(),
+                    source: e,
+                })?;
+                let (len, modified) = (metadata.len(), metadata.modified().ok());
+                let fresh = cache
+                    .get(&file)
+                    .filter(|cached| cached.len == len && cached.modified == modified);
+                let counts = match fresh {
+                    Some(cached) => cached.counts.clone(),
+                    None => {
+                        let counts = count_messages_by_channel(&file)?;
+                        cache.insert(
+                            file,
+                            CachedCounts {
+                                len,
+                                modified,
+                                counts: counts.clone(),
+                            },
+                        );
+                        counts
+                    }
+                };
+                stats.messages += counts.total;
+                stats.bytes += len;
+                for (name, count) in counts.by_channel {
+                    *stats.channels.entry(name).or_insert(0) += count;
+                }
+            }
+            weeks.push(stats);
+        }
+        drop(cache);
+
+        let mut channels = BTreeMap::new();
+        for week in &weeks {
+            for (name, count) in &week.channels {
+                *channels.entry(name.clone()).or_insert(0) += count;
+            }
+        }
+        let file_size = |path: PathBuf| std::fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        let total_bytes = weeks.iter().map(|w| w.bytes).sum::<u64>()
+            + file_size(self.users_path())
+            + file_size(self.channels_path());
+
+        Ok(ArchiveStats {
+            total_messages: weeks.iter().map(|w| w.messages).sum(),
+            total_bytes,
+            first_week: weeks.first().map(|w| YearWeek::new(w.year, w.week)),
+            last_week: weeks.last().map(|w| YearWeek::new(w.year, w.week)),
+            channels,
+            weeks,
+        })
+    }
+
     /// Get the base path for this archive service
     pub fn base_path(&self) -> &Path {
         &self.base_path
     }
+
+    /// Read users.parquet as JSON rows, optionally keeping only `fields`
+    pub fn users_json(&self, fields: Option<&[String]>) -> Result<Vec<JsonRow>> {
+        select_fields(read_parquet_as_json(&self.users_path())?, fields)
+    }
+
+    /// Read channels.parquet as JSON rows, optionally keeping only `fields`
+    pub fn channels_json(&self, fields: Option<&[String]>) -> Result<Vec<JsonRow>> {
+        select_fields(read_parquet_as_json(&self.channels_path())?, fields)
+    }
+
+    /// Read a page of a year/week partition as an in-memory parquet file.
+    ///
+    /// The page's `channel` matches either the channel ID or name.
+    pub fn threads_page(&self, year: i32, week: u32, page: &MessagePage) -> Result<ThreadsPage> {
+        let selected = self.read_page(year, week, page)?;
+        Ok(ThreadsPage {
+            parquet: record_batch_to_parquet_bytes(&selected.batch)?,
+            total: selected.total,
+        })
+    }
+
+    /// Read a page of a year/week partition as JSON rows.
+    ///
+    /// The page's `channel` matches either the channel ID or name; `fields`
+    /// keeps only the given columns.
+    pub fn threads_json(
+        &self,
+        year: i32,
+        week: u32,
+        page: &MessagePage,
+        fields: Option<&[String]>,
+    ) -> Result<ThreadsJsonPage> {
+        let selected = self.read_page(year, week, page)?;
+        Ok(ThreadsJsonPage {
+            rows: select_fields(record_batch_to_json(&selected.batch)?, fields)?,
+            total: selected.total,
+        })
+    }
+
+    /// Read a page of a year/week partition and render each message to HTML.
+    ///
+    /// User and channel mentions are resolved with users.parquet and
+    /// channels.parquet when they exist.
+    pub fn threads_html(&self, year: i32, week: u32, page: &MessagePage) -> Result<ThreadsHtmlPage> {
+        let selected = self.read_page(year, week, page)?;
+        let rows = record_batch_to_json(&selected.batch)?;
+        let user_names = self.names_by_id(&self.users_path(), &["display_name", "real_name", "name"]);
+        let channel_names = self.names_by_id(&self.channels_path(), &["name"]);
+
+        let slack_references = SlackReferences {
+            users: user_names
+                .iter()
+                .map(|(id, name)| (SlackUserId::new(id.clone()), Some(name.clone())))
+                .collect(),
+            channels: channel_names
+                .iter()
+                .map(|(id, name)| (SlackChannelId::new(id.clone()), Some(name.clone())))
+                .collect(),
+            ..SlackReferences::default()
+        };
+        let options = MdToHtmlOptions::gfm();
+
+        let messages = rows
+            .iter()
+            .map(|row| {
+                let text = |key: &str| row.get(key).and_then(|v| v.as_str()).map(str::to_string);
+                let blocks = text("blocks")
+                    .and_then(|b| serde_json::from_str::<serde_json::Value>(&b).ok())
+                    .unwrap_or(serde_json::Value::Null);
+                let message = serde_json::json!({ "blocks": blocks, "text": text("text") });
+                let markdown = render_message_body(&message, &slack_references);
+                let user = text("user");
+
+                Ok(RenderedMessage {
+                    ts: text("ts").unwrap_or_default(),
+                    user_name: user
+                        .as_ref()
+                        .map(|id| user_names.get(id).cloned().unwrap_or_else(|| id.clone())),
+                    user,
+                    channel_name: text("channel_name"),
+                    date: text("date"),
+                    thread_ts: text("thread_ts"),
+                    is_reply: row.get("is_reply").and_then(|v| v.as_bool()).unwrap_or(false),
+                    html: convert_md_to_html(&markdown, &options)?,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(ThreadsHtmlPage {
+            messages,
+            total: selected.total,
+        })
+    }
+
+    /// Read a page of a year/week partition. A channel selected by ID in an
+    /// archive partitioned by channel is read from its own file only; a
+    /// missing week fails reading its threads.parquet.
+    fn read_page(&self, year: i32, week: u32, page: &MessagePage) -> Result<MessagePageBatch> {
+        let channel_file = page
+            .channel
+            .as_deref()
+            .map(|channel| self.channel_threads_path(year, week, channel))
+            .filter(|file| file.is_file());
+        let files = match channel_file {
+            Some(file) => vec![file],
+            None => self.threads_files(year, week),
+        };
+        if files.is_empty() {
+            return read_messages_pages(&[self.threads_path(year, week)], page);
+        }
+        read_messages_pages(&files, page)
+    }
+
+    /// Map `id` to the first non-empty column of `name_columns`; empty if the file is missing
+    fn names_by_id(&self, path: &Path, name_columns: &[&str]) -> HashMap<String, String> {
+        let Ok(rows) = read_parquet_as_json(path) else {
+            return HashMap::new();
+        };
+        rows.iter()
+            .filter_map(|row| {
+                let id = row.get("id")?.as_str()?.to_string();
+                let name = name_columns
+                    .iter()
+                    .filter_map(|column| row.get(*column)?.as_str())
+                    .find(|name| !name.is_empty())?
+                    .to_string();
+                Some((id, name))
+            })
+            .collect()
+    }
+}
+
+/// Subdirectories of `dir` named `<prefix><value>`, as (path, value); a
+/// missing `dir` has no partitions
+fn read_partition_dir(dir: &Path, prefix: &str) -> Result<Vec<(PathBuf, String)>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(AppError::ReadFile {
+                path: dir.display().to_string(),
+                source: e,
+            })
+        }
+    };
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().to_str()?.strip_prefix(prefix)?.to_string();
+            Some((entry.path(), name))
+        })
+        .collect())
+}
+
+/// Folders produced by `download-attachments` and `export-emojis` to serve
+/// under /archive/files and /archive/emojis
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct MediaConfig {
+    /// Output folder of `download-attachments`
+    #[serde(default)]
+    pub attachments: Option<String>,
+    /// Folder of `export-emojis`
+    #[serde(default)]
+    pub emojis: Option<String>,
+}
+
+/// Find a downloaded attachment by Slack file ID.
+///
+/// `download-attachments` stores files as `<dir>/<first 3 chars of id>/<id>.<filetype>`;
+/// duplicates it removed are looked up in its manifest.
+/// IDs that aren't plain alphanumeric are rejected so they can't escape `dir`.
+pub fn find_attachment(dir: &Path, id: &str) -> Option<PathBuf> {
+    if id.is_empty() || !id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return None;
+    }
+    let folder = dir.join(id.get(..3).unwrap_or("unk"));
+    find_by_stem(&folder, id).or_else(|| {
+        let manifest = crate::attachments::load_manifest(dir).ok()?;
+        let entry = manifest.get(id)?;
+        // The manifest is ours, but don't follow paths out of `dir`
+        let safe = Path::new(&entry.path)
+            .components()
+            .all(|c| matches!(c, std::path::Component::Normal(_)));
+        let path = dir.join(&entry.path);
+        (safe && path.is_file()).then_some(path)
+    })
+}
+
+/// Find an exported emoji image (`<dir>/<name>.<ext>`) by emoji name
+pub fn find_emoji(dir: &Path, name: &str) -> Option<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '+' | '\''));
+    if !valid {
+        return None;
+    }
+    find_by_stem(dir, name)
+}
+
+/// File in `folder` named `stem` or `stem.<ext>`, ignoring unfinished
+/// `.partial` downloads
+fn find_by_stem(folder: &Path, stem: &str) -> Option<PathBuf> {
+    let exact = folder.join(stem);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    let prefix = format!("{}.", stem);
+    std::fs::read_dir(folder)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .find(|path| {
+            path.file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix(&prefix))
+                .is_some_and(|ext| !ext.contains('.') && ext != "partial")
+        })
+}
+
+/// Content type for a media file, based on its extension
+pub fn media_content_type(path: &Path) -> &'static str {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+    match ext.as_str() {
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "svg" => "image/svg+xml",
+        "pdf" => "application/pdf",
+        "mp4" => "video/mp4",
+        "mov" => "video/quicktime",
+        "webm" => "video/webm",
+        "mp3" => "audio/mpeg",
+        "m4a" => "audio/mp4",
+        "txt" | "text" => "text/plain; charset=utf-8",
+        "csv" => "text/csv; charset=utf-8",
+        "json" => "application/json",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Strong ETag for a file derived from its size and modification time,
+/// so it changes whenever a week is re-exported without hashing the file
+pub fn file_etag(len: u64, modified: SystemTime) -> String {
+    let since_epoch = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+    format!(
+        "\"{:x}-{:x}-{:x}\"",
+        len,
+        since_epoch.as_secs(),
+        since_epoch.subsec_nanos()
+    )
+}
+
+/// Resolve one range of a `Range: bytes=...` header against a file of `len`
+/// bytes into an inclusive `(start, end)` pair, clamping the end to the file.
+///
+/// Returns `None` when the range can't be satisfied (e.g. starts past the end).
+pub fn resolve_byte_range(start: Bound<u64>, end: Bound<u64>, len: u64) -> Option<(u64, u64)> {
+    let last = len.checked_sub(1)?;
+    let start = match start {
+        Bound::Included(start) => start,
+        Bound::Excluded(start) => start.checked_add(1)?,
+        Bound::Unbounded => 0,
+    };
+    let end = match end {
+        Bound::Included(end) => end.min(last),
+        Bound::Excluded(end) => end.checked_sub(1)?.min(last),
+        Bound::Unbounded => last,
+    };
+    (start <= end).then_some((start, end))
+}
+
+/// Parse a comma-separated field list (`"id,name"`); empty entries are ignored
+pub fn parse_fields(fields: &str) -> Vec<String> {
+    fields
+        .split(',')
+        .map(str::trim)
+        .filter(|f| !f.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Keep only the requested fields of each row.
+///
+/// Returns [`AppError::UnknownField`] if a requested field is not a column.
+fn select_fields(rows: Vec<JsonRow>, fields: Option<&[String]>) -> Result<Vec<JsonRow>> {
+    let Some(fields) = fields.filter(|f| !f.is_empty()) else {
+        return Ok(rows);
+    };
+
+    if let Some(first) = rows.first()
+        && let Some(unknown) = fields.iter().find(|f| !first.contains_key(f.as_str()))
+    {
+        return Err(AppError::UnknownField(unknown.clone()));
+    }
+
+    Ok(rows
+        .into_iter()
+        .map(|mut row| {
+            fields
+                .iter()
+                .filter_map(|f| row.remove_entry(f.as_str()))
+                .collect()
+        })
+        .collect())
 }
 
 #[cfg(test)]
@@ -268,6 +1536,548 @@ index-name = "slack-messages"
         assert_eq!(ms.index_name, "slack-messages");
     }
 
+    fn test_auth_config() -> AuthConfig {
+        AuthConfig {
+            api_keys: vec![
+                ApiKeyConfig {
+                    key: "read-key".to_string(),
+                    role: Role::Read,
+                },
+                ApiKeyConfig {
+                    key: "admin-key".to_string(),
+                    role: Role::Admin,
+                },
+            ],
+            users: vec![BasicAuthUser {
+                username: "alice".to_string(),
+                password: "s3cret".to_string(),
+                role: Role::Read,
+            }],
+            public_routes: vec!["/public".to_string()],
+            admin_routes: default_admin_routes(),
+        }
+    }
+
+    #[test]
+    fn test_config_from_file_with_auth() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let config_content = r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[slack-archive]
+base_path = "/data/archive"
+
+[auth]
+public-routes = ["/index.html"]
+
+[[auth.api-keys]]
+key = "k1"
+
+[[auth.api-keys]]
+key = "k2"
+role = "admin"
+
+[[auth.users]]
+username = "alice"
+password = "pw"
+"#;
+        fs::write(&config_path, config_content).unwrap();
+
+        let auth = Config::from_file(&config_path).unwrap().auth.unwrap();
+        assert_eq!(auth.api_keys.len(), 2);
+        assert_eq!(auth.api_keys[0].role, Role::Read);
+        assert_eq!(auth.api_keys[1].role, Role::Admin);
+        assert_eq!(auth.users[0].username, "alice");
+        assert_eq!(auth.public_routes, vec!["/index.html"]);
+        assert_eq!(auth.admin_routes, vec!["/admin"]);
+    }
+
+    #[test]
+    fn test_auth_required_role() {
+        let auth = test_auth_config();
+        assert_eq!(auth.required_role("/archive/users"), Some(Role::Read));
+        assert_eq!(auth.required_role("/public/app.js"), None);
+        assert_eq!(auth.required_role("/publicity"), Some(Role::Read));
+        assert_eq!(auth.required_role("/admin/update"), Some(Role::Admin));
+
+        // The server's own admin endpoints can't be opened up by config
+        let custom = AuthConfig {
+            admin_routes: Vec::new(),
+            public_routes: vec!["/".to_string()],
+            ..auth
+        };
+        assert_eq!(custom.required_role("/admin/jobs"), Some(Role::Admin));
+        assert_eq!(custom.required_role("/"), None);
+        assert_eq!(custom.required_role("/archive/users"), Some(Role::Read));
+        assert_eq!(custom.required_role("/api/search"), Some(Role::Read));
+
+        // Slack signs its requests instead of sending credentials
+        assert_eq!(custom.required_role(SLACK_EVENTS_PATH), None);
+        assert_eq!(test_auth_config().required_role("/slack/events"), None);
+        assert_eq!(test_auth_config().required_role("/slack/other"), Some(Role::Read));
+    }
+
+    #[test]
+    fn test_auth_authorize() {
+        let auth = test_auth_config();
+        let read = Credentials::ApiKey("read-key");
+        let admin = Credentials::ApiKey("admin-key");
+        let basic = Credentials::Basic {
+            username: "alice",
+            password: "s3cret",
+        };
+        let wrong = Credentials::Basic {
+            username: "alice",
+            password: "nope",
+        };
+
+        assert_eq!(auth.authorize("/archive/users", None), AuthDecision::Unauthorized);
+        assert_eq!(auth.authorize("/archive/users", Some(&read)), AuthDecision::Allowed);
+        assert_eq!(auth.authorize("/archive/users", Some(&basic)), AuthDecision::Allowed);
+        assert_eq!(auth.authorize("/archive/users", Some(&wrong)), AuthDecision::Unauthorized);
+        assert_eq!(auth.authorize("/admin/update", Some(&read)), AuthDecision::Forbidden);
+        assert_eq!(auth.authorize("/admin/update", Some(&admin)), AuthDecision::Allowed);
+        assert_eq!(auth.authorize("/public/index.html", None), AuthDecision::Allowed);
+    }
+
+    #[test]
+    fn test_constant_time_eq() {
+        assert!(constant_time_eq(b"abc", b"abc"));
+        assert!(!constant_time_eq(b"abc", b"abd"));
+        assert!(!constant_time_eq(b"abc", b"abcd"));
+    }
+
+    #[test]
+    fn test_config_compression() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let base = r#"
+[slack-archive]
+base_path = "/data/archive"
+"#;
+
+        fs::write(&config_path, format!("[server]\nhost = \"h\"\nport = 1\n{}", base)).unwrap();
+        let compression = Config::from_file(&config_path).unwrap().server.compression;
+        assert!(compression.enabled && compression.gzip && compression.deflate && compression.zstd);
+        assert_eq!(compression.min_size, 1024);
+
+        fs::write(
+            &config_path,
+            format!(
+                "[server]\nhost = \"h\"\nport = 1\n[server.compression]\nzstd = false\nmin_size = 10\n{}",
+                base
+            ),
+        )
+        .unwrap();
+        let compression = Config::from_file(&config_path).unwrap().server.compression;
+        assert!(compression.gzip && !compression.zstd);
+        assert_eq!(compression.min_size, 10);
+    }
+
+    #[test]
+    fn test_find_attachment() {
+        let dir = tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("F01")).unwrap();
+        fs::write(dir.path().join("F01").join("F01ABC.png"), b"png").unwrap();
+        fs::write(dir.path().join("F01").join("F01NOEXT"), b"raw").unwrap();
+        fs::write(dir.path().join("F01").join("F01PART.partial"), b"par").unwrap();
+
+        assert_eq!(
+            find_attachment(dir.path(), "F01ABC"),
+            Some(dir.path().join("F01").join("F01ABC.png"))
+        );
+        assert_eq!(
+            find_attachment(dir.path(), "F01NOEXT"),
+            Some(dir.path().join("F01").join("F01NOEXT"))
+        );
+        assert_eq!(find_attachment(dir.path(), "F01AB"), None);
+        assert_eq!(find_attachment(dir.path(), "F01PART"), None);
+
+        // Duplicates removed by download-attachments --dedup remove
+        fs::write(
+            dir.path().join("manifest.json"),
+            r#"{"F02DUP": {"path": "F01/F01ABC.png", "name": "a.png", "sha256": "x", "duplicate_of": "F01ABC"},
+                "F02BAD": {"path": "../secret.png", "name": "b.png", "sha256": "y"}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            find_attachment(dir.path(), "F02DUP"),
+            Some(dir.path().join("F01/F01ABC.png"))
+        );
+        assert_eq!(find_attachment(dir.path(), "F02BAD"), None);
+        assert_eq!(find_attachment(dir.path(), "../F01ABC"), None);
+        assert_eq!(find_attachment(dir.path(), ""), None);
+    }
+
+    #[test]
+    fn test_find_emoji() {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("party-parrot.gif"), b"gif").unwrap();
+        fs::write(dir.path().join("+1.png"), b"png").unwrap();
+
+        assert_eq!(
+            find_emoji(dir.path(), "party-parrot"),
+            Some(dir.path().join("party-parrot.gif"))
+        );
+        assert_eq!(find_emoji(dir.path(), "+1"), Some(dir.path().join("+1.png")));
+        assert_eq!(find_emoji(dir.path(), "party"), None);
+        assert_eq!(find_emoji(dir.path(), ".."), None);
+        assert_eq!(find_emoji(dir.path(), "a/../b"), None);
+    }
+
+    #[test]
+    fn test_media_content_type() {
+        assert_eq!(media_content_type(Path::new("a/F1.PNG")), "image/png");
+        assert_eq!(media_content_type(Path::new("a/F1.jpeg")), "image/jpeg");
+        assert_eq!(media_content_type(Path::new("a/F1")), "application/octet-stream");
+    }
+
+    #[test]
+    fn test_file_etag() {
+        let modified = UNIX_EPOCH + std::time::Duration::new(1_700_000_000, 5);
+        let etag = file_etag(1024, modified);
+        assert_eq!(etag, "\"400-6553f100-5\"");
+        assert_ne!(etag, file_etag(1025, modified));
+        assert_ne!(etag, file_etag(1024, modified + std::time::Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn test_resolve_byte_range() {
+        use Bound::{Excluded, Included, Unbounded};
+
+        assert_eq!(resolve_byte_range(Included(0), Included(99), 1000), Some((0, 99)));
+        assert_eq!(resolve_byte_range(Included(900), Unbounded, 1000), Some((900, 999)));
+        assert_eq!(resolve_byte_range(Included(900), Included(5000), 1000), Some((900, 999)));
+        assert_eq!(resolve_byte_range(Included(0), Excluded(10), 1000), Some((0, 9)));
+        assert_eq!(resolve_byte_range(Included(1000), Unbounded, 1000), None);
+        assert_eq!(resolve_byte_range(Included(10), Included(5), 1000), None);
+        assert_eq!(resolve_byte_range(Included(0), Unbounded, 0), None);
+    }
+
+    #[test]
+    fn test_config_cors_defaults() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let config_content = r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[slack-archive]
+base_path = "/data/archive"
+
+[cors]
+allowed-origins = ["https://viewer.example.com"]
+allowed-methods = ["GET"]
+"#;
+        fs::write(&config_path, config_content).unwrap();
+
+        let cors = Config::from_file(&config_path).unwrap().cors.unwrap();
+        assert_eq!(cors.allowed_origins, vec!["https://viewer.example.com"]);
+        assert_eq!(cors.allowed_methods, vec!["GET"]);
+        assert!(cors.allowed_headers.contains(&"Authorization".to_string()));
+        assert!(cors.expose_headers.contains(&"X-Total-Count".to_string()));
+        assert!(!cors.allows_any_origin());
+    }
+
+    fn rate_limiter(per_minute: u32, burst: Option<u32>) -> RateLimiter {
+        RateLimiter::new(RateLimitConfig {
+            requests_per_minute: per_minute,
+            burst,
+            key_requests_per_minute: Some(per_minute * 10),
+            trust_forwarded_for: false,
+        })
+    }
+
+    #[test]
+    fn test_rate_limiter_burst_and_refill() {
+        let limiter = rate_limiter(60, Some(2));
+        let ip = RateLimitKey::Ip("10.0.0.1".parse().unwrap());
+        let now = Instant::now();
+
+        assert!(limiter.check(&ip, now).is_ok());
+        assert!(limiter.check(&ip, now).is_ok());
+        let retry_after = limiter.check(&ip, now).unwrap_err();
+        assert!(retry_after <= Duration::from_secs(1));
+
+        // One request per second refills
+        assert!(limiter.check(&ip, now + Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_rate_limiter_separates_clients() {
+        let limiter = rate_limiter(1, None);
+        let now = Instant::now();
+        let first = RateLimitKey::Ip("10.0.0.1".parse().unwrap());
+        let second = RateLimitKey::Ip("10.0.0.2".parse().unwrap());
+
+        assert!(limiter.check(&first, now).is_ok());
+        assert!(limiter.check(&first, now).is_err());
+        assert!(limiter.check(&second, now).is_ok());
+
+        // Credentials get their own, larger bucket
+        let key = RateLimitKey::Credential("reader");
+        for _ in 0..10 {
+            assert!(limiter.check(&key, now).is_ok());
+        }
+        assert!(limiter.check(&key, now).is_err());
+    }
+
+    #[test]
+    fn test_rate_limit_key_for_request() {
+        let auth = test_auth_config();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        fn key<'a>(credentials: Credentials<'a>, auth: Option<&AuthConfig>) -> RateLimitKey<'a> {
+            RateLimitKey::for_request(Some(&credentials), auth, "10.0.0.1".parse().unwrap())
+        }
+
+        assert_eq!(key(Credentials::ApiKey("read-key"), Some(&auth)), RateLimitKey::Credential("read-key"));
+        let alice = Credentials::Basic { username: "alice", password: "s3cret" };
+        assert_eq!(key(alice, Some(&auth)), RateLimitKey::Credential("alice"));
+
+        // Unchecked or rejected credentials don't get a bucket of their own
+        assert_eq!(key(Credentials::ApiKey("made-up"), Some(&auth)), RateLimitKey::Ip(ip));
+        let guess = Credentials::Basic { username: "alice", password: "guess" };
+        assert_eq!(key(guess, Some(&auth)), RateLimitKey::Ip(ip));
+        assert_eq!(key(Credentials::ApiKey("read-key"), None), RateLimitKey::Ip(ip));
+        assert_eq!(RateLimitKey::for_request(None, Some(&auth), ip), RateLimitKey::Ip(ip));
+    }
+
+    #[test]
+    fn test_config_named_archives() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let config_content = r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[slack-archive]
+base_path = "./archive"
+
+[[archives]]
+name = "acme"
+base_path = "./acme"
+
+[archives.media]
+emojis = "./acme-emojis"
+
+[[archives]]
+name = "oss-community"
+base_path = "./oss"
+"#;
+        fs::write(&config_path, config_content).unwrap();
+
+        let config = Config::from_file(&config_path).unwrap();
+        assert_eq!(config.archives.len(), 2);
+        assert_eq!(config.archives[0].name, "acme");
+        assert_eq!(config.archives[0].media.emojis.as_deref(), Some("./acme-emojis"));
+        assert!(config.archives[1].meilisearch.is_none());
+
+        let mut invalid = config.clone();
+        invalid.archives[1].name = "acme".to_string();
+        assert!(matches!(invalid.validate(), Err(AppError::InvalidConfig(_))));
+        invalid.archives[1].name = "api".to_string();
+        assert!(matches!(invalid.validate(), Err(AppError::InvalidConfig(_))));
+        invalid.archives[1].name = "../etc".to_string();
+        assert!(matches!(invalid.validate(), Err(AppError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_archive_range_request_weeks() {
+        let request = ArchiveRangeRequest {
+            from_year: 2024,
+            from_week: 50,
+            to_year: Some(2025),
+            to_week: Some(2),
+        };
+        let (from, to) = request.weeks().unwrap();
+        assert_eq!(from, YearWeek::new(2024, 50));
+        assert_eq!(to, YearWeek::new(2025, 2));
+
+        let single = ArchiveRangeRequest {
+            to_year: None,
+            to_week: None,
+            ..request.clone()
+        };
+        assert_eq!(single.weeks().unwrap().1, YearWeek::new(2024, 50));
+
+        let backwards = ArchiveRangeRequest {
+            to_year: Some(2024),
+            to_week: Some(1),
+            ..request.clone()
+        };
+        assert!(backwards.weeks().is_err());
+
+        let invalid = ArchiveRangeRequest {
+            from_week: 60,
+            ..request
+        };
+        assert!(invalid.weeks().is_err());
+    }
+
+    #[test]
+    fn test_job_registry_lifecycle() {
+        let jobs = JobRegistry::new();
+        let job = jobs.start("archive-range").unwrap();
+        assert_eq!(job.state, JobState::Running);
+        assert!(jobs.start("archive-range").is_none());
+
+        jobs.progress(&job.id, 1, 4, "2024-W01 - fetching...");
+        let status = jobs.get(&job.id).unwrap();
+        assert_eq!((status.current, status.total), (1, 4));
+        assert_eq!(status.message, "2024-W01 - fetching...");
+
+        jobs.finish(&job.id, Ok(serde_json::json!({"total_messages": 3})));
+        let status = jobs.get(&job.id).unwrap();
+        assert_eq!(status.state, JobState::Completed);
+        assert_eq!(status.result.unwrap()["total_messages"], 3);
+        assert!(status.finished_at.is_some());
+
+        let failed = jobs.start("archive-range").unwrap();
+        jobs.finish(&failed.id, Err(AppError::MissingToken));
+        let status = jobs.get(&failed.id).unwrap();
+        assert_eq!(status.state, JobState::Failed);
+        assert_eq!(status.error.as_deref(), Some("SLACK_TOKEN environment variable not set"));
+        assert_eq!(jobs.list().len(), 2);
+        assert!(jobs.get("unknown").is_none());
+    }
+
+    #[test]
+    fn test_sql_config_resolve() {
+        let config: SqlConfig = toml::from_str(
+            r#"
+[queries]
+per-channel = "SELECT channel_name, COUNT(*) FROM messages GROUP BY 1"
+"#,
+        )
+        .unwrap();
+        assert_eq!(config.max_rows, 1000);
+        assert_eq!(config.timeout_secs, 30);
+        assert!(!config.ad_hoc);
+        assert!(config.resolve(Some("per-channel"), None).unwrap().starts_with("SELECT"));
+        assert!(config.resolve(Some("missing"), None).is_err());
+        assert!(config.resolve(None, Some("SELECT 1")).is_err());
+        assert!(config.resolve(None, None).is_err());
+
+        let ad_hoc = SqlConfig {
+            ad_hoc: true,
+            ..config
+        };
+        assert_eq!(ad_hoc.resolve(None, Some("SELECT 1")).unwrap(), "SELECT 1");
+        assert!(ad_hoc.resolve(Some("per-channel"), Some("SELECT 1")).is_err());
+    }
+
+    #[test]
+    fn test_config_restart_required() {
+        let config: Config = toml::from_str(
+            r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[slack-archive]
+base_path = "./archive"
+"#,
+        )
+        .unwrap();
+        let mut reloaded = config.clone();
+        reloaded.server.static_assets = Some("./static".to_string());
+        reloaded.meilisearch = Some(MeilisearchConfig::default());
+        assert!(config.restart_required(&reloaded).is_empty());
+
+        reloaded.server.port = 9090;
+        assert_eq!(config.restart_required(&reloaded), vec!["server.port"]);
+
+        reloaded.live = Some(LiveConfig {
+            app_token: "xapp-1".to_string(),
+            buffer: 16,
+        });
+        assert_eq!(config.restart_required(&reloaded), vec!["server.port", "live"]);
+
+        reloaded.events = Some(EventsConfig {
+            signing_secret: "secret".to_string(),
+            staging: None,
+            compact_every: 60,
+        });
+        assert_eq!(
+            config.restart_required(&reloaded),
+            vec!["server.port", "live", "events"]
+        );
+    }
+
+    #[test]
+    fn test_events_config() {
+        let config: Config = toml::from_str(
+            r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+
+[slack-archive]
+base_path = "./archive"
+
+[events]
+signing-secret = "abc"
+"#,
+        )
+        .unwrap();
+        let events = config.events.unwrap();
+        assert_eq!(events.signing_secret, "abc");
+        assert_eq!(events.compact_every, 300);
+        assert_eq!(events.staging_dir("./archive"), Path::new("./archive/live-staging"));
+
+        let custom = EventsConfig {
+            staging: Some("/var/spool/slack".to_string()),
+            ..events
+        };
+        assert_eq!(custom.staging_dir("./archive"), Path::new("/var/spool/slack"));
+    }
+
+    #[test]
+    fn test_archive_stats() {
+        let (dir, service) = create_test_archive();
+        let stats = service.stats().unwrap();
+        assert_eq!(stats.total_messages, 0);
+        assert!(stats.first_week.is_none());
+
+        let conversations = vec![
+            serde_json::json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1705312800.000000", "text": "week 3"},
+                    {"ts": "1705917600.000000", "text": "week 4"}
+                ]
+            }),
+            serde_json::json!({
+                "channel_id": "C2",
+                "channel_name": "random",
+                "messages": [{"ts": "1705917700.000000", "text": "week 4"}]
+            }),
+        ];
+        crate::write_conversations_parquet(&dir.path().join("conversations"), &conversations)
+            .unwrap();
+
+        let stats = service.stats().unwrap();
+        assert_eq!(stats.total_messages, 3);
+        assert_eq!(stats.first_week, Some(YearWeek::new(2024, 3)));
+        assert_eq!(stats.last_week, Some(YearWeek::new(2024, 4)));
+        assert_eq!(stats.weeks.len(), 2);
+        assert_eq!(stats.weeks[1].channels.get("random"), Some(&1));
+        assert_eq!(stats.channels.get("general"), Some(&2));
+        assert!(stats.total_bytes > 0);
+
+        // Cached results are reused, removed partitions dropped
+        fs::remove_dir_all(dir.path().join("conversations/year=2024/week=03")).unwrap();
+        let stats = service.stats().unwrap();
+        assert_eq!(stats.total_messages, 2);
+        assert_eq!(stats.first_week, Some(YearWeek::new(2024, 4)));
+    }
+
     #[test]
     fn test_config_from_file_not_found() {
         let result = Config::from_file(Path::new("/nonexistent/config.toml"));
@@ -440,4 +2250,191 @@ index-name = "slack-messages"
         let path = service.threads_path(2024, 42);
         assert!(path.to_string_lossy().contains("week=42"));
     }
+
+    fn write_test_threads(dir: &Path) {
+        let conversations = vec![
+            serde_json::json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [{"ts": "1705312800.000000", "user": "U1", "text": "hello"}]
+            }),
+            serde_json::json!({
+                "channel_id": "C2",
+                "channel_name": "random",
+                "messages": [{"ts": "1705312900.000000", "user": "U2", "text": "lunch?"}]
+            }),
+        ];
+        crate::parquet::write_conversations_parquet(&dir.join("conversations"), &conversations).unwrap();
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        assert_eq!(parse_fields("id, name,,tz "), vec!["id", "name", "tz"]);
+        assert!(parse_fields("").is_empty());
+    }
+
+    #[test]
+    fn test_threads_json_channel_filter_and_fields() {
+        let (dir, service) = create_test_archive();
+        write_test_threads(dir.path());
+        let fields = parse_fields("text,channel_name");
+
+        let page = MessagePage {
+            channel: Some("random".to_string()),
+            ..Default::default()
+        };
+
+        let result = service.threads_json(2024, 3, &page, Some(&fields)).unwrap();
+
+        assert_eq!(result.total, 1);
+        assert_eq!(result.rows.len(), 1);
+        assert_eq!(result.rows[0].len(), 2);
+        assert_eq!(result.rows[0]["text"], "lunch?");
+        assert_eq!(result.rows[0]["channel_name"], "random");
+
+        let page = MessagePage {
+            channel: Some("C1".to_string()),
+            ..Default::default()
+        };
+        let by_id = service.threads_json(2024, 3, &page, None).unwrap();
+        assert_eq!(by_id.rows.len(), 1);
+        assert_eq!(by_id.rows[0]["user"], "U1");
+    }
+
+    #[test]
+    fn test_threads_html_renders_blocks_and_names() {
+        let (dir, service) = create_test_archive();
+        let block = serde_json::json!({
+            "type": "rich_text",
+            "elements": [{
+                "type": "rich_text_section",
+                "elements": [
+                    {"type": "text", "text": "ping "},
+                    {"type": "user", "user_id": "U2"},
+                    {"type": "text", "text": " about ", "style": {"bold": true}}
+                ]
+            }]
+        });
+        let conversations = vec![serde_json::json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "1705312800.000000", "user": "U1", "text": "ping <@U2>", "blocks": [block]},
+                {"ts": "1705312900.000000", "user": "U9", "text": "<b>plain</b>"}
+            ]
+        })];
+        crate::parquet::write_conversations_parquet(&dir.path().join("conversations"), &conversations)
+            .unwrap();
+        let users = vec![
+            serde_json::json!({"id": "U1", "name": "alice", "profile": {"display_name": "Alice"}}),
+            serde_json::json!({"id": "U2", "name": "bob", "profile": {"display_name": ""}}),
+        ];
+        crate::parquet::write_users_parquet(&service.users_path(), &users).unwrap();
+
+        let page = service.threads_html(2024, 3, &MessagePage::default()).unwrap();
+
+        assert_eq!(page.total, 2);
+        let first = &page.messages[0];
+        assert_eq!(first.user_name.as_deref(), Some("Alice"));
+        assert_eq!(first.channel_name.as_deref(), Some("general"));
+        assert!(first.html.contains("<p>"));
+        assert!(first.html.contains("bob"), "{}", first.html);
+
+        // Unknown users keep their ID and raw HTML in text is escaped
+        let second = &page.messages[1];
+        assert_eq!(second.user_name.as_deref(), Some("U9"));
+        assert!(second.html.contains("&lt;b&gt;plain&lt;/b&gt;"), "{}", second.html);
+    }
+
+    #[test]
+    fn test_threads_json_unknown_field() {
+        let (dir, service) = create_test_archive();
+        write_test_threads(dir.path());
+        let fields = parse_fields("text,nope");
+
+        let result = service.threads_json(2024, 3, &MessagePage::default(), Some(&fields));
+
+        assert!(matches!(result, Err(AppError::UnknownField(f)) if f == "nope"));
+    }
+
+    #[test]
+    fn test_threads_json_offset_limit() {
+        let (dir, service) = create_test_archive();
+        write_test_threads(dir.path());
+
+        let first = service
+            .threads_json(2024, 3, &MessagePage { offset: 0, limit: Some(1), channel: None }, None)
+            .unwrap();
+        let second = service
+            .threads_json(2024, 3, &MessagePage { offset: 1, limit: Some(1), channel: None }, None)
+            .unwrap();
+        let past_end = service
+            .threads_json(2024, 3, &MessagePage { offset: 5, limit: Some(1), channel: None }, None)
+            .unwrap();
+
+        assert_eq!(first.total, 2);
+        assert_eq!(first.rows.len(), 1);
+        assert_eq!(second.rows.len(), 1);
+        assert_ne!(first.rows[0]["ts"], second.rows[0]["ts"]);
+        assert!(past_end.rows.is_empty());
+    }
+
+    #[test]
+    fn test_threads_page_is_valid_parquet() {
+        let (dir, service) = create_test_archive();
+        write_test_threads(dir.path());
+        let page = MessagePage {
+            channel: Some("general".to_string()),
+            ..Default::default()
+        };
+
+        let result = service.threads_page(2024, 3, &page).unwrap();
+
+        assert_eq!(result.total, 1);
+        let paged = dir.path().join("page.parquet");
+        fs::write(&paged, &result.parquet).unwrap();
+        let rows = read_parquet_as_json(&paged).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["channel_name"], "general");
+    }
+
+    #[test]
+    fn test_archive_partitioned_by_channel() {
+        let (dir, service) = create_test_archive();
+        let storage = crate::storage::LocalStorage::new(dir.path().join("conversations"));
+        let mut writer = crate::parquet::ConversationsParquetWriter::new(&storage, "")
+            .partitioned_by(crate::parquet::PartitionBy::Channel);
+        for (id, name, ts) in [("C1", "general", "1705312800.000000"), ("C2", "random", "1705312900.000000")] {
+            writer
+                .write(&serde_json::json!({"channel_id": id, "channel_name": name, "messages": [{"ts": ts, "text": "hi"}]}))
+                .unwrap();
+        }
+        writer.finish().unwrap();
+
+        assert!(service.threads_exists(2024, 3));
+        assert_eq!(service.threads_files(2024, 3).len(), 2);
+        assert!(service.channel_threads_path(2024, 3, "C2").is_file());
+        let stats = service.stats().unwrap();
+        assert_eq!(stats.total_messages, 2);
+        assert_eq!(stats.weeks[0].channels.get("random"), Some(&1));
+
+        let page = |channel: Option<&str>| MessagePage {
+            channel: channel.map(str::to_string),
+            ..Default::default()
+        };
+        assert_eq!(service.threads_json(2024, 3, &page(None), None).unwrap().total, 2);
+        let by_id = service.threads_json(2024, 3, &page(Some("C2")), None).unwrap();
+        assert_eq!(by_id.rows[0]["channel_name"], "random");
+        assert_eq!(service.threads_json(2024, 3, &page(Some("general")), None).unwrap().total, 1);
+        assert!(matches!(
+            service.threads_json(2024, 4, &page(None), None),
+            Err(AppError::ReadFile { .. })
+        ));
+    }
+
+    #[test]
+    fn test_users_json_missing_file() {
+        let (_dir, service) = create_test_archive();
+        assert!(matches!(service.users_json(None), Err(AppError::ReadFile { .. })));
+    }
 }
@@ -0,0 +1,478 @@
+//! Attachment manifest written by `download-attachments`: which local file
+//! holds each Slack file, to check an archive and to point exported
+//! conversations at the downloaded copies. Also the size budget that limits
+//! what a run downloads.
+
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::export_writer::JsonArrayWriter;
+use crate::json_stream::for_each_conversation;
+use crate::slack::FileInfo;
+use crate::storage::LocalStorage;
+use crate::{load_json_file, AppError, Result};
+
+/// Manifest file name inside the attachments directory
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// A downloaded attachment
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    /// Location relative to the attachments directory, with `/` separators
+    pub path: String,
+    /// File name in Slack
+    pub name: String,
+    /// Hex SHA-256 of the content
+    pub sha256: String,
+    /// ID of the first file downloaded with the same content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<String>,
+}
+
+/// What to do with a download whose content matches a file already in the
+/// manifest, e.g. an image re-uploaded to another channel
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DedupMode {
+    /// Keep both copies
+    #[default]
+    Keep,
+    /// Replace the new copy with a hard link to the first one
+    HardLink,
+    /// Delete the new copy; its manifest entry points at the first one
+    Remove,
+}
+
+impl std::str::FromStr for DedupMode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(DedupMode::Keep),
+            "hardlink" => Ok(DedupMode::HardLink),
+            "remove" => Ok(DedupMode::Remove),
+            _ => Err(AppError::InvalidConfig(format!(
+                "unknown dedup mode \"{}\" (expected keep, hardlink or remove)",
+                s
+            ))),
+        }
+    }
+}
+
+/// What to do when the files to download add up to more than
+/// `--max-total-size`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BudgetMode {
+    /// Download files in order until the next one would go over the budget
+    #[default]
+    Stop,
+    /// Leave out the largest files until the rest fit
+    SkipLargest,
+}
+
+impl std::str::FromStr for BudgetMode {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "stop" => Ok(BudgetMode::Stop),
+            "skip-largest" => Ok(BudgetMode::SkipLargest),
+            _ => Err(AppError::InvalidConfig(format!(
+                "unknown budget mode \"{}\" (expected stop or skip-largest)",
+                s
+            ))),
+        }
+    }
+}
+
+/// Split `files` into those that fit in `budget` bytes and those left out,
+/// by the sizes in the file metadata. Files without a size count as empty.
+pub fn apply_budget(files: Vec<FileInfo>, budget: u64, mode: BudgetMode) -> (Vec<FileInfo>, Vec<FileInfo>) {
+    let size = |f: &FileInfo| f.size.unwrap_or(0);
+    match mode {
+        BudgetMode::Stop => {
+            let mut used = 0u64;
+            let fits = files
+                .iter()
+                .take_while(|f| {
+                    used = used.saturating_add(size(f));
+                    used <= budget
+                })
+                .count();
+            let mut kept = files;
+            let left_out = kept.split_off(fits);
+            (kept, left_out)
+        }
+        BudgetMode::SkipLargest => {
+            let mut by_size: Vec<usize> = (0..files.len()).collect();
+            by_size.sort_by_key(|&i| std::cmp::Reverse(files.get(i).map_or(0, size)));
+            let mut used: u64 = files.iter().map(size).sum();
+            let mut dropped = vec![false; files.len()];
+            for i in by_size {
+                if used <= budget {
+                    break;
+                }
+                used -= files.get(i).map_or(0, size);
+                if let Some(d) = dropped.get_mut(i) {
+                    *d = true;
+                }
+            }
+            let (left_out, kept): (Vec<_>, Vec<_>) = files
+                .into_iter()
+                .zip(dropped)
+                .partition(|(_, dropped)| *dropped);
+            (
+                kept.into_iter().map(|(f, _)| f).collect(),
+                left_out.into_iter().map(|(f, _)| f).collect(),
+            )
+        }
+    }
+}
+
+/// Fail with `InsufficientSpace` when `needed` bytes don't fit in the free
+/// space of the filesystem holding `dir`. Filesystems that can't report their
+/// free space aren't checked.
+pub fn check_disk_space(dir: &Path, needed: u64) -> Result<()> {
+    let Ok(available) = fs4::available_space(dir) else {
+        return Ok(());
+    };
+    if needed > available {
+        return Err(AppError::InsufficientSpace {
+            path: dir.display().to_string(),
+            needed,
+            available,
+        });
+    }
+    Ok(())
+}
+
+/// Manifest entries by Slack file ID
+pub type Manifest = BTreeMap<String, ManifestEntry>;
+
+/// Read `dir/manifest.json`, empty when the directory has none yet
+pub fn load_manifest(dir: &Path) -> Result<Manifest> {
+    let path = dir.join(MANIFEST_FILE);
+    if !path.is_file() {
+        return Ok(Manifest::new());
+    }
+    load_json_file(&path.to_string_lossy())
+}
+
+/// Write `dir/manifest.json`
+pub fn save_manifest(dir: &Path, manifest: &Manifest) -> Result<()> {
+    let path = dir.join(MANIFEST_FILE);
+    let file = File::create(&path).map_err(|e| AppError::WriteFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), manifest)
+        .map_err(|e| AppError::JsonSerialize(e.to_string()))
+}
+
+/// Hex SHA-256 of a file's content
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    let mut reader = BufReader::new(file);
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).map_err(|e| AppError::ReadFile {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(buf.get(..n).unwrap_or_default());
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Add the file `id` saved at `path` inside `dir` to the manifest. When
+/// `dedup` isn't `Keep` and an earlier file has the same content, the new copy
+/// is linked or removed as asked. Returns whether it was a duplicate.
+pub fn add_to_manifest(
+    manifest: &mut Manifest,
+    dir: &Path,
+    id: &str,
+    name: &str,
+    path: &Path,
+    dedup: DedupMode,
+) -> Result<bool> {
+    let relative = path
+        .strip_prefix(dir)
+        .unwrap_or(path)
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/");
+    let sha256 = sha256_file(path)?;
+
+    let original = manifest
+        .iter()
+        .find(|(other, entry)| {
+            other.as_str() != id
+                && entry.duplicate_of.is_none()
+                && entry.sha256 == sha256
+                && dir.join(&entry.path).is_file()
+        })
+        .map(|(other, entry)| (other.clone(), entry.path.clone()));
+
+    let (entry_path, duplicate_of) = match (dedup, original) {
+        (DedupMode::Keep, original) => (relative, original.map(|(other, _)| other)),
+        (_, None) => (relative, None),
+        (DedupMode::HardLink, Some((other, original_path))) => {
+            // Link under a temporary name first so a failure keeps the copy
+            let link = path.with_extension("dedup");
+            let linked = std::fs::hard_link(dir.join(&original_path), &link)
+                .and_then(|()| std::fs::rename(&link, path));
+            linked.map_err(|e| AppError::WriteFile {
+                path: path.display().to_string(),
+                source: e,
+            })?;
+            (relative, Some(other))
+        }
+        (DedupMode::Remove, Some((other, original_path))) => {
+            std::fs::remove_file(path).map_err(|e| AppError::WriteFile {
+                path: path.display().to_string(),
+                source: e,
+            })?;
+            (original_path, Some(other))
+        }
+    };
+
+    let duplicate = duplicate_of.is_some();
+    manifest.insert(
+        id.to_string(),
+        ManifestEntry {
+            path: entry_path,
+            name: name.to_string(),
+            sha256,
+            duplicate_of,
+        },
+    );
+    Ok(duplicate)
+}
+
+/// Write a copy of `conversations_path` to `output_path` whose file
+/// `url_private`/`url_private_download` fields point at the copies listed in
+/// `attachments_dir`'s manifest, so exports built from it work offline.
+/// Returns how many files were rewritten.
+pub fn rewrite_conversations(conversations_path: &str, attachments_dir: &Path, output_path: &str) -> Result<usize> {
+    let manifest = load_manifest(attachments_dir)?;
+    // Each conversation is rewritten and written as it is read
+    let storage = LocalStorage::default();
+    let mut output = JsonArrayWriter::new(&storage, output_path)?;
+
+    let mut rewritten = 0;
+    for_each_conversation(conversations_path, |mut conversation| {
+        let files = conversation
+            .get_mut("messages")
+            .and_then(|m| m.as_array_mut())
+            .into_iter()
+            .flatten()
+            .filter_map(|message| message.get_mut("files").and_then(|f| f.as_array_mut()))
+            .flatten()
+            .filter_map(|file| file.as_object_mut());
+        for file in files {
+            let Some(entry) = file.get("id").and_then(|id| id.as_str()).and_then(|id| manifest.get(id)) else {
+                continue;
+            };
+            let local = attachments_dir.join(&entry.path).to_string_lossy().into_owned();
+            for key in ["url_private", "url_private_download"] {
+                if file.contains_key(key) {
+                    file.insert(key.to_string(), serde_json::Value::String(local.clone()));
+                }
+            }
+            rewritten += 1;
+        }
+        output.write(&conversation)
+    })?;
+
+    output.finish()?;
+    Ok(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::tempdir;
+
+    fn entry(path: &str) -> ManifestEntry {
+        ManifestEntry {
+            path: path.to_string(),
+            name: "photo.png".to_string(),
+            sha256: "abc".to_string(),
+            duplicate_of: None,
+        }
+    }
+
+    #[test]
+    fn test_manifest_roundtrip() {
+        let dir = tempdir().unwrap();
+        assert!(load_manifest(dir.path()).unwrap().is_empty());
+
+        let mut manifest = Manifest::new();
+        manifest.insert("F01ABC".to_string(), entry("F01/F01ABC.png"));
+        save_manifest(dir.path(), &manifest).unwrap();
+        assert_eq!(load_manifest(dir.path()).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        std::fs::write(&path, b"hello").unwrap();
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_conversations() {
+        let dir = tempdir().unwrap();
+        let attachments = dir.path().join("attachments");
+        std::fs::create_dir_all(&attachments).unwrap();
+        let mut manifest = Manifest::new();
+        manifest.insert("F01ABC".to_string(), entry("F01/F01ABC.png"));
+        save_manifest(&attachments, &manifest).unwrap();
+
+        let input = dir.path().join("conversations.json");
+        let conversations = json!([{
+            "channel_id": "C1",
+            "messages": [{
+                "ts": "1.0",
+                "files": [
+                    {"id": "F01ABC", "url_private": "https://files.slack.com/a", "url_private_download": "https://files.slack.com/a?dl"},
+                    {"id": "F02XYZ", "url_private": "https://files.slack.com/b"}
+                ]
+            }]
+        }]);
+        std::fs::write(&input, conversations.to_string()).unwrap();
+
+        let output = dir.path().join("offline.json");
+        let count = rewrite_conversations(
+            &input.to_string_lossy(),
+            &attachments,
+            &output.to_string_lossy(),
+        )
+        .unwrap();
+        assert_eq!(count, 1);
+
+        let written: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&output).unwrap()).unwrap();
+        let files = &written[0]["messages"][0]["files"];
+        let local = attachments.join("F01/F01ABC.png").to_string_lossy().into_owned();
+        assert_eq!(files[0]["url_private"], json!(local));
+        assert_eq!(files[0]["url_private_download"], json!(local));
+        assert_eq!(files[1]["url_private"], json!("https://files.slack.com/b"));
+    }
+
+    #[test]
+    fn test_dedup_mode_from_str() {
+        assert_eq!("keep".parse::<DedupMode>().unwrap(), DedupMode::Keep);
+        assert_eq!("HardLink".parse::<DedupMode>().unwrap(), DedupMode::HardLink);
+        assert_eq!("remove".parse::<DedupMode>().unwrap(), DedupMode::Remove);
+        assert!(matches!("copy".parse::<DedupMode>(), Err(AppError::InvalidConfig(_))));
+    }
+
+    fn sized(id: &str, size: Option<u64>) -> FileInfo {
+        FileInfo {
+            id: id.to_string(),
+            name: format!("{}.png", id),
+            filetype: Some("png".to_string()),
+            url: None,
+            size,
+            created: None,
+        }
+    }
+
+    fn ids(files: &[FileInfo]) -> Vec<&str> {
+        files.iter().map(|f| f.id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_apply_budget() {
+        let files = vec![
+            sized("A", Some(40)),
+            sized("B", Some(50)),
+            sized("C", None),
+            sized("D", Some(30)),
+        ];
+
+        let (kept, left_out) = apply_budget(files.clone(), 100, BudgetMode::Stop);
+        assert_eq!(ids(&kept), ["A", "B", "C"]);
+        assert_eq!(ids(&left_out), ["D"]);
+
+        let (kept, left_out) = apply_budget(files.clone(), 80, BudgetMode::SkipLargest);
+        assert_eq!(ids(&kept), ["A", "C", "D"]);
+        assert_eq!(ids(&left_out), ["B"]);
+
+        let (kept, left_out) = apply_budget(files, 500, BudgetMode::SkipLargest);
+        assert_eq!(kept.len(), 4);
+        assert!(left_out.is_empty());
+    }
+
+    #[test]
+    fn test_budget_mode_from_str() {
+        assert_eq!("stop".parse::<BudgetMode>().unwrap(), BudgetMode::Stop);
+        assert_eq!("Skip-Largest".parse::<BudgetMode>().unwrap(), BudgetMode::SkipLargest);
+        assert!(matches!("oldest".parse::<BudgetMode>(), Err(AppError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_check_disk_space() {
+        let dir = tempdir().unwrap();
+        check_disk_space(dir.path(), 0).unwrap();
+        assert!(matches!(
+            check_disk_space(dir.path(), u64::MAX),
+            Err(AppError::InsufficientSpace { .. })
+        ));
+    }
+
+    #[test]
+    fn test_add_to_manifest_dedup() {
+        let dir = tempdir().unwrap();
+        let write = |rel: &str, content: &[u8]| {
+            let path = dir.path().join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(&path, content).unwrap();
+            path
+        };
+        let mut manifest = Manifest::new();
+
+        let first = write("F01/F01A.png", b"same");
+        assert!(!add_to_manifest(&mut manifest, dir.path(), "F01A", "a.png", &first, DedupMode::Remove).unwrap());
+        assert_eq!(manifest["F01A"].path, "F01/F01A.png");
+
+        let other = write("F02/F02B.png", b"different");
+        assert!(!add_to_manifest(&mut manifest, dir.path(), "F02B", "b.png", &other, DedupMode::Remove).unwrap());
+
+        let removed = write("F03/F03C.png", b"same");
+        assert!(add_to_manifest(&mut manifest, dir.path(), "F03C", "c.png", &removed, DedupMode::Remove).unwrap());
+        assert!(!removed.exists());
+        assert_eq!(manifest["F03C"].path, "F01/F01A.png");
+        assert_eq!(manifest["F03C"].duplicate_of.as_deref(), Some("F01A"));
+
+        let linked = write("F04/F04D.png", b"same");
+        assert!(add_to_manifest(&mut manifest, dir.path(), "F04D", "d.png", &linked, DedupMode::HardLink).unwrap());
+        assert_eq!(std::fs::read(&linked).unwrap(), b"same");
+        assert_eq!(manifest["F04D"].path, "F04/F04D.png");
+
+        let kept = write("F05/F05E.png", b"same");
+        assert!(add_to_manifest(&mut manifest, dir.path(), "F05E", "e.png", &kept, DedupMode::Keep).unwrap());
+        assert!(kept.exists());
+        assert_eq!(manifest["F05E"].duplicate_of.as_deref(), Some("F01A"));
+    }
+}
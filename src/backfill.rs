@@ -0,0 +1,181 @@
+//! Fill in the thread replies an existing export is missing, e.g. one
+//! converted from an official Slack export, where parents have a
+//! `reply_count` but no `thread_replies`
+
+use serde_json::Value;
+use slack_morphism::prelude::{SlackChannelId, SlackTs};
+
+use crate::error::{AppError, Result};
+use crate::slack::{create_slack_client, fetch_thread_replies};
+use crate::SlackApiCallbacks;
+
+/// Slack errors that only affect one thread, which is skipped
+const SKIPPABLE_ERRORS: &[&str] = &["thread_not_found", "channel_not_found", "not_in_channel", "is_archived"];
+
+/// A parent whose replies aren't all in the export
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingThread {
+    /// Index of the conversation and of the message in it
+    pub conversation: usize,
+    pub message: usize,
+    pub channel_id: String,
+    pub channel_name: String,
+    pub ts: String,
+    /// The parent's `reply_count`
+    pub reply_count: u64,
+}
+
+/// Threads with `reply_count > 0` whose `thread_replies` are missing or fewer
+pub fn missing_threads(conversations: &[Value]) -> Vec<MissingThread> {
+    let mut missing = Vec::new();
+    for (conversation_idx, conversation) in conversations.iter().enumerate() {
+        let text = |key: &str| conversation.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let messages = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        for (message_idx, message) in messages.iter().enumerate() {
+            let reply_count = message.get("reply_count").and_then(|v| v.as_u64()).unwrap_or(0);
+            let replies = message
+                .get("thread_replies")
+                .and_then(|r| r.as_array())
+                .map_or(0, |r| r.len() as u64);
+            let Some(ts) = message.get("ts").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            if reply_count > replies {
+                missing.push(MissingThread {
+                    conversation: conversation_idx,
+                    message: message_idx,
+                    channel_id: text("channel_id"),
+                    channel_name: text("channel_name"),
+                    ts: ts.to_string(),
+                    reply_count,
+                });
+            }
+        }
+    }
+    missing
+}
+
+/// Set a parent's `thread_replies`
+fn patch_replies(conversations: &mut [Value], thread: &MissingThread, replies: Value) {
+    let message = conversations
+        .get_mut(thread.conversation)
+        .and_then(|c| c.get_mut("messages"))
+        .and_then(|m| m.get_mut(thread.message))
+        .and_then(|m| m.as_object_mut());
+    if let Some(message) = message {
+        message.insert("thread_replies".to_string(), replies);
+    }
+}
+
+/// What `backfill_threads` patched
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BackfillResult {
+    /// Threads with replies missing
+    pub missing: usize,
+    /// Threads patched
+    pub threads: usize,
+    pub replies: usize,
+    /// Threads skipped because Slack no longer has them or the token can't
+    /// read the channel
+    pub skipped: usize,
+    /// Why backfilling stopped early, if it did; the threads patched so far
+    /// are kept
+    pub stopped: Option<String>,
+}
+
+/// Fetch the replies of every thread `missing_threads` finds in
+/// `conversations` and patch them in place
+pub async fn backfill_threads(
+    token: &str,
+    conversations: &mut [Value],
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<BackfillResult> {
+    let (client, token_obj) = create_slack_client(token)?;
+    let session = client.open_session(&token_obj);
+
+    let missing = missing_threads(conversations);
+    let mut result = BackfillResult {
+        missing: missing.len(),
+        ..BackfillResult::default()
+    };
+
+    for (idx, thread) in missing.iter().enumerate() {
+        callbacks.report_progress(
+            idx + 1,
+            missing.len(),
+            &format!("#{} - fetching thread {}/{}", thread.channel_name, idx + 1, missing.len()),
+        );
+        let channel_id = SlackChannelId(thread.channel_id.clone());
+        let fetched = fetch_thread_replies(&session, &channel_id, &SlackTs(thread.ts.clone()), callbacks).await;
+        let replies = match fetched {
+            Ok(Some(replies)) => replies,
+            Ok(None) | Err(AppError::Cancelled(_)) => {
+                result.stopped = Some("cancelled".to_string());
+                break;
+            }
+            Err(AppError::SlackApi(e)) if SKIPPABLE_ERRORS.iter().any(|code| e.contains(code)) => {
+                callbacks.log(&format!("conversations.replies #{} {}: skipped, {}", thread.channel_name, thread.ts, e));
+                result.skipped += 1;
+                continue;
+            }
+            Err(e) => {
+                result.stopped = Some(e.to_string());
+                break;
+            }
+        };
+        callbacks.log(&format!(
+            "conversations.replies #{} {}: {} replies",
+            thread.channel_name,
+            thread.ts,
+            replies.len()
+        ));
+
+        result.threads += 1;
+        result.replies += replies.len();
+        let replies = serde_json::to_value(&replies).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+        patch_replies(conversations, thread, replies);
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn export() -> Vec<Value> {
+        vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "3.0", "reply_count": 2},
+                {"ts": "2.0", "reply_count": 2, "thread_replies": [{"ts": "2.1"}]},
+                {"ts": "1.0", "reply_count": 1, "thread_replies": [{"ts": "1.1"}]},
+                {"ts": "0.5"}
+            ]
+        })]
+    }
+
+    #[test]
+    fn test_missing_threads() {
+        let missing = missing_threads(&export());
+        let found: Vec<(&str, usize)> = missing.iter().map(|t| (t.ts.as_str(), t.message)).collect();
+        assert_eq!(found, [("3.0", 0), ("2.0", 1)]);
+        assert_eq!(missing[0].channel_id, "C1");
+        assert_eq!(missing[0].reply_count, 2);
+    }
+
+    #[test]
+    fn test_patch_replies() {
+        let mut conversations = export();
+        let thread = missing_threads(&conversations).remove(0);
+        patch_replies(&mut conversations, &thread, json!([{"ts": "3.1"}, {"ts": "3.2"}]));
+
+        assert_eq!(conversations[0]["messages"][0]["thread_replies"][1]["ts"], "3.2");
+        assert_eq!(missing_threads(&conversations).len(), 1);
+    }
+}
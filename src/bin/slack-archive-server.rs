@@ -2,26 +2,58 @@
 //!
 //! This binary provides an HTTP API to serve parquet files from a Slack archive.
 //! It supports serving users, channels, and conversation thread files,
-//! as well as searching via Meilisearch.
+//! as JSON for lightweight clients, and searching via Meilisearch.
 
-use std::net::SocketAddr;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
 
 use axum::{
-    extract::{Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    extract::{
+        ws::{rejection::WebSocketUpgradeRejection, Message, WebSocket, WebSocketUpgrade},
+        ConnectInfo, Path, Query, Request, State,
+    },
+    http::{header, HeaderMap, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
     routing::{get, post},
     Json, Router,
 };
+use axum_extra::headers::{
+    authorization::{Basic, Bearer},
+    Authorization, ETag, HeaderMapExt, IfModifiedSince, IfNoneMatch, IfRange, LastModified, Range,
+};
 use clap::{Parser, Subcommand};
 use serde::{Deserialize, Serialize};
-use slack_utils::archive_server::{ArchiveService, Config, MeilisearchConfig, YearWeek};
-use slack_utils::{query_meilisearch, IndexEntry};
+use slack_utils::archive_server::{
+    file_etag, parse_fields, resolve_byte_range, ArchiveService, ArchiveStats, AuthConfig, AuthDecision, Config,
+    find_attachment, find_emoji, init_tracing, media_content_type, run_archive_range_job, ArchiveRangeRequest,
+    CorsConfig, Credentials, JobRegistry, JobStatus, MediaConfig, MeilisearchConfig, RateLimitKey,
+    LogFormat, RateLimiter, RenderedMessage, YearWeek, ADMIN_PREFIX, SLACK_EVENTS_PATH,
+};
+#[cfg(feature = "duckdb")]
+use slack_utils::archive_server::SqlConfig;
+#[cfg(feature = "duckdb")]
+use slack_utils::duckdb_query::{execute_archive_query, DuckDbError};
+use slack_morphism::prelude::{SlackEventCallbackBody, SlackPushEvent, SlackSigningSecret};
+use slack_morphism::signature_verifier::SlackEventSignatureVerifier;
+use slack_utils::live::{run_socket_mode, LiveFeed, LiveMessage};
+use slack_utils::live_archive::{archive_channel_names, LiveEvent, SharedStaging};
+use slack_utils::{load_token, query_meilisearch, AppError, IndexEntry, JsonRow, MessagePage, SearchFilters};
 use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio::sync::broadcast::{self, error::RecvError};
 use tokio_util::io::ReaderStream;
+use tower_http::compression::predicate::{NotForContentType, Predicate, SizeAbove};
+use tower_http::compression::CompressionLayer;
+use tower_http::cors::{AllowOrigin, CorsLayer};
 use tower_http::services::ServeDir;
+use tower_http::trace::TraceLayer;
+use tower::ServiceExt;
+use tracing::{debug, error, info, warn, Span};
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{IntoParams, Modify, OpenApi, ToSchema};
 
 /// Slack Archive Server - HTTP server for Slack archive parquet files
 #[derive(Parser)]
@@ -40,6 +72,10 @@ enum Commands {
         /// Path to the configuration file (TOML format)
         #[arg(value_name = "CONFIG_FILE")]
         config: PathBuf,
+
+        /// Log output format; `json` emits one JSON object per access log line
+        #[arg(long, value_enum, default_value_t = LogFormat::Text)]
+        log_format: LogFormat,
     },
 }
 
@@ -48,10 +84,25 @@ enum Commands {
 struct AppState {
     archive: Arc<ArchiveService>,
     meilisearch: Option<MeilisearchConfig>,
+    media: MediaConfig,
+    /// New messages from Socket Mode; only the default archive has one
+    live: Option<LiveFeed>,
+    /// Events API ingestion; only the default archive has one
+    events: Option<SlackEvents>,
+}
+
+/// Staging for message events posted by Slack to /slack/events
+#[derive(Clone)]
+struct SlackEvents {
+    verifier: SlackEventSignatureVerifier,
+    staging: SharedStaging,
+    /// Ingested messages are also sent to live-tail subscribers
+    live: Option<LiveFeed>,
 }
 
 /// Query parameters for threads-in-range endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ThreadsInRangeQuery {
     /// Start date in ISO format (YYYY-MM-DD)
     from: String,
@@ -60,20 +111,135 @@ struct ThreadsInRangeQuery {
 }
 
 /// Query parameters for threads endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ThreadsQuery {
     year: i32,
     week: u32,
+    /// Only messages in this channel (name or ID)
+    channel: Option<String>,
+    /// Number of matching messages to skip (default: 0)
+    #[serde(default)]
+    offset: usize,
+    /// Maximum number of messages to return (default: all)
+    limit: Option<usize>,
+}
+
+impl ThreadsQuery {
+    fn page(&self) -> MessagePage {
+        MessagePage {
+            channel: self.channel.clone(),
+            offset: self.offset,
+            limit: self.limit,
+        }
+    }
+}
+
+/// Header carrying the number of messages matching a threads query
+const TOTAL_COUNT_HEADER: &str = "X-Total-Count";
+
+/// Query parameters for JSON users/channels endpoints
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct FieldsQuery {
+    /// Comma-separated list of columns to return (default: all)
+    fields: Option<String>,
+}
+
+/// Query parameters for JSON threads endpoint
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ApiThreadsQuery {
+    year: i32,
+    week: u32,
+    /// Only messages in this channel (name or ID)
+    channel: Option<String>,
+    /// Number of matching messages to skip (default: 0)
+    #[serde(default)]
+    offset: usize,
+    /// Maximum number of messages to return (default: all)
+    limit: Option<usize>,
+    /// Comma-separated list of columns to return (default: all)
+    fields: Option<String>,
+}
+
+/// Response for JSON endpoints
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct RowsResponse {
+    #[schema(value_type = Vec<Object>)]
+    rows: Vec<JsonRow>,
+    /// Number of matching rows before offset/limit
+    total: usize,
+}
+
+/// One archive hosted by this server
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct ArchiveSummary {
+    /// Archive name from `[[archives]]`; null for the `[slack-archive]` default
+    name: Option<String>,
+    /// Prefix to put before the archive's routes (`""` or `/{name}`)
+    prefix: String,
+    /// Whether `/archive/search` is configured for this archive
+    search: bool,
+}
+
+/// Response for archives index endpoint
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+struct ArchivesResponse {
+    archives: Vec<ArchiveSummary>,
+}
+
+/// State of the admin endpoints
+#[derive(Clone)]
+struct AdminState {
+    /// Default archive, refreshed by archive-range jobs
+    archive: Arc<ArchiveService>,
+    jobs: Arc<JobRegistry>,
+}
+
+/// Response for admin jobs list endpoint
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct JobsResponse {
+    jobs: Vec<JobStatus>,
+}
+
+/// State of the SQL query endpoint
+#[cfg(feature = "duckdb")]
+#[derive(Clone)]
+struct QueryState {
+    archive: Arc<ArchiveService>,
+    sql: Arc<SqlConfig>,
+}
+
+/// Body of the SQL query endpoint; exactly one of `name` and `sql`
+#[cfg(feature = "duckdb")]
+#[derive(Debug, Deserialize, ToSchema)]
+struct QueryRequest {
+    /// Name of an allowlisted query from `[sql.queries]`
+    name: Option<String>,
+    /// Read-only SELECT over `users`, `channels` and `messages` (needs `ad-hoc = true`)
+    sql: Option<String>,
+}
+
+/// Response for SQL query endpoint
+#[cfg(feature = "duckdb")]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+struct QueryResponse {
+    columns: Vec<String>,
+    #[schema(value_type = Vec<Vec<Object>>)]
+    rows: Vec<Vec<serde_json::Value>>,
+    /// More rows matched than `max-rows`
+    truncated: bool,
 }
 
 /// Response for threads-in-range endpoint
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 struct ThreadsInRangeResponse {
     available: Vec<YearWeek>,
 }
 
 /// Error response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct ErrorResponse {
     error: String,
 }
@@ -87,13 +253,23 @@ impl ErrorResponse {
 }
 
 /// Query parameters for search endpoint
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct SearchQuery {
-    /// Search query string
+    /// Search query string; supports Slack-like modifiers such as
+    /// `from:@alice in:#general before:2024-02-01 "exact phrase"`
     query: String,
     /// Maximum number of results (default: 20)
     #[serde(default = "default_search_limit")]
     limit: usize,
+    /// Only threads in this channel (name or ID)
+    channel: Option<String>,
+    /// Only threads involving this user (name or ID)
+    user: Option<String>,
+    /// Only threads on or after this date (YYYY-MM-DD)
+    from: Option<String>,
+    /// Only threads on or before this date (YYYY-MM-DD)
+    to: Option<String>,
 }
 
 fn default_search_limit() -> usize {
@@ -101,7 +277,7 @@ fn default_search_limit() -> usize {
 }
 
 /// Response for search endpoint
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SearchResponse {
     hits: Vec<IndexEntry>,
     processing_time_ms: usize,
@@ -109,16 +285,46 @@ struct SearchResponse {
 }
 
 /// Handler for GET /archive/users
-async fn get_users(State(state): State<AppState>) -> impl IntoResponse {
-    serve_parquet_file(state.archive.users_path()).await
+#[utoipa::path(
+    get,
+    path = "/archive/users",
+    tag = "archive",
+    responses(
+        (status = 200, description = "users.parquet (supports Range and conditional requests)", body = [u8]),
+        (status = 304, description = "Not modified since the cached copy"),
+        (status = 404, description = "File not found", body = ErrorResponse),
+    )
+)]
+async fn get_users(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    serve_parquet_file(state.archive.users_path(), &headers).await
 }
 
 /// Handler for GET /archive/channels
-async fn get_channels(State(state): State<AppState>) -> impl IntoResponse {
-    serve_parquet_file(state.archive.channels_path()).await
+#[utoipa::path(
+    get,
+    path = "/archive/channels",
+    tag = "archive",
+    responses(
+        (status = 200, description = "channels.parquet (supports Range and conditional requests)", body = [u8]),
+        (status = 304, description = "Not modified since the cached copy"),
+        (status = 404, description = "File not found", body = ErrorResponse),
+    )
+)]
+async fn get_channels(State(state): State<AppState>, headers: HeaderMap) -> impl IntoResponse {
+    serve_parquet_file(state.archive.channels_path(), &headers).await
 }
 
 /// Handler for GET /archive/threads-in-range
+#[utoipa::path(
+    get,
+    path = "/archive/threads-in-range",
+    tag = "archive",
+    params(ThreadsInRangeQuery),
+    responses(
+        (status = 200, description = "Year/week partitions with a threads file", body = ThreadsInRangeResponse),
+        (status = 400, description = "Invalid date", body = ErrorResponse),
+    )
+)]
 async fn get_threads_in_range(
     State(state): State<AppState>,
     Query(params): Query<ThreadsInRangeQuery>,
@@ -162,26 +368,466 @@ async fn get_threads_in_range(
 }
 
 /// Handler for GET /archive/threads
+#[utoipa::path(
+    get,
+    path = "/archive/threads",
+    tag = "archive",
+    params(ThreadsQuery),
+    responses(
+        (status = 200, description = "threads.parquet for the week, or the selected page of messages", body = [u8],
+            headers(("X-Total-Count" = usize, description = "Matching messages before offset/limit (paged requests only)"))),
+        (status = 206, description = "Requested byte range of threads.parquet", body = [u8]),
+        (status = 304, description = "Not modified since the cached copy"),
+        (status = 400, description = "Invalid week", body = ErrorResponse),
+        (status = 404, description = "No threads file for the week", body = ErrorResponse),
+    )
+)]
 async fn get_threads(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(params): Query<ThreadsQuery>,
+) -> impl IntoResponse {
+    if let Some(response) = invalid_week_response(params.week) {
+        return response;
+    }
+
+    let page = params.page();
+    let path = state.archive.threads_path(params.year, params.week);
+    // An archive partitioned by channel has no week file to send as is, the
+    // channel files are read into one
+    if page.is_full() && (path.is_file() || !state.archive.threads_exists(params.year, params.week)) {
+        return serve_parquet_file(path, &headers).await;
+    }
+
+    let result = tokio::task::spawn_blocking(move || {
+        state.archive.threads_page(params.year, params.week, &page)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(page)) => axum::response::Response::builder()
+            .status(StatusCode::OK)
+            .header("Content-Type", "application/octet-stream")
+            .header("Content-Disposition", "attachment; filename=\"threads.parquet\"")
+            .header(TOTAL_COUNT_HEADER, page.total)
+            .body(axum::body::Body::from(page.parquet))
+            .unwrap_or_else(|_| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ErrorResponse::new("Failed to build response")),
+                )
+                    .into_response()
+            }),
+        Ok(Err(e)) => read_error_response(e),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Handler for GET /api/users
+#[utoipa::path(
+    get,
+    path = "/api/users",
+    tag = "json",
+    params(FieldsQuery),
+    responses(
+        (status = 200, description = "Users as JSON rows", body = RowsResponse),
+        (status = 400, description = "Unknown field", body = ErrorResponse),
+        (status = 404, description = "File not found", body = ErrorResponse),
+    )
+)]
+async fn get_api_users(
+    State(state): State<AppState>,
+    Query(params): Query<FieldsQuery>,
+) -> impl IntoResponse {
+    let fields = params.fields.as_deref().map(parse_fields);
+    rows_response(move || {
+        let rows = state.archive.users_json(fields.as_deref())?;
+        Ok((rows.len(), rows))
+    })
+    .await
+}
+
+/// Handler for GET /api/channels
+#[utoipa::path(
+    get,
+    path = "/api/channels",
+    tag = "json",
+    params(FieldsQuery),
+    responses(
+        (status = 200, description = "Channels as JSON rows", body = RowsResponse),
+        (status = 400, description = "Unknown field", body = ErrorResponse),
+        (status = 404, description = "File not found", body = ErrorResponse),
+    )
+)]
+async fn get_api_channels(
+    State(state): State<AppState>,
+    Query(params): Query<FieldsQuery>,
+) -> impl IntoResponse {
+    let fields = params.fields.as_deref().map(parse_fields);
+    rows_response(move || {
+        let rows = state.archive.channels_json(fields.as_deref())?;
+        Ok((rows.len(), rows))
+    })
+    .await
+}
+
+/// Handler for GET /api/threads
+#[utoipa::path(
+    get,
+    path = "/api/threads",
+    tag = "json",
+    params(ApiThreadsQuery),
+    responses(
+        (status = 200, description = "Messages for the week as JSON rows", body = RowsResponse),
+        (status = 400, description = "Invalid week or unknown field", body = ErrorResponse),
+        (status = 404, description = "No threads file for the week", body = ErrorResponse),
+    )
+)]
+async fn get_api_threads(
+    State(state): State<AppState>,
+    Query(params): Query<ApiThreadsQuery>,
+) -> impl IntoResponse {
+    if let Some(response) = invalid_week_response(params.week) {
+        return response;
+    }
+
+    let fields = params.fields.as_deref().map(parse_fields);
+    let page = MessagePage {
+        channel: params.channel,
+        offset: params.offset,
+        limit: params.limit,
+    };
+    rows_response(move || {
+        let result = state
+            .archive
+            .threads_json(params.year, params.week, &page, fields.as_deref())?;
+        Ok((result.total, result.rows))
+    })
+    .await
+}
+
+/// Response for the rendered messages endpoint
+#[derive(Debug, Serialize, ToSchema)]
+struct MessagesResponse {
+    messages: Vec<RenderedMessage>,
+    /// Number of matching messages before offset/limit
+    total: usize,
+}
+
+/// Handler for GET /api/threads/html
+#[utoipa::path(
+    get,
+    path = "/api/threads/html",
+    tag = "json",
+    params(ThreadsQuery),
+    responses(
+        (status = 200, description = "Messages for the week rendered to HTML", body = MessagesResponse),
+        (status = 400, description = "Invalid week", body = ErrorResponse),
+        (status = 404, description = "No threads file for the week", body = ErrorResponse),
+    )
+)]
+async fn get_api_threads_html(
     State(state): State<AppState>,
     Query(params): Query<ThreadsQuery>,
 ) -> impl IntoResponse {
-    if params.week == 0 || params.week > 53 {
+    if let Some(response) = invalid_week_response(params.week) {
+        return response;
+    }
+
+    let page = params.page();
+    let result = tokio::task::spawn_blocking(move || {
+        state.archive.threads_html(params.year, params.week, &page)
+    })
+    .await;
+
+    match result {
+        Ok(Ok(page)) => Json(MessagesResponse {
+            messages: page.messages,
+            total: page.total,
+        })
+        .into_response(),
+        Ok(Err(e)) => read_error_response(e),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Query parameters for the live tail
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct LiveQuery {
+    /// Only forward messages of this channel ID
+    channel: Option<String>,
+}
+
+/// Handler for GET /archive/live (WebSocket upgrade)
+#[utoipa::path(
+    get,
+    path = "/archive/live",
+    tag = "archive",
+    params(LiveQuery),
+    responses(
+        (status = 101, description = "WebSocket sending each new message as a JSON text frame", body = LiveMessage),
+        (status = 503, description = "Live tail is not configured", body = ErrorResponse),
+    )
+)]
+async fn get_live(
+    State(state): State<AppState>,
+    Query(params): Query<LiveQuery>,
+    ws: Result<WebSocketUpgrade, WebSocketUpgradeRejection>,
+) -> Response {
+    let Some(feed) = state.live else {
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new("Live tail is not configured")),
+        )
+            .into_response();
+    };
+    match ws {
+        Ok(ws) => {
+            let receiver = feed.subscribe();
+            ws.on_upgrade(move |socket| forward_live(socket, receiver, params.channel))
+        }
+        Err(rejection) => rejection.into_response(),
+    }
+}
+
+/// Send live messages to a WebSocket client until either side goes away
+async fn forward_live(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<LiveMessage>,
+    channel: Option<String>,
+) {
+    loop {
+        tokio::select! {
+            received = receiver.recv() => match received {
+                Ok(message) => {
+                    if channel.as_ref().is_some_and(|channel| *channel != message.channel) {
+                        continue;
+                    }
+                    let Ok(json) = serde_json::to_string(&message) else { continue };
+                    if socket.send(Message::Text(json.into())).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(skipped)) => debug!(skipped, "Live tail client lagging"),
+                Err(RecvError::Closed) => break,
+            },
+            incoming = socket.recv() => match incoming {
+                Some(Ok(Message::Close(_)) | Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+}
+
+/// Handler for GET /api/stats
+#[utoipa::path(
+    get,
+    path = "/api/stats",
+    tag = "json",
+    responses(
+        (status = 200, description = "Message counts per week and channel", body = ArchiveStats),
+    )
+)]
+async fn get_api_stats(State(state): State<AppState>) -> impl IntoResponse {
+    match tokio::task::spawn_blocking(move || state.archive.stats()).await {
+        Ok(Ok(stats)) => Json(stats).into_response(),
+        Ok(Err(e)) => read_error_response(e),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read archive stats: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Serve a media file inline; user uploads (e.g. SVG) are sandboxed so they
+/// can't run scripts on the archive's origin
+async fn serve_media_file(path: PathBuf, headers: &HeaderMap) -> axum::response::Response {
+    let mut response = serve_file(path.clone(), headers, media_content_type(&path), "inline").await;
+    let response_headers = response.headers_mut();
+    response_headers.insert(
+        header::CONTENT_SECURITY_POLICY,
+        HeaderValue::from_static("default-src 'none'; style-src 'unsafe-inline'; sandbox"),
+    );
+    response_headers.insert(header::X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    response
+}
+
+/// Look up a media file in a configured folder on the blocking pool
+async fn media_response(
+    folder: Option<String>,
+    what: &'static str,
+    headers: &HeaderMap,
+    find: impl FnOnce(&std::path::Path) -> Option<PathBuf> + Send + 'static,
+) -> axum::response::Response {
+    let Some(folder) = folder else {
         return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            Json(ErrorResponse::new(format!("{} folder is not configured", what))),
+        )
+            .into_response();
+    };
+
+    match tokio::task::spawn_blocking(move || find(std::path::Path::new(&folder))).await {
+        Ok(Some(path)) => serve_media_file(path, headers).await,
+        Ok(None) => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("{} not found", what))),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Handler for GET /archive/files/{id}
+#[utoipa::path(
+    get,
+    path = "/archive/files/{id}",
+    tag = "archive",
+    params(("id" = String, Path, description = "Slack file ID (e.g. F0123ABCD)")),
+    responses(
+        (status = 200, description = "Attachment downloaded by download-attachments", body = [u8]),
+        (status = 404, description = "Attachment not found", body = ErrorResponse),
+        (status = 503, description = "Attachments folder is not configured", body = ErrorResponse),
+    )
+)]
+async fn get_file(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    media_response(state.media.attachments, "Attachment", &headers, move |dir| {
+        find_attachment(dir, &id)
+    })
+    .await
+}
+
+/// Handler for GET /archive/emojis/{name}
+#[utoipa::path(
+    get,
+    path = "/archive/emojis/{name}",
+    tag = "archive",
+    params(("name" = String, Path, description = "Custom emoji name without colons")),
+    responses(
+        (status = 200, description = "Emoji image exported by export-emojis", body = [u8]),
+        (status = 404, description = "Emoji not found", body = ErrorResponse),
+        (status = 503, description = "Emojis folder is not configured", body = ErrorResponse),
+    )
+)]
+async fn get_emoji(
+    State(state): State<AppState>,
+    Path(name): Path<String>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    media_response(state.media.emojis, "Emoji", &headers, move |dir| find_emoji(dir, &name)).await
+}
+
+/// Built-in viewer served at `/` when no static assets are configured
+const WEB_UI_HTML: &str = include_str!("../../resources/web-ui.html");
+
+/// Line of the web UI that sets the route prefix of the archive it browses
+const WEB_UI_PREFIX_LINE: &str = "const ARCHIVE_PREFIX = \"\";";
+
+/// Handler for GET /
+async fn get_web_ui() -> impl IntoResponse {
+    axum::response::Html(WEB_UI_HTML)
+}
+
+/// Web UI page browsing the archive mounted at `prefix`
+fn web_ui_for_prefix(prefix: &str) -> String {
+    WEB_UI_HTML.replace(
+        WEB_UI_PREFIX_LINE,
+        &format!("const ARCHIVE_PREFIX = \"{}\";", prefix),
+    )
+}
+
+/// Handler for GET /api/archives
+#[utoipa::path(
+    get,
+    path = "/api/archives",
+    tag = "json",
+    responses((status = 200, description = "Archives hosted by this server", body = ArchivesResponse))
+)]
+async fn get_archives(State(archives): State<Arc<ArchivesResponse>>) -> impl IntoResponse {
+    Json(archives.as_ref().clone())
+}
+
+/// Map an error from reading archive files to a response
+fn read_error_response(e: AppError) -> axum::response::Response {
+    match e {
+        AppError::ReadFile { path, source } if source.kind() == std::io::ErrorKind::NotFound => (
+            StatusCode::NOT_FOUND,
+            Json(ErrorResponse::new(format!("File not found: {}", path))),
+        )
+            .into_response(),
+        e @ AppError::UnknownField(_) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(e.to_string())),
+        )
+            .into_response(),
+        e => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Bad request response for weeks outside 1..=53
+fn invalid_week_response(week: u32) -> Option<axum::response::Response> {
+    (week == 0 || week > 53).then(|| {
+        (
             StatusCode::BAD_REQUEST,
             Json(ErrorResponse::new(format!(
                 "Invalid week: {}. Week must be between 1 and 53",
-                params.week
+                week
             ))),
         )
-            .into_response();
-    }
+            .into_response()
+    })
+}
 
-    let path = state.archive.threads_path(params.year, params.week);
-    serve_parquet_file(path).await
+/// Read parquet rows (and their total count) on the blocking pool and return them as JSON
+async fn rows_response<F>(read: F) -> axum::response::Response
+where
+    F: FnOnce() -> slack_utils::Result<(usize, Vec<JsonRow>)> + Send + 'static,
+{
+    match tokio::task::spawn_blocking(read).await {
+        Ok(Ok((total, rows))) => Json(RowsResponse { rows, total }).into_response(),
+        Ok(Err(e)) => read_error_response(e),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+        )
+            .into_response(),
+    }
 }
 
 /// Handler for POST /archive/search
+#[utoipa::path(
+    post,
+    path = "/archive/search",
+    tag = "search",
+    params(SearchQuery),
+    responses(
+        (status = 200, description = "Matching threads", body = SearchResponse),
+        (status = 400, description = "Invalid date filter", body = ErrorResponse),
+        (status = 503, description = "Meilisearch is not configured", body = ErrorResponse),
+    )
+)]
 async fn post_search(
     State(state): State<AppState>,
     Query(params): Query<SearchQuery>,
@@ -199,21 +845,35 @@ async fn post_search(
         }
     };
 
+    let filters = SearchFilters {
+        channel: params.channel,
+        user: params.user,
+        from: params.from,
+        to: params.to,
+        has_attachments: None,
+    };
+
     match query_meilisearch(
         &meilisearch.url,
         &meilisearch.api_key,
         &meilisearch.index_name,
         &params.query,
+        &filters,
         params.limit,
     )
     .await
     {
         Ok(result) => Json(SearchResponse {
-            hits: result.hits,
+            hits: result.hits.into_iter().map(|hit| hit.entry).collect(),
             processing_time_ms: result.processing_time_ms,
             estimated_total_hits: result.estimated_total_hits,
         })
         .into_response(),
+        Err(e @ AppError::InvalidDate(_)) => (
+            StatusCode::BAD_REQUEST,
+            Json(ErrorResponse::new(format!("Invalid search query: {}", e))),
+        )
+            .into_response(),
         Err(e) => (
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ErrorResponse::new(format!("Search failed: {}", e))),
@@ -222,25 +882,129 @@ async fn post_search(
     }
 }
 
-/// Serve a parquet file as a streaming response
-async fn serve_parquet_file(path: PathBuf) -> axum::response::Response {
+/// Byte range selected by a request's `Range` header
+enum RangeRequest {
+    Full,
+    /// Inclusive start and end offsets
+    Partial(u64, u64),
+    Unsatisfiable,
+}
+
+/// Pick the byte range to serve.
+///
+/// Only single ranges are honored; multi-range requests and ranges whose
+/// `If-Range` validator no longer matches get the full file.
+fn requested_range(
+    headers: &HeaderMap,
+    len: u64,
+    etag: Option<&ETag>,
+    modified: Option<SystemTime>,
+) -> RangeRequest {
+    let Some(range) = headers.typed_get::<Range>() else {
+        return RangeRequest::Full;
+    };
+    if let Some(if_range) = headers.typed_get::<IfRange>() {
+        let last_modified = modified.map(LastModified::from);
+        if if_range.is_modified(etag, last_modified.as_ref()) {
+            return RangeRequest::Full;
+        }
+    }
+
+    let mut ranges = range.satisfiable_ranges(len);
+    match (ranges.next(), ranges.next()) {
+        (Some((start, end)), None) => match resolve_byte_range(start, end, len) {
+            Some((start, end)) => RangeRequest::Partial(start, end),
+            None => RangeRequest::Unsatisfiable,
+        },
+        (None, _) => RangeRequest::Unsatisfiable,
+        (Some(_), Some(_)) => RangeRequest::Full,
+    }
+}
+
+/// Serve a parquet file as a streaming download
+async fn serve_parquet_file(path: PathBuf, headers: &HeaderMap) -> axum::response::Response {
+    let disposition = format!(
+        "attachment; filename=\"{}\"",
+        path.file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("data.parquet")
+    );
+    serve_file(path, headers, "application/octet-stream", &disposition).await
+}
+
+/// Serve a file as a streaming response.
+///
+/// Supports single `Range` requests so remote parquet readers (e.g. DuckDB
+/// httpfs) can fetch the footer and selected row groups only.
+async fn serve_file(
+    path: PathBuf,
+    headers: &HeaderMap,
+    content_type: &str,
+    disposition: &str,
+) -> axum::response::Response {
     match File::open(&path).await {
-        Ok(file) => {
-            let stream = ReaderStream::new(file);
+        Ok(mut file) => {
+            let metadata = match file.metadata().await {
+                Ok(metadata) => metadata,
+                Err(e) => {
+                    return (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+                    )
+                        .into_response();
+                }
+            };
+            let modified = metadata.modified().ok();
+            let etag = modified
+                .map(|m| file_etag(metadata.len(), m))
+                .and_then(|tag| tag.parse::<ETag>().ok());
+
+            if is_not_modified(headers, etag.as_ref(), modified) {
+                let mut response = StatusCode::NOT_MODIFIED.into_response();
+                insert_validators(response.headers_mut(), etag, modified);
+                return response;
+            }
+
+            let len = metadata.len();
+            let (status, byte_range) = match requested_range(headers, len, etag.as_ref(), modified) {
+                RangeRequest::Full => (StatusCode::OK, None),
+                RangeRequest::Partial(start, end) => (StatusCode::PARTIAL_CONTENT, Some((start, end))),
+                RangeRequest::Unsatisfiable => {
+                    return (
+                        StatusCode::RANGE_NOT_SATISFIABLE,
+                        [(header::CONTENT_RANGE, format!("bytes */{}", len))],
+                        Json(ErrorResponse::new("Requested range not satisfiable")),
+                    )
+                        .into_response();
+                }
+            };
+
+            let body_len = match byte_range {
+                Some((start, end)) => {
+                    if let Err(e) = file.seek(std::io::SeekFrom::Start(start)).await {
+                        return (
+                            StatusCode::INTERNAL_SERVER_ERROR,
+                            Json(ErrorResponse::new(format!("Failed to read file: {}", e))),
+                        )
+                            .into_response();
+                    }
+                    end - start + 1
+                }
+                None => len,
+            };
+            let stream = ReaderStream::new(file.take(body_len));
             let body = axum::body::Body::from_stream(stream);
 
-            axum::response::Response::builder()
-                .status(StatusCode::OK)
-                .header("Content-Type", "application/octet-stream")
-                .header(
-                    "Content-Disposition",
-                    format!(
-                        "attachment; filename=\"{}\"",
-                        path.file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("data.parquet")
-                    ),
-                )
+            let mut builder = axum::response::Response::builder()
+                .status(status)
+                .header(header::ACCEPT_RANGES, "bytes")
+                .header(header::CONTENT_LENGTH, body_len);
+            if let Some((start, end)) = byte_range {
+                builder = builder.header(header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, len));
+            }
+            let mut response = builder
+                .header("Content-Type", content_type)
+                .header("Content-Disposition", disposition)
                 .body(body)
                 .unwrap_or_else(|_| {
                     (
@@ -248,7 +1012,9 @@ async fn serve_parquet_file(path: PathBuf) -> axum::response::Response {
                         Json(ErrorResponse::new("Failed to build response")),
                     )
                         .into_response()
-                })
+                });
+            insert_validators(response.headers_mut(), etag, modified);
+            response
         }
         Err(e) if e.kind() == std::io::ErrorKind::NotFound => (
             StatusCode::NOT_FOUND,
@@ -266,87 +1032,971 @@ async fn serve_parquet_file(path: PathBuf) -> axum::response::Response {
     }
 }
 
-/// Build the router with all archive endpoints
-fn build_router(state: AppState) -> Router {
-    Router::new()
-        .route("/archive/users", get(get_users))
-        .route("/archive/channels", get(get_channels))
-        .route("/archive/threads-in-range", get(get_threads_in_range))
-        .route("/archive/threads", get(get_threads))
-        .route("/archive/search", post(post_search))
-        .with_state(state)
-}
-
-/// Build the complete application router including static file serving
-fn build_app(state: AppState, static_assets: Option<&str>) -> Router {
-    let api_router = build_router(state);
-
-    match static_assets {
-        Some(path) => api_router.fallback_service(ServeDir::new(path)),
-        None => api_router,
+/// Whether the client's cached copy is current.
+///
+/// `If-None-Match` takes precedence over `If-Modified-Since` (RFC 9110).
+fn is_not_modified(headers: &HeaderMap, etag: Option<&ETag>, modified: Option<SystemTime>) -> bool {
+    if let Some(if_none_match) = headers.typed_get::<IfNoneMatch>() {
+        return etag.is_some_and(|etag| !if_none_match.precondition_passes(etag));
+    }
+    match (headers.typed_get::<IfModifiedSince>(), modified) {
+        (Some(since), Some(modified)) => !since.is_modified(modified),
+        _ => false,
     }
 }
 
-#[tokio::main]
-async fn main() {
-    let cli = Cli::parse();
-
-    match cli.command {
-        Commands::Serve { config } => {
-            if let Err(e) = run_server(&config).await {
-                eprintln!("Error: {}", e);
-                std::process::exit(1);
-            }
-        }
+/// Add `ETag`/`Last-Modified` and ask clients to revalidate before reusing a cached copy
+fn insert_validators(headers: &mut HeaderMap, etag: Option<ETag>, modified: Option<SystemTime>) {
+    if let Some(etag) = etag {
+        headers.typed_insert(etag);
     }
+    if let Some(modified) = modified {
+        headers.typed_insert(LastModified::from(modified));
+    }
+    headers.insert(header::CACHE_CONTROL, header::HeaderValue::from_static("no-cache"));
 }
 
-async fn run_server(config_path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
-    let config = Config::from_file(config_path)?;
-
-    let archive = Arc::new(ArchiveService::new(&config.slack_archive.base_path));
-    let state = AppState {
-        archive,
-        meilisearch: config.meilisearch.clone(),
+/// Handler for POST /admin/archive-range
+#[utoipa::path(
+    post,
+    path = "/admin/archive-range",
+    tag = "admin",
+    request_body = ArchiveRangeRequest,
+    responses(
+        (status = 202, description = "Job started; poll the Location header", body = JobStatus),
+        (status = 400, description = "Invalid week range", body = ErrorResponse),
+        (status = 409, description = "An archive-range job is already running", body = ErrorResponse),
+        (status = 503, description = "SLACK_TOKEN is not set on the server", body = ErrorResponse),
+    )
+)]
+async fn post_archive_range(
+    State(admin): State<AdminState>,
+    Json(request): Json<ArchiveRangeRequest>,
+) -> impl IntoResponse {
+    let weeks = match request.weeks() {
+        Ok(weeks) => weeks,
+        Err(e) => {
+            return (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(e.to_string())))
+                .into_response()
+        }
+    };
+    let token = match load_token() {
+        Ok(token) => token,
+        Err(e) => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                Json(ErrorResponse::new(e.to_string())),
+            )
+                .into_response()
+        }
+    };
+    let Some(job) = admin.jobs.start("archive-range") else {
+        return (
+            StatusCode::CONFLICT,
+            Json(ErrorResponse::new("An archive-range job is already running")),
+        )
+            .into_response();
     };
 
-    let app = build_app(state, config.server.static_assets.as_deref());
-
-    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse()?;
+    let jobs = admin.jobs.clone();
+    let id = job.id.clone();
+    let output = admin.archive.conversations_path();
+    let runtime = tokio::runtime::Handle::current();
+    tokio::task::spawn_blocking(move || {
+        run_archive_range_job(&jobs, &id, &token, weeks, &output, &runtime);
+    });
 
-    println!("Starting Slack Archive Server on {}", addr);
-    println!("Archive base path: {}", config.slack_archive.base_path);
-    if let Some(ref static_path) = config.server.static_assets {
-        println!("Serving static assets from: {}", static_path);
-    }
-    if let Some(ref ms) = config.meilisearch {
-        println!("Meilisearch: {} (index: {})", ms.url, ms.index_name);
-    }
-    println!();
-    println!("Endpoints:");
-    println!("  GET  /archive/users              - Get users.parquet");
-    println!("  GET  /archive/channels           - Get channels.parquet");
-    println!("  GET  /archive/threads-in-range   - List available year/weeks in date range");
-    println!("        ?from=YYYY-MM-DD&to=YYYY-MM-DD");
-    println!("  GET  /archive/threads            - Get threads.parquet for a specific week");
-    println!("        ?year=YYYY&week=WW");
-    println!("  POST /archive/search             - Search messages via Meilisearch");
-    println!("        ?query=<search-query>&limit=<max-results>");
+    let location = format!("{}/jobs/{}", ADMIN_PREFIX, job.id);
+    (StatusCode::ACCEPTED, [(header::LOCATION, location)], Json(job)).into_response()
+}
 
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    axum::serve(listener, app).await?;
+/// Handler for GET /admin/jobs
+#[utoipa::path(
+    get,
+    path = "/admin/jobs",
+    tag = "admin",
+    responses((status = 200, description = "Known jobs, most recent first", body = JobsResponse))
+)]
+async fn get_jobs(State(admin): State<AdminState>) -> impl IntoResponse {
+    Json(JobsResponse {
+        jobs: admin.jobs.list(),
+    })
+}
 
-    Ok(())
+/// Handler for GET /admin/jobs/{id}
+#[utoipa::path(
+    get,
+    path = "/admin/jobs/{id}",
+    tag = "admin",
+    params(("id" = String, Path, description = "Job ID returned when the job was started")),
+    responses(
+        (status = 200, description = "Job progress and outcome", body = JobStatus),
+        (status = 404, description = "Unknown job", body = ErrorResponse),
+    )
+)]
+async fn get_job(State(admin): State<AdminState>, Path(id): Path<String>) -> impl IntoResponse {
+    match admin.jobs.get(&id) {
+        Some(job) => Json(job).into_response(),
+        None => (StatusCode::NOT_FOUND, Json(ErrorResponse::new("Job not found"))).into_response(),
+    }
 }
 
-#[cfg(test)]
+/// Handler for POST /slack/events
+#[utoipa::path(
+    post,
+    path = "/slack/events",
+    tag = "slack",
+    request_body(content = String, description = "Slack Events API payload", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Event accepted, or the url_verification challenge as text"),
+        (status = 400, description = "Not an Events API payload", body = ErrorResponse),
+        (status = 401, description = "Missing or invalid Slack signature", body = ErrorResponse),
+        (status = 500, description = "Staging the event failed; Slack retries it", body = ErrorResponse),
+    )
+)]
+async fn post_slack_events(State(events): State<SlackEvents>, headers: HeaderMap, body: String) -> Response {
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok()).unwrap_or("");
+    if let Err(e) = events.verifier.verify(
+        header(SlackEventSignatureVerifier::SLACK_SIGNED_HASH_HEADER),
+        &body,
+        header(SlackEventSignatureVerifier::SLACK_SIGNED_TIMESTAMP),
+    ) {
+        debug!("Rejected Slack event: {}", e);
+        return (StatusCode::UNAUTHORIZED, Json(ErrorResponse::new("Invalid Slack signature")))
+            .into_response();
+    }
+
+    let message = match serde_json::from_str::<SlackPushEvent>(&body) {
+        Ok(SlackPushEvent::UrlVerification(verification)) => {
+            return ([(header::CONTENT_TYPE, "text/plain")], verification.challenge).into_response();
+        }
+        Ok(SlackPushEvent::EventCallback(callback)) => match callback.event {
+            SlackEventCallbackBody::Message(message) => message,
+            _ => return StatusCode::OK.into_response(),
+        },
+        Ok(SlackPushEvent::AppRateLimited(_)) => {
+            warn!("Slack is rate limiting events sent to this app");
+            return StatusCode::OK.into_response();
+        }
+        Err(e) => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Json(ErrorResponse::new(format!("Invalid event payload: {}", e))),
+            )
+                .into_response();
+        }
+    };
+
+    if let (Some(feed), Some(live_message)) = (&events.live, LiveMessage::from_event(&message)) {
+        feed.publish(live_message);
+    }
+    // The typed event has no thread_ts for edited and deleted messages
+    let payload: serde_json::Value = serde_json::from_str(&body).unwrap_or_default();
+    let Some(event) = LiveEvent::from_payload(&message, payload.get("event").unwrap_or(&payload)) else {
+        return StatusCode::OK.into_response();
+    };
+    let staging = events.staging.clone();
+    match tokio::task::spawn_blocking(move || staging.append(&event)).await {
+        Ok(Ok(())) => StatusCode::OK.into_response(),
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Failed to stage event: {}", e))),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Staging task failed: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Compact events staged by /slack/events into the default archive every
+/// `every`; the first run picks up events left by a previous process
+fn spawn_events_compaction(staging: SharedStaging, channels_parquet: PathBuf, every: Duration) {
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(every.max(Duration::from_secs(1)));
+        loop {
+            ticker.tick().await;
+            let (staging, channels_parquet) = (staging.clone(), channels_parquet.clone());
+            let result = tokio::task::spawn_blocking(move || {
+                let channel_names = archive_channel_names(&channels_parquet)?;
+                staging.compact(&channel_names)
+            })
+            .await;
+            match result {
+                Ok(Ok(result)) if result.events() > 0 => info!(
+                    weeks = result.weeks,
+                    posted = result.posted,
+                    edited = result.edited,
+                    deleted = result.deleted,
+                    "Compacted Slack events into the archive"
+                ),
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => error!("Compacting Slack events failed: {}", e),
+                Err(e) => error!("Compaction task failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Build the admin endpoints; only mounted when `[auth]` is configured
+fn build_admin_router(archive: Arc<ArchiveService>, jobs: Arc<JobRegistry>) -> Router {
+    let state = AdminState { archive, jobs };
+    Router::new()
+        .route("/admin/archive-range", post(post_archive_range))
+        .route("/admin/jobs", get(get_jobs))
+        .route("/admin/jobs/{id}", get(get_job))
+        .with_state(state)
+}
+
+/// OpenAPI document for the archive server
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Slack Archive Server", description = "HTTP API for Slack archive parquet files"),
+    paths(
+        get_users,
+        get_channels,
+        get_threads_in_range,
+        get_threads,
+        post_search,
+        get_api_users,
+        get_api_channels,
+        get_api_threads,
+        get_api_threads_html,
+        get_api_stats,
+        get_file,
+        get_emoji,
+        get_live,
+        post_slack_events,
+        get_archives,
+        post_archive_range,
+        get_jobs,
+        get_job,
+    ),
+    modifiers(&SecuritySchemes),
+    tags(
+        (name = "archive", description = "Raw parquet files"),
+        (name = "json", description = "Parquet data as JSON rows"),
+        (name = "search", description = "Full-text search via Meilisearch"),
+        (name = "admin", description = "Archive maintenance; requires an admin credential"),
+        (name = "slack", description = "Slack Events API ingestion, verified by signing secret"),
+    )
+)]
+struct ApiDoc;
+
+/// Document the credentials accepted when `[auth]` is configured
+struct SecuritySchemes;
+
+impl Modify for SecuritySchemes {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+        );
+        components.add_security_scheme(
+            "api_key",
+            SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+        );
+        components.add_security_scheme(
+            "basic",
+            SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Basic).build()),
+        );
+    }
+}
+
+/// Handler for POST /api/query
+#[cfg(feature = "duckdb")]
+#[utoipa::path(
+    post,
+    path = "/api/query",
+    tag = "json",
+    request_body = QueryRequest,
+    responses(
+        (status = 200, description = "Query result, capped at max-rows", body = QueryResponse),
+        (status = 400, description = "Unknown query name or invalid SQL", body = ErrorResponse),
+        (status = 403, description = "Ad-hoc queries disabled or statement not read-only", body = ErrorResponse),
+        (status = 504, description = "Query ran longer than timeout-secs", body = ErrorResponse),
+    )
+)]
+async fn post_query(
+    State(state): State<QueryState>,
+    Json(request): Json<QueryRequest>,
+) -> impl IntoResponse {
+    let sql = match state.sql.resolve(request.name.as_deref(), request.sql.as_deref()) {
+        Ok(sql) => sql.to_string(),
+        Err(e) => {
+            let status = if request.name.is_none() && request.sql.is_some() {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::BAD_REQUEST
+            };
+            return (status, Json(ErrorResponse::new(e.to_string()))).into_response();
+        }
+    };
+
+    let base_path = state.archive.base_path().to_path_buf();
+    let max_rows = state.sql.max_rows;
+    let timeout = Duration::from_secs(state.sql.timeout_secs);
+    match tokio::task::spawn_blocking(move || execute_archive_query(&base_path, &sql, max_rows, timeout))
+        .await
+    {
+        Ok(Ok(result)) => Json(QueryResponse {
+            columns: result.columns,
+            rows: result.rows,
+            truncated: result.truncated,
+        })
+        .into_response(),
+        Ok(Err(e @ DuckDbError::NotAllowed(_))) => {
+            (StatusCode::FORBIDDEN, Json(ErrorResponse::new(e.to_string()))).into_response()
+        }
+        Ok(Err(e @ DuckDbError::QueryFailed(_))) => {
+            (StatusCode::BAD_REQUEST, Json(ErrorResponse::new(e.to_string()))).into_response()
+        }
+        Ok(Err(e @ DuckDbError::App(AppError::QueryTimeout(_)))) => {
+            (StatusCode::GATEWAY_TIMEOUT, Json(ErrorResponse::new(e.to_string()))).into_response()
+        }
+        Ok(Err(e)) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(e.to_string())),
+        )
+            .into_response(),
+        Err(e) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ErrorResponse::new(format!("Query task failed: {}", e))),
+        )
+            .into_response(),
+    }
+}
+
+/// Build the SQL endpoint for one archive
+#[cfg(feature = "duckdb")]
+fn build_query_router(archive: Arc<ArchiveService>, sql: SqlConfig) -> Router {
+    Router::new()
+        .route("/api/query", post(post_query))
+        .with_state(QueryState {
+            archive,
+            sql: Arc::new(sql),
+        })
+}
+
+/// OpenAPI document for the SQL endpoint, merged in when it's compiled in
+#[cfg(feature = "duckdb")]
+#[derive(OpenApi)]
+#[openapi(paths(post_query))]
+struct QueryApiDoc;
+
+/// Endpoints logged at startup (debug level); optional ones are added by run_server
+const ENDPOINTS: &[(&str, &str, &str)] = &[
+    ("GET", "/archive/users", "Get users.parquet"),
+    ("GET", "/archive/channels", "Get channels.parquet"),
+    ("GET", "/archive/threads-in-range", "List year/weeks in ?from=YYYY-MM-DD&to=YYYY-MM-DD"),
+    ("GET", "/archive/threads", "Get threads.parquet for ?year=YYYY&week=WW"),
+    ("POST", "/archive/search", "Search messages via Meilisearch (?query=&limit=)"),
+    ("GET", "/archive/files/{id}", "Downloaded attachment by Slack file ID"),
+    ("GET", "/archive/emojis/{name}", "Exported custom emoji image"),
+    ("GET", "/archive/live", "WebSocket live tail (?channel=ID), needs [live]"),
+    ("GET", "/api/users", "Users as JSON (?fields=id,name)"),
+    ("GET", "/api/channels", "Channels as JSON (?fields=id,name)"),
+    ("GET", "/api/threads", "Messages for a week as JSON"),
+    ("GET", "/api/threads/html", "Messages for a week rendered to HTML"),
+    ("GET", "/api/stats", "Message counts per week and channel"),
+    ("GET", "/api/archives", "List hosted archives"),
+    ("GET", "/api/openapi.json", "OpenAPI document"),
+];
+
+/// OpenAPI document for the endpoints compiled into this binary
+fn api_doc() -> utoipa::openapi::OpenApi {
+    #[allow(unused_mut)]
+    let mut doc = ApiDoc::openapi();
+    #[cfg(feature = "duckdb")]
+    doc.merge(QueryApiDoc::openapi());
+    doc
+}
+
+/// Handler for GET /api/openapi.json
+async fn get_openapi() -> impl IntoResponse {
+    Json(api_doc())
+}
+
+/// Swagger UI page loading the OpenAPI document; assets come from a CDN
+const SWAGGER_UI_HTML: &str = r##"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Slack Archive Server API</title>
+  <link rel="stylesheet" href="https://unpkg.com/swagger-ui-dist@5/swagger-ui.css">
+</head>
+<body>
+  <div id="swagger-ui"></div>
+  <script src="https://unpkg.com/swagger-ui-dist@5/swagger-ui-bundle.js"></script>
+  <script>
+    window.ui = SwaggerUIBundle({ url: "/api/openapi.json", dom_id: "#swagger-ui" });
+  </script>
+</body>
+</html>
+"##;
+
+/// Handler for GET /api/docs
+async fn get_swagger_ui() -> impl IntoResponse {
+    axum::response::Html(SWAGGER_UI_HTML)
+}
+
+/// Build the router with all archive endpoints
+fn build_router(state: AppState) -> Router {
+    Router::new()
+        .route("/archive/users", get(get_users))
+        .route("/archive/channels", get(get_channels))
+        .route("/archive/threads-in-range", get(get_threads_in_range))
+        .route("/archive/threads", get(get_threads))
+        .route("/archive/search", post(post_search))
+        .route("/archive/files/{id}", get(get_file))
+        .route("/archive/emojis/{name}", get(get_emoji))
+        .route("/archive/live", get(get_live))
+        .route("/api/users", get(get_api_users))
+        .route("/api/channels", get(get_api_channels))
+        .route("/api/threads", get(get_api_threads))
+        .route("/api/threads/html", get(get_api_threads_html))
+        .route("/api/stats", get(get_api_stats))
+        .route("/api/openapi.json", get(get_openapi))
+        .with_state(state)
+}
+
+/// Header accepted as an alternative to `Authorization: Bearer <key>`
+const API_KEY_HEADER: &str = "X-API-Key";
+
+/// Reject requests whose credentials don't grant the role the route requires
+async fn require_auth(State(auth): State<Arc<AuthConfig>>, request: Request, next: Next) -> Response {
+    let headers = request.headers();
+    let bearer = headers.typed_get::<Authorization<Bearer>>();
+    let basic = headers.typed_get::<Authorization<Basic>>();
+    let credentials = request_credentials(headers, bearer.as_ref(), basic.as_ref());
+
+    match auth.authorize(request.uri().path(), credentials.as_ref()) {
+        AuthDecision::Allowed => next.run(request).await,
+        AuthDecision::Unauthorized => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"slack-archive\"")],
+            Json(ErrorResponse::new("Authentication required")),
+        )
+            .into_response(),
+        AuthDecision::Forbidden => (
+            StatusCode::FORBIDDEN,
+            Json(ErrorResponse::new("Insufficient permissions")),
+        )
+            .into_response(),
+    }
+}
+
+/// Credentials from `Authorization` (bearer or basic) or the `X-API-Key` header
+fn request_credentials<'a>(
+    headers: &'a HeaderMap,
+    bearer: Option<&'a Authorization<Bearer>>,
+    basic: Option<&'a Authorization<Basic>>,
+) -> Option<Credentials<'a>> {
+    if let Some(bearer) = bearer {
+        return Some(Credentials::ApiKey(bearer.token()));
+    }
+    if let Some(basic) = basic {
+        return Some(Credentials::Basic {
+            username: basic.username(),
+            password: basic.password(),
+        });
+    }
+    headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .map(Credentials::ApiKey)
+}
+
+/// Reject clients that exceeded their rate limit with 429 and `Retry-After`
+async fn enforce_rate_limit(
+    State((limiter, auth)): State<(Arc<RateLimiter>, Option<Arc<AuthConfig>>)>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let headers = request.headers();
+    let bearer = headers.typed_get::<Authorization<Bearer>>();
+    let basic = headers.typed_get::<Authorization<Basic>>();
+    let credentials = request_credentials(headers, bearer.as_ref(), basic.as_ref());
+    let ip = client_ip(&request, limiter.config().trust_forwarded_for);
+    let key = RateLimitKey::for_request(credentials.as_ref(), auth.as_deref(), ip);
+
+    match limiter.check(&key, Instant::now()) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            let seconds = retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(header::RETRY_AFTER, seconds.max(1).to_string())],
+                Json(ErrorResponse::new("Too many requests")),
+            )
+                .into_response()
+        }
+    }
+}
+
+/// Client address from the connection, or the last `X-Forwarded-For` hop
+/// when the server runs behind a trusted proxy. That hop is the one the
+/// proxy appended; the ones before it are whatever the client sent.
+fn client_ip(request: &Request, trust_forwarded_for: bool) -> IpAddr {
+    let forwarded = trust_forwarded_for
+        .then(|| request.headers().get("X-Forwarded-For"))
+        .flatten()
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.rsplit(',').next())
+        .and_then(|ip| ip.trim().parse().ok());
+
+    forwarded
+        .or_else(|| {
+            request
+                .extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+        .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED))
+}
+
+/// Build a CORS layer from the `[cors]` section
+fn cors_layer(cors: &CorsConfig) -> Result<CorsLayer, AppError> {
+    let origins = if cors.allows_any_origin() {
+        AllowOrigin::any()
+    } else {
+        let origins = cors
+            .allowed_origins
+            .iter()
+            .map(|o| {
+                HeaderValue::from_str(o)
+                    .map_err(|_| AppError::InvalidConfig(format!("invalid CORS origin: {}", o)))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        AllowOrigin::list(origins)
+    };
+    let methods = cors
+        .allowed_methods
+        .iter()
+        .map(|m| {
+            m.parse::<Method>()
+                .map_err(|_| AppError::InvalidConfig(format!("invalid CORS method: {}", m)))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(CorsLayer::new()
+        .allow_origin(origins)
+        .allow_methods(methods)
+        .allow_headers(cors_header_names(&cors.allowed_headers)?)
+        .expose_headers(cors_header_names(&cors.expose_headers)?))
+}
+
+fn cors_header_names(names: &[String]) -> Result<Vec<HeaderName>, AppError> {
+    names
+        .iter()
+        .map(|h| {
+            h.parse::<HeaderName>()
+                .map_err(|_| AppError::InvalidConfig(format!("invalid CORS header: {}", h)))
+        })
+        .collect()
+}
+
+/// Build the complete application router including static file serving,
+/// wrapped in the auth, rate limit, CORS and compression layers enabled in `config`
+#[cfg(test)]
+fn build_app(state: AppState, config: &Config) -> Result<Router, AppError> {
+    build_app_with_jobs(state, config, Arc::new(JobRegistry::new()))
+}
+
+/// Build the full application, keeping admin jobs in `jobs` so their status
+/// survives config reloads
+fn build_app_with_jobs(
+    state: AppState,
+    config: &Config,
+    jobs: Arc<JobRegistry>,
+) -> Result<Router, AppError> {
+    let mut archives = vec![ArchiveSummary {
+        name: None,
+        prefix: String::new(),
+        search: state.meilisearch.is_some(),
+    }];
+    let default_archive = state.archive.clone();
+    let events = state.events.clone();
+    let mut api_router = build_router(state);
+    if let Some(events) = events {
+        api_router = api_router
+            .merge(Router::new().route(SLACK_EVENTS_PATH, post(post_slack_events)).with_state(events));
+    }
+    #[cfg(feature = "duckdb")]
+    if let Some(sql) = &config.sql {
+        api_router = api_router.merge(build_query_router(default_archive.clone(), sql.clone()));
+    }
+
+    for named in &config.archives {
+        let prefix = format!("/{}", named.name);
+        let archive = Arc::new(ArchiveService::new(&named.base_path));
+        let state = AppState {
+            archive: archive.clone(),
+            meilisearch: named.meilisearch.clone(),
+            media: named.media.clone(),
+            live: None,
+            events: None,
+        };
+        let mut router = build_router(state);
+        #[cfg(feature = "duckdb")]
+        if let Some(sql) = &config.sql {
+            router = router.merge(build_query_router(archive.clone(), sql.clone()));
+        }
+        if config.server.static_assets.is_none() {
+            let page = web_ui_for_prefix(&prefix);
+            router = router.route(
+                "/",
+                get(move || std::future::ready(axum::response::Html(page.clone()))),
+            );
+        }
+        api_router = api_router.nest(&prefix, router);
+        archives.push(ArchiveSummary {
+            name: Some(named.name.clone()),
+            prefix,
+            search: named.meilisearch.is_some(),
+        });
+    }
+
+    let api_router = api_router.merge(
+        Router::new()
+            .route("/api/archives", get(get_archives))
+            .with_state(Arc::new(ArchivesResponse { archives })),
+    );
+
+    // Admin jobs write to the archive, so they are never served unauthenticated
+    let api_router = if config.auth.is_some() {
+        api_router.merge(build_admin_router(default_archive, jobs))
+    } else {
+        api_router
+    };
+
+    let api_router = if config.server.swagger_ui {
+        api_router.route("/api/docs", get(get_swagger_ui))
+    } else {
+        api_router
+    };
+
+    let app = match config.server.static_assets.as_deref() {
+        Some(path) => api_router.fallback_service(ServeDir::new(path)),
+        None => api_router.route("/", get(get_web_ui)),
+    };
+
+    let app = match config.auth.clone() {
+        Some(auth) => app.layer(middleware::from_fn_with_state(Arc::new(auth), require_auth)),
+        None => app,
+    };
+
+    // Outside auth so rejected requests count against the limit too; only
+    // credentials auth accepts get a bucket of their own
+    let app = match config.rate_limit.clone() {
+        Some(rate_limit) => app.layer(middleware::from_fn_with_state(
+            (Arc::new(RateLimiter::new(rate_limit)), config.auth.clone().map(Arc::new)),
+            enforce_rate_limit,
+        )),
+        None => app,
+    };
+
+    // Outside auth so preflight requests, which carry no credentials, are answered
+    let app = match config.cors.as_ref() {
+        Some(cors) => app.layer(cors_layer(cors)?),
+        None => app,
+    };
+
+    let compression = &config.server.compression;
+    let app = if compression.enabled {
+        // Images are already compressed; parquet and JSON shrink a lot
+        let predicate = SizeAbove::new(compression.min_size).and(NotForContentType::IMAGES);
+        app.layer(
+            CompressionLayer::new()
+                .gzip(compression.gzip)
+                .deflate(compression.deflate)
+                .zstd(compression.zstd)
+                .compress_when(predicate),
+        )
+    } else {
+        app
+    };
+
+    // Outermost so requests rejected by auth or the rate limiter are logged too
+    let trust_forwarded_for = config
+        .rate_limit
+        .as_ref()
+        .is_some_and(|r| r.trust_forwarded_for);
+    Ok(app.layer(
+        TraceLayer::new_for_http()
+            .make_span_with(move |request: &Request| {
+                tracing::info_span!(
+                    "request",
+                    method = %request.method(),
+                    path = %request.uri().path(),
+                    client_ip = %client_ip(request, trust_forwarded_for),
+                )
+            })
+            .on_request(())
+            .on_response(|response: &Response, latency: Duration, _span: &Span| {
+                let bytes = response
+                    .headers()
+                    .get(header::CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse::<u64>().ok());
+                info!(
+                    target: "access",
+                    status = response.status().as_u16(),
+                    latency_ms = latency.as_secs_f64() * 1000.0,
+                    bytes,
+                    "request completed"
+                );
+            }),
+    ))
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    match cli.command {
+        Commands::Serve { config, log_format } => {
+            init_tracing(log_format);
+            if let Err(e) = run_server(&config).await {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// State for the default archive described by `config`; the live feed and
+/// events staging outlive reloads, so they are passed in
+fn app_state(config: &Config, live: Option<LiveFeed>, events: Option<SlackEvents>) -> AppState {
+    AppState {
+        archive: Arc::new(ArchiveService::new(&config.slack_archive.base_path)),
+        meilisearch: config.meilisearch.clone(),
+        media: config.media.clone(),
+        live,
+        events,
+    }
+}
+
+/// Resolve once SIGINT (Ctrl+C) or SIGTERM is received
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        () = ctrl_c => {},
+        () = terminate => {},
+    }
+    info!("Shutting down, waiting for in-flight requests to finish");
+}
+
+/// Re-read the config file and build a new application from it
+fn reload_app(
+    config_path: &std::path::Path,
+    current: &Config,
+    jobs: &Arc<JobRegistry>,
+    live: Option<LiveFeed>,
+    events: Option<SlackEvents>,
+) -> Result<(Config, Router), AppError> {
+    let config = Config::from_file(config_path)?;
+    for setting in current.restart_required(&config) {
+        warn!("{} changed; restart the server to apply it", setting);
+    }
+    let app = build_app_with_jobs(app_state(&config, live, events), &config, jobs.clone())?;
+    Ok((config, app))
+}
+
+/// Rebuild the application on SIGHUP; the previous one keeps serving if the
+/// new config is invalid
+#[cfg(unix)]
+fn spawn_reload_on_sighup(
+    config_path: PathBuf,
+    mut config: Config,
+    jobs: Arc<JobRegistry>,
+    live: Option<LiveFeed>,
+    events: Option<SlackEvents>,
+    app: tokio::sync::watch::Sender<Router>,
+) -> Result<(), std::io::Error> {
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+    tokio::spawn(async move {
+        while hangup.recv().await.is_some() {
+            info!(path = %config_path.display(), "SIGHUP received, reloading configuration");
+            match reload_app(&config_path, &config, &jobs, live.clone(), events.clone()) {
+                Ok((new_config, new_app)) => {
+                    config = new_config;
+                    let _ = app.send_replace(new_app);
+                    info!("Configuration reloaded");
+                }
+                Err(e) => error!("Reload failed, keeping the current configuration: {}", e),
+            }
+        }
+    });
+    Ok(())
+}
+
+async fn run_server(config_path: &std::path::Path) -> Result<(), Box<dyn std::error::Error>> {
+    let config = Config::from_file(config_path)?;
+
+    let jobs = Arc::new(JobRegistry::new());
+    let live = config.live.as_ref().map(|live| {
+        let feed = LiveFeed::new(live.buffer);
+        let (app_token, socket_feed) = (live.app_token.clone(), feed.clone());
+        tokio::spawn(async move {
+            if let Err(e) = run_socket_mode(&app_token, socket_feed).await {
+                error!("Live tail disabled, Socket Mode connection failed: {}", e);
+            }
+        });
+        feed
+    });
+    let events = config.events.as_ref().map(|events| {
+        let archive = ArchiveService::new(&config.slack_archive.base_path);
+        let staging = SharedStaging::new(
+            events.staging_dir(&config.slack_archive.base_path),
+            archive.conversations_path(),
+        );
+        spawn_events_compaction(
+            staging.clone(),
+            archive.channels_path(),
+            Duration::from_secs(events.compact_every),
+        );
+        SlackEvents {
+            verifier: SlackEventSignatureVerifier::new(&SlackSigningSecret::new(
+                events.signing_secret.clone(),
+            )),
+            staging,
+            live: live.clone(),
+        }
+    });
+    let app = build_app_with_jobs(
+        app_state(&config, live.clone(), events.clone()),
+        &config,
+        jobs.clone(),
+    )?;
+
+    let addr: SocketAddr = format!("{}:{}", config.server.host, config.server.port).parse()?;
+
+    info!(%addr, "Starting Slack Archive Server");
+    info!(base_path = %config.slack_archive.base_path, "Default archive");
+    for named in &config.archives {
+        info!(name = %named.name, base_path = %named.base_path, "Named archive at /{}", named.name);
+    }
+    match config.server.static_assets {
+        Some(ref static_path) => info!(path = %static_path, "Serving static assets"),
+        None => info!("Serving built-in web UI at http://{}/", addr),
+    }
+    if let Some(ref ms) = config.meilisearch {
+        info!(url = %ms.url, index = %ms.index_name, "Meilisearch search enabled");
+    }
+    if !config.server.compression.enabled {
+        info!("Response compression disabled");
+    }
+    if let Some(ref cors) = config.cors {
+        info!(origins = %cors.allowed_origins.join(", "), "CORS enabled");
+    }
+    match (&config.sql, cfg!(feature = "duckdb")) {
+        (Some(sql), true) => info!(
+            named_queries = sql.queries.len(),
+            ad_hoc = sql.ad_hoc,
+            max_rows = sql.max_rows,
+            "SQL endpoint enabled"
+        ),
+        (Some(_), false) => warn!("[sql] ignored, rebuild with --features server,duckdb"),
+        (None, _) => {}
+    }
+    if let Some(ref live) = config.live {
+        info!(buffer = live.buffer, "Live tail enabled at /archive/live (Socket Mode)");
+    }
+    if let Some(ref events) = config.events {
+        info!(
+            staging = %events.staging_dir(&config.slack_archive.base_path).display(),
+            compact_every = events.compact_every,
+            "Slack Events API ingestion enabled at {}",
+            SLACK_EVENTS_PATH
+        );
+    }
+    if let Some(ref rate_limit) = config.rate_limit {
+        info!(requests_per_minute = rate_limit.requests_per_minute, "Rate limiting enabled");
+    }
+    match config.auth {
+        Some(ref auth) => info!(
+            api_keys = auth.api_keys.len(),
+            users = auth.users.len(),
+            "Authentication enabled"
+        ),
+        None => warn!("Authentication disabled (archive is served to anyone)"),
+    }
+    for (method, path, description) in ENDPOINTS {
+        debug!("{:<5}{:<28} {}", method, path, description);
+    }
+    if cfg!(feature = "duckdb") && config.sql.is_some() {
+        debug!("{:<5}{:<28} {}", "POST", "/api/query", "Read-only SQL over users/channels/messages");
+    }
+    if config.auth.is_some() {
+        debug!("{:<5}{:<28} {}", "POST", "/admin/archive-range", "Fetch weeks from Slack (admin)");
+        debug!("{:<5}{:<28} {}", "GET", "/admin/jobs/{id}", "Poll an admin job");
+    }
+    if config.events.is_some() {
+        debug!("{:<5}{:<28} {}", "POST", SLACK_EVENTS_PATH, "Slack Events API (signed by Slack)");
+    }
+    if config.server.swagger_ui {
+        debug!("{:<5}{:<28} {}", "GET", "/api/docs", "Swagger UI");
+    }
+    if !config.archives.is_empty() {
+        debug!("Named archives serve the same endpoints under /{{name}}/…");
+    }
+
+    // Requests are dispatched to the latest app so SIGHUP can swap it
+    let (app_tx, app_rx) = tokio::sync::watch::channel(app);
+    #[cfg(unix)]
+    spawn_reload_on_sighup(config_path.to_path_buf(), config, jobs, live, events, app_tx)?;
+    #[cfg(not(unix))]
+    let _ = (config, jobs, live, events, app_tx);
+    let dispatch = tower::service_fn(move |request: Request| {
+        let app = app_rx.borrow().clone();
+        app.oneshot(request)
+    });
+    let root = Router::new().fallback_service(dispatch);
+
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    axum::serve(listener, root.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(shutdown_signal())
+        .await?;
+
+    info!("Server stopped");
+    Ok(())
+}
+
+#[cfg(test)]
 mod tests {
     use super::*;
+    use slack_utils::archive_server::{
+        CompressionConfig, NamedArchiveConfig, RateLimitConfig, ServerConfig, SlackArchiveConfig,
+    };
     use axum::body::Body;
     use axum::http::Request;
     use std::fs;
     use tempfile::tempdir;
-    use tower::ServiceExt;
 
     fn create_test_app() -> (tempfile::TempDir, Router) {
         let dir = tempdir().unwrap();
@@ -354,6 +2004,9 @@ mod tests {
         let state = AppState {
             archive,
             meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
         };
         let router = build_router(state);
         (dir, router)
@@ -653,53 +2306,314 @@ mod tests {
         assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
     }
 
-    // ===========================================
-    // Path Traversal Security Tests
-    // ===========================================
-
-    /// Verifies that path traversal via URL-encoded sequences in year parameter
-    /// results in a 400 Bad Request (invalid i32 parsing), not file access.
-    #[tokio::test]
-    async fn test_path_traversal_year_url_encoded_dots() {
-        let (_dir, app) = create_test_app();
-
-        // %2e%2e = ".." URL-encoded
+    async fn get_rows(app: Router, uri: &str) -> (StatusCode, Option<RowsResponse>) {
         let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/archive/threads?year=%2e%2e&week=1")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
             .await
             .unwrap();
-
-        // Should fail to parse as i32, returning 400
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        (status, serde_json::from_slice(&body).ok())
     }
 
-    /// Verifies that path traversal via URL-encoded sequences in week parameter
-    /// results in a 400 Bad Request (invalid u32 parsing), not file access.
     #[tokio::test]
-    async fn test_path_traversal_week_url_encoded_dots() {
-        let (_dir, app) = create_test_app();
+    async fn test_api_users_fields() {
+        let (dir, app) = create_test_app();
+        let users = vec![serde_json::json!({"id": "U1", "name": "alice", "tz": "UTC"})];
+        slack_utils::write_users_parquet(&dir.path().join("users.parquet"), &users).unwrap();
 
-        // %2e%2e%2f = "../" URL-encoded
-        let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/archive/threads?year=2024&week=%2e%2e%2f")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
-            .await
-            .unwrap();
+        let (status, body) = get_rows(app, "/api/users?fields=id,name").await;
 
-        // Should fail to parse as u32, returning 400
-        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(status, StatusCode::OK);
+        let rows = body.unwrap().rows;
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].len(), 2);
+        assert_eq!(rows[0]["name"], "alice");
     }
 
-    /// Verifies that path traversal attempts in the 'from' date parameter
+    #[tokio::test]
+    async fn test_api_users_unknown_field() {
+        let (dir, app) = create_test_app();
+        let users = vec![serde_json::json!({"id": "U1", "name": "alice"})];
+        slack_utils::write_users_parquet(&dir.path().join("users.parquet"), &users).unwrap();
+
+        let (status, _) = get_rows(app, "/api/users?fields=password").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_api_channels_not_found() {
+        let (_dir, app) = create_test_app();
+        let (status, _) = get_rows(app, "/api/channels").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+    }
+
+    fn write_two_channel_week(dir: &std::path::Path) {
+        let conversations = vec![
+            serde_json::json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [{"ts": "1705312800.000000", "user": "U1", "text": "hello"}]
+            }),
+            serde_json::json!({
+                "channel_id": "C2",
+                "channel_name": "random",
+                "messages": [{"ts": "1705312900.000000", "user": "U2", "text": "lunch?"}]
+            }),
+        ];
+        slack_utils::write_conversations_parquet(&dir.join("conversations"), &conversations).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_api_threads_channel_filter() {
+        let (dir, app) = create_test_app();
+        write_two_channel_week(dir.path());
+
+        let (status, body) = get_rows(app, "/api/threads?year=2024&week=3&channel=general&fields=text").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let body = body.unwrap();
+        assert_eq!(body.total, 1);
+        assert_eq!(body.rows.len(), 1);
+        assert_eq!(body.rows[0]["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_api_threads_pagination() {
+        let (dir, app) = create_test_app();
+        write_two_channel_week(dir.path());
+
+        let (status, body) = get_rows(app, "/api/threads?year=2024&week=3&offset=1&limit=1").await;
+
+        assert_eq!(status, StatusCode::OK);
+        let body = body.unwrap();
+        assert_eq!(body.total, 2);
+        assert_eq!(body.rows.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_live_not_configured() {
+        let (_dir, app) = create_test_app();
+
+        let response = get_uri(app, "/archive/live").await;
+
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_live_requires_websocket_upgrade() {
+        let dir = tempdir().unwrap();
+        let app = build_router(AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: Some(LiveFeed::new(4)),
+            events: None,
+        });
+
+        let response = get_uri(app, "/archive/live?channel=C1").await;
+
+        assert!(response.status().is_client_error());
+    }
+
+    const TEST_SIGNING_SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+
+    /// App with `/slack/events` enabled and `[auth]` configured, which the
+    /// events endpoint must bypass
+    fn create_events_test_app() -> (tempfile::TempDir, Router, SharedStaging) {
+        let dir = tempdir().unwrap();
+        let archive = ArchiveService::new(dir.path());
+        let staging = SharedStaging::new(dir.path().join("live-staging"), archive.conversations_path());
+        let feed = LiveFeed::new(4);
+        let state = AppState {
+            archive: Arc::new(archive),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: Some(feed.clone()),
+            events: Some(SlackEvents {
+                verifier: SlackEventSignatureVerifier::new(&SlackSigningSecret::new(
+                    TEST_SIGNING_SECRET.to_string(),
+                )),
+                staging: staging.clone(),
+                live: Some(feed),
+            }),
+        };
+        let mut config = test_config(dir.path(), CompressionConfig::default(), None);
+        config.auth = Some(AuthConfig::default());
+        let app = build_app(state, &config).unwrap();
+        (dir, app, staging)
+    }
+
+    fn slack_signature(timestamp: &str, body: &str) -> String {
+        use hmac::{Hmac, Mac};
+        let mut mac = Hmac::<sha2::Sha256>::new_from_slice(TEST_SIGNING_SECRET.as_bytes()).unwrap();
+        mac.update(format!("v0:{}:{}", timestamp, body).as_bytes());
+        let hex: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+        format!("v0={}", hex)
+    }
+
+    async fn post_slack_event(app: Router, body: &str, signature: Option<String>) -> axum::response::Response {
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let signature = signature.unwrap_or_else(|| slack_signature(&timestamp, body));
+        app.oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(SLACK_EVENTS_PATH)
+                .header("Content-Type", "application/json")
+                .header("X-Slack-Request-Timestamp", timestamp)
+                .header("X-Slack-Signature", signature)
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_slack_events_url_verification() {
+        let (_dir, app, _staging) = create_events_test_app();
+        let body = r#"{"type":"url_verification","token":"t","challenge":"3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P"}"#;
+
+        let response = post_slack_event(app.clone(), body, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let bytes = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&bytes[..], b"3eZbrw1aBm2rZgRNFdxV2595E9CY3gmdALWMmHkvFXO7tYXAYM8P");
+
+        let response = post_slack_event(app, body, Some("v0=deadbeef".to_string())).await;
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_slack_events_stage_messages() {
+        let (dir, app, staging) = create_events_test_app();
+        let body = serde_json::json!({
+            "type": "event_callback",
+            "team_id": "T1",
+            "api_app_id": "A1",
+            "event_id": "Ev1",
+            "event_time": 1705312800,
+            "event": {
+                "type": "message",
+                "channel": "C1",
+                "user": "U1",
+                "ts": "1705312800.000100",
+                "text": "hello"
+            }
+        })
+        .to_string();
+
+        let response = post_slack_event(app, &body, None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(dir.path().join("live-staging/year=2024/week=03/events.ndjson").is_file());
+
+        let result = staging.compact(&std::collections::HashMap::new()).unwrap();
+        assert_eq!(result.posted, 1);
+        let rows = slack_utils::read_parquet_as_json(
+            &dir.path().join("conversations/year=2024/week=03/threads.parquet"),
+        )
+        .unwrap();
+        assert_eq!(rows[0]["text"], "hello");
+    }
+
+    #[tokio::test]
+    async fn test_api_stats() {
+        let (dir, app) = create_test_app();
+        write_two_channel_week(dir.path());
+
+        let response = get_uri(app, "/api/stats").await;
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let stats: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(stats["total_messages"], 2);
+        assert_eq!(stats["first_week"], serde_json::json!({"year": 2024, "week": 3}));
+        assert_eq!(stats["channels"]["random"], 1);
+        assert_eq!(stats["weeks"][0]["channels"]["general"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_get_threads_channel_page() {
+        let (dir, app) = create_test_app();
+        write_two_channel_week(dir.path());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/threads?year=2024&week=3&channel=random&limit=10")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get(TOTAL_COUNT_HEADER).unwrap(), "1");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+        let paged = dir.path().join("page.parquet");
+        fs::write(&paged, &body).unwrap();
+        let rows = slack_utils::read_parquet_as_json(&paged).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["text"], "lunch?");
+    }
+
+    #[tokio::test]
+    async fn test_api_threads_invalid_week() {
+        let (_dir, app) = create_test_app();
+        let (status, _) = get_rows(app, "/api/threads?year=2024&week=54").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    // ===========================================
+    // Path Traversal Security Tests
+    // ===========================================
+
+    /// Verifies that path traversal via URL-encoded sequences in year parameter
+    /// results in a 400 Bad Request (invalid i32 parsing), not file access.
+    #[tokio::test]
+    async fn test_path_traversal_year_url_encoded_dots() {
+        let (_dir, app) = create_test_app();
+
+        // %2e%2e = ".." URL-encoded
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/threads?year=%2e%2e&week=1")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Should fail to parse as i32, returning 400
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Verifies that path traversal via URL-encoded sequences in week parameter
+    /// results in a 400 Bad Request (invalid u32 parsing), not file access.
+    #[tokio::test]
+    async fn test_path_traversal_week_url_encoded_dots() {
+        let (_dir, app) = create_test_app();
+
+        // %2e%2e%2f = "../" URL-encoded
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/threads?year=2024&week=%2e%2e%2f")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        // Should fail to parse as u32, returning 400
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+
+    /// Verifies that path traversal attempts in the 'from' date parameter
     /// are rejected by date validation.
     #[tokio::test]
     async fn test_path_traversal_date_from_with_slashes() {
@@ -777,6 +2691,9 @@ mod tests {
         let state = AppState {
             archive,
             meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
         };
         let app = build_router(state);
 
@@ -919,4 +2836,784 @@ mod tests {
         // The important thing is it doesn't expose files
         assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
     }
+
+    fn create_auth_test_app() -> (tempfile::TempDir, Router) {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("users.parquet"), b"users data").unwrap();
+        let static_dir = dir.path().join("static");
+        fs::create_dir_all(&static_dir).unwrap();
+        fs::write(static_dir.join("index.html"), b"<html></html>").unwrap();
+
+        let config: Config = toml::from_str(&format!(
+            r#"
+[server]
+host = "127.0.0.1"
+port = 8080
+static_assets = "{}"
+
+[slack-archive]
+base_path = "{}"
+
+[auth]
+public-routes = ["/index.html"]
+admin-routes = ["/archive/channels"]
+
+[[auth.api-keys]]
+key = "reader"
+
+[[auth.api-keys]]
+key = "admin"
+role = "admin"
+
+[[auth.users]]
+username = "alice"
+password = "s3cret"
+"#,
+            static_dir.display(),
+            dir.path().display()
+        ))
+        .unwrap();
+
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let app = build_app(state, &config).unwrap();
+        (dir, app)
+    }
+
+    async fn auth_status(app: Router, uri: &str, header: Option<(&str, &str)>) -> StatusCode {
+        let mut request = Request::builder().uri(uri);
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+            .status()
+    }
+
+    #[tokio::test]
+    async fn test_auth_rejects_missing_credentials() {
+        let (_dir, app) = create_auth_test_app();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/users")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            response.headers().get("WWW-Authenticate").unwrap(),
+            "Basic realm=\"slack-archive\""
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auth_accepts_api_key_and_basic() {
+        let (_dir, app) = create_auth_test_app();
+
+        let bearer = Some(("Authorization", "Bearer reader"));
+        assert_eq!(auth_status(app.clone(), "/archive/users", bearer).await, StatusCode::OK);
+
+        let api_key = Some(("X-API-Key", "reader"));
+        assert_eq!(auth_status(app.clone(), "/archive/users", api_key).await, StatusCode::OK);
+
+        // alice:s3cret
+        let basic = Some(("Authorization", "Basic YWxpY2U6czNjcmV0"));
+        assert_eq!(auth_status(app.clone(), "/archive/users", basic).await, StatusCode::OK);
+
+        let wrong = Some(("Authorization", "Bearer nope"));
+        assert_eq!(auth_status(app, "/archive/users", wrong).await, StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_auth_admin_routes_and_public_routes() {
+        let (_dir, app) = create_auth_test_app();
+
+        let reader = Some(("Authorization", "Bearer reader"));
+        assert_eq!(auth_status(app.clone(), "/archive/channels", reader).await, StatusCode::FORBIDDEN);
+
+        // Admin keys pass the role check; the file itself is missing
+        let admin = Some(("Authorization", "Bearer admin"));
+        assert_eq!(auth_status(app.clone(), "/archive/channels", admin).await, StatusCode::NOT_FOUND);
+
+        assert_eq!(auth_status(app.clone(), "/index.html", None).await, StatusCode::OK);
+        assert_eq!(auth_status(app, "/other.html", None).await, StatusCode::UNAUTHORIZED);
+    }
+
+    fn test_config(
+        base_path: &std::path::Path,
+        compression: CompressionConfig,
+        cors: Option<CorsConfig>,
+    ) -> Config {
+        Config {
+            server: ServerConfig {
+                host: "127.0.0.1".to_string(),
+                port: 8080,
+                static_assets: None,
+                swagger_ui: false,
+                compression,
+            },
+            slack_archive: SlackArchiveConfig {
+                base_path: base_path.display().to_string(),
+            },
+            meilisearch: None,
+            auth: None,
+            cors,
+            rate_limit: None,
+            media: MediaConfig::default(),
+            archives: Vec::new(),
+            sql: None,
+            live: None,
+            events: None,
+        }
+    }
+
+    fn create_compression_test_app(compression: CompressionConfig) -> (tempfile::TempDir, Router) {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("users.parquet"), "users data ".repeat(500)).unwrap();
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let config = test_config(dir.path(), compression, None);
+        let app = build_app(state, &config).unwrap();
+        (dir, app)
+    }
+
+    async fn content_encoding(app: Router, accept_encoding: &str) -> Option<String> {
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/users")
+                    .header("Accept-Encoding", accept_encoding)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        response
+            .headers()
+            .get("Content-Encoding")
+            .map(|v| v.to_str().unwrap().to_string())
+    }
+
+    #[tokio::test]
+    async fn test_compression_negotiates_encoding() {
+        let (_dir, app) = create_compression_test_app(CompressionConfig::default());
+
+        assert_eq!(content_encoding(app.clone(), "gzip").await.as_deref(), Some("gzip"));
+        assert_eq!(content_encoding(app.clone(), "zstd").await.as_deref(), Some("zstd"));
+        assert_eq!(content_encoding(app, "identity").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_compression_respects_config() {
+        let disabled = CompressionConfig {
+            enabled: false,
+            ..CompressionConfig::default()
+        };
+        let (_dir, app) = create_compression_test_app(disabled);
+        assert_eq!(content_encoding(app, "gzip").await, None);
+
+        let no_zstd = CompressionConfig {
+            zstd: false,
+            ..CompressionConfig::default()
+        };
+        let (_dir, app) = create_compression_test_app(no_zstd);
+        assert_eq!(content_encoding(app.clone(), "zstd").await, None);
+        assert_eq!(content_encoding(app, "deflate").await.as_deref(), Some("deflate"));
+    }
+
+    async fn get_users_with(app: Router, header: Option<(&str, &str)>) -> axum::response::Response {
+        let mut request = Request::builder().uri("/archive/users");
+        if let Some((name, value)) = header {
+            request = request.header(name, value);
+        }
+        app.oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_get_users_conditional_etag() {
+        let (dir, app) = create_test_app();
+        fs::write(dir.path().join("users.parquet"), b"users data").unwrap();
+
+        let response = get_users_with(app.clone(), None).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("Cache-Control").unwrap(), "no-cache");
+        assert!(response.headers().contains_key("Last-Modified"));
+        let etag = response.headers().get("ETag").unwrap().to_str().unwrap().to_string();
+
+        let response = get_users_with(app.clone(), Some(("If-None-Match", etag.as_str()))).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(response.headers().get("ETag").unwrap(), etag.as_str());
+
+        let response = get_users_with(app, Some(("If-None-Match", "\"stale\""))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_get_users_conditional_last_modified() {
+        let (dir, app) = create_test_app();
+        fs::write(dir.path().join("users.parquet"), b"users data").unwrap();
+
+        let response = get_users_with(app.clone(), None).await;
+        let last_modified = response
+            .headers()
+            .get("Last-Modified")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+
+        let response = get_users_with(app.clone(), Some(("If-Modified-Since", last_modified.as_str()))).await;
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response =
+            get_users_with(app, Some(("If-Modified-Since", "Mon, 01 Jan 2001 00:00:00 GMT"))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    async fn get_users_range(app: Router, range: &str) -> axum::response::Response {
+        get_users_with(app, Some(("Range", range))).await
+    }
+
+    async fn body_bytes(response: axum::response::Response) -> Vec<u8> {
+        axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap()
+            .to_vec()
+    }
+
+    #[tokio::test]
+    async fn test_get_users_range_requests() {
+        let (dir, app) = create_test_app();
+        fs::write(dir.path().join("users.parquet"), b"0123456789").unwrap();
+
+        let response = get_users_with(app.clone(), None).await;
+        assert_eq!(response.headers().get("Accept-Ranges").unwrap(), "bytes");
+        assert_eq!(response.headers().get("Content-Length").unwrap(), "10");
+
+        let response = get_users_range(app.clone(), "bytes=2-4").await;
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers().get("Content-Range").unwrap(), "bytes 2-4/10");
+        assert_eq!(body_bytes(response).await, b"234");
+
+        // Suffix range, as used by parquet readers to fetch the footer
+        let response = get_users_range(app.clone(), "bytes=-3").await;
+        assert_eq!(response.headers().get("Content-Range").unwrap(), "bytes 7-9/10");
+        assert_eq!(body_bytes(response).await, b"789");
+
+        let response = get_users_range(app.clone(), "bytes=8-").await;
+        assert_eq!(body_bytes(response).await, b"89");
+
+        let response = get_users_range(app.clone(), "bytes=20-30").await;
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers().get("Content-Range").unwrap(), "bytes */10");
+
+        // Multiple ranges fall back to the full file
+        let response = get_users_range(app, "bytes=0-1,4-5").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"0123456789");
+    }
+
+    #[tokio::test]
+    async fn test_get_users_range_with_stale_if_range() {
+        let (dir, app) = create_test_app();
+        fs::write(dir.path().join("users.parquet"), b"0123456789").unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/archive/users")
+                    .header("Range", "bytes=0-1")
+                    .header("If-Range", "\"stale\"")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"0123456789");
+    }
+
+    fn create_cors_test_app(origins: &[&str]) -> (tempfile::TempDir, Router) {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("users.parquet"), b"users data").unwrap();
+        let cors = CorsConfig {
+            allowed_origins: origins.iter().map(|o| o.to_string()).collect(),
+            allowed_methods: vec!["GET".to_string(), "POST".to_string()],
+            allowed_headers: vec!["Authorization".to_string()],
+            expose_headers: vec!["X-Total-Count".to_string()],
+        };
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let config = test_config(dir.path(), CompressionConfig::default(), Some(cors));
+        let app = build_app(state, &config).unwrap();
+        (dir, app)
+    }
+
+    #[tokio::test]
+    async fn test_cors_allowed_origin() {
+        let (_dir, app) = create_cors_test_app(&["https://viewer.example.com"]);
+
+        let response =
+            get_users_with(app.clone(), Some(("Origin", "https://viewer.example.com"))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("Access-Control-Allow-Origin").unwrap(),
+            "https://viewer.example.com"
+        );
+        assert_eq!(
+            response.headers().get("Access-Control-Expose-Headers").unwrap(),
+            "x-total-count"
+        );
+
+        let response = get_users_with(app, Some(("Origin", "https://evil.example.com"))).await;
+        assert!(!response.headers().contains_key("Access-Control-Allow-Origin"));
+    }
+
+    #[tokio::test]
+    async fn test_cors_preflight() {
+        let (_dir, app) = create_cors_test_app(&["*"]);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("OPTIONS")
+                    .uri("/archive/search")
+                    .header("Origin", "https://viewer.example.com")
+                    .header("Access-Control-Request-Method", "POST")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("Access-Control-Allow-Origin").unwrap(), "*");
+        assert_eq!(
+            response.headers().get("Access-Control-Allow-Methods").unwrap(),
+            "GET,POST"
+        );
+    }
+
+    #[test]
+    fn test_cors_layer_rejects_invalid_origin() {
+        let cors = CorsConfig {
+            allowed_origins: vec!["bad\norigin".to_string()],
+            allowed_methods: vec![],
+            allowed_headers: vec![],
+            expose_headers: vec![],
+        };
+        assert!(matches!(cors_layer(&cors), Err(AppError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_openapi_document() {
+        let (_dir, app) = create_test_app();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/openapi.json")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let doc: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        let paths = doc["paths"].as_object().unwrap();
+        for path in [
+            "/archive/users",
+            "/archive/channels",
+            "/archive/threads-in-range",
+            "/archive/threads",
+            "/archive/search",
+            "/api/users",
+            "/api/channels",
+            "/api/threads",
+        ] {
+            assert!(paths.contains_key(path), "missing {}", path);
+        }
+        assert!(doc["paths"]["/archive/search"]["post"].is_object());
+        assert!(doc["components"]["securitySchemes"]["api_key"].is_object());
+    }
+
+    #[tokio::test]
+    async fn test_swagger_ui_is_optional() {
+        let dir = tempdir().unwrap();
+        let mut config = test_config(dir.path(), CompressionConfig::default(), None);
+        let state = || AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+
+        let app = build_app(state(), &config).unwrap();
+        let response = get_uri(app, "/api/docs").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        config.server.swagger_ui = true;
+        let app = build_app(state(), &config).unwrap();
+        let response = get_uri(app, "/api/docs").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = String::from_utf8(body_bytes(response).await).unwrap();
+        assert!(html.contains("/api/openapi.json"));
+    }
+
+    async fn get_uri(app: Router, uri: &str) -> axum::response::Response {
+        app.oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+            .await
+            .unwrap()
+    }
+
+    fn create_rate_limited_test_app() -> (tempfile::TempDir, Router) {
+        let dir = tempdir().unwrap();
+        fs::write(dir.path().join("users.parquet"), b"users data").unwrap();
+        let mut config = test_config(dir.path(), CompressionConfig::default(), None);
+        config.rate_limit = Some(RateLimitConfig {
+            requests_per_minute: 1,
+            burst: Some(2),
+            key_requests_per_minute: None,
+            trust_forwarded_for: true,
+        });
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let app = build_app(state, &config).unwrap();
+        (dir, app)
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_returns_429_with_retry_after() {
+        let (_dir, app) = create_rate_limited_test_app();
+        let client = Some(("X-Forwarded-For", "203.0.113.7"));
+
+        assert_eq!(get_users_with(app.clone(), client).await.status(), StatusCode::OK);
+        assert_eq!(get_users_with(app.clone(), client).await.status(), StatusCode::OK);
+
+        let response = get_users_with(app.clone(), client).await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .headers()
+            .get("Retry-After")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!((1..=60).contains(&retry_after));
+
+        // Other clients and API keys have their own buckets
+        let other = Some(("X-Forwarded-For", "203.0.113.8"));
+        assert_eq!(get_users_with(app.clone(), other).await.status(), StatusCode::OK);
+        let key = Some(("X-API-Key", "some-key"));
+        assert_eq!(get_users_with(app, key).await.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_web_ui_served_without_static_assets() {
+        let dir = tempdir().unwrap();
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let config = test_config(dir.path(), CompressionConfig::default(), None);
+        let app = build_app(state, &config).unwrap();
+
+        let response = get_uri(app, "/").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = String::from_utf8(body_bytes(response).await).unwrap();
+        assert!(html.contains("/api/threads/html"));
+    }
+
+    #[tokio::test]
+    async fn test_api_threads_html() {
+        let (dir, app) = create_test_app();
+        write_two_channel_week(dir.path());
+
+        let response = get_uri(app.clone(), "/api/threads/html?year=2024&week=3&channel=random").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body["total"], 1);
+        assert_eq!(body["messages"][0]["channel_name"], "random");
+        assert!(body["messages"][0]["html"].as_str().unwrap().starts_with("<p>"));
+
+        let response = get_uri(app, "/api/threads/html?year=2024&week=4").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    fn create_media_test_app() -> (tempfile::TempDir, Router) {
+        let dir = tempdir().unwrap();
+        let attachments = dir.path().join("attachments");
+        let emojis = dir.path().join("emojis");
+        fs::create_dir_all(attachments.join("F01")).unwrap();
+        fs::create_dir_all(&emojis).unwrap();
+        fs::write(attachments.join("F01").join("F01ABC.png"), b"png data").unwrap();
+        fs::write(emojis.join("party-parrot.gif"), b"gif data").unwrap();
+
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig {
+                attachments: Some(attachments.display().to_string()),
+                emojis: Some(emojis.display().to_string()),
+            },
+            live: None,
+            events: None,
+        };
+        (dir, build_router(state))
+    }
+
+    #[tokio::test]
+    async fn test_get_attachment_and_emoji() {
+        let (_dir, app) = create_media_test_app();
+
+        let response = get_uri(app.clone(), "/archive/files/F01ABC").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("Content-Type").unwrap(), "image/png");
+        assert!(response.headers().contains_key("Content-Security-Policy"));
+        assert_eq!(body_bytes(response).await, b"png data");
+
+        let response = get_uri(app.clone(), "/archive/emojis/party-parrot").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers().get("Content-Type").unwrap(), "image/gif");
+
+        let response = get_uri(app.clone(), "/archive/files/F01XYZ").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = get_uri(app, "/archive/emojis/..%2Fattachments").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_media_not_configured() {
+        let (_dir, app) = create_test_app();
+
+        let response = get_uri(app.clone(), "/archive/files/F01ABC").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = get_uri(app, "/archive/emojis/parrot").await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_named_archives() {
+        let dir = tempdir().unwrap();
+        let acme = dir.path().join("acme");
+        fs::create_dir_all(&acme).unwrap();
+        fs::write(dir.path().join("users.parquet"), b"default users").unwrap();
+        fs::write(acme.join("users.parquet"), b"acme users").unwrap();
+
+        let mut config = test_config(dir.path(), CompressionConfig::default(), None);
+        config.archives.push(NamedArchiveConfig {
+            name: "acme".to_string(),
+            base_path: acme.display().to_string(),
+            meilisearch: None,
+            media: MediaConfig::default(),
+        });
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let app = build_app(state, &config).unwrap();
+
+        let response = get_uri(app.clone(), "/archive/users").await;
+        assert_eq!(body_bytes(response).await, b"default users");
+        let response = get_uri(app.clone(), "/acme/archive/users").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(body_bytes(response).await, b"acme users");
+
+        let response = get_uri(app.clone(), "/api/archives").await;
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body["archives"][0]["prefix"], "");
+        assert_eq!(body["archives"][1]["name"], "acme");
+        assert_eq!(body["archives"][1]["prefix"], "/acme");
+
+        let response = get_uri(app.clone(), "/acme").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let html = String::from_utf8(body_bytes(response).await).unwrap();
+        assert!(html.contains("const ARCHIVE_PREFIX = \"/acme\";"));
+
+        let response = get_uri(app, "/other/archive/users").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    async fn post_json(app: Router, uri: &str, key: &str, body: &str) -> axum::response::Response {
+        app.oneshot(
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("X-API-Key", key)
+                .header("Content-Type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_require_admin_role() {
+        let (_dir, app) = create_auth_test_app();
+
+        let reader = Some(("X-API-Key", "reader"));
+        let admin = Some(("X-API-Key", "admin"));
+        assert_eq!(auth_status(app.clone(), "/admin/jobs", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(auth_status(app.clone(), "/admin/jobs", reader).await, StatusCode::FORBIDDEN);
+        assert_eq!(auth_status(app.clone(), "/admin/jobs", admin).await, StatusCode::OK);
+        assert_eq!(
+            auth_status(app.clone(), "/admin/jobs/missing", admin).await,
+            StatusCode::NOT_FOUND
+        );
+
+        let body = r#"{"from_year": 2024, "from_week": 60}"#;
+        let response = post_json(app.clone(), "/admin/archive-range", "admin", body).await;
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = post_json(app, "/admin/archive-range", "reader", body).await;
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[tokio::test]
+    async fn test_admin_endpoints_need_auth_config() {
+        let dir = tempdir().unwrap();
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let config = test_config(dir.path(), CompressionConfig::default(), None);
+        let app = build_app(state, &config).unwrap();
+
+        let response = get_uri(app, "/admin/jobs").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "duckdb")]
+    #[tokio::test]
+    async fn test_sql_query_endpoint() {
+        let dir = tempdir().unwrap();
+        write_two_channel_week(dir.path());
+        let mut config = test_config(dir.path(), CompressionConfig::default(), None);
+        config.sql = Some(toml::from_str(
+            r#"
+max-rows = 1
+[queries]
+per-channel = "SELECT channel_name, COUNT(*) AS n FROM messages GROUP BY 1 ORDER BY 1"
+"#,
+        ).unwrap());
+        let state = AppState {
+            archive: Arc::new(ArchiveService::new(dir.path())),
+            meilisearch: None,
+            media: MediaConfig::default(),
+            live: None,
+            events: None,
+        };
+        let app = build_app(state, &config).unwrap();
+
+        let post = |body: &'static str| {
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/query")
+                    .header("Content-Type", "application/json")
+                    .body(Body::from(body))
+                    .unwrap(),
+            )
+        };
+
+        let response = post(r#"{"name": "per-channel"}"#).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: serde_json::Value = serde_json::from_slice(&body_bytes(response).await).unwrap();
+        assert_eq!(body["columns"], serde_json::json!(["channel_name", "n"]));
+        assert_eq!(body["rows"], serde_json::json!([["general", 1]]));
+        assert_eq!(body["truncated"], true);
+
+        let response = post(r#"{"name": "missing"}"#).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = post(r#"{"sql": "SELECT 1"}"#).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+    }
+
+    #[test]
+    fn test_cli_log_format() {
+        let cli = Cli::try_parse_from(["slack-archive-server", "serve", "config.toml"]).unwrap();
+        let Commands::Serve { log_format, .. } = cli.command;
+        assert_eq!(log_format, LogFormat::Text);
+
+        let cli = Cli::try_parse_from([
+            "slack-archive-server",
+            "serve",
+            "config.toml",
+            "--log-format",
+            "json",
+        ])
+        .unwrap();
+        let Commands::Serve { log_format, .. } = cli.command;
+        assert_eq!(log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_reload_app() {
+        let dir = tempdir().unwrap();
+        let config_path = dir.path().join("config.toml");
+        let write_config = |port: u16, static_assets: &str| {
+            fs::write(
+                &config_path,
+                format!(
+                    "[server]\nhost = \"127.0.0.1\"\nport = {}\nstatic_assets = \"{}\"\n\n[slack-archive]\nbase_path = \"{}\"\n",
+                    port,
+                    static_assets,
+                    dir.path().display()
+                ),
+            )
+            .unwrap();
+        };
+        write_config(8080, "./static");
+        let current = Config::from_file(&config_path).unwrap();
+        let jobs = Arc::new(JobRegistry::new());
+
+        write_config(8080, "./other-static");
+        let (config, _app) = reload_app(&config_path, &current, &jobs, None, None).unwrap();
+        assert_eq!(config.server.static_assets.as_deref(), Some("./other-static"));
+
+        fs::write(&config_path, "not valid toml {{").unwrap();
+        assert!(reload_app(&config_path, &config, &jobs, None, None).is_err());
+    }
 }
@@ -1,4 +1,6 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand};
+
+use crate::LogLevel;
 
 #[derive(Parser)]
 #[command(name = "slack-utils")]
@@ -6,6 +8,25 @@ use clap::{Parser, Subcommand};
 pub struct Cli {
     #[command(subcommand)]
     pub command: Commands,
+
+    /// Diagnostic log level (API requests and retries at debug); written to
+    /// stderr, or to --log-file
+    #[arg(long, global = true, value_enum)]
+    pub log_level: Option<LogLevel>,
+
+    /// Append diagnostic logs to this file (debug level unless --log-level is given)
+    #[arg(long, global = true)]
+    pub log_file: Option<String>,
+
+    /// Print a JSON summary of the result (outputs, counts, duration, failed
+    /// channels) to stdout; the usual messages go to stderr
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Hide the progress bars and lines of long tasks; errors and the final
+    /// result are still printed
+    #[arg(long, global = true)]
+    pub quiet: bool,
 }
 
 #[derive(Subcommand)]
@@ -18,40 +39,62 @@ pub enum Commands {
 
     /// Export conversations in a date range
     ExportConversations {
-        /// Start date (YYYY-MM-DD), defaults to 7 days ago
+        /// Start date (YYYY-MM-DD), relative (7d, 2w, yesterday) or a period
+        /// (this-week, last-week, this-month, last-month, 2024-05, 2024-W18,
+        /// 2024-Q2); defaults to 30 days ago
         #[arg(short, long)]
         from: Option<String>,
 
-        /// End date (YYYY-MM-DD), defaults to today
+        /// End date, in the same forms as --from (a period ends on its last
+        /// day); defaults to the end of the --from period, or today
         #[arg(short, long)]
         to: Option<String>,
 
-        /// Output path (without extension for json, directory path for parquet)
+        /// Output path (without extension for json, directory path for parquet),
+        /// or an s3://bucket/prefix URL
         #[arg(short, long, default_value = "conversations")]
         output: String,
 
         /// Output format (json or parquet)
         #[arg(long, default_value = "json")]
         format: String,
+
+        /// Re-fetch only the channels this report (written next to a JSON
+        /// export as <output>.report.json) marks failed and merge them in
+        #[arg(long, conflicts_with_all = ["from", "to", "auto_join"])]
+        repair: Option<String>,
+
+        /// List the channels that would be fetched, from the channel list
+        /// only, without fetching any message (so with no message counts)
+        #[arg(long, conflicts_with = "repair")]
+        dry_run: bool,
+
+        #[command(flatten)]
+        join: JoinArgs,
     },
 
     /// Export conversations for a specific ISO work week
     ExportConversationsWeek {
-        /// ISO year (defaults to current year)
+        /// ISO year (defaults to current year); with --week current or last
+        /// it must be that week's year
         #[arg(short, long)]
         year: Option<i32>,
 
-        /// ISO week number 1-53 (defaults to current week)
+        /// ISO week number 1-53, current or last (defaults to current week)
         #[arg(short, long)]
-        week: Option<u32>,
+        week: Option<String>,
 
-        /// Output path (without extension for json, directory path for parquet)
+        /// Output path (without extension for json, directory path for parquet),
+        /// or an s3://bucket/prefix URL
         #[arg(short, long, default_value = "conversations")]
         output: String,
 
         /// Output format (json or parquet)
         #[arg(long, default_value = "json")]
         format: String,
+
+        #[command(flatten)]
+        join: JoinArgs,
     },
 
     /// Archive conversations for a range of ISO weeks (parquet format)
@@ -72,14 +115,267 @@ pub enum Commands {
         #[arg(long)]
         to_week: Option<u32>,
 
-        /// Output directory path for parquet files
+        /// Output directory path for parquet files, or an s3://bucket/prefix URL
+        #[arg(short, long, default_value = "conversations")]
+        output: String,
+
+        /// List the weeks that would be fetched or skipped and the channels
+        /// in each, without fetching any message (so with no message counts)
+        #[arg(long)]
+        dry_run: bool,
+
+        /// Files of a new archive: week (year=/week=/threads.parquet) or
+        /// channel (year=/week=/channel=ID/threads.parquet); an existing
+        /// archive keeps its layout
+        #[arg(long)]
+        partition_by: Option<String>,
+
+        /// files (bare parquet partitions) or delta (the same partitions plus
+        /// a Delta Lake log with a commit per archived week); an archive with
+        /// a log stays a delta table and one without is adopted by it
+        #[arg(long)]
+        table_format: Option<String>,
+    },
+
+    /// Read an archived week back from parquet into a conversations JSON file
+    ///
+    /// Messages posted that week, with their thread replies from any later
+    /// week, in the structure export-conversations writes.
+    ArchiveExtract {
+        /// Archive directory of year=*/week=* partitions
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// ISO year (defaults to current year)
+        #[arg(short, long)]
+        year: Option<i32>,
+
+        /// ISO week number 1-53 (defaults to current week)
+        #[arg(short, long)]
+        week: Option<u32>,
+
+        /// Output JSON file path
+        #[arg(short, long, default_value = "conversations.json")]
+        output: String,
+    },
+
+    /// Rewrite archived threads files for faster queries
+    ///
+    /// Rows are sorted by channel and ts and written in large row groups.
+    /// By week, each file is rewritten where it is; by month or year, the
+    /// weeks are merged into year=YYYY/month=MM or year=YYYY files under
+    /// --output, for query engines outside slack-utils.
+    ArchiveCompact {
+        /// Archive directory of year=*/week=* partitions
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// Files to write: week, month or year
+        #[arg(long, default_value = "week")]
+        by: String,
+
+        /// Directory to write to (defaults to the archive, only for --by week)
+        #[arg(short, long)]
+        output: Option<String>,
+
+        /// Most rows per parquet row group
+        #[arg(long, default_value_t = crate::compact::DEFAULT_ROW_GROUP_SIZE)]
+        row_group_size: usize,
+    },
+
+    /// Delete the files a Delta table archive no longer needs
+    ///
+    /// Each commit of an archive kept with --table-format delta writes new
+    /// copies of the weeks it fetched, leaving the ones earlier versions read.
+    /// This deletes the copies removed more than --retain-hours ago, after
+    /// which the table can no longer be read as of those versions.
+    ArchiveVacuum {
+        /// Archive directory (or s3:// / gs:// location) with a _delta_log
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// Keep files removed in the last this many hours
+        #[arg(long, default_value_t = 168)]
+        retain_hours: u64,
+    },
+
+    /// Show where the parquet files of an archive came from
+    ///
+    /// Reads the provenance export-conversations, archive-range and
+    /// live-archive record in each file's footer: slack-utils version, export
+    /// time, date range, channel filter and workspace ID.
+    ArchiveInfo {
+        /// Parquet file, or archive directory searched for .parquet files
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// List every file with its provenance
+        #[arg(long)]
+        files: bool,
+
+        /// Also write the report as JSON to this file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Upload new and changed archive files to object storage
+    ///
+    /// Partitions, the users and channels files and attachments are uploaded
+    /// when their SHA-256 differs from the last sync, recorded in
+    /// sync-manifest.json at the destination, or their copy is missing.
+    ArchiveSync {
+        /// Destination: s3://bucket/prefix, gs://bucket/prefix or a local directory
+        #[arg(short, long)]
+        dest: String,
+
+        /// Archive directory of year=*/week=* partitions
         #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// Users file to upload
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Channels file to upload
+        #[arg(short, long)]
+        channels: Option<String>,
+
+        /// Folder written by download-attachments to upload
+        #[arg(long)]
+        attachments: Option<String>,
+
+        /// List the files that would be uploaded without uploading them
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Refresh the current weeks of the archive on a cron-like schedule
+    ///
+    /// Reads the schedule, output, log rotation and webhook settings from a
+    /// TOML file (see resources/sample-archive-daemon.toml).
+    ArchiveDaemon {
+        /// Daemon configuration file
+        #[arg(short, long, default_value = "archive.toml")]
+        config: String,
+
+        /// Run once now and exit instead of waiting for the schedule
+        #[arg(long)]
+        once: bool,
+    },
+
+    /// Keep the parquet archive current from Socket Mode events
+    ///
+    /// Needs SLACK_APP_TOKEN (an app-level xapp- token with connections:write).
+    /// New messages, edits and deletions are staged as per-week NDJSON and
+    /// compacted into the archive periodically and on Ctrl+C.
+    LiveArchive {
+        /// Archive directory of year=*/week=* partitions
+        #[arg(short, long, default_value = "conversations")]
+        output: String,
+
+        /// Directory for the per-week NDJSON staging files
+        #[arg(long, default_value = "live-staging")]
+        staging: String,
+
+        /// Channels JSON file for the names of new messages' channels
+        #[arg(long)]
+        channels: Option<String>,
+
+        /// Seconds between compactions into the archive
+        #[arg(long, default_value_t = 300)]
+        compact_every: u64,
+    },
+
+    /// Fetch the thread replies an existing export is missing: parents with
+    /// a reply_count but no (or fewer) thread_replies, e.g. in exports
+    /// converted from the official Slack export
+    BackfillThreads {
+        /// Conversations JSON file to patch
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Write the patched export here instead of replacing the input
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Post a message to a channel, e.g. to announce a finished archive run
+    /// or share a generated digest
+    ///
+    /// The token needs chat:write (and the bot must be in the channel).
+    PostMessage {
+        /// Channel ID (or name) to post to
+        #[arg(short, long)]
+        channel: String,
+
+        /// Message text, in Slack mrkdwn
+        #[arg(short, long, required_unless_present = "markdown", conflicts_with = "markdown")]
+        text: Option<String>,
+
+        /// Markdown file to post as markdown blocks; long documents continue
+        /// in the first message's thread
+        #[arg(short, long)]
+        markdown: Option<String>,
+
+        /// Post as a reply in the thread of this message ts
+        #[arg(long)]
+        thread_ts: Option<String>,
+    },
+
+    /// Write the week's highlights, the most-reacted and most-replied
+    /// messages of each channel, to markdown and optionally post them
+    ///
+    /// Reactions come from a conversations JSON export; archived weeks
+    /// (`archive:`) only keep replies.
+    Digest {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Users JSON or parquet file path
+        #[arg(short, long, default_value = "users.json")]
+        users: String,
+
+        /// Channels JSON or parquet file path
+        #[arg(long, default_value = "channels.json")]
+        channels: String,
+
+        /// ISO year of --week (defaults to the week's year)
+        #[arg(short, long, requires = "week")]
+        year: Option<i32>,
+
+        /// Only messages of this ISO week: 1-53, current or last (all of
+        /// the file by default)
+        #[arg(short, long)]
+        week: Option<String>,
+
+        /// Most-reacted and most-replied messages picked per channel
+        #[arg(long, default_value_t = 3)]
+        top: usize,
+
+        /// Output markdown file
+        #[arg(short, long, default_value = "digest.md")]
         output: String,
+
+        /// Also post the digest to this channel ID (the token needs chat:write)
+        #[arg(long)]
+        post: Option<String>,
+    },
+
+    /// Encrypt the secrets of settings.toml (the Meilisearch API key) with
+    /// the passphrase in SLACK_SETTINGS_PASSPHRASE
+    ///
+    /// Once set, the TUI and the CLI decrypt them when loading the settings
+    /// and encrypt them again when saving.
+    EncryptSettings {
+        /// Write the secrets back in plain text instead
+        #[arg(long)]
+        decrypt: bool,
     },
 
     /// Export users
     ExportUsers {
-        /// Output path (without extension)
+        /// Output path (without extension), or an s3://bucket/prefix URL
         #[arg(short, long, default_value = "users")]
         output: String,
 
@@ -90,7 +386,7 @@ pub enum Commands {
 
     /// Export channels
     ExportChannels {
-        /// Output path (without extension)
+        /// Output path (without extension), or an s3://bucket/prefix URL
         #[arg(short, long, default_value = "channels")]
         output: String,
 
@@ -108,23 +404,39 @@ pub enum Commands {
         /// Output directory path
         #[arg(short, long, default_value = "attachments")]
         output: String,
+
+        #[command(flatten)]
+        download: DownloadArgs,
+
+        #[command(flatten)]
+        filter: AttachmentFilterArgs,
+
+        /// Also write a copy of the conversations file to this path with
+        /// attachment URLs pointing at the downloaded files
+        #[arg(long)]
+        rewrite_conversations: Option<String>,
+
+        /// List the files that would be downloaded with their sizes, without
+        /// downloading or writing anything
+        #[arg(long)]
+        dry_run: bool,
     },
 
     /// Export selected conversations to markdown
     ExportMarkdown {
-        /// Input selected conversations file path
+        /// Input selected conversations file path (`-` for stdin, `archive:[DIR/]YYYY/Www` for an archived week)
         #[arg(short, long, default_value = "selected-conversations.json")]
         conversations: String,
 
-        /// Users JSON file path
+        /// Users JSON or parquet file path
         #[arg(short, long, default_value = "users.json")]
         users: String,
 
-        /// Channels JSON file path
+        /// Channels JSON or parquet file path
         #[arg(long, default_value = "channels.json")]
         channels: String,
 
-        /// Output markdown file path
+        /// Output markdown file path (`-` for stdout)
         #[arg(short, long, default_value = "selected-conversations.md")]
         output: String,
 
@@ -148,23 +460,291 @@ pub enum Commands {
         folder: String,
     },
 
+    /// Package exported emojis with a manifest for bulk upload to another workspace
+    ExportEmojiPack {
+        /// Emoji JSON file written by export-emojis
+        #[arg(short, long, default_value = "emojis.json")]
+        emojis: String,
+
+        /// Folder with the images downloaded by export-emojis
+        #[arg(short, long, default_value = "emojis")]
+        folder: String,
+
+        /// Output folder for the images and emojipack.yaml / emojipack.json
+        #[arg(short, long, default_value = "emoji-pack")]
+        output: String,
+
+        /// Pack title written in the manifest
+        #[arg(long, default_value = "slack-emojis")]
+        title: String,
+    },
+
     /// Export conversations to a searchable index
     ExportIndex {
-        /// Input conversations file path
+        /// Input conversations file path (`-` for stdin, `archive:[DIR/]YYYY/Www` for an archived week)
         #[arg(short, long, default_value = "conversations.json")]
         conversations: String,
 
-        /// Users JSON file path
+        /// Users JSON or parquet file path
         #[arg(short, long, default_value = "users.json")]
         users: String,
 
-        /// Channels JSON file path
+        /// Channels JSON or parquet file path
         #[arg(long, default_value = "channels.json")]
         channels: String,
 
-        /// Output index JSON file path
+        /// Output index file path (.json is replaced by .sqlite for sqlite-fts, `-` for stdout)
         #[arg(short, long, default_value = "conversation-index.json")]
         output: String,
+
+        /// Output format: json or sqlite-fts (SQLite file with an FTS5 table)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+
+    /// Message counts per channel, user, weekday and hour, thread participation
+    /// and attachments from exported conversations
+    Stats {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Users JSON or parquet file for display names (user IDs are shown without it)
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Output path without extension (csv writes <output>-channels.csv,
+        /// <output>-users.csv and <output>-activity.csv)
+        #[arg(short, long, default_value = "stats")]
+        output: String,
+
+        /// Output format: json or csv
+        #[arg(short, long, default_value = "json")]
+        format: String,
+
+        /// Also write a markdown report to this file
+        #[arg(long)]
+        report: Option<String>,
+
+        /// Channels and users listed in the markdown report
+        #[arg(long, default_value_t = 20)]
+        top: usize,
+    },
+
+    /// Weekday × hour heatmap and weekly message series per channel, as JSON
+    /// for plotting
+    Activity {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Output JSON file path
+        #[arg(short, long, default_value = "activity.json")]
+        output: String,
+
+        /// Also draw the heatmap as SVG to this file
+        #[arg(long)]
+        svg: Option<String>,
+    },
+
+    /// Ranked report of the links shared in messages, by domain and by URL
+    Links {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Users JSON or parquet file for display names (user IDs are shown without it)
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Output JSON file path
+        #[arg(short, long, default_value = "links.json")]
+        output: String,
+
+        /// Also write a markdown report to this file
+        #[arg(long)]
+        report: Option<String>,
+
+        /// Domains and links listed in the markdown report
+        #[arg(long, default_value_t = 50)]
+        top: usize,
+    },
+
+    /// Messages added, removed and edited, membership changes and renamed
+    /// channels between two conversation exports
+    Diff {
+        /// Older conversations export
+        #[arg(long)]
+        old: String,
+
+        /// Newer conversations export
+        #[arg(long)]
+        new: String,
+
+        /// Users JSON or parquet file for display names in the report
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Output JSON file path
+        #[arg(short, long, default_value = "diff.json")]
+        output: String,
+
+        /// Also write a markdown change report to this file
+        #[arg(long)]
+        report: Option<String>,
+    },
+
+    /// Merge overlapping conversation exports, deduplicated by channel and ts
+    ///
+    /// The most recently edited version of a message wins (the later input
+    /// on ties) and thread replies from every input are combined.
+    Merge {
+        /// Conversations JSON files (or `archive:[DIR/]YYYY/Www` weeks) to merge, oldest first
+        #[arg(short, long, num_args = 1.., required = true)]
+        inputs: Vec<String>,
+
+        /// Output JSON file path
+        #[arg(short, long, default_value = "merged.json")]
+        output: String,
+    },
+
+    /// Convert a conversations export into another platform's import format
+    ///
+    /// Writes one file per channel: matrix-archive style room JSON for Matrix,
+    /// or DiscordChatExporter JSON for Discord migration tools. For Mattermost
+    /// writes a single bulk-import JSONL with placeholder accounts for users.
+    /// Thread replies become Matrix threads, Discord replies to their parent
+    /// or Mattermost replies to a root post.
+    ConvertTo {
+        /// Input conversations file path (`-` for stdin, `archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Target platform: matrix, discord or mattermost
+        #[arg(short, long)]
+        target: String,
+
+        /// Users JSON or parquet file, for author and mention names and Mattermost
+        /// accounts
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Channels JSON or parquet file, for Mattermost channel headers and privacy
+        #[arg(long)]
+        channels: Option<String>,
+
+        /// Matrix homeserver name used in room and user IDs, and the domain
+        /// of Mattermost placeholder emails for users without one
+        #[arg(long, default_value = "example.org")]
+        server_name: String,
+
+        /// Mattermost team to import into
+        #[arg(long, default_value = "slack")]
+        team: String,
+
+        /// Output directory for the converted channels
+        #[arg(short, long, default_value = "converted")]
+        output: String,
+    },
+
+    /// Export a plain-text corpus for embedding or fine-tuning pipelines
+    ///
+    /// One document per thread, or per N messages, with the channel,
+    /// participants and dates as metadata.
+    ExportCorpus {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Users JSON or parquet file, for author and mention names
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Messages per document instead of one document per thread
+        #[arg(long)]
+        messages_per_chunk: Option<usize>,
+
+        /// Start a new document before one gets longer than this many
+        /// characters
+        #[arg(long)]
+        max_chars: Option<usize>,
+
+        /// Replace user names with user1, user2, ... in authors and mentions
+        #[arg(long)]
+        anonymize: bool,
+
+        /// Output format: jsonl (one file) or text (a directory of .txt files)
+        #[arg(short, long, default_value = "jsonl")]
+        format: String,
+
+        /// Output file (jsonl) or directory (text)
+        #[arg(short, long, default_value = "corpus.jsonl")]
+        output: String,
+    },
+
+    /// Split a conversations export into one file per channel or per month
+    Split {
+        /// Input conversations file path (`archive:[DIR/]YYYY/Www` for an archived week)
+        #[arg(short, long, default_value = "conversations.json")]
+        conversations: String,
+
+        /// Group by channel (<channel>.json) or month (YYYY-MM.json, replies
+        /// stay with their thread)
+        #[arg(long, default_value = "channel")]
+        by: String,
+
+        /// Output directory for the parts
+        #[arg(short, long, default_value = "conversations-split")]
+        output: String,
+    },
+
+    /// Check exported files against the expected schema: missing fields,
+    /// duplicate ts, broken thread references and unreadable parquet files
+    ///
+    /// Exits non-zero when any issue is found. Without options, checks the
+    /// conversations.json, users.json and channels.json that exist here.
+    Validate {
+        /// Conversations JSON file to check
+        #[arg(short, long)]
+        conversations: Option<String>,
+
+        /// Users JSON file to check
+        #[arg(short, long)]
+        users: Option<String>,
+
+        /// Channels JSON file to check
+        #[arg(long)]
+        channels: Option<String>,
+
+        /// Parquet archive folder to check (every .parquet file under it)
+        #[arg(short, long)]
+        archive: Option<String>,
+
+        /// Also write the issues as JSON to this file
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+
+    /// Run SQL over the parquet archive with DataFusion
+    ///
+    /// Tables: messages (conversations/year=*/week=*, with year and week
+    /// columns), users and channels, for the files that exist.
+    #[cfg(feature = "datafusion")]
+    Sql {
+        /// The SQL query, e.g. "SELECT channel_name, COUNT(*) FROM messages GROUP BY channel_name"
+        query: String,
+
+        /// Archive folder written by archive-range
+        #[arg(short, long, default_value = ".")]
+        archive: String,
+
+        /// Output format: table, csv or json
+        #[arg(short, long, default_value = "table")]
+        format: String,
+
+        /// Write the results to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
     },
 
     /// Import index to Meilisearch
@@ -192,7 +772,8 @@ pub enum Commands {
 
     /// Query Meilisearch index
     QueryMeilisearch {
-        /// Search query
+        /// Search query; supports from:@user, in:#channel, before:/after:/on:YYYY-MM-DD,
+        /// has:attachment and "exact phrase" (explicit flags take precedence)
         query: String,
 
         /// Meilisearch server URL
@@ -210,17 +791,154 @@ pub enum Commands {
         /// Maximum number of results
         #[arg(short, long, default_value = "20")]
         limit: usize,
+
+        /// Only threads in this channel (name or ID)
+        #[arg(long)]
+        channel: Option<String>,
+
+        /// Only threads involving this user (name or ID)
+        #[arg(long)]
+        user: Option<String>,
+
+        /// Only threads on or after this date (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+
+        /// Only threads on or before this date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+
+        /// Only threads with file attachments
+        #[arg(long)]
+        has_attachments: bool,
     },
 
-    /// Convert Markdown file to HTML
-    MdToHtml {
-        /// Input markdown file path
+    /// Build a local Tantivy search index from an index JSON file
+    IndexTantivy {
+        /// Input index JSON file path
+        #[arg(short, long, default_value = "conversation-index.json")]
         input: String,
 
-        /// Output HTML file path (defaults to input with .html extension)
+        /// Output directory for the Tantivy index
+        #[arg(short, long, default_value = "tantivy-index")]
+        output: String,
+    },
+
+    /// Query a local Tantivy search index
+    QueryTantivy {
+        /// Search query (supports channel:NAME, users:NAME and "exact phrase")
+        query: String,
+
+        /// Tantivy index directory
+        #[arg(short, long, default_value = "tantivy-index")]
+        index: String,
+
+        /// Maximum number of results
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+
+    /// Summarize each channel's week of an archive with an external script
+    /// or HTTP endpoint
+    ///
+    /// The rendered markdown of every channel/week goes to the summarizer and
+    /// the summaries are stored in the week's summaries.json. Channels already
+    /// summarized are skipped unless --force is given.
+    Summarize {
+        /// Archive directory of year=*/week=* partitions
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// Users JSON or parquet file path
+        #[arg(short, long, default_value = "users.json")]
+        users: String,
+
+        /// Channels JSON or parquet file path
+        #[arg(short, long, default_value = "channels.json")]
+        channels: String,
+
+        /// Only weeks of this ISO year
         #[arg(short, long)]
+        year: Option<i32>,
+
+        /// Only this ISO week number 1-53
+        #[arg(short, long, requires = "year")]
+        week: Option<i32>,
+
+        #[command(flatten)]
+        summarizer: SummarizerArgs,
+    },
+
+    /// Build a self-contained static site from an archive
+    ///
+    /// Renders every archived week to markdown and HTML, writes an index page
+    /// and a client-side search page over all messages, and copies the
+    /// downloaded attachments and emojis, ready to publish as is.
+    BuildSite {
+        /// Archive directory of year=*/week=* partitions
+        #[arg(short, long, default_value = "conversations")]
+        archive: String,
+
+        /// Users JSON or parquet file path
+        #[arg(short, long, default_value = "users.json")]
+        users: String,
+
+        /// Channels JSON or parquet file path
+        #[arg(short, long, default_value = "channels.json")]
+        channels: String,
+
+        /// Folder written by download-attachments, copied into the site
+        #[arg(long)]
+        attachments: Option<String>,
+
+        /// Folder written by export-emojis, copied into the site
+        #[arg(long)]
+        emojis: Option<String>,
+
+        /// Heading of the index page
+        #[arg(long, default_value = "Slack archive")]
+        title: String,
+
+        /// Output directory for the site
+        #[arg(short, long, default_value = "site")]
+        output: String,
+    },
+
+    /// Convert Markdown file (or a directory of Markdown files) to HTML
+    MdToHtml {
+        /// Input markdown file path (`-` for stdin)
+        #[arg(required_unless_present = "input_dir", conflicts_with = "input_dir")]
+        input: Option<String>,
+
+        /// Output HTML file path (defaults to input with .html extension, or stdout
+        /// for stdin input; `-` for stdout)
+        #[arg(short, long, conflicts_with = "input_dir")]
         output: Option<String>,
 
+        /// Input directory to convert recursively (all .md files)
+        #[arg(long, requires = "output_dir")]
+        input_dir: Option<String>,
+
+        /// Output directory for batch mode (relative structure is preserved)
+        #[arg(long, requires = "input_dir")]
+        output_dir: Option<String>,
+
+        /// HTML template wrapping each page ({{title}}, {{css}}, {{nav}}, {{content}} placeholders)
+        #[arg(long)]
+        template: Option<String>,
+
+        /// Stylesheet for the full page: a URL is linked, a local file is inlined
+        #[arg(long)]
+        css: Option<String>,
+
+        /// Syntax-highlight code blocks server-side
+        #[arg(long)]
+        highlight: bool,
+
+        /// Theme used by --highlight (InspiredGitHub, base16-ocean.dark, Solarized (light), ...)
+        #[arg(long, default_value = crate::md_to_html::DEFAULT_HIGHLIGHT_THEME)]
+        highlight_theme: String,
+
         /// Use GFM (GitHub Flavored Markdown) preset
         #[arg(long)]
         gfm: bool,
@@ -322,3 +1040,91 @@ pub enum Commands {
         math_text_single_dollar: bool,
     },
 }
+
+/// How `download-attachments` fetches and stores files
+#[derive(Args)]
+pub struct DownloadArgs {
+    /// Number of files downloaded at the same time
+    #[arg(long, default_value_t = 4)]
+    pub concurrency: usize,
+
+    /// Minimum milliseconds between requests to the same host
+    #[arg(long, default_value_t = 100)]
+    pub host_interval_ms: u64,
+
+    /// Extra attempts for files that fail with network errors, 5xx or 429
+    #[arg(long, default_value_t = 3)]
+    pub retries: u32,
+
+    /// Files with the same content as one already downloaded: keep, hardlink
+    /// (replace with a hard link to the first copy) or remove
+    #[arg(long, default_value = "keep")]
+    pub dedup: String,
+
+    /// Most to download in this run, by the sizes in the file metadata
+    /// (e.g. 500MB, 2GB)
+    #[arg(long)]
+    pub max_total_size: Option<String>,
+
+    /// When the files add up to more than --max-total-size: stop (download in
+    /// order until the budget is used) or skip-largest
+    #[arg(long, default_value = "stop")]
+    pub over_budget: String,
+}
+
+/// How `summarize` gets and stores summaries
+#[derive(Args)]
+pub struct SummarizerArgs {
+    /// Executable getting the markdown on stdin (channel and week in
+    /// SUMMARY_CHANNEL_ID, SUMMARY_CHANNEL_NAME and SUMMARY_WEEK) and printing
+    /// the summary
+    #[arg(long, required_unless_present = "endpoint", conflicts_with = "endpoint")]
+    pub script: Option<String>,
+
+    /// URL getting {channel_id, channel_name, week, markdown} POSTed as JSON
+    /// and answering {"summary": "..."}
+    #[arg(long)]
+    pub endpoint: Option<String>,
+
+    /// Directory of YYYY-Www.md files (e.g. site/markdown/weeks) to put each
+    /// week's summaries at the top of
+    #[arg(long)]
+    pub markdown_dir: Option<String>,
+
+    /// Summarize channels that already have a summary again
+    #[arg(long)]
+    pub force: bool,
+}
+
+/// Joining channels before a conversations export
+#[derive(Args)]
+pub struct JoinArgs {
+    /// Join the public channels the token is not a member of before fetching
+    /// them; bot tokens can only read the history of channels they are in
+    #[arg(long)]
+    pub auto_join: bool,
+
+    /// Leave the channels --auto-join joined once the export ends
+    #[arg(long, requires = "auto_join")]
+    pub leave_joined: bool,
+}
+
+/// Which attachments `download-attachments` fetches
+#[derive(Args)]
+pub struct AttachmentFilterArgs {
+    /// Only these comma separated file types (e.g. png,jpg,pdf)
+    #[arg(long)]
+    pub types: Option<String>,
+
+    /// Skip files larger than this (e.g. 500KB, 25MB, 1.5GB)
+    #[arg(long)]
+    pub max_size: Option<String>,
+
+    /// Only files shared on or after this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub from: Option<String>,
+
+    /// Only files shared on or before this date (YYYY-MM-DD)
+    #[arg(long)]
+    pub to: Option<String>,
+}
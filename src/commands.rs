@@ -1,17 +1,49 @@
+use std::io::IsTerminal;
 use std::path::Path;
-
+use std::time::Duration;
+
+use crate::archive_input::{load_channels, load_conversations, load_users};
+use crate::attachments;
+use crate::backfill::{backfill_threads, missing_threads};
+use crate::compact::{compact_archive, CompactBy};
+use crate::convert::{convert_conversations, write_converted, ConvertOptions, ConvertTarget};
+use crate::corpus::{build_corpus, write_corpus, CorpusFormat, CorpusOptions};
+use crate::daemon::{run_archive_daemon as archive_daemon, run_archive_once, DaemonConfig};
+use crate::date_spec::{resolve_date_range, resolve_week};
+use crate::diff::{compute_diff, diff_markdown_report, write_diff};
 use crate::error::Result;
+use crate::export_report::{report_path, ExportReport};
+use crate::extract::extract_week;
 use crate::formatter::MarkdownExportOptions;
-use crate::index::export_conversations_to_index;
+use crate::index::{export_conversations_to_index, IndexFormat};
 use crate::markdown::export_conversations_to_markdown_with_options;
-use crate::meilisearch::{import_index_to_meilisearch, query_meilisearch};
+use crate::index::{split_highlights, SearchHit};
+use crate::merge::merge_conversations;
+use crate::delta::{DeltaTable, TableFormat};
+use crate::parquet::PartitionBy;
+use crate::provenance::archive_info;
+use crate::meilisearch::{import_index_to_meilisearch, query_meilisearch, SearchFilters};
+use crate::post::{post_message, PostContent};
 use crate::settings::Settings;
+use crate::site::{build_site, SiteOptions};
+use crate::summarize::{summarize_archive, SummarizeOptions, Summarizer};
+use crate::summary::Reporter;
+use crate::tantivy_search::{build_tantivy_index, query_tantivy};
+use crate::validate::{validate_json_file, validate_parquet_tree, JsonKind, ValidationReport};
 use crate::slack;
+use crate::links::{compute_links, links_markdown_report, write_links};
+use crate::live_archive::{load_channel_names, run_live_archive as live_archive, LiveArchiveOptions};
+use crate::split::{split_conversations, SplitBy};
+use crate::stats::{compute_activity, load_stats, load_user_names, stats_markdown_report, write_activity, write_stats, StatsFormat};
+use crate::stdio::{is_stdio, InputPath, OutputPath, STDIO_PATH};
+use crate::storage::open_storage;
+use crate::sync::{apply_sync, plan_sync, SyncSources};
 use chrono::Local;
 
 use crate::{
-    cli_callbacks, cli_progress, current_iso_week, default_from_date, default_to_date,
-    load_token, parse_date, week_to_date_range, OutputFormat,
+    CliProgress, current_iso_week,
+    default_from_date, default_to_date, load_app_token, load_token, week_to_date_range,
+    AttachmentFilterArgs, DownloadArgs, JoinArgs, OutputFormat, SlackExporter,
 };
 
 /// Derive output path based on format
@@ -22,10 +54,10 @@ fn derive_output_path(base: &str, format: OutputFormat) -> String {
     }
 }
 
-pub fn run_work_week() {
+pub fn run_work_week(reporter: &mut Reporter) {
     let (year, week) = current_iso_week();
     let today = Local::now().date_naive();
-    println!("{today}  W{week:02} ({year})");
+    reporter.say(format_args!("{today}  W{week:02} ({year})"));
 }
 
 pub async fn run_export_conversations(
@@ -33,18 +65,20 @@ pub async fn run_export_conversations(
     to: Option<String>,
     output: &str,
     format_str: &str,
+    dry_run: bool,
+    join: &JoinArgs,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    let token = load_token()?;
     let format: OutputFormat = format_str.parse()?;
-
-    let from_date = match from {
-        Some(s) => parse_date(&s)?,
-        None => default_from_date(),
-    };
-    let to_date = match to {
-        Some(s) => parse_date(&s)?,
-        None => default_to_date(),
-    };
+    let from = from.unwrap_or_else(|| default_from_date().format("%Y-%m-%d").to_string());
+    let (from_date, to_date) = resolve_date_range(&from, to.as_deref(), default_to_date())?;
+    let exporter = SlackExporter::builder()
+        .token(load_token()?)
+        .date_range(from_date, to_date)
+        .format(format)
+        .auto_join(join.auto_join)
+        .leave_joined(join.leave_joined)
+        .build()?;
 
     // For parquet, output is a directory; for json, output is a file
     let output_path = match format {
@@ -52,45 +86,129 @@ pub async fn run_export_conversations(
         OutputFormat::Parquet => output.to_string(), // Keep as directory path
     };
 
-    println!(
+    reporter.say(format_args!(
         "Exporting conversations from {} to {} to {} (format: {})...",
         from_date, to_date, output_path, format
-    );
+    ));
+
+    if dry_run {
+        let plan = exporter.plan_conversations().await?;
+        reporter.record_count("channels", plan.channels.len());
+        reporter.record_count("min_requests", plan.min_requests());
+        let to_join = plan.to_join().len();
+        reporter.record_count("to_join", to_join);
+        for channel in &plan.channels {
+            let archived = if channel.is_archived { ", archived" } else { "" };
+            let member = if channel.is_member { "" } else { ", not a member" };
+            match channel.num_members {
+                Some(members) => {
+                    reporter.say(format_args!("  #{} ({} members{}{})", channel.name, members, archived, member))
+                }
+                None => reporter.say(format_args!("  #{}{}{}", channel.name, archived, member)),
+            }
+        }
+        reporter.say(format_args!(
+            "Dry run: {} channels to fetch ({} created after {} left out), at least {} history requests plus one per further page and thread.",
+            plan.channels.len(),
+            plan.created_later,
+            to_date,
+            plan.min_requests()
+        ));
+        if join.auto_join {
+            reporter.say(format_args!("{} channels would be joined first.", to_join));
+        } else if to_join > 0 {
+            reporter.say(format_args!(
+                "{} channels are not joined, a bot token cannot read them without --auto-join.",
+                to_join
+            ));
+        }
+        return Ok(());
+    }
 
-    let count = slack::export_conversations(
-        &token,
-        from_date,
-        to_date,
-        Path::new(&output_path),
-        None,
-        cli_callbacks(),
-        format,
-    )
-    .await?;
+    let count = export_conversations_with_summary(&exporter, &output_path, reporter).await?;
 
-    println!(
+    reporter.say(format_args!(
         "Export completed successfully! {} messages exported.",
         count
-    );
+    ));
     Ok(())
 }
 
+/// Export the conversations to `output_path`, recording the output, the
+/// message count and the channels its report marks failed, which are also
+/// listed on stderr
+async fn export_conversations_with_summary(
+    exporter: &SlackExporter,
+    output_path: &str,
+    reporter: &mut Reporter,
+) -> Result<usize> {
+    let (storage, key) = open_storage(output_path)?;
+    let progress = CliProgress::start(reporter);
+    let count = exporter
+        .export_conversations_to(storage.as_ref(), &key, progress.callbacks())
+        .await;
+    progress.finish();
+
+    reporter.record_output(output_path);
+    let report_key = report_path(Path::new(&key)).to_string_lossy().to_string();
+    let report = storage.get(&report_key).ok().flatten();
+    if let Some(report) = report.and_then(|data| serde_json::from_slice::<ExportReport>(&data).ok()) {
+        reporter.record_count("channels", report.channels.len());
+        reporter.record_failed_channels(report.failed());
+        for channel in report.failed() {
+            eprintln!("  #{}: {}", channel.channel_name, channel.error.as_deref().unwrap_or_default());
+        }
+    }
+    let count = count?;
+    reporter.record_count("messages", count);
+    Ok(count)
+}
+
+pub async fn run_repair_conversations(report: &str, reporter: &mut Reporter) -> Result<()> {
+    let token = load_token()?;
+    reporter.say(format_args!("Re-fetching the failed channels in {}...", report));
+
+    let progress = CliProgress::start(reporter);
+    let updated = slack::repair_conversations_export(&token, Path::new(report), progress.callbacks()).await?;
+    progress.finish();
+    reporter.record_output(&updated.output);
+    reporter.record_count("channels", updated.channels.len());
+    reporter.record_failed_channels(updated.failed());
+    let failed: Vec<&str> = updated.failed().map(|c| c.channel_name.as_str()).collect();
+    if failed.is_empty() {
+        reporter.say(format_args!("All {} channels of {} exported.", updated.channels.len(), updated.output));
+        return Ok(());
+    }
+    reporter.say(format_args!("Still failing: #{}", failed.join(", #")));
+    Err(crate::AppError::ExportIncomplete {
+        failed: failed.len(),
+        total: updated.channels.len(),
+        report: report.to_string(),
+    })
+}
+
 pub async fn run_export_conversations_week(
     year: Option<i32>,
-    week: Option<u32>,
+    week: Option<String>,
     output: &str,
     format_str: &str,
+    join: &JoinArgs,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    let token = load_token()?;
     let format: OutputFormat = format_str.parse()?;
 
     // Default to current ISO week
-    let (default_year, default_week) = current_iso_week();
-    let year = year.unwrap_or(default_year);
-    let week = week.unwrap_or(default_week);
+    let (year, week) = resolve_week(year, week.as_deref(), default_to_date())?;
 
     // Convert year/week to date range
     let (from_date, to_date) = week_to_date_range(year, week)?;
+    let exporter = SlackExporter::builder()
+        .token(load_token()?)
+        .date_range(from_date, to_date)
+        .format(format)
+        .auto_join(join.auto_join)
+        .leave_joined(join.leave_joined)
+        .build()?;
 
     // For parquet, output is a directory; for json, output is a file
     let output_path = match format {
@@ -98,36 +216,31 @@ pub async fn run_export_conversations_week(
         OutputFormat::Parquet => output.to_string(),
     };
 
-    println!(
+    reporter.say(format_args!(
         "Exporting conversations for {}-W{:02} ({} to {}) to {} (format: {})...",
         year, week, from_date, to_date, output_path, format
-    );
+    ));
 
-    let count = slack::export_conversations(
-        &token,
-        from_date,
-        to_date,
-        Path::new(&output_path),
-        None,
-        cli_callbacks(),
-        format,
-    )
-    .await?;
+    let count = export_conversations_with_summary(&exporter, &output_path, reporter).await?;
 
-    println!(
+    reporter.say(format_args!(
         "Export completed successfully! {} messages exported.",
         count
-    );
+    ));
     Ok(())
 }
 
 pub async fn run_archive_range(
-    from_year: i32,
-    from_week: u32,
-    to_year: Option<i32>,
-    to_week: Option<u32>,
+    (from_year, from_week): (i32, u32),
+    (to_year, to_week): (Option<i32>, Option<u32>),
     output: &str,
+    dry_run: bool,
+    partition_by: Option<&str>,
+    table_format: Option<&str>,
+    reporter: &mut Reporter,
 ) -> Result<()> {
+    let partition_by: Option<PartitionBy> = partition_by.map(str::parse).transpose()?;
+    let table_format: Option<TableFormat> = table_format.map(str::parse).transpose()?;
     let token = load_token()?;
 
     // Default to current ISO week if from_year/from_week are 0
@@ -139,76 +252,570 @@ pub async fn run_archive_range(
     let to_year = to_year.unwrap_or(from_year);
     let to_week = to_week.unwrap_or(from_week);
 
-    println!(
+    reporter.say(format_args!(
         "Archiving conversations from {}-W{:02} to {}-W{:02} to {}...",
         from_year, from_week, to_year, to_week, output
-    );
+    ));
+
+    let (storage, prefix) = open_storage(output)?;
+    let partition_by = PartitionBy::resolve(partition_by, storage.as_ref(), &prefix)?;
+    let table_format = TableFormat::resolve(table_format, storage.as_ref(), &prefix)?;
+    let weeks = slack::generate_weeks_in_range(from_year, from_week, to_year, to_week);
+    if dry_run {
+        let plan = slack::plan_archive_weeks(&token, &weeks, storage.as_ref(), &prefix).await?;
+        let to_fetch = plan.to_fetch().count();
+        reporter.record_count("weeks", to_fetch);
+        reporter.record_count("weeks_skipped", plan.weeks.len() - to_fetch);
+        reporter.record_count("min_requests", plan.min_requests());
+        for week in &plan.weeks {
+            if week.exists {
+                reporter.say(format_args!("  {}-W{:02}: already archived, skipping", week.year, week.week));
+            } else {
+                reporter.say(format_args!("  {}-W{:02}: {} channels", week.year, week.week, week.channels));
+            }
+        }
+        reporter.say(format_args!(
+            "Dry run: {} of {} weeks to fetch across {} channels, partitioned by {} as {}, at least {} requests plus one per further page and thread.",
+            to_fetch,
+            plan.weeks.len(),
+            plan.channels,
+            partition_by,
+            table_format,
+            plan.min_requests()
+        ));
+        return Ok(());
+    }
+    let progress = CliProgress::start(reporter);
+    let result =
+        slack::archive_weeks_to(
+            &token,
+            &weeks,
+            storage.as_ref(),
+            &prefix,
+            true,
+            (Some(partition_by), Some(table_format)),
+            progress.callbacks(),
+        )
+        .await?;
+    progress.finish();
+    reporter.record_output(output);
+    reporter.record_count("messages", result.total_messages);
+    reporter.record_count("weeks", result.weeks_processed);
+    reporter.record_count("weeks_skipped", result.weeks_skipped);
+    reporter.record_count("weeks_incomplete", result.weeks_incomplete);
+    reporter.record_failed_channels(&result.failed_channels);
+
+    reporter.say(format_args!(
+        "Archive completed! {} messages in {} weeks ({} skipped).",
+        result.total_messages, result.weeks_processed, result.weeks_skipped
+    ));
+    for channel in &result.failed_channels {
+        eprintln!("  #{}: {}", channel.channel_name, channel.error.as_deref().unwrap_or_default());
+    }
+    result.check_complete()
+}
 
-    let result = slack::archive_range(
-        &token,
-        from_year,
-        from_week,
-        to_year,
-        to_week,
-        Path::new(output),
-        cli_callbacks(),
-    )
+pub async fn run_archive_daemon(config_path: &str, once: bool, reporter: &mut Reporter) -> Result<()> {
+    let config = DaemonConfig::from_file(Path::new(config_path))?;
+    let token = load_token()?;
+    reporter.record_output(&config.output);
+    let log = |line: &str| reporter.say(format_args!("{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), line));
+
+    if once {
+        return run_archive_once(&token, &config, &log).await?.check_complete();
+    }
+    reporter.say(format_args!(
+        "Archiving the last {} week(s) to {} on schedule \"{}\". Press Ctrl+C to stop.",
+        config.weeks, config.output, config.schedule
+    ));
+    archive_daemon(&token, &config, &log).await
+}
+
+pub async fn run_live_archive(
+    output: &str,
+    staging: &str,
+    channels: Option<&str>,
+    compact_every: u64,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let app_token = load_app_token()?;
+    let options = LiveArchiveOptions {
+        staging_dir: Path::new(staging).to_path_buf(),
+        conversations_dir: Path::new(output).to_path_buf(),
+        channel_names: load_channel_names(channels)?,
+        compact_every: Duration::from_secs(compact_every),
+    };
+
+    reporter.say(format_args!(
+        "Live archiving to {} (staging in {}, compacting every {}s). Press Ctrl+C to stop.",
+        output, staging, compact_every
+    ));
+    let total = live_archive(&app_token, &options, &|result| {
+        reporter.say(format_args!(
+            "{} Compacted {} weeks: {} posted, {} edited, {} deleted",
+            Local::now().format("%H:%M:%S"),
+            result.weeks,
+            result.posted,
+            result.edited,
+            result.deleted
+        ));
+    })
     .await?;
 
-    println!(
-        "Archive completed! {} messages in {} weeks ({} skipped).",
-        result.total_messages, result.weeks_processed, result.weeks_skipped
-    );
+    reporter.record_output(output);
+    reporter.record_count("posted", total.posted);
+    reporter.record_count("edited", total.edited);
+    reporter.record_count("deleted", total.deleted);
+    reporter.say(format_args!(
+        "Live archive stopped. {} posted, {} edited, {} deleted in total.",
+        total.posted, total.edited, total.deleted
+    ));
+    Ok(())
+}
+
+pub async fn run_backfill_threads(conversations: &str, output: Option<&str>, reporter: &mut Reporter) -> Result<()> {
+    let mut loaded: Vec<serde_json::Value> = crate::load_json_file(conversations)?;
+    let missing = missing_threads(&loaded).len();
+    if missing == 0 {
+        reporter.say(format_args!("No threads with missing replies in {}.", conversations));
+        return Ok(());
+    }
+
+    let token = load_token()?;
+    reporter.say(format_args!("Fetching replies for {} threads in {}...", missing, conversations));
+    let progress = CliProgress::start(reporter);
+    let result = backfill_threads(&token, &mut loaded, progress.callbacks()).await?;
+    progress.finish();
+
+    // Write next to the output and rename, so an interrupted write can't
+    // truncate the export being patched
+    let output = output.unwrap_or(conversations);
+    let partial = format!("{}.partial", output);
+    slack::write_json(Path::new(&partial), &loaded)?;
+    std::fs::rename(&partial, output).map_err(|e| crate::AppError::WriteFile {
+        path: output.to_string(),
+        source: e,
+    })?;
+    reporter.record_output(output);
+    reporter.record_count("threads", result.threads);
+    reporter.record_count("missing", result.missing);
+    reporter.record_count("replies", result.replies);
+    reporter.record_count("skipped", result.skipped);
+
+    reporter.say(format_args!(
+        "Patched {} of {} threads with {} replies into {} ({} skipped).",
+        result.threads, result.missing, result.replies, output, result.skipped
+    ));
+    match result.stopped {
+        Some(reason) => Err(crate::AppError::SlackApi(format!(
+            "stopped early, run again to fetch the rest: {}",
+            reason
+        ))),
+        None => Ok(()),
+    }
+}
+
+pub fn run_archive_extract(
+    archive: &str,
+    year: Option<i32>,
+    week: Option<u32>,
+    output: &str,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let (default_year, default_week) = current_iso_week();
+    let year = year.unwrap_or(default_year);
+    let week = week.unwrap_or(default_week);
+    reporter.say(format_args!("Extracting {}-W{:02} from {}...", year, week, archive));
+
+    let extracted = extract_week(Path::new(archive), year, week)?;
+    slack::write_json(Path::new(output), &extracted.conversations)?;
+    reporter.record_output(output);
+    reporter.record_count("messages", extracted.messages);
+    reporter.record_count("replies", extracted.replies);
+    reporter.record_count("channels", extracted.conversations.len());
+
+    reporter.say(format_args!(
+        "{} messages and {} replies in {} channels written to {}.",
+        extracted.messages,
+        extracted.replies,
+        extracted.conversations.len(),
+        output
+    ));
+    if extracted.earlier_threads > 0 {
+        reporter.say(format_args!(
+            "{} replies to threads started in earlier weeks were left for those weeks.",
+            extracted.earlier_threads
+        ));
+    }
+    Ok(())
+}
+
+pub fn run_archive_compact(
+    archive: &str,
+    by: &str,
+    output: Option<&str>,
+    row_group_size: usize,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let by: CompactBy = by.parse()?;
+    let output = output.unwrap_or(archive);
+    reporter.say(format_args!("Compacting {} by {} into {}...", archive, by, output));
+
+    let progress = reporter.progress();
+    let summary = compact_archive(Path::new(archive), Path::new(output), (by, row_group_size), Some(&progress))?;
+    reporter.record_output(output);
+    reporter.record_count("files_read", summary.files_read);
+    reporter.record_count("files_written", summary.files_written);
+    reporter.record_count("rows", summary.rows);
+
+    reporter.say(format_args!(
+        "{} files ({}) compacted into {} ({}), {} rows.",
+        summary.files_read,
+        crate::format_bytes(summary.bytes_before),
+        summary.files_written,
+        crate::format_bytes(summary.bytes_after),
+        summary.rows
+    ));
+    Ok(())
+}
+
+pub fn run_archive_vacuum(archive: &str, retain_hours: u64, reporter: &mut Reporter) -> Result<()> {
+    let (storage, prefix) = open_storage(archive)?;
+    let table = DeltaTable::new(storage.as_ref(), &prefix);
+    if table.version()?.is_none() {
+        return Err(crate::AppError::MissingInput(format!("delta log in {}", archive)));
+    }
+    let before = i64::try_from(retain_hours)
+        .ok()
+        .and_then(chrono::Duration::try_hours)
+        .and_then(|retain| chrono::Utc::now().checked_sub_signed(retain))
+        .ok_or_else(|| crate::AppError::InvalidConfig(format!("--retain-hours {} is too large", retain_hours)))?;
+    reporter.say(format_args!("Vacuuming {}, keeping files removed in the last {} hours...", archive, retain_hours));
+
+    let deleted = table.vacuum(before)?;
+    reporter.record_output(archive);
+    reporter.record_count("files_deleted", deleted.len());
+    reporter.say(format_args!("Deleted {} files no longer in the table.", deleted.len()));
+    Ok(())
+}
+
+pub fn run_archive_info(archive: &str, list_files: bool, output: Option<&str>, reporter: &mut Reporter) -> Result<()> {
+    let info = archive_info(Path::new(archive))?;
+    if info.files.is_empty() {
+        return Err(crate::AppError::MissingInput(format!("parquet files in {}", archive)));
+    }
+    reporter.record_count("files", info.files.len());
+    reporter.record_count("rows", usize::try_from(info.rows).unwrap_or_default());
+
+    reporter.say(format_args!("{}: {} files, {} rows", archive, info.files.len(), info.rows));
+    if let (Some(from), Some(to)) = (&info.from, &info.to) {
+        reporter.say(format_args!("  Dates:      {} to {}", from, to));
+    }
+    if let (Some(first), Some(last)) = (&info.first_exported_at, &info.last_exported_at) {
+        reporter.say(format_args!("  Exported:   {} to {}", first, last));
+    }
+    if !info.tool_versions.is_empty() {
+        let versions: Vec<&str> = info.tool_versions.iter().map(String::as_str).collect();
+        reporter.say(format_args!("  Written by: slack-utils {}", versions.join(", ")));
+    }
+    if !info.team_ids.is_empty() {
+        let team_ids: Vec<&str> = info.team_ids.iter().map(String::as_str).collect();
+        reporter.say(format_args!("  Workspace:  {}", team_ids.join(", ")));
+    }
+    if info.unknown > 0 {
+        reporter.say(format_args!(
+            "  {} files have no provenance (written by another tool or an older slack-utils)",
+            info.unknown
+        ));
+    }
+    if list_files {
+        for file in &info.files {
+            match &file.provenance {
+                Some(provenance) => reporter.say(format_args!("{} ({} rows): {}", file.path, file.rows, provenance)),
+                None => reporter.say(format_args!("{} ({} rows): no provenance", file.path, file.rows)),
+            }
+        }
+    }
+
+    if let Some(output) = output {
+        let json = serde_json::to_string_pretty(&info).map_err(|e| crate::AppError::JsonSerialize(e.to_string()))?;
+        std::fs::write(output, json).map_err(|e| crate::AppError::WriteFile {
+            path: output.to_string(),
+            source: e,
+        })?;
+        reporter.record_output(output);
+    }
+    Ok(())
+}
+
+pub fn run_archive_sync(
+    archive: &str,
+    users: Option<String>,
+    channels: Option<String>,
+    attachments: Option<String>,
+    dest: &str,
+    dry_run: bool,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let sources = SyncSources {
+        conversations: Some(archive.to_string()),
+        users,
+        channels,
+        attachments,
+    };
+    let (storage, prefix) = open_storage(dest)?;
+
+    reporter.say(format_args!("Comparing {} with {}...", archive, dest));
+    let plan = plan_sync(&sources, storage.as_ref(), &prefix, Some(&reporter.progress()))?;
+    reporter.record_count("unchanged", plan.unchanged);
+    if dry_run {
+        reporter.record_count("to_upload", plan.uploads.len());
+        for upload in &plan.uploads {
+            reporter.say(format_args!("  {} ({})", upload.key, crate::format_bytes(upload.file.size)));
+        }
+        reporter.say(format_args!(
+            "Dry run: {} files to upload ({}), {} unchanged.",
+            plan.uploads.len(),
+            crate::format_bytes(plan.upload_bytes()),
+            plan.unchanged
+        ));
+        return Ok(());
+    }
+
+    let uploaded = apply_sync(&plan, storage.as_ref(), &prefix, Some(&reporter.progress()))?;
+    reporter.record_output(dest);
+    reporter.record_count("uploaded", uploaded);
+    reporter.record_count("bytes", plan.upload_bytes() as usize);
+    reporter.say(format_args!(
+        "Sync completed! {} files uploaded ({}), {} unchanged.",
+        uploaded,
+        crate::format_bytes(plan.upload_bytes()),
+        plan.unchanged
+    ));
     Ok(())
 }
 
-pub async fn run_export_users(output: &str, format_str: &str) -> Result<()> {
+pub async fn run_post_message(
+    channel: &str,
+    text: Option<String>,
+    markdown: Option<&str>,
+    thread_ts: Option<&str>,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let content = match (text, markdown) {
+        (Some(text), _) => PostContent::Text(text),
+        (None, Some(path)) => PostContent::Markdown(std::fs::read_to_string(path).map_err(|e| {
+            crate::AppError::ReadFile {
+                path: path.to_string(),
+                source: e,
+            }
+        })?),
+        (None, None) => return Err(crate::AppError::InvalidConfig("pass --text or --markdown".to_string())),
+    };
     let token = load_token()?;
+
+    let posted = post_message(&token, channel, &content, thread_ts).await?;
+    reporter.record_count("messages", posted.len());
+    match posted.as_slice() {
+        [ts] => reporter.say(format_args!("Posted to {} (ts {}).", channel, ts)),
+        [first, ..] => {
+            reporter.say(format_args!("Posted {} messages to {}, thread ts {}.", posted.len(), channel, first))
+        }
+        [] => {}
+    }
+    Ok(())
+}
+
+pub async fn run_digest(
+    conversations: &str,
+    (users, channels): (&str, &str),
+    (year, week): (Option<i32>, Option<&str>),
+    top: usize,
+    output: &str,
+    post: Option<&str>,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let input = InputPath::new(conversations)?;
+    let out = OutputPath::new(output, reporter)?;
+    let mut loaded: Vec<serde_json::Value> = crate::load_json_file(input.path())?;
+    let title = match week {
+        Some(week) => {
+            let (year, week) = resolve_week(year, Some(week), default_to_date())?;
+            crate::digest::filter_week(&mut loaded, (year, week));
+            format!("Highlights of {}-W{:02}", year, week)
+        }
+        None => "Highlights".to_string(),
+    };
+    reporter.say(format_args!("Selecting the {} most-reacted and most-replied messages per channel...", top));
+
+    let digest = crate::digest::build_digest(&loaded, (users, channels), &title, top)?;
+    std::fs::write(out.path(), &digest.markdown).map_err(|e| crate::AppError::WriteFile {
+        path: output.to_string(),
+        source: e,
+    })?;
+    out.finish()?;
+    reporter.record_output(output);
+    reporter.record_count("channels", digest.channels);
+    reporter.record_count("messages", digest.messages);
+    reporter.say(format_args!("Wrote {} highlights from {} channels to {}.", digest.messages, digest.channels, output));
+
+    if let Some(channel) = post {
+        if digest.messages == 0 {
+            reporter.say(format_args!("Nothing to post to {}.", channel));
+            return Ok(());
+        }
+        let token = load_token()?;
+        let posted = post_message(&token, channel, &PostContent::Markdown(digest.markdown), None).await?;
+        reporter.record_count("posted", posted.len());
+        reporter.say(format_args!("Posted the digest to {} in {} messages.", channel, posted.len()));
+    }
+    Ok(())
+}
+
+pub fn run_encrypt_settings(decrypt: bool, reporter: &mut Reporter) -> Result<()> {
+    let secrets = Settings::convert_secrets(decrypt)?;
+    reporter.record_count("secrets", secrets);
+    let state = if decrypt { "in plain text" } else { "encrypted" };
+    reporter.say(format_args!("settings.toml rewritten with {} secrets {}.", secrets, state));
+    Ok(())
+}
+
+pub async fn run_export_users(output: &str, format_str: &str, reporter: &mut Reporter) -> Result<()> {
     let format: OutputFormat = format_str.parse()?;
+    let exporter = SlackExporter::builder().token(load_token()?).format(format).build()?;
     let output_path = derive_output_path(output, format);
 
-    println!("Exporting users to {} (format: {})...", output_path, format);
+    reporter.say(format_args!("Exporting users to {} (format: {})...", output_path, format));
 
-    let count = slack::export_users(&token, Path::new(&output_path), format).await?;
+    let (storage, key) = open_storage(&output_path)?;
+    let count = exporter.export_users_to(storage.as_ref(), &key).await?;
+    reporter.record_output(&output_path);
+    reporter.record_count("users", count);
 
-    println!("Export completed successfully! {} users exported.", count);
+    reporter.say(format_args!("Export completed successfully! {} users exported.", count));
     Ok(())
 }
 
-pub async fn run_export_channels(output: &str, format_str: &str) -> Result<()> {
-    let token = load_token()?;
+pub async fn run_export_channels(output: &str, format_str: &str, reporter: &mut Reporter) -> Result<()> {
     let format: OutputFormat = format_str.parse()?;
+    let exporter = SlackExporter::builder().token(load_token()?).format(format).build()?;
     let output_path = derive_output_path(output, format);
 
-    println!("Exporting channels to {} (format: {})...", output_path, format);
+    reporter.say(format_args!("Exporting channels to {} (format: {})...", output_path, format));
 
-    let count = slack::export_channels(&token, Path::new(&output_path), format).await?;
+    let (storage, key) = open_storage(&output_path)?;
+    let count = exporter.export_channels_to(storage.as_ref(), &key).await?;
+    reporter.record_output(&output_path);
+    reporter.record_count("channels", count);
 
-    println!(
+    reporter.say(format_args!(
         "Export completed successfully! {} channels exported.",
         count
-    );
+    ));
     Ok(())
 }
 
-pub fn run_download_attachments(input: &str, output: &str) -> Result<()> {
-    let token = load_token()?;
+pub async fn run_download_attachments(
+    input: &str,
+    output: &str,
+    download: &DownloadArgs,
+    filter: &AttachmentFilterArgs,
+    rewrite_conversations: Option<&str>,
+    dry_run: bool,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let filter = slack::AttachmentFilter::parse(
+        filter.types.as_deref().unwrap_or_default(),
+        filter.max_size.as_deref().unwrap_or_default(),
+        filter.from.as_deref().unwrap_or_default(),
+        filter.to.as_deref().unwrap_or_default(),
+    )?;
+    let dedup: attachments::DedupMode = download.dedup.parse()?;
+    let max_total_size = download.max_total_size.as_deref().map(slack::parse_size).transpose()?;
+    let over_budget: attachments::BudgetMode = download.over_budget.parse()?;
+    let options = slack::DownloadOptions {
+        concurrency: download.concurrency,
+        host_interval: Duration::from_millis(download.host_interval_ms),
+        retries: download.retries,
+        filter,
+        dedup,
+        max_total_size,
+        over_budget,
+    };
+
+    if dry_run {
+        reporter.say(format_args!("Listing the attachments in {} to download to {}...", input, output));
+        let selection = slack::select_downloads(input, Path::new(output), None, &options)?;
+        let pending: Vec<&slack::FileInfo> = selection.pending().collect();
+        let unknown_size = pending.iter().filter(|f| f.size.is_none()).count();
+        let downloaded = selection.files.len() - pending.len();
+        reporter.record_count("to_download", pending.len());
+        reporter.record_count("skipped", downloaded);
+        reporter.record_count("over_budget", selection.over_budget);
+        reporter.record_count("bytes", selection.pending_bytes() as usize);
+        for file in &pending {
+            match file.size {
+                Some(size) => reporter.say(format_args!("  {} {} ({})", file.id, file.name, crate::format_bytes(size))),
+                None => reporter.say(format_args!("  {} {} (size unknown)", file.id, file.name)),
+            }
+        }
+        reporter.say(format_args!(
+            "Dry run: {} files to download ({}, {} of unknown size), {} already downloaded, {} left out by --max-total-size.",
+            pending.len(),
+            crate::format_bytes(selection.pending_bytes()),
+            unknown_size,
+            downloaded,
+            selection.over_budget
+        ));
+        return Ok(());
+    }
 
-    println!("Downloading attachments from {} to {}...", input, output);
+    let token = load_token()?;
+    reporter.say(format_args!(
+        "Downloading attachments from {} to {} ({} at a time)...",
+        input, output, download.concurrency
+    ));
 
+    let progress = CliProgress::start(reporter);
     let result = slack::download_attachments(
         &token,
         input,
         Path::new(output),
-        Some(&cli_progress),
-    )?;
+        None,
+        &options,
+        progress.callbacks(),
+    )
+    .await;
+    progress.finish();
+    let result = result?;
+    reporter.record_output(output);
+    reporter.record_count("downloaded", result.downloaded);
+    reporter.record_count("skipped", result.skipped);
+    reporter.record_count("failed", result.failed);
+    reporter.record_count("duplicates", result.duplicates);
+    reporter.record_count("over_budget", result.over_budget);
+
+    reporter.say(format_args!(
+        "Download completed! {} files downloaded, {} skipped, {} failed, {} duplicates.",
+        result.downloaded, result.skipped, result.failed, result.duplicates
+    ));
+    if result.over_budget > 0 {
+        reporter.say(format_args!("{} files left out by --max-total-size.", result.over_budget));
+    }
+    for failure in &result.failures {
+        let failure = format!("{}: {}", failure.item, failure.error);
+        eprintln!("  {}", failure);
+        reporter.record_failure(&failure);
+    }
 
-    println!(
-        "Download completed! {} files downloaded, {} skipped, {} failed.",
-        result.downloaded, result.skipped, result.failed
-    );
-    for error in &result.errors {
-        eprintln!("  {}", error);
+    if let Some(rewritten_path) = rewrite_conversations {
+        let count = attachments::rewrite_conversations(input, Path::new(output), rewritten_path)?;
+        reporter.record_output(rewritten_path);
+        reporter.record_count("rewritten", count);
+        reporter.say(format_args!(
+            "Wrote {} with {} attachment links pointing at {}",
+            rewritten_path, count, output
+        ));
     }
     Ok(())
 }
@@ -220,8 +827,11 @@ pub fn run_export_markdown(
     output: &str,
     formatter_script: Option<&str>,
     backslash_line_breaks: bool,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    println!("Exporting selected conversations to markdown...");
+    let out = OutputPath::new(output, reporter)?;
+    let input = InputPath::new(conversations)?;
+    reporter.say(format_args!("Exporting selected conversations to markdown..."));
 
     // Load settings and merge with CLI args (CLI takes precedence)
     let settings = Settings::load().unwrap_or_default();
@@ -236,10 +846,10 @@ pub fn run_export_markdown(
         backslash_line_breaks || settings.markdown_export.backslash_line_breaks;
 
     if let Some(script) = &effective_script {
-        println!("  Using formatter script: {}", script);
+        reporter.say(format_args!("  Using formatter script: {}", script));
     }
     if effective_backslash_line_breaks {
-        println!("  Using backslash line breaks");
+        reporter.say(format_args!("  Using backslash line breaks"));
     }
 
     let options = MarkdownExportOptions::new()
@@ -247,45 +857,453 @@ pub fn run_export_markdown(
         .with_backslash_line_breaks(effective_backslash_line_breaks);
 
     let (count, stats) = export_conversations_to_markdown_with_options(
-        conversations,
+        input.path(),
         users,
         channels,
-        output,
+        out.path(),
         None,
         &options,
     )?;
+    out.finish()?;
+    reporter.record_output(output);
+    reporter.record_count("messages", count);
 
-    println!(
+    reporter.say(format_args!(
         "Export completed successfully! {} messages exported to {}",
         count, output
-    );
+    ));
 
     if stats.total_calls() > 0 {
-        println!("  {}", stats);
+        reporter.say(format_args!("  {}", stats));
     }
 
     Ok(())
 }
 
-pub async fn run_export_emojis(output: &str, folder: &str) -> Result<()> {
+pub async fn run_export_emojis(output: &str, folder: &str, reporter: &mut Reporter) -> Result<()> {
     let token = load_token()?;
 
-    println!("Exporting custom emojis to {} (images to {})...", output, folder);
+    reporter.say(format_args!("Exporting custom emojis to {} (images to {})...", output, folder));
 
+    let progress = CliProgress::start(reporter);
     let result = slack::fetch_emojis(
         &token,
         Path::new(output),
         Path::new(folder),
-        Some(&cli_progress),
+        None,
+        progress.callbacks(),
     )
-    .await?;
-
-    println!(
+    .await;
+    progress.finish();
+    let result = result?;
+    reporter.record_output(output);
+    reporter.record_output(folder);
+    reporter.record_count("emojis", result.total);
+    reporter.record_count("downloaded", result.downloaded);
+    reporter.record_count("skipped", result.skipped);
+    reporter.record_count("failed", result.failed);
+
+    reporter.say(format_args!(
         "Export completed! {} emojis total ({} downloaded, {} skipped, {} failed).",
         result.total, result.downloaded, result.skipped, result.failed
-    );
-    for error in &result.errors {
-        eprintln!("  {}", error);
+    ));
+    for failure in &result.failures {
+        eprintln!("  {}", failure.error);
+        reporter.record_failure(&failure.error);
+    }
+    Ok(())
+}
+
+pub fn run_export_emoji_pack(
+    emojis: &str,
+    folder: &str,
+    output: &str,
+    title: &str,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    reporter.say(format_args!("Packaging emojis from {} and {} into {}...", emojis, folder, output));
+
+    let result = crate::emojis::export_emoji_pack(emojis, Path::new(folder), Path::new(output), title)?;
+    reporter.record_output(output);
+    reporter.record_count("emojis", result.packed);
+    reporter.record_count("aliases", result.aliases);
+    reporter.record_count("skipped", result.skipped.len());
+
+    reporter.say(format_args!(
+        "Emoji pack written! {} emojis with {} aliases, {} left out.",
+        result.packed,
+        result.aliases,
+        result.skipped.len()
+    ));
+    for (name, problem) in &result.skipped {
+        eprintln!("  {}: {}", name, problem);
+        reporter.record_failure(&format!("{}: {}", name, problem));
+    }
+    Ok(())
+}
+
+pub fn run_stats(
+    conversations: &str,
+    users: Option<&str>,
+    output: &str,
+    format_str: &str,
+    report: Option<&str>,
+    top: usize,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let format: StatsFormat = format_str.parse()?;
+    reporter.say(format_args!("Computing statistics for {}...", conversations));
+
+    let stats = load_stats(conversations, users)?;
+    for path in write_stats(&stats, output, format)? {
+        reporter.say(format_args!("Wrote {}", path));
+        reporter.record_output(&path);
+    }
+    if let Some(report_path) = report {
+        std::fs::write(report_path, stats_markdown_report(&stats, top)).map_err(|e| {
+            crate::AppError::WriteFile {
+                path: report_path.to_string(),
+                source: e,
+            }
+        })?;
+        reporter.say(format_args!("Wrote {}", report_path));
+        reporter.record_output(report_path);
+    }
+    reporter.record_count("messages", stats.messages);
+    reporter.record_count("replies", stats.replies);
+    reporter.record_count("threads", stats.threads);
+    reporter.record_count("attachments", stats.attachments);
+    reporter.record_count("channels", stats.channels.len());
+    reporter.record_count("users", stats.users.len());
+
+    reporter.say(format_args!(
+        "{} messages ({} replies, {} threads, {} attachments) in {} channels by {} users.",
+        stats.messages,
+        stats.replies,
+        stats.threads,
+        stats.attachments,
+        stats.channels.len(),
+        stats.users.len()
+    ));
+    Ok(())
+}
+
+pub fn run_activity(conversations: &str, output: &str, svg: Option<&str>, reporter: &mut Reporter) -> Result<()> {
+    reporter.say(format_args!("Aggregating activity of {}...", conversations));
+
+    let loaded = load_conversations(conversations)?;
+    let report = compute_activity(&loaded);
+    write_activity(&report, output, svg)?;
+    reporter.record_output(output);
+    if let Some(svg_path) = svg {
+        reporter.record_output(svg_path);
+    }
+    reporter.record_count("channels", report.channels.len());
+    reporter.record_count("weeks", report.weeks.len());
+
+    reporter.say(format_args!(
+        "Activity of {} channels over {} weeks written to {}.",
+        report.channels.len(),
+        report.weeks.len(),
+        output
+    ));
+    if let Some(svg_path) = svg {
+        reporter.say(format_args!("Heatmap written to {}.", svg_path));
+    }
+    Ok(())
+}
+
+pub fn run_links(
+    conversations: &str,
+    users: Option<&str>,
+    output: &str,
+    report: Option<&str>,
+    top: usize,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    reporter.say(format_args!("Collecting shared links from {}...", conversations));
+
+    let loaded = load_conversations(conversations)?;
+    let user_names = load_user_names(users)?;
+    let links = compute_links(&loaded, &user_names);
+    write_links(&links, output)?;
+    reporter.record_output(output);
+    reporter.record_count("links", links.links.len());
+    reporter.record_count("domains", links.domains.len());
+    if let Some(report_path) = report {
+        std::fs::write(report_path, links_markdown_report(&links, top)).map_err(|e| {
+            crate::AppError::WriteFile {
+                path: report_path.to_string(),
+                source: e,
+            }
+        })?;
+        reporter.say(format_args!("Wrote {}", report_path));
+        reporter.record_output(report_path);
+    }
+
+    reporter.say(format_args!(
+        "{} distinct links from {} domains written to {}.",
+        links.links.len(),
+        links.domains.len(),
+        output
+    ));
+    Ok(())
+}
+
+pub fn run_diff(
+    old: &str,
+    new: &str,
+    users: Option<&str>,
+    output: &str,
+    report: Option<&str>,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    reporter.say(format_args!("Comparing {} with {}...", old, new));
+
+    let old_conversations = load_conversations(old)?;
+    let new_conversations = load_conversations(new)?;
+    let diff = compute_diff(&old_conversations, &new_conversations);
+    write_diff(&diff, output)?;
+    reporter.record_output(output);
+    if let Some(report_path) = report {
+        let user_names = load_user_names(users)?;
+        std::fs::write(report_path, diff_markdown_report(&diff, &user_names)).map_err(|e| {
+            crate::AppError::WriteFile {
+                path: report_path.to_string(),
+                source: e,
+            }
+        })?;
+        reporter.say(format_args!("Wrote {}", report_path));
+        reporter.record_output(report_path);
+    }
+
+    if diff.is_empty() {
+        reporter.say(format_args!("No changes, written to {}.", output));
+    } else {
+        reporter.say(format_args!("{}, written to {}.", diff.summary(), output));
+    }
+    Ok(())
+}
+
+pub fn run_merge(inputs: &[String], output: &str, reporter: &mut Reporter) -> Result<()> {
+    let mut loaded = Vec::with_capacity(inputs.len());
+    for input in inputs {
+        reporter.say(format_args!("Loading {}...", input));
+        loaded.push(load_conversations(input)?);
+    }
+
+    let (merged, stats) = merge_conversations(&loaded);
+    slack::write_json(Path::new(output), &merged)?;
+    reporter.record_output(output);
+    reporter.record_count("inputs", inputs.len());
+    reporter.record_count("channels", stats.channels);
+    reporter.record_count("messages", stats.messages);
+    reporter.record_count("replies", stats.replies);
+    reporter.record_count("duplicates", stats.duplicates);
+    reporter.record_count("skipped", stats.skipped);
+
+    reporter.say(format_args!(
+        "Merged {} inputs into {}: {} channels, {} messages, {} replies ({} duplicates dropped).",
+        inputs.len(),
+        output,
+        stats.channels,
+        stats.messages,
+        stats.replies,
+        stats.duplicates
+    ));
+    if stats.skipped > 0 {
+        reporter.say(format_args!("Skipped {} messages without a ts.", stats.skipped));
+    }
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run_convert_to(
+    conversations: &str,
+    target: &str,
+    users: Option<&str>,
+    channels: Option<&str>,
+    server_name: &str,
+    team: &str,
+    output: &str,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let target: ConvertTarget = target.parse()?;
+    reporter.say(format_args!("Loading {}...", conversations));
+    let input = InputPath::new(conversations)?;
+    let loaded: Vec<serde_json::Value> = crate::load_json_file(input.path())?;
+    let options = ConvertOptions {
+        user_names: load_user_names(users)?,
+        server_name: server_name.to_string(),
+        users: users.map(load_users).transpose()?.unwrap_or_default(),
+        channels: channels.map(load_channels).transpose()?.unwrap_or_default(),
+        team: team.to_string(),
+    };
+
+    let files = convert_conversations(&loaded, target, &options);
+    let written = write_converted(&files, Path::new(output))?;
+    for (path, file) in written.iter().zip(&files) {
+        reporter.say(format_args!("Wrote {} ({} messages)", path.display(), file.messages));
+        reporter.record_output(&path.display().to_string());
+        reporter.record_count("messages", file.messages);
+    }
+    reporter.record_count("files", written.len());
+
+    reporter.say(format_args!("Converted {} to {:?} format in {}.", conversations, target, output));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run_export_corpus(
+    conversations: &str,
+    users: Option<&str>,
+    messages_per_chunk: Option<usize>,
+    max_chars: Option<usize>,
+    anonymize: bool,
+    format: &str,
+    output: &str,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let format: CorpusFormat = format.parse()?;
+    if messages_per_chunk == Some(0) || max_chars == Some(0) {
+        return Err(crate::AppError::InvalidConfig(
+            "--messages-per-chunk and --max-chars must be greater than 0".to_string(),
+        ));
+    }
+    reporter.say(format_args!("Loading {}...", conversations));
+    let loaded = load_conversations(conversations)?;
+    let options = CorpusOptions {
+        messages_per_chunk,
+        max_chars,
+        user_names: load_user_names(users)?,
+        anonymize,
+    };
+
+    let documents = build_corpus(&loaded, &options);
+    let messages: usize = documents.iter().map(|d| d.messages).sum();
+    write_corpus(&documents, format, Path::new(output))?;
+    reporter.record_output(output);
+    reporter.record_count("documents", documents.len());
+    reporter.record_count("messages", messages);
+
+    reporter.say(format_args!("Wrote {} documents ({} messages) to {}.", documents.len(), messages, output));
+    Ok(())
+}
+
+pub fn run_split(conversations: &str, by: &str, output: &str, reporter: &mut Reporter) -> Result<()> {
+    let by: SplitBy = by.parse()?;
+    reporter.say(format_args!("Splitting {}...", conversations));
+    let input = InputPath::new(conversations)?;
+
+    let written = split_conversations(input.path(), by, Path::new(output), &mut |part| {
+        reporter.say(format_args!("Wrote {} ({} messages)", part.path.display(), part.messages));
+    })?;
+    for part in &written {
+        reporter.record_output(&part.path.display().to_string());
+    }
+    reporter.record_count("files", written.len());
+
+    reporter.say(format_args!("Split {} into {} files in {}.", conversations, written.len(), output));
+    Ok(())
+}
+
+pub fn run_validate(
+    conversations: Option<&str>,
+    users: Option<&str>,
+    channels: Option<&str>,
+    archive: Option<&str>,
+    output: Option<&str>,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let mut files = vec![
+        (conversations, JsonKind::Conversations),
+        (users, JsonKind::Users),
+        (channels, JsonKind::Channels),
+    ];
+    if files.iter().all(|(path, _)| path.is_none()) && archive.is_none() {
+        let defaults = [
+            ("conversations.json", JsonKind::Conversations),
+            ("users.json", JsonKind::Users),
+            (crate::CHANNELS_FILE, JsonKind::Channels),
+        ];
+        files = defaults
+            .into_iter()
+            .filter(|(path, _)| Path::new(path).is_file())
+            .map(|(path, kind)| (Some(path), kind))
+            .collect();
+        if files.is_empty() {
+            return Err(crate::AppError::MissingInput(
+                "conversations.json, users.json or channels.json (or pass --archive)".to_string(),
+            ));
+        }
+    }
+
+    let mut report = ValidationReport::default();
+    for (path, kind) in files {
+        if let Some(path) = path {
+            reporter.say(format_args!("Checking {}...", path));
+            validate_json_file(path, kind, &mut report);
+        }
+    }
+    if let Some(archive) = archive {
+        reporter.say(format_args!("Checking parquet files in {}...", archive));
+        validate_parquet_tree(Path::new(archive), &mut report);
+    }
+
+    if let Some(output) = output {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| crate::AppError::JsonSerialize(e.to_string()))?;
+        std::fs::write(output, json).map_err(|e| crate::AppError::WriteFile {
+            path: output.to_string(),
+            source: e,
+        })?;
+    }
+    for issue in &report.issues {
+        eprintln!("{}", issue);
+        reporter.record_failure(&issue.to_string());
+    }
+    if let Some(output) = output {
+        reporter.record_output(output);
+    }
+    reporter.record_count("files", report.files);
+    reporter.record_count("issues", report.issues.len());
+
+    if report.is_ok() {
+        reporter.say(format_args!("{} files checked, no issues.", report.files));
+        Ok(())
+    } else {
+        reporter.say(format_args!("{} files checked, {} issues.", report.files, report.issues.len()));
+        Err(crate::AppError::ValidationFailed(report.issues.len()))
+    }
+}
+
+#[cfg(feature = "datafusion")]
+pub async fn run_sql(
+    query: &str,
+    archive: &str,
+    format_str: &str,
+    output: Option<&str>,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    use crate::datafusion_query::{execute_sql, format_batches, SqlOutputFormat};
+
+    let sql_error = |e: crate::datafusion_query::SqlError| crate::AppError::SqlQuery(e.to_string());
+    let format: SqlOutputFormat = format_str.parse().map_err(sql_error)?;
+    let batches = execute_sql(Path::new(archive), query).await.map_err(sql_error)?;
+    let formatted = format_batches(&batches, format).map_err(sql_error)?;
+    let rows: usize = batches.iter().map(|b| b.num_rows()).sum();
+    reporter.record_count("rows", rows);
+
+    match output {
+        Some(path) => {
+            std::fs::write(path, formatted).map_err(|e| crate::AppError::WriteFile {
+                path: path.to_string(),
+                source: e,
+            })?;
+            reporter.record_output(path);
+            reporter.say(format_args!("Wrote {} rows to {}", rows, path));
+        }
+        None if reporter.json_output() => eprint!("{}", formatted),
+        None => print!("{}", formatted),
     }
     Ok(())
 }
@@ -295,15 +1313,30 @@ pub fn run_export_index(
     users: &str,
     channels: &str,
     output: &str,
+    format_str: &str,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    println!("Exporting conversations to index...");
+    let format: IndexFormat = format_str.parse()?;
+    let output_path = match format {
+        IndexFormat::SqliteFts if output.ends_with(".json") => {
+            Path::new(output).with_extension("sqlite").to_string_lossy().to_string()
+        }
+        _ => output.to_string(),
+    };
+    let out = OutputPath::new(&output_path, reporter)?;
+    let input = InputPath::new(conversations)?;
 
-    let count = export_conversations_to_index(conversations, users, channels, output)?;
+    reporter.say(format_args!("Exporting conversations to index (format: {})...", format));
 
-    println!(
+    let count = export_conversations_to_index(input.path(), users, channels, out.path(), format)?;
+    out.finish()?;
+    reporter.record_output(&output_path);
+    reporter.record_count("messages", count);
+
+    reporter.say(format_args!(
         "Export completed successfully! {} messages exported to {}",
-        count, output
-    );
+        count, output_path
+    ));
     Ok(())
 }
 
@@ -313,13 +1346,14 @@ pub async fn run_import_index_meilisearch(
     api_key: &str,
     index_name: &str,
     clear: bool,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    println!(
+    reporter.say(format_args!(
         "Importing index to Meilisearch at {} (index: {})...",
         url, index_name
-    );
+    ));
     if clear {
-        println!("  Index will be cleared (using swap operation)");
+        reporter.say(format_args!("  Index will be cleared (using swap operation)"));
     }
 
     let result = import_index_to_meilisearch(
@@ -328,14 +1362,16 @@ pub async fn run_import_index_meilisearch(
         api_key,
         index_name,
         clear,
-        Some(&cli_progress),
+        Some(&reporter.progress()),
     )
     .await?;
+    reporter.record_output(&format!("{}/indexes/{}", url.trim_end_matches('/'), result.index_name));
+    reporter.record_count("documents", result.total);
 
-    println!(
+    reporter.say(format_args!(
         "Import completed successfully! {} documents imported to index '{}'",
         result.total, result.index_name
-    );
+    ));
     Ok(())
 }
 
@@ -344,49 +1380,215 @@ pub async fn run_query_meilisearch(
     api_key: &str,
     index_name: &str,
     query: &str,
+    filters: &SearchFilters,
     limit: usize,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    println!("Searching '{}' in index '{}'...\n", query, index_name);
+    reporter.say(format_args!("Searching '{}' in index '{}'...\n", query, index_name));
 
-    let result = query_meilisearch(url, api_key, index_name, query, limit).await?;
+    let result = query_meilisearch(url, api_key, index_name, query, filters, limit).await?;
+    reporter.record_count("hits", result.hits.len());
 
     if result.hits.is_empty() {
-        println!("No results found.");
+        reporter.say(format_args!("No results found."));
     } else {
-        println!(
+        reporter.say(format_args!(
             "Found {} results (showing {}, {}ms):\n",
             result.estimated_total_hits.unwrap_or(result.hits.len()),
             result.hits.len(),
             result.processing_time_ms
-        );
+        ));
 
-        for (i, hit) in result.hits.iter().enumerate() {
-            println!("{}. [{}] #{}", i + 1, hit.date, hit.channel.name);
-            println!("   Users: {}", hit.users.iter().map(|u| u.name.as_str()).collect::<Vec<_>>().join(", "));
+        print_search_hits(&result.hits, reporter);
+    }
 
-            // Show first 200 chars of text
-            let preview: String = hit.text.chars().take(200).collect();
-            let preview = preview.replace('\n', " ");
-            if hit.text.len() > 200 {
-                println!("   {}...\n", preview);
-            } else {
-                println!("   {}\n", preview);
-            }
-        }
+    Ok(())
+}
+
+pub fn run_index_tantivy(input: &str, output: &str, reporter: &mut Reporter) -> Result<()> {
+    reporter.say(format_args!("Building Tantivy index from {} in {}...", input, output));
+
+    let result = build_tantivy_index(input, output, Some(&reporter.progress()))?;
+    reporter.record_output(&result.index_dir);
+    reporter.record_count("documents", result.total);
+
+    reporter.say(format_args!(
+        "Index built successfully! {} documents indexed in {}",
+        result.total, result.index_dir
+    ));
+    Ok(())
+}
+
+pub fn run_query_tantivy(index_dir: &str, query: &str, limit: usize, reporter: &mut Reporter) -> Result<()> {
+    reporter.say(format_args!("Searching '{}' in Tantivy index {}...\n", query, index_dir));
+
+    let result = query_tantivy(index_dir, query, limit)?;
+    reporter.record_count("hits", result.hits.len());
+
+    if result.hits.is_empty() {
+        reporter.say(format_args!("No results found."));
+    } else {
+        reporter.say(format_args!("Found {} results (showing {}):\n", result.total_hits, result.hits.len()));
+        print_search_hits(&result.hits, reporter);
     }
 
     Ok(())
 }
 
+/// Print search hits with their highlighted match context
+fn print_search_hits(hits: &[SearchHit], reporter: &Reporter) {
+    let use_color = std::io::stdout().is_terminal();
+    for (i, hit) in hits.iter().enumerate() {
+        let entry = &hit.entry;
+        reporter.say(format_args!("{}. [{}] #{}", i + 1, entry.date, entry.channel.name));
+        let users: Vec<&str> = entry.users.iter().map(|u| u.name.as_str()).collect();
+        reporter.say(format_args!("   Users: {}", users.join(", ")));
+
+        if let Some(snippet) = &hit.snippet {
+            reporter.say(format_args!("   {}\n", format_snippet(snippet, use_color)));
+            continue;
+        }
+
+        // Show first 200 chars of text
+        let preview: String = entry.text.chars().take(200).collect();
+        let preview = preview.replace('\n', " ");
+        if entry.text.len() > 200 {
+            reporter.say(format_args!("   {}...\n", preview));
+        } else {
+            reporter.say(format_args!("   {}\n", preview));
+        }
+    }
+}
+
+/// Render a highlighted snippet on one line, emphasizing matched terms
+fn format_snippet(snippet: &str, use_color: bool) -> String {
+    split_highlights(&snippet.replace('\n', " "))
+        .into_iter()
+        .map(|(segment, is_match)| match (is_match, use_color) {
+            (true, true) => format!("\x1b[1;33m{}\x1b[0m", segment),
+            (true, false) => format!("**{}**", segment),
+            (false, _) => segment.to_string(),
+        })
+        .collect()
+}
+
 pub fn run_md_to_html(
     input: &str,
     output: Option<&str>,
     options: &crate::md_to_html::MdToHtmlOptions,
+    page: &crate::md_to_html::HtmlPageOptions,
+    reporter: &mut Reporter,
 ) -> Result<()> {
-    println!("Converting {} to HTML...", input);
+    // Markdown from stdin goes to stdout unless an output is given
+    let output = output.or(is_stdio(input).then_some(STDIO_PATH));
+    let out = output.map(|output| OutputPath::new(output, reporter)).transpose()?;
+    reporter.say(format_args!("Converting {} to HTML...", input));
+
+    let written = crate::md_to_html::convert_md_file_to_html(input, out.as_ref().map(|o| o.path()), options, page)?;
+    if let Some(out) = out {
+        out.finish()?;
+    }
+    let output_path = output.unwrap_or(&written);
+    reporter.record_output(output_path);
 
-    let output_path = crate::md_to_html::convert_md_file_to_html(input, output, options)?;
+    reporter.say(format_args!("Successfully converted to {}", output_path));
+    Ok(())
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn run_build_site(
+    archive: &str,
+    users: &str,
+    channels: &str,
+    attachments: Option<&str>,
+    emojis: Option<&str>,
+    title: &str,
+    output: &str,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    reporter.say(format_args!("Building site from {} in {}...", archive, output));
+    let options = SiteOptions {
+        archive: archive.to_string(),
+        users: users.to_string(),
+        channels: channels.to_string(),
+        attachments: attachments.map(str::to_string),
+        emojis: emojis.map(str::to_string),
+        title: title.to_string(),
+    };
+    let summary = build_site(&options, Path::new(output), Some(&reporter.progress()))?;
+    reporter.record_output(output);
+    reporter.record_count("weeks", summary.weeks);
+    reporter.record_count("messages", summary.messages);
+    reporter.record_count("pages", summary.pages);
+    reporter.record_count("files", summary.files);
+
+    reporter.say(format_args!(
+        "Site built in {}: {} weeks, {} messages, {} pages, {} files copied. Open {}/index.html or publish the folder.",
+        output, summary.weeks, summary.messages, summary.pages, summary.files, output
+    ));
+    Ok(())
+}
+
+pub async fn run_summarize(
+    archive: &str,
+    users: &str,
+    channels: &str,
+    year: Option<i32>,
+    week: Option<i32>,
+    summarizer: &crate::SummarizerArgs,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    let (kind, target) = match (&summarizer.script, &summarizer.endpoint) {
+        (Some(script), _) => (Summarizer::Script(script.clone()), script),
+        (None, Some(endpoint)) => (Summarizer::Endpoint(endpoint.clone()), endpoint),
+        (None, None) => return Err(crate::AppError::InvalidConfig("pass --script or --endpoint".to_string())),
+    };
+    reporter.say(format_args!("Summarizing {} with {}...", archive, target));
+    let options = SummarizeOptions {
+        archive: archive.to_string(),
+        users: users.to_string(),
+        channels: channels.to_string(),
+        summarizer: kind,
+        year,
+        week,
+        markdown_dir: summarizer.markdown_dir.clone(),
+        force: summarizer.force,
+    };
+    let result = summarize_archive(&options, Some(&reporter.progress())).await?;
+    reporter.record_count("summarized", result.summarized);
+    reporter.record_count("weeks", result.weeks);
+    reporter.record_count("skipped", result.skipped);
+    reporter.record_count("markdown_files", result.markdown_files);
+
+    reporter.say(format_args!(
+        "Summarized {} channel weeks in {} weeks ({} already summarized).",
+        result.summarized, result.weeks, result.skipped
+    ));
+    if result.markdown_files > 0 {
+        reporter.say(format_args!("Put the summaries at the top of {} markdown files.", result.markdown_files));
+    }
+    Ok(())
+}
+
+pub fn run_md_to_html_dir(
+    input_dir: &str,
+    output_dir: &str,
+    options: &crate::md_to_html::MdToHtmlOptions,
+    page: &crate::md_to_html::HtmlPageOptions,
+    reporter: &mut Reporter,
+) -> Result<()> {
+    reporter.say(format_args!("Converting markdown files in {} to HTML in {}...", input_dir, output_dir));
+
+    let count = crate::md_to_html::convert_md_dir_to_html(
+        input_dir,
+        output_dir,
+        options,
+        page,
+        Some(&reporter.progress()),
+    )?;
+    reporter.record_output(output_dir);
+    reporter.record_count("files", count);
 
-    println!("Successfully converted to {}", output_path);
+    reporter.say(format_args!("Successfully converted {} files to {}", count, output_dir));
     Ok(())
 }
@@ -0,0 +1,303 @@
+//! `archive-compact`: rewrite the threads files of an archive sorted by
+//! channel and ts in large row groups, or merge its weeks into monthly or
+//! yearly files. Query engines reading a multi-year archive spend most of
+//! their time opening small files and scanning row groups of every channel.
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use chrono::Datelike;
+
+use crate::delta::DeltaTable;
+use crate::error::{AppError, Result};
+use crate::extract::archived_weeks;
+use crate::parquet::{
+    filter_by_date_prefix, read_messages_pages, sort_by_channel_ts, week_threads_files, write_threads_batch, MessagePage,
+};
+use crate::provenance::{read_file_info, Provenance};
+use crate::storage::LocalStorage;
+use crate::{week_to_date_range, ProgressCallback};
+
+/// Rows per row group unless `--row-group-size` says otherwise
+pub const DEFAULT_ROW_GROUP_SIZE: usize = 100_000;
+
+/// The files `archive-compact` writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompactBy {
+    /// Each threads file rewritten where it is, keeping the archive layout
+    #[default]
+    Week,
+    /// `year=YYYY/month=MM/threads.parquet`, by message date
+    Month,
+    /// `year=YYYY/threads.parquet`, by ISO year like the week partitions
+    Year,
+}
+
+impl std::fmt::Display for CompactBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompactBy::Week => write!(f, "week"),
+            CompactBy::Month => write!(f, "month"),
+            CompactBy::Year => write!(f, "year"),
+        }
+    }
+}
+
+impl std::str::FromStr for CompactBy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "week" => Ok(CompactBy::Week),
+            "month" => Ok(CompactBy::Month),
+            "year" => Ok(CompactBy::Year),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected week, month or year)", s))),
+        }
+    }
+}
+
+/// What a compaction read and wrote
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CompactSummary {
+    pub files_read: usize,
+    pub files_written: usize,
+    pub rows: usize,
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+}
+
+/// A file to write, from the rows of `sources` dated with `date_prefix`
+/// (all of them when `None`)
+#[derive(Debug, Clone, PartialEq)]
+struct CompactTarget {
+    key: String,
+    sources: Vec<PathBuf>,
+    date_prefix: Option<String>,
+}
+
+fn plan_targets(archive: &Path, by: CompactBy) -> Result<Vec<CompactTarget>> {
+    let weeks = archived_weeks(archive);
+    let target = |key: String, sources: Vec<PathBuf>, date_prefix: Option<String>| CompactTarget {
+        key,
+        sources,
+        date_prefix,
+    };
+    Ok(match by {
+        CompactBy::Week => weeks
+            .iter()
+            .flat_map(|(_, _, week_dir)| week_threads_files(week_dir))
+            .filter_map(|file| {
+                let key = file.strip_prefix(archive).ok()?.to_string_lossy().replace('\\', "/");
+                Some(target(key, vec![file], None))
+            })
+            .collect(),
+        CompactBy::Year => {
+            let mut years: BTreeMap<i32, Vec<PathBuf>> = BTreeMap::new();
+            for (year, _, week_dir) in &weeks {
+                years.entry(*year).or_default().extend(week_threads_files(week_dir));
+            }
+            years
+                .into_iter()
+                .map(|(year, sources)| target(format!("year={}/threads.parquet", year), sources, None))
+                .collect()
+        }
+        CompactBy::Month => {
+            // A week spanning two months is read for both
+            let mut months: BTreeMap<(i32, u32), Vec<PathBuf>> = BTreeMap::new();
+            for (year, week, week_dir) in &weeks {
+                let week = u32::try_from(*week).map_err(|_| AppError::InvalidDate(format!("Invalid week: {}", week)))?;
+                let (from, to) = week_to_date_range(*year, week)?;
+                let files = week_threads_files(week_dir);
+                for month in HashSet::from([(from.year(), from.month()), (to.year(), to.month())]) {
+                    months.entry(month).or_default().extend(files.iter().cloned());
+                }
+            }
+            months
+                .into_iter()
+                .map(|((year, month), sources)| {
+                    target(
+                        format!("year={}/month={:02}/threads.parquet", year, month),
+                        sources,
+                        Some(format!("{}-{:02}-", year, month)),
+                    )
+                })
+                .collect()
+        }
+    })
+}
+
+fn file_size(path: &Path) -> u64 {
+    fs::metadata(path).map(|m| m.len()).unwrap_or(0)
+}
+
+/// Compact the `year=/week=` threads files of `archive` into `output`, which
+/// may be the archive itself when compacting by week. Rows are sorted by
+/// channel and ts and written in row groups of `row_group_size`; the
+/// provenance of the sources is kept. When `output` has a Delta log, the
+/// files written are committed to it.
+pub fn compact_archive(
+    archive: &Path,
+    output: &Path,
+    (by, row_group_size): (CompactBy, usize),
+    progress: ProgressCallback,
+) -> Result<CompactSummary> {
+    let in_place = output.canonicalize().ok() == archive.canonicalize().ok();
+    if in_place && by != CompactBy::Week {
+        return Err(AppError::InvalidConfig(format!(
+            "{} files would mix with the year=/week= partitions of {}, pass another --output",
+            by,
+            archive.display()
+        )));
+    }
+    let targets = plan_targets(archive, by)?;
+    if targets.is_empty() {
+        return Err(AppError::MissingInput(format!("year=*/week=* partitions in {}", archive.display())));
+    }
+
+    let storage = LocalStorage::new(output);
+    let mut summary = CompactSummary::default();
+    let mut read = HashSet::new();
+    let mut written = Vec::new();
+    for (idx, target) in targets.iter().enumerate() {
+        if let Some(progress) = progress {
+            progress(idx + 1, targets.len(), &target.key);
+        }
+        for source in &target.sources {
+            if read.insert(source.clone()) {
+                summary.bytes_before += file_size(source);
+            }
+        }
+        let sources = target.sources.iter().map(|source| read_file_info(source)).collect::<Result<Vec<_>>>()?;
+        let provenance = Provenance::merged(&sources);
+
+        let batch = read_messages_pages(&target.sources, &MessagePage::default())?.batch;
+        let batch = match &target.date_prefix {
+            Some(prefix) => filter_by_date_prefix(&batch, prefix)?,
+            None => batch,
+        };
+        if batch.num_rows() == 0 && target.date_prefix.is_some() {
+            continue;
+        }
+        let batch = sort_by_channel_ts(&batch)?;
+        write_threads_batch(&storage, &target.key, &batch, row_group_size, provenance.as_ref())?;
+        summary.files_written += 1;
+        summary.rows += batch.num_rows();
+        summary.bytes_after += file_size(&output.join(&target.key));
+        written.push(target.key.clone());
+    }
+    DeltaTable::new(&storage, "").commit_existing(&written, "OPTIMIZE")?;
+    summary.files_read = read.len();
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parquet::{read_parquet_as_json, ConversationsParquetWriter};
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+    use serde_json::json;
+
+    // 2024-01-02 (W01), 2024-01-30 and 2024-02-02 (both W05), 12:00 UTC
+    const JAN_2: &str = "1704196800";
+    const JAN_30: &str = "1706616000";
+    const FEB_2: &str = "1706875200";
+
+    fn archive(dir: &Path) {
+        let storage = LocalStorage::new(dir);
+        let mut writer = ConversationsParquetWriter::new(&storage, "")
+            .provenance(Provenance::now().with_team_id(Some("T1".to_string())));
+        for (channel, ts) in [("C2", FEB_2), ("C1", JAN_30), ("C2", JAN_2), ("C1", JAN_2)] {
+            let conversation = json!({"channel_id": channel, "channel_name": channel, "messages": [{"ts": format!("{}.000000", ts), "text": "hi"}]});
+            writer.write(&conversation).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    fn ids(path: &Path) -> Vec<String> {
+        read_parquet_as_json(path)
+            .unwrap()
+            .iter()
+            .map(|row| format!("{}/{}", row["channel_id"].as_str().unwrap(), &row["ts"].as_str().unwrap()[..10]))
+            .collect()
+    }
+
+    #[test]
+    fn test_compact_by_week_in_place() {
+        let dir = tempfile::TempDir::new().unwrap();
+        archive(dir.path());
+        let summary = compact_archive(dir.path(), dir.path(), (CompactBy::Week, 1), None).unwrap();
+        assert_eq!((summary.files_read, summary.files_written, summary.rows), (2, 2, 4));
+
+        let week5 = dir.path().join("year=2024/week=05/threads.parquet");
+        assert_eq!(ids(&week5), vec![format!("C1/{}", JAN_30), format!("C2/{}", FEB_2)]);
+        let reader = SerializedFileReader::new(std::fs::File::open(&week5).unwrap()).unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        let provenance = read_file_info(&week5).unwrap().provenance.unwrap();
+        assert_eq!(provenance.team_id.as_deref(), Some("T1"));
+    }
+
+    #[test]
+    fn test_compact_in_place_commits_to_delta_log() {
+        let dir = tempfile::TempDir::new().unwrap();
+        archive(dir.path());
+        let storage = LocalStorage::new(dir.path());
+        let table = DeltaTable::new(&storage, "");
+        table.commit(&[], "CREATE TABLE").unwrap();
+        let before = table.files().unwrap();
+
+        compact_archive(dir.path(), dir.path(), (CompactBy::Week, 1), None).unwrap();
+        assert_eq!(table.version().unwrap(), Some(1));
+        let after = table.files().unwrap();
+        assert_eq!(after.len(), before.len());
+        assert!(after.is_disjoint(&before));
+    }
+
+    #[test]
+    fn test_compact_by_month_and_year() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("conversations");
+        archive(&source);
+
+        let monthly = dir.path().join("monthly");
+        let summary = compact_archive(&source, &monthly, (CompactBy::Month, DEFAULT_ROW_GROUP_SIZE), None).unwrap();
+        assert_eq!((summary.files_read, summary.files_written, summary.rows), (2, 2, 4));
+        assert_eq!(
+            ids(&monthly.join("year=2024/month=01/threads.parquet")),
+            vec![format!("C1/{}", JAN_2), format!("C1/{}", JAN_30), format!("C2/{}", JAN_2)]
+        );
+        assert_eq!(ids(&monthly.join("year=2024/month=02/threads.parquet")), vec![format!("C2/{}", FEB_2)]);
+
+        let yearly = dir.path().join("yearly");
+        let summary = compact_archive(&source, &yearly, (CompactBy::Year, DEFAULT_ROW_GROUP_SIZE), None).unwrap();
+        assert_eq!((summary.files_written, summary.rows), (1, 4));
+        assert_eq!(ids(&yearly.join("year=2024/threads.parquet")).len(), 4);
+
+        // Months next to weeks would be read twice by the archive's globs
+        assert!(compact_archive(&source, &source, (CompactBy::Month, DEFAULT_ROW_GROUP_SIZE), None).is_err());
+        assert!(compact_archive(&dir.path().join("missing"), &yearly, (CompactBy::Year, 1), None).is_err());
+    }
+
+    #[test]
+    fn test_compact_by_month_keeps_threads() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let source = dir.path().join("conversations");
+        let storage = LocalStorage::new(&source);
+        let mut writer = ConversationsParquetWriter::new(&storage, "");
+        let thread = json!({"channel_id": "C1", "channel_name": "C1", "messages": [{
+            "ts": format!("{}.000000", JAN_2),
+            "thread_replies": [{"ts": format!("{}.000000", FEB_2), "text": "late"}]
+        }]});
+        writer.write(&thread).unwrap();
+        writer.finish().unwrap();
+
+        // The February reply is stored in W01 with its parent and stays with it
+        let monthly = dir.path().join("monthly");
+        let summary = compact_archive(&source, &monthly, (CompactBy::Month, DEFAULT_ROW_GROUP_SIZE), None).unwrap();
+        assert_eq!((summary.files_written, summary.rows), (1, 2));
+        assert_eq!(
+            ids(&monthly.join("year=2024/month=01/threads.parquet")),
+            vec![format!("C1/{}", JAN_2), format!("C1/{}", FEB_2)]
+        );
+    }
+}
@@ -0,0 +1,714 @@
+//! Convert a conversations export into the import formats of other chat
+//! platforms, for teams moving off Slack that want to keep their history
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use serde_json::{json, Value};
+
+use crate::error::{AppError, Result};
+use crate::extract::ts_order;
+use crate::split::file_stem;
+
+/// Platform `convert-to` writes for
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConvertTarget {
+    /// matrix-archive style JSON, one room per channel with `m.room.message`
+    /// events and replies as `m.thread` relations
+    Matrix,
+    /// DiscordChatExporter JSON, one channel per file, which Discord migration
+    /// tools replay into a server; replies reference their thread's parent
+    Discord,
+    /// Mattermost bulk-import JSONL, a single file with the team, channels,
+    /// placeholder accounts for users and posts with their thread replies
+    Mattermost,
+}
+
+impl std::str::FromStr for ConvertTarget {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "matrix" => Ok(ConvertTarget::Matrix),
+            "discord" => Ok(ConvertTarget::Discord),
+            "mattermost" => Ok(ConvertTarget::Mattermost),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected matrix, discord or mattermost)", s))),
+        }
+    }
+}
+
+/// What a conversion needs besides the conversations
+#[derive(Debug, Clone, Default)]
+pub struct ConvertOptions {
+    /// User ID -> display name, for authors and mentions
+    pub user_names: HashMap<String, String>,
+    /// Matrix homeserver the room and user IDs are made on, and the domain of
+    /// placeholder Mattermost emails
+    pub server_name: String,
+    /// Users export, for Mattermost accounts
+    pub users: Vec<Value>,
+    /// Channels export, for Mattermost channel headers and privacy
+    pub channels: Vec<Value>,
+    /// Mattermost team everything is imported into
+    pub team: String,
+}
+
+/// What a converted file holds
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConvertedContent {
+    Json(Value),
+    /// One JSON object per line
+    JsonLines(Vec<Value>),
+}
+
+/// One file a conversion writes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConvertedFile {
+    pub file_name: String,
+    pub content: ConvertedContent,
+    /// Messages and replies in the file
+    pub messages: usize,
+}
+
+/// A message or reply with the channel and thread it belongs to
+struct FlatMessage<'a> {
+    message: &'a Value,
+    /// ts of the thread's parent, for replies
+    parent_ts: Option<&'a str>,
+}
+
+impl FlatMessage<'_> {
+    fn ts(&self) -> &str {
+        self.message.get("ts").and_then(|v| v.as_str()).unwrap_or_default()
+    }
+
+    fn user(&self) -> Option<&str> {
+        self.message
+            .get("user")
+            .or_else(|| self.message.get("bot_id"))
+            .and_then(|v| v.as_str())
+    }
+
+    fn is_bot(&self) -> bool {
+        self.message.get("bot_id").is_some() || self.message.get("subtype").and_then(|v| v.as_str()) == Some("bot_message")
+    }
+}
+
+/// Every message and reply of a conversation, oldest first
+fn flatten(conversation: &Value) -> Vec<FlatMessage<'_>> {
+    let mut flat = Vec::new();
+    let messages = conversation.get("messages").and_then(|m| m.as_array()).into_iter().flatten();
+    for message in messages {
+        let Some(ts) = message.get("ts").and_then(|v| v.as_str()) else {
+            continue;
+        };
+        flat.push(FlatMessage {
+            message,
+            parent_ts: None,
+        });
+        let replies = message.get("thread_replies").and_then(|r| r.as_array()).into_iter().flatten();
+        for reply in replies.filter(|r| r.get("ts").and_then(|v| v.as_str()).is_some_and(|r| r != ts)) {
+            flat.push(FlatMessage {
+                message: reply,
+                parent_ts: Some(ts),
+            });
+        }
+    }
+    flat.sort_by(|a, b| ts_order(a.ts(), b.ts()));
+    flat
+}
+
+/// Milliseconds since the epoch of a Slack timestamp
+fn ts_millis(ts: &str) -> Option<i64> {
+    let (secs, fraction) = ts.split_once('.').unwrap_or((ts, ""));
+    let millis: String = fraction.chars().chain("000".chars()).take(3).collect();
+    Some(secs.parse::<i64>().ok()? * 1000 + millis.parse::<i64>().ok()?)
+}
+
+/// Slack mrkdwn `text` with user and channel references, special mentions
+/// and links turned into the plain markdown every target displays, and HTML
+/// entities unescaped
+pub fn slack_text_to_markdown(
+    text: &str,
+    user_names: &HashMap<String, String>,
+    channel_names: &HashMap<String, String>,
+) -> String {
+    let unescape = |s: &str| s.replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&");
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        let Some(end) = rest[start..].find('>').map(|e| start + e) else {
+            break;
+        };
+        output.push_str(&unescape(&rest[..start]));
+        let inner = &rest[start + 1..end];
+        let (target, label) = match inner.split_once('|') {
+            Some((target, label)) => (target, Some(unescape(label))),
+            None => (inner, None),
+        };
+        let converted = if let Some(user) = target.strip_prefix('@') {
+            let name = user_names.get(user).cloned().or(label).unwrap_or_else(|| user.to_string());
+            format!("@{}", name)
+        } else if let Some(channel) = target.strip_prefix('#') {
+            let name = label.or_else(|| channel_names.get(channel).cloned()).unwrap_or_else(|| channel.to_string());
+            format!("#{}", name)
+        } else if let Some(special) = target.strip_prefix('!') {
+            match special {
+                "here" | "channel" | "everyone" => format!("@{}", special),
+                _ => label.unwrap_or_else(|| special.to_string()),
+            }
+        } else {
+            let url = unescape(target);
+            match label {
+                Some(label) if label != url && !url.starts_with("mailto:") => format!("[{}]({})", label, url),
+                Some(label) => label,
+                None => url,
+            }
+        };
+        output.push_str(&converted);
+        rest = &rest[end + 1..];
+    }
+    output.push_str(&unescape(rest));
+    output
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+/// File names per conversation: the channel name, its ID when two channels
+/// share a name
+fn file_names(conversations: &[Value]) -> Vec<String> {
+    let mut used = HashSet::new();
+    conversations
+        .iter()
+        .map(|conversation| {
+            let id = file_stem(text(conversation, "channel_id"));
+            let stem = match file_stem(text(conversation, "channel_name")) {
+                name if name.is_empty() => id,
+                name if used.contains(&name) => format!("{}-{}", name, id),
+                name => name,
+            };
+            used.insert(stem.clone());
+            format!("{}.json", stem)
+        })
+        .collect()
+}
+
+/// Matrix user ID for a Slack user or bot ID
+fn matrix_user(id: Option<&str>, server_name: &str) -> String {
+    format!("@{}:{}", id.unwrap_or("unknown").to_lowercase(), server_name)
+}
+
+fn matrix_event_id(channel_id: &str, ts: &str) -> String {
+    format!("${}-{}", channel_id, ts)
+}
+
+fn matrix_room(conversation: &Value, body: &dyn Fn(&Value) -> String, options: &ConvertOptions) -> (Value, usize) {
+    let channel_id = text(conversation, "channel_id");
+    let room_id = format!("!{}:{}", channel_id, options.server_name);
+    let flat = flatten(conversation);
+
+    let mut members: BTreeMap<String, Value> = BTreeMap::new();
+    let events: Vec<Value> = flat
+        .iter()
+        .map(|m| {
+            let sender = matrix_user(m.user(), &options.server_name);
+            let displayname = m.user().map(|id| options.user_names.get(id).map_or(id, |n| n.as_str()));
+            members.entry(sender.clone()).or_insert_with(|| json!({"displayname": displayname}));
+
+            let mut content = json!({"msgtype": "m.text", "body": body(m.message)});
+            if let Some(parent_ts) = m.parent_ts
+                && let Some(fields) = content.as_object_mut()
+            {
+                let parent = matrix_event_id(channel_id, parent_ts);
+                fields.insert(
+                    "m.relates_to".to_string(),
+                    json!({
+                        "rel_type": "m.thread",
+                        "event_id": parent,
+                        "is_falling_back": true,
+                        "m.in_reply_to": {"event_id": parent},
+                    }),
+                );
+            }
+            json!({
+                "type": "m.room.message",
+                "event_id": matrix_event_id(channel_id, m.ts()),
+                "room_id": room_id,
+                "sender": sender,
+                "origin_server_ts": ts_millis(m.ts()),
+                "content": content,
+            })
+        })
+        .collect();
+
+    let room = json!({
+        "room_id": room_id,
+        "name": text(conversation, "channel_name"),
+        "members": members,
+        "messages": events,
+    });
+    (room, flat.len())
+}
+
+fn discord_channel(conversation: &Value, body: &dyn Fn(&Value) -> String, options: &ConvertOptions) -> (Value, usize) {
+    let channel_id = text(conversation, "channel_id");
+    let flat = flatten(conversation);
+    let messages: Vec<Value> = flat
+        .iter()
+        .map(|m| {
+            let id = m.user().unwrap_or("unknown");
+            let name = options.user_names.get(id).map_or(id, |n| n.as_str());
+            let timestamp = ts_millis(m.ts())
+                .and_then(DateTime::from_timestamp_millis)
+                .map(|t| t.format("%Y-%m-%dT%H:%M:%S%.3f+00:00").to_string());
+            let attachments: Vec<Value> = m
+                .message
+                .get("files")
+                .and_then(|f| f.as_array())
+                .into_iter()
+                .flatten()
+                .map(|file| {
+                    json!({
+                        "id": text(file, "id"),
+                        "url": text(file, "url_private"),
+                        "fileName": text(file, "name"),
+                        "fileSizeBytes": file.get("size").and_then(|s| s.as_u64()).unwrap_or(0),
+                    })
+                })
+                .collect();
+            let mut message = json!({
+                "id": m.ts(),
+                "type": if m.parent_ts.is_some() { "Reply" } else { "Default" },
+                "timestamp": timestamp,
+                "content": body(m.message),
+                "author": {"id": id, "name": name, "nickname": name, "isBot": m.is_bot()},
+                "attachments": attachments,
+            });
+            if let Some(parent_ts) = m.parent_ts
+                && let Some(fields) = message.as_object_mut()
+            {
+                fields.insert(
+                    "reference".to_string(),
+                    json!({"messageId": parent_ts, "channelId": channel_id}),
+                );
+            }
+            message
+        })
+        .collect();
+
+    let channel = json!({
+        "guild": {"id": "0", "name": "Slack"},
+        "channel": {"id": channel_id, "type": "GuildTextChat", "name": text(conversation, "channel_name")},
+        "messageCount": messages.len(),
+        "messages": messages,
+    });
+    (channel, flat.len())
+}
+
+/// Characters Mattermost accepts in one post; longer messages are cut
+const MATTERMOST_MESSAGE_LIMIT: usize = 16_383;
+
+/// `name` as a Mattermost team, channel or user name: lowercase letters,
+/// digits, `-`, `_` and `.` (users only), at most `max` characters
+fn mattermost_name(name: &str, allow_dot: bool, max: usize) -> String {
+    name.to_lowercase()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' || c == '_' || (allow_dot && c == '.') {
+                c
+            } else {
+                '-'
+            }
+        })
+        .take(max)
+        .collect()
+}
+
+/// Placeholder account names: the Slack handle when it's a valid, unused
+/// Mattermost username, `user-<id>` otherwise, for every user in the users
+/// export and every author or mention only found in messages
+fn mattermost_usernames(conversations: &[Value], users: &[Value]) -> BTreeMap<String, String> {
+    let mut ids: Vec<(String, &str)> = users
+        .iter()
+        .filter_map(|u| Some((u.get("id")?.as_str()?.to_string(), text(u, "name"))))
+        .collect();
+    for conversation in conversations {
+        for message in flatten(conversation) {
+            if let Some(id) = message.user()
+                && !ids.iter().any(|(known, _)| known == id)
+            {
+                ids.push((id.to_string(), ""));
+            }
+        }
+    }
+
+    let mut used = HashSet::new();
+    let mut usernames = BTreeMap::new();
+    for (id, handle) in ids {
+        let handle = mattermost_name(handle, true, 22);
+        let username = if handle.len() >= 3 && handle.starts_with(|c: char| c.is_ascii_lowercase()) && !used.contains(&handle)
+        {
+            handle
+        } else {
+            mattermost_name(&format!("user-{}", id), true, 22)
+        };
+        used.insert(username.clone());
+        usernames.insert(id, username);
+    }
+    usernames
+}
+
+/// Mattermost bulk-import lines: version, team, channels, users (members of
+/// the channels they posted in) and posts oldest first, each thread as a root
+/// post with its replies
+fn mattermost_import(conversations: &[Value], channel_names: &HashMap<String, String>, options: &ConvertOptions) -> (Vec<Value>, usize) {
+    let team = mattermost_name(&options.team, false, 64);
+    let usernames = mattermost_usernames(conversations, &options.users);
+    let mention_names: HashMap<String, String> = usernames.iter().map(|(id, name)| (id.clone(), name.clone())).collect();
+    let message = |value: &Value| -> String {
+        slack_text_to_markdown(text(value, "text"), &mention_names, channel_names)
+            .chars()
+            .take(MATTERMOST_MESSAGE_LIMIT)
+            .collect()
+    };
+    let username = |value: &Value| {
+        let id = value.get("user").or_else(|| value.get("bot_id")).and_then(|v| v.as_str()).unwrap_or_default();
+        usernames.get(id).cloned().unwrap_or_else(|| "unknown".to_string())
+    };
+
+    let mut lines = vec![
+        json!({"type": "version", "version": 1}),
+        json!({"type": "team", "team": {"name": team, "display_name": options.team, "type": "O"}}),
+    ];
+    let mut used = HashSet::new();
+    let mut memberships: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut posts = Vec::new();
+    let mut count = 0;
+    for conversation in conversations {
+        let channel_id = text(conversation, "channel_id");
+        let info = options.channels.iter().find(|c| text(c, "id") == channel_id);
+        let display_name = text(conversation, "channel_name");
+        let mut name = mattermost_name(display_name, false, 64);
+        if name.len() < 2 || used.contains(&name) {
+            name = mattermost_name(&format!("{}-{}", name, channel_id), false, 64).trim_start_matches('-').to_string();
+        }
+        used.insert(name.clone());
+        let private = info.and_then(|c| c.get("is_private")).and_then(|v| v.as_bool()).unwrap_or(false)
+            || channel_id.starts_with(['D', 'G']);
+        let topic = |key: &str| info.and_then(|c| c.get(key)).and_then(|t| t.get("value")).and_then(|v| v.as_str());
+        lines.push(json!({
+            "type": "channel",
+            "channel": {
+                "team": team,
+                "name": name,
+                "display_name": if display_name.is_empty() { channel_id } else { display_name },
+                "type": if private { "P" } else { "O" },
+                "header": topic("topic").unwrap_or_default(),
+                "purpose": topic("purpose").unwrap_or_default(),
+            }
+        }));
+
+        let mut roots: Vec<&Value> = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .into_iter()
+            .flatten()
+            .filter(|m| m.get("ts").and_then(|v| v.as_str()).is_some())
+            .collect();
+        roots.sort_by(|a, b| ts_order(text(a, "ts"), text(b, "ts")));
+        for root in roots {
+            let ts = text(root, "ts");
+            let mut thread: Vec<&Value> = root
+                .get("thread_replies")
+                .and_then(|r| r.as_array())
+                .into_iter()
+                .flatten()
+                .filter(|r| r.get("ts").and_then(|v| v.as_str()).is_some_and(|r| r != ts))
+                .collect();
+            thread.sort_by(|a, b| ts_order(text(a, "ts"), text(b, "ts")));
+            for author in std::iter::once(root).chain(thread.iter().copied()).map(username) {
+                let channels = memberships.entry(author).or_default();
+                if !channels.contains(&name) {
+                    channels.push(name.clone());
+                }
+            }
+            count += 1 + thread.len();
+            let replies: Vec<Value> = thread
+                .iter()
+                .map(|r| json!({"user": username(r), "message": message(r), "create_at": ts_millis(text(r, "ts"))}))
+                .collect();
+            posts.push(json!({
+                "type": "post",
+                "post": {
+                    "team": team,
+                    "channel": name,
+                    "user": username(root),
+                    "message": message(root),
+                    "create_at": ts_millis(ts),
+                    "replies": replies,
+                }
+            }));
+        }
+    }
+
+    let mut placeholders: Vec<(&String, &String)> = usernames.iter().map(|(id, name)| (name, id)).collect();
+    placeholders.sort();
+    for (name, id) in placeholders {
+        let profile = options.users.iter().find(|u| text(u, "id") == id).and_then(|u| u.get("profile"));
+        let email = profile
+            .and_then(|p| p.get("email"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{}@{}", name, options.server_name));
+        let channels: Vec<Value> = memberships
+            .get(name)
+            .into_iter()
+            .flatten()
+            .map(|channel| json!({"name": channel, "roles": "channel_user"}))
+            .collect();
+        lines.push(json!({
+            "type": "user",
+            "user": {
+                "username": name,
+                "email": email,
+                "nickname": options.user_names.get(id).map_or(id.as_str(), |n| n.as_str()),
+                "teams": [{"name": team, "roles": "team_user", "channels": channels}],
+            }
+        }));
+    }
+    lines.extend(posts);
+    (lines, count)
+}
+
+/// Convert `conversations` into `target`'s format: one file per channel, or
+/// a single import file for Mattermost
+pub fn convert_conversations(conversations: &[Value], target: ConvertTarget, options: &ConvertOptions) -> Vec<ConvertedFile> {
+    let channel_names: HashMap<String, String> = conversations
+        .iter()
+        .map(|c| (text(c, "channel_id").to_string(), text(c, "channel_name").to_string()))
+        .collect();
+    let body = |message: &Value| slack_text_to_markdown(text(message, "text"), &options.user_names, &channel_names);
+
+    let convert_channel = match target {
+        ConvertTarget::Matrix => matrix_room,
+        ConvertTarget::Discord => discord_channel,
+        ConvertTarget::Mattermost => {
+            let (lines, messages) = mattermost_import(conversations, &channel_names, options);
+            return vec![ConvertedFile {
+                file_name: "mattermost-import.jsonl".to_string(),
+                content: ConvertedContent::JsonLines(lines),
+                messages,
+            }];
+        }
+    };
+    conversations
+        .iter()
+        .zip(file_names(conversations))
+        .map(|(conversation, file_name)| {
+            let (content, messages) = convert_channel(conversation, &body, options);
+            ConvertedFile {
+                file_name,
+                content: ConvertedContent::Json(content),
+                messages,
+            }
+        })
+        .collect()
+}
+
+/// Write each converted file to `output_dir`, creating it; returns the paths
+/// written
+pub fn write_converted(files: &[ConvertedFile], output_dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(output_dir).map_err(|e| AppError::WriteFile {
+        path: output_dir.display().to_string(),
+        source: e,
+    })?;
+    let mut written = Vec::with_capacity(files.len());
+    for file in files {
+        let path = output_dir.join(&file.file_name);
+        match &file.content {
+            ConvertedContent::Json(content) => crate::slack::write_json(&path, content)?,
+            ConvertedContent::JsonLines(lines) => {
+                let mut jsonl = String::new();
+                for line in lines {
+                    jsonl.push_str(&serde_json::to_string(line).map_err(|e| AppError::JsonSerialize(e.to_string()))?);
+                    jsonl.push('\n');
+                }
+                std::fs::write(&path, jsonl).map_err(|e| AppError::WriteFile {
+                    path: path.display().to_string(),
+                    source: e,
+                })?;
+            }
+        }
+        written.push(path);
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> Vec<Value> {
+        vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1705399200.000100", "bot_id": "B1", "subtype": "bot_message", "text": "deployed &amp; done"},
+                    {"ts": "1705312800.123456", "user": "U1", "text": "hi <@U2>, see <#C2>",
+                     "files": [{"id": "F1", "name": "plan.pdf", "url_private": "https://files.slack.com/plan.pdf", "size": 10}],
+                     "thread_replies": [
+                         {"ts": "1705312800.123456", "user": "U1", "text": "parent repeated"},
+                         {"ts": "1705312860.000100", "user": "U2", "text": "thanks"}
+                     ]}
+                ]
+            }),
+            json!({"channel_id": "C2", "channel_name": "random", "messages": []}),
+        ]
+    }
+
+    fn options() -> ConvertOptions {
+        ConvertOptions {
+            user_names: HashMap::from([("U1".to_string(), "ana".to_string()), ("U2".to_string(), "bob".to_string())]),
+            server_name: "example.org".to_string(),
+            users: vec![
+                json!({"id": "U1", "name": "ana.p", "profile": {"email": "ana@example.com"}}),
+                json!({"id": "U2", "name": "bo"}),
+            ],
+            channels: vec![json!({"id": "C2", "name": "random", "is_private": true, "topic": {"value": "off-topic"}})],
+            team: "Acme Inc".to_string(),
+        }
+    }
+
+    fn content(file: &ConvertedFile) -> &Value {
+        match &file.content {
+            ConvertedContent::Json(content) => content,
+            ConvertedContent::JsonLines(_) => panic!("expected JSON"),
+        }
+    }
+
+    #[test]
+    fn test_slack_text_to_markdown() {
+        let options = options();
+        let channels = HashMap::from([("C2".to_string(), "random".to_string())]);
+        let convert = |text: &str| slack_text_to_markdown(text, &options.user_names, &channels);
+
+        assert_eq!(convert("<@U1> in <#C2> and <#C3|ops>"), "@ana in #random and #ops");
+        assert_eq!(convert("<!here> <!subteam^S1|@devs>"), "@here @devs");
+        assert_eq!(convert("<https://example.com|docs> <https://example.com>"), "[docs](https://example.com) https://example.com");
+        assert_eq!(convert("<mailto:a@b.c|a@b.c>"), "a@b.c");
+        assert_eq!(convert("a &lt; b &amp;&amp; <unclosed"), "a < b && <unclosed");
+    }
+
+    #[test]
+    fn test_ts_millis() {
+        assert_eq!(ts_millis("1705312800.123456"), Some(1705312800123));
+        assert_eq!(ts_millis("1705312800"), Some(1705312800000));
+        assert_eq!(ts_millis("nope"), None);
+    }
+
+    #[test]
+    fn test_convert_matrix() {
+        let files = convert_conversations(&export(), ConvertTarget::Matrix, &options());
+        assert_eq!(files.len(), 2);
+        let room = &files[0];
+        assert_eq!((room.file_name.as_str(), room.messages), ("general.json", 3));
+        assert_eq!(content(room)["room_id"], "!C1:example.org");
+        assert_eq!(content(room)["members"]["@u1:example.org"]["displayname"], "ana");
+
+        let events = content(room)["messages"].as_array().unwrap();
+        assert_eq!(events[0]["sender"], "@u1:example.org");
+        assert_eq!(events[0]["origin_server_ts"], 1705312800123i64);
+        assert_eq!(events[0]["content"]["body"], "hi @bob, see #random");
+        assert_eq!(events[1]["content"]["m.relates_to"]["rel_type"], "m.thread");
+        assert_eq!(events[1]["content"]["m.relates_to"]["event_id"], "$C1-1705312800.123456");
+        assert_eq!(events[2]["sender"], "@b1:example.org");
+        assert_eq!(events[2]["content"]["body"], "deployed & done");
+    }
+
+    #[test]
+    fn test_convert_discord() {
+        let files = convert_conversations(&export(), ConvertTarget::Discord, &options());
+        let channel = content(&files[0]);
+        assert_eq!(channel["channel"]["name"], "general");
+        assert_eq!(channel["messageCount"], 3);
+
+        let messages = channel["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["timestamp"], "2024-01-15T10:00:00.123+00:00");
+        assert_eq!(messages[0]["author"]["name"], "ana");
+        assert_eq!(messages[0]["attachments"][0]["fileName"], "plan.pdf");
+        assert_eq!(messages[1]["type"], "Reply");
+        assert_eq!(messages[1]["reference"]["messageId"], "1705312800.123456");
+        assert_eq!(messages[2]["author"]["isBot"], true);
+    }
+
+    #[test]
+    fn test_convert_mattermost() {
+        let files = convert_conversations(&export(), ConvertTarget::Mattermost, &options());
+        assert_eq!(files.len(), 1);
+        assert_eq!((files[0].file_name.as_str(), files[0].messages), ("mattermost-import.jsonl", 3));
+        let ConvertedContent::JsonLines(lines) = &files[0].content else {
+            panic!("expected JSON lines");
+        };
+        let kinds: Vec<&str> = lines.iter().map(|l| l["type"].as_str().unwrap()).collect();
+        assert_eq!(kinds, ["version", "team", "channel", "channel", "user", "user", "user", "post", "post"]);
+        assert_eq!(lines[1]["team"]["name"], "acme-inc");
+        assert_eq!(lines[3]["channel"]["type"], "P");
+        assert_eq!(lines[3]["channel"]["header"], "off-topic");
+
+        // "bo" is too short for a Mattermost username
+        let users: Vec<&str> = lines[4..7].iter().map(|l| l["user"]["username"].as_str().unwrap()).collect();
+        assert_eq!(users, ["ana.p", "user-b1", "user-u2"]);
+        assert_eq!(lines[4]["user"]["email"], "ana@example.com");
+        assert_eq!(lines[5]["user"]["email"], "user-b1@example.org");
+        assert_eq!(lines[4]["user"]["teams"][0]["channels"][0]["name"], "general");
+
+        let root = &lines[7]["post"];
+        assert_eq!(root["user"], "ana.p");
+        assert_eq!(root["message"], "hi @user-u2, see #random");
+        assert_eq!(root["create_at"], 1705312800123i64);
+        assert_eq!(root["replies"].as_array().unwrap().len(), 1);
+        assert_eq!(root["replies"][0]["user"], "user-u2");
+        assert_eq!(lines[8]["post"]["user"], "user-b1");
+    }
+
+    #[test]
+    fn test_mattermost_name() {
+        assert_eq!(mattermost_name("Dev Team!", false, 64), "dev-team-");
+        assert_eq!(mattermost_name("ana.p", true, 3), "ana");
+    }
+
+    #[test]
+    fn test_file_names() {
+        let conversations = vec![
+            json!({"channel_id": "C1", "channel_name": "general"}),
+            json!({"channel_id": "C2", "channel_name": "general"}),
+            json!({"channel_id": "D3"}),
+        ];
+        assert_eq!(file_names(&conversations), ["general.json", "general-C2.json", "D3.json"]);
+    }
+
+    #[test]
+    fn test_write_converted() {
+        let dir = tempfile::tempdir().unwrap();
+        let files = convert_conversations(&export(), ConvertTarget::Discord, &options());
+        let written = write_converted(&files, &dir.path().join("discord")).unwrap();
+        let loaded: Value = crate::load_json_file(&written[1].to_string_lossy()).unwrap();
+        assert_eq!(loaded["channel"]["id"], "C2");
+
+        let files = convert_conversations(&export(), ConvertTarget::Mattermost, &options());
+        let written = write_converted(&files, dir.path()).unwrap();
+        let jsonl = std::fs::read_to_string(&written[0]).unwrap();
+        assert_eq!(jsonl.lines().count(), 9);
+        assert_eq!(jsonl.lines().next(), Some(r#"{"type":"version","version":1}"#));
+    }
+
+    #[test]
+    fn test_convert_target_from_str() {
+        assert_eq!("Matrix".parse::<ConvertTarget>().unwrap(), ConvertTarget::Matrix);
+        assert!("irc".parse::<ConvertTarget>().is_err());
+    }
+}
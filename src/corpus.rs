@@ -0,0 +1,380 @@
+//! Plain-text corpus of an export for embedding or fine-tuning pipelines:
+//! documents of one thread or a fixed number of messages, each with its
+//! channel, participants and dates
+
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::convert::slack_text_to_markdown;
+use crate::error::{AppError, Result};
+use crate::extract::ts_order;
+use crate::index::slack_ts_to_iso8601;
+use crate::split::file_stem;
+
+/// How `export-corpus` writes its documents
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum CorpusFormat {
+    /// One JSON document per line, metadata as fields
+    #[default]
+    Jsonl,
+    /// A directory with one `.txt` file per document, metadata as a header
+    Text,
+}
+
+impl std::str::FromStr for CorpusFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "jsonl" => Ok(CorpusFormat::Jsonl),
+            "text" | "txt" => Ok(CorpusFormat::Text),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected jsonl or text)", s))),
+        }
+    }
+}
+
+/// How the corpus is cut into documents
+#[derive(Debug, Clone, Default)]
+pub struct CorpusOptions {
+    /// Messages per document, a thread (parent and replies) per document
+    /// when `None`
+    pub messages_per_chunk: Option<usize>,
+    /// Start a new document before one would get longer than this
+    pub max_chars: Option<usize>,
+    /// User ID -> display name
+    pub user_names: HashMap<String, String>,
+    /// Name users `user1`, `user2`, ... in order of appearance, authors and
+    /// mentions alike, instead of by their names
+    pub anonymize: bool,
+}
+
+/// One document of the corpus
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct CorpusDocument {
+    /// `<channel_id>-<ts>` of the thread or first message, with `-<n>` for
+    /// the later parts of a thread cut by `max_chars`
+    pub id: String,
+    pub channel_id: String,
+    pub channel: String,
+    /// Authors, sorted
+    pub participants: Vec<String>,
+    /// ISO 8601 dates of the first and last message
+    pub start: String,
+    pub end: String,
+    pub messages: usize,
+    /// `name: text` per message, replies indented
+    pub text: String,
+}
+
+impl CorpusDocument {
+    /// The document with its metadata as a header, for the text format
+    pub fn to_text(&self) -> String {
+        format!(
+            "channel: #{}\nparticipants: {}\ndate: {} to {}\n\n{}\n",
+            self.channel,
+            self.participants.join(", "),
+            self.start,
+            self.end,
+            self.text
+        )
+    }
+}
+
+/// A message rendered for the corpus
+struct Line {
+    ts: String,
+    author: String,
+    text: String,
+}
+
+/// IDs of the users mentioned in `text` as `<@U…>`
+fn mentioned_users(text: &str) -> impl Iterator<Item = &str> {
+    text.split("<@").skip(1).filter_map(|rest| rest.split(['>', '|']).next())
+}
+
+/// `user1`, `user2`, ... for every author and mentioned user, in order of
+/// appearance
+fn pseudonyms(conversations: &[Value]) -> HashMap<String, String> {
+    let mut names = HashMap::new();
+    let mut add = |id: &str| {
+        let next = format!("user{}", names.len() + 1);
+        names.entry(id.to_string()).or_insert(next);
+    };
+    for conversation in conversations {
+        for message in messages(conversation) {
+            let replies = message.get("thread_replies").and_then(|r| r.as_array()).into_iter().flatten();
+            for message in std::iter::once(message).chain(replies) {
+                if let Some(user) = message.get("user").and_then(|v| v.as_str()) {
+                    add(user);
+                }
+                mentioned_users(text(message, "text")).for_each(&mut add);
+            }
+        }
+    }
+    names
+}
+
+fn text<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+/// Top-level messages with a ts, oldest first
+fn messages(conversation: &Value) -> Vec<&Value> {
+    let mut messages: Vec<&Value> = conversation
+        .get("messages")
+        .and_then(|m| m.as_array())
+        .into_iter()
+        .flatten()
+        .filter(|m| m.get("ts").and_then(|v| v.as_str()).is_some())
+        .collect();
+    messages.sort_by(|a, b| ts_order(text(a, "ts"), text(b, "ts")));
+    messages
+}
+
+/// Each thread of a conversation, oldest first: the parent, then its replies
+fn threads(conversation: &Value) -> Vec<Vec<(&Value, bool)>> {
+    messages(conversation)
+        .into_iter()
+        .map(|parent| {
+            let ts = text(parent, "ts");
+            let mut replies: Vec<&Value> = parent
+                .get("thread_replies")
+                .and_then(|r| r.as_array())
+                .into_iter()
+                .flatten()
+                .filter(|r| r.get("ts").and_then(|v| v.as_str()).is_some_and(|r| r != ts))
+                .collect();
+            replies.sort_by(|a, b| ts_order(text(a, "ts"), text(b, "ts")));
+            std::iter::once((parent, false)).chain(replies.into_iter().map(|r| (r, true))).collect()
+        })
+        .collect()
+}
+
+/// Cut `lines` before `max_messages` or `max_chars` would be exceeded; a
+/// single longer message still makes a document of its own
+fn chunk_lines(lines: Vec<Line>, max_messages: Option<usize>, max_chars: Option<usize>) -> Vec<Vec<Line>> {
+    let mut chunks: Vec<Vec<Line>> = Vec::new();
+    let mut current: Vec<Line> = Vec::new();
+    let mut chars = 0;
+    for line in lines {
+        let line_chars = line.text.chars().count() + 1;
+        let full = max_messages.is_some_and(|max| current.len() >= max)
+            || max_chars.is_some_and(|max| chars + line_chars > max);
+        if full && !current.is_empty() {
+            chunks.push(std::mem::take(&mut current));
+            chars = 0;
+        }
+        chars += line_chars;
+        current.push(line);
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+fn document(id: String, channel_id: &str, channel: &str, lines: &[Line]) -> Option<CorpusDocument> {
+    let (first, last) = (lines.first()?, lines.last()?);
+    let participants: BTreeSet<&str> = lines.iter().map(|l| l.author.as_str()).collect();
+    Some(CorpusDocument {
+        id,
+        channel_id: channel_id.to_string(),
+        channel: channel.to_string(),
+        participants: participants.into_iter().map(str::to_string).collect(),
+        start: slack_ts_to_iso8601(&first.ts),
+        end: slack_ts_to_iso8601(&last.ts),
+        messages: lines.len(),
+        text: lines.iter().map(|l| l.text.as_str()).collect::<Vec<_>>().join("\n"),
+    })
+}
+
+/// Cut `conversations` into documents
+pub fn build_corpus(conversations: &[Value], options: &CorpusOptions) -> Vec<CorpusDocument> {
+    let names = if options.anonymize {
+        pseudonyms(conversations)
+    } else {
+        options.user_names.clone()
+    };
+    let channel_names: HashMap<String, String> = conversations
+        .iter()
+        .map(|c| (text(c, "channel_id").to_string(), text(c, "channel_name").to_string()))
+        .collect();
+    let render = |message: &Value, reply: bool| -> Option<Line> {
+        let body = slack_text_to_markdown(text(message, "text"), &names, &channel_names);
+        if body.trim().is_empty() {
+            return None;
+        }
+        let user = message.get("user").and_then(|v| v.as_str());
+        let author = match user.and_then(|id| names.get(id)) {
+            Some(name) => name.clone(),
+            None if options.anonymize => "unknown".to_string(),
+            None => user.or_else(|| message.get("username").and_then(|v| v.as_str())).unwrap_or("unknown").to_string(),
+        };
+        let indent = if reply { "  " } else { "" };
+        let text = format!("{}{}: {}", indent, author, body.trim()).replace('\n', &format!("\n{}  ", indent));
+        Some(Line {
+            ts: message.get("ts").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            author,
+            text,
+        })
+    };
+
+    let mut documents = Vec::new();
+    for conversation in conversations {
+        let (channel_id, channel) = (text(conversation, "channel_id"), text(conversation, "channel_name"));
+        let render_thread =
+            |thread: Vec<(&Value, bool)>| thread.into_iter().filter_map(|(m, reply)| render(m, reply)).collect::<Vec<_>>();
+        let groups: Vec<Vec<Line>> = match options.messages_per_chunk {
+            None => threads(conversation).into_iter().map(render_thread).collect(),
+            Some(_) => vec![threads(conversation).into_iter().flat_map(render_thread).collect()],
+        };
+        for group in groups {
+            let thread_ts = group.first().map(|l| l.ts.clone()).unwrap_or_default();
+            let chunks = chunk_lines(group, options.messages_per_chunk, options.max_chars);
+            for (part, chunk) in chunks.iter().enumerate() {
+                let id = match (options.messages_per_chunk, part) {
+                    (None, 0) => format!("{}-{}", channel_id, thread_ts),
+                    (None, part) => format!("{}-{}-{}", channel_id, thread_ts, part),
+                    (Some(_), _) => format!("{}-{}", channel_id, chunk.first().map_or("", |l| l.ts.as_str())),
+                };
+                documents.extend(document(id, channel_id, channel, chunk));
+            }
+        }
+    }
+    documents
+}
+
+/// Write `documents` to `output` as JSON lines, or as text files in the
+/// `output` directory; returns the paths written
+pub fn write_corpus(documents: &[CorpusDocument], format: CorpusFormat, output: &Path) -> Result<Vec<PathBuf>> {
+    let write = |path: &Path, content: String| {
+        std::fs::write(path, content).map_err(|e| AppError::WriteFile {
+            path: path.display().to_string(),
+            source: e,
+        })
+    };
+    match format {
+        CorpusFormat::Jsonl => {
+            let mut jsonl = String::new();
+            for document in documents {
+                jsonl.push_str(&serde_json::to_string(document).map_err(|e| AppError::JsonSerialize(e.to_string()))?);
+                jsonl.push('\n');
+            }
+            write(output, jsonl)?;
+            Ok(vec![output.to_path_buf()])
+        }
+        CorpusFormat::Text => {
+            std::fs::create_dir_all(output).map_err(|e| AppError::WriteFile {
+                path: output.display().to_string(),
+                source: e,
+            })?;
+            let mut written = Vec::with_capacity(documents.len());
+            for document in documents {
+                let path = output.join(format!("{}.txt", file_stem(&document.id)));
+                write(&path, document.to_text())?;
+                written.push(path);
+            }
+            Ok(written)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn export() -> Vec<Value> {
+        vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "1705312900.000100", "user": "U2", "text": "second thread"},
+                {"ts": "1705312800.000100", "user": "U1", "text": "deploy?\nasking <@U3>",
+                 "thread_replies": [
+                     {"ts": "1705312860.000100", "user": "U2", "text": "done"},
+                     {"ts": "1705312870.000100", "user": "U1", "text": ""}
+                 ]}
+            ]
+        })]
+    }
+
+    fn options() -> CorpusOptions {
+        CorpusOptions {
+            user_names: HashMap::from([
+                ("U1".to_string(), "ana".to_string()),
+                ("U2".to_string(), "bob".to_string()),
+                ("U3".to_string(), "cy".to_string()),
+            ]),
+            ..CorpusOptions::default()
+        }
+    }
+
+    #[test]
+    fn test_corpus_by_thread() {
+        let documents = build_corpus(&export(), &options());
+        assert_eq!(documents.len(), 2);
+        let thread = &documents[0];
+        assert_eq!(thread.id, "C1-1705312800.000100");
+        assert_eq!(thread.participants, ["ana", "bob"]);
+        assert_eq!(thread.messages, 2);
+        assert_eq!(thread.start, "2024-01-15T10:00:00+00:00");
+        assert_eq!(thread.end, "2024-01-15T10:01:00+00:00");
+        assert_eq!(thread.text, "ana: deploy?\n  asking @cy\n  bob: done");
+        assert!(thread.to_text().starts_with("channel: #general\nparticipants: ana, bob\n"));
+    }
+
+    #[test]
+    fn test_corpus_by_messages() {
+        let options = CorpusOptions {
+            messages_per_chunk: Some(2),
+            ..options()
+        };
+        let documents = build_corpus(&export(), &options);
+        let counts: Vec<usize> = documents.iter().map(|d| d.messages).collect();
+        assert_eq!(counts, [2, 1]);
+        assert_eq!(documents[1].text, "bob: second thread");
+    }
+
+    #[test]
+    fn test_corpus_max_chars() {
+        let options = CorpusOptions {
+            max_chars: Some(30),
+            ..options()
+        };
+        let documents = build_corpus(&export(), &options);
+        let ids: Vec<&str> = documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, ["C1-1705312800.000100", "C1-1705312800.000100-1", "C1-1705312900.000100"]);
+    }
+
+    #[test]
+    fn test_corpus_anonymized() {
+        let options = CorpusOptions {
+            anonymize: true,
+            ..options()
+        };
+        let documents = build_corpus(&export(), &options);
+        assert_eq!(documents[0].participants, ["user1", "user3"]);
+        assert_eq!(documents[0].text, "user1: deploy?\n  asking @user2\n  user3: done");
+        assert!(!documents.iter().any(|d| d.text.contains("ana") || d.text.contains("bob")));
+    }
+
+    #[test]
+    fn test_write_corpus() {
+        let dir = tempfile::tempdir().unwrap();
+        let documents = build_corpus(&export(), &options());
+
+        let jsonl = dir.path().join("corpus.jsonl");
+        write_corpus(&documents, CorpusFormat::Jsonl, &jsonl).unwrap();
+        let content = std::fs::read_to_string(&jsonl).unwrap();
+        let first: Value = serde_json::from_str(content.lines().next().unwrap()).unwrap();
+        assert_eq!(first["channel"], "general");
+        assert_eq!(content.lines().count(), 2);
+
+        let written = write_corpus(&documents, CorpusFormat::Text, &dir.path().join("corpus")).unwrap();
+        assert_eq!(written[0].file_name().unwrap(), "C1-1705312800.000100.txt");
+    }
+}
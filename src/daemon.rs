@@ -0,0 +1,530 @@
+//! Recurring archive runs on a cron-like schedule (`archive-daemon`)
+//!
+//! Each run refreshes the most recent ISO weeks of the archive, appends to a
+//! size-rotated log file and can post a summary to a Slack incoming webhook.
+
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, Timelike};
+use serde::Deserialize;
+
+use crate::slack::{archive_weeks, ArchiveRangeResult};
+use crate::{AppError, ProgressEvent, Result, SlackApiCallbacks};
+
+/// Minutes searched for the next run before a schedule is deemed impossible
+/// (e.g. February 30th); leap days recur within this window
+const SCHEDULE_HORIZON_MINUTES: i64 = 5 * 366 * 24 * 60;
+
+/// How often a run's API call and rate limit events are written to the log
+const EVENT_LOG_INTERVAL: Duration = Duration::from_millis(200);
+
+/// A cron expression: `minute hour day-of-month month day-of-week`, each a
+/// `*`, a value, a range `a-b`, a step `*/n` or `a-b/n`, or a comma list of
+/// those. `@hourly`, `@daily` and `@weekly` are accepted as shorthands.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Like cron, when both day fields are restricted either may match
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Bitmask of the values `field` selects between `min` and `max`
+fn parse_field(field: &str, min: u32, max: u32) -> Option<u64> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().ok().filter(|s| *s > 0)?),
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (start.parse().ok()?, end.parse().ok()?)
+        } else {
+            let value = range.parse().ok()?;
+            // "5/15" means every 15 starting at 5
+            (value, if step > 1 { max } else { value })
+        };
+        if start < min || end > max || start > end {
+            return None;
+        }
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Some(mask)
+}
+
+impl std::str::FromStr for Schedule {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let expression = match s.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 1",
+            other => other,
+        };
+        let invalid = || {
+            AppError::InvalidConfig(format!(
+                "invalid schedule {:?} (expected minute hour day month weekday, e.g. \"0 * * * *\")",
+                s
+            ))
+        };
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            return Err(invalid());
+        };
+        let weekdays = parse_field(weekday, 0, 7).ok_or_else(invalid)?;
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).ok_or_else(invalid)?,
+            hours: parse_field(hour, 0, 23).ok_or_else(invalid)?,
+            days: parse_field(day, 1, 31).ok_or_else(invalid)?,
+            months: parse_field(month, 1, 12).ok_or_else(invalid)?,
+            // 7 is Sunday too
+            weekdays: (weekdays | (weekdays >> 7)) & 0x7f,
+            days_restricted: !day.starts_with('*'),
+            weekdays_restricted: !weekday.starts_with('*'),
+        })
+    }
+}
+
+impl Schedule {
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            (true, false) => day,
+            (false, true) => weekday,
+            (false, false) => true,
+        }
+    }
+
+    /// First minute strictly after `after` the schedule matches, or `None`
+    /// if it never does
+    pub fn next_after(&self, after: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = after.with_second(0)?.with_nanosecond(0)? + chrono::Duration::minutes(1);
+        let limit = time + chrono::Duration::minutes(SCHEDULE_HORIZON_MINUTES);
+        while time < limit {
+            let date = time.date();
+            if self.months & (1 << date.month()) == 0 {
+                let (year, month) = match date.month() {
+                    12 => (date.year() + 1, 1),
+                    month => (date.year(), month + 1),
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.day_matches(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + chrono::Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += chrono::Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+/// Log file rotated by size: `archive.log` is moved to `archive.log.1`,
+/// `archive.log.1` to `archive.log.2` and so on, keeping `keep` old files
+#[derive(Debug, Clone)]
+pub struct RotatingLog {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl RotatingLog {
+    pub fn new(path: impl Into<PathBuf>, max_bytes: u64, keep: usize) -> Self {
+        Self {
+            path: path.into(),
+            max_bytes,
+            keep,
+        }
+    }
+
+    fn rotated(&self, index: usize) -> PathBuf {
+        let mut name = self.path.as_os_str().to_owned();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&self) -> std::io::Result<()> {
+        if self.keep == 0 {
+            return fs::remove_file(&self.path);
+        }
+        for index in (1..self.keep).rev() {
+            let from = self.rotated(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated(1))
+    }
+
+    /// Append a timestamped line, rotating first if the file is full
+    pub fn write(&self, line: &str) -> Result<()> {
+        let write_error = |e| AppError::WriteFile {
+            path: self.path.display().to_string(),
+            source: e,
+        };
+        let size = fs::metadata(&self.path).map(|m| m.len()).unwrap_or(0);
+        if size > 0 && size >= self.max_bytes {
+            self.rotate().map_err(write_error)?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(write_error)?;
+        writeln!(file, "{} {}", Local::now().format("%Y-%m-%d %H:%M:%S"), line).map_err(write_error)
+    }
+}
+
+/// `[log]` section of the daemon config
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DaemonLogConfig {
+    pub path: String,
+    /// Size at which the log is rotated
+    #[serde(default = "default_log_max_size_mb", rename = "max-size-mb")]
+    pub max_size_mb: u64,
+    /// Rotated files kept besides the current one
+    #[serde(default = "default_log_keep")]
+    pub keep: usize,
+}
+
+fn default_log_max_size_mb() -> u64 {
+    10
+}
+
+fn default_log_keep() -> usize {
+    5
+}
+
+/// Configuration file of `archive-daemon`
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct DaemonConfig {
+    /// Cron expression in local time
+    pub schedule: String,
+    /// Archive directory of year=*/week=* partitions
+    #[serde(default = "default_daemon_output")]
+    pub output: String,
+    /// Weeks refreshed per run counting back from the current one; 2 also
+    /// picks up late thread replies in last week's threads
+    #[serde(default = "default_daemon_weeks")]
+    pub weeks: u32,
+    /// Slack incoming webhook receiving a summary of each run
+    #[serde(default, rename = "webhook-url")]
+    pub webhook_url: Option<String>,
+    /// Only post failed runs, and runs that left channels out, to the webhook
+    #[serde(default, rename = "notify-failures-only")]
+    pub notify_failures_only: bool,
+    #[serde(default)]
+    pub log: Option<DaemonLogConfig>,
+}
+
+fn default_daemon_output() -> String {
+    "conversations".to_string()
+}
+
+fn default_daemon_weeks() -> u32 {
+    1
+}
+
+impl DaemonConfig {
+    /// Load and validate a daemon config file
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(|e| AppError::ReadFile {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        let config: Self = toml::from_str(&content).map_err(|e| AppError::TomlParse(e.to_string()))?;
+        config.schedule()?;
+        if config.weeks == 0 {
+            return Err(AppError::InvalidConfig("weeks must be at least 1".to_string()));
+        }
+        Ok(config)
+    }
+
+    pub fn schedule(&self) -> Result<Schedule> {
+        self.schedule.parse()
+    }
+
+    fn log_file(&self) -> Option<RotatingLog> {
+        self.log.as_ref().map(|log| {
+            RotatingLog::new(&log.path, log.max_size_mb.saturating_mul(1024 * 1024), log.keep)
+        })
+    }
+}
+
+/// The `count` ISO weeks ending with the one containing `today`, oldest first
+pub fn recent_weeks(today: NaiveDate, count: u32) -> Vec<(i32, u32)> {
+    (0..count)
+        .rev()
+        .filter_map(|back| today.checked_sub_days(chrono::Days::new(7 * u64::from(back))))
+        .map(|day| (day.iso_week().year(), day.iso_week().week()))
+        .collect()
+}
+
+/// One-line summary of a run, as logged and posted to the webhook
+pub fn run_summary(
+    weeks: &[(i32, u32)],
+    output: &str,
+    outcome: &Result<ArchiveRangeResult>,
+    elapsed: Duration,
+) -> String {
+    let label = |(year, week): &(i32, u32)| format!("{}-W{:02}", year, week);
+    let range = match (weeks.first(), weeks.last()) {
+        (Some(first), Some(last)) if first != last => format!("{}..{}", label(first), label(last)),
+        (Some(first), _) => label(first),
+        _ => String::from("no weeks"),
+    };
+    match outcome {
+        Ok(result) if result.weeks_incomplete > 0 => format!(
+            "Archived {} to {}: {} messages in {} weeks, {} of them without {} failed channels ({:.1}s)",
+            range,
+            output,
+            result.total_messages,
+            result.weeks_processed,
+            result.weeks_incomplete,
+            result.failed_channels.len(),
+            elapsed.as_secs_f64()
+        ),
+        Ok(result) => format!(
+            "Archived {} to {}: {} messages in {} weeks ({:.1}s)",
+            range,
+            output,
+            result.total_messages,
+            result.weeks_processed,
+            elapsed.as_secs_f64()
+        ),
+        Err(e) => format!("Archive run for {} to {} failed: {}", range, output, e),
+    }
+}
+
+/// Post `text` to a Slack incoming webhook
+pub async fn post_webhook(url: &str, text: &str) -> Result<()> {
+    let response = reqwest::Client::new()
+        .post(url)
+        .json(&serde_json::json!({ "text": text }))
+        .send()
+        .await
+        .map_err(|e| AppError::Webhook(e.to_string()))?;
+    if !response.status().is_success() {
+        return Err(AppError::Webhook(format!("HTTP {}", response.status())));
+    }
+    Ok(())
+}
+
+/// Refresh the configured weeks once, log the outcome and notify the
+/// webhook. Returns the run's result so `--once` can report failure.
+pub async fn run_archive_once(
+    token: &str,
+    config: &DaemonConfig,
+    on_log: &dyn Fn(&str),
+) -> Result<ArchiveRangeResult> {
+    let log_file = config.log_file();
+    let log = |line: &str| {
+        on_log(line);
+        if let Some(log_file) = &log_file
+            && let Err(e) = log_file.write(line)
+        {
+            on_log(&format!("Failed to write log: {}", e));
+        }
+    };
+    let log_events = |received: &mpsc::Receiver<ProgressEvent>| {
+        for event in received.try_iter() {
+            match event {
+                ProgressEvent::Log(line) => log(&line),
+                ProgressEvent::RateLimited {
+                    wait_secs,
+                    attempt,
+                    max_attempts,
+                } => log(&format!(
+                    "Rate limited, waiting {}s (attempt {}/{})",
+                    wait_secs, attempt, max_attempts
+                )),
+                _ => {}
+            }
+        }
+    };
+
+    let weeks = recent_weeks(Local::now().date_naive(), config.weeks);
+    let started = Instant::now();
+    let (events, received) = mpsc::channel();
+    let archive = archive_weeks(
+        token,
+        &weeks,
+        Path::new(&config.output),
+        false,
+        SlackApiCallbacks::new().with_events(&events),
+    );
+    tokio::pin!(archive);
+    let mut ticks = tokio::time::interval(EVENT_LOG_INTERVAL);
+    let outcome = loop {
+        tokio::select! {
+            outcome = &mut archive => break outcome,
+            _ = ticks.tick() => log_events(&received),
+        }
+    };
+    log_events(&received);
+
+    let summary = run_summary(&weeks, &config.output, &outcome, started.elapsed());
+    log(&summary);
+    if let Some(url) = &config.webhook_url
+        && (!outcome.as_ref().is_ok_and(|r| r.weeks_incomplete == 0) || !config.notify_failures_only)
+        && let Err(e) = post_webhook(url, &summary).await
+    {
+        log(&format!("Failed to post summary: {}", e));
+    }
+    outcome
+}
+
+/// Run the archive on `config.schedule` until Ctrl+C; a failed run is logged
+/// and the next one still happens
+pub async fn run_archive_daemon(token: &str, config: &DaemonConfig, on_log: &dyn Fn(&str)) -> Result<()> {
+    let schedule = config.schedule()?;
+    loop {
+        let now = Local::now().naive_local();
+        let next = schedule.next_after(now).ok_or_else(|| {
+            AppError::InvalidConfig(format!("schedule {:?} never matches", config.schedule))
+        })?;
+        on_log(&format!("Next run at {}", next.format("%Y-%m-%d %H:%M")));
+        let wait = (next - now).to_std().unwrap_or_default();
+        tokio::select! {
+            _ = tokio::time::sleep(wait) => {}
+            _ = tokio::signal::ctrl_c() => return Ok(()),
+        }
+        tokio::select! {
+            // Errors are already logged and posted
+            _ = run_archive_once(token, config, on_log) => {}
+            _ = tokio::signal::ctrl_c() => {
+                on_log("Stopped during a run; weeks already written are kept");
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export_report::ChannelReport;
+
+    fn at(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn next(schedule: &str, after: &str) -> Option<NaiveDateTime> {
+        schedule.parse::<Schedule>().unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        assert_eq!(next("0 * * * *", "2024-01-15 10:00"), Some(at("2024-01-15 11:00")));
+        assert_eq!(next("*/15 * * * *", "2024-01-15 10:07"), Some(at("2024-01-15 10:15")));
+        assert_eq!(next("30 2 * * *", "2024-01-15 10:00"), Some(at("2024-01-16 02:30")));
+        assert_eq!(next("@daily", "2024-12-31 23:59"), Some(at("2025-01-01 00:00")));
+        // 2024-01-15 is a Monday; Sunday may be written as 0 or 7
+        assert_eq!(next("0 6 * * 7", "2024-01-15 10:00"), Some(at("2024-01-21 06:00")));
+        assert_eq!(next("0 6 * * 1-5", "2024-01-19 10:00"), Some(at("2024-01-22 06:00")));
+        // Either day field matches when both are restricted
+        assert_eq!(next("0 0 1 * 3", "2024-01-15 10:00"), Some(at("2024-01-17 00:00")));
+        assert_eq!(next("0 0 29 2 *", "2024-03-01 00:00"), Some(at("2028-02-29 00:00")));
+        assert_eq!(next("0 0 30 2 *", "2024-01-01 00:00"), None);
+    }
+
+    #[test]
+    fn test_schedule_invalid() {
+        for invalid in ["", "* * * *", "60 * * * *", "* * 0 * *", "*/0 * * * *", "5-1 * * * *", "a * * * *"] {
+            assert!(matches!(invalid.parse::<Schedule>(), Err(AppError::InvalidConfig(_))), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_recent_weeks() {
+        let monday = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        assert_eq!(recent_weeks(monday, 1), vec![(2025, 2)]);
+        assert_eq!(recent_weeks(monday, 3), vec![(2024, 52), (2025, 1), (2025, 2)]);
+    }
+
+    #[test]
+    fn test_rotating_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("daemon.log");
+        let log = RotatingLog::new(&path, 10, 2);
+
+        for line in ["first run", "second run", "third run", "fourth run"] {
+            log.write(line).unwrap();
+        }
+
+        assert!(fs::read_to_string(&path).unwrap().contains("fourth run"));
+        assert!(fs::read_to_string(dir.path().join("daemon.log.1")).unwrap().contains("third run"));
+        assert!(fs::read_to_string(dir.path().join("daemon.log.2")).unwrap().contains("second run"));
+        assert!(!dir.path().join("daemon.log.3").exists());
+    }
+
+    #[test]
+    fn test_daemon_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("archive.toml");
+        fs::write(
+            &path,
+            "schedule = \"0 * * * *\"\nweeks = 2\n[log]\npath = \"daemon.log\"\n",
+        )
+        .unwrap();
+
+        let config = DaemonConfig::from_file(&path).unwrap();
+        assert_eq!(config.output, "conversations");
+        assert_eq!(config.weeks, 2);
+        assert_eq!(config.log.as_ref().map(|l| l.max_size_mb), Some(10));
+        assert!(config.webhook_url.is_none());
+
+        fs::write(&path, "schedule = \"every hour\"\n").unwrap();
+        assert!(matches!(DaemonConfig::from_file(&path), Err(AppError::InvalidConfig(_))));
+    }
+
+    #[test]
+    fn test_run_summary() {
+        let weeks = [(2024, 2), (2024, 3)];
+        let ok = Ok(ArchiveRangeResult {
+            total_messages: 42,
+            weeks_processed: 2,
+            weeks_skipped: 0,
+            weeks_incomplete: 0,
+            failed_channels: Vec::new(),
+        });
+        assert_eq!(
+            run_summary(&weeks, "conversations", &ok, Duration::from_millis(1500)),
+            "Archived 2024-W02..2024-W03 to conversations: 42 messages in 2 weeks (1.5s)"
+        );
+        let partial = Ok(ArchiveRangeResult {
+            total_messages: 40,
+            weeks_processed: 2,
+            weeks_skipped: 0,
+            weeks_incomplete: 1,
+            failed_channels: vec![ChannelReport::failed("C2", "random", "not_in_channel".to_string())],
+        });
+        assert_eq!(
+            run_summary(&weeks, "conversations", &partial, Duration::from_millis(1500)),
+            "Archived 2024-W02..2024-W03 to conversations: 40 messages in 2 weeks, 1 of them without 1 failed channels (1.5s)"
+        );
+        let failed = Err(AppError::MissingToken);
+        assert_eq!(
+            run_summary(&weeks[..1], "conversations", &failed, Duration::ZERO),
+            "Archive run for 2024-W02 to conversations failed: SLACK_TOKEN environment variable not set"
+        );
+    }
+}
@@ -0,0 +1,265 @@
+//! Local SQL over the parquet archive with DataFusion, no server or DuckDB
+//! needed
+
+use std::fmt::Write as FmtWrite;
+use std::path::Path;
+
+use arrow::datatypes::DataType;
+use arrow::record_batch::RecordBatch;
+use arrow::util::display::{ArrayFormatter, FormatOptions};
+use datafusion::execution::context::SQLOptions;
+use datafusion::prelude::{ParquetReadOptions, SessionContext};
+use thiserror::Error;
+
+use crate::parquet::PartitionBy;
+
+/// Errors for DataFusion queries
+#[derive(Error, Debug)]
+pub enum SqlError {
+    #[error("query failed: {0}")]
+    QueryFailed(#[from] datafusion::error::DataFusionError),
+
+    #[error("failed to format results: {0}")]
+    FormatFailed(#[from] arrow::error::ArrowError),
+
+    #[error("no tables in {0} (expected users.parquet, channels.parquet or conversations/)")]
+    NoTables(String),
+
+    #[error("invalid output format: {0} (expected table, csv or json)")]
+    InvalidFormat(String),
+}
+
+/// Result type for DataFusion queries
+pub type Result<T> = std::result::Result<T, SqlError>;
+
+/// How `sql` prints its results
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SqlOutputFormat {
+    /// Aligned columns for the terminal
+    #[default]
+    Table,
+    Csv,
+    /// A JSON array of row objects
+    Json,
+}
+
+impl std::str::FromStr for SqlOutputFormat {
+    type Err = SqlError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(SqlOutputFormat::Table),
+            "csv" => Ok(SqlOutputFormat::Csv),
+            "json" => Ok(SqlOutputFormat::Json),
+            _ => Err(SqlError::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+/// Register the tables of the archive in `base_path` that exist: `users`,
+/// `channels` and `messages` (the `threads.parquet` of every
+/// `conversations/year=*/week=*` partition, with `year` and `week` columns,
+/// and `channel` when partitioned by channel). Returns the registered table
+/// names.
+pub async fn register_archive(ctx: &SessionContext, base_path: &Path) -> Result<Vec<&'static str>> {
+    let mut tables = Vec::new();
+    for (name, file) in [("users", "users.parquet"), ("channels", "channels.parquet")] {
+        let path = base_path.join(file);
+        if path.is_file() {
+            ctx.register_parquet(name, path.to_string_lossy(), ParquetReadOptions::default())
+                .await?;
+            tables.push(name);
+        }
+    }
+
+    let conversations = base_path.join("conversations");
+    if conversations.is_dir() {
+        // A trailing slash makes DataFusion list the directory as a table
+        let path = format!("{}/", conversations.to_string_lossy().trim_end_matches('/'));
+        let mut partition_cols = vec![
+            ("year".to_string(), DataType::Int32),
+            ("week".to_string(), DataType::Int32),
+        ];
+        if PartitionBy::of_dir(&conversations) == PartitionBy::Channel {
+            partition_cols.push(("channel".to_string(), DataType::Utf8));
+        }
+        // Only the threads files: Delta tables keep `part-*.parquet` copies
+        // of them in the same folders
+        let options = ParquetReadOptions::default()
+            .file_extension("threads.parquet")
+            .table_partition_cols(partition_cols);
+        ctx.register_parquet("messages", path, options).await?;
+        tables.push("messages");
+    }
+
+    if tables.is_empty() {
+        return Err(SqlError::NoTables(base_path.display().to_string()));
+    }
+    Ok(tables)
+}
+
+/// Run `sql` against the archive in `base_path`. Statements that create
+/// tables or write files are rejected.
+pub async fn execute_sql(base_path: &Path, sql: &str) -> Result<Vec<RecordBatch>> {
+    let ctx = SessionContext::new();
+    register_archive(&ctx, base_path).await?;
+
+    let options = SQLOptions::new()
+        .with_allow_ddl(false)
+        .with_allow_dml(false)
+        .with_allow_statements(false);
+    let df = ctx.sql_with_options(sql, options).await?;
+    Ok(df.collect().await?)
+}
+
+/// Aligned text table of `batches`, like the DuckDB CLI prints
+fn format_table(batches: &[RecordBatch]) -> Result<String> {
+    let Some(schema) = batches.first().map(|b| b.schema()) else {
+        return Ok(String::from("(no rows)\n"));
+    };
+    let columns: Vec<String> = schema.fields().iter().map(|f| f.name().clone()).collect();
+
+    let options = FormatOptions::default().with_null("NULL");
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    for batch in batches {
+        let formatters = batch
+            .columns()
+            .iter()
+            .map(|column| ArrayFormatter::try_new(column.as_ref(), &options))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+        for row in 0..batch.num_rows() {
+            rows.push(formatters.iter().map(|f| f.value(row).to_string()).collect());
+        }
+    }
+
+    let mut widths: Vec<usize> = columns.iter().map(|c| c.len()).collect();
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: &[String]| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    };
+
+    let mut output = String::new();
+    let _ = writeln!(output, "{}", line(&columns));
+    let separator: Vec<String> = widths.iter().map(|w| "-".repeat(*w)).collect();
+    let _ = writeln!(output, "{}", separator.join("-+-"));
+    for row in &rows {
+        let _ = writeln!(output, "{}", line(row));
+    }
+    let _ = writeln!(output, "\n({} rows)", rows.len());
+    Ok(output)
+}
+
+/// Render query results in `format`
+pub fn format_batches(batches: &[RecordBatch], format: SqlOutputFormat) -> Result<String> {
+    let mut buf = Vec::new();
+    match format {
+        SqlOutputFormat::Table => return format_table(batches),
+        SqlOutputFormat::Csv => {
+            let mut writer = arrow::csv::WriterBuilder::new().with_header(true).build(&mut buf);
+            for batch in batches {
+                writer.write(batch)?;
+            }
+        }
+        SqlOutputFormat::Json => {
+            let mut writer = arrow::json::ArrayWriter::new(&mut buf);
+            writer.write_batches(&batches.iter().collect::<Vec<_>>())?;
+            writer.finish()?;
+            buf.push(b'\n');
+        }
+    }
+    Ok(String::from_utf8_lossy(&buf).into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::{Int64Array, StringArray};
+    use arrow::datatypes::{Field, Schema};
+    use std::sync::Arc;
+
+    fn batch() -> RecordBatch {
+        let schema = Schema::new(vec![
+            Field::new("channel_name", DataType::Utf8, true),
+            Field::new("messages", DataType::Int64, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(StringArray::from(vec![Some("general"), None])),
+                Arc::new(Int64Array::from(vec![12, 3])),
+            ],
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_format_batches() {
+        let batches = [batch()];
+        assert_eq!(
+            format_batches(&batches, SqlOutputFormat::Table).unwrap(),
+            "channel_name | messages\n-------------+---------\ngeneral      | 12      \nNULL         | 3       \n\n(2 rows)\n"
+        );
+        assert_eq!(
+            format_batches(&batches, SqlOutputFormat::Csv).unwrap(),
+            "channel_name,messages\ngeneral,12\n,3\n"
+        );
+        assert_eq!(
+            format_batches(&batches, SqlOutputFormat::Json).unwrap(),
+            "[{\"channel_name\":\"general\",\"messages\":12},{\"messages\":3}]\n"
+        );
+        assert_eq!(format_batches(&[], SqlOutputFormat::Json).unwrap(), "[]\n");
+        assert_eq!(format_batches(&[], SqlOutputFormat::Table).unwrap(), "(no rows)\n");
+    }
+
+    #[test]
+    fn test_sql_output_format_from_str() {
+        assert_eq!("CSV".parse::<SqlOutputFormat>().unwrap(), SqlOutputFormat::Csv);
+        assert!(matches!("xml".parse::<SqlOutputFormat>(), Err(SqlError::InvalidFormat(_))));
+    }
+
+    #[tokio::test]
+    async fn test_execute_sql_on_delta_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let key = "conversations/year=2024/week=01/threads.parquet";
+        let path = dir.path().join(key);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let batch = batch();
+        let mut writer =
+            parquet::arrow::ArrowWriter::try_new(std::fs::File::create(&path).unwrap(), batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+
+        let storage = crate::storage::LocalStorage::new(dir.path());
+        crate::delta::DeltaTable::new(&storage, "conversations")
+            .commit(&[key.to_string()], "WRITE")
+            .unwrap();
+
+        // The part copy made by the commit is not read twice
+        let batches = execute_sql(dir.path(), "SELECT COUNT(*) AS n, MIN(week) AS week FROM messages")
+            .await
+            .unwrap();
+        assert_eq!(
+            format_batches(&batches, SqlOutputFormat::Csv).unwrap(),
+            "n,week\n2,1\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_register_archive_without_tables() {
+        let dir = tempfile::tempdir().unwrap();
+        let ctx = SessionContext::new();
+        assert!(matches!(
+            register_archive(&ctx, dir.path()).await,
+            Err(SqlError::NoTables(_))
+        ));
+    }
+}
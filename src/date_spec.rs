@@ -0,0 +1,214 @@
+//! Dates and ISO weeks given on the command line: besides `YYYY-MM-DD`,
+//! relative dates (`7d`, `2w`, `yesterday`) and periods (`last-month`,
+//! `2024-Q2`), resolved against today so recurring exports don't need the
+//! dates worked out by hand
+
+use chrono::{Datelike, Days, Duration, NaiveDate};
+
+use crate::error::{AppError, Result};
+use crate::week_to_date_range;
+
+/// What `--from` and `--to` accept, for error messages and help
+pub const DATE_SPEC_FORMATS: &str = "YYYY-MM-DD, Nd, Nw, today, yesterday, this-week, last-week, \
+     this-month, last-month, YYYY-MM, YYYY-Www or YYYY-Qn";
+
+/// Resolve a date argument to its first day, and its last day when it names
+/// a period. Plain and relative dates have no last day, so ranges starting on
+/// them end today.
+pub fn resolve_date_spec(spec: &str, today: NaiveDate) -> Result<(NaiveDate, Option<NaiveDate>)> {
+    let spec = spec.trim();
+    let invalid = || AppError::InvalidDate(format!("{} (expected {})", spec, DATE_SPEC_FORMATS));
+    let period = |(from, to): (NaiveDate, NaiveDate)| Ok((from, Some(to)));
+
+    match spec {
+        "today" => return Ok((today, None)),
+        "yesterday" => return Ok((today - Duration::days(1), None)),
+        "this-week" => return period(iso_week_of(today)?),
+        "last-week" => return period(iso_week_of(today - Duration::weeks(1))?),
+        "this-month" => return period(month(today.year(), today.month()).ok_or_else(invalid)?),
+        "last-month" => {
+            let first = today.with_day(1).ok_or_else(invalid)?;
+            let last_month = first - Duration::days(1);
+            return period(month(last_month.year(), last_month.month()).ok_or_else(invalid)?);
+        }
+        _ => {}
+    }
+
+    if let Ok(date) = NaiveDate::parse_from_str(spec, "%Y-%m-%d") {
+        return Ok((date, None));
+    }
+    // Counts reaching before the earliest date chrono supports are invalid
+    let days_ago = |days: u64| {
+        today
+            .checked_sub_days(Days::new(days))
+            .map(|date| (date, None))
+            .ok_or_else(|| AppError::InvalidDate(format!("{} is too far back", spec)))
+    };
+    if let Some(count) = spec.strip_suffix('d').and_then(|n| n.parse::<u32>().ok()) {
+        return days_ago(count.into());
+    }
+    if let Some(count) = spec.strip_suffix('w').and_then(|n| n.parse::<u32>().ok()) {
+        return days_ago(u64::from(count) * 7);
+    }
+
+    let (year, rest) = spec.split_once('-').ok_or_else(invalid)?;
+    let year: i32 = year.parse().map_err(|_| invalid())?;
+    if let Some(quarter) = rest.strip_prefix('Q').and_then(|q| q.parse::<u32>().ok()) {
+        if !(1..=4).contains(&quarter) {
+            return Err(invalid());
+        }
+        let (from, _) = month(year, quarter * 3 - 2).ok_or_else(invalid)?;
+        let (_, to) = month(year, quarter * 3).ok_or_else(invalid)?;
+        return period((from, to));
+    }
+    if let Some(week) = rest.strip_prefix('W').and_then(|w| w.parse::<u32>().ok()) {
+        return period(week_to_date_range(year, week)?);
+    }
+    let month_number: u32 = rest.parse().map_err(|_| invalid())?;
+    period(month(year, month_number).ok_or_else(invalid)?)
+}
+
+/// The dates of `--from` and `--to`: a missing `--to` is the end of the
+/// `--from` period, or today
+pub fn resolve_date_range(from: &str, to: Option<&str>, today: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+    let (from_date, period_end) = resolve_date_spec(from, today)?;
+    let to_date = match to {
+        Some(to) => {
+            let (start, end) = resolve_date_spec(to, today)?;
+            end.unwrap_or(start)
+        }
+        None => period_end.unwrap_or(today),
+    };
+    if to_date < from_date {
+        return Err(AppError::InvalidDate(format!("{} is after {}", from_date, to_date)));
+    }
+    Ok((from_date, to_date))
+}
+
+/// Resolve `--year` and `--week` of a week command: the week is a number,
+/// `current` or `last`, and defaults to the current week. `--year` must be
+/// the year of a `current` or `last` week.
+pub fn resolve_week(year: Option<i32>, week: Option<&str>, today: NaiveDate) -> Result<(i32, u32)> {
+    let iso = |date: NaiveDate| (date.iso_week().year(), date.iso_week().week());
+    // A relative week is in one year only; another --year was not asked for
+    let relative = |name: &str, (week_year, week): (i32, u32)| match year {
+        Some(year) if year != week_year => Err(AppError::InvalidDate(format!(
+            "--week {} is in {}, not {}",
+            name, week_year, year
+        ))),
+        _ => Ok((week_year, week)),
+    };
+    match week {
+        // --year alone keeps this week's number in that year
+        None => {
+            let (current_year, current_week) = iso(today);
+            Ok((year.unwrap_or(current_year), current_week))
+        }
+        Some("current") => relative("current", iso(today)),
+        Some("last") => relative("last", iso(today - Duration::weeks(1))),
+        Some(week) => {
+            let week: u32 = week.parse().map_err(|_| {
+                AppError::InvalidDate(format!("Invalid week: {} (expected 1-53, current or last)", week))
+            })?;
+            let year = year.unwrap_or_else(|| iso(today).0);
+            week_to_date_range(year, week)?;
+            Ok((year, week))
+        }
+    }
+}
+
+fn iso_week_of(date: NaiveDate) -> Result<(NaiveDate, NaiveDate)> {
+    let week = date.iso_week();
+    week_to_date_range(week.year(), week.week())
+}
+
+/// First and last day of a month
+fn month(year: i32, month: u32) -> Option<(NaiveDate, NaiveDate)> {
+    let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+    let next = if month == 12 {
+        NaiveDate::from_ymd_opt(year + 1, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    Some((first, next - Duration::days(1)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_resolve_date_spec() {
+        // A Wednesday in ISO week 2024-W11
+        let today = date("2024-03-13");
+        let cases = [
+            ("2024-01-05", "2024-01-05", None),
+            ("7d", "2024-03-06", None),
+            ("2w", "2024-02-28", None),
+            ("yesterday", "2024-03-12", None),
+            ("this-week", "2024-03-11", Some("2024-03-17")),
+            ("last-week", "2024-03-04", Some("2024-03-10")),
+            ("this-month", "2024-03-01", Some("2024-03-31")),
+            ("last-month", "2024-02-01", Some("2024-02-29")),
+            ("2024-Q2", "2024-04-01", Some("2024-06-30")),
+            ("2023-Q4", "2023-10-01", Some("2023-12-31")),
+            ("2024-02", "2024-02-01", Some("2024-02-29")),
+            ("2024-W01", "2024-01-01", Some("2024-01-07")),
+        ];
+        for (spec, from, to) in cases {
+            assert_eq!(
+                resolve_date_spec(spec, today).unwrap(),
+                (date(from), to.map(date)),
+                "{}",
+                spec
+            );
+        }
+        // last-month in January is December of the previous year
+        assert_eq!(
+            resolve_date_spec("last-month", date("2024-01-15")).unwrap(),
+            (date("2023-12-01"), Some(date("2023-12-31")))
+        );
+        for spec in ["2024-Q5", "2024-13", "d", "last-year", "2024-01-32", "100000000d", "4000000000w"] {
+            assert!(resolve_date_spec(spec, today).is_err(), "{}", spec);
+        }
+    }
+
+    #[test]
+    fn test_resolve_date_range() {
+        let today = date("2024-03-13");
+        assert_eq!(
+            resolve_date_range("7d", None, today).unwrap(),
+            (date("2024-03-06"), today)
+        );
+        assert_eq!(
+            resolve_date_range("2024-Q1", None, today).unwrap(),
+            (date("2024-01-01"), date("2024-03-31"))
+        );
+        // A period as --to ends on its last day
+        assert_eq!(
+            resolve_date_range("2024-01-10", Some("2024-02"), today).unwrap(),
+            (date("2024-01-10"), date("2024-02-29"))
+        );
+        assert!(resolve_date_range("this-month", Some("last-month"), today).is_err());
+    }
+
+    #[test]
+    fn test_resolve_week() {
+        // 2024-01-03 is in 2024-W01, so last week is 2023-W52
+        let today = date("2024-01-03");
+        assert_eq!(resolve_week(None, None, today).unwrap(), (2024, 1));
+        assert_eq!(resolve_week(None, Some("current"), today).unwrap(), (2024, 1));
+        assert_eq!(resolve_week(None, Some("last"), today).unwrap(), (2023, 52));
+        assert_eq!(resolve_week(Some(2022), Some("10"), today).unwrap(), (2022, 10));
+        assert!(resolve_week(Some(2024), Some("last"), today).is_err());
+        assert_eq!(resolve_week(Some(2024), Some("current"), today).unwrap(), (2024, 1));
+        assert!(resolve_week(Some(2023), Some("current"), today).is_err());
+        assert_eq!(resolve_week(Some(2023), None, today).unwrap(), (2023, 1));
+        assert!(resolve_week(None, Some("previous"), today).is_err());
+        assert!(resolve_week(Some(2024), Some("54"), today).is_err());
+    }
+}
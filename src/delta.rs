@@ -0,0 +1,397 @@
+//! Delta Lake table over the threads files of an archive. With
+//! `archive-range --table-format delta` every archived week is a commit in
+//! `_delta_log/`, so engines reading the table see a week's files all at
+//! once or not at all and can read it as of an earlier version. The table's
+//! files are immutable copies of the threads files, `part-<uuid>.parquet`
+//! next to each, so a week fetched again doesn't change what earlier
+//! versions read; `vacuum` deletes the copies no version needs anymore.
+//! Only what the archive needs is written: protocol 1/2, add and remove
+//! actions and no checkpoints, which readers don't require.
+
+use std::collections::BTreeSet;
+
+use arrow::datatypes::DataType;
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use crate::error::{AppError, Result};
+use crate::parquet::messages_schema;
+use crate::storage::{join_key, Storage};
+
+/// Directory of the commits, next to the `year=` partitions
+const LOG_DIR: &str = "_delta_log";
+
+/// How the threads files of an archive are exposed to readers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableFormat {
+    /// Bare `year=/week=` parquet files
+    #[default]
+    Files,
+    /// The same files plus a Delta Lake log with a commit per week
+    Delta,
+}
+
+impl std::fmt::Display for TableFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableFormat::Files => write!(f, "files"),
+            TableFormat::Delta => write!(f, "delta"),
+        }
+    }
+}
+
+impl std::str::FromStr for TableFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "files" => Ok(TableFormat::Files),
+            "delta" => Ok(TableFormat::Delta),
+            "iceberg" => Err(AppError::InvalidFormat("iceberg tables are not supported, use delta".to_string())),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected files or delta)", s))),
+        }
+    }
+}
+
+impl TableFormat {
+    /// Format to write the archive under `prefix` with: delta once it has a
+    /// log, otherwise `requested` (files by default). Bare files are adopted
+    /// by the first commit of a new log.
+    pub fn resolve(requested: Option<Self>, storage: &dyn Storage, prefix: &str) -> Result<Self> {
+        let is_table = DeltaTable::new(storage, prefix).version()?.is_some();
+        match (is_table, requested) {
+            (true, Some(TableFormat::Files)) => Err(AppError::InvalidFormat(format!(
+                "{} is a delta table, weeks written as bare files would be missing from it",
+                prefix
+            ))),
+            (true, _) => Ok(TableFormat::Delta),
+            (false, requested) => Ok(requested.unwrap_or_default()),
+        }
+    }
+}
+
+/// The Delta log of the archive under `prefix`
+pub struct DeltaTable<'a> {
+    storage: &'a dyn Storage,
+    prefix: String,
+}
+
+impl<'a> DeltaTable<'a> {
+    pub fn new(storage: &'a dyn Storage, prefix: &str) -> Self {
+        DeltaTable {
+            storage,
+            prefix: prefix.trim_end_matches('/').to_string(),
+        }
+    }
+
+    fn log_key(&self, version: u64) -> String {
+        join_key(&self.prefix, &format!("{}/{:020}.json", LOG_DIR, version))
+    }
+
+    /// Path of `key` relative to the table, as the log refers to files
+    fn relative<'k>(&self, key: &'k str) -> &'k str {
+        key.strip_prefix(&self.prefix).map(|path| path.trim_start_matches('/')).unwrap_or(key)
+    }
+
+    /// Latest committed version, `None` when the archive has no log
+    pub fn version(&self) -> Result<Option<u64>> {
+        let log_dir = join_key(&self.prefix, &format!("{}/", LOG_DIR));
+        Ok(self
+            .storage
+            .list(&log_dir)?
+            .iter()
+            .filter_map(|key| key.strip_prefix(&log_dir)?.strip_suffix(".json")?.parse().ok())
+            .max())
+    }
+
+    /// Actions of the commit of `version`
+    fn actions(&self, version: u64) -> Result<Vec<Value>> {
+        let key = self.log_key(version);
+        let commit = self
+            .storage
+            .get(&key)?
+            .ok_or_else(|| AppError::MissingInput(format!("{} (the delta log has a gap)", key)))?;
+        String::from_utf8_lossy(&commit)
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| serde_json::from_str(line).map_err(|e| AppError::JsonParse(format!("{}: {}", key, e))))
+            .collect()
+    }
+
+    /// Files of the latest version, relative to the table
+    pub fn files(&self) -> Result<BTreeSet<String>> {
+        match self.version()? {
+            Some(latest) => self.files_at(latest),
+            None => Ok(BTreeSet::new()),
+        }
+    }
+
+    /// Files of `version`, relative to the table; they stay readable until
+    /// `vacuum` deletes the ones later versions removed
+    pub fn files_at(&self, version: u64) -> Result<BTreeSet<String>> {
+        let mut files = BTreeSet::new();
+        for version in 0..=version {
+            for action in self.actions(version)? {
+                if let Some(path) = action_path(&action, "add") {
+                    files.insert(path.to_string());
+                } else if let Some(path) = action_path(&action, "remove") {
+                    files.remove(path);
+                }
+            }
+        }
+        Ok(files)
+    }
+
+    /// Commit the threads files at `keys`, just written, as the next
+    /// version. Each is copied to a new `part-<uuid>.parquet` next to it,
+    /// which replaces the part the table had for that file, so the files of
+    /// earlier versions are never overwritten. The first commit creates the
+    /// table with every threads file already under the prefix. Returns the
+    /// version, `None` when there was nothing to commit.
+    pub fn commit(&self, keys: &[String], operation: &str) -> Result<Option<u64>> {
+        let latest = self.version()?;
+        let mut keys: BTreeSet<String> = keys.iter().cloned().collect();
+        let mut actions = vec![commit_info(operation)];
+        let current = match latest {
+            Some(_) => self.files()?,
+            None => {
+                actions.push(json!({"protocol": {"minReaderVersion": 1, "minWriterVersion": 2}}));
+                actions.push(metadata());
+                let existing = self.storage.list(&self.prefix)?;
+                keys.extend(existing.into_iter().filter(|key| is_threads_file(self.relative(key))));
+                BTreeSet::new()
+            }
+        };
+        if latest.is_some() && keys.is_empty() {
+            return Ok(None);
+        }
+
+        let now = Utc::now().timestamp_millis();
+        for key in &keys {
+            let data = self
+                .storage
+                .get(key)?
+                .ok_or_else(|| AppError::MissingObject(key.clone()))?;
+            let dir = parent(self.relative(key));
+            let part = join_key(dir, &format!("part-{}.parquet", uuid::Uuid::new_v4()));
+            self.storage.put(&join_key(&self.prefix, &part), &data)?;
+
+            if let Some(previous) = current.iter().find(|path| parent(path) == dir) {
+                actions.push(json!({"remove": {"path": previous, "deletionTimestamp": now, "dataChange": true}}));
+            }
+            actions.push(json!({"add": {
+                "path": part,
+                "partitionValues": {},
+                "size": data.len(),
+                "modificationTime": now,
+                "dataChange": true
+            }}));
+        }
+
+        let version = latest.map_or(0, |latest| latest + 1);
+        let key = self.log_key(version);
+        // Commits are never replaced; a second writer finds its version taken
+        if self.storage.exists(&key)? {
+            return Err(AppError::CommitConflict(key));
+        }
+        let lines: Vec<String> = actions.iter().map(Value::to_string).collect();
+        self.storage.put(&key, format!("{}\n", lines.join("\n")).as_bytes())?;
+        Ok(Some(version))
+    }
+
+    /// `commit` when the archive already has a log, so writers rewriting its
+    /// threads files in place keep Delta readers current. Archives of bare
+    /// files are left as they are.
+    pub fn commit_existing(&self, keys: &[String], operation: &str) -> Result<Option<u64>> {
+        if self.version()?.is_none() {
+            return Ok(None);
+        }
+        self.commit(keys, operation)
+    }
+
+    /// Delete the part files removed from the table before `before`, after
+    /// which the versions that had them can no longer be read. Returns the
+    /// keys deleted.
+    pub fn vacuum(&self, before: DateTime<Utc>) -> Result<Vec<String>> {
+        let Some(latest) = self.version()? else {
+            return Ok(Vec::new());
+        };
+        let current = self.files_at(latest)?;
+        let cutoff = before.timestamp_millis();
+        let mut expired = BTreeSet::new();
+        for version in 0..=latest {
+            for action in self.actions(version)? {
+                let removed_at = action.get("remove").and_then(|remove| remove.get("deletionTimestamp")?.as_i64());
+                if let (Some(path), Some(removed_at)) = (action_path(&action, "remove"), removed_at)
+                    && removed_at < cutoff
+                    && !current.contains(path)
+                {
+                    expired.insert(join_key(&self.prefix, path));
+                }
+            }
+        }
+        for key in &expired {
+            self.storage.delete(key)?;
+        }
+        Ok(expired.into_iter().collect())
+    }
+}
+
+/// Path of the `kind` (add or remove) file action, `None` for other actions
+fn action_path<'v>(action: &'v Value, kind: &str) -> Option<&'v str> {
+    action.get(kind)?.get("path")?.as_str()
+}
+
+/// Directory of `path`, empty at the top of the table
+fn parent(path: &str) -> &str {
+    path.rsplit_once('/').map_or("", |(dir, _)| dir)
+}
+
+/// Whether `path`, relative to the archive, is one of its threads files
+fn is_threads_file(path: &str) -> bool {
+    path.starts_with("year=") && path.ends_with("/threads.parquet")
+}
+
+fn commit_info(operation: &str) -> Value {
+    json!({"commitInfo": {
+        "timestamp": Utc::now().timestamp_millis(),
+        "operation": operation,
+        "operationParameters": {"mode": "Append"},
+        "engineInfo": format!("slack-utils/{}", env!("CARGO_PKG_VERSION"))
+    }})
+}
+
+/// Table metadata with the schema of the threads files. The year and week
+/// of the partition directories are columns of the files too, so the table
+/// declares no partition columns.
+fn metadata() -> Value {
+    let fields: Vec<Value> = messages_schema()
+        .fields()
+        .iter()
+        .map(|field| {
+            json!({
+                "name": field.name(),
+                "type": delta_type(field.data_type()),
+                "nullable": field.is_nullable(),
+                "metadata": {}
+            })
+        })
+        .collect();
+    let schema = json!({"type": "struct", "fields": fields});
+    json!({"metaData": {
+        "id": uuid::Uuid::new_v4().to_string(),
+        "format": {"provider": "parquet", "options": {}},
+        "schemaString": schema.to_string(),
+        "partitionColumns": [],
+        "configuration": {},
+        "createdTime": Utc::now().timestamp_millis()
+    }})
+}
+
+fn delta_type(data_type: &DataType) -> &'static str {
+    match data_type {
+        DataType::Boolean => "boolean",
+        DataType::Int32 => "integer",
+        DataType::Int64 => "long",
+        // Utf8, the type of every other column
+        _ => "string",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+
+    fn actions(storage: &dyn Storage, key: &str) -> Vec<Value> {
+        let commit = storage.get(key).unwrap().unwrap();
+        String::from_utf8(commit).unwrap().lines().map(|line| serde_json::from_str(line).unwrap()).collect()
+    }
+
+    /// Contents of the files of `version`, in path order
+    fn read_version(storage: &dyn Storage, table: &DeltaTable, version: u64) -> Vec<String> {
+        let files = table.files_at(version).unwrap();
+        files
+            .iter()
+            .map(|path| String::from_utf8(storage.get(&join_key("archive", path)).unwrap().unwrap()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let week = |n: u32| format!("archive/year=2024/week={:02}/threads.parquet", n);
+        // Archived before the table existed
+        storage.put(&week(1), b"one").unwrap();
+        let table = DeltaTable::new(&storage, "archive");
+        assert_eq!(table.version().unwrap(), None);
+
+        storage.put(&week(2), b"two").unwrap();
+        assert_eq!(table.commit(&[week(2)], "WRITE").unwrap(), Some(0));
+        let first = actions(&storage, "archive/_delta_log/00000000000000000000.json");
+        assert_eq!(first[1]["protocol"]["minReaderVersion"], 1);
+        let schema: Value = serde_json::from_str(first[2]["metaData"]["schemaString"].as_str().unwrap()).unwrap();
+        assert_eq!(schema["fields"][0], json!({"name": "ts", "type": "string", "nullable": false, "metadata": {}}));
+        assert_eq!(schema["fields"][6]["type"], "boolean");
+        assert_eq!(schema["fields"][8]["type"], "integer");
+        let added = first[3]["add"]["path"].as_str().unwrap();
+        assert!(added.starts_with("year=2024/week=01/part-") && added.ends_with(".parquet"));
+        assert_eq!(first[4]["add"]["size"], 3);
+        assert_eq!(read_version(&storage, &table, 0), vec!["one", "two"]);
+
+        // A week fetched again gets a new part, the one of version 0 stays
+        storage.put(&week(2), b"second").unwrap();
+        storage.put(&week(3), b"three").unwrap();
+        assert_eq!(table.commit(&[week(3), week(2)], "WRITE").unwrap(), Some(1));
+        let second = actions(&storage, "archive/_delta_log/00000000000000000001.json");
+        assert_eq!(second[1]["remove"]["path"], first[4]["add"]["path"]);
+        assert_ne!(second[2]["add"]["path"], first[4]["add"]["path"]);
+        assert_eq!(second[2]["add"]["size"], 6);
+        assert!(second[3]["add"]["path"].as_str().unwrap().starts_with("year=2024/week=03/part-"));
+        assert_eq!(table.files().unwrap().len(), 3);
+        assert_eq!(read_version(&storage, &table, 0), vec!["one", "two"]);
+        assert_eq!(read_version(&storage, &table, 1), vec!["one", "second", "three"]);
+
+        assert_eq!(table.commit(&[], "WRITE").unwrap(), None);
+        assert_eq!(table.version().unwrap(), Some(1));
+        assert!(table.commit(&["archive/year=2024/week=09/threads.parquet".to_string()], "WRITE").is_err());
+    }
+
+    #[test]
+    fn test_vacuum() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let week = "archive/year=2024/week=01/threads.parquet".to_string();
+        let table = DeltaTable::new(&storage, "archive");
+        storage.put(&week, b"one").unwrap();
+        table.commit(std::slice::from_ref(&week), "WRITE").unwrap();
+        let replaced = table.files().unwrap();
+        storage.put(&week, b"again").unwrap();
+        table.commit(std::slice::from_ref(&week), "WRITE").unwrap();
+
+        // Removed after the cutoff, still needed to read version 0
+        let an_hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert!(table.vacuum(an_hour_ago).unwrap().is_empty());
+        assert_eq!(read_version(&storage, &table, 0), vec!["one"]);
+
+        let deleted = table.vacuum(Utc::now() + chrono::Duration::seconds(1)).unwrap();
+        let expected: Vec<String> = replaced.iter().map(|path| join_key("archive", path)).collect();
+        assert_eq!(deleted, expected);
+        assert!(!storage.exists(&deleted[0]).unwrap());
+        assert_eq!(read_version(&storage, &table, 1), vec!["again"]);
+        assert!(storage.exists(&week).unwrap());
+    }
+
+    #[test]
+    fn test_resolve_table_format() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        assert_eq!(TableFormat::resolve(None, &storage, "").unwrap(), TableFormat::Files);
+        assert_eq!(TableFormat::resolve(Some(TableFormat::Delta), &storage, "").unwrap(), TableFormat::Delta);
+        DeltaTable::new(&storage, "").commit(&[], "CREATE TABLE").unwrap();
+        assert_eq!(TableFormat::resolve(None, &storage, "").unwrap(), TableFormat::Delta);
+        assert!(TableFormat::resolve(Some(TableFormat::Files), &storage, "").is_err());
+        assert!("iceberg".parse::<TableFormat>().is_err());
+    }
+}
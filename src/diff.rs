@@ -0,0 +1,415 @@
+//! Differences between two conversation exports: messages added, removed
+//! and edited, membership changes and renamed channels
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// A message present in only one of the exports
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DiffMessage {
+    pub channel_id: String,
+    pub channel: String,
+    pub ts: String,
+    pub user: Option<String>,
+    /// Parent ts for thread replies
+    pub thread_ts: Option<String>,
+    pub text: String,
+}
+
+/// A message whose text or edit time changed
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EditedMessage {
+    pub channel_id: String,
+    pub channel: String,
+    pub ts: String,
+    pub user: Option<String>,
+    pub old_text: String,
+    pub new_text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MembershipKind {
+    Joined,
+    Left,
+}
+
+/// A join or leave message that only the new export has
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MembershipChange {
+    pub channel_id: String,
+    pub channel: String,
+    pub user: Option<String>,
+    pub ts: String,
+    pub change: MembershipKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelRename {
+    pub channel_id: String,
+    pub old_name: String,
+    pub new_name: String,
+}
+
+/// Changes from an old export to a new one, each list sorted by channel and ts
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ExportDiff {
+    pub added: Vec<DiffMessage>,
+    pub removed: Vec<DiffMessage>,
+    pub edited: Vec<EditedMessage>,
+    pub membership: Vec<MembershipChange>,
+    pub renamed: Vec<ChannelRename>,
+    /// Names of channels only in the new export
+    pub channels_added: Vec<String>,
+    /// Names of channels only in the old export
+    pub channels_removed: Vec<String>,
+}
+
+impl ExportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty()
+            && self.removed.is_empty()
+            && self.edited.is_empty()
+            && self.renamed.is_empty()
+            && self.channels_added.is_empty()
+            && self.channels_removed.is_empty()
+    }
+
+    /// One-line counts, as printed by `diff`
+    pub fn summary(&self) -> String {
+        format!(
+            "{} added, {} removed, {} edited, {} membership changes, {} renamed channels",
+            self.added.len(),
+            self.removed.len(),
+            self.edited.len(),
+            self.membership.len(),
+            self.renamed.len()
+        )
+    }
+}
+
+struct Indexed<'a> {
+    channel: &'a str,
+    thread_ts: Option<&'a str>,
+    message: &'a serde_json::Value,
+}
+
+/// Channel names by ID and every message and reply by (channel ID, ts)
+type ExportIndex<'a> = (BTreeMap<&'a str, &'a str>, BTreeMap<(&'a str, &'a str), Indexed<'a>>);
+
+fn index_export(conversations: &[serde_json::Value]) -> ExportIndex<'_> {
+    let mut channels = BTreeMap::new();
+    let mut messages = BTreeMap::new();
+    for conversation in conversations {
+        let channel_id = conversation
+            .get("channel_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let channel = conversation
+            .get("channel_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(channel_id);
+        channels.insert(channel_id, channel);
+
+        let parents = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        for parent in parents {
+            let Some(parent_ts) = parent.get("ts").and_then(|v| v.as_str()) else {
+                continue;
+            };
+            let thread_ts = parent.get("thread_ts").and_then(|v| v.as_str());
+            messages.insert((channel_id, parent_ts), Indexed { channel, thread_ts, message: parent });
+
+            let replies = parent
+                .get("thread_replies")
+                .and_then(|r| r.as_array())
+                .map(|a| a.as_slice())
+                .unwrap_or_default();
+            for reply in replies {
+                if let Some(ts) = reply.get("ts").and_then(|v| v.as_str()) {
+                    let entry = Indexed { channel, thread_ts: Some(parent_ts), message: reply };
+                    messages.insert((channel_id, ts), entry);
+                }
+            }
+        }
+    }
+    (channels, messages)
+}
+
+fn text(message: &serde_json::Value) -> &str {
+    message.get("text").and_then(|v| v.as_str()).unwrap_or_default()
+}
+
+fn user(message: &serde_json::Value) -> Option<String> {
+    message.get("user").and_then(|v| v.as_str()).map(str::to_string)
+}
+
+fn diff_message(channel_id: &str, ts: &str, indexed: &Indexed) -> DiffMessage {
+    DiffMessage {
+        channel_id: channel_id.to_string(),
+        channel: indexed.channel.to_string(),
+        ts: ts.to_string(),
+        user: user(indexed.message),
+        thread_ts: indexed.thread_ts.filter(|t| *t != ts).map(str::to_string),
+        text: text(indexed.message).to_string(),
+    }
+}
+
+fn membership_kind(message: &serde_json::Value) -> Option<MembershipKind> {
+    match message.get("subtype").and_then(|v| v.as_str())? {
+        "channel_join" | "group_join" => Some(MembershipKind::Joined),
+        "channel_leave" | "group_leave" => Some(MembershipKind::Left),
+        _ => None,
+    }
+}
+
+/// Compare two conversation exports, replies included. A message counts as
+/// edited when its text or its `edited.ts` differs.
+pub fn compute_diff(old: &[serde_json::Value], new: &[serde_json::Value]) -> ExportDiff {
+    let (old_channels, old_messages) = index_export(old);
+    let (new_channels, new_messages) = index_export(new);
+    let mut diff = ExportDiff::default();
+
+    for (&(channel_id, ts), indexed) in &new_messages {
+        let Some(previous) = old_messages.get(&(channel_id, ts)) else {
+            if let Some(change) = membership_kind(indexed.message) {
+                diff.membership.push(MembershipChange {
+                    channel_id: channel_id.to_string(),
+                    channel: indexed.channel.to_string(),
+                    user: user(indexed.message),
+                    ts: ts.to_string(),
+                    change,
+                });
+            }
+            diff.added.push(diff_message(channel_id, ts, indexed));
+            continue;
+        };
+        let edited_ts = |m: &serde_json::Value| m.pointer("/edited/ts").cloned();
+        if text(previous.message) != text(indexed.message)
+            || edited_ts(previous.message) != edited_ts(indexed.message)
+        {
+            diff.edited.push(EditedMessage {
+                channel_id: channel_id.to_string(),
+                channel: indexed.channel.to_string(),
+                ts: ts.to_string(),
+                user: user(indexed.message),
+                old_text: text(previous.message).to_string(),
+                new_text: text(indexed.message).to_string(),
+            });
+        }
+    }
+    for (&(channel_id, ts), indexed) in &old_messages {
+        if !new_messages.contains_key(&(channel_id, ts)) {
+            diff.removed.push(diff_message(channel_id, ts, indexed));
+        }
+    }
+
+    for (&channel_id, &new_name) in &new_channels {
+        match old_channels.get(channel_id) {
+            Some(&old_name) if old_name != new_name => diff.renamed.push(ChannelRename {
+                channel_id: channel_id.to_string(),
+                old_name: old_name.to_string(),
+                new_name: new_name.to_string(),
+            }),
+            Some(_) => {}
+            None => diff.channels_added.push(new_name.to_string()),
+        }
+    }
+    let only_old = old_channels.iter().filter(|(id, _)| !new_channels.contains_key(*id));
+    diff.channels_removed = only_old.map(|(_, name)| name.to_string()).collect();
+
+    let by_channel = |a: &DiffMessage, b: &DiffMessage| (&a.channel, &a.ts).cmp(&(&b.channel, &b.ts));
+    diff.added.sort_by(by_channel);
+    diff.removed.sort_by(by_channel);
+    diff.edited.sort_by(|a, b| (&a.channel, &a.ts).cmp(&(&b.channel, &b.ts)));
+    diff.membership.sort_by(|a, b| (&a.channel, &a.ts).cmp(&(&b.channel, &b.ts)));
+    diff
+}
+
+/// Write `diff` as JSON to `output_path`
+pub fn write_diff(diff: &ExportDiff, output_path: &str) -> Result<()> {
+    let file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), diff).map_err(|e| AppError::JsonSerialize(e.to_string()))
+}
+
+/// First line of `text`, cut to 80 characters, for table cells
+fn excerpt(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().replace('|', "\\|");
+    match line.char_indices().nth(80) {
+        Some((end, _)) => format!("{}…", &line[..end]),
+        None => line,
+    }
+}
+
+/// Markdown change report of `diff`; `user_names` maps user IDs to display
+/// names
+pub fn diff_markdown_report(diff: &ExportDiff, user_names: &HashMap<String, String>) -> String {
+    let name = |user: &Option<String>| {
+        user.as_deref()
+            .map(|id| user_names.get(id).map_or(id, String::as_str))
+            .unwrap_or_default()
+            .to_string()
+    };
+    let mut md = format!("# Export changes\n\n{}.\n", diff.summary());
+
+    if !diff.renamed.is_empty() || !diff.channels_added.is_empty() || !diff.channels_removed.is_empty() {
+        md.push_str("\n## Channels\n\n");
+        for rename in &diff.renamed {
+            let _ = writeln!(md, "- #{} renamed to #{}", rename.old_name, rename.new_name);
+        }
+        for channel in &diff.channels_added {
+            let _ = writeln!(md, "- #{} added", channel);
+        }
+        for channel in &diff.channels_removed {
+            let _ = writeln!(md, "- #{} removed", channel);
+        }
+    }
+
+    if !diff.membership.is_empty() {
+        md.push_str("\n## Membership\n\n");
+        for change in &diff.membership {
+            let verb = match change.change {
+                MembershipKind::Joined => "joined",
+                MembershipKind::Left => "left",
+            };
+            let _ = writeln!(md, "- {} {} #{}", name(&change.user), verb, change.channel);
+        }
+    }
+
+    if !diff.edited.is_empty() {
+        md.push_str("\n## Edited\n\n| Channel | ts | User | Before | After |\n|---|---|---|---|---|\n");
+        for edit in &diff.edited {
+            let _ = writeln!(
+                md,
+                "| #{} | {} | {} | {} | {} |",
+                edit.channel,
+                edit.ts,
+                name(&edit.user),
+                excerpt(&edit.old_text),
+                excerpt(&edit.new_text)
+            );
+        }
+    }
+
+    for (title, messages) in [("Added", &diff.added), ("Removed", &diff.removed)] {
+        if messages.is_empty() {
+            continue;
+        }
+        let _ = write!(md, "\n## {}\n\n| Channel | ts | User | Text |\n|---|---|---|---|\n", title);
+        for message in messages {
+            let _ = writeln!(
+                md,
+                "| #{} | {} | {} | {} |",
+                message.channel,
+                message.ts,
+                name(&message.user),
+                excerpt(&message.text)
+            );
+        }
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn old_export() -> Vec<serde_json::Value> {
+        vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1.0", "user": "U1", "text": "hello", "thread_ts": "1.0",
+                     "thread_replies": [{"ts": "1.5", "user": "U2", "text": "hi"}]},
+                    {"ts": "2.0", "user": "U1", "text": "typo"},
+                    {"ts": "3.0", "user": "U2", "text": "gone soon"}
+                ]
+            }),
+            json!({"channel_id": "C2", "channel_name": "old-name", "messages": []}),
+            json!({"channel_id": "C3", "channel_name": "archived", "messages": []}),
+        ]
+    }
+
+    fn new_export() -> Vec<serde_json::Value> {
+        vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1.0", "user": "U1", "text": "hello", "thread_ts": "1.0",
+                     "thread_replies": [
+                         {"ts": "1.5", "user": "U2", "text": "hi"},
+                         {"ts": "1.7", "user": "U3", "text": "late reply"}
+                     ]},
+                    {"ts": "2.0", "user": "U1", "text": "fixed", "edited": {"user": "U1", "ts": "2.5"}},
+                    {"ts": "4.0", "user": "U4", "subtype": "channel_join", "text": "<@U4> has joined the channel"}
+                ]
+            }),
+            json!({"channel_id": "C2", "channel_name": "new-name", "messages": []}),
+            json!({"channel_id": "C4", "channel_name": "fresh", "messages": []}),
+        ]
+    }
+
+    #[test]
+    fn test_compute_diff() {
+        let diff = compute_diff(&old_export(), &new_export());
+
+        let added: Vec<&str> = diff.added.iter().map(|m| m.ts.as_str()).collect();
+        assert_eq!(added, vec!["1.7", "4.0"]);
+        assert_eq!(diff.added[0].thread_ts.as_deref(), Some("1.0"));
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].text, "gone soon");
+        assert_eq!(diff.edited.len(), 1);
+        assert_eq!(diff.edited[0].old_text, "typo");
+        assert_eq!(diff.edited[0].new_text, "fixed");
+        assert_eq!(
+            diff.membership,
+            vec![MembershipChange {
+                channel_id: "C1".to_string(),
+                channel: "general".to_string(),
+                user: Some("U4".to_string()),
+                ts: "4.0".to_string(),
+                change: MembershipKind::Joined,
+            }]
+        );
+        assert_eq!(diff.renamed.len(), 1);
+        assert_eq!(diff.renamed[0].new_name, "new-name");
+        assert_eq!(diff.channels_added, vec!["fresh"]);
+        assert_eq!(diff.channels_removed, vec!["archived"]);
+        assert_eq!(
+            diff.summary(),
+            "2 added, 1 removed, 1 edited, 1 membership changes, 1 renamed channels"
+        );
+    }
+
+    #[test]
+    fn test_compute_diff_identical() {
+        assert!(compute_diff(&old_export(), &old_export()).is_empty());
+    }
+
+    #[test]
+    fn test_diff_markdown_report() {
+        let diff = compute_diff(&old_export(), &new_export());
+        let names = HashMap::from([("U4".to_string(), "dana".to_string())]);
+        let md = diff_markdown_report(&diff, &names);
+
+        assert!(md.contains("- #old-name renamed to #new-name"));
+        assert!(md.contains("- dana joined #general"));
+        assert!(md.contains("| #general | 2.0 | U1 | typo | fixed |"));
+        assert!(md.contains("## Removed"));
+    }
+}
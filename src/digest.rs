@@ -0,0 +1,257 @@
+//! Weekly highlights: the most-reacted and most-replied messages of each
+//! channel, rendered to markdown like `export-markdown` and optionally posted
+//! back to a channel with `post-message`'s markdown blocks
+
+use std::collections::{HashMap, HashSet};
+
+use serde_json::Value;
+
+use crate::error::Result;
+use crate::formatter::MarkdownExportOptions;
+use crate::markdown::render_conversation_values_to_markdown;
+use crate::parquet::ts_partition;
+
+/// Characters of a message's text shown in the summary table
+const PREVIEW_LEN: usize = 60;
+
+/// A selected message, with what got it selected
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub channel_name: String,
+    pub user: String,
+    pub reactions: u64,
+    pub replies: u64,
+    pub text: String,
+}
+
+/// Reactions on `message`, adding up every emoji's count
+fn reaction_count(message: &Value) -> u64 {
+    message
+        .get("reactions")
+        .and_then(Value::as_array)
+        .map(|reactions| reactions.iter().filter_map(|r| r.get("count")?.as_u64()).sum())
+        .unwrap_or(0)
+}
+
+/// Replies in `message`'s thread: the fetched ones, or Slack's count when
+/// they were not fetched
+fn reply_count(message: &Value) -> u64 {
+    match message.get("thread_replies").and_then(Value::as_array) {
+        Some(replies) => replies.len() as u64,
+        None => message.get("reply_count").and_then(Value::as_u64).unwrap_or(0),
+    }
+}
+
+/// Keep only the top-level messages posted in ISO `week` of `year`
+pub fn filter_week(conversations: &mut [Value], (year, week): (i32, u32)) {
+    for conversation in conversations {
+        if let Some(messages) = conversation.get_mut("messages").and_then(Value::as_array_mut) {
+            messages.retain(|message| {
+                message
+                    .get("ts")
+                    .and_then(Value::as_str)
+                    .and_then(ts_partition)
+                    .is_some_and(|(_, y, w)| y == year && u32::try_from(w) == Ok(week))
+            });
+        }
+    }
+}
+
+/// Up to `top` most-reacted then `top` most-replied messages of each
+/// channel, the conversations cut down to them in that order. Messages
+/// without reactions or replies are never picked; channels with nothing to
+/// pick are left out.
+pub fn select_highlights(conversations: &[Value], top: usize) -> Vec<Value> {
+    let mut selected = Vec::new();
+    for conversation in conversations {
+        let messages = conversation
+            .get("messages")
+            .and_then(Value::as_array)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        let ranked = |count: fn(&Value) -> u64| {
+            let mut ranked: Vec<&Value> = messages.iter().filter(|m| count(m) > 0).collect();
+            // Ties go to the earlier message
+            ranked.sort_by_key(|m| (std::cmp::Reverse(count(m)), m.get("ts").and_then(Value::as_str).unwrap_or_default()));
+            ranked.truncate(top);
+            ranked
+        };
+
+        let mut seen = HashSet::new();
+        let picked: Vec<Value> = ranked(reaction_count)
+            .into_iter()
+            .chain(ranked(reply_count))
+            .filter(|m| seen.insert(m.get("ts").and_then(Value::as_str).unwrap_or_default()))
+            .cloned()
+            .collect();
+        if picked.is_empty() {
+            continue;
+        }
+        let mut conversation = conversation.clone();
+        if let Some(object) = conversation.as_object_mut() {
+            object.insert("messages".to_string(), Value::Array(picked));
+        }
+        selected.push(conversation);
+    }
+    selected
+}
+
+/// The highlights of the conversations `select_highlights` returned, for
+/// the summary table
+pub fn highlights(selected: &[Value], user_names: &HashMap<String, String>) -> Vec<Highlight> {
+    let mut highlights = Vec::new();
+    for conversation in selected {
+        let channel_name = conversation
+            .get("channel_name")
+            .and_then(Value::as_str)
+            .or_else(|| conversation.get("channel_id").and_then(Value::as_str))
+            .unwrap_or_default();
+        for message in conversation.get("messages").and_then(Value::as_array).into_iter().flatten() {
+            let user = message.get("user").and_then(Value::as_str).unwrap_or_default();
+            highlights.push(Highlight {
+                channel_name: channel_name.to_string(),
+                user: user_names.get(user).cloned().unwrap_or_else(|| user.to_string()),
+                reactions: reaction_count(message),
+                replies: reply_count(message),
+                text: message.get("text").and_then(Value::as_str).unwrap_or_default().to_string(),
+            });
+        }
+    }
+    highlights
+}
+
+/// First line of `text`, cut to `PREVIEW_LEN` characters, safe in a table cell
+fn preview(text: &str) -> String {
+    let line = text.lines().next().unwrap_or_default().replace('|', "\\|");
+    if line.chars().count() > PREVIEW_LEN {
+        format!("{}…", line.chars().take(PREVIEW_LEN).collect::<String>())
+    } else {
+        line
+    }
+}
+
+/// Title and summary table of the digest, put before the rendered messages
+pub fn digest_header(title: &str, highlights: &[Highlight]) -> String {
+    let mut md = format!("# {}\n\n", title);
+    if highlights.is_empty() {
+        md.push_str("No messages with reactions or replies.\n");
+        return md;
+    }
+    md.push_str("| Channel | Author | Reactions | Replies | Message |\n|---|---|---:|---:|---|\n");
+    for highlight in highlights {
+        md.push_str(&format!(
+            "| #{} | {} | {} | {} | {} |\n",
+            highlight.channel_name,
+            highlight.user,
+            highlight.reactions,
+            highlight.replies,
+            preview(&highlight.text)
+        ));
+    }
+    md.push('\n');
+    md
+}
+
+/// What `build_digest` produced
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Digest {
+    pub markdown: String,
+    pub channels: usize,
+    pub messages: usize,
+}
+
+/// The digest of `conversations`: the summary table, then each channel's
+/// selected messages rendered with the names in `users_path` and
+/// `channels_path`
+pub fn build_digest(
+    conversations: &[Value],
+    (users_path, channels_path): (&str, &str),
+    title: &str,
+    top: usize,
+) -> Result<Digest> {
+    let selected = select_highlights(conversations, top);
+    let user_names = crate::stats::load_user_names(Some(users_path))?;
+    let highlights = highlights(&selected, &user_names);
+
+    let mut markdown = digest_header(title, &highlights);
+    if !selected.is_empty() {
+        let options = MarkdownExportOptions::new();
+        let rendered = render_conversation_values_to_markdown(&selected, users_path, channels_path, &options)?;
+        // Channel headings of the rendered messages go under the title
+        let headings: HashSet<String> = highlights.iter().map(|h| format!("# {}", h.channel_name)).collect();
+        for line in rendered.lines() {
+            if headings.contains(line) {
+                markdown.push('#');
+            }
+            markdown.push_str(line);
+            markdown.push('\n');
+        }
+    }
+    Ok(Digest {
+        markdown,
+        channels: selected.len(),
+        messages: highlights.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn conversations() -> Vec<Value> {
+        // 1707696000 is Monday 2024-02-12 (2024-W07), 1707091200 is 2024-02-05
+        vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1707696000.000100", "user": "U1", "text": "quiet"},
+                    {"ts": "1707696100.000100", "user": "U1", "text": "liked | a lot",
+                     "reactions": [{"name": "tada", "count": 3}, {"name": "+1", "count": 2}]},
+                    {"ts": "1707696200.000100", "user": "U2", "text": "question",
+                     "reactions": [{"name": "eyes", "count": 1}],
+                     "thread_replies": [{"ts": "1707696300.000100"}, {"ts": "1707696400.000100"}]},
+                    {"ts": "1707091200.000100", "user": "U2", "text": "last week", "reply_count": 9}
+                ]
+            }),
+            json!({"channel_id": "C2", "channel_name": "random", "messages": [{"ts": "1707696000.000200"}]}),
+        ]
+    }
+
+    #[test]
+    fn test_select_highlights() {
+        let selected = select_highlights(&conversations(), 1);
+        assert_eq!(selected.len(), 1);
+        let texts: Vec<&str> = selected[0]["messages"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["text"].as_str().unwrap())
+            .collect();
+        assert_eq!(texts, ["liked | a lot", "last week"]);
+
+        // A message first in both rankings is listed once
+        let texts = select_highlights(&conversations(), 5)[0]["messages"].as_array().unwrap().len();
+        assert_eq!(texts, 3);
+    }
+
+    #[test]
+    fn test_filter_week() {
+        let mut conversations = conversations();
+        filter_week(&mut conversations, (2024, 7));
+        assert_eq!(conversations[0]["messages"].as_array().unwrap().len(), 3);
+
+        let selected = select_highlights(&conversations, 1);
+        let names = HashMap::from([("U1".to_string(), "ana".to_string())]);
+        let highlights = highlights(&selected, &names);
+        assert_eq!(highlights.len(), 2);
+        assert_eq!((highlights[0].user.as_str(), highlights[0].reactions), ("ana", 5));
+        assert_eq!((highlights[1].user.as_str(), highlights[1].replies), ("U2", 2));
+
+        let header = digest_header("Highlights of 2024-W07", &highlights);
+        assert!(header.starts_with("# Highlights of 2024-W07\n\n| Channel |"));
+        assert!(header.contains("| #general | ana | 5 | 0 | liked \\| a lot |\n"));
+        assert!(digest_header("Empty", &[]).contains("No messages"));
+    }
+}
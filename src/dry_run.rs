@@ -0,0 +1,203 @@
+//! What export-conversations and archive-range would fetch (`--dry-run`),
+//! worked out from the channel list alone so the scope of a long run can be
+//! checked without downloading any message
+//!
+//! Slack has no metadata call that counts a channel's messages, or their size,
+//! in a date range, so plans count channels and requests, never messages or
+//! bytes: estimating those would mean fetching the history the dry run avoids
+
+use std::collections::HashSet;
+
+use chrono::NaiveDate;
+use slack_morphism::prelude::SlackChannelInfo;
+
+use crate::error::{AppError, Result};
+use crate::week_to_date_range;
+
+/// A channel as conversations.list describes it
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedChannel {
+    pub id: String,
+    pub name: String,
+    pub num_members: Option<u64>,
+    pub is_archived: bool,
+    /// Whether the token's user is in the channel; bot tokens can only read
+    /// the history of channels they are in
+    pub is_member: bool,
+    /// Unix seconds
+    pub created: i64,
+}
+
+impl From<&SlackChannelInfo> for PlannedChannel {
+    fn from(channel: &SlackChannelInfo) -> Self {
+        PlannedChannel {
+            id: channel.id.0.clone(),
+            name: channel.name.clone().unwrap_or_else(|| "unknown".to_string()),
+            num_members: channel.num_members,
+            is_archived: channel.flags.is_archived.unwrap_or(false),
+            is_member: channel.flags.is_member.unwrap_or(false),
+            created: channel.created.0.timestamp(),
+        }
+    }
+}
+
+/// Unix seconds of the midnight ending `date`
+fn end_of_day(date: NaiveDate) -> Result<i64> {
+    date.succ_opt()
+        .and_then(|next| next.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc().timestamp())
+        .ok_or_else(|| AppError::InvalidDate(format!("Cannot compute day after {}", date)))
+}
+
+/// The channels of a conversations export, without message counts or sizes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConversationsPlan {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Channels whose history would be fetched
+    pub channels: Vec<PlannedChannel>,
+    /// Selected channels created after the range, which have nothing in it
+    pub created_later: usize,
+}
+
+impl ConversationsPlan {
+    /// Plan an export of `listed` (or the `selected` ones among them) between
+    /// the two dates, inclusive
+    pub fn new(
+        listed: &[SlackChannelInfo],
+        selected: Option<&HashSet<String>>,
+        (from, to): (NaiveDate, NaiveDate),
+    ) -> Result<Self> {
+        let end = end_of_day(to)?;
+        let (channels, later): (Vec<PlannedChannel>, Vec<PlannedChannel>) = listed
+            .iter()
+            .filter(|c| selected.is_none_or(|ids| ids.contains(&c.id.0)))
+            .map(PlannedChannel::from)
+            .partition(|c| c.created < end);
+        Ok(ConversationsPlan {
+            from,
+            to,
+            channels,
+            created_later: later.len(),
+        })
+    }
+
+    /// Fewest history requests the export makes: a page per channel, before
+    /// further pages and a request per thread
+    pub fn min_requests(&self) -> usize {
+        self.channels.len()
+    }
+
+    /// Channels to join before fetching: the ones the token is not in, but
+    /// for archived ones, which cannot be joined
+    pub fn to_join(&self) -> Vec<&PlannedChannel> {
+        self.channels.iter().filter(|c| !c.is_member && !c.is_archived).collect()
+    }
+}
+
+/// One week of an archive plan
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedWeek {
+    pub year: i32,
+    pub week: u32,
+    /// Already archived, so skipped
+    pub exists: bool,
+    /// Channels that existed by the end of the week
+    pub channels: usize,
+}
+
+/// The weeks of an archive-range run, with the channels in each but no
+/// message counts or sizes
+#[derive(Debug, Clone, PartialEq)]
+pub struct ArchivePlan {
+    pub weeks: Vec<PlannedWeek>,
+    /// Channels listed now
+    pub channels: usize,
+}
+
+impl ArchivePlan {
+    /// Plan archiving `weeks` of the `listed` channels when the `existing`
+    /// weeks are already archived
+    pub fn new(listed: &[SlackChannelInfo], weeks: &[(i32, u32)], existing: &HashSet<(i32, u32)>) -> Result<Self> {
+        let created: Vec<i64> = listed.iter().map(|c| c.created.0.timestamp()).collect();
+        let weeks = weeks
+            .iter()
+            .map(|&(year, week)| {
+                let (_, to) = week_to_date_range(year, week)?;
+                let end = end_of_day(to)?;
+                Ok(PlannedWeek {
+                    year,
+                    week,
+                    exists: existing.contains(&(year, week)),
+                    channels: created.iter().filter(|&&c| c < end).count(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(ArchivePlan {
+            weeks,
+            channels: listed.len(),
+        })
+    }
+
+    pub fn to_fetch(&self) -> impl Iterator<Item = &PlannedWeek> {
+        self.weeks.iter().filter(|w| !w.exists)
+    }
+
+    /// Fewest requests the run makes: the channel list and a history page
+    /// per channel for each week fetched
+    pub fn min_requests(&self) -> usize {
+        self.to_fetch().map(|w| w.channels + 1).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: &str, created: &str) -> SlackChannelInfo {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id.to_lowercase(),
+            "created": NaiveDate::parse_from_str(created, "%Y-%m-%d").unwrap().and_hms_opt(12, 0, 0).unwrap().and_utc().timestamp(),
+            "num_members": 3,
+            "is_archived": id == "C3",
+            "is_member": id == "C1",
+        }))
+        .unwrap()
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_conversations_plan() {
+        let listed = vec![channel("C1", "2023-05-01"), channel("C2", "2024-01-31"), channel("C3", "2024-02-01")];
+        let plan = ConversationsPlan::new(&listed, None, (date("2024-01-01"), date("2024-01-31"))).unwrap();
+        let ids: Vec<&str> = plan.channels.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(ids, vec!["C1", "C2"]);
+        assert_eq!(plan.created_later, 1);
+        assert_eq!(plan.min_requests(), 2);
+        assert_eq!(plan.channels[0].num_members, Some(3));
+        let to_join: Vec<&str> = plan.to_join().iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(to_join, vec!["C2"]);
+
+        let selected: HashSet<String> = ["C2".to_string()].into();
+        let plan = ConversationsPlan::new(&listed, Some(&selected), (date("2024-01-01"), date("2024-01-31"))).unwrap();
+        assert_eq!(plan.channels.len(), 1);
+        assert_eq!(plan.created_later, 0);
+    }
+
+    #[test]
+    fn test_archive_plan() {
+        // 2024-W01 ends on 2024-01-07, 2024-W02 on 2024-01-14
+        let listed = vec![channel("C1", "2023-05-01"), channel("C2", "2024-01-10")];
+        let existing: HashSet<(i32, u32)> = [(2024, 3)].into();
+        let plan = ArchivePlan::new(&listed, &[(2024, 1), (2024, 2), (2024, 3)], &existing).unwrap();
+        let channels: Vec<usize> = plan.weeks.iter().map(|w| w.channels).collect();
+        assert_eq!(channels, vec![1, 2, 2]);
+        assert_eq!(plan.to_fetch().count(), 2);
+        assert_eq!(plan.min_requests(), 2 + 3);
+        assert_eq!(plan.channels, 2);
+    }
+}
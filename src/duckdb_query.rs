@@ -2,8 +2,16 @@
 
 use duckdb::Connection;
 use std::fmt::Write as FmtWrite;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
 use thiserror::Error;
 
+use crate::error::AppError;
+use crate::parquet::PartitionBy;
+
 /// Errors for DuckDB operations
 #[derive(Error, Debug)]
 pub enum DuckDbError {
@@ -15,6 +23,12 @@ pub enum DuckDbError {
 
     #[error("no results returned from query")]
     NoResults,
+
+    #[error("query not allowed: {0}")]
+    NotAllowed(String),
+
+    #[error(transparent)]
+    App(#[from] AppError),
 }
 
 /// Result type for DuckDB operations
@@ -154,6 +168,210 @@ pub fn execute_query(config: &QueryConfig) -> Result<QueryResult> {
     Ok(QueryResult { columns, rows })
 }
 
+/// Result of a query against an archive, capped at a row limit
+#[derive(Debug)]
+pub struct ArchiveQueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<serde_json::Value>>,
+    /// Whether more rows matched than the limit allowed
+    pub truncated: bool,
+}
+
+/// Statements that would let a query write files, load extensions or change
+/// settings; only plain SELECT/WITH queries are accepted
+const FORBIDDEN_KEYWORDS: &[&str] = &[
+    "ATTACH", "DETACH", "COPY", "EXPORT", "IMPORT", "INSTALL", "LOAD", "PRAGMA", "SET", "RESET",
+    "CREATE", "DROP", "ALTER", "INSERT", "UPDATE", "DELETE", "CALL", "CHECKPOINT", "VACUUM",
+];
+
+/// Blank out string literals and quoted identifiers so their contents aren't
+/// mistaken for keywords or statement separators
+fn strip_quoted(sql: &str) -> String {
+    let mut quote = None;
+    sql.chars()
+        .map(|c| match quote {
+            Some(q) if c == q => {
+                quote = None;
+                ' '
+            }
+            Some(_) => ' ',
+            None if c == '\'' || c == '"' => {
+                quote = Some(c);
+                ' '
+            }
+            None => c,
+        })
+        .collect()
+}
+
+/// Check that `sql` is a single read-only query
+pub fn check_read_only(sql: &str) -> Result<()> {
+    let code = strip_quoted(sql);
+    let trimmed = code.trim().trim_end_matches(';').trim();
+    if trimmed.contains(';') {
+        return Err(DuckDbError::NotAllowed(
+            "only a single statement is allowed".to_string(),
+        ));
+    }
+
+    let words: Vec<String> = trimmed
+        .split(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_ascii_uppercase())
+        .collect();
+    match words.first().map(String::as_str) {
+        Some("SELECT" | "WITH" | "FROM") => {}
+        _ => {
+            return Err(DuckDbError::NotAllowed(
+                "only SELECT queries are allowed".to_string(),
+            ))
+        }
+    }
+    if let Some(keyword) = words
+        .iter()
+        .find(|word| FORBIDDEN_KEYWORDS.contains(&word.as_str()))
+    {
+        return Err(DuckDbError::NotAllowed(format!("{} is not allowed", keyword)));
+    }
+    Ok(())
+}
+
+/// Execute a read-only query against an archive folder.
+///
+/// `users`, `channels` and `messages` (all weekly threads.parquet files, with
+/// `year`/`week` partition columns) are exposed as views for the files that
+/// exist. File access is then restricted to `base_path` and the configuration
+/// locked, so the query itself can't read or write elsewhere.
+pub fn execute_archive_query(
+    base_path: &Path,
+    sql: &str,
+    max_rows: usize,
+    timeout: Duration,
+) -> Result<ArchiveQueryResult> {
+    check_read_only(sql)?;
+
+    let conn = Connection::open_in_memory()?;
+    conn.execute_batch("SET enable_progress_bar = false;")?;
+
+    let base = base_path.display().to_string().replace('\'', "''");
+    let conversations = base_path.join("conversations");
+    let views = [
+        ("users", base_path.join("users.parquet").is_file(), format!("{}/users.parquet", base)),
+        (
+            "channels",
+            base_path.join("channels.parquet").is_file(),
+            format!("{}/channels.parquet", base),
+        ),
+        (
+            "messages",
+            conversations.is_dir(),
+            format!("{}/conversations/{}", base, PartitionBy::of_dir(&conversations).threads_glob()),
+        ),
+    ];
+    for (name, exists, path) in views {
+        if exists {
+            conn.execute_batch(&format!(
+                "CREATE VIEW {} AS SELECT * FROM read_parquet('{}', hive_partitioning = true, union_by_name = true);",
+                name, path
+            ))
+            .map_err(|e| DuckDbError::QueryFailed(format!("Failed to read {}: {}", name, e)))?;
+        }
+    }
+    conn.execute_batch(&format!(
+        "SET allowed_directories = ['{}']; SET enable_external_access = false; SET lock_configuration = true;",
+        base
+    ))?;
+
+    // Interrupt the connection from a watchdog thread unless the query finishes first
+    let (finished, deadline) = mpsc::channel::<()>();
+    let timed_out = Arc::new(AtomicBool::new(false));
+    let watchdog = {
+        let interrupt = conn.interrupt_handle();
+        let timed_out = Arc::clone(&timed_out);
+        std::thread::spawn(move || {
+            if let Err(RecvTimeoutError::Timeout) = deadline.recv_timeout(timeout) {
+                timed_out.store(true, Ordering::SeqCst);
+                interrupt.interrupt();
+            }
+        })
+    };
+    let result = fetch_rows(&conn, sql, max_rows);
+    drop(finished);
+    let _ = watchdog.join();
+
+    match result {
+        Err(_) if timed_out.load(Ordering::SeqCst) => Err(AppError::QueryTimeout(timeout).into()),
+        result => result,
+    }
+}
+
+/// Run `sql` on a prepared connection, keeping at most `max_rows` rows
+fn fetch_rows(conn: &Connection, sql: &str, max_rows: usize) -> Result<ArchiveQueryResult> {
+    let mut stmt = conn
+        .prepare(sql)
+        .map_err(|e| DuckDbError::QueryFailed(e.to_string()))?;
+    let mut result_rows = stmt
+        .query([])
+        .map_err(|e| DuckDbError::QueryFailed(e.to_string()))?;
+
+    let column_count = result_rows.as_ref().map(|s| s.column_count()).unwrap_or(0);
+    let columns: Vec<String> = (0..column_count)
+        .map(|i| {
+            result_rows
+                .as_ref()
+                .and_then(|s| s.column_name(i).ok())
+                .map(|s| s.to_string())
+                .unwrap_or_else(|| "?".to_string())
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut truncated = false;
+    while let Some(row) = result_rows
+        .next()
+        .map_err(|e| DuckDbError::QueryFailed(e.to_string()))?
+    {
+        if rows.len() == max_rows {
+            truncated = true;
+            break;
+        }
+        let mut values = Vec::with_capacity(column_count);
+        for i in 0..column_count {
+            let value: duckdb::types::Value = row
+                .get(i)
+                .map_err(|e| DuckDbError::QueryFailed(e.to_string()))?;
+            values.push(value_to_json(&value));
+        }
+        rows.push(values);
+    }
+
+    Ok(ArchiveQueryResult {
+        columns,
+        rows,
+        truncated,
+    })
+}
+
+/// Convert a DuckDB value to JSON, keeping numbers and nulls typed
+fn value_to_json(value: &duckdb::types::Value) -> serde_json::Value {
+    use duckdb::types::Value;
+    match value {
+        Value::Null => serde_json::Value::Null,
+        Value::Boolean(b) => (*b).into(),
+        Value::TinyInt(n) => (*n).into(),
+        Value::SmallInt(n) => (*n).into(),
+        Value::Int(n) => (*n).into(),
+        Value::BigInt(n) => (*n).into(),
+        Value::UTinyInt(n) => (*n).into(),
+        Value::USmallInt(n) => (*n).into(),
+        Value::UInt(n) => (*n).into(),
+        Value::UBigInt(n) => (*n).into(),
+        Value::Float(f) => (*f).into(),
+        Value::Double(d) => (*d).into(),
+        other => format_value(other).into(),
+    }
+}
+
 /// Format a DuckDB value for display
 fn format_value(value: &duckdb::types::Value) -> String {
     match value {
@@ -184,6 +402,78 @@ fn format_value(value: &duckdb::types::Value) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_check_read_only() {
+        assert!(check_read_only("SELECT * FROM messages LIMIT 10").is_ok());
+        assert!(check_read_only("  with t AS (SELECT 1) SELECT * FROM t;").is_ok());
+        assert!(check_read_only("SELECT 1; SELECT 2").is_err());
+        assert!(check_read_only("COPY messages TO 'out.csv'").is_err());
+        assert!(check_read_only("SELECT * FROM read_csv('x'); ATTACH 'db'").is_err());
+        assert!(matches!(
+            check_read_only("SELECT 1 FROM (SELECT 1) WHERE 1 = 1 UNION SELECT * FROM t; INSTALL x"),
+            Err(DuckDbError::NotAllowed(_))
+        ));
+        assert!(check_read_only("PRAGMA version").is_err());
+        // Column names that merely contain a keyword are fine, as are literals
+        assert!(check_read_only("SELECT created_at, settings FROM users").is_ok());
+        assert!(check_read_only("SELECT * FROM messages WHERE text ILIKE '%delete; drop%'").is_ok());
+        assert!(check_read_only("SELECT 'it''s' AS s").is_ok());
+    }
+
+    #[test]
+    fn test_execute_archive_query() {
+        let dir = tempfile::tempdir().unwrap();
+        let week = dir.path().join("conversations/year=2024/week=03");
+        std::fs::create_dir_all(&week).unwrap();
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&format!(
+            "COPY (SELECT * FROM (VALUES ('general', 'hi'), ('general', 'yo'), ('random', 'hey')) t(channel_name, text)) TO '{}' (FORMAT parquet);",
+            week.join("threads.parquet").display()
+        ))
+        .unwrap();
+
+        let result = execute_archive_query(
+            dir.path(),
+            "SELECT channel_name, COUNT(*) AS n, week FROM messages GROUP BY ALL ORDER BY n DESC",
+            10,
+            Duration::from_secs(30),
+        )
+        .unwrap();
+        assert_eq!(result.columns, vec!["channel_name", "n", "week"]);
+        assert_eq!(result.rows[0][0], "general");
+        assert_eq!(result.rows[0][1], 2);
+        assert!(!result.truncated);
+
+        let limited = execute_archive_query(dir.path(), "SELECT * FROM messages", 2, Duration::from_secs(30))
+            .unwrap();
+        assert_eq!(limited.rows.len(), 2);
+        assert!(limited.truncated);
+
+        let outside = execute_archive_query(
+            dir.path(),
+            "SELECT * FROM read_csv('/etc/passwd')",
+            10,
+            Duration::from_secs(30),
+        );
+        assert!(outside.is_err());
+    }
+
+    #[test]
+    fn test_execute_archive_query_timeout() {
+        let dir = tempfile::tempdir().unwrap();
+        let timeout = Duration::from_millis(100);
+        let result = execute_archive_query(
+            dir.path(),
+            "SELECT COUNT(*) FROM range(100000) a, range(100000) b, range(100000) c",
+            10,
+            timeout,
+        );
+        assert!(matches!(
+            result,
+            Err(DuckDbError::App(AppError::QueryTimeout(t))) if t == timeout
+        ));
+    }
+
     #[test]
     fn test_format_table_empty_columns() {
         let result = QueryResult {
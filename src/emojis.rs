@@ -0,0 +1,350 @@
+//! Custom emoji catalog: reads the `emoji.list` JSON written by
+//! `export-emojis` together with the downloaded images, to audit an export
+//! or package it for re-import into another workspace
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+use serde::Serialize;
+
+use crate::{AppError, Result};
+
+/// emojipacks-style YAML manifest written by `export-emoji-pack`
+pub const EMOJI_PACK_YAML: &str = "emojipack.yaml";
+/// JSON copy of the same manifest
+pub const EMOJI_PACK_JSON: &str = "emojipack.json";
+
+/// File extension of an emoji image URL, `png` when the URL has none
+pub fn emoji_extension(url: &str) -> String {
+    url.split('.')
+        .next_back()
+        .and_then(|s| s.split('?').next())
+        .unwrap_or("png")
+        .to_string()
+}
+
+/// Follow alias chains from `name` to the emoji holding an image.
+/// Returns `None` for missing targets and circular aliases.
+pub fn resolve_alias<'a>(emojis: &'a serde_json::Map<String, serde_json::Value>, name: &'a str) -> Option<&'a str> {
+    let mut current = name;
+    let mut visited = HashSet::new();
+    loop {
+        let value = emojis.get(current)?.as_str()?;
+        match value.strip_prefix("alias:") {
+            Some(next) => {
+                if !visited.insert(current) {
+                    return None;
+                }
+                current = next;
+            }
+            None => return Some(current),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmojiKind {
+    Image { extension: String },
+    Alias { target: String },
+}
+
+/// One custom emoji of an export and what its image file looks like on disk
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EmojiEntry {
+    pub name: String,
+    pub kind: EmojiKind,
+    /// Image file in the emojis folder, for aliases the copy of their target
+    pub file_name: Option<String>,
+    /// Size of `file_name`, `None` when it wasn't downloaded
+    pub file_size: Option<u64>,
+    /// What is wrong with the entry, `None` when the image is in place
+    pub problem: Option<String>,
+}
+
+/// Build the catalog of `emojis_path` (as written by `export-emojis`),
+/// checking each image against `emojis_folder`. Entries are sorted by name.
+pub fn load_emoji_catalog(emojis_path: &str, emojis_folder: &Path) -> Result<Vec<EmojiEntry>> {
+    let emojis: serde_json::Map<String, serde_json::Value> = crate::load_json_file(emojis_path)?;
+    let sorted: BTreeMap<&String, &serde_json::Value> = emojis.iter().collect();
+
+    let entries = sorted
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let url = value.as_str()?;
+            let (kind, extension, problem) = match url.strip_prefix("alias:") {
+                Some(target) => {
+                    let resolved = resolve_alias(&emojis, name);
+                    let extension = resolved
+                        .and_then(|r| emojis.get(r)?.as_str())
+                        .map(emoji_extension);
+                    let problem = match resolved {
+                        Some(_) => None,
+                        None if emojis.contains_key(target) => Some("circular alias".to_string()),
+                        None => Some(format!("alias target {} not found", target)),
+                    };
+                    let kind = EmojiKind::Alias {
+                        target: target.to_string(),
+                    };
+                    (kind, extension, problem)
+                }
+                None => {
+                    let extension = emoji_extension(url);
+                    let kind = EmojiKind::Image {
+                        extension: extension.clone(),
+                    };
+                    (kind, Some(extension), None)
+                }
+            };
+
+            let file_name = extension.map(|ext| format!("{}.{}", name, ext));
+            let file_size = file_name
+                .as_ref()
+                .and_then(|f| std::fs::metadata(emojis_folder.join(f)).ok())
+                .map(|m| m.len());
+            let problem = problem.or_else(|| match (&file_name, file_size) {
+                (Some(f), None) => Some(format!("{} not downloaded", f)),
+                (Some(f), Some(0)) => Some(format!("{} is empty", f)),
+                _ => None,
+            });
+
+            Some(EmojiEntry {
+                name: name.clone(),
+                kind,
+                file_name,
+                file_size,
+                problem,
+            })
+        })
+        .collect();
+
+    Ok(entries)
+}
+
+/// One emoji of a pack: the image next to the manifest and its aliases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct PackEmoji {
+    pub name: String,
+    /// Image file name, relative to the manifest
+    pub src: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+}
+
+/// Manifest in the `title` / `emojis` layout read by bulk emoji uploaders
+/// such as emojipacks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EmojiPack {
+    pub title: String,
+    pub emojis: Vec<PackEmoji>,
+}
+
+impl EmojiPack {
+    /// The manifest as YAML; strings are double quoted so any emoji name is
+    /// valid
+    pub fn to_yaml(&self) -> String {
+        let quote = |s: &str| serde_json::to_string(s).unwrap_or_default();
+        let mut yaml = format!("title: {}\nemojis:\n", quote(&self.title));
+        for emoji in &self.emojis {
+            let _ = writeln!(yaml, "  - name: {}", quote(&emoji.name));
+            let _ = writeln!(yaml, "    src: {}", quote(&emoji.src));
+            if !emoji.aliases.is_empty() {
+                yaml.push_str("    aliases:\n");
+                for alias in &emoji.aliases {
+                    let _ = writeln!(yaml, "      - {}", quote(alias));
+                }
+            }
+        }
+        yaml
+    }
+}
+
+/// Result of `export_emoji_pack`
+#[derive(Debug)]
+pub struct EmojiPackResult {
+    /// Emojis with an image in the pack
+    pub packed: usize,
+    /// Aliases listed under their emoji
+    pub aliases: usize,
+    /// Emojis left out and why (image not downloaded, broken alias)
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Package the export in `emojis_path` / `emojis_folder` into `output_dir`:
+/// one image per emoji plus `emojipack.yaml` and `emojipack.json` listing
+/// them with their aliases, ready for a bulk emoji upload tool
+pub fn export_emoji_pack(
+    emojis_path: &str,
+    emojis_folder: &Path,
+    output_dir: &Path,
+    title: &str,
+) -> Result<EmojiPackResult> {
+    let emojis: serde_json::Map<String, serde_json::Value> = crate::load_json_file(emojis_path)?;
+    let catalog = load_emoji_catalog(emojis_path, emojis_folder)?;
+
+    std::fs::create_dir_all(output_dir).map_err(|e| AppError::WriteFile {
+        path: output_dir.display().to_string(),
+        source: e,
+    })?;
+
+    let mut pack = EmojiPack {
+        title: title.to_string(),
+        emojis: Vec::new(),
+    };
+    let mut skipped = Vec::new();
+    for entry in catalog.iter().filter(|e| matches!(e.kind, EmojiKind::Image { .. })) {
+        let (Some(file_name), None) = (&entry.file_name, &entry.problem) else {
+            let problem = entry.problem.clone().unwrap_or_default();
+            skipped.push((entry.name.clone(), problem));
+            continue;
+        };
+        let target = output_dir.join(file_name);
+        std::fs::copy(emojis_folder.join(file_name), &target).map_err(|e| AppError::WriteFile {
+            path: target.display().to_string(),
+            source: e,
+        })?;
+        pack.emojis.push(PackEmoji {
+            name: entry.name.clone(),
+            src: file_name.clone(),
+            aliases: Vec::new(),
+        });
+    }
+
+    let mut aliases = 0;
+    for entry in catalog.iter().filter(|e| matches!(e.kind, EmojiKind::Alias { .. })) {
+        let packed = resolve_alias(&emojis, &entry.name)
+            .and_then(|target| pack.emojis.iter_mut().find(|e| e.name == target));
+        match (packed, &entry.problem) {
+            (Some(emoji), _) => {
+                emoji.aliases.push(entry.name.clone());
+                aliases += 1;
+            }
+            (None, Some(problem)) => skipped.push((entry.name.clone(), problem.clone())),
+            (None, None) => skipped.push((entry.name.clone(), "alias target not packed".to_string())),
+        }
+    }
+
+    let yaml_path = output_dir.join(EMOJI_PACK_YAML);
+    std::fs::write(&yaml_path, pack.to_yaml()).map_err(|e| AppError::WriteFile {
+        path: yaml_path.display().to_string(),
+        source: e,
+    })?;
+    let json_path = output_dir.join(EMOJI_PACK_JSON);
+    let file = File::create(&json_path).map_err(|e| AppError::WriteFile {
+        path: json_path.display().to_string(),
+        source: e,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), &pack)
+        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+
+    Ok(EmojiPackResult {
+        packed: pack.emojis.len(),
+        aliases,
+        skipped,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_alias() {
+        let emojis = serde_json::json!({
+            "party": "https://emoji.slack-edge.com/T1/party/abc.gif",
+            "tada": "alias:party",
+            "yay": "alias:tada",
+            "loop-a": "alias:loop-b",
+            "loop-b": "alias:loop-a",
+            "orphan": "alias:gone",
+        });
+        let emojis = emojis.as_object().unwrap();
+
+        assert_eq!(resolve_alias(emojis, "party"), Some("party"));
+        assert_eq!(resolve_alias(emojis, "yay"), Some("party"));
+        assert_eq!(resolve_alias(emojis, "loop-a"), None);
+        assert_eq!(resolve_alias(emojis, "orphan"), None);
+    }
+
+    #[test]
+    fn test_load_emoji_catalog() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("emojis.json");
+        std::fs::write(
+            &json,
+            r#"{
+                "party": "https://emoji.slack-edge.com/T1/party/abc.gif?x=1",
+                "tada": "alias:party",
+                "cat": "https://emoji.slack-edge.com/T1/cat/def.png",
+                "orphan": "alias:gone"
+            }"#,
+        )
+        .unwrap();
+        let folder = dir.path().join("emojis");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("party.gif"), b"GIF89a").unwrap();
+        std::fs::write(folder.join("tada.gif"), b"GIF89a").unwrap();
+
+        let catalog = load_emoji_catalog(json.to_str().unwrap(), &folder).unwrap();
+        let names: Vec<&str> = catalog.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["cat", "orphan", "party", "tada"]);
+
+        assert_eq!(catalog[0].problem.as_deref(), Some("cat.png not downloaded"));
+        assert_eq!(catalog[1].problem.as_deref(), Some("alias target gone not found"));
+        assert_eq!(catalog[1].file_name, None);
+        assert_eq!(
+            catalog[2].kind,
+            EmojiKind::Image {
+                extension: "gif".to_string()
+            }
+        );
+        assert_eq!(catalog[2].file_size, Some(6));
+        assert_eq!(catalog[3].file_name.as_deref(), Some("tada.gif"));
+        assert_eq!(catalog[3].problem, None);
+    }
+
+    #[test]
+    fn test_export_emoji_pack() {
+        let dir = tempfile::tempdir().unwrap();
+        let json = dir.path().join("emojis.json");
+        std::fs::write(
+            &json,
+            r#"{
+                "party": "https://emoji.slack-edge.com/T1/party/abc.gif",
+                "tada": "alias:party",
+                "yay": "alias:tada",
+                "cat": "https://emoji.slack-edge.com/T1/cat/def.png",
+                "kitty": "alias:cat",
+                "orphan": "alias:gone"
+            }"#,
+        )
+        .unwrap();
+        let folder = dir.path().join("emojis");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("party.gif"), b"GIF89a").unwrap();
+
+        let output = dir.path().join("pack");
+        let result = export_emoji_pack(json.to_str().unwrap(), &folder, &output, "team").unwrap();
+        assert_eq!(result.packed, 1);
+        assert_eq!(result.aliases, 2);
+        let skipped: Vec<&str> = result.skipped.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(skipped, ["cat", "kitty", "orphan"]);
+
+        assert_eq!(std::fs::read(output.join("party.gif")).unwrap(), b"GIF89a");
+        assert_eq!(
+            std::fs::read_to_string(output.join(EMOJI_PACK_YAML)).unwrap(),
+            "title: \"team\"\nemojis:\n  - name: \"party\"\n    src: \"party.gif\"\n    aliases:\n      - \"tada\"\n      - \"yay\"\n"
+        );
+        let manifest: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(output.join(EMOJI_PACK_JSON)).unwrap()).unwrap();
+        assert_eq!(
+            manifest,
+            serde_json::json!({
+                "title": "team",
+                "emojis": [{"name": "party", "src": "party.gif", "aliases": ["tada", "yay"]}]
+            })
+        );
+    }
+}
@@ -6,21 +6,40 @@ pub enum AppError {
     #[error("SLACK_TOKEN environment variable not set")]
     MissingToken,
 
+    #[error("SLACK_APP_TOKEN environment variable not set (an xapp- token with connections:write)")]
+    MissingAppToken,
+
     #[error("IO error: {0}")]
     Io(#[from] io::Error),
 
     #[error("invalid date format: {0}")]
     InvalidDate(String),
 
+    #[error("invalid size: {0} (expected e.g. 500KB, 25MB or 1.5GB)")]
+    InvalidSize(String),
+
+    #[error(
+        "not enough disk space in {path}: {} needed, {} available",
+        crate::format_bytes(*.needed),
+        crate::format_bytes(*.available)
+    )]
+    InsufficientSpace { path: String, needed: u64, available: u64 },
+
     #[error("invalid timestamp format: {0}")]
     InvalidTimestamp(String),
 
+    #[error("SQL query failed: {0}")]
+    SqlQuery(String),
+
     #[error("Slack client initialization failed: {0}")]
     SlackClientInit(String),
 
     #[error("Slack API error: {0}")]
     SlackApi(String),
 
+    #[error("webhook request failed: {0}")]
+    Webhook(String),
+
     #[error("Slack rate limit error: retry after {retry_after_secs}s")]
     SlackRateLimit { retry_after_secs: u64 },
 
@@ -50,6 +69,9 @@ pub enum AppError {
     #[error("TOML serialization error: {0}")]
     TomlSerialize(String),
 
+    #[error("settings secret error: {0}")]
+    Secret(String),
+
     #[error("Meilisearch error: {0}")]
     Meilisearch(String),
 
@@ -59,11 +81,97 @@ pub enum AppError {
     #[error("Parquet error: {0}")]
     Parquet(String),
 
+    #[error("S3 {method} s3://{bucket}/{key} failed with HTTP {status}{}", .message.as_deref().map(|m| format!(": {}", m)).unwrap_or_default())]
+    S3Status {
+        method: String,
+        bucket: String,
+        key: String,
+        status: u16,
+        message: Option<String>,
+    },
+
+    #[error("S3 {method} {key} request failed: {message}")]
+    S3Request {
+        method: String,
+        key: String,
+        message: String,
+    },
+
+    #[error("S3 request signing error: {0}")]
+    S3Signing(String),
+
+    #[error("storage object not found: {0}")]
+    MissingObject(String),
+
+    #[error("{0} was committed by another writer")]
+    CommitConflict(String),
+
     #[error("formatter script error: {0}")]
     FormatterScript(String),
 
+    #[error("summarizer error: {0}")]
+    Summarizer(String),
+
     #[error("markdown conversion error: {0}")]
     MarkdownConvert(String),
+
+    #[error("syntax highlighting error: {0}")]
+    SyntaxHighlight(String),
+
+    #[error("Tantivy error: {0}")]
+    Tantivy(String),
+
+    #[error("SQLite error: {0}")]
+    Sqlite(String),
+
+    #[error("unknown field: {0}")]
+    UnknownField(String),
+
+    #[error("invalid configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("invalid query: {0}")]
+    InvalidQuery(String),
+
+    #[error("cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("query timed out after {0:?}")]
+    QueryTimeout(std::time::Duration),
+
+    #[error("input file not found: {0}")]
+    MissingInput(String),
+
+    #[error("{failed} of {total} channels failed, re-fetch them with export-conversations --repair {report}")]
+    ExportIncomplete { failed: usize, total: usize, report: String },
+
+    #[error("{failed} of {total} channels failed (see {report}), export the range again to fetch them")]
+    PartitionsIncomplete { failed: usize, total: usize, report: String },
+
+    #[error("{weeks} of {total} weeks were archived without some channels (see report.json in their partitions), the next archive run fetches them again")]
+    ArchiveIncomplete { weeks: usize, total: usize },
+
+    #[error("all {total} channels failed, see {report}")]
+    ExportFailed { total: usize, report: String },
+
+    #[error("validation failed: {0} issues found")]
+    ValidationFailed(usize),
+}
+
+/// Exit code of a command that wrote its output but left some channels out
+pub const EXIT_PARTIAL: i32 = 2;
+
+impl AppError {
+    /// Process exit code for this error: `EXIT_PARTIAL` when the output was
+    /// written without some channels, 1 otherwise
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::ExportIncomplete { .. }
+            | AppError::PartitionsIncomplete { .. }
+            | AppError::ArchiveIncomplete { .. } => EXIT_PARTIAL,
+            _ => 1,
+        }
+    }
 }
 
 pub type Result<T> = std::result::Result<T, AppError>;
@@ -99,6 +207,28 @@ mod tests {
         assert_eq!(err.to_string(), "invalid date format: not-a-date");
     }
 
+    #[test]
+    fn test_insufficient_space_display() {
+        let err = AppError::InsufficientSpace {
+            path: "attachments".to_string(),
+            needed: 3 * 1024 * 1024,
+            available: 512 * 1024,
+        };
+        assert_eq!(
+            err.to_string(),
+            "not enough disk space in attachments: 3.0 MB needed, 512.0 KB available"
+        );
+    }
+
+    #[test]
+    fn test_invalid_size_display() {
+        let err = AppError::InvalidSize("lots".to_string());
+        assert_eq!(
+            err.to_string(),
+            "invalid size: lots (expected e.g. 500KB, 25MB or 1.5GB)"
+        );
+    }
+
     #[test]
     fn test_slack_api_display() {
         let err = AppError::SlackApi("rate limited".to_string());
@@ -177,12 +307,101 @@ mod tests {
         assert_eq!(err.to_string(), "TOML serialization error: serialization failed");
     }
 
+    #[test]
+    fn test_secret_display() {
+        let err = AppError::Secret("value is not encrypted".to_string());
+        assert_eq!(err.to_string(), "settings secret error: value is not encrypted");
+    }
+
     #[test]
     fn test_meilisearch_display() {
         let err = AppError::Meilisearch("connection refused".to_string());
         assert_eq!(err.to_string(), "Meilisearch error: connection refused");
     }
 
+    #[test]
+    fn test_syntax_highlight_display() {
+        let err = AppError::SyntaxHighlight("unknown theme".to_string());
+        assert_eq!(err.to_string(), "syntax highlighting error: unknown theme");
+    }
+
+    #[test]
+    fn test_tantivy_display() {
+        let err = AppError::Tantivy("index not found".to_string());
+        assert_eq!(err.to_string(), "Tantivy error: index not found");
+    }
+
+    #[test]
+    fn test_sqlite_display() {
+        let err = AppError::Sqlite("no such table: messages".to_string());
+        assert_eq!(err.to_string(), "SQLite error: no such table: messages");
+    }
+
+    #[test]
+    fn test_unknown_field_display() {
+        let err = AppError::UnknownField("emial".to_string());
+        assert_eq!(err.to_string(), "unknown field: emial");
+    }
+
+    #[test]
+    fn test_s3_status_display() {
+        let err = AppError::S3Status {
+            method: "PUT".to_string(),
+            bucket: "archive".to_string(),
+            key: "users.json".to_string(),
+            status: 403,
+            message: Some("Access Denied".to_string()),
+        };
+        assert_eq!(err.to_string(), "S3 PUT s3://archive/users.json failed with HTTP 403: Access Denied");
+        let err = AppError::S3Status {
+            method: "GET".to_string(),
+            bucket: "archive".to_string(),
+            key: "".to_string(),
+            status: 500,
+            message: None,
+        };
+        assert_eq!(err.to_string(), "S3 GET s3://archive/ failed with HTTP 500");
+    }
+
+    #[test]
+    fn test_commit_conflict_display() {
+        let err = AppError::CommitConflict("_delta_log/00000000000000000001.json".to_string());
+        assert_eq!(
+            err.to_string(),
+            "_delta_log/00000000000000000001.json was committed by another writer"
+        );
+    }
+
+    #[test]
+    fn test_invalid_config_display() {
+        let err = AppError::InvalidConfig("invalid CORS origin: \n".to_string());
+        assert_eq!(err.to_string(), "invalid configuration: invalid CORS origin: \n");
+    }
+
+    #[test]
+    fn test_invalid_query_display() {
+        let err = AppError::InvalidQuery("unknown query \"x\"".to_string());
+        assert_eq!(err.to_string(), "invalid query: unknown query \"x\"");
+    }
+
+    #[test]
+    fn test_query_timeout_display() {
+        let err = AppError::QueryTimeout(std::time::Duration::from_secs(30));
+        assert_eq!(err.to_string(), "query timed out after 30s");
+    }
+
+    #[test]
+    fn test_cancelled_display() {
+        let err = AppError::Cancelled("2 of 5 weeks archived".to_string());
+        assert_eq!(err.to_string(), "cancelled: 2 of 5 weeks archived");
+    }
+
+    #[test]
+    fn test_missing_input_display() {
+        let err = AppError::MissingInput("conversations.json".to_string());
+        assert_eq!(err.to_string(), "input file not found: conversations.json");
+    }
+
     #[test]
     fn test_error_is_send() {
         fn assert_send<T: Send>() {}
@@ -214,4 +433,21 @@ mod tests {
         let result: Result<i32> = Err(AppError::MissingToken);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_exit_code() {
+        let partial = AppError::ExportIncomplete {
+            failed: 1,
+            total: 3,
+            report: "conversations.json.report.json".to_string(),
+        };
+        assert_eq!(partial.exit_code(), EXIT_PARTIAL);
+        assert_eq!(AppError::ArchiveIncomplete { weeks: 1, total: 4 }.exit_code(), EXIT_PARTIAL);
+        let failed = AppError::ExportFailed {
+            total: 3,
+            report: "conversations.json.report.json".to_string(),
+        };
+        assert_eq!(failed.exit_code(), 1);
+        assert_eq!(AppError::MissingToken.exit_code(), 1);
+    }
 }
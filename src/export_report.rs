@@ -0,0 +1,226 @@
+//! Per-channel outcome of a conversations export, written next to the export
+//! so channels that failed can be re-fetched (`--repair` for JSON)
+
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::OutputFormat;
+
+/// Error of the channels a cancelled export didn't get to
+pub(crate) const CANCELLED_CHANNEL: &str = "cancelled";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ChannelStatus {
+    Ok,
+    Failed,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelReport {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub status: ChannelStatus,
+    /// Messages and replies fetched
+    pub messages: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ChannelReport {
+    pub fn ok(channel_id: &str, channel_name: &str, messages: usize) -> Self {
+        ChannelReport {
+            channel_id: channel_id.to_string(),
+            channel_name: channel_name.to_string(),
+            status: ChannelStatus::Ok,
+            messages,
+            error: None,
+        }
+    }
+
+    pub fn failed(channel_id: &str, channel_name: &str, error: String) -> Self {
+        ChannelReport {
+            channel_id: channel_id.to_string(),
+            channel_name: channel_name.to_string(),
+            status: ChannelStatus::Failed,
+            messages: 0,
+            error: Some(error),
+        }
+    }
+}
+
+/// The date range and output of an export and how each channel went
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportReport {
+    /// YYYY-MM-DD
+    pub from: String,
+    pub to: String,
+    /// The export file the channels were written to
+    pub output: String,
+    pub channels: Vec<ChannelReport>,
+}
+
+impl ExportReport {
+    pub fn failed(&self) -> impl Iterator<Item = &ChannelReport> {
+        self.channels.iter().filter(|c| c.status == ChannelStatus::Failed)
+    }
+
+    /// IDs of the channels that failed
+    pub fn failed_ids(&self) -> HashSet<String> {
+        self.failed().map(|c| c.channel_id.clone()).collect()
+    }
+
+    /// Whether the export was stopped before fetching every channel
+    pub fn cancelled(&self) -> bool {
+        self.failed().any(|c| c.error.as_deref() == Some(CANCELLED_CHANNEL))
+    }
+
+    /// The export's result, given the `messages` written and where this
+    /// report was saved: an error when it was cancelled or channels failed
+    pub(crate) fn outcome(&self, format: OutputFormat, messages: usize, report_key: &str) -> Result<()> {
+        let failed = self.failed().count();
+        let total = self.channels.len();
+        if self.cancelled() {
+            let remedy = match format {
+                OutputFormat::Json => format!("fetch the rest with export-conversations --repair {}", report_key),
+                OutputFormat::Parquet => "export the range again to fetch the rest".to_string(),
+            };
+            return Err(AppError::Cancelled(format!(
+                "{} of {} channels fetched ({} messages) and written to {}, {}",
+                total - failed,
+                total,
+                messages,
+                self.output,
+                remedy
+            )));
+        }
+        let report = report_key.to_string();
+        match (failed, format) {
+            (0, _) => Ok(()),
+            (failed, _) if failed == total => Err(AppError::ExportFailed { total, report }),
+            (failed, OutputFormat::Json) => Err(AppError::ExportIncomplete { failed, total, report }),
+            (failed, OutputFormat::Parquet) => Err(AppError::PartitionsIncomplete { failed, total, report }),
+        }
+    }
+
+    /// Replace the entries of re-fetched channels with their new outcome
+    pub fn update(&mut self, refetched: Vec<ChannelReport>) {
+        for report in refetched {
+            match self.channels.iter_mut().find(|c| c.channel_id == report.channel_id) {
+                Some(existing) => *existing = report,
+                None => self.channels.push(report),
+            }
+        }
+    }
+}
+
+/// Where the report of the export at `output_path` goes: `conversations.json`
+/// gets `conversations.json.report.json`
+pub fn report_path(output_path: &Path) -> PathBuf {
+    let mut name = output_path.as_os_str().to_owned();
+    name.push(".report.json");
+    PathBuf::from(name)
+}
+
+/// Put re-fetched conversations into an export, replacing the channels they
+/// cover and appending the rest
+pub fn merge_refetched(export: &mut Vec<Value>, refetched: Vec<Value>) {
+    for conversation in refetched {
+        let channel_id = conversation.get("channel_id").cloned();
+        match export.iter_mut().find(|c| c.get("channel_id") == channel_id.as_ref()) {
+            Some(existing) => *existing = conversation,
+            None => export.push(conversation),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn report() -> ExportReport {
+        ExportReport {
+            from: "2024-01-01".to_string(),
+            to: "2024-01-07".to_string(),
+            output: "conversations.json".to_string(),
+            channels: vec![
+                ChannelReport::ok("C1", "general", 12),
+                ChannelReport::failed("C2", "random", "Slack API error: ratelimited".to_string()),
+            ],
+        }
+    }
+
+    #[test]
+    fn test_report_round_trip() {
+        let json = serde_json::to_value(report()).unwrap();
+        assert_eq!(json["channels"][0]["status"], "ok");
+        assert!(json["channels"][0].get("error").is_none());
+        assert_eq!(json["channels"][1]["status"], "failed");
+
+        let parsed: ExportReport = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, report());
+        assert_eq!(parsed.failed_ids(), HashSet::from(["C2".to_string()]));
+    }
+
+    #[test]
+    fn test_update() {
+        let mut report = report();
+        report.update(vec![ChannelReport::ok("C2", "random", 3)]);
+        assert_eq!(report.failed().count(), 0);
+        assert_eq!(report.channels[1].messages, 3);
+    }
+
+    #[test]
+    fn test_outcome() {
+        let key = "conversations.json.report.json";
+        let mut report = report();
+        assert!(matches!(
+            report.outcome(OutputFormat::Json, 12, key),
+            Err(AppError::ExportIncomplete { failed: 1, total: 2, .. })
+        ));
+        assert!(matches!(
+            report.outcome(OutputFormat::Parquet, 12, key),
+            Err(AppError::PartitionsIncomplete { failed: 1, total: 2, .. })
+        ));
+
+        report.channels[0] = ChannelReport::failed("C1", "general", "not_in_channel".to_string());
+        assert!(matches!(
+            report.outcome(OutputFormat::Json, 0, key),
+            Err(AppError::ExportFailed { total: 2, .. })
+        ));
+
+        report.channels[1] = ChannelReport::failed("C2", "random", CANCELLED_CHANNEL.to_string());
+        assert!(matches!(report.outcome(OutputFormat::Json, 0, key), Err(AppError::Cancelled(_))));
+
+        report.update(vec![ChannelReport::ok("C1", "general", 1), ChannelReport::ok("C2", "random", 2)]);
+        assert!(report.outcome(OutputFormat::Parquet, 3, key).is_ok());
+    }
+
+    #[test]
+    fn test_report_path() {
+        assert_eq!(
+            report_path(Path::new("out/conversations.json")),
+            PathBuf::from("out/conversations.json.report.json")
+        );
+    }
+
+    #[test]
+    fn test_merge_refetched() {
+        let mut export = vec![json!({"channel_id": "C1", "messages": [{"ts": "1.0"}]})];
+        merge_refetched(
+            &mut export,
+            vec![
+                json!({"channel_id": "C1", "messages": [{"ts": "1.0"}, {"ts": "2.0"}]}),
+                json!({"channel_id": "C2", "messages": [{"ts": "3.0"}]}),
+            ],
+        );
+        assert_eq!(export.len(), 2);
+        assert_eq!(export[0]["messages"].as_array().unwrap().len(), 2);
+        assert_eq!(export[1]["channel_id"], "C2");
+    }
+}
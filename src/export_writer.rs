@@ -0,0 +1,164 @@
+//! Conversation exports written channel by channel as they are fetched, so
+//! memory use is bounded by the largest channel instead of the whole date
+//! range of the workspace
+
+use std::io::Write;
+
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::parquet::{ConversationsParquetWriter, PartitionBy};
+use crate::provenance::Provenance;
+use crate::slack::ConversationExport;
+use crate::storage::{Storage, StorageWriter};
+use crate::OutputFormat;
+
+/// A JSON array written one item at a time, byte for byte what
+/// `serde_json::to_vec_pretty` gives for the whole array
+pub struct JsonArrayWriter<'a> {
+    out: Box<dyn StorageWriter + 'a>,
+    key: String,
+    items: usize,
+}
+
+impl<'a> JsonArrayWriter<'a> {
+    pub fn new(storage: &'a dyn Storage, key: &str) -> Result<Self> {
+        Ok(JsonArrayWriter {
+            out: storage.writer(key)?,
+            key: key.to_string(),
+            items: 0,
+        })
+    }
+
+    pub fn write<T: Serialize>(&mut self, item: &T) -> Result<()> {
+        let json = serde_json::to_string_pretty(item).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+        // Newlines inside strings are escaped, so every one is a line break
+        let item = json.replace('\n', "\n  ");
+        let separator = if self.items == 0 { "[\n  " } else { ",\n  " };
+        self.write_bytes(separator.as_bytes())?;
+        self.write_bytes(item.as_bytes())?;
+        self.items += 1;
+        Ok(())
+    }
+
+    /// Close the array, returning how many items it has
+    pub fn finish(mut self) -> Result<usize> {
+        let end = if self.items == 0 { "[]" } else { "\n]" };
+        self.write_bytes(end.as_bytes())?;
+        self.out.finish()?;
+        Ok(self.items)
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) -> Result<()> {
+        self.out.write_all(bytes).map_err(|e| AppError::WriteFile {
+            path: self.key.clone(),
+            source: e,
+        })
+    }
+}
+
+/// Destination of a conversation export in either output format
+pub enum ConversationWriter<'a> {
+    /// A JSON array of `ConversationExport`s
+    Json(JsonArrayWriter<'a>),
+    /// `year=YYYY/week=WW` partitions
+    Parquet(ConversationsParquetWriter<'a>),
+}
+
+impl<'a> ConversationWriter<'a> {
+    pub fn new(storage: &'a dyn Storage, key: &str, format: OutputFormat) -> Result<Self> {
+        Ok(match format {
+            OutputFormat::Json => ConversationWriter::Json(JsonArrayWriter::new(storage, key)?),
+            OutputFormat::Parquet => ConversationWriter::Parquet(ConversationsParquetWriter::new(storage, key)),
+        })
+    }
+
+    /// Split parquet partitions further; JSON is a single file either way
+    pub fn partitioned_by(self, partition_by: PartitionBy) -> Self {
+        match self {
+            ConversationWriter::Parquet(writer) => ConversationWriter::Parquet(writer.partitioned_by(partition_by)),
+            json => json,
+        }
+    }
+
+    /// Record the export's provenance in the parquet footers; JSON has no
+    /// place for it
+    pub fn provenance(self, provenance: Provenance) -> Self {
+        match self {
+            ConversationWriter::Parquet(writer) => ConversationWriter::Parquet(writer.provenance(provenance)),
+            json => json,
+        }
+    }
+
+    pub fn write(&mut self, conversation: &ConversationExport) -> Result<()> {
+        match self {
+            ConversationWriter::Json(writer) => writer.write(conversation),
+            ConversationWriter::Parquet(writer) => {
+                let value = serde_json::to_value(conversation).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+                writer.write(&value).map(|_| ())
+            }
+        }
+    }
+
+    /// Complete the output, returning the keys of the files written;
+    /// nothing is visible under the key before this
+    pub fn finish(self) -> Result<Vec<String>> {
+        match self {
+            ConversationWriter::Json(writer) => {
+                let key = writer.key.clone();
+                writer.finish()?;
+                Ok(vec![key])
+            }
+            ConversationWriter::Parquet(writer) => {
+                let keys = writer.keys();
+                writer.finish()?;
+                Ok(keys)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+
+    fn conversation(id: &str) -> ConversationExport {
+        ConversationExport {
+            channel_id: id.to_string(),
+            channel_name: "general".to_string(),
+            messages: vec![serde_json::json!({"ts": "1705312800.000000", "text": "line one\nline two"})],
+        }
+    }
+
+    #[test]
+    fn test_json_array_writer_matches_pretty_output() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let conversations = vec![conversation("C1"), conversation("C2")];
+
+        let mut writer = JsonArrayWriter::new(&storage, "conversations.json").unwrap();
+        for conversation in &conversations {
+            writer.write(conversation).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), 2);
+        assert_eq!(
+            storage.get("conversations.json").unwrap(),
+            Some(serde_json::to_vec_pretty(&conversations).unwrap())
+        );
+
+        JsonArrayWriter::new(&storage, "empty.json").unwrap().finish().unwrap();
+        assert_eq!(storage.get("empty.json").unwrap(), Some(b"[]".to_vec()));
+    }
+
+    #[test]
+    fn test_conversation_writer_parquet() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let mut writer = ConversationWriter::new(&storage, "threads", OutputFormat::Parquet).unwrap();
+        writer.write(&conversation("C1")).unwrap();
+        let keys = writer.finish().unwrap();
+        assert_eq!(keys, vec!["threads/year=2024/week=03/threads.parquet"]);
+        assert_eq!(storage.list("threads").unwrap(), keys);
+    }
+}
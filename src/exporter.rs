@@ -0,0 +1,344 @@
+//! Library entry point for embedding the exports in other Rust tools:
+//!
+//! ```text
+//! let exporter = SlackExporter::builder()
+//!     .token(token)
+//!     .channels(["C01234567"])
+//!     .date_range(from, to)
+//!     .format(OutputFormat::Parquet)
+//!     .build()?;
+//! let fetched = exporter.conversations(SlackApiCallbacks::new()).await?;
+//!
+//! // Progress arrives as `ProgressEvent`s on a channel
+//! let (events, received) = std::sync::mpsc::channel();
+//! std::thread::spawn(move || received.iter().for_each(|event| println!("{:?}", event)));
+//! let callbacks = SlackApiCallbacks::new().with_events(&events);
+//! exporter.export_conversations(Path::new("conversations"), callbacks).await?;
+//! ```
+
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+pub use crate::dry_run::{ConversationsPlan, PlannedChannel};
+pub use crate::export_report::{ChannelReport, ChannelStatus};
+pub use crate::slack::ConversationExport;
+pub use slack_morphism::prelude::{SlackChannelInfo, SlackUser};
+
+use crate::error::{AppError, Result};
+use crate::storage::Storage;
+use crate::{default_from_date, default_to_date, load_token, slack, OutputFormat, SlackApiCallbacks};
+
+/// Conversations fetched by `SlackExporter::conversations`
+#[derive(Debug, Clone)]
+pub struct Conversations {
+    pub conversations: Vec<ConversationExport>,
+    /// How each channel went; a channel whose requests failed is reported
+    /// here and left out of `conversations`
+    pub channels: Vec<ChannelReport>,
+}
+
+impl Conversations {
+    /// Messages and replies fetched across all channels
+    pub fn message_count(&self) -> usize {
+        self.conversations.iter().map(|c| c.messages.len()).sum()
+    }
+
+    pub fn failed(&self) -> impl Iterator<Item = &ChannelReport> {
+        self.channels.iter().filter(|c| c.status == ChannelStatus::Failed)
+    }
+}
+
+/// Exports a workspace's users, channels and conversations, either returned
+/// as values or written in the configured format. Built with
+/// `SlackExporter::builder()`.
+#[derive(Debug, Clone)]
+pub struct SlackExporter {
+    token: String,
+    channels: Option<HashSet<String>>,
+    from: NaiveDate,
+    to: NaiveDate,
+    format: OutputFormat,
+    auto_join: bool,
+    leave_joined: bool,
+}
+
+/// Configures a `SlackExporter`; everything but the token has a default
+#[derive(Debug, Clone, Default)]
+pub struct SlackExporterBuilder {
+    token: Option<String>,
+    channels: Option<HashSet<String>>,
+    from: Option<NaiveDate>,
+    to: Option<NaiveDate>,
+    format: OutputFormat,
+    auto_join: bool,
+    leave_joined: bool,
+}
+
+impl SlackExporterBuilder {
+    /// Slack token to call the API with; `SLACK_TOKEN` when not set
+    pub fn token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    /// IDs of the channels to export conversations from; all public channels
+    /// when not set
+    pub fn channels<I, S>(mut self, channel_ids: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.channels = Some(channel_ids.into_iter().map(Into::into).collect());
+        self
+    }
+
+    /// Days to export conversations from, both inclusive; the last 30 days
+    /// when not set
+    pub fn date_range(mut self, from: NaiveDate, to: NaiveDate) -> Self {
+        self.from = Some(from);
+        self.to = Some(to);
+        self
+    }
+
+    /// Format of the `export_*` methods' output, JSON when not set
+    pub fn format(mut self, format: OutputFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Join the selected public channels the token is not in before
+    /// fetching conversations, since bot tokens can only read the channels
+    /// they are members of; off when not set
+    pub fn auto_join(mut self, auto_join: bool) -> Self {
+        self.auto_join = auto_join;
+        self
+    }
+
+    /// Leave the channels `auto_join` joined once the conversations are
+    /// fetched; off when not set
+    pub fn leave_joined(mut self, leave_joined: bool) -> Self {
+        self.leave_joined = leave_joined;
+        self
+    }
+
+    pub fn build(self) -> Result<SlackExporter> {
+        let token = match self.token {
+            Some(token) => token,
+            None => load_token()?,
+        };
+        if token.trim().is_empty() {
+            return Err(AppError::MissingToken);
+        }
+        let from = self.from.unwrap_or_else(default_from_date);
+        let to = self.to.unwrap_or_else(default_to_date);
+        if from > to {
+            return Err(AppError::InvalidDate(format!("{} is after {}", from, to)));
+        }
+        Ok(SlackExporter {
+            token,
+            channels: self.channels,
+            from,
+            to,
+            format: self.format,
+            auto_join: self.auto_join,
+            leave_joined: self.leave_joined,
+        })
+    }
+}
+
+impl SlackExporter {
+    pub fn builder() -> SlackExporterBuilder {
+        SlackExporterBuilder::default()
+    }
+
+    pub fn from_date(&self) -> NaiveDate {
+        self.from
+    }
+
+    pub fn to_date(&self) -> NaiveDate {
+        self.to
+    }
+
+    pub fn format(&self) -> OutputFormat {
+        self.format
+    }
+
+    pub async fn users(&self) -> Result<Vec<SlackUser>> {
+        slack::fetch_users(&self.token).await
+    }
+
+    /// The workspace's public channels, regardless of the selected ones
+    pub async fn channels(&self) -> Result<Vec<SlackChannelInfo>> {
+        slack::fetch_channel_list(&self.token).await
+    }
+
+    /// The selected channels an export would fetch, from the channel list
+    /// only; no message is downloaded
+    pub async fn plan_conversations(&self) -> Result<ConversationsPlan> {
+        let listed = self.channels().await?;
+        ConversationsPlan::new(&listed, self.channels.as_ref(), (self.from, self.to))
+    }
+
+    /// Fetch the selected channels' messages in the date range. A channel
+    /// whose requests fail is reported as failed and the rest are still
+    /// fetched.
+    pub async fn conversations(&self, callbacks: SlackApiCallbacks<'_>) -> Result<Conversations> {
+        let fetch = slack::fetch_conversations(
+            &self.token,
+            (self.from, self.to),
+            self.channels.as_ref(),
+            callbacks,
+            true,
+        );
+        let (conversations, channels) = self.joined(callbacks, fetch).await?;
+        Ok(Conversations { conversations, channels })
+    }
+
+    /// Run `fetch`, joining the channels it needs first and leaving them
+    /// afterwards as configured
+    async fn joined<T>(&self, callbacks: SlackApiCallbacks<'_>, fetch: impl Future<Output = Result<T>>) -> Result<T> {
+        if !self.auto_join {
+            return fetch.await;
+        }
+        let plan = self.plan_conversations().await?;
+        let joined = match slack::join_channels(&self.token, &plan.to_join(), callbacks).await {
+            Ok(joined) => joined,
+            Err(stopped) => {
+                if self.leave_joined {
+                    slack::leave_channels(&self.token, &stopped.joined, callbacks).await;
+                }
+                return Err(stopped.error);
+            }
+        };
+        let fetched = fetch.await;
+        if self.leave_joined {
+            slack::leave_channels(&self.token, &joined, callbacks).await;
+        }
+        fetched
+    }
+
+    /// Write the users to `output`, returning how many were written
+    pub async fn export_users(&self, output: &Path) -> Result<usize> {
+        slack::export_users(&self.token, output, self.format).await
+    }
+
+    /// `export_users` to `key` in `storage`
+    pub async fn export_users_to(&self, storage: &dyn Storage, key: &str) -> Result<usize> {
+        slack::export_users_to(&self.token, storage, key, self.format).await
+    }
+
+    /// Write the public channels to `output`, returning how many were written
+    pub async fn export_channels(&self, output: &Path) -> Result<usize> {
+        slack::export_channels(&self.token, output, self.format).await
+    }
+
+    /// `export_channels` to `key` in `storage`
+    pub async fn export_channels_to(&self, storage: &dyn Storage, key: &str) -> Result<usize> {
+        slack::export_channels_to(&self.token, storage, key, self.format).await
+    }
+
+    /// Write the conversations to `output`, a file for JSON and a directory
+    /// of partitions for Parquet, returning the number of messages written
+    pub async fn export_conversations(&self, output: &Path, callbacks: SlackApiCallbacks<'_>) -> Result<usize> {
+        let export = slack::export_conversations(
+            &self.token,
+            self.from,
+            self.to,
+            output,
+            self.channels.as_ref(),
+            callbacks,
+            self.format,
+        );
+        self.joined(callbacks, export).await
+    }
+
+    /// `export_conversations` to `key` in `storage`
+    pub async fn export_conversations_to(
+        &self,
+        storage: &dyn Storage,
+        key: &str,
+        callbacks: SlackApiCallbacks<'_>,
+    ) -> Result<usize> {
+        let export = slack::export_conversations_to(
+            &self.token,
+            (self.from, self.to),
+            storage,
+            key,
+            self.channels.as_ref(),
+            callbacks,
+            self.format,
+        );
+        self.joined(callbacks, export).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_builder_defaults() {
+        let exporter = SlackExporter::builder().token("xoxb-test").build().unwrap();
+        assert_eq!(exporter.from_date(), default_from_date());
+        assert_eq!(exporter.to_date(), default_to_date());
+        assert_eq!(exporter.format(), OutputFormat::Json);
+        assert!(exporter.channels.is_none());
+        assert!(!exporter.auto_join && !exporter.leave_joined);
+    }
+
+    #[test]
+    fn test_builder_options() {
+        let exporter = SlackExporter::builder()
+            .token("xoxb-test")
+            .channels(["C1", "C2"])
+            .date_range(date("2024-01-01"), date("2024-01-31"))
+            .format(OutputFormat::Parquet)
+            .auto_join(true)
+            .leave_joined(true)
+            .build()
+            .unwrap();
+        assert_eq!(exporter.from_date(), date("2024-01-01"));
+        assert_eq!(exporter.to_date(), date("2024-01-31"));
+        assert_eq!(exporter.format(), OutputFormat::Parquet);
+        assert_eq!(
+            exporter.channels,
+            Some(HashSet::from(["C1".to_string(), "C2".to_string()]))
+        );
+        assert!(exporter.auto_join && exporter.leave_joined);
+    }
+
+    #[test]
+    fn test_builder_rejects_invalid_input() {
+        let reversed = SlackExporter::builder()
+            .token("xoxb-test")
+            .date_range(date("2024-02-01"), date("2024-01-01"))
+            .build();
+        assert!(matches!(reversed, Err(AppError::InvalidDate(_))));
+        let empty = SlackExporter::builder().token(" ").build();
+        assert!(matches!(empty, Err(AppError::MissingToken)));
+    }
+
+    #[test]
+    fn test_conversations_counts() {
+        let conversations = Conversations {
+            conversations: vec![ConversationExport {
+                channel_id: "C1".to_string(),
+                channel_name: "general".to_string(),
+                messages: vec![serde_json::json!({"ts": "1.0"}), serde_json::json!({"ts": "2.0"})],
+            }],
+            channels: vec![
+                ChannelReport::ok("C1", "general", 2),
+                ChannelReport::failed("C2", "random", "ratelimited".to_string()),
+            ],
+        };
+        assert_eq!(conversations.message_count(), 2);
+        assert_eq!(conversations.failed().map(|c| c.channel_id.as_str()).collect::<Vec<_>>(), vec!["C2"]);
+    }
+}
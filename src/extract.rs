@@ -0,0 +1,251 @@
+//! Read archived weeks back from parquet into the conversations.json
+//! structure, so the JSON tooling (markdown, index, stats) works on them
+
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde_json::{json, Value};
+
+use crate::error::{AppError, Result};
+use crate::parquet::{read_parquet_as_json, week_threads_files, JsonRow};
+
+/// Every `year=*/week=*` partition directory under `base_path` with threads
+/// files, with its ISO year and week, sorted
+pub fn archived_weeks(base_path: &Path) -> Vec<(i32, i32, PathBuf)> {
+    let partition = |path: &Path, prefix: &str| -> Option<i32> {
+        path.file_name()?.to_str()?.strip_prefix(prefix)?.parse().ok()
+    };
+    let mut weeks = Vec::new();
+    let Ok(years) = fs::read_dir(base_path) else {
+        return weeks;
+    };
+    for year_dir in years.flatten().map(|e| e.path()) {
+        let (Some(year), Ok(week_dirs)) = (partition(&year_dir, "year="), fs::read_dir(&year_dir)) else {
+            continue;
+        };
+        for week_dir in week_dirs.flatten().map(|e| e.path()) {
+            if let Some(week) = partition(&week_dir, "week=")
+                && !week_threads_files(&week_dir).is_empty()
+            {
+                weeks.push((year, week, week_dir));
+            }
+        }
+    }
+    weeks.sort();
+    weeks
+}
+
+/// An archived week as conversations
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExtractedWeek {
+    pub conversations: Vec<Value>,
+    pub messages: usize,
+    pub replies: usize,
+    /// Replies posted this week to threads started in an earlier week; they
+    /// are extracted with their thread's week instead
+    pub earlier_threads: usize,
+}
+
+fn text<'a>(row: &'a JsonRow, key: &str) -> Option<&'a str> {
+    row.get(key).and_then(|v| v.as_str())
+}
+
+fn is_reply(row: &JsonRow) -> bool {
+    row.get("is_reply").and_then(|v| v.as_bool()).unwrap_or(false)
+}
+
+/// A message object as the exporter writes it, from a parquet row
+fn row_to_message(row: &JsonRow) -> Value {
+    let mut message = json!({"type": "message", "ts": text(row, "ts")});
+    if let Some(fields) = message.as_object_mut() {
+        for key in ["user", "text", "thread_ts"] {
+            if let Some(value) = text(row, key) {
+                fields.insert(key.to_string(), json!(value));
+            }
+        }
+        if let Some(blocks) = text(row, "blocks").and_then(|b| serde_json::from_str::<Value>(b).ok()) {
+            fields.insert("blocks".to_string(), blocks);
+        }
+    }
+    message
+}
+
+/// Numeric order of Slack timestamps, which all have six decimals
+pub(crate) fn ts_order(a: &str, b: &str) -> std::cmp::Ordering {
+    a.len().cmp(&b.len()).then_with(|| a.cmp(b))
+}
+
+/// Rebuild conversations from the rows of one week (`week_rows`) and the
+/// replies of later weeks (`later_rows`, other rows are ignored): channels by
+/// name, messages newest first, each thread's replies oldest first
+pub fn rows_to_conversations(week_rows: &[JsonRow], later_rows: &[JsonRow]) -> ExtractedWeek {
+    let parents: HashSet<(&str, &str)> = week_rows
+        .iter()
+        .filter(|row| !is_reply(row))
+        .filter_map(|row| Some((text(row, "channel_id")?, text(row, "ts")?)))
+        .collect();
+
+    let mut replies: BTreeMap<(&str, &str), Vec<&JsonRow>> = BTreeMap::new();
+    // A reply stored both with its thread and in the week it was posted
+    // is listed once
+    let mut seen = HashSet::new();
+    let mut earlier_threads = 0;
+    for (row, same_week) in week_rows.iter().map(|r| (r, true)).chain(later_rows.iter().map(|r| (r, false))) {
+        if !is_reply(row) {
+            continue;
+        }
+        let (Some(channel_id), Some(thread_ts)) = (text(row, "channel_id"), text(row, "thread_ts")) else {
+            continue;
+        };
+        if parents.contains(&(channel_id, thread_ts)) {
+            if !seen.insert((channel_id, text(row, "ts").unwrap_or_default())) {
+                continue;
+            }
+            replies.entry((channel_id, thread_ts)).or_default().push(row);
+        } else if same_week {
+            earlier_threads += 1;
+        }
+    }
+
+    let mut channels: BTreeMap<(&str, &str), Vec<&JsonRow>> = BTreeMap::new();
+    for row in week_rows.iter().filter(|row| !is_reply(row)) {
+        let key = (text(row, "channel_name").unwrap_or_default(), text(row, "channel_id").unwrap_or_default());
+        channels.entry(key).or_default().push(row);
+    }
+
+    let mut extracted = ExtractedWeek {
+        conversations: Vec::new(),
+        messages: 0,
+        replies: 0,
+        earlier_threads,
+    };
+    for ((channel_name, channel_id), mut rows) in channels {
+        rows.sort_by(|a, b| ts_order(text(b, "ts").unwrap_or_default(), text(a, "ts").unwrap_or_default()));
+        let messages: Vec<Value> = rows
+            .into_iter()
+            .map(|row| {
+                let mut message = row_to_message(row);
+                let ts = text(row, "ts").unwrap_or_default();
+                if let Some(thread) = replies.get_mut(&(channel_id, ts))
+                    && let Some(fields) = message.as_object_mut()
+                {
+                    thread.sort_by(|a, b| ts_order(text(a, "ts").unwrap_or_default(), text(b, "ts").unwrap_or_default()));
+                    extracted.replies += thread.len();
+                    fields.insert("reply_count".to_string(), json!(thread.len()));
+                    let thread_replies = thread.iter().map(|r| row_to_message(r)).collect();
+                    fields.insert("thread_replies".to_string(), Value::Array(thread_replies));
+                }
+                message
+            })
+            .collect();
+        extracted.messages += messages.len();
+        extracted.conversations.push(json!({
+            "channel_id": channel_id,
+            "channel_name": channel_name,
+            "messages": messages,
+        }));
+    }
+    extracted
+}
+
+/// Extract ISO `year`-W`week` from the archive in `base_path`, with the
+/// replies its threads got in later weeks
+pub fn extract_week(base_path: &Path, year: i32, week: u32) -> Result<ExtractedWeek> {
+    let weeks = archived_weeks(base_path);
+    let week = week as i32;
+    let Some((_, _, week_dir)) = weeks.iter().find(|(y, w, _)| (*y, *w) == (year, week)) else {
+        return Err(AppError::MissingInput(format!(
+            "{}/year={}/week={:02}/threads.parquet",
+            base_path.display(),
+            year,
+            week
+        )));
+    };
+
+    let mut week_rows = Vec::new();
+    for file in week_threads_files(week_dir) {
+        week_rows.extend(read_parquet_as_json(&file)?);
+    }
+    let mut later_rows = Vec::new();
+    for (_, _, later_dir) in weeks.iter().filter(|(y, w, _)| (*y, *w) > (year, week)) {
+        for file in week_threads_files(later_dir) {
+            later_rows.extend(read_parquet_as_json(&file)?.into_iter().filter(is_reply));
+        }
+    }
+    Ok(rows_to_conversations(&week_rows, &later_rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parquet::write_conversations_parquet;
+
+    #[test]
+    fn test_extract_week_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        // 1705312800 is Monday 2024-01-15 (W03), 1705917600 is 2024-01-22 (W04)
+        let conversations = vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1705399200.000100", "user": "U2", "text": "later",
+                     "blocks": [{"type": "rich_text", "elements": []}]},
+                    {"ts": "1705312800.000100", "user": "U1", "text": "question", "thread_ts": "1705312800.000100",
+                     "thread_replies": [
+                         {"ts": "1705312860.000100", "user": "U2", "text": "same week"},
+                         {"ts": "1705917600.000100", "user": "U3", "text": "next week"}
+                     ]}
+                ]
+            }),
+            json!({
+                "channel_id": "C2",
+                "channel_name": "announcements",
+                "messages": [
+                    {"ts": "1705917700.000100", "text": "next week's thread",
+                     "thread_replies": [{"ts": "1705917800.000100", "text": "reply"}]}
+                ]
+            }),
+        ];
+        write_conversations_parquet(dir.path(), &conversations).unwrap();
+        assert_eq!(archived_weeks(dir.path()).len(), 2);
+
+        let week = extract_week(dir.path(), 2024, 3).unwrap();
+        assert_eq!((week.messages, week.replies, week.earlier_threads), (2, 2, 0));
+        assert_eq!(week.conversations.len(), 1);
+        let general = &week.conversations[0];
+        assert_eq!(general["channel_name"], "general");
+        let messages = general["messages"].as_array().unwrap();
+        assert_eq!(messages[0]["ts"], "1705399200.000100");
+        assert_eq!(messages[0]["blocks"][0]["type"], "rich_text");
+        assert_eq!(messages[1]["reply_count"], 2);
+        assert_eq!(messages[1]["thread_replies"][1]["text"], "next week");
+        assert_eq!(messages[1]["thread_replies"][1]["thread_ts"], "1705312800.000100");
+
+        let next = extract_week(dir.path(), 2024, 4).unwrap();
+        // The W03 thread's reply posted in W04 is stored with its thread
+        assert_eq!((next.messages, next.replies, next.earlier_threads), (1, 1, 0));
+    }
+
+    #[test]
+    fn test_rows_to_conversations_overflow_once() {
+        let row = |ts: &str, thread_ts: &str| {
+            json!({"ts": ts, "thread_ts": thread_ts, "is_reply": ts != thread_ts, "channel_id": "C1", "channel_name": "general"})
+                .as_object()
+                .unwrap()
+                .clone()
+        };
+        // A reply kept in a later week by an older archive and merged into its thread's
+        let week = [row("1.000100", "1.000100"), row("2.000100", "1.000100")];
+        let later = [row("2.000100", "1.000100"), row("3.000100", "1.000100")];
+        let extracted = rows_to_conversations(&week, &later);
+        assert_eq!((extracted.messages, extracted.replies), (1, 2));
+    }
+
+    #[test]
+    fn test_extract_missing_week() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(matches!(extract_week(dir.path(), 2024, 7), Err(AppError::MissingInput(_))));
+    }
+}
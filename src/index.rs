@@ -8,10 +8,12 @@ use crate::slack_render::{render_blocks_as_markdown, SlackReferences};
 use slack_morphism::prelude::{SlackBlock, SlackChannelId, SlackUserId};
 
 use crate::error::{AppError, Result};
+use crate::json_stream::ConversationSource;
 use crate::ProgressCallback;
 
 /// A user entry in the index
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct IndexUser {
     pub id: String,
     pub name: String,
@@ -19,6 +21,7 @@ pub struct IndexUser {
 
 /// Channel information in the index
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct IndexChannel {
     pub id: String,
     pub name: String,
@@ -26,6 +29,7 @@ pub struct IndexChannel {
 
 /// A single entry in the conversation index
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[cfg_attr(feature = "server", derive(utoipa::ToSchema))]
 pub struct IndexEntry {
     /// Unique identifier (ts with dots replaced for Meilisearch compatibility)
     pub id: String,
@@ -39,16 +43,192 @@ pub struct IndexEntry {
     pub users: Vec<IndexUser>,
     /// Channel information
     pub channel: IndexChannel,
+    /// Unix timestamp of the message in seconds (used for date filters and sorting)
+    #[serde(default)]
+    pub timestamp: i64,
+    /// Whether the message or any of its thread replies has file attachments
+    #[serde(default)]
+    pub has_attachments: bool,
 }
 
-/// Convert a Slack timestamp to ISO 8601 datetime string
-fn slack_ts_to_iso8601(ts: &str) -> String {
-    // Slack ts format: "1767636991.559059" (seconds.microseconds)
-    let secs: i64 = ts
-        .split('.')
+/// Tag inserted before a matched term in search snippets
+pub const HIGHLIGHT_PRE_TAG: &str = "<mark>";
+/// Tag inserted after a matched term in search snippets
+pub const HIGHLIGHT_POST_TAG: &str = "</mark>";
+
+/// A search hit with the highlighted context of the match
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub entry: IndexEntry,
+    /// Text cropped around the match, with matched terms wrapped in highlight tags
+    pub snippet: Option<String>,
+}
+
+/// Split highlighted text into `(segment, is_match)` pieces for rendering
+pub fn split_highlights(text: &str) -> Vec<(&str, bool)> {
+    let mut segments = Vec::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(HIGHLIGHT_PRE_TAG) {
+        let (before, after) = rest.split_at(start);
+        let after = after.get(HIGHLIGHT_PRE_TAG.len()..).unwrap_or_default();
+        let end = after.find(HIGHLIGHT_POST_TAG).unwrap_or(after.len());
+        let (matched, remaining) = after.split_at(end);
+
+        if !before.is_empty() {
+            segments.push((before, false));
+        }
+        if !matched.is_empty() {
+            segments.push((matched, true));
+        }
+        rest = remaining.get(HIGHLIGHT_POST_TAG.len()..).unwrap_or_default();
+    }
+    if !rest.is_empty() {
+        segments.push((rest, false));
+    }
+
+    segments
+}
+
+#[cfg(feature = "tui")]
+/// Characters of context kept before the first match in local search snippets
+const SNIPPET_BEFORE: usize = 40;
+#[cfg(feature = "tui")]
+/// Maximum length in characters of local search snippets
+const SNIPPET_LEN: usize = 160;
+
+#[cfg(feature = "tui")]
+/// A conversation index loaded in memory, searchable without a server
+#[derive(Debug, Clone, Default)]
+pub struct LocalIndex {
+    entries: Vec<IndexEntry>,
+    /// Lowercased text, channel and user names of each entry
+    haystacks: Vec<String>,
+}
+
+#[cfg(feature = "tui")]
+impl LocalIndex {
+    /// Load an index written by `export-index` in JSON format
+    pub fn load(path: &str) -> Result<Self> {
+        let entries: Vec<IndexEntry> = crate::load_json_file(path)?;
+        Ok(Self::new(entries))
+    }
+
+    pub fn new(mut entries: Vec<IndexEntry>) -> Self {
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.timestamp));
+        let haystacks = entries
+            .iter()
+            .map(|entry| {
+                let users: Vec<&str> = entry.users.iter().map(|u| u.name.as_str()).collect();
+                format!("{}\n{}\n{}", entry.text, entry.channel.name, users.join(" ")).to_lowercase()
+            })
+            .collect();
+        Self { entries, haystacks }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Newest entries containing every whitespace-separated term of `query`
+    /// (case-insensitive) in their text, channel or user names
+    pub fn search(&self, query: &str, limit: usize) -> Vec<SearchHit> {
+        let query = query.to_lowercase();
+        let terms: Vec<&str> = query.split_whitespace().collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        self.entries
+            .iter()
+            .zip(&self.haystacks)
+            .filter(|(_, haystack)| terms.iter().all(|term| haystack.contains(term)))
+            .take(limit)
+            .map(|(entry, _)| SearchHit {
+                entry: entry.clone(),
+                snippet: local_snippet(&entry.text, &terms),
+            })
+            .collect()
+    }
+}
+
+#[cfg(feature = "tui")]
+/// Text cropped around the first of `terms` found in `text`, with every
+/// occurrence of the terms wrapped in highlight tags
+fn local_snippet(text: &str, terms: &[&str]) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    // Per-character lowercasing keeps indices aligned with `chars`
+    let lower: Vec<char> = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect();
+
+    let mut highlighted = vec![false; chars.len()];
+    for term in terms {
+        let term: Vec<char> = term.chars().collect();
+        if term.is_empty() {
+            continue;
+        }
+        let mut start = 0;
+        while let Some(window) = lower.get(start..start + term.len()) {
+            if window == term.as_slice() {
+                highlighted
+                    .iter_mut()
+                    .skip(start)
+                    .take(term.len())
+                    .for_each(|h| *h = true);
+                start += term.len();
+            } else {
+                start += 1;
+            }
+        }
+    }
+
+    let first = highlighted.iter().position(|h| *h)?;
+    let start = first.saturating_sub(SNIPPET_BEFORE);
+    let end = (start + SNIPPET_LEN).min(chars.len());
+
+    let mut snippet = String::new();
+    if start > 0 {
+        snippet.push_str("...");
+    }
+    let mut in_match = false;
+    for (c, is_match) in chars.iter().zip(&highlighted).take(end).skip(start) {
+        if *is_match != in_match {
+            snippet.push_str(if *is_match { HIGHLIGHT_PRE_TAG } else { HIGHLIGHT_POST_TAG });
+            in_match = *is_match;
+        }
+        snippet.push(*c);
+    }
+    if in_match {
+        snippet.push_str(HIGHLIGHT_POST_TAG);
+    }
+    if end < chars.len() {
+        snippet.push_str("...");
+    }
+    Some(snippet)
+}
+
+/// Seconds part of a Slack timestamp ("1767636991.559059" -> 1767636991)
+pub(crate) fn slack_ts_to_secs(ts: &str) -> i64 {
+    ts.split('.')
         .next()
         .and_then(|s| s.parse().ok())
-        .unwrap_or(0);
+        .unwrap_or(0)
+}
+
+/// Whether a message has a non-empty `files` array
+fn has_files(message: &serde_json::Value) -> bool {
+    message
+        .get("files")
+        .and_then(|f| f.as_array())
+        .is_some_and(|files| !files.is_empty())
+}
+
+/// Convert a Slack timestamp to ISO 8601 datetime string
+pub(crate) fn slack_ts_to_iso8601(ts: &str) -> String {
+    // Slack ts format: "1767636991.559059" (seconds.microseconds)
+    let secs = slack_ts_to_secs(ts);
 
     Utc.timestamp_opt(secs, 0)
         .single()
@@ -56,30 +236,106 @@ fn slack_ts_to_iso8601(ts: &str) -> String {
         .unwrap_or_default()
 }
 
-/// Export conversations to an index JSON file
+/// Output format for the conversation index
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum IndexFormat {
+    #[default]
+    Json,
+    /// Single SQLite file with an FTS5 table
+    SqliteFts,
+}
+
+impl IndexFormat {
+    /// Infer the format from the output file extension (`.sqlite`/`.db` use SQLite)
+    pub fn from_output_path(path: &str) -> Self {
+        match std::path::Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("sqlite" | "sqlite3" | "db") => IndexFormat::SqliteFts,
+            _ => IndexFormat::Json,
+        }
+    }
+}
+
+impl std::fmt::Display for IndexFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            IndexFormat::Json => write!(f, "json"),
+            IndexFormat::SqliteFts => write!(f, "sqlite-fts"),
+        }
+    }
+}
+
+impl std::str::FromStr for IndexFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(IndexFormat::Json),
+            "sqlite-fts" | "sqlite" => Ok(IndexFormat::SqliteFts),
+            _ => Err(AppError::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+/// Export conversations to an index file
 pub fn export_conversations_to_index(
     conversations_path: &str,
     users_path: &str,
     channels_path: &str,
     output_path: &str,
+    format: IndexFormat,
 ) -> Result<usize> {
     export_conversations_to_index_with_progress(
         conversations_path,
         users_path,
         channels_path,
         output_path,
+        format,
         None,
     )
 }
 
-/// Export conversations to an index JSON file with progress reporting
+/// Export conversations to an index file with progress reporting
 pub fn export_conversations_to_index_with_progress(
     conversations_path: &str,
     users_path: &str,
     channels_path: &str,
     output_path: &str,
+    format: IndexFormat,
     progress_callback: ProgressCallback,
 ) -> Result<usize> {
+    // Read one channel at a time; only the index entries are kept
+    let conversations = ConversationSource::File(conversations_path);
+    let index_entries = build_index_entries(conversations, users_path, channels_path, progress_callback)?;
+    let message_count = index_entries.len();
+    if let Some(cb) = progress_callback {
+        cb(message_count, message_count, "Writing output file...");
+    }
+
+    if format == IndexFormat::SqliteFts {
+        crate::sqlite_index::write_sqlite_fts_index(&index_entries, output_path, progress_callback)?;
+        return Ok(message_count);
+    }
+
+    // Write the index to the output file
+    let output_file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    let writer = BufWriter::new(output_file);
+    serde_json::to_writer_pretty(writer, &index_entries)
+        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+
+    Ok(message_count)
+}
+
+/// Build the index entries of conversations, one per top-level message with
+/// its thread replies
+pub fn build_index_entries(
+    conversations: ConversationSource<'_>,
+    users_path: &str,
+    channels_path: &str,
+    progress_callback: ProgressCallback,
+) -> Result<Vec<IndexEntry>> {
     let report_progress = |current: usize, total: usize, msg: &str| {
         if let Some(cb) = progress_callback {
             cb(current, total, msg);
@@ -89,7 +345,7 @@ pub fn export_conversations_to_index_with_progress(
     report_progress(0, 100, "Loading users...");
 
     // Load users.json to build user_id -> display_name map
-    let users_data: Vec<serde_json::Value> = crate::load_json_file(users_path)?;
+    let users_data = crate::archive_input::load_users(users_path)?;
 
     let user_names: HashMap<String, String> = users_data
         .iter()
@@ -111,7 +367,7 @@ pub fn export_conversations_to_index_with_progress(
     report_progress(0, 100, "Loading channels...");
 
     // Load channels.json to build channel_id -> channel_name map
-    let channels_data: Vec<serde_json::Value> = crate::load_json_file(channels_path)?;
+    let channels_data = crate::archive_input::load_channels(channels_path)?;
 
     let channel_names: HashMap<String, String> = channels_data
         .iter()
@@ -139,17 +395,8 @@ pub fn export_conversations_to_index_with_progress(
         ..SlackReferences::default()
     };
 
-    report_progress(0, 100, "Loading conversations...");
-
-    // Load conversations.json
-    let conversations: Vec<serde_json::Value> = crate::load_json_file(conversations_path)?;
-
     // Count total messages for progress reporting
-    let total_messages: usize = conversations
-        .iter()
-        .filter_map(|ch| ch.get("messages").and_then(|m| m.as_array()))
-        .map(|msgs| msgs.len())
-        .sum();
+    let total_messages = conversations.message_count()?;
 
     report_progress(0, total_messages, "Processing messages...");
 
@@ -157,7 +404,7 @@ pub fn export_conversations_to_index_with_progress(
     let mut message_count = 0;
 
     // Process each channel entry in the conversations file
-    for channel_entry in &conversations {
+    conversations.for_each(|channel_entry| {
         let channel_id = channel_entry
             .get("channel_id")
             .and_then(|id| id.as_str())
@@ -201,10 +448,13 @@ pub fn export_conversations_to_index_with_progress(
 
             // Build the markdown text for the main message
             let mut full_text = render_message_to_markdown(message, &slack_references, &user_names);
+            let mut has_attachments = has_files(message);
 
             // Process thread replies if present
             if let Some(replies) = message.get("thread_replies").and_then(|r| r.as_array()) {
                 for reply in replies {
+                    has_attachments |= has_files(reply);
+
                     // Add reply author to users set
                     if let Some(user_id) = reply.get("user").and_then(|u| u.as_str()) {
                         thread_user_ids.insert(user_id.to_string());
@@ -240,25 +490,17 @@ pub fn export_conversations_to_index_with_progress(
                     id: channel_id.clone(),
                     name: channel_name.clone(),
                 },
+                timestamp: slack_ts_to_secs(&message_id),
+                has_attachments,
             };
 
             index_entries.push(entry);
             message_count += 1;
         }
-    }
-
-    report_progress(message_count, total_messages, "Writing output file...");
-
-    // Write the index to the output file
-    let output_file = File::create(output_path).map_err(|e| AppError::WriteFile {
-        path: output_path.to_string(),
-        source: e,
+        Ok(())
     })?;
-    let writer = BufWriter::new(output_file);
-    serde_json::to_writer_pretty(writer, &index_entries)
-        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
 
-    Ok(message_count)
+    Ok(index_entries)
 }
 
 /// Render a single message to markdown
@@ -278,7 +520,13 @@ fn render_message_to_markdown(
 
     // Add message header with username
     output.push_str(&format!("**{}**\n\n", user_name));
+    output.push_str(&render_message_body(message, slack_references));
 
+    output
+}
+
+/// Render a message's rich text blocks to markdown, falling back to its plain `text`
+pub(crate) fn render_message_body(message: &serde_json::Value, slack_references: &SlackReferences) -> String {
     // Try to render blocks if available
     if let Some(blocks_array) = message.get("blocks").and_then(|b| b.as_array()) {
         let blocks: Vec<SlackBlock> = blocks_array
@@ -299,20 +547,17 @@ fn render_message_to_markdown(
                 Some("**".to_string()),
             );
             if !rendered.is_empty() {
-                output.push_str(&rendered);
-                return output;
+                return rendered;
             }
         }
     }
 
     // Fall back to plain text field
-    let text = message
+    message
         .get("text")
         .and_then(|t| t.as_str())
-        .unwrap_or("");
-    output.push_str(text);
-
-    output
+        .unwrap_or("")
+        .to_string()
 }
 
 #[cfg(test)]
@@ -370,6 +615,8 @@ mod tests {
                 id: "C456".to_string(),
                 name: "general".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: true,
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -384,6 +631,38 @@ mod tests {
         assert_eq!(deserialized.users[0].name, "testuser");
         assert_eq!(deserialized.channel.id, "C456");
         assert_eq!(deserialized.channel.name, "general");
+        assert_eq!(deserialized.timestamp, 1234567890);
+        assert!(deserialized.has_attachments);
+    }
+
+    #[test]
+    fn test_index_entry_deserialize_without_facet_fields() {
+        let json = r#"{
+            "id": "1234567890_123456",
+            "ts": "1234567890.123456",
+            "date": "2009-02-13T23:31:30+00:00",
+            "text": "old index",
+            "users": [],
+            "channel": {"id": "C456", "name": "general"}
+        }"#;
+
+        let entry: IndexEntry = serde_json::from_str(json).unwrap();
+
+        assert_eq!(entry.timestamp, 0);
+        assert!(!entry.has_attachments);
+    }
+
+    #[test]
+    fn test_slack_ts_to_secs() {
+        assert_eq!(slack_ts_to_secs("1767636991.559059"), 1767636991);
+        assert_eq!(slack_ts_to_secs("invalid"), 0);
+    }
+
+    #[test]
+    fn test_has_files() {
+        assert!(has_files(&json!({"files": [{"id": "F1"}]})));
+        assert!(!has_files(&json!({"files": []})));
+        assert!(!has_files(&json!({"text": "no files"})));
     }
 
     #[test]
@@ -504,4 +783,99 @@ mod tests {
         assert_eq!(id, "1767636991_559059");
         assert!(!id.contains('.'));
     }
+
+    #[test]
+    fn test_index_format_from_str() {
+        assert_eq!("json".parse::<IndexFormat>().unwrap(), IndexFormat::Json);
+        assert_eq!("sqlite-fts".parse::<IndexFormat>().unwrap(), IndexFormat::SqliteFts);
+        assert_eq!("SQLITE".parse::<IndexFormat>().unwrap(), IndexFormat::SqliteFts);
+        assert!("csv".parse::<IndexFormat>().is_err());
+        assert_eq!(IndexFormat::SqliteFts.to_string(), "sqlite-fts");
+    }
+
+    #[test]
+    fn test_index_format_from_output_path() {
+        assert_eq!(IndexFormat::from_output_path("index.json"), IndexFormat::Json);
+        assert_eq!(IndexFormat::from_output_path("index.sqlite"), IndexFormat::SqliteFts);
+        assert_eq!(IndexFormat::from_output_path("out/index.db"), IndexFormat::SqliteFts);
+        assert_eq!(IndexFormat::from_output_path("index"), IndexFormat::Json);
+    }
+
+    #[test]
+    fn test_split_highlights() {
+        let segments = split_highlights("deploy <mark>failed</mark> on <mark>prod</mark>");
+
+        assert_eq!(
+            segments,
+            vec![("deploy ", false), ("failed", true), (" on ", false), ("prod", true)]
+        );
+    }
+
+    #[test]
+    fn test_split_highlights_without_markers() {
+        assert_eq!(split_highlights("plain text"), vec![("plain text", false)]);
+        assert!(split_highlights("").is_empty());
+    }
+
+    #[test]
+    fn test_split_highlights_unclosed_marker() {
+        assert_eq!(split_highlights("a <mark>b"), vec![("a ", false), ("b", true)]);
+    }
+
+    #[cfg(feature = "tui")]
+    fn local_entry(ts: i64, channel: &str, user: &str, text: &str) -> IndexEntry {
+        IndexEntry {
+            id: ts.to_string(),
+            ts: format!("{}.000000", ts),
+            date: slack_ts_to_iso8601(&ts.to_string()),
+            text: text.to_string(),
+            users: vec![IndexUser { id: "U1".to_string(), name: user.to_string() }],
+            channel: IndexChannel { id: "C1".to_string(), name: channel.to_string() },
+            timestamp: ts,
+            has_attachments: false,
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn test_local_index_search() {
+        let index = LocalIndex::new(vec![
+            local_entry(100, "general", "alice", "Deploy went fine"),
+            local_entry(300, "ops", "bob", "deploy failed, rolling back"),
+            local_entry(200, "random", "carol", "lunch?"),
+        ]);
+        assert_eq!(index.len(), 3);
+
+        // Case-insensitive, newest first
+        let hits = index.search("DEPLOY", 10);
+        assert_eq!(hits.len(), 2);
+        assert_eq!(hits[0].entry.channel.name, "ops");
+        assert_eq!(hits[0].snippet.as_deref(), Some("<mark>deploy</mark> failed, rolling back"));
+
+        // Every term must match, channel and user names count
+        let hits = index.search("deploy general", 10);
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].entry.channel.name, "general");
+
+        // A match only in the metadata has no snippet
+        let hits = index.search("carol", 10);
+        assert_eq!(hits.len(), 1);
+        assert!(hits[0].snippet.is_none());
+
+        assert!(index.search("   ", 10).is_empty());
+        assert_eq!(index.search("deploy", 1).len(), 1);
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn test_local_snippet_crops_long_text() {
+        let text = format!("{} needle {}", "a".repeat(100), "b".repeat(200));
+        let snippet = local_snippet(&text, &["needle"]).unwrap();
+
+        assert!(snippet.starts_with("..."));
+        assert!(snippet.ends_with("..."));
+        assert!(snippet.contains("<mark>needle</mark>"));
+        assert!(local_snippet("no match", &["needle"]).is_none());
+    }
+
 }
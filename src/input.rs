@@ -1,21 +1,321 @@
 use std::sync::mpsc;
-use std::thread;
 
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::widgets::ListState;
 
 use crate::app::App;
-use crate::slack;
+use crate::slack::{self, AttachmentFilter, DownloadOptions};
 use crate::ui::types::{
-    ArchiveRangeField, AsyncResult, ConvExportField, ConvExportWeekField,
-    DownloadAttachmentsField, EditConvPathField, EditableChannelList, ExportEmojisField,
+    ArchiveRangeField, AsyncResult, BulkMatcher, BulkToggle, ChannelNameAction, ChannelNamePrompt, ChannelSelection, ConvExportField, ConvExportWeekField,
+    DownloadAttachmentsField, EditConvPathField, EditableChannel, EditableChannelList, ExportEmojisField,
     ExportIndexField, ExportTask, ImportMeilisearchField, ListNavigation, MarkdownExportField,
-    MdToHtmlField, MenuItem, QueryMeilisearchField, Screen,
+    MdToHtmlField, MenuItem, OverwriteChoice, PipelineField, QueryMeilisearchField, Screen,
+    SearchBackend, SearchLocalField, SelectionPicker, UndoStack,
 };
-use crate::widgets::TextInput;
+use crate::widgets::{TextArea, TextInput};
 use crate::OutputFormat;
 
+/// Ctrl+P opens the calendar on date and week fields; while it is open it
+/// takes every key. Returns true when the key was consumed.
+fn handle_date_picker_key(app: &mut App, key: KeyEvent) -> bool {
+    let Some(picker) = app.date_picker.as_mut() else {
+        if key.code == KeyCode::Char('p') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            app.date_picker = app.screen.active_date_target().map(|target| target.picker());
+            return app.date_picker.is_some();
+        }
+        return false;
+    };
+
+    match key.code {
+        KeyCode::Esc => app.date_picker = None,
+        KeyCode::Enter => {
+            if let Some(target) = app.screen.active_date_target() {
+                target.set(picker);
+            }
+            app.date_picker = None;
+        }
+        _ => {
+            picker.handle_key(key);
+        }
+    }
+    true
+}
+
+/// Keys of the conversation editor's bulk enable/disable prompt, which takes
+/// every key while open. Returns true when the key was consumed.
+fn handle_bulk_toggle_key(app: &mut App, key: KeyEvent) -> bool {
+    let Some(prompt) = app.bulk_toggle.as_mut() else {
+        return false;
+    };
+
+    match key.code {
+        KeyCode::Esc => app.bulk_toggle = None,
+        KeyCode::Enter => {
+            let matcher = match BulkMatcher::parse(prompt.input.text()) {
+                Ok(matcher) => matcher,
+                Err(e) => {
+                    prompt.error = Some(e);
+                    return true;
+                }
+            };
+            let enable = prompt.enable;
+            let pattern = prompt.input.text().to_string();
+            let (channels, users, only_channel) = match &mut app.screen {
+                Screen::EditConversationsChannelList { channels, users, .. } => (channels, users, None),
+                Screen::EditConversationsMessageList {
+                    channels,
+                    users,
+                    channel_idx,
+                    ..
+                } => (channels, users, Some(*channel_idx)),
+                _ => {
+                    app.bulk_toggle = None;
+                    return true;
+                }
+            };
+
+            app.edit_undo.record(channels.clone());
+            let changed: usize = channels
+                .channels
+                .iter_mut()
+                .enumerate()
+                .filter(|(idx, _)| only_channel.is_none_or(|c| c == *idx))
+                .map(|(_, channel)| channel.set_enabled_matching(&matcher, users, enable))
+                .sum();
+            app.log.push(&format!(
+                "{} {} messages matching {}",
+                if enable { "Enabled" } else { "Disabled" },
+                changed,
+                pattern
+            ));
+            app.bulk_toggle = None;
+        }
+        _ => {
+            if prompt.input.handle_key(key) {
+                prompt.error = None;
+            }
+        }
+    }
+    true
+}
+
+/// `p` on a channel list opens the saved selections of the settings
+/// profile; while open the popup takes every key. Returns true when the key
+/// was consumed.
+fn handle_selection_picker_key(app: &mut App, key: KeyEvent) -> bool {
+    let Some(picker) = app.selection_picker.as_mut() else {
+        if key.code == KeyCode::Char('p')
+            && key.modifiers.is_empty()
+            && app.screen.active_channel_selection().is_some_and(|sel| !sel.filtering)
+        {
+            let entries = app.settings.channel_selections();
+            app.selection_picker = Some(SelectionPicker::new(entries, app.settings.profile.clone()));
+            return true;
+        }
+        return false;
+    };
+
+    match key.code {
+        KeyCode::Esc => app.selection_picker = None,
+        KeyCode::Up => picker.previous(),
+        KeyCode::Down => picker.next(),
+        KeyCode::Delete => {
+            if let Some(name) = picker.current().map(str::to_string) {
+                app.settings.remove_channel_selection(&name);
+                let _ = app.settings.save();
+                app.log.push(&format!("Deleted channel selection {}", name));
+                picker.set_entries(app.settings.channel_selections());
+            }
+        }
+        KeyCode::Enter => {
+            let name = picker.name.text().trim().to_string();
+            let current = picker.current().map(str::to_string);
+            let Some(sel) = app.screen.active_channel_selection() else {
+                app.selection_picker = None;
+                return true;
+            };
+            if !name.is_empty() {
+                let ids = sel.selected_ids();
+                app.log.push(&format!("Saved {} channels as {}", ids.len(), name));
+                app.settings.save_channel_selection(&name, ids);
+                let _ = app.settings.save();
+            } else if let Some(saved) = current.as_deref().and_then(|name| app.settings.channel_selection(name)) {
+                sel.select_only(&saved);
+                app.log.push(&format!(
+                    "Loaded channel selection {} ({} of {} channels listed)",
+                    current.unwrap_or_default(),
+                    sel.selected.len(),
+                    saved.len()
+                ));
+            }
+            app.selection_picker = None;
+        }
+        _ => {
+            picker.name.handle_key(key);
+        }
+    }
+    true
+}
+
+/// Lines scrolled by PgUp/PgDn in the markdown preview
+const PREVIEW_PAGE: u16 = 20;
+
+/// Keys of the channel picker's `/` filter. Returns true when the key was
+/// consumed, so the screen's own bindings don't see it.
+fn handle_channel_filter_key(sel: &mut ChannelSelection, key: KeyEvent) -> bool {
+    if !sel.filtering {
+        return match key.code {
+            KeyCode::Char('/') => {
+                sel.filtering = true;
+                true
+            }
+            // First Esc drops the filter, the next one leaves the screen
+            KeyCode::Esc if !sel.filter.text().is_empty() => {
+                sel.clear_filter();
+                true
+            }
+            _ => false,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => sel.clear_filter(),
+        KeyCode::Enter => sel.filtering = false,
+        KeyCode::Up => sel.previous(),
+        KeyCode::Down => sel.next(),
+        // Channel names never contain spaces, so Space can still toggle
+        KeyCode::Char(' ') => sel.toggle_current(),
+        _ => {
+            if sel.filter.handle_key(key) {
+                sel.apply_filter();
+            }
+        }
+    }
+    true
+}
+
+/// Ctrl+Z/Ctrl+Y in the conversation editor. Returns true when the key was
+/// consumed. The message filters typed since the snapshot are kept.
+fn handle_edit_undo_key(
+    undo: &mut UndoStack<EditableChannelList>,
+    channels: &mut EditableChannelList,
+    users: &serde_json::Value,
+    key: KeyEvent,
+) -> bool {
+    if !key.modifiers.contains(KeyModifiers::CONTROL) {
+        return false;
+    }
+    let restored = match key.code {
+        KeyCode::Char('z') => undo.undo(channels.clone()),
+        KeyCode::Char('y') => undo.redo(channels.clone()),
+        _ => return false,
+    };
+    if let Some(mut restored) = restored {
+        for channel in &mut restored.channels {
+            if let Some(current) = channels.channels.iter().find(|c| c.id == channel.id) {
+                channel.filter = current.filter.clone();
+            }
+            channel.filtering = false;
+            channel.apply_filter(users);
+        }
+        restored.export_path = channels.export_path.clone();
+        *channels = restored;
+    }
+    true
+}
+
+/// Keys of the conversation editor's `/` message filter, same as the channel
+/// picker's. Returns true when the key was consumed.
+fn handle_message_filter_key(channel: &mut EditableChannel, users: &serde_json::Value, key: KeyEvent) -> bool {
+    if !channel.filtering {
+        return match key.code {
+            KeyCode::Char('/') => {
+                channel.filtering = true;
+                true
+            }
+            // First Esc drops the filter, the next one leaves the screen
+            KeyCode::Esc if !channel.filter.text().is_empty() => {
+                channel.clear_filter(users);
+                true
+            }
+            _ => false,
+        };
+    }
+
+    match key.code {
+        KeyCode::Esc => channel.clear_filter(users),
+        KeyCode::Enter => channel.filtering = false,
+        KeyCode::Up => channel.previous(),
+        KeyCode::Down => channel.next(),
+        _ => {
+            if channel.filter.handle_key(key) {
+                channel.apply_filter(users);
+            }
+        }
+    }
+    true
+}
+
+/// Keys of the log pane, available on screens without text input. Returns
+/// true when the key was consumed.
+fn handle_log_key(app: &mut App, key: KeyEvent) -> bool {
+    const PAGE: usize = 10;
+
+    if !matches!(
+        app.screen,
+        Screen::MainMenu
+            | Screen::Loading { .. }
+            | Screen::Success { .. }
+            | Screen::Error { .. }
+            | Screen::DownloadResults { .. }
+    ) {
+        return false;
+    }
+    match key.code {
+        KeyCode::Char('l') => app.log.visible = !app.log.visible,
+        KeyCode::PageUp if app.log.visible => app.log.scroll_up(PAGE),
+        KeyCode::PageDown if app.log.visible => app.log.scroll_down(PAGE),
+        _ => return false,
+    }
+    true
+}
+
 pub fn handle_input(app: &mut App, key: KeyEvent) {
+    if handle_log_key(app, key) {
+        return;
+    }
+
+    if handle_date_picker_key(app, key) {
+        return;
+    }
+
+    if handle_bulk_toggle_key(app, key) {
+        return;
+    }
+
+    if handle_selection_picker_key(app, key) {
+        return;
+    }
+
+    // Up/Down on a text field cycle through its recent values
+    if matches!(key.code, KeyCode::Up | KeyCode::Down)
+        && app.screen.cycles_history()
+        && app.screen.active_text_field().is_some()
+    {
+        app.cycle_history(key.code == KeyCode::Up);
+        return;
+    }
+    app.history_cursor = None;
+
+    // Ctrl-Space completes path fields; Tab keeps moving between fields
+    app.path_candidates.clear();
+    if key.code == KeyCode::Char(' ') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        if let Some(field) = app.screen.active_path_field() {
+            app.path_candidates = field.complete();
+        }
+        return;
+    }
+
     match &mut app.screen {
         Screen::MainMenu => match key.code {
             KeyCode::Char('q') | KeyCode::Esc => app.should_quit = true,
@@ -59,6 +359,10 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         app.screen = Screen::DownloadAttachments {
                             conversations_path: s.conversations_path.clone(),
                             output_path: s.output_path.clone(),
+                            types: TextInput::default(),
+                            max_size: TextInput::default(),
+                            from_date: TextInput::default(),
+                            to_date: TextInput::default(),
                             active_field: DownloadAttachmentsField::ConversationsPath,
                         };
                     }
@@ -72,6 +376,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                             formatter_script: s.formatter_script.clone().unwrap_or_default(),
                             backslash_line_breaks: s.backslash_line_breaks,
                             active_field: MarkdownExportField::Conversations,
+                            editor: None,
                         };
                     }
                     MenuItem::ExportEmojis => {
@@ -105,17 +410,27 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     }
                     MenuItem::QueryMeilisearch => {
                         let s = &app.settings.meilisearch;
+                        let tantivy_dir = if app.settings.tantivy.index_dir.is_empty() {
+                            "tantivy-index".to_string()
+                        } else {
+                            app.settings.tantivy.index_dir.clone()
+                        };
                         app.screen = Screen::QueryMeilisearch {
                             query: String::new(),
+                            backend: SearchBackend::Meilisearch,
                             url: s.url.clone(),
                             api_key: s.api_key.clone(),
                             index_name: s.index_name.clone(),
+                            tantivy_dir,
                             active_field: QueryMeilisearchField::Query,
                             results: None,
                             result_state: ListState::default(),
                             error: None,
                         };
                     }
+                    MenuItem::SearchLocal => app.open_search_local(),
+                    MenuItem::BrowseEmojis => app.open_emoji_browser(),
+                    MenuItem::Pipeline => app.open_pipeline(),
                     MenuItem::MdToHtml => {
                         let s = &app.settings.md_to_html;
                         app.screen = Screen::MdToHtml {
@@ -142,6 +457,13 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 return;
             }
 
+            if *active_field == ConvExportField::Channels
+                && let Some(sel) = channel_selection.as_mut()
+                && handle_channel_filter_key(sel, key)
+            {
+                return;
+            }
+
             match key.code {
                 KeyCode::Esc => app.screen = Screen::MainMenu,
                 KeyCode::Tab => {
@@ -167,12 +489,9 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     app.async_result_rx = Some(rx);
 
                     let token = app.token.clone();
-                    thread::spawn(move || {
-                        let rt = tokio::runtime::Runtime::new().unwrap();
-                        let result = rt.block_on(async { slack::fetch_channels(&token).await });
-                        let _ = tx.send(AsyncResult::ChannelsLoaded(
-                            result.map_err(|e| e.to_string()),
-                        ));
+                    app.runtime.spawn(async move {
+                        let result = slack::fetch_channels(&token).await.map_err(|e| e.to_string());
+                        let _ = tx.send(AsyncResult::ChannelsLoaded(result));
                     });
                 }
                 KeyCode::Char('a') if *active_field == ConvExportField::Channels => {
@@ -185,6 +504,11 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         sel.select_none();
                     }
                 }
+                KeyCode::Char('s') if *active_field == ConvExportField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.cycle_sort();
+                    }
+                }
                 KeyCode::Char(' ') if *active_field == ConvExportField::Channels => {
                     if let Some(sel) = channel_selection {
                         sel.toggle_current();
@@ -233,14 +557,11 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         selected_channels,
                         format: OutputFormat::Json, // Default to JSON for TUI
                     };
-                    app.screen = Screen::Loading {
-                        progress: None,
-                        message: format!(
-                            "Exporting conversations from {} to {}...",
-                            from_date_str, to_date_str
-                        ),
-                    };
-                    app.start_task(task);
+                    let message = format!(
+                        "Exporting conversations from {} to {}...",
+                        from_date_str, to_date_str
+                    );
+                    app.run_task(task, message);
                 }
                 _ if *active_field != ConvExportField::Channels => {
                     let field = match active_field {
@@ -262,6 +583,13 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             channel_selection,
             ..
         } => {
+            if *active_field == ConvExportWeekField::Channels
+                && let Some(sel) = channel_selection.as_mut()
+                && handle_channel_filter_key(sel, key)
+            {
+                return;
+            }
+
             match key.code {
                 KeyCode::Esc => app.screen = Screen::MainMenu,
                 KeyCode::Tab => {
@@ -290,6 +618,11 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         sel.select_none();
                     }
                 }
+                KeyCode::Char('s') if *active_field == ConvExportWeekField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.cycle_sort();
+                    }
+                }
                 KeyCode::Char(' ') if *active_field == ConvExportWeekField::Channels => {
                     if let Some(sel) = channel_selection {
                         sel.toggle_current();
@@ -338,14 +671,11 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         selected_channels,
                         format: OutputFormat::Json, // Default to JSON for TUI
                     };
-                    app.screen = Screen::Loading {
-                        progress: None,
-                        message: format!(
-                            "Exporting conversations for {}-W{:02}...",
-                            year_val, week_val
-                        ),
-                    };
-                    app.start_task(task);
+                    let message = format!(
+                        "Exporting conversations for {}-W{:02}...",
+                        year_val, week_val
+                    );
+                    app.run_task(task, message);
                 }
                 _ if *active_field != ConvExportWeekField::Channels => {
                     let field = match active_field {
@@ -403,14 +733,11 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         to_week: to_week_val,
                         output_path: output_path_str,
                     };
-                    app.screen = Screen::Loading {
-                        progress: None,
-                        message: format!(
-                            "Archiving conversations from {}-W{:02} to {}-W{:02}...",
-                            from_year_val, from_week_val, to_year_val, to_week_val
-                        ),
-                    };
-                    app.start_task(task);
+                    let message = format!(
+                        "Archiving conversations from {}-W{:02} to {}-W{:02}...",
+                        from_year_val, from_week_val, to_year_val, to_week_val
+                    );
+                    app.run_task(task, message);
                 }
                 _ => {
                     let field = match active_field {
@@ -437,11 +764,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     output_path: output_path_str,
                     format: OutputFormat::Json, // Default to JSON for TUI
                 };
-                app.screen = Screen::Loading {
-                    message: "Exporting users...".to_string(),
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, "Exporting users...".to_string());
             }
             _ => {}
         },
@@ -458,69 +781,73 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     output_path: output_path_str,
                     format: OutputFormat::Json, // Default to JSON for TUI
                 };
-                app.screen = Screen::Loading {
-                    message: "Exporting channels...".to_string(),
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, "Exporting channels...".to_string());
             }
             _ => {}
         },
         Screen::DownloadAttachments {
             conversations_path,
             output_path,
+            types,
+            max_size,
+            from_date,
+            to_date,
             active_field,
         } => match key.code {
             KeyCode::Esc => app.screen = Screen::MainMenu,
-            KeyCode::Tab => {
-                *active_field = match active_field {
-                    DownloadAttachmentsField::ConversationsPath => {
-                        DownloadAttachmentsField::OutputPath
-                    }
-                    DownloadAttachmentsField::OutputPath => {
-                        DownloadAttachmentsField::ConversationsPath
-                    }
-                };
-            }
-            KeyCode::BackTab => {
-                *active_field = match active_field {
-                    DownloadAttachmentsField::ConversationsPath => {
-                        DownloadAttachmentsField::OutputPath
-                    }
-                    DownloadAttachmentsField::OutputPath => {
-                        DownloadAttachmentsField::ConversationsPath
+            KeyCode::Tab => *active_field = active_field.next(),
+            KeyCode::BackTab => *active_field = active_field.prev(),
+            KeyCode::Enter => {
+                let filter = match AttachmentFilter::parse(
+                    types.text(),
+                    max_size.text(),
+                    from_date.text(),
+                    to_date.text(),
+                ) {
+                    Ok(filter) => filter,
+                    Err(e) => {
+                        app.screen = Screen::Error { message: e.to_string() };
+                        return;
                     }
                 };
-            }
-            KeyCode::Char(c) => {
-                let field = match active_field {
-                    DownloadAttachmentsField::ConversationsPath => conversations_path,
-                    DownloadAttachmentsField::OutputPath => output_path,
-                };
-                field.push(c);
-            }
-            KeyCode::Backspace => {
-                let field = match active_field {
-                    DownloadAttachmentsField::ConversationsPath => conversations_path,
-                    DownloadAttachmentsField::OutputPath => output_path,
-                };
-                field.pop();
-            }
-            KeyCode::Enter => {
                 let conv_path = conversations_path.clone();
                 let out_path = output_path.clone();
                 app.save_download_attachments_settings(&conv_path, &out_path);
                 let task = ExportTask::DownloadAttachments {
                     conversations_path: conv_path,
                     output_path: out_path,
+                    only: None,
+                    options: DownloadOptions {
+                        filter,
+                        ..app.settings.download_attachments.download_options()
+                    },
                 };
-                app.screen = Screen::Loading {
-                    message: "Downloading attachments...".to_string(),
-                    progress: None,
+                app.run_task(task, "Downloading attachments...".to_string());
+            }
+            _ => {
+                let input = match active_field {
+                    DownloadAttachmentsField::Types => types,
+                    DownloadAttachmentsField::MaxSize => max_size,
+                    DownloadAttachmentsField::FromDate => from_date,
+                    DownloadAttachmentsField::ToDate => to_date,
+                    DownloadAttachmentsField::ConversationsPath | DownloadAttachmentsField::OutputPath => {
+                        let field = if *active_field == DownloadAttachmentsField::ConversationsPath {
+                            conversations_path
+                        } else {
+                            output_path
+                        };
+                        match key.code {
+                            KeyCode::Char(c) => field.push(c),
+                            KeyCode::Backspace => {
+                                field.pop();
+                            }
+                            _ => {}
+                        }
+                        return;
+                    }
                 };
-                app.start_task(task);
+                input.handle_key(key);
             }
-            _ => {}
         },
         Screen::MarkdownExport {
             conversations_path,
@@ -530,8 +857,19 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             formatter_script,
             backslash_line_breaks,
             active_field,
+            editor,
         } => match key.code {
-            KeyCode::Esc => app.screen = Screen::MainMenu,
+            KeyCode::Esc => {
+                app.screen = match editor.take() {
+                    Some(editor) => *editor,
+                    None => Screen::MainMenu,
+                };
+            }
+            KeyCode::F(5) => {
+                let path = output_path.clone();
+                let previous = std::mem::replace(&mut app.screen, Screen::MainMenu);
+                app.open_markdown_preview(&path, Some(Box::new(previous)));
+            }
             KeyCode::Tab => {
                 *active_field = match active_field {
                     MarkdownExportField::Conversations => MarkdownExportField::Users,
@@ -539,12 +877,14 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     MarkdownExportField::Channels => MarkdownExportField::Output,
                     MarkdownExportField::Output => MarkdownExportField::FormatterScript,
                     MarkdownExportField::FormatterScript => MarkdownExportField::BackslashLineBreaks,
+                    MarkdownExportField::BackslashLineBreaks if editor.is_some() => MarkdownExportField::Users,
                     MarkdownExportField::BackslashLineBreaks => MarkdownExportField::Conversations,
                 };
             }
             KeyCode::BackTab => {
                 *active_field = match active_field {
                     MarkdownExportField::Conversations => MarkdownExportField::BackslashLineBreaks,
+                    MarkdownExportField::Users if editor.is_some() => MarkdownExportField::BackslashLineBreaks,
                     MarkdownExportField::Users => MarkdownExportField::Conversations,
                     MarkdownExportField::Channels => MarkdownExportField::Users,
                     MarkdownExportField::Output => MarkdownExportField::Channels,
@@ -592,6 +932,12 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     Some(formatter_script.clone())
                 };
                 let bl_breaks = *backslash_line_breaks;
+                let conversations = match editor.as_deref() {
+                    Some(Screen::EditConversationsChannelList { channels, .. }) => {
+                        Some(slack::edited_conversations_to_values(&channels.to_export_data()))
+                    }
+                    _ => None,
+                };
                 app.save_markdown_export_settings(
                     &conv_path,
                     &usr_path,
@@ -602,17 +948,14 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 );
                 let task = ExportTask::MarkdownExport {
                     conversations_path: conv_path,
+                    conversations,
                     users_path: usr_path,
                     channels_path: ch_path,
                     output_path: out_path,
                     formatter_script: script,
                     backslash_line_breaks: bl_breaks,
                 };
-                app.screen = Screen::Loading {
-                    message: "Exporting to markdown...".to_string(),
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, "Exporting to markdown...".to_string());
             }
             _ => {}
         },
@@ -655,12 +998,9 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let task = ExportTask::ExportEmojis {
                     output_path: out_path,
                     emojis_folder: emojis_dir,
+                    only: None,
                 };
-                app.screen = Screen::Loading {
-                    message: "Exporting custom emojis...".to_string(),
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, "Exporting custom emojis...".to_string());
             }
             _ => {}
         },
@@ -723,11 +1063,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     channels_path: ch_path,
                     output_path: out_path,
                 };
-                app.screen = Screen::Loading {
-                    message: "Exporting to index...".to_string(),
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, "Exporting to index...".to_string());
             }
             _ => {}
         },
@@ -800,19 +1136,17 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 } else {
                     "Importing to Meilisearch...".to_string()
                 };
-                app.screen = Screen::Loading {
-                    message: msg,
-                    progress: None,
-                };
-                app.start_task(task);
+                app.run_task(task, msg);
             }
             _ => {}
         },
         Screen::QueryMeilisearch {
             query,
+            backend,
             url,
             api_key,
             index_name,
+            tantivy_dir,
             active_field,
             results,
             result_state,
@@ -820,20 +1154,15 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
         } => match key.code {
             KeyCode::Esc => app.screen = Screen::MainMenu,
             KeyCode::Tab => {
-                *active_field = match active_field {
-                    QueryMeilisearchField::Query => QueryMeilisearchField::Url,
-                    QueryMeilisearchField::Url => QueryMeilisearchField::ApiKey,
-                    QueryMeilisearchField::ApiKey => QueryMeilisearchField::IndexName,
-                    QueryMeilisearchField::IndexName => QueryMeilisearchField::Query,
-                };
+                *active_field = active_field.next(*backend);
             }
             KeyCode::BackTab => {
-                *active_field = match active_field {
-                    QueryMeilisearchField::Query => QueryMeilisearchField::IndexName,
-                    QueryMeilisearchField::Url => QueryMeilisearchField::Query,
-                    QueryMeilisearchField::ApiKey => QueryMeilisearchField::Url,
-                    QueryMeilisearchField::IndexName => QueryMeilisearchField::ApiKey,
-                };
+                *active_field = active_field.prev(*backend);
+            }
+            KeyCode::Char(' ') if *active_field == QueryMeilisearchField::Backend => {
+                *backend = backend.toggle();
+                *results = None;
+                *error = None;
             }
             KeyCode::Up | KeyCode::Char('k') if results.is_some() => {
                 if let Some(res) = results
@@ -869,24 +1198,45 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     result_state.select(Some(i));
                 }
             }
-            KeyCode::Char(c) => {
+            KeyCode::Char(c) if *active_field != QueryMeilisearchField::Backend => {
                 let field = match active_field {
                     QueryMeilisearchField::Query => query,
                     QueryMeilisearchField::Url => url,
                     QueryMeilisearchField::ApiKey => api_key,
                     QueryMeilisearchField::IndexName => index_name,
+                    QueryMeilisearchField::TantivyDir => tantivy_dir,
+                    QueryMeilisearchField::Backend => return,
                 };
                 field.push(c);
             }
-            KeyCode::Backspace => {
+            KeyCode::Backspace if *active_field != QueryMeilisearchField::Backend => {
                 let field = match active_field {
                     QueryMeilisearchField::Query => query,
                     QueryMeilisearchField::Url => url,
                     QueryMeilisearchField::ApiKey => api_key,
                     QueryMeilisearchField::IndexName => index_name,
+                    QueryMeilisearchField::TantivyDir => tantivy_dir,
+                    QueryMeilisearchField::Backend => return,
                 };
                 field.pop();
             }
+            KeyCode::Enter if *backend == SearchBackend::Tantivy => {
+                // Local index queries are fast but still run off the UI thread
+                *error = None;
+                let (tx, rx) = mpsc::channel();
+                app.async_result_rx = Some(rx);
+
+                let query_str = query.clone();
+                let index_dir = tantivy_dir.clone();
+                app.save_tantivy_settings(&index_dir);
+
+                app.runtime.spawn_blocking(move || {
+                    let result = crate::tantivy_search::query_tantivy(&index_dir, &query_str, 20);
+                    let _ = tx.send(AsyncResult::QueryResult(
+                        result.map(|r| r.hits).map_err(|e| e.to_string()),
+                    ));
+                });
+            }
             KeyCode::Enter => {
                 // Start async query
                 *error = None;
@@ -898,18 +1248,16 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let api_key_str = api_key.clone();
                 let index_name_str = index_name.clone();
 
-                thread::spawn(move || {
-                    let rt = tokio::runtime::Runtime::new().unwrap();
-                    let result = rt.block_on(async {
-                        crate::meilisearch::query_meilisearch(
-                            &url_str,
-                            &api_key_str,
-                            &index_name_str,
-                            &query_str,
-                            20,
-                        )
-                        .await
-                    });
+                app.runtime.spawn(async move {
+                    let result = crate::meilisearch::query_meilisearch(
+                        &url_str,
+                        &api_key_str,
+                        &index_name_str,
+                        &query_str,
+                        &crate::meilisearch::SearchFilters::default(),
+                        20,
+                    )
+                    .await;
                     let _ = tx.send(AsyncResult::QueryResult(
                         result.map(|r| r.hits).map_err(|e| e.to_string()),
                     ));
@@ -917,6 +1265,180 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             }
             _ => {}
         },
+        Screen::Pipeline {
+            from_date,
+            to_date,
+            active_field,
+            targets,
+            channel_selection,
+        } => {
+            if *active_field == PipelineField::Channels
+                && let Some(sel) = channel_selection.as_mut()
+                && handle_channel_filter_key(sel, key)
+            {
+                return;
+            }
+
+            match key.code {
+                KeyCode::Esc => app.screen = Screen::MainMenu,
+                KeyCode::Tab => {
+                    *active_field = match active_field {
+                        PipelineField::FromDate => PipelineField::ToDate,
+                        PipelineField::ToDate => PipelineField::Targets,
+                        PipelineField::Targets => PipelineField::Channels,
+                        PipelineField::Channels => PipelineField::FromDate,
+                    };
+                }
+                KeyCode::BackTab => {
+                    *active_field = match active_field {
+                        PipelineField::FromDate => PipelineField::Channels,
+                        PipelineField::ToDate => PipelineField::FromDate,
+                        PipelineField::Targets => PipelineField::ToDate,
+                        PipelineField::Channels => PipelineField::Targets,
+                    };
+                }
+                KeyCode::Up | KeyCode::Char('k') if *active_field == PipelineField::Targets => {
+                    targets.previous();
+                }
+                KeyCode::Down | KeyCode::Char('j') if *active_field == PipelineField::Targets => {
+                    targets.next();
+                }
+                KeyCode::Char(' ') if *active_field == PipelineField::Targets => {
+                    targets.toggle_current();
+                }
+                KeyCode::Char('a') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.select_all();
+                    }
+                }
+                KeyCode::Char('n') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.select_none();
+                    }
+                }
+                KeyCode::Char('s') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.cycle_sort();
+                    }
+                }
+                KeyCode::Char(' ') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.toggle_current();
+                    }
+                }
+                KeyCode::Up | KeyCode::Char('k') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.previous();
+                    }
+                }
+                KeyCode::Down | KeyCode::Char('j') if *active_field == PipelineField::Channels => {
+                    if let Some(sel) = channel_selection {
+                        sel.next();
+                    }
+                }
+                KeyCode::Enter => {
+                    let Some(sel) = channel_selection.as_ref().filter(|s| !s.selected.is_empty()) else {
+                        return;
+                    };
+                    let dates = crate::parse_date(from_date.text())
+                        .and_then(|from| Ok((from, crate::parse_date(to_date.text())?)));
+                    let (from, to) = match dates {
+                        Ok(dates) => dates,
+                        Err(e) => {
+                            app.screen = Screen::Error { message: e.to_string() };
+                            return;
+                        }
+                    };
+
+                    let selected_channels = sel.selected.clone();
+                    let selected_ids = sel.selected_ids();
+                    let targets = targets.clone();
+                    let options = app.pipeline_options(from, to, selected_channels, &targets);
+                    app.save_selected_channels(selected_ids);
+
+                    let message = format!("Running pipeline from {} to {}...", from, to);
+                    app.run_task(ExportTask::Pipeline(options), message);
+                }
+                _ => match active_field {
+                    PipelineField::FromDate => {
+                        from_date.handle_key(key);
+                    }
+                    PipelineField::ToDate => {
+                        to_date.handle_key(key);
+                    }
+                    PipelineField::Targets | PipelineField::Channels => {}
+                },
+            }
+        }
+        Screen::SearchLocal {
+            index_path,
+            query,
+            active_field,
+            source,
+            loading,
+            results,
+            result_state,
+            error,
+        } => {
+            if *loading {
+                if key.code == KeyCode::Esc {
+                    app.screen = Screen::MainMenu;
+                }
+                return;
+            }
+            match key.code {
+                KeyCode::Esc => app.screen = Screen::MainMenu,
+                KeyCode::Tab | KeyCode::BackTab => {
+                    *active_field = match active_field {
+                        SearchLocalField::IndexPath => SearchLocalField::Query,
+                        SearchLocalField::Query => SearchLocalField::IndexPath,
+                    };
+                }
+                KeyCode::Enter if *active_field == SearchLocalField::IndexPath => {
+                    let path = index_path.text().to_string();
+                    app.remember_values(&[("index-path", path)]);
+                    app.load_local_index();
+                }
+                KeyCode::Up => {
+                    if !results.is_empty() {
+                        let i = match result_state.selected() {
+                            Some(0) | None => results.len() - 1,
+                            Some(i) => i - 1,
+                        };
+                        result_state.select(Some(i));
+                    }
+                }
+                KeyCode::Down => {
+                    if !results.is_empty() {
+                        let i = match result_state.selected() {
+                            Some(i) if i + 1 < results.len() => i + 1,
+                            _ => 0,
+                        };
+                        result_state.select(Some(i));
+                    }
+                }
+                _ => match active_field {
+                    SearchLocalField::IndexPath => {
+                        index_path.handle_key(key);
+                    }
+                    SearchLocalField::Query => {
+                        // Search as you type
+                        if query.handle_key(key)
+                            && let Some(source) = source
+                        {
+                            match source.search(query.text(), 50) {
+                                Ok(hits) => {
+                                    *results = hits;
+                                    *error = None;
+                                }
+                                Err(e) => *error = Some(e.to_string()),
+                            }
+                            result_state.select((!results.is_empty()).then_some(0));
+                        }
+                    }
+                },
+            }
+        }
         Screen::EditConversationsPathInput {
             conversations_path,
             users_path,
@@ -960,10 +1482,16 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let ch_path = channels_path.clone();
 
                 app.save_edit_conversations_settings(&conv_path, &usr_path, &ch_path);
+                app.remember_values(&[
+                    ("conversations-path", conv_path.clone()),
+                    ("users-path", usr_path.clone()),
+                    ("channels-path", ch_path.clone()),
+                ]);
 
                 match slack::load_conversations_for_editing(&conv_path, &usr_path, &ch_path) {
                     Ok((channels, users, channel_data)) => {
                         let mut channel_list = EditableChannelList::new(channels);
+                        app.edit_undo = UndoStack::new();
                         // Use saved export path from settings
                         channel_list.export_path = app.settings.edit_conversations.export_path.clone();
                         app.screen = Screen::EditConversationsChannelList {
@@ -971,6 +1499,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                             users,
                             channel_data,
                             editing_export_path: false,
+                            name_prompt: None,
                         };
                     }
                     Err(e) => {
@@ -987,8 +1516,40 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             users,
             channel_data,
             editing_export_path,
+            name_prompt,
         } => {
-            if *editing_export_path {
+            if let Some(prompt) = name_prompt {
+                match key.code {
+                    KeyCode::Esc => *name_prompt = None,
+                    KeyCode::Enter => {
+                        let name = prompt.input.text().trim().trim_start_matches('#').to_string();
+                        if name.is_empty() {
+                            prompt.error = Some("enter a channel name".to_string());
+                            return;
+                        }
+                        let before = channels.clone();
+                        let result = match prompt.action {
+                            ChannelNameAction::Rename => {
+                                channels.rename_current(&name);
+                                Ok(())
+                            }
+                            ChannelNameAction::Merge => channels.merge_marked(&name),
+                        };
+                        match result {
+                            Ok(()) => {
+                                app.edit_undo.record(before);
+                                *name_prompt = None;
+                            }
+                            Err(e) => prompt.error = Some(e),
+                        }
+                    }
+                    _ => {
+                        if prompt.input.handle_key(key) {
+                            prompt.error = None;
+                        }
+                    }
+                }
+            } else if *editing_export_path {
                 match key.code {
                     KeyCode::Esc => *editing_export_path = false,
                     KeyCode::Char(c) => channels.export_path.push(c),
@@ -1000,7 +1561,10 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     }
                     _ => {}
                 }
-            } else {
+            } else if !handle_edit_undo_key(&mut app.edit_undo, channels, users, key) {
+                if matches!(key.code, KeyCode::Up | KeyCode::Down) && key.modifiers == KeyModifiers::ALT {
+                    app.edit_undo.record(channels.clone());
+                }
                 match (key.code, key.modifiers) {
                     (KeyCode::Esc, _) => app.screen = Screen::MainMenu,
                     (KeyCode::Up, KeyModifiers::ALT) => channels.move_current_up(),
@@ -1043,6 +1607,50 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     (KeyCode::Char('p'), _) => {
                         *editing_export_path = true;
                     }
+                    (KeyCode::Char(c @ ('+' | '-')), _) => {
+                        app.bulk_toggle = Some(BulkToggle::new(c == '+'));
+                    }
+                    (KeyCode::Char('x'), _) => {
+                        app.edit_undo.record(channels.clone());
+                        channels.toggle_mark_current();
+                    }
+                    (KeyCode::Char('r'), _) => {
+                        let current = channels
+                            .list_state
+                            .selected()
+                            .and_then(|idx| channels.channels.get(idx))
+                            .map(|c| c.name.clone())
+                            .unwrap_or_default();
+                        *name_prompt = Some(ChannelNamePrompt {
+                            action: ChannelNameAction::Rename,
+                            input: TextInput::new(current),
+                            error: None,
+                        });
+                    }
+                    (KeyCode::Char('g'), _) => {
+                        *name_prompt = Some(ChannelNamePrompt {
+                            action: ChannelNameAction::Merge,
+                            input: TextInput::default(),
+                            error: None,
+                        });
+                    }
+                    (KeyCode::Char('m'), _) => {
+                        let s = &app.settings.markdown_export;
+                        let form = Screen::MarkdownExport {
+                            conversations_path: s.conversations_path.clone(),
+                            users_path: s.users_path.clone(),
+                            channels_path: s.channels_path.clone(),
+                            output_path: s.output_path.clone(),
+                            formatter_script: s.formatter_script.clone().unwrap_or_default(),
+                            backslash_line_breaks: s.backslash_line_breaks,
+                            active_field: MarkdownExportField::Users,
+                            editor: None,
+                        };
+                        let editor = std::mem::replace(&mut app.screen, form);
+                        if let Screen::MarkdownExport { editor: slot, .. } = &mut app.screen {
+                            *slot = Some(Box::new(editor));
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -1053,7 +1661,26 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             users,
             channel_data,
         } => {
+            let filtering = channels
+                .channels
+                .get(*channel_idx)
+                .is_some_and(|c| c.filtering);
+            if !filtering {
+                if handle_edit_undo_key(&mut app.edit_undo, channels, users, key) {
+                    return;
+                }
+                let is_edit = matches!(
+                    (key.code, key.modifiers),
+                    (KeyCode::Up | KeyCode::Down, KeyModifiers::ALT) | (KeyCode::Char(' ' | 'a' | 'n'), _)
+                );
+                if is_edit {
+                    app.edit_undo.record(channels.clone());
+                }
+            }
             if let Some(channel) = channels.channels.get_mut(*channel_idx) {
+                if handle_message_filter_key(channel, users, key) {
+                    return;
+                }
                 match (key.code, key.modifiers) {
                     (KeyCode::Esc, _) => {
                         app.screen = Screen::EditConversationsChannelList {
@@ -1061,6 +1688,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                             users: users.clone(),
                             channel_data: channel_data.clone(),
                             editing_export_path: false,
+                            name_prompt: None,
                         };
                     }
                     (KeyCode::Up, KeyModifiers::ALT) => {
@@ -1082,7 +1710,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         channel.toggle_collapse_current();
                     }
                     (KeyCode::Enter, _) => {
-                        if let Some(msg_idx) = channel.list_state.selected() {
+                        if let Some(msg_idx) = channel.current_index() {
                             let mut attachment_list_state = ListState::default();
                             let msg = &channel.messages[msg_idx];
                             if !msg.files().is_empty() || !msg.links().is_empty() {
@@ -1096,19 +1724,16 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                                 channel_data: channel_data.clone(),
                                 attachment_list_state,
                                 editing_title: None,
+                                editing_text: None,
+                                replies_focused: false,
                             };
                         }
                     }
-                    (KeyCode::Char('a'), _) => {
-                        for msg in &mut channel.messages {
-                            msg.enabled = true;
-                        }
-                    }
-                    (KeyCode::Char('n'), _) => {
-                        for msg in &mut channel.messages {
-                            msg.enabled = false;
-                        }
+                    (KeyCode::Char(c @ ('+' | '-')), _) => {
+                        app.bulk_toggle = Some(BulkToggle::new(c == '+'));
                     }
+                    (KeyCode::Char('a'), _) => channel.set_visible_enabled(true),
+                    (KeyCode::Char('n'), _) => channel.set_visible_enabled(false),
                     _ => {}
                 }
             }
@@ -1121,7 +1746,31 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             channel_data,
             attachment_list_state,
             editing_title,
+            editing_text,
+            replies_focused,
         } => {
+            // Handle message text editing mode
+            if let Some(text_area) = editing_text {
+                match (key.code, key.modifiers) {
+                    (KeyCode::Esc, _) => {
+                        *editing_text = None;
+                    }
+                    (KeyCode::Char('s'), KeyModifiers::CONTROL) => {
+                        app.edit_undo.record(channels.clone());
+                        if let Some(channel) = channels.channels.get_mut(*channel_idx)
+                            && let Some(msg) = channel.messages.get_mut(*message_idx)
+                        {
+                            msg.set_text(text_area.text());
+                        }
+                        *editing_text = None;
+                    }
+                    _ => {
+                        text_area.handle_key(key);
+                    }
+                }
+                return;
+            }
+
             // Handle title editing mode
             if let Some((link_idx, text_input)) = editing_title {
                 match key.code {
@@ -1129,6 +1778,7 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                         *editing_title = None;
                     }
                     KeyCode::Enter => {
+                        app.edit_undo.record(channels.clone());
                         if let Some(channel) = channels.channels.get_mut(*channel_idx)
                             && let Some(msg) = channel.messages.get_mut(*message_idx)
                         {
@@ -1149,6 +1799,21 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 return;
             }
 
+            if handle_edit_undo_key(&mut app.edit_undo, channels, users, key) {
+                return;
+            }
+            let is_edit = match (key.code, key.modifiers) {
+                (KeyCode::Char(' ' | 'a' | 'n'), _) => true,
+                (KeyCode::Char('m' | 'f'), _) => !*replies_focused,
+                (KeyCode::Up | KeyCode::Down, KeyModifiers::ALT) | (KeyCode::Char('d') | KeyCode::Delete, _) => {
+                    *replies_focused
+                }
+                _ => false,
+            };
+            if is_edit {
+                app.edit_undo.record(channels.clone());
+            }
+
             if let Some(channel) = channels.channels.get_mut(*channel_idx)
                 && let Some(msg) = channel.messages.get_mut(*message_idx)
             {
@@ -1156,6 +1821,49 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let links_count = msg.links().len();
                 let total_items = files_count + links_count;
 
+                if key.code == KeyCode::Tab && !msg.replies.is_empty() {
+                    *replies_focused = !*replies_focused;
+                    return;
+                }
+                if *replies_focused {
+                    match (key.code, key.modifiers) {
+                        (KeyCode::Up, KeyModifiers::ALT) => msg.move_current_reply_up(),
+                        (KeyCode::Down, KeyModifiers::ALT) => msg.move_current_reply_down(),
+                        (KeyCode::Up, _) | (KeyCode::Char('k'), _) => msg.previous(),
+                        (KeyCode::Down, _) | (KeyCode::Char('j'), _) => msg.next(),
+                        (KeyCode::Char(' '), _) => msg.toggle_current_reply(),
+                        (KeyCode::Char('d'), _) | (KeyCode::Delete, _) => {
+                            msg.delete_current_reply();
+                            if msg.replies.is_empty() {
+                                *replies_focused = false;
+                            }
+                        }
+                        (KeyCode::Char('a'), _) => {
+                            for reply in &mut msg.replies {
+                                reply.enabled = true;
+                            }
+                        }
+                        (KeyCode::Char('n'), _) => {
+                            for reply in &mut msg.replies {
+                                reply.enabled = false;
+                            }
+                        }
+                        (KeyCode::Char('t'), _) => {
+                            *editing_text = Some(TextArea::new(msg.text()));
+                        }
+                        (KeyCode::Esc, _) => {
+                            app.screen = Screen::EditConversationsMessageList {
+                                channel_idx: *channel_idx,
+                                channels: channels.clone(),
+                                users: users.clone(),
+                                channel_data: channel_data.clone(),
+                            };
+                        }
+                        _ => {}
+                    }
+                    return;
+                }
+
                 match key.code {
                     KeyCode::Esc => {
                         app.screen = Screen::EditConversationsMessageList {
@@ -1232,6 +1940,9 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                             *editing_title = Some((idx, TextInput::new(current_title)));
                         }
                     }
+                    KeyCode::Char('t') => {
+                        *editing_text = Some(TextArea::new(msg.text()));
+                    }
                     KeyCode::Char('f') => {
                         if let Some(idx) = attachment_list_state.selected()
                             && idx >= files_count
@@ -1314,6 +2025,10 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let use_gfm = *gfm;
 
                 app.save_md_to_html_settings(&input, output.as_deref(), use_gfm);
+                app.remember_values(&[
+                    ("input-path", input.clone()),
+                    ("output-path", output.clone().unwrap_or_default()),
+                ]);
 
                 app.screen = Screen::Loading {
                     message: "Converting markdown to HTML...".to_string(),
@@ -1323,13 +2038,15 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                 let (tx, rx) = mpsc::channel();
                 app.async_result_rx = Some(rx);
 
-                thread::spawn(move || {
+                app.runtime.spawn_blocking(move || {
                     let options = if use_gfm {
                         crate::md_to_html::MdToHtmlOptions::gfm()
                     } else {
                         crate::md_to_html::MdToHtmlOptions::default()
                     };
-                    let result = crate::run_md_to_html(&input, output.as_deref(), &options);
+                    let page = crate::md_to_html::HtmlPageOptions::default();
+                    let mut reporter = crate::Reporter::default();
+                    let result = crate::run_md_to_html(&input, output.as_deref(), &options, &page, &mut reporter);
                     let _ = tx.send(AsyncResult::MdToHtmlResult(
                         result.map_err(|e| e.to_string()),
                     ));
@@ -1337,7 +2054,14 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             }
             _ => {}
         },
-        Screen::Loading { .. } => {}
+        Screen::Loading { message, .. } => {
+            if key.code == KeyCode::Esc
+                && let Some(cancel) = &app.cancel
+            {
+                cancel.cancel();
+                *message = "Cancelling, waiting for the current request...".to_string();
+            }
+        }
         Screen::Success {
             details,
             details_scroll,
@@ -1357,6 +2081,25 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
                     *details_scroll = details_scroll.saturating_sub(1);
                 }
             }
+            KeyCode::Char('p') => {
+                if let Some(path) = app.markdown_output.clone() {
+                    app.open_markdown_preview(&path, None);
+                }
+            }
+            _ => {}
+        },
+        Screen::MarkdownPreview { scroll, previous, .. } => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => {
+                app.screen = match previous.take() {
+                    Some(previous) => *previous,
+                    None => Screen::MainMenu,
+                };
+            }
+            KeyCode::Down | KeyCode::Char('j') => *scroll = scroll.saturating_add(1),
+            KeyCode::Up | KeyCode::Char('k') => *scroll = scroll.saturating_sub(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => *scroll = scroll.saturating_add(PREVIEW_PAGE),
+            KeyCode::PageUp => *scroll = scroll.saturating_sub(PREVIEW_PAGE),
+            KeyCode::Home | KeyCode::Char('g') => *scroll = 0,
             _ => {}
         },
         Screen::Error { .. } => match key.code {
@@ -1366,5 +2109,41 @@ pub fn handle_input(app: &mut App, key: KeyEvent) {
             }
             _ => {}
         },
+        Screen::DownloadResults { failures, table_state, .. } => match key.code {
+            KeyCode::Enter | KeyCode::Esc => {
+                app.screen = Screen::MainMenu;
+                app.menu_state.select(Some(0));
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                let i = table_state.selected().unwrap_or(0).saturating_sub(1);
+                table_state.select(Some(i));
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                let i = table_state.selected().map_or(0, |i| i + 1);
+                table_state.select(Some(i.min(failures.len().saturating_sub(1))));
+            }
+            KeyCode::Char('r') => app.retry_failed_downloads(),
+            _ => {}
+        },
+        Screen::EmojiBrowser { browser, .. } => match key.code {
+            KeyCode::Esc => app.screen = Screen::MainMenu,
+            KeyCode::Up => browser.previous(),
+            KeyCode::Down => browser.next(),
+            KeyCode::Tab => {
+                browser.only_problems = !browser.only_problems;
+                browser.apply_filter();
+            }
+            _ => {
+                if browser.filter.handle_key(key) {
+                    browser.apply_filter();
+                }
+            }
+        },
+        Screen::ConfirmOverwrite { .. } => match key.code {
+            KeyCode::Char('y') => app.confirm_overwrite(OverwriteChoice::Overwrite),
+            KeyCode::Char('t') => app.confirm_overwrite(OverwriteChoice::Timestamp),
+            KeyCode::Char('n') | KeyCode::Esc => app.confirm_overwrite(OverwriteChoice::Cancel),
+            _ => {}
+        },
     }
 }
@@ -0,0 +1,180 @@
+//! Conversation files read one channel at a time instead of parsed whole, so
+//! exports of several GB can be turned into markdown, indexes or downloads
+//! with memory for the largest channel only
+
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+use std::marker::PhantomData;
+
+use serde::de::{self, DeserializeOwned, IgnoredAny, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer};
+
+use crate::error::{AppError, Result};
+
+/// Call `f` with each item of the JSON array in the file at `path`, parsed
+/// as it is read. An error from `f` stops the reading and is returned.
+pub fn for_each_in_array<T, F>(path: &str, mut f: F) -> Result<()>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.to_string(),
+        source: e,
+    })?;
+    let mut deserializer = serde_json::Deserializer::from_reader(BufReader::new(file));
+    let mut failure = None;
+    let visitor = ArrayVisitor {
+        f: &mut f,
+        failure: &mut failure,
+        item: PhantomData,
+    };
+    let parsed = deserializer.deserialize_seq(visitor).and_then(|_| deserializer.end());
+    match (failure, parsed) {
+        (Some(e), _) => Err(e),
+        (None, Err(e)) => Err(AppError::JsonParse(format!("{}: {}", path, e))),
+        (None, Ok(())) => Ok(()),
+    }
+}
+
+/// Call `f` with each conversation (`{channel_id, channel_name, messages}`)
+/// of a conversations file, one at a time
+pub fn for_each_conversation<F>(path: &str, f: F) -> Result<()>
+where
+    F: FnMut(serde_json::Value) -> Result<()>,
+{
+    for_each_in_array(path, f)
+}
+
+/// Top-level messages in a conversations file, counted without keeping them
+pub fn count_conversation_messages(path: &str) -> Result<usize> {
+    #[derive(Deserialize)]
+    struct MessageCount {
+        #[serde(default)]
+        messages: Vec<IgnoredAny>,
+    }
+
+    let mut count = 0;
+    for_each_in_array(path, |conversation: MessageCount| {
+        count += conversation.messages.len();
+        Ok(())
+    })?;
+    Ok(count)
+}
+
+struct ArrayVisitor<'a, T, F> {
+    f: &'a mut F,
+    /// Error returned by `f`, kept as is instead of turned into a parse error
+    failure: &'a mut Option<AppError>,
+    item: PhantomData<T>,
+}
+
+impl<'de, T, F> Visitor<'de> for ArrayVisitor<'_, T, F>
+where
+    T: DeserializeOwned,
+    F: FnMut(T) -> Result<()>,
+{
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a JSON array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> std::result::Result<(), A::Error> {
+        while let Some(item) = seq.next_element::<T>()? {
+            if let Err(e) = (self.f)(item) {
+                *self.failure = Some(e);
+                return Err(de::Error::custom("stopped"));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Conversations to process: already in memory, or a file read one
+/// conversation at a time
+#[derive(Debug, Clone, Copy)]
+pub enum ConversationSource<'a> {
+    Loaded(&'a [serde_json::Value]),
+    File(&'a str),
+}
+
+impl ConversationSource<'_> {
+    /// Top-level messages across all conversations
+    pub fn message_count(&self) -> Result<usize> {
+        match self {
+            ConversationSource::Loaded(conversations) => Ok(conversations
+                .iter()
+                .filter_map(|c| c.get("messages").and_then(|m| m.as_array()))
+                .map(|m| m.len())
+                .sum()),
+            ConversationSource::File(path) => count_conversation_messages(path),
+        }
+    }
+
+    /// Call `f` with each conversation in order
+    pub fn for_each<F>(&self, mut f: F) -> Result<()>
+    where
+        F: FnMut(&serde_json::Value) -> Result<()>,
+    {
+        match self {
+            ConversationSource::Loaded(conversations) => conversations.iter().try_for_each(f),
+            ConversationSource::File(path) => for_each_conversation(path, |conversation| f(&conversation)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_conversations(dir: &std::path::Path) -> String {
+        let path = dir.join("conversations.json");
+        let conversations = serde_json::json!([
+            {"channel_id": "C1", "channel_name": "general", "messages": [{"ts": "1.0"}, {"ts": "2.0"}]},
+            {"channel_id": "C2", "channel_name": "random", "messages": [{"ts": "3.0"}]}
+        ]);
+        std::fs::write(&path, serde_json::to_vec_pretty(&conversations).unwrap()).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_for_each_conversation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_conversations(dir.path());
+        let mut ids = Vec::new();
+        for_each_conversation(&path, |conversation| {
+            ids.push(conversation["channel_id"].as_str().unwrap().to_string());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(ids, vec!["C1", "C2"]);
+        assert_eq!(count_conversation_messages(&path).unwrap(), 3);
+        assert_eq!(ConversationSource::File(&path).message_count().unwrap(), 3);
+    }
+
+    #[test]
+    fn test_for_each_conversation_stops_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = write_conversations(dir.path());
+        let mut seen = 0;
+        let result = for_each_conversation(&path, |_| {
+            seen += 1;
+            Err(AppError::Cancelled("enough".to_string()))
+        });
+        assert!(matches!(result, Err(AppError::Cancelled(_))));
+        assert_eq!(seen, 1);
+    }
+
+    #[test]
+    fn test_for_each_conversation_invalid_json() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("broken.json");
+        std::fs::write(&path, r#"[{"channel_id": "C1"}, {"#).unwrap();
+        let result = for_each_conversation(&path.to_string_lossy(), |_| Ok(()));
+        assert!(matches!(result, Err(AppError::JsonParse(_))));
+        let result = for_each_conversation(&dir.path().join("missing.json").to_string_lossy(), |_| Ok(()));
+        assert!(matches!(result, Err(AppError::ReadFile { .. })));
+    }
+}
@@ -1,20 +1,64 @@
 use std::fs::File;
-use std::io::BufReader;
+use std::io::{BufReader, IsTerminal};
+use std::sync::mpsc;
+use std::time::Duration;
 
 use chrono::{Datelike, Local, NaiveDate};
 
+use progress_bars::{quiet_progress, ProgressBars};
+use summary::say;
+
+mod archive_input;
+mod attachments;
+mod backfill;
 mod cli;
 mod commands;
+mod compact;
+mod convert;
+mod corpus;
+mod daemon;
+mod date_spec;
+mod delta;
+mod diff;
+mod digest;
+mod dry_run;
+mod emojis;
 mod error;
+mod export_report;
+mod export_writer;
+pub mod exporter;
+mod extract;
 mod formatter;
 mod index;
+mod json_stream;
+mod links;
+pub mod live_archive;
+mod logging;
 mod markdown;
 pub mod md_to_html;
 mod meilisearch;
+mod merge;
+mod overflow;
 mod parquet;
+mod post;
+pub mod pipeline;
+mod progress_bars;
+mod provenance;
+mod secrets;
 mod settings;
+mod site;
 mod slack;
 mod slack_render;
+mod split;
+mod sqlite_index;
+mod stats;
+mod stdio;
+pub mod storage;
+mod summarize;
+mod summary;
+mod sync;
+mod tantivy_search;
+mod validate;
 
 #[cfg(feature = "tui")]
 mod app;
@@ -28,14 +72,20 @@ mod widgets;
 #[cfg(feature = "duckdb")]
 pub mod duckdb_query;
 
+#[cfg(feature = "datafusion")]
+pub mod datafusion_query;
+
 #[cfg(feature = "server")]
 pub mod archive_server;
+#[cfg(feature = "server")]
+pub mod live;
 
 // Re-export meilisearch types for the server binary
 #[cfg(feature = "server")]
-pub use index::{IndexChannel, IndexEntry, IndexUser};
+pub use index::{IndexChannel, IndexEntry, IndexUser, SearchHit};
 #[cfg(feature = "server")]
 pub use meilisearch::query_meilisearch;
+pub use meilisearch::SearchFilters;
 
 /// Output format for export commands
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
@@ -67,30 +117,191 @@ impl std::str::FromStr for OutputFormat {
 }
 
 // Re-export public API
-pub use cli::{Cli, Commands};
-pub use error::{AppError, Result};
+pub use cli::{AttachmentFilterArgs, Cli, Commands, DownloadArgs, JoinArgs, SummarizerArgs};
+pub use error::{AppError, Result, EXIT_PARTIAL};
+pub use exporter::{SlackExporter, SlackExporterBuilder};
+pub use logging::{init_logging, LogLevel};
+pub use progress_bars::set_quiet_progress;
+pub use summary::{CommandSummary, Reporter};
+pub use tokio_util::sync::CancellationToken;
 pub use formatter::{FormatterOutput, FormatterResponse, FormatterStats, MarkdownExportOptions};
 pub use markdown::{
-    export_conversations_to_markdown, export_conversations_to_markdown_with_options,
-    export_conversations_to_markdown_with_progress,
+    export_conversation_values_to_markdown, export_conversations_to_markdown,
+    export_conversations_to_markdown_with_options, export_conversations_to_markdown_with_progress,
 };
-pub use parquet::{write_channels_parquet, write_conversations_parquet, write_users_parquet};
+pub use parquet::{
+    read_channels_parquet, read_parquet_as_json, read_users_parquet, write_channels_parquet,
+    write_conversations_parquet, write_users_parquet, JsonRow, MessagePage,
+};
+pub use provenance::{archive_info, ArchiveInfo, ParquetFileInfo, Provenance};
+pub use archive_input::{load_channels, load_conversations, load_users, ArchiveWeek, ARCHIVE_INPUT_PREFIX};
 
-/// Type alias for progress callback functions
+/// Type alias for progress callback functions of local tasks (file
+/// conversions, indexing); Slack tasks send `ProgressEvent`s instead
 pub type ProgressCallback<'a> = Option<&'a dyn Fn(usize, usize, &str)>;
 
-/// Type alias for rate limit callback functions (wait_secs, attempt, max_attempts)
-pub type RateLimitCallback<'a> = Option<&'a dyn Fn(u64, u32, u32)>;
+/// What a Slack task reports while it runs, sent over the channel given to
+/// `SlackApiCallbacks::with_events`. The CLI prints them, the TUI turns them
+/// into its progress and log panes and library users can render them as
+/// they like.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProgressEvent {
+    /// Step `current` of `total` (0 when unknown) of the task
+    Status { current: usize, total: usize, message: String },
+    /// Fetching a channel's history started, after `index` of `total` channels
+    ChannelStarted { channel: String, index: usize, total: usize },
+    /// Page `page` of a channel's history arrived with `messages` messages
+    PageFetched { channel: String, page: usize, messages: usize },
+    /// Thread `done` of the `total` in a channel arrived with `replies` replies
+    ThreadFetched { channel: String, done: usize, total: usize, replies: usize },
+    /// Every channel of a conversations export was fetched
+    ChannelsFetched { total: usize },
+    /// Waiting before retrying a rate limited call
+    RateLimited { wait_secs: u64, attempt: u32, max_attempts: u32 },
+    /// Bytes received, rate and ETA of a download task
+    BytesDownloaded(TransferProgress),
+    /// A line describing an API call or step, for activity logs
+    Log(String),
+}
+
+/// Sending half of a task's event channel
+pub type ProgressSender = mpsc::Sender<ProgressEvent>;
+
+/// Nested progress of a conversation export, built from its
+/// `ProgressEvent`s with `apply`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportProgress {
+    pub channels_done: usize,
+    pub channels_total: usize,
+    /// Name of the channel being fetched
+    pub channel: String,
+    /// History pages fetched for the current channel
+    pub pages: usize,
+    pub threads_done: usize,
+    pub threads_total: usize,
+    /// Messages and replies fetched so far across all channels
+    pub messages: usize,
+}
 
-/// Unified callbacks for Slack API operations
-/// This struct provides a consistent way to handle progress and rate limit
-/// notifications across both CLI and TUI contexts.
+impl ExportProgress {
+    /// Update with an event of the export; returns whether it was one
+    pub fn apply(&mut self, event: &ProgressEvent) -> bool {
+        match event {
+            ProgressEvent::ChannelStarted { channel, index, total } => {
+                self.channels_done = *index;
+                self.channels_total = *total;
+                self.channel = channel.clone();
+                self.pages = 0;
+                self.threads_done = 0;
+                self.threads_total = 0;
+            }
+            ProgressEvent::PageFetched { page, messages, .. } => {
+                self.pages = *page;
+                self.messages += messages;
+            }
+            ProgressEvent::ThreadFetched { done, total, replies, .. } => {
+                self.threads_done = *done;
+                self.threads_total = *total;
+                self.messages += replies;
+            }
+            ProgressEvent::ChannelsFetched { total } => self.channels_done = *total,
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Byte progress of a download task (attachments, emojis), reported
+/// alongside the flat (current, total, name) progress
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TransferProgress {
+    pub files_done: usize,
+    pub files_total: usize,
+    /// Bytes received so far in this run
+    pub bytes: u64,
+    /// Sum of the sizes of the files to download, 0 when unknown
+    pub bytes_total: u64,
+    /// Average bytes per second since the download started
+    pub rate: f64,
+    /// Time left at the current rate; by file count when sizes are unknown
+    pub eta: Option<Duration>,
+}
+
+impl TransferProgress {
+    /// Progress after `elapsed`, with the rate and ETA worked out
+    pub fn new(files_done: usize, files_total: usize, bytes: u64, bytes_total: u64, elapsed: Duration) -> Self {
+        let secs = elapsed.as_secs_f64();
+        let rate = if secs > 0.0 { bytes as f64 / secs } else { 0.0 };
+        let eta = if bytes_total > 0 && rate > 0.0 {
+            Some(Duration::from_secs_f64(bytes_total.saturating_sub(bytes) as f64 / rate))
+        } else if files_done > 0 && files_done <= files_total {
+            Some(elapsed.mul_f64((files_total - files_done) as f64 / files_done as f64))
+        } else {
+            None
+        };
+        Self {
+            files_done,
+            files_total,
+            bytes,
+            bytes_total,
+            rate,
+            eta,
+        }
+    }
+
+    /// One line summary, e.g. `12.0 MB / 48.0 MB, 1.5 MB/s, ETA 0:24`
+    pub fn summary(&self) -> String {
+        let mut summary = format_bytes(self.bytes);
+        if self.bytes_total > 0 {
+            summary.push_str(&format!(" / {}", format_bytes(self.bytes_total)));
+        }
+        summary.push_str(&format!(", {}/s", format_bytes(self.rate as u64)));
+        if let Some(eta) = self.eta {
+            let secs = eta.as_secs();
+            summary.push_str(&format!(", ETA {}:{:02}", secs / 60, secs % 60));
+        }
+        summary
+    }
+}
+
+/// Size in B, KB, MB or GB (1024 based) with one decimal
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    let unit = UNITS.get(unit).copied().unwrap_or("B");
+    if unit == "B" {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, unit)
+    }
+}
+
+/// Type alias for cancellation flags; long tasks stop once the token is cancelled
+pub type CancelFlag<'a> = Option<&'a CancellationToken>;
+
+/// Whether `cancel` has been cancelled
+pub fn is_cancelled(cancel: CancelFlag<'_>) -> bool {
+    cancel.is_some_and(CancellationToken::is_cancelled)
+}
+
+/// Where a Slack task sends its progress events and what stops it, shared
+/// by the CLI, the TUI and library users
 #[derive(Clone, Copy, Default)]
 pub struct SlackApiCallbacks<'a> {
-    /// Called to report progress (current, total, message)
-    pub on_progress: ProgressCallback<'a>,
-    /// Called when rate limited (wait_secs, attempt, max_attempts)
-    pub on_rate_limit: RateLimitCallback<'a>,
+    /// Receives the task's `ProgressEvent`s
+    pub events: Option<&'a ProgressSender>,
+    /// Prepended to `Status` messages, for tasks run as a step of another
+    pub status_prefix: Option<&'a str>,
+    /// Set for a task run as part of a larger one that reports its own
+    /// steps: its `Status` events are not sent, the rest are
+    pub nested: bool,
+    /// Checked between API calls; once set the operation returns `AppError::Cancelled`
+    pub cancel: CancelFlag<'a>,
 }
 
 impl<'a> SlackApiCallbacks<'a> {
@@ -98,35 +309,92 @@ impl<'a> SlackApiCallbacks<'a> {
         Self::default()
     }
 
-    pub fn with_progress(mut self, callback: &'a dyn Fn(usize, usize, &str)) -> Self {
-        self.on_progress = Some(callback);
+    pub fn with_events(mut self, events: &'a ProgressSender) -> Self {
+        self.events = Some(events);
         self
     }
 
-    pub fn with_rate_limit(mut self, callback: &'a dyn Fn(u64, u32, u32)) -> Self {
-        self.on_rate_limit = Some(callback);
+    pub fn with_status_prefix(mut self, prefix: &'a str) -> Self {
+        self.status_prefix = Some(prefix);
         self
     }
 
+    pub fn with_cancel(mut self, cancel: &'a CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
+    /// The same events and cancel flag for a task run as part of this one
+    pub fn nested(self) -> Self {
+        Self { nested: true, ..self }
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        is_cancelled(self.cancel)
+    }
+
+    /// Resolves once the cancel token is cancelled, never without one; raced
+    /// against waits (rate limits, downloads in flight) so they stop promptly
+    pub async fn cancelled(&self) {
+        match self.cancel {
+            Some(token) => token.cancelled().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Whether anything receives the events, so work done only to report
+    /// them can be skipped
+    pub fn has_events(&self) -> bool {
+        self.events.is_some()
+    }
+
+    pub fn emit(&self, event: ProgressEvent) {
+        if let Some(events) = self.events {
+            // A receiver that went away only stops seeing progress
+            let _ = events.send(event);
+        }
+    }
+
     pub fn report_progress(&self, current: usize, total: usize, message: &str) {
-        if let Some(cb) = self.on_progress {
-            cb(current, total, message);
+        if self.nested {
+            return;
         }
+        let message = match self.status_prefix {
+            Some(prefix) => format!("{}: {}", prefix, message),
+            None => message.to_string(),
+        };
+        self.emit(ProgressEvent::Status { current, total, message });
+    }
+
+    pub fn report_transfer(&self, progress: &TransferProgress) {
+        self.emit(ProgressEvent::BytesDownloaded(progress.clone()));
+    }
+
+    /// Also recorded in the diagnostic logs at debug level
+    pub fn log(&self, line: &str) {
+        tracing::debug!("{}", line);
+        self.emit(ProgressEvent::Log(line.to_string()));
     }
 
     pub fn report_rate_limit(&self, wait_secs: u64, attempt: u32, max_attempts: u32) {
-        if let Some(cb) = self.on_rate_limit {
-            cb(wait_secs, attempt, max_attempts);
-        }
+        self.emit(ProgressEvent::RateLimited {
+            wait_secs,
+            attempt,
+            max_attempts,
+        });
     }
 }
 
-/// CLI progress callback - prints progress to stdout
-pub fn cli_progress(current: usize, total: usize, name: &str) {
+/// CLI progress callback - prints progress to stdout (stderr when
+/// `to_stderr`), nothing under `--quiet`
+pub fn cli_progress(to_stderr: bool, current: usize, total: usize, name: &str) {
+    if quiet_progress() {
+        return;
+    }
     if total > 0 {
-        println!("  [{}/{}] {}", current, total, name);
+        say(to_stderr, format_args!("  [{}/{}] {}", current, total, name));
     } else {
-        println!("  {}", name);
+        say(to_stderr, format_args!("  {}", name));
     }
 }
 
@@ -138,26 +406,137 @@ pub fn cli_rate_limit(wait_secs: u64, attempt: u32, max_attempts: u32) {
     );
 }
 
-/// Create CLI-appropriate SlackApiCallbacks
-pub fn cli_callbacks() -> SlackApiCallbacks<'static> {
-    SlackApiCallbacks::new()
-        .with_progress(&cli_progress)
-        .with_rate_limit(&cli_rate_limit)
+/// Shows the progress events of a CLI command's Slack task from a
+/// background thread, until finished or dropped: as progress bars on a
+/// terminal, one line per status and rate limit otherwise, and not at all
+/// under `--quiet`. Ctrl+C meanwhile cancels the task, which stops at its
+/// next step and reports what it got done; a second Ctrl+C quits right away.
+pub struct CliProgress {
+    sender: Option<ProgressSender>,
+    printer: Option<std::thread::JoinHandle<()>>,
+    cancel: CancellationToken,
+    ctrl_c: Option<tokio::task::JoinHandle<()>>,
+}
+
+impl CliProgress {
+    pub fn start(reporter: &Reporter) -> Self {
+        let to_stderr = reporter.messages_to_stderr();
+        let (sender, receiver) = mpsc::channel();
+        let printer = std::thread::spawn(move || {
+            if quiet_progress() {
+                receiver.into_iter().for_each(drop);
+            } else if std::io::stderr().is_terminal() {
+                let mut bars = ProgressBars::stderr();
+                receiver.into_iter().for_each(|event| bars.handle(event));
+                bars.finish();
+            } else {
+                for event in receiver {
+                    match event {
+                        ProgressEvent::Status { current, total, message } => {
+                            cli_progress(to_stderr, current, total, &message)
+                        }
+                        ProgressEvent::RateLimited {
+                            wait_secs,
+                            attempt,
+                            max_attempts,
+                        } => cli_rate_limit(wait_secs, attempt, max_attempts),
+                        _ => {}
+                    }
+                }
+            }
+        });
+        let cancel = CancellationToken::new();
+        let ctrl_c = tokio::runtime::Handle::try_current().ok().map(|runtime| {
+            let cancel = cancel.clone();
+            runtime.spawn(async move {
+                if tokio::signal::ctrl_c().await.is_ok() {
+                    eprintln!("\nStopping after the current step, press Ctrl+C again to quit now...");
+                    cancel.cancel();
+                    if tokio::signal::ctrl_c().await.is_ok() {
+                        std::process::exit(130);
+                    }
+                }
+            })
+        });
+        Self {
+            sender: Some(sender),
+            printer: Some(printer),
+            cancel,
+            ctrl_c,
+        }
+    }
+
+    pub fn callbacks(&self) -> SlackApiCallbacks<'_> {
+        SlackApiCallbacks {
+            events: self.sender.as_ref(),
+            ..SlackApiCallbacks::new().with_cancel(&self.cancel)
+        }
+    }
+
+    /// Wait until every event sent has been printed
+    pub fn finish(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(ctrl_c) = self.ctrl_c.take() {
+            ctrl_c.abort();
+        }
+        self.sender = None;
+        if let Some(printer) = self.printer.take() {
+            let _ = printer.join();
+        }
+    }
+}
+
+impl Drop for CliProgress {
+    fn drop(&mut self) {
+        self.stop();
+    }
 }
 
 // Re-export command functions for main.rs
+pub use commands::run_activity;
+pub use commands::run_archive_compact;
+pub use commands::run_archive_daemon;
+pub use commands::run_archive_extract;
+pub use commands::run_archive_info;
 pub use commands::run_archive_range;
+pub use commands::run_archive_sync;
+pub use commands::run_archive_vacuum;
+pub use commands::run_backfill_threads;
+pub use commands::run_build_site;
+pub use commands::run_convert_to;
+pub use commands::run_diff;
+pub use commands::run_digest;
 pub use commands::run_download_attachments;
+pub use commands::run_encrypt_settings;
 pub use commands::run_export_channels;
 pub use commands::run_export_conversations;
 pub use commands::run_export_conversations_week;
+pub use commands::run_export_corpus;
+pub use commands::run_export_emoji_pack;
 pub use commands::run_export_emojis;
 pub use commands::run_export_index;
 pub use commands::run_export_markdown;
 pub use commands::run_export_users;
 pub use commands::run_import_index_meilisearch;
+pub use commands::run_links;
+pub use commands::run_live_archive;
+pub use commands::run_index_tantivy;
 pub use commands::run_md_to_html;
+pub use commands::run_md_to_html_dir;
+pub use commands::run_merge;
+pub use commands::run_post_message;
 pub use commands::run_query_meilisearch;
+pub use commands::run_query_tantivy;
+pub use commands::run_repair_conversations;
+#[cfg(feature = "datafusion")]
+pub use commands::run_sql;
+pub use commands::run_split;
+pub use commands::run_stats;
+pub use commands::run_summarize;
+pub use commands::run_validate;
 pub use commands::run_work_week;
 
 /// Constant for the channels file
@@ -168,6 +547,11 @@ pub fn load_token() -> Result<String> {
     std::env::var("SLACK_TOKEN").map_err(|_| AppError::MissingToken)
 }
 
+/// Load the app-level Socket Mode token from environment
+pub fn load_app_token() -> Result<String> {
+    std::env::var("SLACK_APP_TOKEN").map_err(|_| AppError::MissingAppToken)
+}
+
 /// Default from date (30 days ago)
 pub fn default_from_date() -> NaiveDate {
     Local::now().date_naive() - chrono::Duration::days(30)
@@ -193,6 +577,19 @@ pub fn load_json_file<T: serde::de::DeserializeOwned>(path: &str) -> Result<T> {
     serde_json::from_reader(reader).map_err(|e| AppError::JsonParse(e.to_string()))
 }
 
+/// Insert a local timestamp before the extension of `path`, e.g.
+/// `users.json` becomes `users-20240115-093000.json`
+pub fn timestamped_path(path: &str, now: chrono::NaiveDateTime) -> String {
+    let stamp = now.format("%Y%m%d-%H%M%S");
+    let path = std::path::Path::new(path);
+    let stem = path.file_stem().map(|s| s.to_string_lossy()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{}-{}.{}", stem, stamp, ext.to_string_lossy()),
+        None => format!("{}-{}", stem, stamp),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
 /// Get current ISO year and week number
 pub fn current_iso_week() -> (i32, u32) {
     let today = Local::now().date_naive();
@@ -215,7 +612,6 @@ pub fn week_to_date_range(year: i32, week: u32) -> Result<(NaiveDate, NaiveDate)
 #[cfg(feature = "tui")]
 pub fn run_ui() -> Result<()> {
     use std::io;
-    use std::time::Duration;
 
     use crossterm::{
         event::{self, Event, KeyEventKind},
@@ -226,13 +622,24 @@ pub fn run_ui() -> Result<()> {
 
     let token = load_token()?;
 
+    // Background tasks share the runtime of the caller (the async main), or
+    // one made here when called outside of one
+    let owned_runtime = match tokio::runtime::Handle::try_current() {
+        Ok(_) => None,
+        Err(_) => Some(tokio::runtime::Runtime::new()?),
+    };
+    let runtime = match &owned_runtime {
+        Some(runtime) => runtime.handle().clone(),
+        None => tokio::runtime::Handle::current(),
+    };
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen)?;
     let backend = ratatui::backend::CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = app::App::new(token);
+    let mut app = app::App::new(token, runtime);
 
     loop {
         app.check_async_result();
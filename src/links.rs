@@ -0,0 +1,359 @@
+//! Shared links report: the URLs found in message text, blocks and unfurled
+//! attachments, normalized and ranked by how often they were shared
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+use crate::stats::post_time;
+
+/// Query parameters that only track where a click came from
+fn is_tracking_param(key: &str) -> bool {
+    key.starts_with("utm_") || matches!(key, "fbclid" | "gclid" | "msclkid" | "mc_cid" | "mc_eid")
+}
+
+/// Normalize a shared URL so different spellings of one page count together:
+/// lowercase host, no fragment, no tracking parameters, no trailing slash.
+/// Returns the URL and its domain without `www.`, `None` for anything that
+/// isn't http(s).
+pub fn normalize_url(raw: &str) -> Option<(String, String)> {
+    let mut url = url::Url::parse(raw.trim()).ok()?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return None;
+    }
+    url.set_fragment(None);
+    let kept: Vec<(String, String)> = url
+        .query_pairs()
+        .filter(|(key, _)| !is_tracking_param(key))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+    if kept.is_empty() {
+        url.set_query(None);
+    } else {
+        url.query_pairs_mut().clear().extend_pairs(kept);
+    }
+    let domain = url.host_str()?.trim_start_matches("www.").to_string();
+
+    let mut normalized = url.to_string();
+    if url.query().is_none() && normalized.ends_with('/') {
+        normalized.pop();
+    }
+    Some((normalized, domain))
+}
+
+/// `<https://...|label>` links in mrkdwn text
+fn text_links(text: &str) -> impl Iterator<Item = &str> {
+    text.split('<')
+        .skip(1)
+        .filter_map(|rest| rest.split('>').next())
+        .map(|link| link.split('|').next().unwrap_or(link))
+        .filter(|link| link.starts_with("http"))
+}
+
+/// `link` elements anywhere in a block tree
+fn block_links<'a>(value: &'a serde_json::Value, links: &mut Vec<&'a str>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            if map.get("type").and_then(|t| t.as_str()) == Some("link")
+                && let Some(url) = map.get("url").and_then(|u| u.as_str())
+            {
+                links.push(url);
+            }
+            map.values().for_each(|v| block_links(v, links));
+        }
+        serde_json::Value::Array(items) => items.iter().for_each(|v| block_links(v, links)),
+        _ => {}
+    }
+}
+
+/// Normalized URLs shared in one message, each once
+pub fn message_links(message: &serde_json::Value) -> BTreeSet<(String, String)> {
+    let mut raw: Vec<&str> = Vec::new();
+    if let Some(text) = message.get("text").and_then(|t| t.as_str()) {
+        raw.extend(text_links(text));
+    }
+    if let Some(blocks) = message.get("blocks") {
+        block_links(blocks, &mut raw);
+    }
+    let attachments = message
+        .get("attachments")
+        .and_then(|a| a.as_array())
+        .map(|a| a.as_slice())
+        .unwrap_or_default();
+    for attachment in attachments {
+        for key in ["from_url", "original_url", "title_link"] {
+            if let Some(url) = attachment.get(key).and_then(|u| u.as_str()) {
+                raw.push(url);
+            }
+        }
+    }
+    raw.into_iter().filter_map(normalize_url).collect()
+}
+
+/// One URL and who shared it when
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinkStats {
+    pub url: String,
+    pub domain: String,
+    /// Messages that shared it
+    pub count: usize,
+    /// YYYY-MM-DD, UTC
+    pub first_shared: String,
+    pub last_shared: String,
+    /// Display names, sorted
+    pub sharers: Vec<String>,
+    /// Channel names, sorted
+    pub channels: Vec<String>,
+}
+
+/// Links aggregated by domain
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DomainStats {
+    pub domain: String,
+    /// Messages that shared a link to it
+    pub count: usize,
+    /// Distinct URLs
+    pub urls: usize,
+    pub first_shared: String,
+    pub last_shared: String,
+}
+
+/// Shared links ranked by count, most shared first
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LinksReport {
+    pub domains: Vec<DomainStats>,
+    pub links: Vec<LinkStats>,
+}
+
+#[derive(Default)]
+struct Shares {
+    domain: String,
+    count: usize,
+    /// Distinct URLs, when aggregating a domain
+    urls: usize,
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    sharers: BTreeSet<String>,
+    channels: BTreeSet<String>,
+}
+
+/// The earlier of two optional times, either one when the other is unknown
+fn earliest(a: Option<DateTime<Utc>>, b: Option<DateTime<Utc>>) -> Option<DateTime<Utc>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+fn day(time: Option<DateTime<Utc>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d").to_string()).unwrap_or_default()
+}
+
+/// Collect the links shared in `conversations`, replies included.
+/// `user_names` maps user IDs to display names; users missing from it are
+/// shown by ID.
+pub fn compute_links(conversations: &[serde_json::Value], user_names: &HashMap<String, String>) -> LinksReport {
+    let mut by_url: HashMap<String, Shares> = HashMap::new();
+
+    for conversation in conversations {
+        let channel_id = conversation
+            .get("channel_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let channel = conversation
+            .get("channel_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(channel_id);
+        let messages = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        let replies = messages
+            .iter()
+            .filter_map(|m| m.get("thread_replies").and_then(|r| r.as_array()))
+            .flatten();
+
+        for message in messages.iter().chain(replies) {
+            let time = post_time(message);
+            let sharer = message
+                .get("user")
+                .and_then(|u| u.as_str())
+                .map(|id| user_names.get(id).map_or(id, |name| name.as_str()));
+            for (url, domain) in message_links(message) {
+                let shares = by_url.entry(url).or_default();
+                shares.domain = domain;
+                shares.count += 1;
+                shares.first = earliest(shares.first, time);
+                shares.last = shares.last.max(time);
+                shares.sharers.extend(sharer.map(str::to_string));
+                shares.channels.insert(channel.to_string());
+            }
+        }
+    }
+
+    let mut by_domain: HashMap<&str, Shares> = HashMap::new();
+    for shares in by_url.values() {
+        let domain = by_domain.entry(&shares.domain).or_default();
+        domain.count += shares.count;
+        domain.urls += 1;
+        domain.first = earliest(domain.first, shares.first);
+        domain.last = domain.last.max(shares.last);
+    }
+    let mut domains: Vec<DomainStats> = by_domain
+        .into_iter()
+        .map(|(domain, shares)| DomainStats {
+            domain: domain.to_string(),
+            count: shares.count,
+            urls: shares.urls,
+            first_shared: day(shares.first),
+            last_shared: day(shares.last),
+        })
+        .collect();
+    domains.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.domain.cmp(&b.domain)));
+
+    let mut links: Vec<LinkStats> = by_url
+        .into_iter()
+        .map(|(url, shares)| LinkStats {
+            url,
+            domain: shares.domain,
+            count: shares.count,
+            first_shared: day(shares.first),
+            last_shared: day(shares.last),
+            sharers: shares.sharers.into_iter().collect(),
+            channels: shares.channels.into_iter().collect(),
+        })
+        .collect();
+    links.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.url.cmp(&b.url)));
+
+    LinksReport { domains, links }
+}
+
+/// Write `report` as JSON to `output_path`
+pub fn write_links(report: &LinksReport, output_path: &str) -> Result<()> {
+    let file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), report).map_err(|e| AppError::JsonSerialize(e.to_string()))
+}
+
+/// Markdown report of `report`, listing at most `top` domains and links
+pub fn links_markdown_report(report: &LinksReport, top: usize) -> String {
+    let mut md = String::from("# Shared links\n\n## Domains\n\n");
+    md.push_str("| Domain | Shares | URLs | First shared | Last shared |\n|---|---:|---:|---|---|\n");
+    for d in report.domains.iter().take(top) {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} | {} |",
+            d.domain, d.count, d.urls, d.first_shared, d.last_shared
+        );
+    }
+
+    md.push_str("\n## Links\n\n| URL | Shares | First shared | Last shared | Shared by | Channels |\n");
+    md.push_str("|---|---:|---|---|---|---|\n");
+    for l in report.links.iter().take(top) {
+        let channels: Vec<String> = l.channels.iter().map(|c| format!("#{}", c)).collect();
+        let _ = writeln!(
+            md,
+            "| <{}> | {} | {} | {} | {} | {} |",
+            l.url,
+            l.count,
+            l.first_shared,
+            l.last_shared,
+            l.sharers.join(", "),
+            channels.join(", ")
+        );
+    }
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_normalize_url() {
+        assert_eq!(
+            normalize_url("https://WWW.Example.com/docs/?utm_source=slack&id=3#intro"),
+            Some(("https://www.example.com/docs/?id=3".to_string(), "example.com".to_string()))
+        );
+        assert_eq!(
+            normalize_url("https://example.com/docs/?utm_medium=x"),
+            Some(("https://example.com/docs".to_string(), "example.com".to_string()))
+        );
+        assert_eq!(
+            normalize_url("http://example.com"),
+            Some(("http://example.com".to_string(), "example.com".to_string()))
+        );
+        assert_eq!(normalize_url("mailto:ana@example.com"), None);
+        assert_eq!(normalize_url("not a url"), None);
+    }
+
+    #[test]
+    fn test_message_links() {
+        let message = json!({
+            "text": "see <https://example.com/a|the docs> and <@U1> <https://example.com/a#top>",
+            "blocks": [{"type": "rich_text", "elements": [{"type": "rich_text_section", "elements": [
+                {"type": "link", "url": "https://rust-lang.org/"}
+            ]}]}],
+            "attachments": [{"from_url": "https://github.com/org/repo?utm_source=slack"}]
+        });
+        let urls: Vec<String> = message_links(&message).into_iter().map(|(url, _)| url).collect();
+        assert_eq!(
+            urls,
+            ["https://example.com/a", "https://github.com/org/repo", "https://rust-lang.org"]
+        );
+    }
+
+    #[test]
+    fn test_compute_links() {
+        // 1704103200 is 2024-01-01, 1705312800 is 2024-01-15
+        let conversations = vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {
+                        "ts": "1705312800.000100",
+                        "user": "U1",
+                        "text": "<https://example.com/a>",
+                        "thread_replies": [
+                            {"ts": "1705312860.000100", "user": "U2", "text": "also <https://example.com/b>"}
+                        ]
+                    }
+                ]
+            }),
+            json!({
+                "channel_id": "C2",
+                "channel_name": "random",
+                "messages": [{"ts": "1704103200.000100", "user": "U2", "text": "<https://example.com/a/>"}]
+            }),
+        ];
+        let names = HashMap::from([("U1".to_string(), "ana".to_string())]);
+        let report = compute_links(&conversations, &names);
+
+        assert_eq!(report.links.len(), 2);
+        let top = &report.links[0];
+        assert_eq!(top.url, "https://example.com/a");
+        assert_eq!(top.count, 2);
+        assert_eq!((top.first_shared.as_str(), top.last_shared.as_str()), ("2024-01-01", "2024-01-15"));
+        assert_eq!(top.sharers, ["U2", "ana"]);
+        assert_eq!(top.channels, ["general", "random"]);
+
+        assert_eq!(report.domains.len(), 1);
+        assert_eq!(report.domains[0].count, 3);
+        assert_eq!(report.domains[0].urls, 2);
+        assert_eq!(report.domains[0].first_shared, "2024-01-01");
+
+        let md = links_markdown_report(&report, 1);
+        assert!(md.contains("| <https://example.com/a> | 2 | 2024-01-01 | 2024-01-15 | U2, ana | #general, #random |"));
+        assert!(!md.contains("example.com/b"));
+    }
+}
@@ -0,0 +1,166 @@
+//! Live tail of new Slack messages via Socket Mode
+//!
+//! The server opens a Socket Mode connection with an app-level token and
+//! re-broadcasts every new message to the WebSocket clients of `/archive/live`.
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+use tokio::sync::broadcast;
+
+use crate::{AppError, Result};
+
+/// A new message as sent to live-tail subscribers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, utoipa::ToSchema)]
+pub struct LiveMessage {
+    pub channel: String,
+    pub ts: String,
+    pub user: Option<String>,
+    pub thread_ts: Option<String>,
+    pub text: String,
+}
+
+impl LiveMessage {
+    /// Messages worth showing in a tail; edits, deletions and hidden
+    /// events are skipped
+    pub fn from_event(event: &SlackMessageEvent) -> Option<Self> {
+        if event.hidden == Some(true)
+            || matches!(
+                event.subtype,
+                Some(SlackMessageEventType::MessageChanged | SlackMessageEventType::MessageDeleted)
+            )
+        {
+            return None;
+        }
+        Some(Self {
+            channel: event.origin.channel.as_ref()?.to_string(),
+            ts: event.origin.ts.to_string(),
+            user: event.sender.user.as_ref().map(ToString::to_string),
+            thread_ts: event.origin.thread_ts.as_ref().map(ToString::to_string),
+            text: event.content.as_ref()?.text.clone().unwrap_or_default(),
+        })
+    }
+}
+
+/// Fan-out of live messages to any number of subscribers
+#[derive(Debug, Clone)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveMessage>,
+}
+
+impl LiveFeed {
+    /// Subscribers lagging more than `capacity` messages behind skip ahead
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity.max(1));
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveMessage> {
+        self.sender.subscribe()
+    }
+
+    /// Send a message to current subscribers, returning how many received it
+    pub fn publish(&self, message: LiveMessage) -> usize {
+        self.sender.send(message).unwrap_or(0)
+    }
+}
+
+async fn on_push_event(
+    event: SlackPushEventCallback,
+    _client: Arc<SlackHyperClient>,
+    state: SlackClientEventsUserState,
+) -> UserCallbackResult<()> {
+    if let SlackEventCallbackBody::Message(message) = event.event {
+        let state = state.read().await;
+        if let (Some(feed), Some(message)) =
+            (state.get_user_state::<LiveFeed>(), LiveMessage::from_event(&message))
+        {
+            feed.publish(message);
+        }
+    }
+    Ok(())
+}
+
+/// Connect to Slack with an app-level token (`xapp-…`) and publish new
+/// messages to `feed`. Runs until the task is dropped; lost connections are
+/// re-established by the Socket Mode client.
+pub async fn run_socket_mode(app_token: &str, feed: LiveFeed) -> Result<()> {
+    let connector = SlackClientHyperConnector::new()
+        .map_err(|e| AppError::SlackClientInit(e.to_string()))?;
+    let client = Arc::new(SlackClient::new(connector));
+    let environment =
+        Arc::new(SlackClientEventsListenerEnvironment::new(client).with_user_state(feed));
+    let callbacks = SlackSocketModeListenerCallbacks::new().with_push_events(on_push_event);
+    let listener = SlackClientSocketModeListener::new(
+        &SlackClientSocketModeConfig::new(),
+        environment,
+        callbacks,
+    );
+
+    let token = SlackApiToken::new(SlackApiTokenValue(app_token.to_string()));
+    listener
+        .listen_for(&token)
+        .await
+        .map_err(|e| AppError::SlackApi(e.to_string()))?;
+    listener.start().await;
+    std::future::pending::<()>().await;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message_event(json: serde_json::Value) -> SlackMessageEvent {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_live_message_from_event() {
+        let event = message_event(serde_json::json!({
+            "type": "message",
+            "channel": "C1",
+            "user": "U1",
+            "ts": "1705312800.000100",
+            "text": "hello"
+        }));
+
+        let message = LiveMessage::from_event(&event).unwrap();
+        assert_eq!(message.channel, "C1");
+        assert_eq!(message.user.as_deref(), Some("U1"));
+        assert_eq!(message.text, "hello");
+        assert!(message.thread_ts.is_none());
+    }
+
+    #[test]
+    fn test_live_message_skips_edits() {
+        let event = message_event(serde_json::json!({
+            "type": "message",
+            "subtype": "message_changed",
+            "channel": "C1",
+            "ts": "1705312800.000200",
+            "hidden": true
+        }));
+
+        assert!(LiveMessage::from_event(&event).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_live_feed_fan_out() {
+        let feed = LiveFeed::new(4);
+        let mut first = feed.subscribe();
+        let mut second = feed.subscribe();
+        let message = LiveMessage {
+            channel: "C1".to_string(),
+            ts: "1.0".to_string(),
+            user: None,
+            thread_ts: None,
+            text: "hi".to_string(),
+        };
+
+        assert_eq!(feed.publish(message.clone()), 2);
+        assert_eq!(first.recv().await.unwrap(), message);
+        assert_eq!(second.recv().await.unwrap(), message);
+    }
+}
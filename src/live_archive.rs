@@ -0,0 +1,860 @@
+//! Continuous archiving from Slack message events
+//!
+//! Message events, from Socket Mode (`live-archive`) or the Events API (the
+//! archive server's `/slack/events`), are appended to per-week NDJSON staging
+//! files as they arrive and periodically compacted into the `year=YYYY/week=WW/threads.parquet`
+//! layout written by `archive-range`, so the archive stays current without
+//! periodic backfills.
+
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs::{self, File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use slack_morphism::prelude::*;
+use tokio::sync::mpsc;
+
+use crate::delta::DeltaTable;
+use crate::overflow::thread_week;
+use crate::parquet::{read_parquet_as_json, ts_partition, week_threads_files, write_message_rows, PartitionBy};
+use crate::provenance::{read_file_info, Provenance};
+use crate::storage::{path_to_key, LocalStorage};
+use crate::{week_to_date_range, AppError, JsonRow, Result};
+
+/// Name of the staging file in each `year=/week=` directory
+const STAGING_FILE: &str = "events.ndjson";
+
+/// What happened to a message
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEventKind {
+    Posted,
+    Edited,
+    Deleted,
+}
+
+/// One staged message event, a line of a staging file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiveEvent {
+    pub kind: LiveEventKind,
+    pub channel: String,
+    pub ts: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thread_ts: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Blocks as a JSON string, as stored in the archive
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blocks: Option<String>,
+}
+
+impl LiveEvent {
+    /// Posts, edits (`message_changed`) and deletions (`message_deleted`);
+    /// other hidden events are skipped
+    pub fn from_event(event: &SlackMessageEvent) -> Option<Self> {
+        let channel = event.origin.channel.as_ref()?.to_string();
+        let blocks_json = |content: Option<&SlackMessageContent>| {
+            content
+                .and_then(|c| c.blocks.as_ref())
+                .and_then(|b| serde_json::to_string(b).ok())
+        };
+
+        match event.subtype {
+            Some(SlackMessageEventType::MessageChanged) => {
+                let edited = event.message.as_ref()?;
+                Some(Self {
+                    kind: LiveEventKind::Edited,
+                    channel,
+                    ts: edited.ts.to_string(),
+                    user: edited.sender.user.as_ref().map(ToString::to_string),
+                    thread_ts: None,
+                    text: edited.content.as_ref().and_then(|c| c.text.clone()),
+                    blocks: blocks_json(edited.content.as_ref()),
+                })
+            }
+            Some(SlackMessageEventType::MessageDeleted) => Some(Self {
+                kind: LiveEventKind::Deleted,
+                channel,
+                ts: event.deleted_ts.as_ref()?.to_string(),
+                user: None,
+                thread_ts: None,
+                text: None,
+                blocks: None,
+            }),
+            _ if event.hidden == Some(true) => None,
+            _ => Some(Self {
+                kind: LiveEventKind::Posted,
+                channel,
+                ts: event.origin.ts.to_string(),
+                user: event.sender.user.as_ref().map(ToString::to_string),
+                thread_ts: event.origin.thread_ts.as_ref().map(ToString::to_string),
+                text: event.content.as_ref().and_then(|c| c.text.clone()),
+                blocks: blocks_json(event.content.as_ref()),
+            }),
+        }
+    }
+
+    /// `from_event` with what the typed event drops: the thread of an
+    /// edited or deleted message, from `payload`, the event's JSON
+    pub fn from_payload(event: &SlackMessageEvent, payload: &serde_json::Value) -> Option<Self> {
+        let mut live = Self::from_event(event)?;
+        if live.thread_ts.is_none() {
+            let message = match live.kind {
+                LiveEventKind::Edited => payload.get("message"),
+                LiveEventKind::Deleted => payload.get("previous_message"),
+                LiveEventKind::Posted => None,
+            };
+            live.thread_ts = message
+                .and_then(|m| m.get("thread_ts"))
+                .and_then(|t| t.as_str())
+                .map(str::to_string);
+        }
+        Some(live)
+    }
+
+    /// ISO year and week the message is archived in, its thread's for a reply
+    fn week(&self) -> Option<(i32, i32)> {
+        thread_week(&self.ts, self.thread_ts.as_deref())
+    }
+
+    /// Archive row for a message not archived yet
+    fn to_row(&self, channel_name: &str) -> Option<JsonRow> {
+        let (date, _, _) = ts_partition(&self.ts)?;
+        let (year, week) = self.week()?;
+        let is_reply = self.thread_ts.as_ref().is_some_and(|t| *t != self.ts);
+        let row = serde_json::json!({
+            "ts": self.ts,
+            "user": self.user,
+            "text": self.text,
+            "channel_id": self.channel,
+            "channel_name": channel_name,
+            "thread_ts": self.thread_ts,
+            "is_reply": is_reply,
+            "date": date,
+            "year": year,
+            "week": week,
+            "blocks": self.blocks,
+        });
+        match row {
+            serde_json::Value::Object(map) => Some(map),
+            _ => None,
+        }
+    }
+}
+
+/// Append `event` to the staging file of the week its message is archived
+/// in, the thread's week for a reply
+pub fn append_event(staging_dir: &Path, event: &LiveEvent) -> Result<()> {
+    let (year, week) = event.week().ok_or_else(|| AppError::InvalidTimestamp(event.ts.clone()))?;
+    let dir = staging_dir.join(format!("year={}/week={:02}", year, week));
+    fs::create_dir_all(&dir).map_err(|e| AppError::WriteFile {
+        path: dir.display().to_string(),
+        source: e,
+    })?;
+
+    let path = dir.join(STAGING_FILE);
+    let write_error = |e| AppError::WriteFile {
+        path: path.display().to_string(),
+        source: e,
+    };
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(write_error)?;
+    let line = serde_json::to_string(event).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+    writeln!(file, "{}", line).map_err(write_error)
+}
+
+/// Outcome of compacting the staging files into the archive
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CompactResult {
+    pub weeks: usize,
+    pub posted: usize,
+    pub edited: usize,
+    pub deleted: usize,
+}
+
+impl CompactResult {
+    pub fn events(&self) -> usize {
+        self.posted + self.edited + self.deleted
+    }
+}
+
+/// `year=/week=` directories under `root` with their ISO year and week
+fn week_dirs(root: &Path) -> Vec<(i32, i32, PathBuf)> {
+    let partition = |path: &Path, prefix: &str| -> Option<i32> {
+        path.file_name()?.to_str()?.strip_prefix(prefix)?.parse().ok()
+    };
+    let mut dirs = Vec::new();
+    let Ok(years) = fs::read_dir(root) else {
+        return dirs;
+    };
+    for year_dir in years.flatten().map(|e| e.path()) {
+        let (Some(year), Ok(weeks)) = (partition(&year_dir, "year="), fs::read_dir(&year_dir))
+        else {
+            continue;
+        };
+        for week_dir in weeks.flatten().map(|e| e.path()) {
+            if let Some(week) = partition(&week_dir, "week=") {
+                dirs.push((year, week, week_dir));
+            }
+        }
+    }
+    dirs.sort();
+    dirs
+}
+
+/// Staging files under `staging_dir` with their ISO year and week
+fn staged_weeks(staging_dir: &Path) -> Vec<(i32, i32, PathBuf)> {
+    week_dirs(staging_dir)
+        .into_iter()
+        .map(|(year, week, dir)| (year, week, dir.join(STAGING_FILE)))
+        .filter(|(_, _, file)| file.is_file())
+        .collect()
+}
+
+fn read_staged_events(path: &Path) -> Result<Vec<LiveEvent>> {
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    let mut events = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        // A line cut short by a crash mid-append is dropped
+        if let Ok(event) = serde_json::from_str(&line) {
+            events.push(event);
+        }
+    }
+    Ok(events)
+}
+
+/// Channel and ts of an archive row, what events refer to it by
+fn row_key(row: &JsonRow) -> (String, String) {
+    let field = |name| row.get(name).and_then(|v| v.as_str()).unwrap_or("").to_string();
+    (field("channel_id"), field("ts"))
+}
+
+/// Channel and ts of the message an event is about
+fn event_key(event: &LiveEvent) -> (String, String) {
+    (event.channel.clone(), event.ts.clone())
+}
+
+/// Apply `events` in order to archive `rows`, returning the counts
+fn apply_events(
+    rows: &mut Vec<JsonRow>,
+    events: &[LiveEvent],
+    channel_names: &HashMap<String, String>,
+) -> CompactResult {
+    let mut index: HashMap<(String, String), usize> =
+        rows.iter().enumerate().map(|(i, row)| (row_key(row), i)).collect();
+    let mut deleted = HashSet::new();
+    let mut result = CompactResult::default();
+
+    for event in events {
+        let event_key = event_key(event);
+        let existing = index.get(&event_key).copied().filter(|i| !deleted.contains(i));
+        match (event.kind, existing) {
+            (LiveEventKind::Deleted, Some(i)) => {
+                deleted.insert(i);
+                result.deleted += 1;
+            }
+            (LiveEventKind::Deleted, None) => {}
+            (LiveEventKind::Edited, Some(i)) => {
+                if let Some(row) = rows.get_mut(i) {
+                    row.insert("text".to_string(), event.text.clone().into());
+                    row.insert("blocks".to_string(), event.blocks.clone().into());
+                }
+                result.edited += 1;
+            }
+            // Posts replayed after a crash replace the earlier copy
+            (LiveEventKind::Posted, Some(i)) => {
+                if let Some(row) = event.to_row(channel_name(channel_names, &event.channel))
+                    && let Some(slot) = rows.get_mut(i)
+                {
+                    *slot = row;
+                }
+                result.posted += 1;
+            }
+            // An edit of a message from before the archive starts is kept
+            // as if it had been posted
+            (kind, None) => {
+                if let Some(row) = event.to_row(channel_name(channel_names, &event.channel)) {
+                    index.insert(event_key, rows.len());
+                    rows.push(row);
+                    match kind {
+                        LiveEventKind::Edited => result.edited += 1,
+                        _ => result.posted += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    if !deleted.is_empty() {
+        let mut i = 0;
+        rows.retain(|_| {
+            i += 1;
+            !deleted.contains(&(i - 1))
+        });
+    }
+    result
+}
+
+fn channel_name<'a>(channel_names: &'a HashMap<String, String>, id: &'a str) -> &'a str {
+    channel_names.get(id).map(String::as_str).unwrap_or(id)
+}
+
+/// Merge every staging file under `staging_dir` into the matching
+/// `threads.parquet` under `conversations_dir`, commit them to its Delta log
+/// if it has one, then remove it. Events are idempotent, so a staging file
+/// replayed after a crash gives the same archive. `channel_names` maps
+/// channel IDs to the names stored with new messages.
+pub fn compact_staging(
+    staging_dir: &Path,
+    conversations_dir: &Path,
+    channel_names: &HashMap<String, String>,
+) -> Result<CompactResult> {
+    let mut total = CompactResult::default();
+    let partition_by = PartitionBy::of_dir(conversations_dir);
+    let staged = staged_weeks(staging_dir);
+    let mut weeks: BTreeMap<(i32, i32), Vec<LiveEvent>> = BTreeMap::new();
+    for (year, week, staging_file) in &staged {
+        weeks.entry((*year, *week)).or_default().extend(read_staged_events(staging_file)?);
+    }
+    move_to_thread_weeks(conversations_dir, &mut weeks)?;
+
+    let mut written = Vec::new();
+    for ((year, week), events) in weeks {
+        let partition = conversations_dir.join(format!("year={}/week={:02}", year, week));
+        let files = week_threads_files(&partition);
+        let mut rows = Vec::new();
+        for file in &files {
+            rows.extend(read_parquet_as_json(file)?);
+        }
+
+        let result = apply_events(&mut rows, &events, channel_names);
+        if result.events() > 0 {
+            let provenance = compacted_provenance(&files, (year, week))?;
+            written.extend(write_week(&partition, &files, rows, (partition_by, &provenance))?);
+        }
+        total.weeks += 1;
+        total.posted += result.posted;
+        total.edited += result.edited;
+        total.deleted += result.deleted;
+    }
+    let storage = LocalStorage::new(conversations_dir);
+    let keys: Vec<String> = written
+        .iter()
+        .filter_map(|file| file.strip_prefix(conversations_dir).ok())
+        .map(path_to_key)
+        .collect();
+    DeltaTable::new(&storage, "").commit_existing(&keys, "UPDATE")?;
+
+    for (_, _, staging_file) in staged {
+        fs::remove_file(&staging_file).map_err(|e| AppError::WriteFile {
+            path: staging_file.display().to_string(),
+            source: e,
+        })?;
+        if let Some(week_dir) = staging_file.parent() {
+            let _ = fs::remove_dir(week_dir);
+        }
+    }
+    Ok(total)
+}
+
+/// Move the edits and deletions of messages their week has neither archived
+/// nor staged to the latest earlier week that has them. Socket Mode events
+/// don't say which thread an edited or deleted message is in, so those of
+/// a reply are staged in its own week instead of its thread's.
+fn move_to_thread_weeks(conversations_dir: &Path, weeks: &mut BTreeMap<(i32, i32), Vec<LiveEvent>>) -> Result<()> {
+    let staged_posts: HashMap<(i32, i32), HashSet<(String, String)>> = weeks
+        .iter()
+        .map(|(week, events)| {
+            let posts = events.iter().filter(|e| e.kind == LiveEventKind::Posted).map(event_key).collect();
+            (*week, posts)
+        })
+        .collect();
+    let mut archived: HashMap<(i32, i32), HashSet<(String, String)>> = HashMap::new();
+    let mut has = |week: (i32, i32), key: &(String, String)| -> Result<bool> {
+        if staged_posts.get(&week).is_some_and(|posts| posts.contains(key)) {
+            return Ok(true);
+        }
+        let keys = match archived.entry(week) {
+            Entry::Occupied(keys) => keys.into_mut(),
+            Entry::Vacant(slot) => {
+                let partition = conversations_dir.join(format!("year={}/week={:02}", week.0, week.1));
+                let mut keys = HashSet::new();
+                for file in week_threads_files(&partition) {
+                    keys.extend(read_parquet_as_json(&file)?.iter().map(row_key));
+                }
+                slot.insert(keys)
+            }
+        };
+        Ok(keys.contains(key))
+    };
+
+    let mut candidates: BTreeSet<(i32, i32)> = weeks.keys().copied().collect();
+    candidates.extend(week_dirs(conversations_dir).into_iter().map(|(year, week, _)| (year, week)));
+    let mut moves = Vec::new();
+    for (week, events) in weeks.iter() {
+        for (i, event) in events.iter().enumerate() {
+            if event.kind == LiveEventKind::Posted || event.thread_ts.is_some() {
+                continue;
+            }
+            let key = event_key(event);
+            if has(*week, &key)? {
+                continue;
+            }
+            for earlier in candidates.range(..*week).rev() {
+                if has(*earlier, &key)? {
+                    moves.push((*week, i, *earlier));
+                    break;
+                }
+            }
+        }
+    }
+
+    // Taken from the back, so the indexes of the ones left stay valid
+    let mut moved = Vec::new();
+    for (week, i, target) in moves.into_iter().rev() {
+        if let Some(events) = weeks.get_mut(&week)
+            && i < events.len()
+        {
+            moved.push((target, events.remove(i)));
+        }
+    }
+    for (target, event) in moved.into_iter().rev() {
+        weeks.entry(target).or_default().push(event);
+    }
+    Ok(())
+}
+
+/// Provenance of a compacted week: written now and covering the whole week,
+/// keeping the workspace its files were exported from
+fn compacted_provenance(files: &[PathBuf], (year, week): (i32, i32)) -> Result<Provenance> {
+    let team_id = files
+        .iter()
+        .find_map(|file| read_file_info(file).ok()?.provenance?.team_id);
+    let week = u32::try_from(week).map_err(|_| AppError::InvalidDate(format!("Invalid week: {}", week)))?;
+    Ok(Provenance::now().with_range(week_to_date_range(year, week)?).with_team_id(team_id))
+}
+
+/// Write a week's rows back as its threads files, returning their paths;
+/// with the archive partitioned by channel, a channel left without messages
+/// gets an empty file
+fn write_week(
+    partition: &Path,
+    files: &[PathBuf],
+    rows: Vec<JsonRow>,
+    (partition_by, provenance): (PartitionBy, &Provenance),
+) -> Result<Vec<PathBuf>> {
+    let mut by_file: HashMap<PathBuf, Vec<JsonRow>> = files.iter().map(|file| (file.clone(), Vec::new())).collect();
+    for row in rows {
+        let file = match partition_by {
+            PartitionBy::Week => partition.join("threads.parquet"),
+            PartitionBy::Channel => {
+                let channel_id = row.get("channel_id").and_then(|v| v.as_str()).unwrap_or_default();
+                partition.join(format!("channel={}", channel_id)).join("threads.parquet")
+            }
+        };
+        by_file.entry(file).or_default().push(row);
+    }
+    if by_file.is_empty() && partition_by == PartitionBy::Week {
+        by_file.insert(partition.join("threads.parquet"), Vec::new());
+    }
+    let mut written = Vec::new();
+    for (file, rows) in by_file {
+        let dir = file.parent().unwrap_or(partition);
+        fs::create_dir_all(dir).map_err(|e| AppError::WriteFile {
+            path: dir.display().to_string(),
+            source: e,
+        })?;
+        write_message_rows(&file, &rows, Some(provenance))?;
+        written.push(file);
+    }
+    Ok(written)
+}
+
+/// Channel ID to name map from a channels JSON file; without one, new
+/// messages are archived with the channel ID as name
+pub(crate) fn load_channel_names(channels_path: Option<&str>) -> Result<HashMap<String, String>> {
+    let Some(path) = channels_path else {
+        return Ok(HashMap::new());
+    };
+    let channels: Vec<serde_json::Value> = crate::load_json_file(path)?;
+    Ok(channels
+        .iter()
+        .filter_map(|channel| {
+            let id = channel.get("id")?.as_str()?;
+            let name = channel.get("name").and_then(|n| n.as_str()).unwrap_or(id);
+            Some((id.to_string(), name.to_string()))
+        })
+        .collect())
+}
+
+/// Channel ID to name map from an archive's `channels.parquet`
+pub fn archive_channel_names(channels_parquet: &Path) -> Result<HashMap<String, String>> {
+    if !channels_parquet.is_file() {
+        return Ok(HashMap::new());
+    }
+    Ok(read_parquet_as_json(channels_parquet)?
+        .iter()
+        .filter_map(|row| {
+            let id = row.get("id")?.as_str()?;
+            let name = row.get("name").and_then(|n| n.as_str()).unwrap_or(id);
+            Some((id.to_string(), name.to_string()))
+        })
+        .collect())
+}
+
+/// Staging area written to by concurrent tasks, such as request handlers;
+/// appends and compactions are serialized so no event is lost between
+/// reading a staging file and removing it
+#[derive(Debug, Clone)]
+pub struct SharedStaging {
+    staging_dir: PathBuf,
+    conversations_dir: PathBuf,
+    lock: Arc<Mutex<()>>,
+}
+
+impl SharedStaging {
+    pub fn new(staging_dir: impl Into<PathBuf>, conversations_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            staging_dir: staging_dir.into(),
+            conversations_dir: conversations_dir.into(),
+            lock: Arc::new(Mutex::new(())),
+        }
+    }
+
+    pub fn append(&self, event: &LiveEvent) -> Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        append_event(&self.staging_dir, event)
+    }
+
+    pub fn compact(&self, channel_names: &HashMap<String, String>) -> Result<CompactResult> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        compact_staging(&self.staging_dir, &self.conversations_dir, channel_names)
+    }
+}
+
+/// Where `live-archive` stages and compacts events
+#[derive(Debug, Clone)]
+pub struct LiveArchiveOptions {
+    pub staging_dir: PathBuf,
+    pub conversations_dir: PathBuf,
+    pub channel_names: HashMap<String, String>,
+    pub compact_every: Duration,
+}
+
+async fn on_push_event(
+    event: SlackPushEventCallback,
+    _client: Arc<SlackHyperClient>,
+    state: SlackClientEventsUserState,
+) -> UserCallbackResult<()> {
+    if let SlackEventCallbackBody::Message(message) = event.event {
+        let state = state.read().await;
+        if let (Some(sender), Some(event)) = (
+            state.get_user_state::<mpsc::UnboundedSender<LiveEvent>>(),
+            LiveEvent::from_event(&message),
+        ) {
+            let _ = sender.send(event);
+        }
+    }
+    Ok(())
+}
+
+/// Connect to Slack with an app-level token (`xapp-…`), stage message events
+/// as they arrive and compact them into the archive every
+/// `options.compact_every`, until Ctrl+C. Staged events left by an earlier
+/// run are compacted on start. `on_compact` is called after each compaction
+/// that applied events.
+pub async fn run_live_archive(
+    app_token: &str,
+    options: &LiveArchiveOptions,
+    on_compact: &dyn Fn(&CompactResult),
+) -> Result<CompactResult> {
+    let (sender, mut receiver) = mpsc::unbounded_channel::<LiveEvent>();
+    let connector = SlackClientHyperConnector::new()
+        .map_err(|e| AppError::SlackClientInit(e.to_string()))?;
+    let client = Arc::new(SlackClient::new(connector));
+    let environment =
+        Arc::new(SlackClientEventsListenerEnvironment::new(client).with_user_state(sender));
+    let callbacks = SlackSocketModeListenerCallbacks::new().with_push_events(on_push_event);
+    let listener = SlackClientSocketModeListener::new(
+        &SlackClientSocketModeConfig::new(),
+        environment,
+        callbacks,
+    );
+
+    let token = SlackApiToken::new(SlackApiTokenValue(app_token.to_string()));
+    listener
+        .listen_for(&token)
+        .await
+        .map_err(|e| AppError::SlackApi(e.to_string()))?;
+    listener.start().await;
+
+    let compact = |total: &mut CompactResult| -> Result<()> {
+        let result =
+            compact_staging(&options.staging_dir, &options.conversations_dir, &options.channel_names)?;
+        if result.events() > 0 {
+            on_compact(&result);
+        }
+        total.weeks += result.weeks;
+        total.posted += result.posted;
+        total.edited += result.edited;
+        total.deleted += result.deleted;
+        Ok(())
+    };
+
+    let mut total = CompactResult::default();
+    // The first tick completes immediately, picking up leftovers
+    let mut ticker = tokio::time::interval(options.compact_every.max(Duration::from_secs(1)));
+    let outcome = loop {
+        tokio::select! {
+            Some(event) = receiver.recv() => {
+                if let Err(e) = append_event(&options.staging_dir, &event) {
+                    break Err(e);
+                }
+            }
+            _ = ticker.tick() => {
+                if let Err(e) = compact(&mut total) {
+                    break Err(e);
+                }
+            }
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        }
+    };
+    listener.shutdown().await;
+
+    // Whatever arrived before shutdown is staged and compacted too
+    while let Ok(event) = receiver.try_recv() {
+        append_event(&options.staging_dir, &event)?;
+    }
+    outcome?;
+    compact(&mut total)?;
+    Ok(total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(kind: LiveEventKind, ts: &str, text: Option<&str>) -> LiveEvent {
+        LiveEvent {
+            kind,
+            channel: "C1".to_string(),
+            ts: ts.to_string(),
+            user: Some("U1".to_string()),
+            thread_ts: None,
+            text: text.map(str::to_string),
+            blocks: None,
+        }
+    }
+
+    #[test]
+    fn test_live_event_from_event() {
+        let posted: SlackMessageEvent = serde_json::from_value(serde_json::json!({
+            "type": "message",
+            "channel": "C1",
+            "user": "U1",
+            "ts": "1705312800.000200",
+            "thread_ts": "1705312800.000100",
+            "text": "reply"
+        }))
+        .unwrap();
+        let posted = LiveEvent::from_event(&posted).unwrap();
+        assert_eq!(posted.kind, LiveEventKind::Posted);
+        assert_eq!(posted.thread_ts.as_deref(), Some("1705312800.000100"));
+
+        let edited: SlackMessageEvent = serde_json::from_value(serde_json::json!({
+            "type": "message",
+            "subtype": "message_changed",
+            "channel": "C1",
+            "ts": "1705312900.000000",
+            "hidden": true,
+            "message": {"ts": "1705312800.000200", "user": "U1", "text": "fixed"}
+        }))
+        .unwrap();
+        let edited = LiveEvent::from_event(&edited).unwrap();
+        assert_eq!(edited.kind, LiveEventKind::Edited);
+        assert_eq!(edited.ts, "1705312800.000200");
+        assert_eq!(edited.text.as_deref(), Some("fixed"));
+        assert_eq!(edited.thread_ts, None);
+
+        let payload = serde_json::json!({
+            "type": "message",
+            "subtype": "message_changed",
+            "channel": "C1",
+            "ts": "1705312900.000000",
+            "hidden": true,
+            "message": {"ts": "1705312800.000200", "thread_ts": "1705312800.000100", "user": "U1", "text": "fixed"}
+        });
+        let reply = LiveEvent::from_payload(&serde_json::from_value(payload.clone()).unwrap(), &payload).unwrap();
+        assert_eq!(reply.thread_ts.as_deref(), Some("1705312800.000100"));
+
+        let deleted: SlackMessageEvent = serde_json::from_value(serde_json::json!({
+            "type": "message",
+            "subtype": "message_deleted",
+            "channel": "C1",
+            "ts": "1705313000.000000",
+            "hidden": true,
+            "deleted_ts": "1705312800.000200"
+        }))
+        .unwrap();
+        let deleted = LiveEvent::from_event(&deleted).unwrap();
+        assert_eq!(deleted.kind, LiveEventKind::Deleted);
+        assert_eq!(deleted.ts, "1705312800.000200");
+
+        let payload = serde_json::json!({
+            "type": "message",
+            "subtype": "message_deleted",
+            "channel": "C1",
+            "ts": "1705313000.000000",
+            "hidden": true,
+            "deleted_ts": "1705312800.000200",
+            "previous_message": {"ts": "1705312800.000200", "thread_ts": "1705312800.000100", "text": "reply"}
+        });
+        let reply = LiveEvent::from_payload(&serde_json::from_value(payload.clone()).unwrap(), &payload).unwrap();
+        assert_eq!(reply.thread_ts.as_deref(), Some("1705312800.000100"));
+    }
+
+    #[test]
+    fn test_compact_staging() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let conversations = dir.path().join("conversations");
+        let names = HashMap::from([("C1".to_string(), "general".to_string())]);
+
+        // 2024-01-15, ISO week 3
+        for e in [
+            event(LiveEventKind::Posted, "1705312800.000100", Some("hello")),
+            event(LiveEventKind::Posted, "1705312800.000200", Some("typo")),
+            event(LiveEventKind::Edited, "1705312800.000200", Some("fixed")),
+            event(LiveEventKind::Posted, "1705312800.000300", Some("oops")),
+            event(LiveEventKind::Deleted, "1705312800.000300", None),
+        ] {
+            append_event(&staging, &e).unwrap();
+        }
+
+        let result = compact_staging(&staging, &conversations, &names).unwrap();
+        assert_eq!(
+            result,
+            CompactResult {
+                weeks: 1,
+                posted: 3,
+                edited: 1,
+                deleted: 1
+            }
+        );
+        assert!(!staging.join("year=2024/week=03/events.ndjson").exists());
+
+        let threads = conversations.join("year=2024/week=03/threads.parquet");
+        let rows = read_parquet_as_json(&threads).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0]["channel_name"], "general");
+        assert_eq!(rows[1]["text"], "fixed");
+        assert_eq!(rows[1]["week"], 3);
+        let provenance = read_file_info(&threads).unwrap().provenance.unwrap();
+        assert_eq!(provenance.from.as_deref(), Some("2024-01-15"));
+        assert_eq!(provenance.to.as_deref(), Some("2024-01-21"));
+
+        // A second run merges into the existing partition
+        append_event(&staging, &event(LiveEventKind::Deleted, "1705312800.000100", None)).unwrap();
+        let result = compact_staging(&staging, &conversations, &names).unwrap();
+        assert_eq!(result.deleted, 1);
+        let rows = read_parquet_as_json(&threads).unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["ts"], "1705312800.000200");
+    }
+
+    #[test]
+    fn test_compact_staging_commits_to_delta_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let conversations = dir.path().join("conversations");
+        fs::create_dir_all(&conversations).unwrap();
+        let storage = LocalStorage::new(&conversations);
+        let table = DeltaTable::new(&storage, "");
+        table.commit(&[], "CREATE TABLE").unwrap();
+
+        append_event(&staging, &event(LiveEventKind::Posted, "1705312800.000100", Some("hello"))).unwrap();
+        compact_staging(&staging, &conversations, &HashMap::new()).unwrap();
+        assert_eq!(table.version().unwrap(), Some(1));
+        let files = table.files().unwrap();
+        assert_eq!(files.len(), 1);
+        assert!(files.iter().all(|file| file.starts_with("year=2024/week=03/part-")));
+    }
+
+    #[test]
+    fn test_compact_staging_replies_in_thread_week() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = dir.path().join("staging");
+        let conversations = dir.path().join("conversations");
+        let reply = |kind, ts: &str, text, thread_ts: Option<&str>| LiveEvent {
+            thread_ts: thread_ts.map(str::to_string),
+            ..event(kind, ts, text)
+        };
+
+        // A thread of 2024-W03 with two replies sent in W04
+        let parent = "1705312800.000100";
+        for e in [
+            event(LiveEventKind::Posted, parent, Some("question")),
+            reply(LiveEventKind::Posted, "1705917600.000200", Some("typo"), Some(parent)),
+            reply(LiveEventKind::Posted, "1705917600.000300", Some("oops"), Some(parent)),
+        ] {
+            append_event(&staging, &e).unwrap();
+        }
+        assert!(!staging.join("year=2024/week=04").exists());
+        compact_staging(&staging, &conversations, &HashMap::new()).unwrap();
+        let week3 = conversations.join("year=2024/week=03/threads.parquet");
+        let rows = read_parquet_as_json(&week3).unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[1]["week"], 3);
+        assert_eq!(rows[1]["is_reply"], true);
+
+        // Socket Mode edits don't say which thread, /slack/events deletes do
+        append_event(&staging, &reply(LiveEventKind::Edited, "1705917600.000200", Some("fixed"), None)).unwrap();
+        append_event(&staging, &reply(LiveEventKind::Deleted, "1705917600.000300", None, Some(parent))).unwrap();
+        assert!(staging.join("year=2024/week=04/events.ndjson").exists());
+        let result = compact_staging(&staging, &conversations, &HashMap::new()).unwrap();
+        assert_eq!((result.edited, result.deleted, result.posted), (1, 1, 0));
+
+        let rows = read_parquet_as_json(&week3).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[1]["ts"], "1705917600.000200");
+        assert_eq!(rows[1]["text"], "fixed");
+        assert_eq!(rows[1]["thread_ts"], parent);
+        assert!(!conversations.join("year=2024/week=04").exists());
+        assert!(!staging.join("year=2024/week=04").exists());
+    }
+
+    #[test]
+    fn test_shared_staging() {
+        let dir = tempfile::tempdir().unwrap();
+        let staging = SharedStaging::new(dir.path().join("staging"), dir.path().join("conversations"));
+        staging.append(&event(LiveEventKind::Posted, "1705312800.000100", Some("hi"))).unwrap();
+
+        let result = staging.compact(&HashMap::new()).unwrap();
+        assert_eq!(result.posted, 1);
+        assert_eq!(staging.compact(&HashMap::new()).unwrap(), CompactResult::default());
+        assert!(archive_channel_names(&dir.path().join("channels.parquet")).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_compact_staging_replay_is_idempotent() {
+        let mut rows = Vec::new();
+        let events = [event(LiveEventKind::Posted, "1705312800.000100", Some("hello"))];
+        apply_events(&mut rows, &events, &HashMap::new());
+        apply_events(&mut rows, &events, &HashMap::new());
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["channel_name"], "C1");
+    }
+}
@@ -0,0 +1,89 @@
+//! Diagnostic logs of the CLI (`--log-level`, `--log-file`): a span per
+//! channel and week, the API requests and the retries, recorded with
+//! `tracing` so a failed export can be looked into after the fact
+
+use std::fs::OpenOptions;
+use std::sync::Mutex;
+
+use tracing_subscriber::EnvFilter;
+
+use crate::error::{AppError, Result};
+
+/// Verbosity of the diagnostic logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum LogLevel {
+    Off,
+    Error,
+    Warn,
+    Info,
+    /// Also every API request and retry
+    Debug,
+    Trace,
+}
+
+impl LogLevel {
+    fn as_str(self) -> &'static str {
+        match self {
+            LogLevel::Off => "off",
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        }
+    }
+}
+
+/// Filter for `level`: this crate's events at `level`, the libraries' (HTTP
+/// client, TLS) only from warnings up so they don't bury the API calls
+pub fn log_filter(level: LogLevel) -> String {
+    match level {
+        LogLevel::Off => "off".to_string(),
+        LogLevel::Error => "error".to_string(),
+        level => format!("warn,slack_utils={}", level.as_str()),
+    }
+}
+
+/// Install the global subscriber. Nothing is logged unless a level, a file
+/// or `RUST_LOG` is given; a file gets `debug` unless a level is. Logs go to
+/// stderr, or are appended to the file without colors.
+pub fn init_logging(level: Option<LogLevel>, file: Option<&str>) -> Result<()> {
+    let filter = match (level, file) {
+        (Some(level), _) => EnvFilter::new(log_filter(level)),
+        (None, file) => match EnvFilter::try_from_default_env() {
+            Ok(filter) => filter,
+            Err(_) if file.is_some() => EnvFilter::new(log_filter(LogLevel::Debug)),
+            Err(_) => return Ok(()),
+        },
+    };
+    let builder = tracing_subscriber::fmt().with_env_filter(filter);
+    // Ignore the error if a subscriber is already installed (e.g. in tests)
+    let _ = match file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| AppError::WriteFile {
+                    path: path.to_string(),
+                    source: e,
+                })?;
+            builder.with_ansi(false).with_writer(Mutex::new(file)).try_init()
+        }
+        None => builder.with_writer(std::io::stderr).try_init(),
+    };
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_filter() {
+        assert_eq!(log_filter(LogLevel::Debug), "warn,slack_utils=debug");
+        assert_eq!(log_filter(LogLevel::Info), "warn,slack_utils=info");
+        assert_eq!(log_filter(LogLevel::Error), "error");
+        assert_eq!(log_filter(LogLevel::Off), "off");
+    }
+}
@@ -1,38 +1,132 @@
-use clap::Parser;
+use clap::{CommandFactory, FromArgMatches};
 use slack_utils::{Cli, Commands};
 
 #[tokio::main]
 async fn main() {
     // Initialize rustls crypto provider
-    rustls::crypto::aws_lc_rs::default_provider()
+    if rustls::crypto::aws_lc_rs::default_provider()
         .install_default()
-        .expect("Failed to install rustls crypto provider");
+        .is_err()
+    {
+        eprintln!("Error: Failed to install rustls crypto provider");
+        std::process::exit(1);
+    }
 
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    if let Err(e) = slack_utils::init_logging(cli.log_level, cli.log_file.as_deref()) {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+    let json = cli.json;
+    let mut reporter = slack_utils::Reporter::new(json);
+    if cli.quiet {
+        slack_utils::set_quiet_progress();
+    }
+    let started = std::time::Instant::now();
 
     let result = match cli.command {
         Commands::Ui => slack_utils::run_ui(),
         Commands::WorkWeek => {
-            slack_utils::run_work_week();
+            slack_utils::run_work_week(&mut reporter);
             Ok(())
         }
-        Commands::ExportConversations { from, to, output, format } => {
-            slack_utils::run_export_conversations(from, to, &output, &format).await
+        Commands::ExportConversations { from, to, output, format, repair, dry_run, join } => match repair {
+            Some(report) => slack_utils::run_repair_conversations(&report, &mut reporter).await,
+            None => slack_utils::run_export_conversations(
+                from,
+                to,
+                &output,
+                &format,
+                dry_run,
+                &join,
+                &mut reporter,
+            ).await,
+        },
+        Commands::ExportConversationsWeek { year, week, output, format, join } => {
+            slack_utils::run_export_conversations_week(year, week, &output, &format, &join, &mut reporter).await
+        }
+        Commands::ArchiveRange { from_year, from_week, to_year, to_week, output, dry_run, partition_by, table_format } => {
+            slack_utils::run_archive_range(
+                (from_year, from_week),
+                (to_year, to_week),
+                &output,
+                dry_run,
+                partition_by.as_deref(),
+                table_format.as_deref(),
+                &mut reporter,
+            )
+            .await
+        }
+        Commands::ArchiveExtract { archive, year, week, output } => {
+            slack_utils::run_archive_extract(&archive, year, week, &output, &mut reporter)
+        }
+        Commands::ArchiveCompact { archive, by, output, row_group_size } => {
+            slack_utils::run_archive_compact(&archive, &by, output.as_deref(), row_group_size, &mut reporter)
+        }
+        Commands::ArchiveVacuum { archive, retain_hours } => {
+            slack_utils::run_archive_vacuum(&archive, retain_hours, &mut reporter)
+        }
+        Commands::ArchiveInfo { archive, files, output } => {
+            slack_utils::run_archive_info(&archive, files, output.as_deref(), &mut reporter)
+        }
+        Commands::ArchiveSync { dest, archive, users, channels, attachments, dry_run } => {
+            slack_utils::run_archive_sync(&archive, users, channels, attachments, &dest, dry_run, &mut reporter)
         }
-        Commands::ExportConversationsWeek { year, week, output, format } => {
-            slack_utils::run_export_conversations_week(year, week, &output, &format).await
+        Commands::ArchiveDaemon { config, once } => slack_utils::run_archive_daemon(&config, once, &mut reporter).await,
+        Commands::LiveArchive { output, staging, channels, compact_every } => {
+            slack_utils::run_live_archive(&output, &staging, channels.as_deref(), compact_every, &mut reporter).await
         }
-        Commands::ArchiveRange { from_year, from_week, to_year, to_week, output } => {
-            slack_utils::run_archive_range(from_year, from_week, to_year, to_week, &output).await
+        Commands::BackfillThreads { conversations, output } => {
+            slack_utils::run_backfill_threads(&conversations, output.as_deref(), &mut reporter).await
         }
+        Commands::PostMessage { channel, text, markdown, thread_ts } => {
+            slack_utils::run_post_message(
+                &channel,
+                text,
+                markdown.as_deref(),
+                thread_ts.as_deref(),
+                &mut reporter,
+            )
+            .await
+        }
+        Commands::Digest { conversations, users, channels, year, week, top, output, post } => {
+            slack_utils::run_digest(
+                &conversations,
+                (&users, &channels),
+                (year, week.as_deref()),
+                top,
+                &output,
+                post.as_deref(),
+                &mut reporter,
+            )
+            .await
+        }
+        Commands::EncryptSettings { decrypt } => slack_utils::run_encrypt_settings(decrypt, &mut reporter),
         Commands::ExportUsers { output, format } => {
-            slack_utils::run_export_users(&output, &format).await
+            slack_utils::run_export_users(&output, &format, &mut reporter).await
         }
         Commands::ExportChannels { output, format } => {
-            slack_utils::run_export_channels(&output, &format).await
+            slack_utils::run_export_channels(&output, &format, &mut reporter).await
         }
-        Commands::DownloadAttachments { input, output } => {
-            slack_utils::run_download_attachments(&input, &output)
+        Commands::DownloadAttachments {
+            input,
+            output,
+            download,
+            filter,
+            rewrite_conversations,
+            dry_run,
+        } => {
+            slack_utils::run_download_attachments(
+                &input,
+                &output,
+                &download,
+                &filter,
+                rewrite_conversations.as_deref(),
+                dry_run,
+                &mut reporter,
+            )
+            .await
         }
         Commands::ExportMarkdown {
             conversations,
@@ -41,16 +135,116 @@ async fn main() {
             output,
             formatter_script,
             backslash_line_breaks,
-        } => slack_utils::run_export_markdown(&conversations, &users, &channels, &output, formatter_script.as_deref(), backslash_line_breaks),
+        } => slack_utils::run_export_markdown(
+            &conversations,
+            &users,
+            &channels,
+            &output,
+            formatter_script.as_deref(),
+            backslash_line_breaks,
+            &mut reporter,
+        ),
         Commands::ExportEmojis { output, folder } => {
-            slack_utils::run_export_emojis(&output, &folder).await
+            slack_utils::run_export_emojis(&output, &folder, &mut reporter).await
         }
+        Commands::ExportEmojiPack {
+            emojis,
+            folder,
+            output,
+            title,
+        } => slack_utils::run_export_emoji_pack(&emojis, &folder, &output, &title, &mut reporter),
+        Commands::Stats {
+            conversations,
+            users,
+            output,
+            format,
+            report,
+            top,
+        } => slack_utils::run_stats(
+            &conversations,
+            users.as_deref(),
+            &output,
+            &format,
+            report.as_deref(),
+            top,
+            &mut reporter,
+        ),
+        Commands::Activity {
+            conversations,
+            output,
+            svg,
+        } => slack_utils::run_activity(&conversations, &output, svg.as_deref(), &mut reporter),
+        Commands::Links {
+            conversations,
+            users,
+            output,
+            report,
+            top,
+        } => slack_utils::run_links(&conversations, users.as_deref(), &output, report.as_deref(), top, &mut reporter),
+        Commands::Diff {
+            old,
+            new,
+            users,
+            output,
+            report,
+        } => slack_utils::run_diff(&old, &new, users.as_deref(), &output, report.as_deref(), &mut reporter),
+        Commands::Merge { inputs, output } => slack_utils::run_merge(&inputs, &output, &mut reporter),
+        Commands::ConvertTo { conversations, target, users, channels, server_name, team, output } => slack_utils::run_convert_to(
+            &conversations,
+            &target,
+            users.as_deref(),
+            channels.as_deref(),
+            &server_name,
+            &team,
+            &output,
+            &mut reporter,
+        ),
+        Commands::ExportCorpus { conversations, users, messages_per_chunk, max_chars, anonymize, format, output } => {
+            slack_utils::run_export_corpus(
+                &conversations,
+                users.as_deref(),
+                messages_per_chunk,
+                max_chars,
+                anonymize,
+                &format,
+                &output,
+                &mut reporter,
+            )
+        }
+        Commands::Split { conversations, by, output } => slack_utils::run_split(
+            &conversations,
+            &by,
+            &output,
+            &mut reporter,
+        ),
+        Commands::Validate {
+            conversations,
+            users,
+            channels,
+            archive,
+            output,
+        } => slack_utils::run_validate(
+            conversations.as_deref(),
+            users.as_deref(),
+            channels.as_deref(),
+            archive.as_deref(),
+            output.as_deref(),
+            &mut reporter,
+        ),
+        #[cfg(feature = "datafusion")]
+        Commands::Sql {
+            query,
+            archive,
+            format,
+            output,
+        } => slack_utils::run_sql(&query, &archive, &format, output.as_deref(), &mut reporter).await,
         Commands::ExportIndex {
             conversations,
             users,
             channels,
             output,
-        } => slack_utils::run_export_index(&conversations, &users, &channels, &output),
+            format,
+        } => slack_utils::run_export_index(&conversations, &users, &channels, &output, &format, &mut reporter),
         Commands::ImportIndexMeilisearch {
             input,
             url,
@@ -58,7 +252,7 @@ async fn main() {
             index_name,
             clear,
         } => {
-            slack_utils::run_import_index_meilisearch(&input, &url, &api_key, &index_name, clear)
+            slack_utils::run_import_index_meilisearch(&input, &url, &api_key, &index_name, clear, &mut reporter)
                 .await
         }
         Commands::QueryMeilisearch {
@@ -67,10 +261,59 @@ async fn main() {
             api_key,
             index_name,
             limit,
-        } => slack_utils::run_query_meilisearch(&url, &api_key, &index_name, &query, limit).await,
+            channel,
+            user,
+            from,
+            to,
+            has_attachments,
+        } => {
+            let filters = slack_utils::SearchFilters {
+                channel,
+                user,
+                from,
+                to,
+                has_attachments: has_attachments.then_some(true),
+            };
+            slack_utils::run_query_meilisearch(
+                &url,
+                &api_key,
+                &index_name,
+                &query,
+                &filters,
+                limit,
+                &mut reporter,
+            )
+            .await
+        }
+        Commands::IndexTantivy { input, output } => slack_utils::run_index_tantivy(&input, &output, &mut reporter),
+        Commands::QueryTantivy { query, index, limit } => slack_utils::run_query_tantivy(
+            &index,
+            &query,
+            limit,
+            &mut reporter,
+        ),
+        Commands::Summarize { archive, users, channels, year, week, summarizer } => {
+            slack_utils::run_summarize(&archive, &users, &channels, year, week, &summarizer, &mut reporter).await
+        }
+        Commands::BuildSite { archive, users, channels, attachments, emojis, title, output } => slack_utils::run_build_site(
+            &archive,
+            &users,
+            &channels,
+            attachments.as_deref(),
+            emojis.as_deref(),
+            &title,
+            &output,
+            &mut reporter,
+        ),
         Commands::MdToHtml {
             input,
             output,
+            input_dir,
+            output_dir,
+            template,
+            css,
+            highlight,
+            highlight_theme,
             gfm,
             autolink,
             code_indented,
@@ -128,12 +371,32 @@ async fn main() {
                     math_text_single_dollar,
                 }
             };
-            slack_utils::run_md_to_html(&input, output.as_deref(), &options)
+            let page = slack_utils::md_to_html::HtmlPageOptions {
+                template,
+                css,
+                highlight_theme: highlight.then_some(highlight_theme),
+            };
+            match (input_dir, output_dir) {
+                (Some(input_dir), Some(output_dir)) => {
+                    slack_utils::run_md_to_html_dir(&input_dir, &output_dir, &options, &page, &mut reporter)
+                }
+                _ => slack_utils::run_md_to_html(
+                    &input.unwrap_or_default(),
+                    output.as_deref(),
+                    &options,
+                    &page,
+                    &mut reporter,
+                ),
+            }
         }
     };
 
+    if json {
+        let command = matches.subcommand_name().unwrap_or_default();
+        reporter.print_summary(command, started.elapsed(), &result);
+    }
     if let Err(e) = result {
         eprintln!("Error: {}", e);
-        std::process::exit(1);
+        std::process::exit(e.exit_code());
     }
 }
@@ -9,6 +9,7 @@ use webpage::{Webpage, WebpageOptions};
 
 use crate::error::{AppError, Result};
 use crate::formatter::{format_attachment, format_file, format_permalink, format_prefix, format_suffix, FormatterStats, MarkdownExportOptions};
+use crate::json_stream::ConversationSource;
 use crate::ProgressCallback;
 
 /// Maximum bytes to fetch when resolving link titles (32KB should be enough for <title>)
@@ -227,6 +228,97 @@ pub fn export_conversations_to_markdown_with_options(
     output_path: &str,
     progress_callback: ProgressCallback,
     options: &MarkdownExportOptions,
+) -> Result<(usize, FormatterStats)> {
+    // The formatter's prefix and suffix get every thread at once; otherwise
+    // the file is read one channel at a time
+    if options.formatter_script.is_some() {
+        if let Some(cb) = progress_callback {
+            cb(0, 4, "Loading conversations...");
+        }
+        let conversations: Vec<serde_json::Value> = crate::load_json_file(conversations_path)?;
+        return export_conversation_values_to_markdown(
+            &conversations,
+            users_path,
+            channels_path,
+            output_path,
+            progress_callback,
+            options,
+        );
+    }
+
+    let output_file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    let mut writer = BufWriter::new(output_file);
+    write_conversation_values_markdown(
+        ConversationSource::File(conversations_path),
+        users_path,
+        channels_path,
+        &mut writer,
+        output_path,
+        progress_callback,
+        options,
+    )
+}
+
+/// Export conversations already in memory (same shape as
+/// selected-conversations.json) to markdown, e.g. straight from the editor
+pub fn export_conversation_values_to_markdown(
+    conversations: &[serde_json::Value],
+    users_path: &str,
+    channels_path: &str,
+    output_path: &str,
+    progress_callback: ProgressCallback,
+    options: &MarkdownExportOptions,
+) -> Result<(usize, FormatterStats)> {
+    let output_file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    let mut writer = BufWriter::new(output_file);
+    write_conversation_values_markdown(
+        ConversationSource::Loaded(conversations),
+        users_path,
+        channels_path,
+        &mut writer,
+        output_path,
+        progress_callback,
+        options,
+    )
+}
+
+/// Render conversations already in memory to a markdown string
+pub fn render_conversation_values_to_markdown(
+    conversations: &[serde_json::Value],
+    users_path: &str,
+    channels_path: &str,
+    options: &MarkdownExportOptions,
+) -> Result<String> {
+    let mut buffer = Vec::new();
+    write_conversation_values_markdown(
+        ConversationSource::Loaded(conversations),
+        users_path,
+        channels_path,
+        &mut buffer,
+        "markdown",
+        None,
+        options,
+    )?;
+    Ok(String::from_utf8_lossy(&buffer).into_owned())
+}
+
+/// Write the markdown of `source` to `writer`; `output_path` names it in
+/// errors. The formatter's prefix and suffix are only asked for when the
+/// conversations are loaded, as they get all of them.
+fn write_conversation_values_markdown<W: Write>(
+    source: ConversationSource<'_>,
+    users_path: &str,
+    channels_path: &str,
+    writer: &mut W,
+    output_path: &str,
+    progress_callback: ProgressCallback,
+    options: &MarkdownExportOptions,
 ) -> Result<(usize, FormatterStats)> {
     let mut formatter_stats = FormatterStats::new();
     let report_progress = |current: usize, total: usize, msg: &str| {
@@ -243,7 +335,7 @@ pub fn export_conversations_to_markdown_with_options(
     report_progress(1, 4, "Loading users...");
 
     // Load users.json to build user_id -> display_name map
-    let users_data: Vec<serde_json::Value> = crate::load_json_file(users_path)?;
+    let users_data = crate::archive_input::load_users(users_path)?;
 
     let user_names: HashMap<String, String> = users_data
         .iter()
@@ -271,7 +363,7 @@ pub fn export_conversations_to_markdown_with_options(
     report_progress(2, 4, "Loading channels...");
 
     // Load channels.json to build channel_id -> channel_name map
-    let channels_data: Vec<serde_json::Value> = crate::load_json_file(channels_path)?;
+    let channels_data = crate::archive_input::load_channels(channels_path)?;
 
     let channel_names: HashMap<String, String> = channels_data
         .iter()
@@ -299,30 +391,17 @@ pub fn export_conversations_to_markdown_with_options(
         ..SlackReferences::default()
     };
 
-    report_progress(3, 4, "Loading conversations...");
-
-    // Load selected-conversations.json
-    let conversations: Vec<serde_json::Value> = crate::load_json_file(conversations_path)?;
+    report_progress(3, 4, "Counting messages...");
 
     // Count total messages for progress reporting
-    let total_messages: usize = conversations
-        .iter()
-        .filter_map(|ch| ch.get("messages").and_then(|m| m.as_array()))
-        .map(|msgs| msgs.len())
-        .sum();
+    let total_messages = source.message_count()?;
 
     report_progress(4, 4, "Starting export...");
 
-    // Open output file
-    let output_file = File::create(output_path).map_err(|e| AppError::WriteFile {
-        path: output_path.to_string(),
-        source: e,
-    })?;
-    let mut writer = BufWriter::new(output_file);
-
     // Call formatter for prefix content if script is configured
     if let Some(script_path) = &options.formatter_script
-        && let Some(prefix_content) = format_prefix(script_path, &conversations, &mut formatter_stats)
+        && let ConversationSource::Loaded(conversations) = source
+        && let Some(prefix_content) = format_prefix(script_path, conversations, &mut formatter_stats)
     {
         write!(writer, "{}", prefix_content).map_err(|e| AppError::WriteFile {
             path: output_path.to_string(),
@@ -334,7 +413,7 @@ pub fn export_conversations_to_markdown_with_options(
     let mut current_channel_id: Option<String> = None;
 
     // Process each channel entry in the conversations file
-    for channel_entry in &conversations {
+    source.for_each(|channel_entry| {
         let channel_id = channel_entry
             .get("channel_id")
             .and_then(|id| id.as_str())
@@ -346,10 +425,13 @@ pub fn export_conversations_to_markdown_with_options(
             .map(|a| a.as_slice())
             .unwrap_or(&[]);
 
-        // Get channel name for progress and formatter
-        let channel_name = channel_names
-            .get(channel_id)
-            .map(|s| s.as_str())
+        // Get channel name for progress and formatter; the entry's own name
+        // wins so channels renamed or merged in the editor keep their name
+        let channel_name = channel_entry
+            .get("channel_name")
+            .and_then(|n| n.as_str())
+            .filter(|n| !n.is_empty())
+            .or_else(|| channel_names.get(channel_id).map(|s| s.as_str()))
             .unwrap_or(channel_id);
 
         let messages_len = messages.len();
@@ -618,11 +700,13 @@ pub fn export_conversations_to_markdown_with_options(
 
             message_count += 1;
         }
-    }
+        Ok(())
+    })?;
 
     // Call formatter for suffix content if script is configured
     if let Some(script_path) = &options.formatter_script
-        && let Some(suffix_content) = format_suffix(script_path, &conversations, &mut formatter_stats)
+        && let ConversationSource::Loaded(conversations) = source
+        && let Some(suffix_content) = format_suffix(script_path, conversations, &mut formatter_stats)
     {
         write!(writer, "{}", suffix_content).map_err(|e| AppError::WriteFile {
             path: output_path.to_string(),
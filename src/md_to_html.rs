@@ -1,7 +1,13 @@
 use std::fs;
 use std::path::Path;
 
-use crate::{AppError, Result};
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::html::highlighted_html_for_string;
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use walkdir::WalkDir;
+
+use crate::stdio::{is_stdio, read_input};
+use crate::{AppError, ProgressCallback, Result};
 
 /// Options for markdown to HTML conversion
 #[derive(Debug, Clone, Default)]
@@ -166,6 +172,226 @@ impl MdToHtmlOptions {
     }
 }
 
+/// Built-in page template used when `--css` is given without `--template`.
+const DEFAULT_PAGE_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<meta name="viewport" content="width=device-width, initial-scale=1">
+<title>{{title}}</title>
+{{css}}
+</head>
+<body>
+{{nav}}
+<main>
+{{content}}
+</main>
+</body>
+</html>
+"#;
+
+/// Default syntect theme used to highlight code blocks
+pub const DEFAULT_HIGHLIGHT_THEME: &str = "InspiredGitHub";
+
+/// Options for the generated HTML document (page wrapping and code highlighting)
+#[derive(Debug, Clone, Default)]
+pub struct HtmlPageOptions {
+    /// Path to an HTML template with `{{title}}`, `{{css}}`, `{{nav}}` and `{{content}}` placeholders
+    pub template: Option<String>,
+    /// Stylesheet to include: a URL is linked, a local file is inlined
+    pub css: Option<String>,
+    /// Highlight code blocks server-side with this syntect theme
+    pub highlight_theme: Option<String>,
+}
+
+impl HtmlPageOptions {
+    /// Whether the output should be a full page instead of a bare fragment
+    pub fn is_enabled(&self) -> bool {
+        self.template.is_some() || self.css.is_some()
+    }
+
+    /// Read the template and stylesheet once so they can be reused for many files
+    fn load(&self) -> Result<Option<PageTemplate>> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+
+        let template = match &self.template {
+            Some(path) => fs::read_to_string(path).map_err(|e| AppError::ReadFile {
+                path: path.clone(),
+                source: e,
+            })?,
+            None => DEFAULT_PAGE_TEMPLATE.to_string(),
+        };
+
+        let css = match &self.css {
+            Some(css) if css.contains("://") => {
+                format!("<link rel=\"stylesheet\" href=\"{}\">", escape_html(css))
+            }
+            Some(path) => {
+                let content = fs::read_to_string(path).map_err(|e| AppError::ReadFile {
+                    path: path.clone(),
+                    source: e,
+                })?;
+                format!("<style>\n{}\n</style>", content)
+            }
+            None => String::new(),
+        };
+
+        Ok(Some(PageTemplate { template, css }))
+    }
+
+    /// Load syntax definitions and the selected theme once for all files
+    fn highlighter(&self) -> Result<Option<CodeHighlighter>> {
+        self.highlight_theme.as_deref().map(CodeHighlighter::new).transpose()
+    }
+}
+
+/// Server-side syntax highlighting for `<pre><code>` blocks
+struct CodeHighlighter {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl CodeHighlighter {
+    fn new(theme_name: &str) -> Result<Self> {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes.themes.remove(theme_name).ok_or_else(|| {
+            let available: Vec<&str> = themes.themes.keys().map(String::as_str).collect();
+            AppError::SyntaxHighlight(format!(
+                "unknown theme '{}' (available: {})",
+                theme_name,
+                available.join(", ")
+            ))
+        })?;
+
+        Ok(Self {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme,
+        })
+    }
+
+    /// Replace every code block with its highlighted version.
+    ///
+    /// Slack preformatted blocks carry no language, so when the fence has no
+    /// `language-*` class the syntax is guessed from the first line (e.g. a
+    /// shebang or `<?xml`) and falls back to plain text.
+    fn highlight_code_blocks(&self, html: &str) -> Result<String> {
+        const CODE_OPEN: &str = "<pre><code";
+        const CODE_CLOSE: &str = "</code></pre>";
+
+        let mut output = String::with_capacity(html.len());
+        let mut rest = html;
+
+        while let Some(start) = rest.find(CODE_OPEN) {
+            let (before, block) = rest.split_at(start);
+            let Some(open_end) = block.get(CODE_OPEN.len()..).and_then(|b| b.find('>')) else { break };
+            let open_end = open_end + CODE_OPEN.len();
+            let Some(close) = block.find(CODE_CLOSE) else { break };
+            let (Some(attrs), Some(escaped)) = (block.get(CODE_OPEN.len()..open_end), block.get(open_end + 1..close))
+            else {
+                break;
+            };
+
+            let code = unescape_html(escaped);
+            let syntax = self.find_syntax(code_language(attrs), &code);
+            let highlighted = highlighted_html_for_string(&code, &self.syntaxes, syntax, &self.theme)
+                .map_err(|e| AppError::SyntaxHighlight(e.to_string()))?;
+
+            output.push_str(before);
+            output.push_str(&highlighted);
+            rest = block.get(close + CODE_CLOSE.len()..).unwrap_or_default();
+        }
+        output.push_str(rest);
+
+        Ok(output)
+    }
+
+    fn find_syntax(&self, language: Option<&str>, code: &str) -> &SyntaxReference {
+        language
+            .and_then(|lang| self.syntaxes.find_syntax_by_token(lang))
+            .or_else(|| {
+                code.lines()
+                    .next()
+                    .and_then(|line| self.syntaxes.find_syntax_by_first_line(line))
+            })
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text())
+    }
+}
+
+/// Extract `x` from a `class="language-x"` attribute
+fn code_language(attrs: &str) -> Option<&str> {
+    let start = attrs.find("class=\"language-")? + "class=\"language-".len();
+    let lang = attrs.get(start..)?;
+    lang.get(..lang.find('"')?)
+}
+
+/// A loaded page template ready to wrap converted fragments
+struct PageTemplate {
+    template: String,
+    css: String,
+}
+
+impl PageTemplate {
+    fn render(&self, title: &str, nav: &str, content: &str) -> String {
+        // Content goes last so placeholders inside the document are left untouched
+        self.template
+            .replace("{{title}}", &escape_html(title))
+            .replace("{{css}}", &self.css)
+            .replace("{{nav}}", nav)
+            .replace("{{content}}", content)
+    }
+}
+
+/// Use the text of the first `<h1>` as the page title, falling back to `fallback`
+fn extract_title(html: &str, fallback: &str) -> String {
+    let title = html.find("<h1").and_then(|start| {
+        let rest = html.get(start..)?;
+        let open_end = rest.find('>')?;
+        let inner = rest.get(open_end + 1..)?;
+        let close = inner.find("</h1>")?;
+        inner.get(..close)
+    });
+
+    match title.map(strip_tags).map(|t| t.trim().to_string()) {
+        Some(t) if !t.is_empty() => unescape_html(&t),
+        _ => fallback.to_string(),
+    }
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut output = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            _ if !in_tag => output.push(c),
+            _ => {}
+        }
+    }
+    output
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn unescape_html(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// Navigation linking back to the root index of a converted directory
+fn index_nav(depth: usize) -> String {
+    format!("<nav><a href=\"{}index.html\">Index</a></nav>", "../".repeat(depth))
+}
+
 /// Convert markdown string to HTML
 pub fn convert_md_to_html(input: &str, options: &MdToHtmlOptions) -> Result<String> {
     let md_options = options.to_markdown_options();
@@ -173,28 +399,38 @@ pub fn convert_md_to_html(input: &str, options: &MdToHtmlOptions) -> Result<Stri
         .map_err(|e| AppError::MarkdownConvert(e.to_string()))
 }
 
-/// Convert markdown file to HTML file
+/// Convert markdown file to HTML file; an `input_path` of `-` reads stdin
 pub fn convert_md_file_to_html(
     input_path: &str,
     output_path: Option<&str>,
     options: &MdToHtmlOptions,
+    page: &HtmlPageOptions,
 ) -> Result<String> {
     let input = Path::new(input_path);
+    let page_template = page.load()?;
+    let highlighter = page.highlighter()?;
 
-    // Read input file
-    let content = fs::read_to_string(input).map_err(|e| AppError::ReadFile {
-        path: input_path.to_string(),
-        source: e,
-    })?;
+    // Read input file, or stdin for `-`
+    let content = read_input(input_path)?;
 
     // Convert to HTML
-    let html = convert_md_to_html(&content, options)?;
+    let mut html = convert_md_to_html(&content, options)?;
+    if let Some(highlighter) = &highlighter {
+        html = highlighter.highlight_code_blocks(&html)?;
+    }
+    let stem = input
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .filter(|_| !is_stdio(input_path))
+        .unwrap_or("output");
+    if let Some(template) = &page_template {
+        html = template.render(&extract_title(&html, stem), "", &html);
+    }
 
     // Determine output path
     let output = match output_path {
         Some(p) => p.to_string(),
         None => {
-            let stem = input.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
             let parent = input.parent().unwrap_or(Path::new("."));
             parent.join(format!("{}.html", stem)).to_string_lossy().to_string()
         }
@@ -209,6 +445,115 @@ pub fn convert_md_file_to_html(
     Ok(output)
 }
 
+/// Convert every markdown file under `input_dir` to HTML under `output_dir`.
+///
+/// The relative directory structure is preserved and relative links to `.md`
+/// files are rewritten to point at the generated `.html` files. When a page
+/// template is used and the directory has a root `index.md`, every page links
+/// back to it. Returns the number of files converted.
+pub fn convert_md_dir_to_html(
+    input_dir: &str,
+    output_dir: &str,
+    options: &MdToHtmlOptions,
+    page: &HtmlPageOptions,
+    progress_callback: ProgressCallback,
+) -> Result<usize> {
+    let input_root = Path::new(input_dir);
+    let output_root = Path::new(output_dir);
+    let page_template = page.load()?;
+    let highlighter = page.highlighter()?;
+
+    let mut md_files = Vec::new();
+    for entry in WalkDir::new(input_root).sort_by_file_name() {
+        let entry = entry.map_err(|e| AppError::ReadFile {
+            path: input_dir.to_string(),
+            source: e.into(),
+        })?;
+        if entry.file_type().is_file() && is_markdown_path(entry.path()) {
+            md_files.push(entry.into_path());
+        }
+    }
+
+    let has_index = md_files.iter().any(|p| p == &input_root.join("index.md"));
+    let total = md_files.len();
+    for (idx, md_path) in md_files.iter().enumerate() {
+        let relative = md_path.strip_prefix(input_root).unwrap_or(md_path);
+        if let Some(cb) = progress_callback {
+            cb(idx + 1, total, &relative.display().to_string());
+        }
+
+        let content = fs::read_to_string(md_path).map_err(|e| AppError::ReadFile {
+            path: md_path.display().to_string(),
+            source: e,
+        })?;
+        let mut html = rewrite_md_links(&convert_md_to_html(&content, options)?);
+        if let Some(highlighter) = &highlighter {
+            html = highlighter.highlight_code_blocks(&html)?;
+        }
+        if let Some(template) = &page_template {
+            let stem = md_path.file_stem().and_then(|s| s.to_str()).unwrap_or("output");
+            let depth = relative.components().count().saturating_sub(1);
+            let nav = if has_index { index_nav(depth) } else { String::new() };
+            html = template.render(&extract_title(&html, stem), &nav, &html);
+        }
+
+        let out_path = output_root.join(relative).with_extension("html");
+        if let Some(parent) = out_path.parent() {
+            fs::create_dir_all(parent).map_err(|e| AppError::WriteFile {
+                path: parent.display().to_string(),
+                source: e,
+            })?;
+        }
+        fs::write(&out_path, &html).map_err(|e| AppError::WriteFile {
+            path: out_path.display().to_string(),
+            source: e,
+        })?;
+    }
+
+    Ok(total)
+}
+
+fn is_markdown_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .map(|e| e.eq_ignore_ascii_case("md") || e.eq_ignore_ascii_case("markdown"))
+        .unwrap_or(false)
+}
+
+/// Rewrite relative `href` targets ending in `.md` to `.html`, keeping any fragment or query.
+fn rewrite_md_links(html: &str) -> String {
+    const HREF: &str = "href=\"";
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+
+    while let Some(start) = rest.find(HREF) {
+        let (before, after) = rest.split_at(start + HREF.len());
+        output.push_str(before);
+
+        let end = after.find('"').unwrap_or(after.len());
+        let (target, remaining) = after.split_at(end);
+        output.push_str(&rewrite_md_target(target));
+        rest = remaining;
+    }
+    output.push_str(rest);
+
+    output
+}
+
+fn rewrite_md_target(target: &str) -> String {
+    if target.contains("://") || target.starts_with("mailto:") || target.starts_with('#') {
+        return target.to_string();
+    }
+
+    let split_at = target.find(['#', '?']).unwrap_or(target.len());
+    let (path, suffix) = target.split_at(split_at);
+    match path.strip_suffix(".md") {
+        Some(stem) => format!("{}.html{}", stem, suffix),
+        None => target.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -307,4 +652,164 @@ mod tests {
         assert!(html.contains("src=\"image.png\""));
         assert!(html.contains("alt=\"alt\""));
     }
+
+    #[test]
+    fn test_rewrite_md_links_relative() {
+        let html = r#"<a href="notes/week-01.md">w1</a> <a href="other.md#top">o</a>"#;
+        let rewritten = rewrite_md_links(html);
+
+        assert!(rewritten.contains(r#"href="notes/week-01.html""#));
+        assert!(rewritten.contains(r#"href="other.html#top""#));
+    }
+
+    #[test]
+    fn test_rewrite_md_links_keeps_absolute_and_other_links() {
+        let html = r#"<a href="https://example.com/readme.md">r</a> <a href="image.png">i</a>"#;
+        let rewritten = rewrite_md_links(html);
+
+        assert_eq!(rewritten, html);
+    }
+
+    #[test]
+    fn test_convert_md_dir_to_html_preserves_structure() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::create_dir_all(input.path().join("2024/week-01")).unwrap();
+        fs::write(input.path().join("index.md"), "[Week 1](2024/week-01/general.md)").unwrap();
+        fs::write(input.path().join("2024/week-01/general.md"), "# General").unwrap();
+        fs::write(input.path().join("notes.txt"), "not markdown").unwrap();
+
+        let count = convert_md_dir_to_html(
+            input.path().to_str().unwrap(),
+            output.path().to_str().unwrap(),
+            &MdToHtmlOptions::new(),
+            &HtmlPageOptions::default(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(count, 2);
+        let index = fs::read_to_string(output.path().join("index.html")).unwrap();
+        assert!(index.contains(r#"href="2024/week-01/general.html""#));
+        let general = fs::read_to_string(output.path().join("2024/week-01/general.html")).unwrap();
+        assert!(general.contains("<h1>General</h1>"));
+        assert!(!output.path().join("notes.html").exists());
+    }
+
+    #[test]
+    fn test_page_options_disabled_by_default() {
+        let page = HtmlPageOptions::default();
+        assert!(!page.is_enabled());
+        assert!(page.load().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_default_template_wraps_fragment() {
+        let page = HtmlPageOptions {
+            template: None,
+            css: Some("https://example.com/style.css".to_string()),
+            highlight_theme: None,
+        };
+        let template = page.load().unwrap().unwrap();
+        let html = template.render("My <Archive>", "<nav></nav>", "<p>body</p>");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<title>My &lt;Archive&gt;</title>"));
+        assert!(html.contains(r#"<link rel="stylesheet" href="https://example.com/style.css">"#));
+        assert!(html.contains("<nav></nav>"));
+        assert!(html.contains("<p>body</p>"));
+    }
+
+    #[test]
+    fn test_custom_template_and_inlined_css() {
+        let dir = tempfile::tempdir().unwrap();
+        let template_path = dir.path().join("page.html");
+        let css_path = dir.path().join("style.css");
+        fs::write(&template_path, "<title>{{title}}</title>{{css}}{{nav}}<article>{{content}}</article>").unwrap();
+        fs::write(&css_path, "body { color: red; }").unwrap();
+
+        let page = HtmlPageOptions {
+            template: Some(template_path.to_string_lossy().to_string()),
+            css: Some(css_path.to_string_lossy().to_string()),
+            highlight_theme: None,
+        };
+        let template = page.load().unwrap().unwrap();
+        let html = template.render("T", "", "<p>{{title}}</p>");
+
+        assert_eq!(
+            html,
+            "<title>T</title><style>\nbody { color: red; }\n</style><article><p>{{title}}</p></article>"
+        );
+    }
+
+    #[test]
+    fn test_extract_title() {
+        assert_eq!(extract_title("<h1>Hello <em>there</em></h1><p>x</p>", "fb"), "Hello there");
+        assert_eq!(extract_title("<h1>Q&amp;A</h1>", "fb"), "Q&A");
+        assert_eq!(extract_title("<p>no heading</p>", "fb"), "fb");
+    }
+
+    #[test]
+    fn test_convert_md_dir_to_html_with_page_nav() {
+        let input = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        fs::create_dir_all(input.path().join("2024")).unwrap();
+        fs::write(input.path().join("index.md"), "# Archive").unwrap();
+        fs::write(input.path().join("2024/general.md"), "# General").unwrap();
+
+        let page = HtmlPageOptions {
+            template: None,
+            css: Some("https://example.com/style.css".to_string()),
+            highlight_theme: None,
+        };
+        convert_md_dir_to_html(
+            input.path().to_str().unwrap(),
+            output.path().to_str().unwrap(),
+            &MdToHtmlOptions::new(),
+            &page,
+            None,
+        )
+        .unwrap();
+
+        let general = fs::read_to_string(output.path().join("2024/general.html")).unwrap();
+        assert!(general.contains("<title>General</title>"));
+        assert!(general.contains(r#"<a href="../index.html">Index</a>"#));
+        let index = fs::read_to_string(output.path().join("index.html")).unwrap();
+        assert!(index.contains(r#"<a href="index.html">Index</a>"#));
+    }
+
+    #[test]
+    fn test_code_language() {
+        assert_eq!(code_language(r#" class="language-rust""#), Some("rust"));
+        assert_eq!(code_language(""), None);
+    }
+
+    #[test]
+    fn test_highlight_code_blocks_with_language() {
+        let highlighter = CodeHighlighter::new(DEFAULT_HIGHLIGHT_THEME).unwrap();
+        let html = convert_md_to_html("```rust\nfn main() {}\n```", &MdToHtmlOptions::new()).unwrap();
+        let highlighted = highlighter.highlight_code_blocks(&html).unwrap();
+
+        assert!(!highlighted.contains("<code"));
+        assert!(highlighted.contains("<pre style="));
+        assert!(highlighted.contains("<span style="));
+        assert!(highlighted.contains("main"));
+    }
+
+    #[test]
+    fn test_highlight_code_blocks_without_language_keeps_text() {
+        let highlighter = CodeHighlighter::new(DEFAULT_HIGHLIGHT_THEME).unwrap();
+        let html = convert_md_to_html("before\n\n```\na < b && c\n```\n\nafter", &MdToHtmlOptions::new()).unwrap();
+        let highlighted = highlighter.highlight_code_blocks(&html).unwrap();
+
+        assert!(highlighted.starts_with("<p>before</p>"));
+        assert!(highlighted.contains("a &lt; b &amp;&amp; c"));
+        assert!(highlighted.ends_with("<p>after</p>"));
+    }
+
+    #[test]
+    fn test_highlighter_unknown_theme() {
+        let result = CodeHighlighter::new("no-such-theme");
+        assert!(matches!(result, Err(AppError::SyntaxHighlight(_))));
+    }
 }
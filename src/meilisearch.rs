@@ -1,16 +1,19 @@
 use std::fs::File;
 use std::io::BufReader;
 
+use chrono::Duration;
+
 use meilisearch_sdk::client::{Client, SwapIndexes};
 use meilisearch_sdk::indexes::Index;
+use meilisearch_sdk::search::Selectors;
 use meilisearch_sdk::task_info::TaskInfo;
 use meilisearch_sdk::tasks::Task;
 use uuid::Uuid;
 
 use crate::error::{AppError, Result};
-use crate::index::IndexEntry;
+use crate::index::{slack_ts_to_secs, IndexEntry, SearchHit, HIGHLIGHT_POST_TAG, HIGHLIGHT_PRE_TAG};
 use crate::settings::{MeilisearchSettings, Settings};
-use crate::ProgressCallback;
+use crate::{parse_date, ProgressCallback};
 
 /// Entry with sanitized ID for Meilisearch (no dots allowed)
 #[derive(Debug, Clone, serde::Serialize)]
@@ -21,10 +24,19 @@ struct MeilisearchEntry {
     pub text: String,
     pub users: Vec<crate::index::IndexUser>,
     pub channel: crate::index::IndexChannel,
+    pub timestamp: i64,
+    pub has_attachments: bool,
 }
 
 impl From<IndexEntry> for MeilisearchEntry {
     fn from(entry: IndexEntry) -> Self {
+        // Index files written before facets existed have no timestamp
+        let timestamp = if entry.timestamp == 0 {
+            slack_ts_to_secs(&entry.ts)
+        } else {
+            entry.timestamp
+        };
+
         Self {
             // id is already sanitized in IndexEntry now
             id: entry.id,
@@ -33,6 +45,8 @@ impl From<IndexEntry> for MeilisearchEntry {
             text: entry.text,
             users: entry.users,
             channel: entry.channel,
+            timestamp,
+            has_attachments: entry.has_attachments,
         }
     }
 }
@@ -40,6 +54,166 @@ impl From<IndexEntry> for MeilisearchEntry {
 const BATCH_SIZE: usize = 100;
 const TEMP_INDEX_PREFIX: &str = "slack_utils_temp_";
 
+/// Attributes usable in search filters
+const FILTERABLE_ATTRIBUTES: [&str; 6] = [
+    "channel.id",
+    "channel.name",
+    "users.id",
+    "users.name",
+    "timestamp",
+    "has_attachments",
+];
+
+/// Attributes search results can be sorted by
+const SORTABLE_ATTRIBUTES: [&str; 1] = ["timestamp"];
+
+/// Filters applied to a Meilisearch query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SearchFilters {
+    /// Channel name or ID
+    pub channel: Option<String>,
+    /// Name or ID of a user involved in the thread
+    pub user: Option<String>,
+    /// Only threads on or after this date (YYYY-MM-DD)
+    pub from: Option<String>,
+    /// Only threads on or before this date (YYYY-MM-DD)
+    pub to: Option<String>,
+    /// Only threads with (`true`) or without (`false`) attachments
+    pub has_attachments: Option<bool>,
+}
+
+impl SearchFilters {
+    /// Build the Meilisearch filter expression, or `None` when no filter is set
+    pub fn to_filter_expression(&self) -> Result<Option<String>> {
+        let mut clauses = Vec::new();
+
+        if let Some(channel) = &self.channel {
+            let value = quote_filter_value(channel);
+            clauses.push(format!("(channel.name = {value} OR channel.id = {value})"));
+        }
+        if let Some(user) = &self.user {
+            let value = quote_filter_value(user);
+            clauses.push(format!("(users.name = {value} OR users.id = {value})"));
+        }
+        if let Some(from) = &self.from {
+            let start = date_start_timestamp(from)?;
+            clauses.push(format!("timestamp >= {}", start));
+        }
+        if let Some(to) = &self.to {
+            let end = date_start_timestamp(to)? + Duration::days(1).num_seconds();
+            clauses.push(format!("timestamp < {}", end));
+        }
+        if let Some(has_attachments) = self.has_attachments {
+            clauses.push(format!("has_attachments = {}", has_attachments));
+        }
+
+        Ok((!clauses.is_empty()).then(|| clauses.join(" AND ")))
+    }
+}
+
+/// Quote a string for use in a filter expression
+fn quote_filter_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Unix timestamp of midnight UTC for a YYYY-MM-DD date
+fn date_start_timestamp(date: &str) -> Result<i64> {
+    let date = parse_date(date)?;
+    Ok(date.and_time(chrono::NaiveTime::MIN).and_utc().timestamp())
+}
+
+/// Free text and filters parsed from a Slack-like search query
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParsedQuery {
+    /// Remaining free text, including any `"exact phrase"`
+    pub text: String,
+    pub filters: SearchFilters,
+}
+
+/// Parse a Slack-like search query into free text and filters.
+///
+/// Supported modifiers: `from:@user`, `in:#channel`, `before:YYYY-MM-DD`,
+/// `after:YYYY-MM-DD` (both exclusive, like Slack), `on:YYYY-MM-DD` and
+/// `has:attachment`. Values may be quoted (`in:"team chat"`). Quoted phrases
+/// and unknown `key:value` tokens (e.g. URLs) are kept as free text.
+pub fn parse_search_query(input: &str) -> Result<ParsedQuery> {
+    let mut parsed = ParsedQuery::default();
+    let mut text_parts = Vec::new();
+
+    for token in tokenize_query(input) {
+        let Some((key, value)) = token.split_once(':').filter(|(key, _)| !key.starts_with('"')) else {
+            text_parts.push(token);
+            continue;
+        };
+        let value = value.trim_matches('"');
+        let filters = &mut parsed.filters;
+        match key.to_lowercase().as_str() {
+            "from" if !value.is_empty() => filters.user = Some(value.trim_start_matches('@').to_string()),
+            "in" if !value.is_empty() => filters.channel = Some(value.trim_start_matches('#').to_string()),
+            "before" => filters.to = Some(shift_date(value, -1)?),
+            "after" => filters.from = Some(shift_date(value, 1)?),
+            "on" | "during" => {
+                let date = shift_date(value, 0)?;
+                filters.from = Some(date.clone());
+                filters.to = Some(date);
+            }
+            "has" if matches!(value, "attachment" | "attachments" | "file" | "files") => {
+                filters.has_attachments = Some(true);
+            }
+            _ => text_parts.push(token),
+        }
+    }
+
+    parsed.text = text_parts.join(" ");
+    Ok(parsed)
+}
+
+/// Split a query on whitespace, keeping quoted sections (`"a b"`, `in:"a b"`) together
+fn tokenize_query(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(c);
+            }
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+}
+
+/// Parse a YYYY-MM-DD date and move it by `days`
+fn shift_date(date: &str, days: i64) -> Result<String> {
+    let date = parse_date(date)? + Duration::days(days);
+    Ok(date.format("%Y-%m-%d").to_string())
+}
+
+impl SearchFilters {
+    /// Fill unset filters from `fallback`; filters already set take precedence
+    pub fn or(&self, fallback: &SearchFilters) -> SearchFilters {
+        SearchFilters {
+            channel: self.channel.clone().or_else(|| fallback.channel.clone()),
+            user: self.user.clone().or_else(|| fallback.user.clone()),
+            from: self.from.clone().or_else(|| fallback.from.clone()),
+            to: self.to.clone().or_else(|| fallback.to.clone()),
+            has_attachments: self.has_attachments.or(fallback.has_attachments),
+        }
+    }
+}
+
 /// Result of importing to Meilisearch
 #[derive(Debug)]
 pub struct MeilisearchImportResult {
@@ -113,6 +287,9 @@ where
 
     let index = client.index(index_name);
 
+    report_progress(0, total, "Configuring filterable attributes...");
+    configure_search_attributes(client, &index, report_progress, 0, total).await?;
+
     // Import in batches
     let mut imported = 0;
     for (batch_num, batch) in entries.chunks(BATCH_SIZE).enumerate() {
@@ -184,6 +361,9 @@ where
     };
     let _ = target_exists; // silence unused warning
 
+    report_progress(0, total, "Configuring filterable attributes...");
+    configure_search_attributes(client, &temp_index, report_progress, 0, total).await?;
+
     // Import in batches to temp index
     let mut imported = 0;
     for (batch_num, batch) in entries.chunks(BATCH_SIZE).enumerate() {
@@ -288,6 +468,30 @@ async fn copy_index_settings(source: &Index, target: &Index) -> Result<()> {
     Ok(())
 }
 
+/// Make channel, user, date and attachment fields filterable and dates sortable
+async fn configure_search_attributes<F>(
+    client: &Client,
+    index: &Index,
+    report_progress: &F,
+    current: usize,
+    total: usize,
+) -> Result<()>
+where
+    F: Fn(usize, usize, &str),
+{
+    let task = index
+        .set_filterable_attributes(FILTERABLE_ATTRIBUTES)
+        .await
+        .map_err(|e| AppError::Meilisearch(e.to_string()))?;
+    wait_for_task(client, &task, report_progress, current, total).await?;
+
+    let task = index
+        .set_sortable_attributes(SORTABLE_ATTRIBUTES)
+        .await
+        .map_err(|e| AppError::Meilisearch(e.to_string()))?;
+    wait_for_task(client, &task, report_progress, current, total).await
+}
+
 /// Save Meilisearch settings to settings.toml
 fn save_meilisearch_settings(input_path: &str, url: &str, api_key: &str, index_name: &str) -> Result<()> {
     let mut settings = Settings::load().unwrap_or_default();
@@ -300,35 +504,66 @@ fn save_meilisearch_settings(input_path: &str, url: &str, api_key: &str, index_n
     settings.save()
 }
 
+/// Number of words kept around the match in the highlighted snippet
+const SNIPPET_CROP_LENGTH: usize = 30;
+
 /// Search result from Meilisearch
 #[derive(Debug)]
 pub struct MeilisearchSearchResult {
-    pub hits: Vec<IndexEntry>,
+    pub hits: Vec<SearchHit>,
     pub processing_time_ms: usize,
     pub estimated_total_hits: Option<usize>,
 }
 
 /// Query Meilisearch index
+///
+/// The query may contain Slack-like modifiers (see [`parse_search_query`]).
 pub async fn query_meilisearch(
     url: &str,
     api_key: &str,
     index_name: &str,
     query: &str,
+    filters: &SearchFilters,
     limit: usize,
 ) -> Result<MeilisearchSearchResult> {
+    // Modifiers in the query (from:, in:, ...) apply unless the filter is set explicitly
+    let parsed = parse_search_query(query)?;
+    let filter = filters.or(&parsed.filters).to_filter_expression()?;
     let client = Client::new(url, Some(api_key)).map_err(|e| AppError::Meilisearch(e.to_string()))?;
     let index = client.index(index_name);
 
-    let results = index
-        .search()
-        .with_query(query)
+    let mut search = index.search();
+    search
+        .with_query(&parsed.text)
         .with_limit(limit)
+        .with_attributes_to_highlight(Selectors::Some(&["text"]))
+        .with_attributes_to_crop(Selectors::Some(&[("text", None)]))
+        .with_crop_length(SNIPPET_CROP_LENGTH)
+        .with_highlight_pre_tag(HIGHLIGHT_PRE_TAG)
+        .with_highlight_post_tag(HIGHLIGHT_POST_TAG);
+    if let Some(filter) = &filter {
+        search.with_filter(filter);
+    }
+
+    let results = search
         .execute::<IndexEntry>()
         .await
         .map_err(|e| AppError::Meilisearch(e.to_string()))?;
 
     Ok(MeilisearchSearchResult {
-        hits: results.hits.into_iter().map(|h| h.result).collect(),
+        hits: results
+            .hits
+            .into_iter()
+            .map(|h| SearchHit {
+                snippet: h
+                    .formatted_result
+                    .as_ref()
+                    .and_then(|f| f.get("text"))
+                    .and_then(|t| t.as_str())
+                    .map(str::to_string),
+                entry: h.result,
+            })
+            .collect(),
         processing_time_ms: results.processing_time_ms,
         estimated_total_hits: results.estimated_total_hits,
     })
@@ -364,6 +599,8 @@ mod tests {
                 id: "C456".to_string(),
                 name: "general".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let ms_entry = MeilisearchEntry::from(index_entry.clone());
@@ -394,6 +631,8 @@ mod tests {
                 id: "C456".to_string(),
                 name: "general".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -418,6 +657,8 @@ mod tests {
                 id: "C456".to_string(),
                 name: "general".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let ms_entry = MeilisearchEntry::from(index_entry);
@@ -438,6 +679,8 @@ mod tests {
                 id: "C456".to_string(),
                 name: "general".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let ms_entry = MeilisearchEntry::from(index_entry);
@@ -472,6 +715,8 @@ mod tests {
                 id: "C789".to_string(),
                 name: "random".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let ms_entry = MeilisearchEntry::from(index_entry);
@@ -494,6 +739,8 @@ mod tests {
                 id: "C789".to_string(),
                 name: "announcements".to_string(),
             },
+            timestamp: 1234567890,
+            has_attachments: false,
         };
 
         let ms_entry = MeilisearchEntry::from(index_entry);
@@ -515,22 +762,28 @@ mod tests {
     #[test]
     fn test_meilisearch_search_result() {
         let result = MeilisearchSearchResult {
-            hits: vec![IndexEntry {
-                id: "123_456".to_string(),
-                ts: "123.456".to_string(),
-                date: "2009-02-13T23:31:30+00:00".to_string(),
-                text: "Found message".to_string(),
-                users: vec![],
-                channel: IndexChannel {
-                    id: "C789".to_string(),
-                    name: "general".to_string(),
+            hits: vec![SearchHit {
+                entry: IndexEntry {
+                    id: "123_456".to_string(),
+                    ts: "123.456".to_string(),
+                    date: "2009-02-13T23:31:30+00:00".to_string(),
+                    text: "Found message".to_string(),
+                    users: vec![],
+                    channel: IndexChannel {
+                        id: "C789".to_string(),
+                        name: "general".to_string(),
+                    },
+                    timestamp: 123,
+                    has_attachments: false,
                 },
+                snippet: Some("<mark>Found</mark> message".to_string()),
             }],
             processing_time_ms: 5,
             estimated_total_hits: Some(42),
         };
 
         assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].entry.channel.name, "general");
         assert_eq!(result.processing_time_ms, 5);
         assert_eq!(result.estimated_total_hits, Some(42));
     }
@@ -556,4 +809,137 @@ mod tests {
         assert!(temp_name.starts_with("slack_utils_temp_"));
         assert!(temp_name.len() > TEMP_INDEX_PREFIX.len());
     }
+
+    #[test]
+    fn test_meilisearch_entry_timestamp_fallback_from_ts() {
+        let index_entry = IndexEntry {
+            id: "1234567890_123456".to_string(),
+            ts: "1234567890.123456".to_string(),
+            date: "2009-02-13T23:31:30+00:00".to_string(),
+            text: "Old index entry".to_string(),
+            users: vec![],
+            channel: IndexChannel {
+                id: "C456".to_string(),
+                name: "general".to_string(),
+            },
+            timestamp: 0,
+            has_attachments: true,
+        };
+
+        let ms_entry = MeilisearchEntry::from(index_entry);
+
+        assert_eq!(ms_entry.timestamp, 1234567890);
+        assert!(ms_entry.has_attachments);
+    }
+
+    #[test]
+    fn test_search_filters_empty() {
+        let filters = SearchFilters::default();
+        assert_eq!(filters.to_filter_expression().unwrap(), None);
+    }
+
+    #[test]
+    fn test_search_filters_all() {
+        let filters = SearchFilters {
+            channel: Some("general".to_string()),
+            user: Some("U123".to_string()),
+            from: Some("2024-01-01".to_string()),
+            to: Some("2024-01-31".to_string()),
+            has_attachments: Some(true),
+        };
+
+        let expression = filters.to_filter_expression().unwrap().unwrap();
+
+        assert_eq!(
+            expression,
+            "(channel.name = \"general\" OR channel.id = \"general\") AND \
+             (users.name = \"U123\" OR users.id = \"U123\") AND \
+             timestamp >= 1704067200 AND timestamp < 1706745600 AND has_attachments = true"
+        );
+    }
+
+    #[test]
+    fn test_search_filters_invalid_date() {
+        let filters = SearchFilters {
+            from: Some("not-a-date".to_string()),
+            ..Default::default()
+        };
+
+        assert!(matches!(filters.to_filter_expression(), Err(AppError::InvalidDate(_))));
+    }
+
+    #[test]
+    fn test_quote_filter_value_escapes_quotes() {
+        assert_eq!(quote_filter_value(r#"say "hi""#), r#""say \"hi\"""#);
+    }
+
+    #[test]
+    fn test_filterable_attributes_cover_facets() {
+        for attr in ["channel.name", "users.name", "timestamp", "has_attachments"] {
+            assert!(FILTERABLE_ATTRIBUTES.contains(&attr));
+        }
+        assert_eq!(SORTABLE_ATTRIBUTES, ["timestamp"]);
+    }
+
+    #[test]
+    fn test_parse_search_query_modifiers() {
+        let parsed =
+            parse_search_query(r#"from:@alice in:#general before:2024-02-01 "exact phrase" deploy"#).unwrap();
+
+        assert_eq!(parsed.text, r#""exact phrase" deploy"#);
+        assert_eq!(parsed.filters.user.as_deref(), Some("alice"));
+        assert_eq!(parsed.filters.channel.as_deref(), Some("general"));
+        assert_eq!(parsed.filters.to.as_deref(), Some("2024-01-31"));
+        assert_eq!(parsed.filters.from, None);
+    }
+
+    #[test]
+    fn test_parse_search_query_dates_and_has() {
+        let parsed = parse_search_query("after:2024-01-31 has:attachment").unwrap();
+        assert_eq!(parsed.text, "");
+        assert_eq!(parsed.filters.from.as_deref(), Some("2024-02-01"));
+        assert_eq!(parsed.filters.has_attachments, Some(true));
+
+        let parsed = parse_search_query("on:2024-03-05").unwrap();
+        assert_eq!(parsed.filters.from.as_deref(), Some("2024-03-05"));
+        assert_eq!(parsed.filters.to.as_deref(), Some("2024-03-05"));
+    }
+
+    #[test]
+    fn test_parse_search_query_quoted_value() {
+        let parsed = parse_search_query(r#"in:"team chat" lunch"#).unwrap();
+        assert_eq!(parsed.filters.channel.as_deref(), Some("team chat"));
+        assert_eq!(parsed.text, "lunch");
+    }
+
+    #[test]
+    fn test_parse_search_query_keeps_unknown_modifiers_as_text() {
+        let parsed = parse_search_query("https://example.com has:reaction \"a:b\"").unwrap();
+        assert_eq!(parsed.text, "https://example.com has:reaction \"a:b\"");
+        assert_eq!(parsed.filters, SearchFilters::default());
+    }
+
+    #[test]
+    fn test_parse_search_query_invalid_date() {
+        assert!(matches!(parse_search_query("before:yesterday"), Err(AppError::InvalidDate(_))));
+    }
+
+    #[test]
+    fn test_search_filters_or_prefers_explicit() {
+        let explicit = SearchFilters {
+            channel: Some("ops".to_string()),
+            ..Default::default()
+        };
+        let parsed = SearchFilters {
+            channel: Some("general".to_string()),
+            user: Some("alice".to_string()),
+            ..Default::default()
+        };
+
+        let merged = explicit.or(&parsed);
+
+        assert_eq!(merged.channel.as_deref(), Some("ops"));
+        assert_eq!(merged.user.as_deref(), Some("alice"));
+    }
+
 }
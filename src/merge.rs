@@ -0,0 +1,260 @@
+//! Merge overlapping conversation exports into one, deduplicating messages
+//! by (channel ID, ts)
+
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap};
+
+use serde_json::Value;
+
+/// What a merge combined
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct MergeStats {
+    pub channels: usize,
+    /// Top-level messages in the output
+    pub messages: usize,
+    pub replies: usize,
+    /// Copies of a message dropped because another input had it too
+    pub duplicates: usize,
+    /// Messages and replies without a ts, which can't be merged and are dropped
+    pub skipped: usize,
+}
+
+/// Slack timestamps ordered numerically, `"1705312800.000100"` as
+/// (1705312800, 100); unparseable parts sort first
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct TsKey(u64, u64, String);
+
+impl TsKey {
+    fn new(ts: &str) -> Self {
+        let (secs, micros) = ts.split_once('.').unwrap_or((ts, "0"));
+        TsKey(secs.parse().unwrap_or(0), micros.parse().unwrap_or(0), ts.to_string())
+    }
+}
+
+fn edited_ts(message: &Value) -> Option<TsKey> {
+    message.pointer("/edited/ts").and_then(|v| v.as_str()).map(TsKey::new)
+}
+
+/// Whether `candidate`, from a later input, replaces `current`: the most
+/// recently edited version wins, and the later input on ties
+fn replaces(candidate: &Value, current: &Value) -> bool {
+    edited_ts(candidate).cmp(&edited_ts(current)) != Ordering::Less
+}
+
+#[derive(Default)]
+struct MergedThread {
+    message: Value,
+    replies: BTreeMap<TsKey, Value>,
+}
+
+struct MergedChannel {
+    channel_id: String,
+    channel_name: String,
+    messages: BTreeMap<TsKey, MergedThread>,
+}
+
+/// Insert `message` under `ts`, keeping whichever version `replaces` picks
+fn upsert(versions: &mut BTreeMap<TsKey, Value>, ts: TsKey, message: Value, stats: &mut MergeStats) {
+    match versions.get_mut(&ts) {
+        Some(current) => {
+            stats.duplicates += 1;
+            if replaces(&message, current) {
+                *current = message;
+            }
+        }
+        None => {
+            versions.insert(ts, message);
+        }
+    }
+}
+
+/// Union conversation exports, given oldest first. Channels keep the order
+/// they first appear in and take their name from the last input that has
+/// them; messages are newest first and replies oldest first, as exported.
+/// A thread's replies are the union of the replies in every input.
+pub fn merge_conversations(inputs: &[Vec<Value>]) -> (Vec<Value>, MergeStats) {
+    let mut stats = MergeStats::default();
+    let mut channels: Vec<MergedChannel> = Vec::new();
+    let mut by_id: HashMap<String, usize> = HashMap::new();
+
+    for conversation in inputs.iter().flatten() {
+        let text = |key: &str| conversation.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+        let channel_id = text("channel_id");
+        let channel_name = text("channel_name");
+        let idx = *by_id.entry(channel_id.clone()).or_insert_with(|| {
+            channels.push(MergedChannel {
+                channel_id,
+                channel_name: String::new(),
+                messages: BTreeMap::new(),
+            });
+            channels.len() - 1
+        });
+        let Some(channel) = channels.get_mut(idx) else {
+            continue;
+        };
+        if !channel_name.is_empty() {
+            channel.channel_name = channel_name;
+        }
+
+        let messages = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        for message in messages {
+            let Some(ts) = message.get("ts").and_then(|v| v.as_str()).map(TsKey::new) else {
+                stats.skipped += 1;
+                continue;
+            };
+            let mut message = message.clone();
+            let replies = message
+                .as_object_mut()
+                .and_then(|m| m.remove("thread_replies"))
+                .and_then(|r| match r {
+                    Value::Array(replies) => Some(replies),
+                    _ => None,
+                })
+                .unwrap_or_default();
+
+            let thread = channel.messages.entry(ts).or_default();
+            if thread.message.is_null() {
+                thread.message = message;
+            } else {
+                stats.duplicates += 1;
+                if replaces(&message, &thread.message) {
+                    thread.message = message;
+                }
+            }
+            for reply in replies {
+                match reply.get("ts").and_then(|v| v.as_str()).map(TsKey::new) {
+                    Some(reply_ts) => upsert(&mut thread.replies, reply_ts, reply, &mut stats),
+                    None => stats.skipped += 1,
+                }
+            }
+        }
+    }
+
+    stats.channels = channels.len();
+    let merged = channels
+        .into_iter()
+        .map(|channel| {
+            let messages: Vec<Value> = channel
+                .messages
+                .into_values()
+                .rev()
+                .map(|thread| {
+                    let mut message = thread.message;
+                    stats.messages += 1;
+                    stats.replies += thread.replies.len();
+                    if let Some(fields) = message.as_object_mut()
+                        && !thread.replies.is_empty()
+                    {
+                        fields.insert("thread_replies".to_string(), thread.replies.into_values().collect());
+                    }
+                    message
+                })
+                .collect();
+            serde_json::json!({
+                "channel_id": channel.channel_id,
+                "channel_name": channel.channel_name,
+                "messages": messages,
+            })
+        })
+        .collect();
+    (merged, stats)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn ts_list(messages: &Value) -> Vec<&str> {
+        messages
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|m| m["ts"].as_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_merge_conversations() {
+        let older = vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1705312900.000100", "text": "typo", "edited": {"ts": "1705312950.000000"}},
+                    {"ts": "1705312800.000100", "text": "question", "reply_count": 1,
+                     "thread_replies": [{"ts": "1705312860.000100", "text": "first answer"}]}
+                ]
+            }),
+            json!({"channel_id": "C2", "channel_name": "random", "messages": [{"ts": "1.0", "text": "hi"}]}),
+        ];
+        let newer = vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general-renamed",
+            "messages": [
+                {"ts": "1705399200.000100", "text": "next day"},
+                {"ts": "1705312900.000100", "text": "fixed", "edited": {"ts": "1705313000.000000"}},
+                {"ts": "1705312800.000100", "text": "question", "reply_count": 2,
+                 "thread_replies": [
+                     {"ts": "1705312860.000100", "text": "first answer"},
+                     {"ts": "1705312870.000100", "text": "second answer"}
+                 ]},
+                {"text": "no ts"}
+            ]
+        })];
+
+        let (merged, stats) = merge_conversations(&[older, newer]);
+        assert_eq!(
+            stats,
+            MergeStats {
+                channels: 2,
+                messages: 4,
+                replies: 2,
+                duplicates: 3,
+                skipped: 1,
+            }
+        );
+
+        let general = &merged[0];
+        assert_eq!(general["channel_name"], "general-renamed");
+        assert_eq!(
+            ts_list(&general["messages"]),
+            ["1705399200.000100", "1705312900.000100", "1705312800.000100"]
+        );
+        assert_eq!(general["messages"][1]["text"], "fixed");
+        let thread = &general["messages"][2];
+        assert_eq!(thread["reply_count"], 2);
+        assert_eq!(
+            ts_list(&thread["thread_replies"]),
+            ["1705312860.000100", "1705312870.000100"]
+        );
+        assert_eq!(merged[1]["channel_id"], "C2");
+    }
+
+    #[test]
+    fn test_merge_keeps_latest_edit_over_input_order() {
+        let edited = vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1.0", "text": "edited", "edited": {"ts": "2.0"}}]
+        })];
+        let stale = vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1.0", "text": "original"}]
+        })];
+
+        let (merged, _) = merge_conversations(&[edited, stale]);
+        assert_eq!(merged[0]["messages"][0]["text"], "edited");
+    }
+
+    #[test]
+    fn test_ts_key_orders_numerically() {
+        assert!(TsKey::new("999.000100") < TsKey::new("1000.000000"));
+        assert!(TsKey::new("1000.000009") < TsKey::new("1000.000010"));
+    }
+}
@@ -0,0 +1,241 @@
+//! A thread belongs to the week of its parent message, however late its
+//! replies come. Archives written before that rule, `live-archive` and
+//! thread broadcasts leave rows in the week they were posted in instead;
+//! `merge_overflow` moves those "overflow" rows to their thread's week, so
+//! each message is stored once and replacing a week loses none of them.
+
+use std::collections::{BTreeMap, HashSet};
+
+use serde_json::{json, Value};
+
+use crate::parquet::{read_threads_rows, ts_partition, write_threads_rows, JsonRow, PartitionBy};
+use crate::provenance::Provenance;
+use crate::storage::Storage;
+use crate::{AppError, Result};
+
+/// What `merge_overflow` changed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OverflowMerge {
+    /// Rows added to their thread's week
+    pub moved: usize,
+    /// Rows dropped because their thread's week already had them
+    pub duplicates: usize,
+    /// Keys of the threads files rewritten, sorted
+    pub keys: Vec<String>,
+}
+
+impl OverflowMerge {
+    pub fn add(&mut self, other: OverflowMerge) {
+        self.moved += other.moved;
+        self.duplicates += other.duplicates;
+        self.keys.extend(other.keys);
+        self.keys.sort();
+        self.keys.dedup();
+    }
+}
+
+fn text<'a>(row: &'a JsonRow, key: &str) -> Option<&'a str> {
+    row.get(key).and_then(Value::as_str)
+}
+
+/// ISO year and week a row belongs to: its thread parent's for a reply, its
+/// own otherwise
+pub(crate) fn owner_week(row: &JsonRow) -> Option<(i32, i32)> {
+    thread_week(text(row, "ts")?, text(row, "thread_ts"))
+}
+
+/// ISO year and week of the thread of the message `ts`, in `thread_ts` when
+/// it is a reply
+pub(crate) fn thread_week(ts: &str, thread_ts: Option<&str>) -> Option<(i32, i32)> {
+    let owner = thread_ts.filter(|thread_ts| *thread_ts != ts).unwrap_or(ts);
+    let (_, year, week) = ts_partition(owner)?;
+    Some((year, week))
+}
+
+/// ISO year and week of a `year=YYYY/week=WW` threads file key
+fn key_week(key: &str) -> Option<(i32, i32)> {
+    if !key.ends_with("/threads.parquet") {
+        return None;
+    }
+    let (mut year, mut week) = (None, None);
+    for part in key.split('/') {
+        if let Some(value) = part.strip_prefix("year=") {
+            year = value.parse().ok();
+        } else if let Some(value) = part.strip_prefix("week=") {
+            week = value.parse().ok();
+        }
+    }
+    Some((year?, week?))
+}
+
+/// Threads files under `prefix` by the ISO week they hold; the Delta log's
+/// `part-*.parquet` copies of them are not included
+pub fn archived_week_keys(storage: &dyn Storage, prefix: &str) -> Result<BTreeMap<(i32, i32), Vec<String>>> {
+    let mut weeks: BTreeMap<(i32, i32), Vec<String>> = BTreeMap::new();
+    for key in storage.list(prefix)?.into_iter().filter(|key| key.ends_with("/threads.parquet")) {
+        if let Some(week) = key_week(&key) {
+            weeks.entry(week).or_default().push(key);
+        }
+    }
+    Ok(weeks)
+}
+
+/// Move the rows of ISO `year`-W`week` under `prefix` that belong to
+/// another week's threads into that week, dropping those it already has.
+/// Rows whose thread's week is not archived stay where they are, to be
+/// merged once it is.
+pub fn merge_overflow(
+    storage: &dyn Storage,
+    prefix: &str,
+    partition_by: PartitionBy,
+    (year, week): (i32, u32),
+) -> Result<OverflowMerge> {
+    let week = (year, i32::try_from(week).map_err(|_| AppError::InvalidDate(format!("Invalid week: {}", week)))?);
+    let archived = archived_week_keys(storage, prefix)?;
+    let mut merge = OverflowMerge::default();
+    let Some(sources) = archived.get(&week) else {
+        return Ok(merge);
+    };
+
+    let mut kept: Vec<(&String, Vec<JsonRow>, Option<Provenance>)> = Vec::new();
+    let mut leaving: BTreeMap<String, Vec<JsonRow>> = BTreeMap::new();
+    for key in sources {
+        let Some((rows, provenance)) = read_threads_rows(storage, key)? else {
+            continue;
+        };
+        let (stay, go): (Vec<JsonRow>, Vec<JsonRow>) = rows.into_iter().partition(|row| {
+            owner_week(row).is_none_or(|owner| owner == week || !archived.contains_key(&owner))
+        });
+        if go.is_empty() {
+            continue;
+        }
+        for mut row in go {
+            let Some((owner_year, owner_week)) = owner_week(&row) else {
+                continue;
+            };
+            row.insert("is_reply".to_string(), json!(true));
+            row.insert("year".to_string(), json!(owner_year));
+            row.insert("week".to_string(), json!(owner_week));
+            let channel_id = text(&row, "channel_id").unwrap_or_default();
+            let target = partition_by.threads_key(prefix, (owner_year, owner_week), channel_id);
+            leaving.entry(target).or_default().push(row);
+        }
+        kept.push((key, stay, provenance));
+    }
+
+    // Rows are added to their week before leaving this one, so a failure
+    // in between leaves a duplicate rather than a gap
+    for (target, rows) in leaving {
+        let (mut target_rows, provenance) = read_threads_rows(storage, &target)?.unwrap_or_default();
+        let mut present: HashSet<(String, String)> = target_rows.iter().map(row_key).collect();
+        for row in rows {
+            if present.insert(row_key(&row)) {
+                target_rows.push(row);
+                merge.moved += 1;
+            } else {
+                merge.duplicates += 1;
+            }
+        }
+        write_threads_rows(storage, &target, &target_rows, provenance.as_ref())?;
+        merge.keys.push(target);
+    }
+    for (key, rows, provenance) in kept {
+        write_threads_rows(storage, key, &rows, provenance.as_ref())?;
+        merge.keys.push(key.clone());
+    }
+    merge.keys.sort();
+    Ok(merge)
+}
+
+/// Channel and ts, which identify a message
+fn row_key(row: &JsonRow) -> (String, String) {
+    let field = |name| text(row, name).unwrap_or_default().to_string();
+    (field("channel_id"), field("ts"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parquet::{read_parquet_as_json, write_conversations_parquet_to};
+    use crate::storage::LocalStorage;
+
+    // 1705312800 is Monday 2024-01-15 (W03), 1705917600 is 2024-01-22 (W04)
+    const W03: &str = "1705312800.000100";
+    const W04: &str = "1705917600.000100";
+
+    fn row(ts: &str, thread_ts: Option<&str>, week: i32) -> JsonRow {
+        json!({
+            "ts": ts, "user": "U1", "text": ts, "channel_id": "C1", "channel_name": "general",
+            "thread_ts": thread_ts, "is_reply": thread_ts.is_some_and(|t| t != ts),
+            "date": "2024-01-22", "year": 2024, "week": week, "blocks": null
+        })
+        .as_object()
+        .unwrap()
+        .clone()
+    }
+
+    fn ts_of(dir: &std::path::Path, key: &str) -> Vec<String> {
+        let mut ts: Vec<String> = read_parquet_as_json(&dir.join(key))
+            .unwrap()
+            .iter()
+            .map(|row| row["ts"].as_str().unwrap().to_string())
+            .collect();
+        ts.sort();
+        ts
+    }
+
+    #[test]
+    fn test_key_week() {
+        assert_eq!(key_week("archive/year=2024/week=03/threads.parquet"), Some((2024, 3)));
+        assert_eq!(key_week("year=2024/week=03/channel=C1/threads.parquet"), Some((2024, 3)));
+        assert_eq!(key_week("year=2024/month=01/threads.parquet"), None);
+        assert_eq!(key_week("users.parquet"), None);
+    }
+
+    #[test]
+    fn test_merge_overflow() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        // The W03 thread as archived now, with a reply posted in W04
+        let thread = json!({"channel_id": "C1", "channel_name": "general", "messages": [{
+            "ts": W03, "thread_ts": W03,
+            "thread_replies": [{"ts": "1705917660.000100", "text": "late"}]
+        }]});
+        write_conversations_parquet_to(&storage, "", &[thread]).unwrap();
+        assert_eq!(ts_of(dir.path(), "year=2024/week=03/threads.parquet").len(), 2);
+        assert!(!storage.exists("year=2024/week=04/threads.parquet").unwrap());
+
+        // W04 as an older version wrote it: the same late reply, another one
+        // and a reply to a thread of a week not archived
+        let rows = vec![
+            row(W04, Some(W04), 4),
+            row("1705917660.000100", Some(W03), 4),
+            row("1705917720.000100", Some(W03), 4),
+            row("1705917780.000100", Some("1705000000.000100"), 4),
+        ];
+        write_threads_rows(&storage, "year=2024/week=04/threads.parquet", &rows, None).unwrap();
+        // The Delta table's copies of both weeks are not read as more rows
+        crate::delta::DeltaTable::new(&storage, "").commit(&[], "CREATE TABLE").unwrap();
+
+        let merge = merge_overflow(&storage, "", PartitionBy::Week, (2024, 4)).unwrap();
+        assert_eq!((merge.moved, merge.duplicates), (1, 1));
+        assert_eq!(
+            merge.keys,
+            vec!["year=2024/week=03/threads.parquet", "year=2024/week=04/threads.parquet"]
+        );
+        assert_eq!(
+            ts_of(dir.path(), "year=2024/week=03/threads.parquet"),
+            vec![W03, "1705917660.000100", "1705917720.000100"]
+        );
+        assert_eq!(
+            ts_of(dir.path(), "year=2024/week=04/threads.parquet"),
+            vec![W04, "1705917780.000100"]
+        );
+        let moved = read_parquet_as_json(&dir.path().join("year=2024/week=03/threads.parquet")).unwrap();
+        assert!(moved.iter().all(|row| row["week"] == 3));
+
+        // Nothing left to move
+        assert_eq!(merge_overflow(&storage, "", PartitionBy::Week, (2024, 4)).unwrap(), OverflowMerge::default());
+        assert_eq!(merge_overflow(&storage, "", PartitionBy::Week, (2024, 9)).unwrap(), OverflowMerge::default());
+    }
+}
@@ -1,20 +1,39 @@
-use std::collections::HashMap;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use arrow::array::{ArrayRef, BooleanArray, Int32Array, Int64Array, StringBuilder};
-use arrow::datatypes::{DataType, Field, Schema};
+use arrow::array::{ArrayRef, AsArray, BooleanArray, Int32Array, Int64Array, StringBuilder};
+use arrow::compute::{concat_batches, filter_record_batch, lexsort_to_indices, take_record_batch, SortColumn};
+use arrow::datatypes::{DataType, Field, Int32Type, Schema};
+use arrow::json::writer::{JsonArray, WriterBuilder};
 use arrow::record_batch::RecordBatch;
+use bytes::Bytes;
 use chrono::{DateTime, Datelike};
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
 use parquet::arrow::ArrowWriter;
+#[cfg(feature = "server")]
+use parquet::arrow::ProjectionMask;
 use parquet::basic::Compression;
 use parquet::file::properties::WriterProperties;
 
+use crate::provenance::Provenance;
+use crate::storage::{join_key, LocalStorage, Storage, StorageWriter};
 use crate::{AppError, Result};
 
 /// Write users data to a parquet file
 pub fn write_users_parquet(path: &Path, users: &[serde_json::Value]) -> Result<()> {
+    let batch = users_batch(users)?;
+    write_parquet_file(path, &batch.schema(), &[batch], None)
+}
+
+/// Users data encoded as an in-memory parquet file
+pub(crate) fn users_parquet_bytes(users: &[serde_json::Value]) -> Result<Vec<u8>> {
+    record_batch_to_parquet_bytes(&users_batch(users)?)
+}
+
+fn users_batch(users: &[serde_json::Value]) -> Result<RecordBatch> {
     let schema = Arc::new(Schema::new(vec![
         Field::new("id", DataType::Utf8, false),
         Field::new("name", DataType::Utf8, true),
@@ -54,8 +73,8 @@ pub fn write_users_parquet(path: &Path, users: &[serde_json::Value]) -> Result<(
         tz_builder.append_option(user.get("tz").and_then(|v| v.as_str()));
     }
 
-    let batch = RecordBatch::try_new(
-        schema.clone(),
+    RecordBatch::try_new(
+        schema,
         vec![
             Arc::new(id_builder.finish()) as ArrayRef,
             Arc::new(name_builder.finish()) as ArrayRef,
@@ -67,13 +86,21 @@ pub fn write_users_parquet(path: &Path, users: &[serde_json::Value]) -> Result<(
             Arc::new(tz_builder.finish()) as ArrayRef,
         ],
     )
-    .map_err(|e| AppError::Parquet(e.to_string()))?;
-
-    write_parquet_file(path, &schema, &[batch])
+    .map_err(|e| AppError::Parquet(e.to_string()))
 }
 
 /// Write channels data to a parquet file
 pub fn write_channels_parquet(path: &Path, channels: &[serde_json::Value]) -> Result<()> {
+    let batch = channels_batch(channels)?;
+    write_parquet_file(path, &batch.schema(), &[batch], None)
+}
+
+/// Channels data encoded as an in-memory parquet file
+pub(crate) fn channels_parquet_bytes(channels: &[serde_json::Value]) -> Result<Vec<u8>> {
+    record_batch_to_parquet_bytes(&channels_batch(channels)?)
+}
+
+fn channels_batch(channels: &[serde_json::Value]) -> Result<RecordBatch> {
     let schema = Arc::new(Schema::new(vec![
         Field::new("id", DataType::Utf8, false),
         Field::new("name", DataType::Utf8, true),
@@ -120,8 +147,8 @@ pub fn write_channels_parquet(path: &Path, channels: &[serde_json::Value]) -> Re
         );
     }
 
-    let batch = RecordBatch::try_new(
-        schema.clone(),
+    RecordBatch::try_new(
+        schema,
         vec![
             Arc::new(id_builder.finish()) as ArrayRef,
             Arc::new(name_builder.finish()) as ArrayRef,
@@ -133,9 +160,7 @@ pub fn write_channels_parquet(path: &Path, channels: &[serde_json::Value]) -> Re
             Arc::new(Int32Array::from(num_members_builder)) as ArrayRef,
         ],
     )
-    .map_err(|e| AppError::Parquet(e.to_string()))?;
-
-    write_parquet_file(path, &schema, &[batch])
+    .map_err(|e| AppError::Parquet(e.to_string()))
 }
 
 /// Flattened message for parquet export
@@ -153,68 +178,288 @@ struct FlatMessage {
     blocks: Option<String>,
 }
 
+/// How the messages of an archive are split into `threads.parquet` files
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PartitionBy {
+    /// `year=YYYY/week=WW/threads.parquet`
+    #[default]
+    Week,
+    /// `year=YYYY/week=WW/channel=ID/threads.parquet`, so reading one
+    /// channel doesn't scan the whole week
+    Channel,
+}
+
+impl std::fmt::Display for PartitionBy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PartitionBy::Week => write!(f, "week"),
+            PartitionBy::Channel => write!(f, "channel"),
+        }
+    }
+}
+
+impl std::str::FromStr for PartitionBy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "week" => Ok(PartitionBy::Week),
+            "channel" => Ok(PartitionBy::Channel),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected week or channel)", s))),
+        }
+    }
+}
+
+impl PartitionBy {
+    /// Key of the threads file a message of `channel_id` posted in ISO
+    /// `year`-W`week` goes to
+    pub fn threads_key(self, prefix: &str, (year, week): (i32, i32), channel_id: &str) -> String {
+        let partition = match self {
+            PartitionBy::Week => format!("year={}/week={:02}/threads.parquet", year, week),
+            PartitionBy::Channel => format!("year={}/week={:02}/channel={}/threads.parquet", year, week, channel_id),
+        };
+        join_key(prefix, &partition)
+    }
+
+    /// Glob of the threads files under an archive's conversations directory
+    #[cfg(feature = "duckdb")]
+    pub fn threads_glob(self) -> &'static str {
+        match self {
+            PartitionBy::Week => "year=*/week=*/threads.parquet",
+            PartitionBy::Channel => "year=*/week=*/channel=*/threads.parquet",
+        }
+    }
+
+    /// Layout of the local archive in `conversations_dir`, by week when empty
+    pub fn of_dir(conversations_dir: &Path) -> Self {
+        Self::detect(&LocalStorage::new(conversations_dir), "")
+            .ok()
+            .flatten()
+            .unwrap_or_default()
+    }
+
+    /// Layout of the archive under `prefix`, `None` while it has no partitions
+    pub fn detect(storage: &dyn Storage, prefix: &str) -> Result<Option<Self>> {
+        let keys = storage.list(prefix)?;
+        let threads = keys.iter().find(|key| key.contains("year=") && key.ends_with("/threads.parquet"));
+        Ok(threads.map(|key| {
+            if key.contains("/channel=") {
+                PartitionBy::Channel
+            } else {
+                PartitionBy::Week
+            }
+        }))
+    }
+
+    /// Layout to write the archive under `prefix` with: the archive's own,
+    /// or `requested` (week by default) for a new one. An archive is never
+    /// mixed, readers expect one layout.
+    pub fn resolve(requested: Option<Self>, storage: &dyn Storage, prefix: &str) -> Result<Self> {
+        match (Self::detect(storage, prefix)?, requested) {
+            (Some(existing), Some(requested)) if existing != requested => Err(AppError::InvalidFormat(format!(
+                "{} is partitioned by {}, not {}",
+                prefix, existing, requested
+            ))),
+            (Some(existing), _) => Ok(existing),
+            (None, requested) => Ok(requested.unwrap_or_default()),
+        }
+    }
+}
+
+/// The threads files of a `year=YYYY/week=WW` partition directory: its
+/// `threads.parquet`, or one per `channel=` directory, sorted; empty when
+/// the week is not archived
+pub fn week_threads_files(week_dir: &Path) -> Vec<PathBuf> {
+    let week_file = week_dir.join("threads.parquet");
+    if week_file.is_file() {
+        return vec![week_file];
+    }
+    let Ok(entries) = fs::read_dir(week_dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_string_lossy().starts_with("channel="))
+        .map(|entry| entry.path().join("threads.parquet"))
+        .filter(|file| file.is_file())
+        .collect();
+    files.sort();
+    files
+}
+
 /// Write conversations data to partitioned parquet files (Hive-style: year=YYYY/week=WW)
 pub fn write_conversations_parquet(
     base_path: &Path,
     conversations: &[serde_json::Value],
 ) -> Result<usize> {
-    // Flatten all messages and group by year/week
-    let mut messages_by_partition: HashMap<(i32, i32), Vec<FlatMessage>> = HashMap::new();
+    write_conversations_parquet_to(&LocalStorage::new(base_path), "", conversations)
+}
 
+/// Write conversations data to `year=YYYY/week=WW/threads.parquet` under
+/// `prefix` in `storage`
+pub fn write_conversations_parquet_to(
+    storage: &dyn Storage,
+    prefix: &str,
+    conversations: &[serde_json::Value],
+) -> Result<usize> {
+    let mut writer = ConversationsParquetWriter::new(storage, prefix);
     for conv in conversations {
-        let channel_id = conv
-            .get("channel_id")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-        let channel_name = conv
-            .get("channel_name")
-            .and_then(|v| v.as_str())
-            .unwrap_or("")
-            .to_string();
-
-        if let Some(messages) = conv.get("messages").and_then(|m| m.as_array()) {
-            for msg in messages {
-                // Process parent message
-                if let Some(flat) =
-                    flatten_message(msg, &channel_id, &channel_name, None, false)
-                {
-                    let key = (flat.year, flat.week);
-                    messages_by_partition.entry(key).or_default().push(flat);
-                }
+        writer.write(conv)?;
+    }
+    writer.finish()
+}
 
-                // Process thread replies
-                if let Some(replies) = msg.get("thread_replies").and_then(|r| r.as_array()) {
-                    let parent_ts = msg.get("ts").and_then(|v| v.as_str()).map(|s| s.to_string());
-                    for reply in replies {
-                        if let Some(flat) =
-                            flatten_message(reply, &channel_id, &channel_name, parent_ts.clone(), true)
-                        {
-                            let key = (flat.year, flat.week);
-                            messages_by_partition.entry(key).or_default().push(flat);
-                        }
-                    }
+/// Writes conversations one at a time to the `year=YYYY/week=WW` partitions
+/// under a prefix, flushing a row group per conversation and week so only the
+/// conversation being written is held in memory. The partitions appear once
+/// `finish` is called; dropping the writer before leaves them untouched.
+pub struct ConversationsParquetWriter<'a> {
+    storage: &'a dyn Storage,
+    prefix: String,
+    partition_by: PartitionBy,
+    provenance: Option<Provenance>,
+    /// Open files by key
+    partitions: HashMap<String, ArrowWriter<Box<dyn StorageWriter + 'a>>>,
+    rows: usize,
+}
+
+impl<'a> ConversationsParquetWriter<'a> {
+    pub fn new(storage: &'a dyn Storage, prefix: &str) -> Self {
+        ConversationsParquetWriter {
+            storage,
+            prefix: prefix.to_string(),
+            partition_by: PartitionBy::Week,
+            provenance: None,
+            partitions: HashMap::new(),
+            rows: 0,
+        }
+    }
+
+    /// Split the partitions further, by week by default
+    pub fn partitioned_by(mut self, partition_by: PartitionBy) -> Self {
+        self.partition_by = partition_by;
+        self
+    }
+
+    /// Record where the export came from in the footer of every file
+    pub fn provenance(mut self, provenance: Provenance) -> Self {
+        self.provenance = Some(provenance);
+        self
+    }
+
+    /// Add a conversation in the shape of `ConversationExport`, returning the
+    /// messages and replies written
+    pub fn write(&mut self, conversation: &serde_json::Value) -> Result<usize> {
+        let mut written = 0;
+        let channel_id = conversation.get("channel_id").and_then(|v| v.as_str()).unwrap_or("");
+        for (partition, messages) in flatten_conversation(conversation) {
+            let key = self.partition_by.threads_key(&self.prefix, partition, channel_id);
+            let writer = match self.partitions.entry(key) {
+                Entry::Occupied(entry) => entry.into_mut(),
+                Entry::Vacant(entry) => {
+                    let props = WriterProperties::builder()
+                        .set_compression(Compression::SNAPPY)
+                        .set_key_value_metadata(self.provenance.as_ref().map(Provenance::to_key_values))
+                        .build();
+                    let writer = ArrowWriter::try_new(self.storage.writer(entry.key())?, messages_schema(), Some(props))
+                        .map_err(|e| AppError::Parquet(e.to_string()))?;
+                    entry.insert(writer)
                 }
-            }
+            };
+            writer
+                .write(&messages_batch(&messages)?)
+                .map_err(|e| AppError::Parquet(e.to_string()))?;
+            writer.flush().map_err(|e| AppError::Parquet(e.to_string()))?;
+            written += messages.len();
         }
+        self.rows += written;
+        Ok(written)
     }
 
-    let mut total_written = 0;
+    /// Keys of the files written so far, sorted
+    pub fn keys(&self) -> Vec<String> {
+        let mut keys: Vec<String> = self.partitions.keys().cloned().collect();
+        keys.sort();
+        keys
+    }
 
-    // Write each partition
-    for ((year, week), messages) in messages_by_partition {
-        let partition_path = base_path.join(format!("year={}/week={:02}", year, week));
-        fs::create_dir_all(&partition_path).map_err(|e| AppError::WriteFile {
-            path: partition_path.display().to_string(),
-            source: e,
-        })?;
+    /// Close every partition, returning the messages and replies written
+    pub fn finish(self) -> Result<usize> {
+        for (_, writer) in self.partitions {
+            writer
+                .into_inner()
+                .map_err(|e| AppError::Parquet(e.to_string()))?
+                .finish()?;
+        }
+        Ok(self.rows)
+    }
+}
 
-        let file_path = partition_path.join("threads.parquet");
-        write_messages_parquet(&file_path, &messages)?;
-        total_written += messages.len();
+/// Messages and thread replies of a conversation grouped by (year, week),
+/// replies in the partition of their parent
+fn flatten_conversation(conv: &serde_json::Value) -> HashMap<(i32, i32), Vec<FlatMessage>> {
+    let mut messages_by_partition: HashMap<(i32, i32), Vec<FlatMessage>> = HashMap::new();
+    let channel_id = conv
+        .get("channel_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+    let channel_name = conv
+        .get("channel_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    if let Some(messages) = conv.get("messages").and_then(|m| m.as_array()) {
+        // Replies the history lists too, such as thread broadcasts, are
+        // written once, with their thread
+        let reply_ts: HashSet<&str> = messages
+            .iter()
+            .filter_map(|msg| msg.get("thread_replies")?.as_array())
+            .flatten()
+            .filter_map(|reply| reply.get("ts")?.as_str())
+            .collect();
+        for msg in messages {
+            if msg.get("ts").and_then(|v| v.as_str()).is_some_and(|ts| reply_ts.contains(ts)) {
+                continue;
+            }
+            // Process parent message
+            let mut parent_partition = None;
+            if let Some(flat) = flatten_message(msg, &channel_id, &channel_name, None, false) {
+                let key = (flat.year, flat.week);
+                parent_partition = Some(key);
+                messages_by_partition.entry(key).or_default().push(flat);
+            }
+
+            // Process thread replies, which belong to their parent's week
+            // however late they were posted
+            if let Some(replies) = msg.get("thread_replies").and_then(|r| r.as_array()) {
+                let parent_ts = msg.get("ts").and_then(|v| v.as_str()).map(|s| s.to_string());
+                for reply in replies {
+                    if let Some(mut flat) =
+                        flatten_message(reply, &channel_id, &channel_name, parent_ts.clone(), true)
+                    {
+                        if let Some((year, week)) = parent_partition {
+                            flat.year = year;
+                            flat.week = week;
+                        }
+                        let key = (flat.year, flat.week);
+                        messages_by_partition.entry(key).or_default().push(flat);
+                    }
+                }
+            }
+        }
     }
+    messages_by_partition
+}
 
-    Ok(total_written)
+/// Date (`YYYY-MM-DD`), ISO year and ISO week of a Slack timestamp, which
+/// pick the `year=/week=` partition a message is written to
+pub(crate) fn ts_partition(ts: &str) -> Option<(String, i32, i32)> {
+    let ts_float: f64 = ts.parse().ok()?;
+    let datetime = DateTime::from_timestamp(ts_float as i64, 0)?.naive_utc();
+    let date = datetime.format("%Y-%m-%d").to_string();
+    Some((date, datetime.iso_week().year(), datetime.iso_week().week() as i32))
 }
 
 fn flatten_message(
@@ -225,13 +470,7 @@ fn flatten_message(
     is_reply: bool,
 ) -> Option<FlatMessage> {
     let ts = msg.get("ts").and_then(|v| v.as_str())?.to_string();
-
-    // Parse timestamp to get date info
-    let ts_float: f64 = ts.parse().ok()?;
-    let datetime = DateTime::from_timestamp(ts_float as i64, 0)?.naive_utc();
-    let date = datetime.format("%Y-%m-%d").to_string();
-    let year = datetime.iso_week().year();
-    let week = datetime.iso_week().week() as i32;
+    let (date, year, week) = ts_partition(&ts)?;
 
     let user = msg.get("user").and_then(|v| v.as_str()).map(|s| s.to_string());
     let text = msg.get("text").and_then(|v| v.as_str()).map(|s| s.to_string());
@@ -261,8 +500,79 @@ fn flatten_message(
     })
 }
 
-fn write_messages_parquet(path: &Path, messages: &[FlatMessage]) -> Result<()> {
-    let schema = Arc::new(Schema::new(vec![
+/// Rewrite a threads parquet file from rows in the shape returned by
+/// [`read_parquet_as_json`]; rows without a `ts` are dropped
+pub(crate) fn write_message_rows(path: &Path, rows: &[JsonRow], provenance: Option<&Provenance>) -> Result<usize> {
+    let batch = message_rows_batch(rows)?;
+    let written = batch.num_rows();
+    write_parquet_file(path, &batch.schema(), &[batch], provenance)?;
+    Ok(written)
+}
+
+/// Threads rows in the shape returned by [`read_parquet_as_json`] as a
+/// record batch; rows without a `ts` are dropped
+fn message_rows_batch(rows: &[JsonRow]) -> Result<RecordBatch> {
+    let text = |row: &JsonRow, key: &str| row.get(key).and_then(|v| v.as_str()).map(str::to_string);
+    let int = |row: &JsonRow, key: &str| row.get(key).and_then(|v| v.as_i64()).unwrap_or(0) as i32;
+    let messages: Vec<FlatMessage> = rows
+        .iter()
+        .filter_map(|row| {
+            Some(FlatMessage {
+                ts: text(row, "ts")?,
+                user: text(row, "user"),
+                text: text(row, "text"),
+                channel_id: text(row, "channel_id").unwrap_or_default(),
+                channel_name: text(row, "channel_name").unwrap_or_default(),
+                thread_ts: text(row, "thread_ts"),
+                is_reply: row.get("is_reply").and_then(|v| v.as_bool()).unwrap_or(false),
+                date: text(row, "date").unwrap_or_default(),
+                year: int(row, "year"),
+                week: int(row, "week"),
+                blocks: text(row, "blocks"),
+            })
+        })
+        .collect();
+    messages_batch(&messages)
+}
+
+/// Rows and provenance of the threads file at `key` in `storage`, `None`
+/// when there is no such file
+pub(crate) fn read_threads_rows(storage: &dyn Storage, key: &str) -> Result<Option<(Vec<JsonRow>, Option<Provenance>)>> {
+    let Some(data) = storage.get(key)? else {
+        return Ok(None);
+    };
+    let builder = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(data))
+        .map_err(|e| AppError::Parquet(format!("{}: {}", key, e)))?;
+    let provenance = builder
+        .metadata()
+        .file_metadata()
+        .key_value_metadata()
+        .and_then(|entries| Provenance::from_key_values(entries));
+    let reader = builder.build().map_err(|e| AppError::Parquet(e.to_string()))?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| AppError::Parquet(e.to_string()))?;
+        rows.extend(record_batch_to_json(&batch)?);
+    }
+    Ok(Some((rows, provenance)))
+}
+
+/// Replace the threads file at `key` in `storage` with `rows`, in the shape
+/// returned by [`read_threads_rows`]
+pub(crate) fn write_threads_rows(
+    storage: &dyn Storage,
+    key: &str,
+    rows: &[JsonRow],
+    provenance: Option<&Provenance>,
+) -> Result<usize> {
+    let batch = message_rows_batch(rows)?;
+    write_threads_batch(storage, key, &batch, batch.num_rows(), provenance)?;
+    Ok(batch.num_rows())
+}
+
+pub(crate) fn messages_schema() -> Arc<Schema> {
+    Arc::new(Schema::new(vec![
         Field::new("ts", DataType::Utf8, false),
         Field::new("user", DataType::Utf8, true),
         Field::new("text", DataType::Utf8, true),
@@ -274,7 +584,11 @@ fn write_messages_parquet(path: &Path, messages: &[FlatMessage]) -> Result<()> {
         Field::new("year", DataType::Int32, false),
         Field::new("week", DataType::Int32, false),
         Field::new("blocks", DataType::Utf8, true),
-    ]));
+    ]))
+}
+
+fn messages_batch(messages: &[FlatMessage]) -> Result<RecordBatch> {
+    let schema = messages_schema();
 
     let mut ts_builder = StringBuilder::new();
     let mut user_builder = StringBuilder::new();
@@ -302,8 +616,8 @@ fn write_messages_parquet(path: &Path, messages: &[FlatMessage]) -> Result<()> {
         blocks_builder.append_option(msg.blocks.as_deref());
     }
 
-    let batch = RecordBatch::try_new(
-        schema.clone(),
+    RecordBatch::try_new(
+        schema,
         vec![
             Arc::new(ts_builder.finish()) as ArrayRef,
             Arc::new(user_builder.finish()) as ArrayRef,
@@ -318,12 +632,15 @@ fn write_messages_parquet(path: &Path, messages: &[FlatMessage]) -> Result<()> {
             Arc::new(blocks_builder.finish()) as ArrayRef,
         ],
     )
-    .map_err(|e| AppError::Parquet(e.to_string()))?;
-
-    write_parquet_file(path, &schema, &[batch])
+    .map_err(|e| AppError::Parquet(e.to_string()))
 }
 
-fn write_parquet_file(path: &Path, schema: &Arc<Schema>, batches: &[RecordBatch]) -> Result<()> {
+fn write_parquet_file(
+    path: &Path,
+    schema: &Arc<Schema>,
+    batches: &[RecordBatch],
+    provenance: Option<&Provenance>,
+) -> Result<()> {
     let file = File::create(path).map_err(|e| AppError::WriteFile {
         path: path.display().to_string(),
         source: e,
@@ -331,6 +648,7 @@ fn write_parquet_file(path: &Path, schema: &Arc<Schema>, batches: &[RecordBatch]
 
     let props = WriterProperties::builder()
         .set_compression(Compression::SNAPPY)
+        .set_key_value_metadata(provenance.map(Provenance::to_key_values))
         .build();
 
     let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(props))
@@ -349,6 +667,305 @@ fn write_parquet_file(path: &Path, schema: &Arc<Schema>, batches: &[RecordBatch]
     Ok(())
 }
 
+/// A parquet row as a JSON object keyed by column name
+pub type JsonRow = serde_json::Map<String, serde_json::Value>;
+
+/// Read all rows of a parquet file as JSON objects (nulls are kept as `null`)
+pub fn read_parquet_as_json(path: &Path) -> Result<Vec<JsonRow>> {
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    let reader = ParquetRecordBatchReaderBuilder::try_new(file)
+        .and_then(|builder| builder.build())
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+
+    let mut rows = Vec::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| AppError::Parquet(e.to_string()))?;
+        rows.extend(record_batch_to_json(&batch)?);
+    }
+
+    Ok(rows)
+}
+
+/// Users read back from `users.parquet` as users.list objects, with the
+/// fields the file keeps
+pub fn read_users_parquet(path: &Path) -> Result<Vec<serde_json::Value>> {
+    let rows = read_parquet_as_json(path)?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let mut user = present_fields(row, &["id", "name", "real_name", "is_bot", "is_admin", "tz"]);
+            let profile = present_fields(row, &["display_name", "real_name", "email"]);
+            user.insert("profile".to_string(), serde_json::Value::Object(profile));
+            serde_json::Value::Object(user)
+        })
+        .collect())
+}
+
+/// Channels read back from `channels.parquet` as conversations.list
+/// objects, with the fields the file keeps
+pub fn read_channels_parquet(path: &Path) -> Result<Vec<serde_json::Value>> {
+    let rows = read_parquet_as_json(path)?;
+    Ok(rows
+        .iter()
+        .map(|row| {
+            let mut channel = present_fields(row, &["id", "name", "is_private", "is_archived", "created", "num_members"]);
+            for key in ["topic", "purpose"] {
+                if let Some(value) = row.get(key).filter(|v| !v.is_null()) {
+                    channel.insert(key.to_string(), serde_json::json!({ "value": value }));
+                }
+            }
+            serde_json::Value::Object(channel)
+        })
+        .collect())
+}
+
+/// The non-null `keys` of a row
+fn present_fields(row: &JsonRow, keys: &[&str]) -> JsonRow {
+    keys.iter()
+        .filter_map(|key| Some((key.to_string(), row.get(*key).filter(|v| !v.is_null())?.clone())))
+        .collect()
+}
+
+/// Selection of messages from a threads parquet file
+#[derive(Debug, Clone, Default)]
+pub struct MessagePage {
+    /// Only messages in this channel (ID or name)
+    pub channel: Option<String>,
+    /// Number of matching messages to skip
+    pub offset: usize,
+    /// Maximum number of messages to return (default: all)
+    pub limit: Option<usize>,
+}
+
+impl MessagePage {
+    /// True when the page selects the whole file
+    pub fn is_full(&self) -> bool {
+        self.channel.is_none() && self.offset == 0 && self.limit.is_none()
+    }
+}
+
+/// A page of messages and the number of messages matching the channel filter
+#[derive(Debug)]
+pub struct MessagePageBatch {
+    pub batch: RecordBatch,
+    /// Only read by the server's paginated endpoints
+    #[cfg_attr(not(feature = "server"), allow(dead_code))]
+    pub total: usize,
+}
+
+/// Read the messages selected by `page` from the threads files of a week,
+/// in order, as if they were one file
+pub fn read_messages_pages(paths: &[PathBuf], page: &MessagePage) -> Result<MessagePageBatch> {
+    let mut schema = messages_schema();
+    let mut batches = Vec::new();
+    for path in paths {
+        let file = File::open(path).map_err(|e| AppError::ReadFile {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+            .map_err(|e| AppError::Parquet(e.to_string()))?;
+        schema = builder.schema().clone();
+        let reader = builder
+            .build()
+            .map_err(|e| AppError::Parquet(e.to_string()))?;
+
+        for batch in reader {
+            let batch = batch.map_err(|e| AppError::Parquet(e.to_string()))?;
+            let batch = match &page.channel {
+                Some(channel) => filter_by_channel(&batch, channel)?,
+                None => batch,
+            };
+            batches.push(batch);
+        }
+    }
+
+    let all = concat_batches(&schema, &batches).map_err(|e| AppError::Parquet(e.to_string()))?;
+    let total = all.num_rows();
+    let offset = page.offset.min(total);
+    let len = page.limit.unwrap_or(total).min(total - offset);
+
+    Ok(MessagePageBatch {
+        batch: all.slice(offset, len),
+        total,
+    })
+}
+
+/// Order threads rows by channel, then ts, so readers filtering on a
+/// channel skip most row groups
+pub(crate) fn sort_by_channel_ts(batch: &RecordBatch) -> Result<RecordBatch> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .cloned()
+            .ok_or_else(|| AppError::UnknownField(name.to_string()))
+    };
+    let columns = [column("channel_id")?, column("ts")?].map(|values| SortColumn { values, options: None });
+    let indices = lexsort_to_indices(&columns, None).map_err(|e| AppError::Parquet(e.to_string()))?;
+    take_record_batch(batch, &indices).map_err(|e| AppError::Parquet(e.to_string()))
+}
+
+/// Write threads rows to `key` in `storage` in row groups of at most
+/// `row_group_size` rows, replacing the file once complete
+pub(crate) fn write_threads_batch(
+    storage: &dyn Storage,
+    key: &str,
+    batch: &RecordBatch,
+    row_group_size: usize,
+    provenance: Option<&Provenance>,
+) -> Result<()> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .set_max_row_group_size(row_group_size.max(1))
+        .set_key_value_metadata(provenance.map(Provenance::to_key_values))
+        .build();
+    let mut writer = ArrowWriter::try_new(storage.writer(key)?, batch.schema(), Some(props))
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+    writer.write(batch).map_err(|e| AppError::Parquet(e.to_string()))?;
+    writer
+        .into_inner()
+        .map_err(|e| AppError::Parquet(e.to_string()))?
+        .finish()
+}
+
+/// Message counts of a threads parquet file
+#[cfg(feature = "server")]
+#[derive(Debug, Clone, Default)]
+pub struct MessageCounts {
+    /// Total rows, from the file metadata
+    pub total: usize,
+    /// Rows per `channel_name`
+    pub by_channel: std::collections::BTreeMap<String, usize>,
+}
+
+/// Count messages in a threads parquet file, reading only the `channel_name` column
+#[cfg(feature = "server")]
+pub fn count_messages_by_channel(path: &Path) -> Result<MessageCounts> {
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+
+    let builder = ParquetRecordBatchReaderBuilder::try_new(file)
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+    let total = usize::try_from(builder.metadata().file_metadata().num_rows()).unwrap_or(0);
+    let mask = ProjectionMask::columns(builder.parquet_schema(), ["channel_name"]);
+    let reader = builder
+        .with_projection(mask)
+        .build()
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+
+    let mut by_channel = std::collections::BTreeMap::new();
+    for batch in reader {
+        let batch = batch.map_err(|e| AppError::Parquet(e.to_string()))?;
+        let names = batch
+            .column_by_name("channel_name")
+            .and_then(|c| c.as_string_opt::<i32>())
+            .ok_or_else(|| AppError::UnknownField("channel_name".to_string()))?;
+        for name in names.iter() {
+            *by_channel.entry(name.unwrap_or_default().to_string()).or_insert(0) += 1;
+        }
+    }
+
+    Ok(MessageCounts { total, by_channel })
+}
+
+/// Keep rows whose `channel_id` or `channel_name` equals `channel`
+fn filter_by_channel(batch: &RecordBatch, channel: &str) -> Result<RecordBatch> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .and_then(|c| c.as_string_opt::<i32>())
+            .ok_or_else(|| AppError::UnknownField(name.to_string()))
+    };
+    let ids = column("channel_id")?;
+    let names = column("channel_name")?;
+
+    let mask: BooleanArray = ids
+        .iter()
+        .zip(names.iter())
+        .map(|(id, name)| Some(id == Some(channel) || name == Some(channel)))
+        .collect();
+
+    filter_record_batch(batch, &mask).map_err(|e| AppError::Parquet(e.to_string()))
+}
+
+/// Keep rows whose `date` (`YYYY-MM-DD`) starts with `prefix`. A reply
+/// stored in its parent's week goes by its parent's date, so threads are
+/// never split.
+pub(crate) fn filter_by_date_prefix(batch: &RecordBatch, prefix: &str) -> Result<RecordBatch> {
+    let column = |name: &str| {
+        batch
+            .column_by_name(name)
+            .ok_or_else(|| AppError::UnknownField(name.to_string()))
+    };
+    let strings = |name: &str| {
+        column(name)?
+            .as_string_opt::<i32>()
+            .ok_or_else(|| AppError::UnknownField(name.to_string()))
+    };
+    let ints = |name: &str| {
+        column(name)?
+            .as_primitive_opt::<Int32Type>()
+            .ok_or_else(|| AppError::UnknownField(name.to_string()))
+    };
+    let dates = strings("date")?;
+    let thread_ts = strings("thread_ts")?;
+    let is_reply = column("is_reply")?
+        .as_boolean_opt()
+        .ok_or_else(|| AppError::UnknownField("is_reply".to_string()))?;
+    let weeks = ints("year")?.iter().zip(ints("week")?.iter());
+
+    let mask: BooleanArray = dates
+        .iter()
+        .zip(thread_ts.iter().zip(is_reply.iter()))
+        .zip(weeks)
+        .map(|((date, (thread_ts, is_reply)), (year, week))| {
+            let parent = thread_ts
+                .filter(|_| is_reply == Some(true))
+                .and_then(ts_partition)
+                .filter(|(_, y, w)| (Some(*y), Some(*w)) == (year, week));
+            let date = parent.as_ref().map(|(date, _, _)| date.as_str()).or(date);
+            Some(date.is_some_and(|d| d.starts_with(prefix)))
+        })
+        .collect();
+    filter_record_batch(batch, &mask).map_err(|e| AppError::Parquet(e.to_string()))
+}
+
+/// Encode a record batch as an in-memory parquet file
+pub fn record_batch_to_parquet_bytes(batch: &RecordBatch) -> Result<Vec<u8>> {
+    let props = WriterProperties::builder()
+        .set_compression(Compression::SNAPPY)
+        .build();
+
+    let mut writer = ArrowWriter::try_new(Vec::new(), batch.schema(), Some(props))
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+    writer
+        .write(batch)
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+    writer
+        .into_inner()
+        .map_err(|e| AppError::Parquet(e.to_string()))
+}
+
+/// Convert a record batch to JSON objects (nulls are kept as `null`)
+pub fn record_batch_to_json(batch: &RecordBatch) -> Result<Vec<JsonRow>> {
+    let mut writer = WriterBuilder::new()
+        .with_explicit_nulls(true)
+        .build::<_, JsonArray>(Vec::new());
+    writer
+        .write(batch)
+        .and_then(|_| writer.finish())
+        .map_err(|e| AppError::Parquet(e.to_string()))?;
+
+    serde_json::from_slice(&writer.into_inner()).map_err(|e| AppError::JsonParse(e.to_string()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -387,6 +1004,13 @@ mod tests {
         assert!(result.is_ok());
         assert!(path.exists());
         assert!(fs::metadata(&path).unwrap().len() > 0);
+
+        let read = read_users_parquet(&path).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0]["id"], "U123");
+        assert_eq!(read[0]["profile"]["display_name"], "Test");
+        assert_eq!(read[0]["profile"]["email"], "test@example.com");
+        assert_eq!(read[0]["is_admin"], true);
     }
 
     #[test]
@@ -418,6 +1042,9 @@ mod tests {
         let result = write_channels_parquet(&path, &channels);
         assert!(result.is_ok());
         assert!(path.exists());
+
+        // Every field the file keeps reads back as exported
+        assert_eq!(read_channels_parquet(&path).unwrap(), channels);
     }
 
     #[test]
@@ -491,6 +1118,28 @@ mod tests {
         assert_eq!(result.unwrap(), 2); // Parent + reply
     }
 
+    #[test]
+    fn test_replies_written_with_parent_week() {
+        let dir = tempdir().unwrap();
+        // 1705312800 is in 2024-W03, 1705917600 in W04; the late reply was
+        // also broadcast to the channel
+        let conversations = vec![serde_json::json!({
+            "channel_id": "C123",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "1705917600.000000", "thread_ts": "1705312800.000000", "text": "Late reply"},
+                {"ts": "1705312800.000000", "thread_ts": "1705312800.000000", "text": "Parent",
+                 "thread_replies": [{"ts": "1705917600.000000", "text": "Late reply"}]}
+            ]
+        })];
+
+        assert_eq!(write_conversations_parquet(dir.path(), &conversations).unwrap(), 2);
+        assert!(!dir.path().join("year=2024/week=04").exists());
+        let rows = read_parquet_as_json(&dir.path().join("year=2024/week=03/threads.parquet")).unwrap();
+        let reply = rows.iter().find(|row| row["is_reply"] == true).unwrap();
+        assert_eq!((&reply["date"], &reply["week"]), (&serde_json::json!("2024-01-22"), &serde_json::json!(3)));
+    }
+
     #[test]
     fn test_flatten_message() {
         let msg = serde_json::json!({
@@ -546,4 +1195,135 @@ mod tests {
         let result = flatten_message(&msg, "C123", "general", None, false);
         assert!(result.is_none());
     }
+
+    #[test]
+    fn test_read_parquet_as_json_roundtrip() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("channels.parquet");
+        let channels = vec![serde_json::json!({
+            "id": "C123",
+            "name": "general",
+            "is_private": false,
+            "num_members": 42
+        })];
+        write_channels_parquet(&path, &channels).unwrap();
+
+        let rows = read_parquet_as_json(&path).unwrap();
+
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0]["id"], "C123");
+        assert_eq!(rows[0]["name"], "general");
+        assert_eq!(rows[0]["num_members"], 42);
+        assert!(rows[0]["topic"].is_null());
+    }
+
+    #[test]
+    fn test_read_parquet_as_json_missing_file() {
+        let dir = tempdir().unwrap();
+        let result = read_parquet_as_json(&dir.path().join("missing.parquet"));
+        assert!(matches!(result, Err(AppError::ReadFile { .. })));
+    }
+
+    #[test]
+    #[cfg(feature = "server")]
+    fn test_count_messages_by_channel() {
+        let dir = tempdir().unwrap();
+        let conversations = vec![
+            serde_json::json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1705312800.000000", "text": "a", "thread_replies": [
+                        {"ts": "1705312900.000000", "text": "b"}
+                    ]}
+                ]
+            }),
+            serde_json::json!({
+                "channel_id": "C2",
+                "channel_name": "random",
+                "messages": [{"ts": "1705313000.000000", "text": "c"}]
+            }),
+        ];
+        write_conversations_parquet(dir.path(), &conversations).unwrap();
+
+        let counts =
+            count_messages_by_channel(&dir.path().join("year=2024/week=03/threads.parquet")).unwrap();
+        assert_eq!(counts.total, 3);
+        assert_eq!(counts.by_channel.get("general"), Some(&2));
+        assert_eq!(counts.by_channel.get("random"), Some(&1));
+    }
+
+    #[test]
+    fn test_conversations_parquet_writer_row_group_per_channel() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let channel = |id: &str, ts: &str| {
+            serde_json::json!({"channel_id": id, "channel_name": id, "messages": [{"ts": ts, "text": "hi"}]})
+        };
+
+        let mut writer = ConversationsParquetWriter::new(&storage, "threads");
+        assert_eq!(writer.write(&channel("C1", "1705312800.000000")).unwrap(), 1);
+        assert_eq!(writer.write(&channel("C2", "1705313000.000000")).unwrap(), 1);
+        assert_eq!(writer.write(&channel("C3", "1705917600.000000")).unwrap(), 1);
+        assert!(!storage.exists("threads/year=2024/week=03/threads.parquet").unwrap());
+        assert_eq!(writer.finish().unwrap(), 3);
+
+        let path = dir.path().join("threads/year=2024/week=03/threads.parquet");
+        let file = File::open(&path).unwrap();
+        let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+        assert_eq!(builder.metadata().num_row_groups(), 2);
+        assert_eq!(builder.metadata().file_metadata().num_rows(), 2);
+        assert_eq!(
+            storage.list("threads").unwrap(),
+            vec![
+                "threads/year=2024/week=03/threads.parquet",
+                "threads/year=2024/week=04/threads.parquet"
+            ]
+        );
+
+        let mut dropped = ConversationsParquetWriter::new(&storage, "dropped");
+        dropped.write(&channel("C1", "1705312800.000000")).unwrap();
+        drop(dropped);
+        assert!(storage.list("dropped").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_conversations_partitioned_by_channel() {
+        let dir = tempdir().unwrap();
+        let storage = LocalStorage::new(dir.path());
+        let channel = |id: &str, ts: &str| {
+            serde_json::json!({"channel_id": id, "channel_name": id.to_lowercase(), "messages": [{"ts": ts, "text": "hi"}]})
+        };
+        assert_eq!(PartitionBy::detect(&storage, "threads").unwrap(), None);
+
+        let mut writer = ConversationsParquetWriter::new(&storage, "threads").partitioned_by(PartitionBy::Channel);
+        writer.write(&channel("C1", "1705312800.000000")).unwrap();
+        writer.write(&channel("C2", "1705313000.000000")).unwrap();
+        writer.write(&channel("C2", "1705917600.000000")).unwrap();
+        assert_eq!(writer.finish().unwrap(), 3);
+        assert_eq!(
+            storage.list("threads").unwrap(),
+            vec![
+                "threads/year=2024/week=03/channel=C1/threads.parquet",
+                "threads/year=2024/week=03/channel=C2/threads.parquet",
+                "threads/year=2024/week=04/channel=C2/threads.parquet"
+            ]
+        );
+
+        assert_eq!(PartitionBy::detect(&storage, "threads").unwrap(), Some(PartitionBy::Channel));
+        assert_eq!(PartitionBy::resolve(None, &storage, "threads").unwrap(), PartitionBy::Channel);
+        assert!(PartitionBy::resolve(Some(PartitionBy::Week), &storage, "threads").is_err());
+        assert_eq!(PartitionBy::resolve(Some(PartitionBy::Channel), &storage, "new").unwrap(), PartitionBy::Channel);
+
+        let files = week_threads_files(&dir.path().join("threads/year=2024/week=03"));
+        assert_eq!(files.len(), 2);
+        let all = read_messages_pages(&files, &MessagePage::default()).unwrap();
+        assert_eq!(all.total, 2);
+        let page = MessagePage {
+            channel: Some("c2".to_string()),
+            ..MessagePage::default()
+        };
+        assert_eq!(read_messages_pages(&files, &page).unwrap().total, 1);
+        assert!(week_threads_files(&dir.path().join("threads/year=2024/week=05")).is_empty());
+    }
 }
@@ -0,0 +1,260 @@
+//! Full pipeline: export conversations, then download their attachments,
+//! render markdown, build the search index and import it into Meilisearch,
+//! one step after the other
+
+use std::collections::HashSet;
+use std::path::Path;
+
+use chrono::NaiveDate;
+
+use crate::formatter::MarkdownExportOptions;
+use crate::index::{export_conversations_to_index_with_progress, IndexFormat};
+use crate::markdown::export_conversations_to_markdown_with_options;
+use crate::meilisearch::import_index_to_meilisearch;
+use crate::slack::DownloadOptions;
+use crate::{slack, AppError, OutputFormat, Result, SlackApiCallbacks};
+
+/// Meilisearch server the pipeline imports the index into
+#[derive(Debug, Clone)]
+pub struct MeilisearchTarget {
+    pub url: String,
+    pub api_key: String,
+    pub index_name: String,
+}
+
+/// What the pipeline exports and where; optional targets are skipped when `None`
+#[derive(Debug, Clone)]
+pub struct PipelineOptions {
+    pub from_date: NaiveDate,
+    pub to_date: NaiveDate,
+    /// Channel IDs to export, all channels when `None`
+    pub selected_channels: Option<HashSet<String>>,
+    pub conversations_path: String,
+    pub users_path: String,
+    pub channels_path: String,
+    pub attachments_dir: Option<String>,
+    pub download_options: DownloadOptions,
+    pub markdown_path: Option<String>,
+    pub index_path: Option<String>,
+    /// Imports the index written to `index_path`
+    pub meilisearch: Option<MeilisearchTarget>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PipelineStep {
+    ExportConversations,
+    DownloadAttachments,
+    ExportMarkdown,
+    ExportIndex,
+    ImportMeilisearch,
+}
+
+impl PipelineStep {
+    pub fn label(&self) -> &'static str {
+        match self {
+            PipelineStep::ExportConversations => "Export conversations",
+            PipelineStep::DownloadAttachments => "Download attachments",
+            PipelineStep::ExportMarkdown => "Export markdown",
+            PipelineStep::ExportIndex => "Export index",
+            PipelineStep::ImportMeilisearch => "Import to Meilisearch",
+        }
+    }
+}
+
+impl PipelineOptions {
+    /// Steps to run, in order
+    pub fn steps(&self) -> Result<Vec<PipelineStep>> {
+        if self.meilisearch.is_some() && self.index_path.is_none() {
+            return Err(AppError::InvalidConfig(
+                "Meilisearch import needs the index step".to_string(),
+            ));
+        }
+
+        let mut steps = vec![PipelineStep::ExportConversations];
+        if self.attachments_dir.is_some() {
+            steps.push(PipelineStep::DownloadAttachments);
+        }
+        if self.markdown_path.is_some() {
+            steps.push(PipelineStep::ExportMarkdown);
+        }
+        if self.index_path.is_some() {
+            steps.push(PipelineStep::ExportIndex);
+        }
+        if self.meilisearch.is_some() {
+            steps.push(PipelineStep::ImportMeilisearch);
+        }
+        Ok(steps)
+    }
+}
+
+/// Run every step of the pipeline, returning one summary line per step.
+///
+/// Progress messages are prefixed with `[step/total] Step:`; cancellation is
+/// checked between steps and inside the Slack steps. The Slack and
+/// Meilisearch steps run on `runtime`; must not be called from one of its
+/// threads, since it blocks on them.
+pub fn run_pipeline(
+    token: &str,
+    options: &PipelineOptions,
+    rt: &tokio::runtime::Handle,
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<Vec<String>> {
+    let steps = options.steps()?;
+    let total = steps.len();
+    let mut summary = Vec::new();
+
+    for (idx, step) in steps.iter().enumerate() {
+        if callbacks.is_cancelled() {
+            return Err(AppError::Cancelled(format!(
+                "{} of {} steps done: {}",
+                idx,
+                total,
+                summary.join("; ")
+            )));
+        }
+
+        let prefix = format!("[{}/{}] {}", idx + 1, total, step.label());
+        callbacks.log(&prefix);
+        let progress = |current: usize, count: usize, msg: &str| {
+            callbacks.report_progress(current, count, &format!("{}: {}", prefix, msg));
+        };
+        progress(0, 0, "starting...");
+
+        let line = match step {
+            PipelineStep::ExportConversations => {
+                let step_callbacks = callbacks.with_status_prefix(&prefix);
+                let count = rt.block_on(slack::export_conversations(
+                    token,
+                    options.from_date,
+                    options.to_date,
+                    Path::new(&options.conversations_path),
+                    options.selected_channels.as_ref(),
+                    step_callbacks,
+                    OutputFormat::Json,
+                ))?;
+                format!("{} messages to {}", count, options.conversations_path)
+            }
+            PipelineStep::DownloadAttachments => {
+                let dir = options.attachments_dir.as_deref().unwrap_or_default();
+                let step_callbacks = callbacks.with_status_prefix(&prefix);
+                let result = rt.block_on(slack::download_attachments(
+                    token,
+                    &options.conversations_path,
+                    Path::new(dir),
+                    None,
+                    &options.download_options,
+                    step_callbacks,
+                ))?;
+                format!(
+                    "{} files to {} ({} skipped, {} over budget, {} failed)",
+                    result.downloaded, dir, result.skipped, result.over_budget, result.failed
+                )
+            }
+            PipelineStep::ExportMarkdown => {
+                let path = options.markdown_path.as_deref().unwrap_or_default();
+                let (count, _) = export_conversations_to_markdown_with_options(
+                    &options.conversations_path,
+                    &options.users_path,
+                    &options.channels_path,
+                    path,
+                    Some(&progress),
+                    &MarkdownExportOptions::new(),
+                )?;
+                format!("{} messages to {}", count, path)
+            }
+            PipelineStep::ExportIndex => {
+                let path = options.index_path.as_deref().unwrap_or_default();
+                let count = export_conversations_to_index_with_progress(
+                    &options.conversations_path,
+                    &options.users_path,
+                    &options.channels_path,
+                    path,
+                    IndexFormat::from_output_path(path),
+                    Some(&progress),
+                )?;
+                format!("{} entries to {}", count, path)
+            }
+            PipelineStep::ImportMeilisearch => {
+                let path = options.index_path.as_deref().unwrap_or_default();
+                let target = options.meilisearch.as_ref().ok_or_else(|| {
+                    AppError::InvalidConfig("no Meilisearch target".to_string())
+                })?;
+                let result = rt.block_on(import_index_to_meilisearch(
+                    path,
+                    &target.url,
+                    &target.api_key,
+                    &target.index_name,
+                    false,
+                    Some(&progress),
+                ))?;
+                format!("{} documents into {}", result.total, result.index_name)
+            }
+        };
+
+        callbacks.log(&format!("{}: {}", prefix, line));
+        summary.push(format!("{}: {}", step.label(), line));
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> PipelineOptions {
+        PipelineOptions {
+            from_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            to_date: NaiveDate::from_ymd_opt(2024, 1, 7).unwrap(),
+            selected_channels: None,
+            conversations_path: "conversations.json".to_string(),
+            users_path: "users.json".to_string(),
+            channels_path: "channels.json".to_string(),
+            attachments_dir: None,
+            download_options: DownloadOptions::default(),
+            markdown_path: None,
+            index_path: None,
+            meilisearch: None,
+        }
+    }
+
+    #[test]
+    fn test_pipeline_steps() {
+        assert_eq!(options().steps().unwrap(), vec![PipelineStep::ExportConversations]);
+
+        let all = PipelineOptions {
+            attachments_dir: Some("attachments".to_string()),
+            markdown_path: Some("conversations.md".to_string()),
+            index_path: Some("conversation-index.json".to_string()),
+            meilisearch: Some(MeilisearchTarget {
+                url: "http://localhost:7700".to_string(),
+                api_key: String::new(),
+                index_name: "slack".to_string(),
+            }),
+            ..options()
+        };
+        assert_eq!(
+            all.steps().unwrap(),
+            vec![
+                PipelineStep::ExportConversations,
+                PipelineStep::DownloadAttachments,
+                PipelineStep::ExportMarkdown,
+                PipelineStep::ExportIndex,
+                PipelineStep::ImportMeilisearch,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_pipeline_meilisearch_requires_index() {
+        let options = PipelineOptions {
+            meilisearch: Some(MeilisearchTarget {
+                url: "http://localhost:7700".to_string(),
+                api_key: String::new(),
+                index_name: "slack".to_string(),
+            }),
+            ..options()
+        };
+        assert!(matches!(options.steps(), Err(AppError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,127 @@
+//! Post messages to a channel, e.g. to announce that an archive run finished
+//! or to share a generated digest
+
+use slack_morphism::prelude::*;
+
+use crate::error::{AppError, Result};
+use crate::slack::{create_slack_client, with_rate_limit_retry};
+use crate::SlackApiCallbacks;
+
+/// Characters Slack accepts in the markdown blocks of one message
+pub const MARKDOWN_BLOCK_LIMIT: usize = 12_000;
+
+/// What to post
+#[derive(Debug, Clone, PartialEq)]
+pub enum PostContent {
+    /// Slack mrkdwn text
+    Text(String),
+    /// Standard markdown, sent as markdown blocks; longer documents are split
+    /// at paragraphs and the rest posted as replies in the first message's
+    /// thread
+    Markdown(String),
+}
+
+/// Split `markdown` into pieces of at most `limit` characters, at blank
+/// lines when possible, then at line ends, then anywhere
+pub fn markdown_chunks(markdown: &str, limit: usize) -> Vec<String> {
+    let mut chunks: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut push = |current: &mut String, piece: &str, separator: &str| {
+        if !current.is_empty() && current.chars().count() + separator.len() + piece.chars().count() > limit {
+            chunks.push(std::mem::take(current));
+        }
+        if !current.is_empty() {
+            current.push_str(separator);
+        }
+        current.push_str(piece);
+    };
+
+    for paragraph in markdown.trim().split("\n\n") {
+        if paragraph.chars().count() <= limit {
+            push(&mut current, paragraph, "\n\n");
+            continue;
+        }
+        let mut separator = "\n\n";
+        for line in paragraph.lines() {
+            let chars: Vec<char> = line.chars().collect();
+            for piece in chars.chunks(limit.max(1)) {
+                push(&mut current, &piece.iter().collect::<String>(), separator);
+                separator = "\n";
+            }
+        }
+    }
+    if !current.is_empty() {
+        chunks.push(current);
+    }
+    chunks
+}
+
+/// Notification text for a markdown message: its first line without
+/// heading markers
+fn fallback_text(markdown: &str) -> String {
+    let first = markdown.lines().find(|l| !l.trim().is_empty()).unwrap_or_default();
+    first.trim_start_matches('#').trim().to_string()
+}
+
+/// Post `content` to `channel` (an ID, or a name the token's bot can post
+/// to), optionally as a reply in the thread of `thread_ts`. Returns the ts of
+/// each message posted.
+pub async fn post_message(token: &str, channel: &str, content: &PostContent, thread_ts: Option<&str>) -> Result<Vec<String>> {
+    let messages: Vec<SlackMessageContent> = match content {
+        PostContent::Text(text) => vec![SlackMessageContent::new().with_text(text.clone())],
+        PostContent::Markdown(markdown) => markdown_chunks(markdown, MARKDOWN_BLOCK_LIMIT)
+            .into_iter()
+            .map(|chunk| {
+                SlackMessageContent::new()
+                    .with_text(fallback_text(&chunk))
+                    .with_blocks(vec![SlackMarkdownBlock::new(chunk).into()])
+            })
+            .collect(),
+    };
+    if messages.is_empty() {
+        return Err(AppError::InvalidConfig("nothing to post".to_string()));
+    }
+
+    let (client, token_obj) = create_slack_client(token)?;
+    let session = client.open_session(&token_obj);
+    let mut posted = Vec::new();
+    let mut thread = thread_ts.map(|ts| SlackTs(ts.to_string()));
+    for content in messages {
+        let request = SlackApiChatPostMessageRequest::new(SlackChannelId(channel.to_string()), content)
+            .opt_thread_ts(thread.clone());
+        let response = with_rate_limit_retry(|| session.chat_post_message(&request), SlackApiCallbacks::new()).await?;
+        // Continuation chunks go into the first message's thread
+        thread.get_or_insert_with(|| response.ts.clone());
+        posted.push(response.ts.0);
+    }
+    Ok(posted)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_markdown_chunks() {
+        let markdown = "# Weekly digest\n\nfirst paragraph\n\nsecond paragraph\n";
+        assert_eq!(markdown_chunks(markdown, 100), vec![markdown.trim()]);
+        assert_eq!(
+            markdown_chunks(markdown, 35),
+            vec!["# Weekly digest\n\nfirst paragraph", "second paragraph"]
+        );
+    }
+
+    #[test]
+    fn test_markdown_chunks_long_paragraph() {
+        let markdown = "- one\n- two\n- three";
+        assert_eq!(markdown_chunks(markdown, 12), vec!["- one\n- two", "- three"]);
+        assert_eq!(markdown_chunks("abcdefgh", 3), vec!["abc", "def", "gh"]);
+        assert_eq!(markdown_chunks("intro\n\n- one\n- two\n- three", 15), vec!["intro\n\n- one", "- two\n- three"]);
+        assert!(markdown_chunks("  \n", 10).is_empty());
+    }
+
+    #[test]
+    fn test_fallback_text() {
+        assert_eq!(fallback_text("\n## Archive done\n\nbody"), "Archive done");
+    }
+}
@@ -0,0 +1,219 @@
+//! Progress bars of the CLI's Slack tasks on a terminal: the channels done,
+//! the current channel's threads and the downloads, drawn with indicatif on
+//! stderr. `--quiet` hides them, along with the progress lines printed when
+//! stderr is not a terminal.
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+
+use crate::{ProgressEvent, TransferProgress};
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+/// Hide the progress of CLI commands, keeping their start and end lines
+pub fn set_quiet_progress() {
+    QUIET.store(true, Ordering::Relaxed);
+}
+
+pub fn quiet_progress() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+const SPINNER_TICK: Duration = Duration::from_millis(120);
+
+fn style(template: &str) -> ProgressStyle {
+    ProgressStyle::with_template(template)
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("=> ")
+}
+
+/// The bars, each added the first time an event needs it
+pub(crate) struct ProgressBars {
+    multi: MultiProgress,
+    status: Option<ProgressBar>,
+    channels: Option<ProgressBar>,
+    threads: Option<ProgressBar>,
+    transfer: Option<ProgressBar>,
+    /// Messages fetched in the current channel
+    channel_messages: usize,
+}
+
+impl ProgressBars {
+    pub(crate) fn stderr() -> Self {
+        Self::with_draw_target(ProgressDrawTarget::stderr())
+    }
+
+    fn with_draw_target(target: ProgressDrawTarget) -> Self {
+        ProgressBars {
+            multi: MultiProgress::with_draw_target(target),
+            status: None,
+            channels: None,
+            threads: None,
+            transfer: None,
+            channel_messages: 0,
+        }
+    }
+
+    fn bar(multi: &MultiProgress, slot: &mut Option<ProgressBar>, template: &str) -> ProgressBar {
+        slot.get_or_insert_with(|| multi.add(ProgressBar::new(0).with_style(style(template))))
+            .clone()
+    }
+
+    pub(crate) fn handle(&mut self, event: ProgressEvent) {
+        match event {
+            ProgressEvent::Status { current, total, message } => {
+                let status = Self::bar(
+                    &self.multi,
+                    &mut self.status,
+                    "{spinner} {prefix}{msg}",
+                );
+                if total > 0 {
+                    status.set_prefix(format!("[{}/{}] ", current, total));
+                } else {
+                    status.set_prefix("");
+                }
+                status.set_message(message);
+                status.enable_steady_tick(SPINNER_TICK);
+            }
+            ProgressEvent::ChannelStarted { channel, index, total } => {
+                let channels = Self::bar(
+                    &self.multi,
+                    &mut self.channels,
+                    "channels [{bar:30}] {pos}/{len} {msg}",
+                );
+                channels.set_length(total as u64);
+                channels.set_position(index as u64);
+                channels.set_message(format!("#{}", channel));
+                self.channel_messages = 0;
+                if let Some(threads) = &self.threads {
+                    threads.reset();
+                    threads.set_length(0);
+                }
+            }
+            ProgressEvent::PageFetched { channel, page, messages } => {
+                self.channel_messages += messages;
+                if let Some(channels) = &self.channels {
+                    channels.set_message(format!(
+                        "#{}: {} pages, {} messages",
+                        channel, page, self.channel_messages
+                    ));
+                }
+            }
+            ProgressEvent::ThreadFetched { done, total, .. } => {
+                let threads = Self::bar(
+                    &self.multi,
+                    &mut self.threads,
+                    "threads  [{bar:30}] {pos}/{len}",
+                );
+                threads.set_length(total as u64);
+                threads.set_position(done as u64);
+            }
+            ProgressEvent::ChannelsFetched { total } => {
+                if let Some(channels) = &self.channels {
+                    channels.set_position(total as u64);
+                    channels.set_message("done");
+                }
+            }
+            ProgressEvent::BytesDownloaded(progress) => self.transfer(&progress),
+            ProgressEvent::RateLimited {
+                wait_secs,
+                attempt,
+                max_attempts,
+            } => {
+                let _ = self.multi.println(format!(
+                    "  Rate limited, waiting {}s (attempt {}/{})",
+                    wait_secs, attempt, max_attempts
+                ));
+            }
+            ProgressEvent::Log(_) => {}
+        }
+    }
+
+    fn transfer(&mut self, progress: &TransferProgress) {
+        let transfer = Self::bar(
+            &self.multi,
+            &mut self.transfer,
+            "files    [{bar:30}] {pos}/{len} {msg}",
+        );
+        transfer.set_length(progress.files_total as u64);
+        transfer.set_position(progress.files_done as u64);
+        transfer.set_message(progress.summary());
+    }
+
+    /// Leave the channel and download bars drawn with their final counts and
+    /// clear the rest
+    pub(crate) fn finish(self) {
+        for bar in [self.status, self.threads].into_iter().flatten() {
+            bar.finish_and_clear();
+        }
+        for bar in [self.channels, self.transfer].into_iter().flatten() {
+            bar.abandon();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_progress_bars_follow_events() {
+        let mut bars = ProgressBars::with_draw_target(ProgressDrawTarget::hidden());
+        bars.handle(ProgressEvent::ChannelStarted {
+            channel: "general".to_string(),
+            index: 1,
+            total: 3,
+        });
+        bars.handle(ProgressEvent::PageFetched {
+            channel: "general".to_string(),
+            page: 1,
+            messages: 200,
+        });
+        bars.handle(ProgressEvent::PageFetched {
+            channel: "general".to_string(),
+            page: 2,
+            messages: 50,
+        });
+        bars.handle(ProgressEvent::ThreadFetched {
+            channel: "general".to_string(),
+            done: 4,
+            total: 10,
+            replies: 12,
+        });
+
+        let channels = bars.channels.clone().unwrap();
+        assert_eq!((channels.position(), channels.length()), (1, Some(3)));
+        assert_eq!(channels.message(), "#general: 2 pages, 250 messages");
+        let threads = bars.threads.clone().unwrap();
+        assert_eq!((threads.position(), threads.length()), (4, Some(10)));
+
+        // A new channel starts its threads from zero
+        bars.handle(ProgressEvent::ChannelStarted {
+            channel: "random".to_string(),
+            index: 2,
+            total: 3,
+        });
+        assert_eq!((threads.position(), threads.length()), (0, Some(0)));
+        bars.handle(ProgressEvent::ChannelsFetched { total: 3 });
+        assert_eq!(channels.position(), 3);
+        assert!(bars.transfer.is_none() && bars.status.is_none());
+    }
+
+    #[test]
+    fn test_progress_bars_transfer() {
+        let mut bars = ProgressBars::with_draw_target(ProgressDrawTarget::hidden());
+        bars.handle(ProgressEvent::BytesDownloaded(TransferProgress::new(
+            2,
+            5,
+            2048,
+            0,
+            Duration::from_secs(1),
+        )));
+        let transfer = bars.transfer.clone().unwrap();
+        assert_eq!((transfer.position(), transfer.length()), (2, Some(5)));
+        assert!(transfer.message().starts_with(&crate::format_bytes(2048)));
+        bars.finish();
+    }
+}
@@ -0,0 +1,305 @@
+//! Where a parquet file came from, kept as key/value metadata in its footer
+//! so an archive describes itself: the slack-utils version that wrote it,
+//! when, the dates and channels exported and the workspace of the token.
+//! `archive-info` reads it back.
+
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use chrono::{NaiveDate, SecondsFormat, Utc};
+use parquet::file::metadata::KeyValue;
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::error::{AppError, Result};
+
+/// Prefix of the footer keys slack-utils writes, apart from the ones other
+/// tools (such as arrow's `ARROW:schema`) add
+const KEY_PREFIX: &str = "slack_utils.";
+
+/// How a parquet file was produced
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Provenance {
+    pub tool_version: String,
+    /// RFC 3339, UTC
+    pub exported_at: String,
+    /// Exported dates, `YYYY-MM-DD`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub from: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<String>,
+    /// Channel IDs the export was limited to; `None` for every channel
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub channels: Option<Vec<String>>,
+    /// Workspace of the token, as `auth.test` reports it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub team_id: Option<String>,
+}
+
+impl Provenance {
+    /// Provenance of a file written now by this build
+    pub fn now() -> Self {
+        Provenance {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_range(mut self, (from, to): (NaiveDate, NaiveDate)) -> Self {
+        self.from = Some(from.to_string());
+        self.to = Some(to.to_string());
+        self
+    }
+
+    pub fn with_channels<'a>(mut self, channels: Option<impl IntoIterator<Item = &'a String>>) -> Self {
+        self.channels = channels.map(|ids| {
+            let mut ids: Vec<String> = ids.into_iter().cloned().collect();
+            ids.sort();
+            ids
+        });
+        self
+    }
+
+    pub fn with_team_id(mut self, team_id: Option<String>) -> Self {
+        self.team_id = team_id;
+        self
+    }
+
+    /// Provenance of a file merged from `sources` by this build: their
+    /// dates, channels and workspace, and the latest export time. `None`
+    /// when no source has any.
+    pub fn merged(sources: &[ParquetFileInfo]) -> Option<Self> {
+        let known: Vec<&Provenance> = sources.iter().filter_map(|file| file.provenance.as_ref()).collect();
+        if known.is_empty() {
+            return None;
+        }
+        let info = ArchiveInfo::from_files(sources.to_vec());
+        // A source of every channel makes the merged file one too
+        let channels = known
+            .iter()
+            .map(|provenance| provenance.channels.clone())
+            .collect::<Option<Vec<Vec<String>>>>()
+            .map(|lists| lists.into_iter().flatten().collect::<BTreeSet<String>>().into_iter().collect());
+        let mut team_ids = info.team_ids.into_iter();
+        let team_id = match (team_ids.next(), team_ids.next()) {
+            (Some(team_id), None) => Some(team_id),
+            _ => None,
+        };
+        Some(Provenance {
+            exported_at: info.last_exported_at.unwrap_or_default(),
+            from: info.from,
+            to: info.to,
+            channels,
+            team_id,
+            ..Provenance::now()
+        })
+    }
+
+    /// Footer entries for `WriterProperties::set_key_value_metadata`
+    pub fn to_key_values(&self) -> Vec<KeyValue> {
+        let entry = |key: &str, value: String| KeyValue::new(format!("{}{}", KEY_PREFIX, key), value);
+        let mut entries = vec![
+            entry("tool_version", self.tool_version.clone()),
+            entry("exported_at", self.exported_at.clone()),
+        ];
+        entries.extend(self.from.clone().map(|from| entry("from", from)));
+        entries.extend(self.to.clone().map(|to| entry("to", to)));
+        entries.extend(self.channels.as_ref().map(|ids| entry("channels", ids.join(","))));
+        entries.extend(self.team_id.clone().map(|team_id| entry("team_id", team_id)));
+        entries
+    }
+
+    /// Read the entries back; `None` when the file has none of them, as with
+    /// files written by other tools or before provenance was recorded
+    pub fn from_key_values(entries: &[KeyValue]) -> Option<Self> {
+        let mut provenance = Provenance::default();
+        let mut found = false;
+        for entry in entries {
+            let (Some(key), Some(value)) = (entry.key.strip_prefix(KEY_PREFIX), entry.value.clone()) else {
+                continue;
+            };
+            found = true;
+            match key {
+                "tool_version" => provenance.tool_version = value,
+                "exported_at" => provenance.exported_at = value,
+                "from" => provenance.from = Some(value),
+                "to" => provenance.to = Some(value),
+                "channels" => {
+                    provenance.channels = Some(value.split(',').filter(|id| !id.is_empty()).map(str::to_string).collect())
+                }
+                "team_id" => provenance.team_id = Some(value),
+                _ => {}
+            }
+        }
+        found.then_some(provenance)
+    }
+}
+
+impl std::fmt::Display for Provenance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "slack-utils {}, exported {}", self.tool_version, self.exported_at)?;
+        if let (Some(from), Some(to)) = (&self.from, &self.to) {
+            write!(f, ", {} to {}", from, to)?;
+        }
+        if let Some(channels) = &self.channels {
+            write!(f, ", channels {}", channels.join(","))?;
+        }
+        if let Some(team_id) = &self.team_id {
+            write!(f, ", workspace {}", team_id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A parquet file with its row count and provenance
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParquetFileInfo {
+    pub path: String,
+    pub rows: i64,
+    pub provenance: Option<Provenance>,
+}
+
+/// Row count and provenance of a parquet file, from its footer alone
+pub fn read_file_info(path: &Path) -> Result<ParquetFileInfo> {
+    let file = File::open(path).map_err(|e| AppError::ReadFile {
+        path: path.display().to_string(),
+        source: e,
+    })?;
+    let reader = SerializedFileReader::new(file)
+        .map_err(|e| AppError::Parquet(format!("{}: {}", path.display(), e)))?;
+    let metadata = reader.metadata().file_metadata();
+    Ok(ParquetFileInfo {
+        path: path.display().to_string(),
+        rows: metadata.num_rows(),
+        provenance: metadata.key_value_metadata().and_then(|entries| Provenance::from_key_values(entries)),
+    })
+}
+
+/// What `archive-info` reports for a parquet file or a directory of them
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ArchiveInfo {
+    pub files: Vec<ParquetFileInfo>,
+    pub rows: i64,
+    /// Earliest `from` and latest `to` across the files
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub tool_versions: BTreeSet<String>,
+    pub team_ids: BTreeSet<String>,
+    /// Earliest and latest export times
+    pub first_exported_at: Option<String>,
+    pub last_exported_at: Option<String>,
+    /// Files written without provenance
+    pub unknown: usize,
+}
+
+impl ArchiveInfo {
+    pub fn from_files(files: Vec<ParquetFileInfo>) -> Self {
+        let mut info = ArchiveInfo::default();
+        for file in &files {
+            info.rows += file.rows;
+            let Some(provenance) = &file.provenance else {
+                info.unknown += 1;
+                continue;
+            };
+            info.from = earliest(info.from.take(), provenance.from.clone());
+            info.to = info.to.take().max(provenance.to.clone());
+            info.tool_versions.insert(provenance.tool_version.clone());
+            info.team_ids.extend(provenance.team_id.clone());
+            let exported_at = Some(provenance.exported_at.clone());
+            info.first_exported_at = earliest(info.first_exported_at.take(), exported_at.clone());
+            info.last_exported_at = info.last_exported_at.take().max(exported_at);
+        }
+        info.files = files;
+        info
+    }
+}
+
+/// The lesser of two values, ignoring a missing one
+fn earliest(a: Option<String>, b: Option<String>) -> Option<String> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Read the footers of `path`, a parquet file or a directory searched for
+/// them
+pub fn archive_info(path: &Path) -> Result<ArchiveInfo> {
+    let mut paths: Vec<PathBuf> = if path.is_dir() {
+        WalkDir::new(path)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|e| e == "parquet"))
+            // Delta table copies of the threads files beside them
+            .filter(|entry| !entry.file_name().to_string_lossy().starts_with("part-"))
+            .map(|entry| entry.into_path())
+            .collect()
+    } else {
+        vec![path.to_path_buf()]
+    };
+    paths.sort();
+    let files = paths.iter().map(|path| read_file_info(path)).collect::<Result<_>>()?;
+    Ok(ArchiveInfo::from_files(files))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_key_values_round_trip() {
+        let selected = ["C2".to_string(), "C1".to_string()];
+        let provenance = Provenance::now()
+            .with_range((date("2024-01-01"), date("2024-01-07")))
+            .with_channels(Some(&selected))
+            .with_team_id(Some("T1".to_string()));
+        let entries = provenance.to_key_values();
+        assert!(entries.iter().all(|e| e.key.starts_with(KEY_PREFIX)));
+        let read = Provenance::from_key_values(&entries).unwrap();
+        assert_eq!(read, provenance);
+        assert_eq!(read.channels, Some(vec!["C1".to_string(), "C2".to_string()]));
+
+        // Entries of other tools are not provenance
+        assert_eq!(Provenance::from_key_values(&[KeyValue::new("ARROW:schema".to_string(), "x".to_string())]), None);
+    }
+
+    #[test]
+    fn test_archive_info() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let storage = crate::storage::LocalStorage::new(dir.path());
+        let conversation = |ts: &str| json!({"channel_id": "C1", "channel_name": "general", "messages": [{"ts": ts, "text": "hi"}]});
+
+        let mut writer = crate::parquet::ConversationsParquetWriter::new(&storage, "")
+            .provenance(Provenance::now().with_range((date("2024-01-01"), date("2024-01-07"))).with_team_id(Some("T1".to_string())));
+        writer.write(&conversation("1704196800.000000")).unwrap();
+        writer.finish().unwrap();
+        let mut writer = crate::parquet::ConversationsParquetWriter::new(&storage, "")
+            .provenance(Provenance::now().with_range((date("2024-01-08"), date("2024-01-14"))).with_team_id(Some("T1".to_string())));
+        writer.write(&conversation("1704801600.000000")).unwrap();
+        writer.finish().unwrap();
+        // Written without provenance
+        crate::parquet::write_users_parquet(&dir.path().join("users.parquet"), &[json!({"id": "U1"})]).unwrap();
+
+        let info = archive_info(dir.path()).unwrap();
+        assert_eq!(info.files.len(), 3);
+        assert_eq!(info.rows, 3);
+        assert_eq!(info.unknown, 1);
+        assert_eq!(info.from.as_deref(), Some("2024-01-01"));
+        assert_eq!(info.to.as_deref(), Some("2024-01-14"));
+        assert_eq!(info.team_ids, BTreeSet::from(["T1".to_string()]));
+        assert_eq!(info.tool_versions, BTreeSet::from([env!("CARGO_PKG_VERSION").to_string()]));
+
+        let week = archive_info(&dir.path().join("year=2024/week=01/threads.parquet")).unwrap();
+        assert_eq!(week.files.len(), 1);
+        assert_eq!(week.to.as_deref(), Some("2024-01-07"));
+        assert!(archive_info(&dir.path().join("missing.parquet")).is_err());
+    }
+}
@@ -0,0 +1,105 @@
+//! At-rest encryption of the secret fields of `settings.toml`. With
+//! `SLACK_SETTINGS_PASSPHRASE` set, secrets are written as
+//! `enc:v1:<base64>`: a random salt and nonce followed by the AES-256-GCM
+//! ciphertext, keyed by PBKDF2-HMAC-SHA256 of the passphrase.
+
+use std::num::NonZeroU32;
+
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+
+use crate::{AppError, Result};
+
+/// Environment variable with the passphrase secrets are encrypted with
+pub const PASSPHRASE_ENV: &str = "SLACK_SETTINGS_PASSPHRASE";
+
+/// Marks an encrypted value and the version of its format
+const PREFIX: &str = "enc:v1:";
+
+const SALT_LEN: usize = 16;
+
+const KEY_LEN: usize = 32;
+
+const PBKDF2_ITERATIONS: u32 = 210_000;
+
+/// The passphrase from `SLACK_SETTINGS_PASSPHRASE`, `None` when unset or empty
+pub fn passphrase() -> Option<String> {
+    std::env::var(PASSPHRASE_ENV).ok().filter(|p| !p.is_empty())
+}
+
+/// Whether `value` was written by `encrypt`
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+fn key(passphrase: &str, salt: &[u8]) -> Result<LessSafeKey> {
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).ok_or_else(|| AppError::Secret("no iterations".to_string()))?;
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    let key = UnboundKey::new(&AES_256_GCM, &key).map_err(|_| AppError::Secret("invalid key".to_string()))?;
+    Ok(LessSafeKey::new(key))
+}
+
+/// Encrypt `plain` with `passphrase`, with a new salt and nonce each time
+pub fn encrypt(plain: &str, passphrase: &str) -> Result<String> {
+    let rng = SystemRandom::new();
+    let mut header = [0u8; SALT_LEN + NONCE_LEN];
+    rng.fill(&mut header)
+        .map_err(|_| AppError::Secret("no random numbers available".to_string()))?;
+    let (salt, nonce) = header.split_at(SALT_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| AppError::Secret("invalid nonce".to_string()))?;
+
+    let mut sealed = plain.as_bytes().to_vec();
+    key(passphrase, salt)?
+        .seal_in_place_append_tag(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| AppError::Secret("encryption failed".to_string()))?;
+
+    let mut data = header.to_vec();
+    data.extend(sealed);
+    Ok(format!("{}{}", PREFIX, STANDARD.encode(data)))
+}
+
+/// Decrypt a value written by `encrypt`; a wrong passphrase or a modified
+/// value fail the same way
+pub fn decrypt(value: &str, passphrase: &str) -> Result<String> {
+    let encoded = value
+        .strip_prefix(PREFIX)
+        .ok_or_else(|| AppError::Secret("value is not encrypted".to_string()))?;
+    let data = STANDARD
+        .decode(encoded)
+        .map_err(|e| AppError::Secret(format!("invalid encrypted value: {}", e)))?;
+    if data.len() < SALT_LEN + NONCE_LEN {
+        return Err(AppError::Secret("encrypted value is truncated".to_string()));
+    }
+    let (header, sealed) = data.split_at(SALT_LEN + NONCE_LEN);
+    let (salt, nonce) = header.split_at(SALT_LEN);
+    let nonce = Nonce::try_assume_unique_for_key(nonce).map_err(|_| AppError::Secret("invalid nonce".to_string()))?;
+
+    let mut sealed = sealed.to_vec();
+    let plain = key(passphrase, salt)?
+        .open_in_place(nonce, Aad::empty(), &mut sealed)
+        .map_err(|_| AppError::Secret(format!("wrong {} or corrupted value", PASSPHRASE_ENV)))?;
+    String::from_utf8(plain.to_vec()).map_err(|e| AppError::Secret(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encrypt_roundtrip() {
+        let encrypted = encrypt("masterKey", "correct horse").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("masterKey"));
+        assert_ne!(encrypted, encrypt("masterKey", "correct horse").unwrap());
+        assert_eq!(decrypt(&encrypted, "correct horse").unwrap(), "masterKey");
+
+        assert!(decrypt(&encrypted, "battery staple").is_err());
+        assert!(decrypt(&encrypted[..PREFIX.len() + 8], "correct horse").is_err());
+        assert!(decrypt("masterKey", "correct horse").is_err());
+        assert!(!is_encrypted("masterKey"));
+    }
+}
@@ -1,12 +1,32 @@
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::Path;
+#[cfg(feature = "tui")]
+use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use crate::attachments::{BudgetMode, DedupMode};
+use crate::secrets;
+#[cfg(feature = "tui")]
+use crate::slack::DownloadOptions;
 use crate::{AppError, Result};
 
 const SETTINGS_FILE: &str = "settings.toml";
 
+/// Table and key of each secret field, encrypted on save when
+/// `SLACK_SETTINGS_PASSPHRASE` is set
+const SECRET_FIELDS: &[(&str, &str)] = &[("meilisearch", "api-key")];
+
+/// Environment variable naming the profile whose channel selections the TUI
+/// uses, e.g. one per workspace token
+#[cfg(feature = "tui")]
+const PROFILE_ENV: &str = "SLACK_PROFILE";
+
+/// Recent values kept per history key
+#[cfg(feature = "tui")]
+const HISTORY_LEN: usize = 10;
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct Settings {
     #[serde(default)]
@@ -33,12 +53,35 @@ pub struct Settings {
     pub meilisearch: MeilisearchSettings,
     #[serde(default, rename = "md-to-html")]
     pub md_to_html: MdToHtmlSettings,
+    #[serde(default)]
+    pub tantivy: TantivySettings,
+    #[serde(default, rename = "search-local")]
+    pub search_local: SearchLocalSettings,
+    /// Recent values of TUI text fields, newest first, by field kind
+    #[serde(default)]
+    pub history: BTreeMap<String, Vec<String>>,
+    /// Channel selections of each named profile; `ui` holds the ones used
+    /// without a profile
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, UiSettings>,
+    /// Encrypted secrets that could not be decrypted, by `table.key`; the
+    /// fields stay empty and the values are written back as they were
+    #[serde(skip)]
+    pub locked_secrets: BTreeMap<String, String>,
+    /// Profile in use, from `SLACK_PROFILE` when the TUI starts
+    #[cfg(feature = "tui")]
+    #[serde(skip)]
+    pub profile: Option<String>,
 }
 
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct UiSettings {
+    /// Channels of the last export, preselected in the next one
     #[serde(default, rename = "selected-channels")]
     pub selected_channels: Vec<String>,
+    /// Selections saved under a name, to load in the channel picker
+    #[serde(default, rename = "channel-selections", skip_serializing_if = "BTreeMap::is_empty")]
+    pub channel_selections: BTreeMap<String, Vec<String>>,
 }
 
 /// Generic settings for operations that only need an output path.
@@ -66,6 +109,44 @@ pub struct DownloadAttachmentsSettings {
     pub conversations_path: String,
     #[serde(default, rename = "output-path")]
     pub output_path: String,
+    /// Files downloaded at the same time, 4 when unset
+    #[serde(default)]
+    pub concurrency: Option<usize>,
+    /// Minimum milliseconds between requests to the same host, 100 when unset
+    #[serde(default, rename = "host-interval-ms")]
+    pub host_interval_ms: Option<u64>,
+    /// Extra attempts for a failed file, 3 when unset
+    #[serde(default)]
+    pub retries: Option<u32>,
+    /// keep, hardlink or remove files whose content was already downloaded
+    #[serde(default)]
+    pub dedup: DedupMode,
+    /// Most megabytes to download in one run, no limit when unset
+    #[serde(default, rename = "max-total-size-mb")]
+    pub max_total_size_mb: Option<u64>,
+    /// stop or skip-largest when the files add up to more than the budget
+    #[serde(default, rename = "over-budget")]
+    pub over_budget: BudgetMode,
+}
+
+#[cfg(feature = "tui")]
+impl DownloadAttachmentsSettings {
+    /// Download options from these settings, defaults for what is unset
+    pub fn download_options(&self) -> DownloadOptions {
+        let defaults = DownloadOptions::default();
+        DownloadOptions {
+            concurrency: self.concurrency.unwrap_or(defaults.concurrency),
+            host_interval: self
+                .host_interval_ms
+                .map(Duration::from_millis)
+                .unwrap_or(defaults.host_interval),
+            retries: self.retries.unwrap_or(defaults.retries),
+            dedup: self.dedup,
+            max_total_size: self.max_total_size_mb.map(|mb| mb.saturating_mul(1024 * 1024)),
+            over_budget: self.over_budget,
+            ..defaults
+        }
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -128,6 +209,18 @@ pub struct MeilisearchSettings {
     pub index_name: String,
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TantivySettings {
+    #[serde(default, rename = "index-dir")]
+    pub index_dir: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SearchLocalSettings {
+    #[serde(default, rename = "index-path")]
+    pub index_path: String,
+}
+
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MdToHtmlSettings {
     #[serde(default, rename = "input-path")]
@@ -140,37 +233,194 @@ pub struct MdToHtmlSettings {
 
 impl Settings {
     pub fn load() -> Result<Self> {
-        let path = Path::new(SETTINGS_FILE);
+        Self::load_from(Path::new(SETTINGS_FILE), secrets::passphrase().as_deref())
+    }
+
+    /// Settings in `path`, decrypting the secret fields with `passphrase`.
+    /// Without it, or with the wrong one, they are left locked.
+    fn load_from(path: &Path, passphrase: Option<&str>) -> Result<Self> {
         if !path.exists() {
             return Ok(Self::default());
         }
 
         let content = fs::read_to_string(path).map_err(|e| AppError::ReadFile {
-            path: SETTINGS_FILE.to_string(),
+            path: path.display().to_string(),
             source: e,
         })?;
 
-        toml::from_str(&content).map_err(|e| AppError::TomlParse(e.to_string()))
+        let mut table: toml::Table = toml::from_str(&content).map_err(|e| AppError::TomlParse(e.to_string()))?;
+        let mut locked = BTreeMap::new();
+        for (field, value) in secret_values(&mut table) {
+            let Some(encrypted) = value.as_str().filter(|v| secrets::is_encrypted(v)) else {
+                continue;
+            };
+            let plain = match passphrase.map(|p| secrets::decrypt(encrypted, p)) {
+                Some(Ok(plain)) => Some(plain),
+                Some(Err(e)) => {
+                    tracing::warn!(field, error = %e, "settings secret left encrypted");
+                    None
+                }
+                None => None,
+            };
+            if plain.is_none() {
+                locked.insert(field, encrypted.to_string());
+            }
+            *value = toml::Value::String(plain.unwrap_or_default());
+        }
+
+        let mut settings: Self = table.try_into().map_err(|e: toml::de::Error| AppError::TomlParse(e.to_string()))?;
+        settings.locked_secrets = locked;
+        Ok(settings)
     }
 
     pub fn save(&self) -> Result<()> {
-        let content = toml::to_string_pretty(self).map_err(|e| AppError::TomlSerialize(e.to_string()))?;
-        fs::write(SETTINGS_FILE, content).map_err(|e| AppError::WriteFile {
-            path: SETTINGS_FILE.to_string(),
+        self.save_to(Path::new(SETTINGS_FILE), secrets::passphrase().as_deref())
+    }
+
+    /// Write the settings to `path`, encrypting the secret fields when there
+    /// is a `passphrase`. Locked secrets nobody replaced are kept as they were.
+    fn save_to(&self, path: &Path, passphrase: Option<&str>) -> Result<()> {
+        let mut table = toml::Table::try_from(self).map_err(|e| AppError::TomlSerialize(e.to_string()))?;
+        for (field, value) in secret_values(&mut table) {
+            let plain = value.as_str().unwrap_or_default();
+            if plain.is_empty() {
+                if let Some(encrypted) = self.locked_secrets.get(&field) {
+                    *value = toml::Value::String(encrypted.clone());
+                }
+            } else if let Some(passphrase) = passphrase
+                && !secrets::is_encrypted(plain)
+            {
+                *value = toml::Value::String(secrets::encrypt(plain, passphrase)?);
+            }
+        }
+
+        let content = toml::to_string_pretty(&table).map_err(|e| AppError::TomlSerialize(e.to_string()))?;
+        fs::write(path, content).map_err(|e| AppError::WriteFile {
+            path: path.display().to_string(),
             source: e,
         })?;
         Ok(())
     }
+
+    /// Rewrite the settings file with its secrets encrypted, or in plain
+    /// text when `decrypt`, returning how many secrets were set
+    pub fn convert_secrets(decrypt: bool) -> Result<usize> {
+        let passphrase = secrets::passphrase().ok_or_else(|| {
+            AppError::Secret(format!("set {} to the passphrase", secrets::PASSPHRASE_ENV))
+        })?;
+        let path = Path::new(SETTINGS_FILE);
+        let settings = Self::load_from(path, Some(&passphrase))?;
+        if let Some(field) = settings.locked_secrets.keys().next() {
+            return Err(AppError::Secret(format!("{} could not be decrypted with {}", field, secrets::PASSPHRASE_ENV)));
+        }
+        settings.save_to(path, (!decrypt).then_some(passphrase.as_str()))?;
+
+        let mut table = toml::Table::try_from(&settings).map_err(|e| AppError::TomlSerialize(e.to_string()))?;
+        Ok(secret_values(&mut table)
+            .iter()
+            .filter(|(_, value)| value.as_str().is_some_and(|v| !v.is_empty()))
+            .count())
+    }
+}
+
+/// The secret fields present in `table`, by `table.key`
+fn secret_values(table: &mut toml::Table) -> Vec<(String, &mut toml::Value)> {
+    let mut values = Vec::new();
+    for (name, inner) in table.iter_mut() {
+        let Some(inner) = inner.as_table_mut() else {
+            continue;
+        };
+        for (key, value) in inner.iter_mut() {
+            if SECRET_FIELDS.contains(&(name.as_str(), key.as_str())) {
+                values.push((format!("{}.{}", name, key), value));
+            }
+        }
+    }
+    values
 }
 
 #[cfg(feature = "tui")]
 impl Settings {
+    /// `load` with the profile named by `SLACK_PROFILE`
+    pub fn load_for_profile() -> Result<Self> {
+        let mut settings = Self::load()?;
+        settings.profile = std::env::var(PROFILE_ENV).ok().filter(|p| !p.trim().is_empty());
+        Ok(settings)
+    }
+
+    /// Channel selections of the profile in use
+    fn profile_ui(&self) -> Option<&UiSettings> {
+        match &self.profile {
+            Some(profile) => self.profiles.get(profile),
+            None => Some(&self.ui),
+        }
+    }
+
+    fn profile_ui_mut(&mut self) -> &mut UiSettings {
+        match &self.profile {
+            Some(profile) => self.profiles.entry(profile.clone()).or_default(),
+            None => &mut self.ui,
+        }
+    }
+
     pub fn selected_channels_set(&self) -> std::collections::HashSet<String> {
-        self.ui.selected_channels.iter().cloned().collect()
+        self.profile_ui()
+            .map(|ui| ui.selected_channels.iter().cloned().collect())
+            .unwrap_or_default()
     }
 
     pub fn set_selected_channels(&mut self, channels: Vec<String>) {
-        self.ui.selected_channels = channels;
+        self.profile_ui_mut().selected_channels = channels;
+    }
+
+    /// Names of the profile's saved selections with their channel counts,
+    /// sorted by name
+    pub fn channel_selections(&self) -> Vec<(String, usize)> {
+        self.profile_ui()
+            .map(|ui| ui.channel_selections.iter().map(|(name, ids)| (name.clone(), ids.len())).collect())
+            .unwrap_or_default()
+    }
+
+    /// Channels of the profile's selection saved as `name`
+    pub fn channel_selection(&self, name: &str) -> Option<std::collections::HashSet<String>> {
+        let ids = self.profile_ui()?.channel_selections.get(name)?;
+        Some(ids.iter().cloned().collect())
+    }
+
+    /// Save `channels` as `name` in the profile, replacing a selection of
+    /// the same name
+    pub fn save_channel_selection(&mut self, name: &str, mut channels: Vec<String>) {
+        channels.sort();
+        self.profile_ui_mut().channel_selections.insert(name.trim().to_string(), channels);
+    }
+
+    /// Forget the profile's selection saved as `name`
+    pub fn remove_channel_selection(&mut self, name: &str) {
+        if let Some(profile) = &self.profile {
+            if let Some(ui) = self.profiles.get_mut(profile) {
+                ui.channel_selections.remove(name);
+            }
+        } else {
+            self.ui.channel_selections.remove(name);
+        }
+    }
+
+    /// Recent values for `key`, newest first
+    pub fn history(&self, key: &str) -> &[String] {
+        self.history.get(key).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Make `value` the newest entry for `key`, dropping older duplicates
+    /// and anything past the last `HISTORY_LEN` values
+    pub fn remember(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            return;
+        }
+        let values = self.history.entry(key.to_string()).or_default();
+        values.retain(|v| v != value);
+        values.insert(0, value.to_string());
+        values.truncate(HISTORY_LEN);
     }
 }
 
@@ -178,6 +428,86 @@ impl Settings {
 mod tests {
     use super::*;
 
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_remember_history() {
+        let mut settings = Settings::default();
+        settings.remember("output-path", "a.json");
+        settings.remember("output-path", "b.json");
+        settings.remember("output-path", " a.json ");
+        settings.remember("output-path", "");
+        assert_eq!(settings.history("output-path"), ["a.json", "b.json"]);
+        assert!(settings.history("date").is_empty());
+
+        for i in 0..HISTORY_LEN + 5 {
+            settings.remember("date", &i.to_string());
+        }
+        assert_eq!(settings.history("date").len(), HISTORY_LEN);
+        assert_eq!(settings.history("date")[0], (HISTORY_LEN + 4).to_string());
+    }
+
+    #[cfg(feature = "tui")]
+    #[test]
+    fn test_profile_channel_selections() {
+        let mut settings = Settings::default();
+        settings.set_selected_channels(vec!["C1".to_string()]);
+        settings.save_channel_selection(" eng ", vec!["C3".to_string(), "C2".to_string()]);
+
+        settings.profile = Some("acme".to_string());
+        assert!(settings.selected_channels_set().is_empty());
+        assert!(settings.channel_selections().is_empty());
+        settings.set_selected_channels(vec!["D1".to_string()]);
+        settings.save_channel_selection("eng", vec!["D2".to_string()]);
+        settings.remove_channel_selection("missing");
+
+        let toml = toml::to_string_pretty(&settings).unwrap();
+        let mut settings: Settings = toml::from_str(&toml).unwrap();
+        assert_eq!(settings.ui.selected_channels, ["C1"]);
+        assert_eq!(settings.ui.channel_selections["eng"], ["C2", "C3"]);
+        assert_eq!(settings.profiles["acme"].selected_channels, ["D1"]);
+        assert_eq!(settings.channel_selections(), [("eng".to_string(), 2)]);
+
+        settings.profile = Some("acme".to_string());
+        assert_eq!(settings.channel_selection("eng").unwrap().len(), 1);
+        settings.remove_channel_selection("eng");
+        assert!(settings.channel_selection("eng").is_none());
+        settings.profile = None;
+        assert!(settings.channel_selection("eng").is_some());
+    }
+
+    #[test]
+    fn test_encrypted_secrets() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("settings.toml");
+        let mut settings = Settings::default();
+        settings.meilisearch.url = "http://localhost:7700".to_string();
+        settings.meilisearch.api_key = "masterKey".to_string();
+        settings.save_to(&path, Some("pass")).unwrap();
+        let content = fs::read_to_string(&path).unwrap();
+        assert!(content.contains("api-key = \"enc:v1:"));
+        assert!(!content.contains("masterKey"));
+
+        let loaded = Settings::load_from(&path, Some("pass")).unwrap();
+        assert_eq!(loaded.meilisearch.api_key, "masterKey");
+        assert!(loaded.locked_secrets.is_empty());
+
+        // Without the passphrase the key is locked and survives a save
+        for passphrase in [None, Some("wrong")] {
+            let mut locked = Settings::load_from(&path, passphrase).unwrap();
+            assert!(locked.meilisearch.api_key.is_empty());
+            assert!(locked.locked_secrets.contains_key("meilisearch.api-key"));
+            locked.meilisearch.index_name = "slack".to_string();
+            locked.save_to(&path, passphrase).unwrap();
+        }
+        let loaded = Settings::load_from(&path, Some("pass")).unwrap();
+        assert_eq!(loaded.meilisearch.api_key, "masterKey");
+        assert_eq!(loaded.meilisearch.index_name, "slack");
+
+        // Saving without a passphrase writes plain text, as before
+        loaded.save_to(&path, None).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("api-key = \"masterKey\""));
+    }
+
     #[test]
     fn test_settings_file_constant() {
         assert_eq!(SETTINGS_FILE, "settings.toml");
@@ -430,4 +760,45 @@ url = "http://example.com:7700"
         assert_eq!(settings.output_path, Some("output.html".to_string()));
         assert!(settings.gfm);
     }
+
+    #[test]
+    fn test_tantivy_settings_roundtrip() {
+        let mut settings = Settings::default();
+        settings.tantivy.index_dir = "my-index".to_string();
+
+        let toml_str = toml::to_string_pretty(&settings).unwrap();
+        assert!(toml_str.contains("index-dir = \"my-index\""));
+
+        let parsed: Settings = toml::from_str(&toml_str).unwrap();
+        assert_eq!(parsed.tantivy.index_dir, "my-index");
+    }
+
+    #[test]
+    #[cfg(feature = "tui")]
+    fn test_download_attachments_options() {
+        let settings: Settings = toml::from_str(
+            r#"
+[download-attachments]
+concurrency = 8
+retries = 0
+dedup = "hardlink"
+max-total-size-mb = 2
+over-budget = "skip-largest"
+"#,
+        )
+        .unwrap();
+        let options = settings.download_attachments.download_options();
+        assert_eq!(options.concurrency, 8);
+        assert_eq!(options.host_interval, DownloadOptions::default().host_interval);
+        assert_eq!(options.retries, 0);
+        assert_eq!(options.dedup, DedupMode::HardLink);
+        assert_eq!(options.max_total_size, Some(2 * 1024 * 1024));
+        assert_eq!(options.over_budget, BudgetMode::SkipLargest);
+
+        let settings: Settings = toml::from_str("[download-attachments]\nhost-interval-ms = 250").unwrap();
+        let options = settings.download_attachments.download_options();
+        assert_eq!(options.concurrency, 4);
+        assert_eq!(options.host_interval, Duration::from_millis(250));
+        assert_eq!(options.max_total_size, None);
+    }
 }
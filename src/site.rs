@@ -0,0 +1,289 @@
+//! Build a self-contained static site from an archive: a markdown and HTML
+//! page per archived week, an index page, a client-side search page and the
+//! downloaded attachments and emojis
+
+use std::fs;
+use std::path::Path;
+
+use walkdir::WalkDir;
+
+use crate::error::{AppError, Result};
+use crate::extract::{archived_weeks, extract_week};
+use crate::formatter::MarkdownExportOptions;
+use crate::index::build_index_entries;
+use crate::json_stream::ConversationSource;
+use crate::markdown::export_conversation_values_to_markdown;
+use crate::md_to_html::{convert_md_dir_to_html, HtmlPageOptions, MdToHtmlOptions};
+use crate::ProgressCallback;
+
+/// Stylesheet inlined into every page
+const SITE_CSS: &str = include_str!("../resources/site.css");
+/// Search page, reading the index from `search-index.js`
+const SEARCH_HTML: &str = include_str!("../resources/site-search.html");
+
+/// Inputs of `build_site`
+#[derive(Debug, Clone)]
+pub struct SiteOptions {
+    /// Archive directory of year=*/week=* partitions
+    pub archive: String,
+    pub users: String,
+    pub channels: String,
+    /// Folder written by download-attachments, copied to `attachments/`
+    pub attachments: Option<String>,
+    /// Folder written by export-emojis, copied to `emojis/`
+    pub emojis: Option<String>,
+    /// Heading of the index page
+    pub title: String,
+}
+
+/// What `build_site` wrote
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SiteSummary {
+    pub weeks: usize,
+    pub messages: usize,
+    /// HTML pages, the search page included
+    pub pages: usize,
+    /// Attachment and emoji files copied
+    pub files: usize,
+}
+
+fn write_file(path: &Path, content: &str) -> Result<()> {
+    fs::write(path, content).map_err(|e| AppError::WriteFile {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+fn create_dir(path: &Path) -> Result<()> {
+    fs::create_dir_all(path).map_err(|e| AppError::WriteFile {
+        path: path.display().to_string(),
+        source: e,
+    })
+}
+
+/// Copy the files under `from` to `to`, keeping the directory structure;
+/// returns the number of files copied
+fn copy_dir(from: &Path, to: &Path) -> Result<usize> {
+    let mut copied = 0;
+    for entry in WalkDir::new(from) {
+        let entry = entry.map_err(|e| AppError::ReadFile {
+            path: from.display().to_string(),
+            source: e.into(),
+        })?;
+        let relative = entry.path().strip_prefix(from).unwrap_or(entry.path());
+        let target = to.join(relative);
+        if entry.file_type().is_dir() {
+            create_dir(&target)?;
+        } else if entry.file_type().is_file() {
+            fs::copy(entry.path(), &target).map_err(|e| AppError::WriteFile {
+                path: target.display().to_string(),
+                source: e,
+            })?;
+            copied += 1;
+        }
+    }
+    Ok(copied)
+}
+
+/// `YYYY-Www`, the name of a week's pages
+fn week_name(year: i32, week: i32) -> String {
+    format!("{}-W{:02}", year, week)
+}
+
+/// Markdown of the index page: the search page, then every week newest
+/// first with its message count
+fn index_markdown(title: &str, weeks: &[(String, usize)]) -> String {
+    let mut markdown = format!("# {}\n\n[Search](search.html)\n", title);
+    let mut year = "";
+    for (name, messages) in weeks.iter().rev() {
+        let week_year = name.split('-').next().unwrap_or_default();
+        if week_year != year {
+            year = week_year;
+            markdown.push_str(&format!("\n## {}\n\n", year));
+        }
+        markdown.push_str(&format!("- [{}](weeks/{}.md) ({} messages)\n", name, name, messages));
+    }
+    markdown
+}
+
+/// Build the site in `output_dir`:
+///
+/// - `markdown/`: the page sources, `index.md` and `weeks/YYYY-Www.md`
+/// - `index.html` and `weeks/YYYY-Www.html`, rendered with `site.css` inlined
+/// - `search.html` with `search-index.js`, the index entries of every week
+///   with the page they are on, searched in the browser
+/// - `attachments/` and `emojis/` when their folders are given
+pub fn build_site(options: &SiteOptions, output_dir: &Path, progress_callback: ProgressCallback) -> Result<SiteSummary> {
+    let report_progress = |current: usize, total: usize, msg: &str| {
+        if let Some(cb) = progress_callback {
+            cb(current, total, msg);
+        }
+    };
+
+    let weeks = archived_weeks(Path::new(&options.archive));
+    if weeks.is_empty() {
+        return Err(AppError::MissingInput(format!("{}/year=*/week=*/threads.parquet", options.archive)));
+    }
+
+    let markdown_dir = output_dir.join("markdown");
+    create_dir(&markdown_dir.join("weeks"))?;
+    let markdown_options = MarkdownExportOptions::new();
+    let mut summary = SiteSummary {
+        weeks: weeks.len(),
+        ..SiteSummary::default()
+    };
+    let mut week_pages = Vec::with_capacity(weeks.len());
+    let mut search_entries = Vec::new();
+    for (idx, (year, week, _)) in weeks.iter().enumerate() {
+        let name = week_name(*year, *week);
+        report_progress(idx + 1, weeks.len(), &format!("Rendering {}", name));
+        let extracted = extract_week(Path::new(&options.archive), *year, *week as u32)?;
+
+        let page = markdown_dir.join("weeks").join(format!("{}.md", name));
+        export_conversation_values_to_markdown(
+            &extracted.conversations,
+            &options.users,
+            &options.channels,
+            &page.to_string_lossy(),
+            None,
+            &markdown_options,
+        )?;
+        let body = fs::read_to_string(&page).map_err(|e| AppError::ReadFile {
+            path: page.display().to_string(),
+            source: e,
+        })?;
+        write_file(&page, &format!("# {}\n\n{}", name, body))?;
+
+        let conversations = ConversationSource::Loaded(&extracted.conversations);
+        for entry in build_index_entries(conversations, &options.users, &options.channels, None)? {
+            let mut entry = serde_json::to_value(entry).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+            if let Some(fields) = entry.as_object_mut() {
+                fields.insert("page".to_string(), format!("weeks/{}.html", name).into());
+            }
+            search_entries.push(entry);
+        }
+        summary.messages += extracted.messages;
+        week_pages.push((name, extracted.messages));
+    }
+    write_file(&markdown_dir.join("index.md"), &index_markdown(&options.title, &week_pages))?;
+
+    let css_path = output_dir.join("site.css");
+    write_file(&css_path, SITE_CSS)?;
+    let page = HtmlPageOptions {
+        css: Some(css_path.to_string_lossy().to_string()),
+        ..HtmlPageOptions::default()
+    };
+    summary.pages = convert_md_dir_to_html(
+        &markdown_dir.to_string_lossy(),
+        &output_dir.to_string_lossy(),
+        &MdToHtmlOptions::gfm(),
+        &page,
+        progress_callback,
+    )? + 1;
+
+    let search_index = serde_json::to_string(&search_entries).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+    write_file(&output_dir.join("search-index.js"), &format!("window.SEARCH_INDEX = {};\n", search_index))?;
+    write_file(&output_dir.join("search.html"), SEARCH_HTML)?;
+
+    for (folder, target) in [(&options.attachments, "attachments"), (&options.emojis, "emojis")] {
+        if let Some(folder) = folder {
+            report_progress(0, 0, &format!("Copying {}", folder));
+            summary.files += copy_dir(Path::new(folder), &output_dir.join(target))?;
+        }
+    }
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parquet::write_conversations_parquet;
+    use serde_json::json;
+
+    #[test]
+    fn test_index_markdown() {
+        let weeks = vec![
+            ("2023-W52".to_string(), 3),
+            ("2024-W01".to_string(), 5),
+            ("2024-W02".to_string(), 0),
+        ];
+        assert_eq!(
+            index_markdown("Archive", &weeks),
+            "# Archive\n\n[Search](search.html)\n\
+             \n## 2024\n\n- [2024-W02](weeks/2024-W02.md) (0 messages)\n- [2024-W01](weeks/2024-W01.md) (5 messages)\n\
+             \n## 2023\n\n- [2023-W52](weeks/2023-W52.md) (3 messages)\n"
+        );
+    }
+
+    #[test]
+    fn test_build_site() {
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        // 1705312800 is 2024-01-15 (W03), 1705917600 is 2024-01-22 (W04)
+        let conversations = vec![json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "1705917600.000100", "user": "U1", "text": "deploy done"},
+                {"ts": "1705312800.000100", "user": "U1", "text": "hello world",
+                 "thread_replies": [{"ts": "1705312860.000100", "user": "U1", "text": "a reply"}]}
+            ]
+        })];
+        write_conversations_parquet(&archive, &conversations).unwrap();
+        let users = dir.path().join("users.json");
+        fs::write(&users, r#"[{"id": "U1", "name": "ana"}]"#).unwrap();
+        let channels = dir.path().join("channels.json");
+        fs::write(&channels, r#"[{"id": "C1", "name": "general"}]"#).unwrap();
+        let emojis = dir.path().join("emojis");
+        fs::create_dir_all(emojis.join("nested")).unwrap();
+        fs::write(emojis.join("nested/party.png"), "png").unwrap();
+
+        let options = SiteOptions {
+            archive: archive.to_string_lossy().to_string(),
+            users: users.to_string_lossy().to_string(),
+            channels: channels.to_string_lossy().to_string(),
+            attachments: None,
+            emojis: Some(emojis.to_string_lossy().to_string()),
+            title: "Slack archive".to_string(),
+        };
+        let site = dir.path().join("site");
+        let summary = build_site(&options, &site, None).unwrap();
+        assert_eq!(
+            summary,
+            SiteSummary {
+                weeks: 2,
+                messages: 2,
+                pages: 4,
+                files: 1
+            }
+        );
+
+        let index = fs::read_to_string(site.join("index.html")).unwrap();
+        assert!(index.contains("<style>"));
+        assert!(index.contains("href=\"weeks/2024-W03.html\""));
+        let week = fs::read_to_string(site.join("weeks/2024-W03.html")).unwrap();
+        assert!(week.contains("hello world"));
+        assert!(week.contains("../index.html"));
+
+        let search_index = fs::read_to_string(site.join("search-index.js")).unwrap();
+        assert!(search_index.starts_with("window.SEARCH_INDEX = ["));
+        assert!(search_index.contains("\"page\":\"weeks/2024-W04.html\""));
+        assert!(site.join("search.html").is_file());
+        assert!(site.join("emojis/nested/party.png").is_file());
+    }
+
+    #[test]
+    fn test_build_site_without_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let options = SiteOptions {
+            archive: dir.path().to_string_lossy().to_string(),
+            users: "users.json".to_string(),
+            channels: "channels.json".to_string(),
+            attachments: None,
+            emojis: None,
+            title: "Slack archive".to_string(),
+        };
+        let result = build_site(&options, &dir.path().join("site"), None);
+        assert!(matches!(result, Err(AppError::MissingInput(_))));
+    }
+}
@@ -1,25 +1,45 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
-use std::path::Path;
-use std::time::Duration;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 use chrono::{Datelike, NaiveDate};
+use futures::StreamExt;
 use serde::Serialize;
 use slack_morphism::errors::SlackClientError;
 use slack_morphism::prelude::*;
-
+use tracing::Instrument;
+
+use crate::attachments::{self, BudgetMode, DedupMode};
+use crate::delta::{DeltaTable, TableFormat};
+use crate::dry_run::{ArchivePlan, PlannedChannel};
+use crate::export_report::{merge_refetched, report_path, ChannelReport, ExportReport, CANCELLED_CHANNEL};
+use crate::export_writer::ConversationWriter;
+use crate::parquet::PartitionBy;
+use crate::provenance::Provenance;
+use crate::json_stream::for_each_conversation;
+use crate::overflow::{archived_week_keys, merge_overflow, OverflowMerge};
+use crate::storage::{join_key, LocalStorage, Storage};
 use crate::{
-    parquet, week_to_date_range, AppError, OutputFormat, ProgressCallback, RateLimitCallback,
-    SlackApiCallbacks, Result,
+    parquet, week_to_date_range, AppError, OutputFormat, ProgressEvent, SlackApiCallbacks, Result,
+    TransferProgress,
 };
 
 /// Maximum retries for rate-limited API calls
 const MAX_RATE_LIMIT_RETRIES: u32 = 5;
 
+/// Wait before the first download retry, doubled on each further one
+const RETRY_BASE_DELAY: Duration = Duration::from_secs(1);
+
+/// Longest wait before a download retry, whatever Retry-After asks for
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60);
+
 /// Creates a Slack client and token for API calls.
 /// Returns a tuple that can be used to open a session: `client.open_session(&token)`
-fn create_slack_client(
+pub(crate) fn create_slack_client(
     token: &str,
 ) -> Result<(
     SlackClient<SlackClientHyperHttpsConnector>,
@@ -34,9 +54,10 @@ fn create_slack_client(
 
 /// Executes a Slack API call with automatic retry on rate limit errors.
 /// Takes a closure that returns a Future, allowing the call to be retried.
-async fn with_rate_limit_retry<F, Fut, T>(
+/// Each wait is reported as a `ProgressEvent::RateLimited`.
+pub(crate) async fn with_rate_limit_retry<F, Fut, T>(
     api_call: F,
-    on_rate_limit: RateLimitCallback<'_>,
+    callbacks: SlackApiCallbacks<'_>,
 ) -> Result<T>
 where
     F: Fn() -> Fut,
@@ -51,17 +72,23 @@ where
                 if let AppError::SlackRateLimit { retry_after_secs } = app_err {
                     retries += 1;
                     if retries > MAX_RATE_LIMIT_RETRIES {
+                        tracing::error!(retries, "rate limited too many times, giving up");
                         return Err(AppError::SlackApi(format!(
                             "Rate limited {} times, giving up",
                             retries
                         )));
                     }
-                    if let Some(cb) = on_rate_limit {
-                        cb(retry_after_secs, retries, MAX_RATE_LIMIT_RETRIES);
+                    tracing::warn!(retry_after_secs, attempt = retries, "rate limited, waiting");
+                    callbacks.report_rate_limit(retry_after_secs, retries, MAX_RATE_LIMIT_RETRIES);
+                    tokio::select! {
+                        () = tokio::time::sleep(Duration::from_secs(retry_after_secs)) => {}
+                        () = callbacks.cancelled() => {
+                            return Err(AppError::Cancelled("stopped while waiting out a rate limit".to_string()));
+                        }
                     }
-                    tokio::time::sleep(Duration::from_secs(retry_after_secs)).await;
                     continue;
                 }
+                tracing::debug!(error = %app_err, "API call failed");
                 return Err(app_err);
             }
         }
@@ -79,7 +106,8 @@ fn get_next_cursor(metadata: &Option<SlackResponseMetadata>) -> Option<SlackCurs
     })
 }
 
-pub async fn export_users(token: &str, output_path: &Path, format: OutputFormat) -> Result<usize> {
+/// Every user of the workspace
+pub(crate) async fn fetch_users(token: &str) -> Result<Vec<SlackUser>> {
     let (client, token_obj) = create_slack_client(token)?;
     let session = client.open_session(&token_obj);
 
@@ -91,7 +119,8 @@ pub async fn export_users(token: &str, output_path: &Path, format: OutputFormat)
             .with_limit(200)
             .opt_cursor(cursor);
 
-        let response = with_rate_limit_retry(|| session.users_list(&request), None).await?;
+        let response = with_rate_limit_retry(|| session.users_list(&request), SlackApiCallbacks::new()).await?;
+        tracing::debug!("users.list: {} users", response.members.len());
         all_users.extend(response.members);
 
         cursor = get_next_cursor(&response.response_metadata);
@@ -100,14 +129,27 @@ pub async fn export_users(token: &str, output_path: &Path, format: OutputFormat)
         }
     }
 
+    Ok(all_users)
+}
+
+pub async fn export_users(token: &str, output_path: &Path, format: OutputFormat) -> Result<usize> {
+    export_users_to(token, &LocalStorage::default(), &output_path.to_string_lossy(), format).await
+}
+
+/// Write the users to `key` in `storage`, returning how many were written
+pub async fn export_users_to(token: &str, storage: &dyn Storage, key: &str, format: OutputFormat) -> Result<usize> {
+    let all_users = fetch_users(token).await?;
     let count = all_users.len();
 
-    write_output(&all_users, output_path, format, parquet::write_users_parquet)?;
+    write_output(&all_users, storage, key, format, |storage, key, values| {
+        storage.put(key, &parquet::users_parquet_bytes(values)?)
+    })?;
 
     Ok(count)
 }
 
-pub async fn export_channels(token: &str, output_path: &Path, format: OutputFormat) -> Result<usize> {
+/// Every public channel of the workspace
+pub(crate) async fn fetch_channel_list(token: &str) -> Result<Vec<SlackChannelInfo>> {
     let (client, token_obj) = create_slack_client(token)?;
     let session = client.open_session(&token_obj);
 
@@ -120,7 +162,8 @@ pub async fn export_channels(token: &str, output_path: &Path, format: OutputForm
             .with_types(vec![SlackConversationType::Public])
             .opt_cursor(cursor);
 
-        let response = with_rate_limit_retry(|| session.conversations_list(&request), None).await?;
+        let response = with_rate_limit_retry(|| session.conversations_list(&request), SlackApiCallbacks::new()).await?;
+        tracing::debug!("conversations.list: {} channels", response.channels.len());
         all_channels.extend(response.channels);
 
         cursor = get_next_cursor(&response.response_metadata);
@@ -129,9 +172,139 @@ pub async fn export_channels(token: &str, output_path: &Path, format: OutputForm
         }
     }
 
+    Ok(all_channels)
+}
+
+/// Workspace ID of the token, from auth.test, recorded in the parquet files
+/// written. A failure is logged and leaves it out instead of failing the
+/// export.
+pub(crate) async fn fetch_team_id(token: &str) -> Option<String> {
+    let response = async {
+        let (client, token_obj) = create_slack_client(token)?;
+        let session = client.open_session(&token_obj);
+        with_rate_limit_retry(|| session.auth_test(), SlackApiCallbacks::new()).await
+    }
+    .await;
+    match response {
+        Ok(response) => Some(response.team_id.0),
+        Err(e) => {
+            tracing::warn!(error = %e, "auth.test failed, writing without the workspace ID");
+            None
+        }
+    }
+}
+
+/// Channels `join_channels` joined before it stopped, with what stopped it
+#[derive(Debug)]
+pub(crate) struct JoinError {
+    pub joined: Vec<PlannedChannel>,
+    pub error: AppError,
+}
+
+/// Join `channels` with conversations.join so their history can be read,
+/// returning the ones joined. A channel that cannot be joined is logged and
+/// left to fail when fetched. Cancelled, the error keeps the channels
+/// already joined so they can still be left.
+pub(crate) async fn join_channels(
+    token: &str,
+    channels: &[&PlannedChannel],
+    callbacks: SlackApiCallbacks<'_>,
+) -> std::result::Result<Vec<PlannedChannel>, JoinError> {
+    let (client, token_obj) = create_slack_client(token).map_err(|error| JoinError {
+        joined: Vec::new(),
+        error,
+    })?;
+    let session = client.open_session(&token_obj);
+    join_each(channels, callbacks, |channel| {
+        let request = SlackApiConversationsJoinRequest::new(SlackChannelId(channel.id.clone()));
+        let session = &session;
+        async move { with_rate_limit_retry(|| session.conversations_join(&request), callbacks).await.map(|_| ()) }
+    })
+    .await
+}
+
+/// `join_channels` with `join` making the request for each channel
+async fn join_each<F, Fut>(
+    channels: &[&PlannedChannel],
+    callbacks: SlackApiCallbacks<'_>,
+    join: F,
+) -> std::result::Result<Vec<PlannedChannel>, JoinError>
+where
+    F: Fn(&PlannedChannel) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let mut joined = Vec::new();
+    for (index, channel) in channels.iter().enumerate() {
+        if callbacks.is_cancelled() {
+            let error = AppError::Cancelled(format!("stopped after joining {} channels", joined.len()));
+            return Err(JoinError { joined, error });
+        }
+        callbacks.report_progress(index + 1, channels.len(), &format!("Joining #{}", channel.name));
+        match join(channel).await {
+            Ok(()) => {
+                tracing::info!(channel = %channel.name, "joined channel");
+                joined.push((*channel).clone());
+            }
+            Err(error @ AppError::Cancelled(_)) => return Err(JoinError { joined, error }),
+            Err(e) => {
+                tracing::warn!(channel = %channel.name, error = %e, "cannot join channel");
+                callbacks.log(&format!("#{}: cannot join, {}", channel.name, e));
+            }
+        }
+    }
+    Ok(joined)
+}
+
+/// Leave the channels `join_channels` joined; failures are only logged, the
+/// export is already written. Runs even once the task is cancelled, since
+/// it undoes what the task did.
+pub(crate) async fn leave_channels(token: &str, channels: &[PlannedChannel], callbacks: SlackApiCallbacks<'_>) {
+    let (client, token_obj) = match create_slack_client(token) {
+        Ok(client) => client,
+        Err(e) => {
+            tracing::warn!(error = %e, "cannot leave joined channels");
+            return;
+        }
+    };
+    let session = client.open_session(&token_obj);
+    leave_each(channels, callbacks, |channel, callbacks| {
+        let request = SlackApiConversationsLeaveRequest::new(SlackChannelId(channel.id.clone()));
+        let session = &session;
+        async move { with_rate_limit_retry(|| session.conversations_leave(&request), callbacks).await.map(|_| ()) }
+    })
+    .await;
+}
+
+/// `leave_channels` with `leave` making the request for each channel, given
+/// `callbacks` without their cancellation
+async fn leave_each<'a, F, Fut>(channels: &[PlannedChannel], callbacks: SlackApiCallbacks<'a>, leave: F)
+where
+    F: Fn(&PlannedChannel, SlackApiCallbacks<'a>) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let callbacks = SlackApiCallbacks { cancel: None, ..callbacks };
+    for (index, channel) in channels.iter().enumerate() {
+        callbacks.report_progress(index + 1, channels.len(), &format!("Leaving #{}", channel.name));
+        if let Err(e) = leave(channel, callbacks).await {
+            tracing::warn!(channel = %channel.name, error = %e, "cannot leave channel");
+            callbacks.log(&format!("#{}: cannot leave, {}", channel.name, e));
+        }
+    }
+}
+
+pub async fn export_channels(token: &str, output_path: &Path, format: OutputFormat) -> Result<usize> {
+    export_channels_to(token, &LocalStorage::default(), &output_path.to_string_lossy(), format).await
+}
+
+/// Write the public channels to `key` in `storage`, returning how many were
+/// written
+pub async fn export_channels_to(token: &str, storage: &dyn Storage, key: &str, format: OutputFormat) -> Result<usize> {
+    let all_channels = fetch_channel_list(token).await?;
     let count = all_channels.len();
 
-    write_output(&all_channels, output_path, format, parquet::write_channels_parquet)?;
+    write_output(&all_channels, storage, key, format, |storage, key, values| {
+        storage.put(key, &parquet::channels_parquet_bytes(values)?)
+    })?;
 
     Ok(count)
 }
@@ -145,7 +318,177 @@ pub async fn export_conversations(
     callbacks: SlackApiCallbacks<'_>,
     format: OutputFormat,
 ) -> Result<usize> {
-    let rate_limit_cb = callbacks.on_rate_limit;
+    export_conversations_to(
+        token,
+        (from_date, to_date),
+        &LocalStorage::default(),
+        &output_path.to_string_lossy(),
+        selected_channel_ids,
+        callbacks,
+        format,
+    )
+    .await
+}
+
+/// Export conversations to `key` in `storage`: the JSON file, or the prefix
+/// of the parquet partitions. Channels whose requests fail are left out and
+/// the rest still written; `<key>.report.json` records how each went, and a
+/// failed or cancelled channel makes the result an error.
+pub async fn export_conversations_to(
+    token: &str,
+    (from_date, to_date): (NaiveDate, NaiveDate),
+    storage: &dyn Storage,
+    key: &str,
+    selected_channel_ids: Option<&HashSet<String>>,
+    callbacks: SlackApiCallbacks<'_>,
+    format: OutputFormat,
+) -> Result<usize> {
+    let (total_messages, report, _) = write_conversations(
+        token,
+        (from_date, to_date),
+        storage,
+        key,
+        selected_channel_ids,
+        callbacks,
+        (format, PartitionBy::Week),
+    )
+    .await?;
+    let path = report_path(Path::new(key)).to_string_lossy().to_string();
+    storage.put(&path, &json_bytes(&report)?)?;
+    report.outcome(format, total_messages, &path)?;
+    Ok(total_messages)
+}
+
+/// Fetch and write the conversations like `export_conversations_to`,
+/// returning the messages written, the report without saving it and the
+/// keys of the files written
+async fn write_conversations(
+    token: &str,
+    (from_date, to_date): (NaiveDate, NaiveDate),
+    storage: &dyn Storage,
+    key: &str,
+    selected_channel_ids: Option<&HashSet<String>>,
+    callbacks: SlackApiCallbacks<'_>,
+    (format, partition_by): (OutputFormat, PartitionBy),
+) -> Result<(usize, ExportReport, Vec<String>)> {
+    let team_id = match format {
+        OutputFormat::Parquet => fetch_team_id(token).await,
+        OutputFormat::Json => None,
+    };
+    let provenance = Provenance::now()
+        .with_range((from_date, to_date))
+        .with_channels(selected_channel_ids)
+        .with_team_id(team_id);
+    // Each channel is written as soon as it is fetched; the output only
+    // appears under `key` once finished
+    let mut writer = ConversationWriter::new(storage, key, format)?
+        .partitioned_by(partition_by)
+        .provenance(provenance);
+    let mut total_messages = 0;
+    let mut write_channel = |conversation: ConversationExport| {
+        total_messages += conversation.messages.len();
+        writer.write(&conversation)
+    };
+    let channels = fetch_conversations_with(
+        token,
+        (from_date, to_date),
+        selected_channel_ids,
+        callbacks,
+        true,
+        &mut write_channel,
+    )
+    .await?;
+
+    callbacks.report_progress(channels.len(), channels.len(), "Finishing output file...");
+    tracing::info!(key, messages = total_messages, channels = channels.len(), "finishing conversations output");
+    let keys = writer.finish()?;
+
+    let report = ExportReport {
+        from: from_date.to_string(),
+        to: to_date.to_string(),
+        output: key.to_string(),
+        channels,
+    };
+    Ok((total_messages, report, keys))
+}
+
+/// Re-fetch the channels the report at `report_path` marks failed, put them
+/// into the JSON export it describes and update the report. Returns the
+/// updated report, which may still have failed channels.
+pub async fn repair_conversations_export(
+    token: &str,
+    report_path: &Path,
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<ExportReport> {
+    let mut report: ExportReport = crate::load_json_file(&report_path.display().to_string())?;
+    if Path::new(&report.output).is_dir() {
+        return Err(AppError::InvalidFormat(format!(
+            "{} holds parquet partitions, --repair only merges into JSON exports; export the range again instead",
+            report.output
+        )));
+    }
+    let failed = report.failed_ids();
+    if failed.is_empty() {
+        return Ok(report);
+    }
+    let range = (crate::parse_date(&report.from)?, crate::parse_date(&report.to)?);
+
+    let (refetched, channels) = fetch_conversations(token, range, Some(&failed), callbacks, true).await?;
+    let refetched: Vec<serde_json::Value> = refetched
+        .iter()
+        .map(serde_json::to_value)
+        .collect::<std::result::Result<_, _>>()
+        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+
+    let mut export: Vec<serde_json::Value> = crate::load_json_file(&report.output)?;
+    merge_refetched(&mut export, refetched);
+    // Channels no longer listed (deleted or made private) stay failed
+    let listed: HashSet<&str> = channels.iter().map(|c| c.channel_id.as_str()).collect();
+    let missing = failed.iter().filter(|id| !listed.contains(id.as_str()));
+    let gone: Vec<ChannelReport> = missing
+        .filter_map(|id| report.channels.iter().find(|c| &c.channel_id == id))
+        .map(|c| ChannelReport::failed(&c.channel_id, &c.channel_name, "channel not listed anymore".to_string()))
+        .collect();
+    report.update(channels);
+    report.update(gone);
+
+    write_json(Path::new(&report.output), &export)?;
+    write_json(report_path, &report)?;
+    Ok(report)
+}
+
+/// Fetch the messages and thread replies of every public channel (or the
+/// selected ones) posted between the two dates, inclusive. With `keep_going`
+/// a channel whose requests fail is reported as failed and the rest are
+/// still fetched; otherwise the first error is returned.
+pub(crate) async fn fetch_conversations(
+    token: &str,
+    range: (NaiveDate, NaiveDate),
+    selected_channel_ids: Option<&HashSet<String>>,
+    callbacks: SlackApiCallbacks<'_>,
+    keep_going: bool,
+) -> Result<(Vec<ConversationExport>, Vec<ChannelReport>)> {
+    let mut conversations = Vec::new();
+    let mut collect = |conversation| {
+        conversations.push(conversation);
+        Ok(())
+    };
+    let reports =
+        fetch_conversations_with(token, range, selected_channel_ids, callbacks, keep_going, &mut collect).await?;
+    Ok((conversations, reports))
+}
+
+/// `fetch_conversations` handing each channel with messages to `on_channel`
+/// as soon as it is fetched instead of collecting them; an error from
+/// `on_channel` stops the fetch
+pub(crate) async fn fetch_conversations_with(
+    token: &str,
+    (from_date, to_date): (NaiveDate, NaiveDate),
+    selected_channel_ids: Option<&HashSet<String>>,
+    callbacks: SlackApiCallbacks<'_>,
+    keep_going: bool,
+    on_channel: &mut (dyn FnMut(ConversationExport) -> Result<()> + Send),
+) -> Result<Vec<ChannelReport>> {
     let (client, token_obj) = create_slack_client(token)?;
     let session = client.open_session(&token_obj);
 
@@ -166,8 +509,12 @@ pub async fn export_conversations(
             .with_types(vec![SlackConversationType::Public])
             .opt_cursor(cursor);
 
+        if callbacks.is_cancelled() {
+            return Err(AppError::Cancelled("stopped while listing channels, nothing written".to_string()));
+        }
         let response =
-            with_rate_limit_retry(|| session.conversations_list(&request), rate_limit_cb).await?;
+            with_rate_limit_retry(|| session.conversations_list(&request), callbacks).await?;
+        callbacks.log(&format!("conversations.list: {} channels", response.channels.len()));
         all_channels.extend(response.channels);
 
         cursor = get_next_cursor(&response.response_metadata);
@@ -187,7 +534,16 @@ pub async fn export_conversations(
         .collect();
 
     let total_channels = channels_to_fetch.len();
-    let mut all_conversations = Vec::new();
+    let mut fetched_messages = 0;
+    let mut reports: Vec<ChannelReport> = Vec::new();
+
+    // A partial date range would look complete to later runs, so nothing is written
+    let cancelled = |channels_done: usize, messages: usize| {
+        AppError::Cancelled(format!(
+            "{} of {} channels fetched ({} messages), nothing written",
+            channels_done, total_channels, messages
+        ))
+    };
 
     for (channel_idx, channel) in channels_to_fetch.iter().enumerate() {
         let channel_id = &channel.id;
@@ -198,118 +554,173 @@ pub async fn export_conversations(
             total_channels,
             &format!("Fetching #{}", channel_name),
         );
+        callbacks.emit(ProgressEvent::ChannelStarted {
+            channel: channel_name.clone(),
+            index: channel_idx,
+            total: total_channels,
+        });
 
-        let mut messages: Vec<SlackHistoryMessage> = Vec::new();
-        let mut msg_cursor: Option<SlackCursorId> = None;
-
-        loop {
-            let request = SlackApiConversationsHistoryRequest::new()
-                .with_channel(channel_id.clone())
-                .with_oldest(oldest_ts.clone())
-                .with_latest(latest_ts.clone())
-                .with_limit(200)
-                .opt_cursor(msg_cursor);
-
-            let response =
-                with_rate_limit_retry(|| session.conversations_history(&request), rate_limit_cb).await?;
-            messages.extend(response.messages);
-
-            msg_cursor = get_next_cursor(&response.response_metadata);
-            if msg_cursor.is_none() {
+        let range = (&oldest_ts, &latest_ts);
+        let span = tracing::info_span!("channel", id = %channel_id.0, name = %channel_name);
+        let fetched = fetch_channel(&session, (channel_id, &channel_name), range, callbacks)
+            .instrument(span)
+            .await;
+        let messages_with_replies = match fetched {
+            Ok(Some(messages)) => messages,
+            Ok(None) | Err(AppError::Cancelled(_)) if keep_going => {
+                // The channels not fetched are reported failed, so the
+                // export written with what was fetched can be repaired
+                for channel in channels_to_fetch.iter().skip(channel_idx) {
+                    let name = channel.name.as_deref().unwrap_or("unknown");
+                    reports.push(ChannelReport::failed(&channel.id.0, name, CANCELLED_CHANNEL.to_string()));
+                }
                 break;
             }
-        }
+            Ok(None) | Err(AppError::Cancelled(_)) => return Err(cancelled(channel_idx, fetched_messages)),
+            Err(e) if keep_going => {
+                tracing::warn!(channel = %channel_name, error = %e, "channel failed, continuing");
+                callbacks.log(&format!("#{}: failed, {}", channel_name, e));
+                reports.push(ChannelReport::failed(&channel_id.0, &channel_name, e.to_string()));
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
 
-        // Count messages with replies for progress reporting
-        let messages_with_thread: Vec<_> = messages
+        let replies: usize = messages_with_replies
             .iter()
-            .filter(|m| m.parent.reply_count.map(|c| c > 0).unwrap_or(false))
-            .collect();
-        let total_threads = messages_with_thread.len();
-
-        // Fetch thread replies for messages that have them
-        let mut messages_with_replies: Vec<serde_json::Value> = Vec::new();
-        let mut thread_idx = 0;
+            .filter_map(|m| m.get("thread_replies").and_then(|r| r.as_array()))
+            .map(|r| r.len())
+            .sum();
+        reports.push(ChannelReport::ok(&channel_id.0, &channel_name, messages_with_replies.len() + replies));
+        if !messages_with_replies.is_empty() {
+            fetched_messages += messages_with_replies.len();
+            on_channel(ConversationExport {
+                channel_id: channel_id.0.clone(),
+                channel_name,
+                messages: messages_with_replies,
+            })?;
+        }
+    }
 
-        for message in messages {
-            let mut msg_value = serde_json::to_value(&message)
-                .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+    callbacks.emit(ProgressEvent::ChannelsFetched { total: total_channels });
 
-            // Check if message has replies
-            if let Some(reply_count) = message.parent.reply_count
-                && reply_count > 0
-            {
-                thread_idx += 1;
-                callbacks.report_progress(
-                    thread_idx,
-                    total_threads,
-                    &format!("#{} - fetching thread {}/{}", channel_name, thread_idx, total_threads),
-                );
-
-                let mut replies: Vec<SlackHistoryMessage> = Vec::new();
-                let mut reply_cursor: Option<SlackCursorId> = None;
+    Ok(reports)
+}
 
-                loop {
-                    let request = SlackApiConversationsRepliesRequest::new(
-                        channel_id.clone(),
-                        message.origin.ts.clone(),
-                    )
-                    .with_limit(200)
-                    .opt_cursor(reply_cursor);
-
-                    let response = with_rate_limit_retry(
-                        || session.conversations_replies(&request),
-                        rate_limit_cb,
-                    ).await?;
-
-                    // Skip the first message (parent) if it matches our message ts
-                    let thread_replies: Vec<_> = response
-                        .messages
-                        .into_iter()
-                        .filter(|m| m.origin.ts != message.origin.ts)
-                        .collect();
-                    replies.extend(thread_replies);
-
-                    reply_cursor = get_next_cursor(&response.response_metadata);
-                    if reply_cursor.is_none() {
-                        break;
-                    }
-                }
+/// Messages of one channel between two timestamps, each with its
+/// `thread_replies`. Returns `None` when cancelled.
+async fn fetch_channel(
+    session: &SlackClientSession<'_, SlackClientHyperHttpsConnector>,
+    (channel_id, channel_name): (&SlackChannelId, &str),
+    (oldest_ts, latest_ts): (&SlackTs, &SlackTs),
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<Option<Vec<serde_json::Value>>> {
+    let mut pages = 0;
+    let mut messages: Vec<SlackHistoryMessage> = Vec::new();
+    let mut msg_cursor: Option<SlackCursorId> = None;
 
-                if !replies.is_empty() {
-                    msg_value["thread_replies"] = serde_json::to_value(&replies)
-                        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
-                }
-            }
+    loop {
+        let request = SlackApiConversationsHistoryRequest::new()
+            .with_channel(channel_id.clone())
+            .with_oldest(oldest_ts.clone())
+            .with_latest(latest_ts.clone())
+            .with_limit(200)
+            .opt_cursor(msg_cursor);
 
-            messages_with_replies.push(msg_value);
+        if callbacks.is_cancelled() {
+            return Ok(None);
         }
+        let response =
+            with_rate_limit_retry(|| session.conversations_history(&request), callbacks).await?;
+        pages += 1;
+        callbacks.log(&format!(
+            "conversations.history #{}: page {}, {} messages",
+            channel_name,
+            pages,
+            response.messages.len()
+        ));
+        callbacks.emit(ProgressEvent::PageFetched {
+            channel: channel_name.to_string(),
+            page: pages,
+            messages: response.messages.len(),
+        });
+        messages.extend(response.messages);
 
-        if !messages_with_replies.is_empty() {
-            all_conversations.push(ConversationExport {
-                channel_id: channel_id.0.clone(),
-                channel_name,
-                messages: messages_with_replies,
-            });
+        msg_cursor = get_next_cursor(&response.response_metadata);
+        if msg_cursor.is_none() {
+            break;
         }
     }
 
-    callbacks.report_progress(total_channels, total_channels, "Writing output file...");
+    // Count messages with replies for progress reporting
+    let total_threads = messages
+        .iter()
+        .filter(|m| m.parent.reply_count.map(|c| c > 0).unwrap_or(false))
+        .count();
 
-    let total_messages: usize = all_conversations.iter().map(|c| c.messages.len()).sum();
+    // Fetch thread replies for messages that have them
+    let mut messages_with_replies: Vec<serde_json::Value> = Vec::new();
+    let mut thread_idx = 0;
 
-    write_output(&all_conversations, output_path, format, |path, values| {
-        parquet::write_conversations_parquet(path, values).map(|_| ())
-    })?;
+    for message in messages {
+        let mut msg_value = serde_json::to_value(&message)
+            .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
 
-    Ok(total_messages)
+        // Check if message has replies
+        if let Some(reply_count) = message.parent.reply_count
+            && reply_count > 0
+        {
+            thread_idx += 1;
+            callbacks.report_progress(
+                thread_idx,
+                total_threads,
+                &format!("#{} - fetching thread {}/{}", channel_name, thread_idx, total_threads),
+            );
+
+            let Some(replies) =
+                fetch_thread_replies(session, channel_id, &message.origin.ts, callbacks).await?
+            else {
+                return Ok(None);
+            };
+            callbacks.emit(ProgressEvent::ThreadFetched {
+                channel: channel_name.to_string(),
+                done: thread_idx,
+                total: total_threads,
+                replies: replies.len(),
+            });
+            callbacks.log(&format!(
+                "conversations.replies #{} {}: {} replies",
+                channel_name, message.origin.ts, replies.len()
+            ));
+
+            if !replies.is_empty()
+                && let Some(object) = msg_value.as_object_mut()
+            {
+                let replies = serde_json::to_value(&replies)
+                    .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+                object.insert("thread_replies".to_string(), replies);
+            }
+        }
+
+        messages_with_replies.push(msg_value);
+    }
+
+    callbacks.log(&format!(
+        "#{}: {} messages, {} threads",
+        channel_name,
+        messages_with_replies.len(),
+        total_threads
+    ));
+    Ok(Some(messages_with_replies))
 }
 
-#[derive(Serialize)]
-struct ConversationExport {
-    channel_id: String,
-    channel_name: String,
-    messages: Vec<serde_json::Value>,
+/// The messages of one channel, each with its `thread_replies`, as written
+/// to a JSON conversations export
+#[derive(Debug, Clone, Serialize)]
+pub struct ConversationExport {
+    pub channel_id: String,
+    pub channel_name: String,
+    pub messages: Vec<serde_json::Value>,
 }
 
 fn date_to_slack_ts(date: NaiveDate) -> SlackTs {
@@ -322,7 +733,41 @@ fn date_to_slack_ts(date: NaiveDate) -> SlackTs {
     SlackTs(format!("{}.000000", timestamp))
 }
 
-fn write_json<T: Serialize>(path: &Path, data: &T) -> Result<()> {
+/// Every reply in the thread started at `ts`, without the parent itself.
+/// Returns `None` when cancelled between pages.
+pub(crate) async fn fetch_thread_replies(
+    session: &SlackClientSession<'_, SlackClientHyperHttpsConnector>,
+    channel_id: &SlackChannelId,
+    ts: &SlackTs,
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<Option<Vec<SlackHistoryMessage>>> {
+    let mut replies: Vec<SlackHistoryMessage> = Vec::new();
+    let mut reply_cursor: Option<SlackCursorId> = None;
+
+    loop {
+        if callbacks.is_cancelled() {
+            return Ok(None);
+        }
+        let request = SlackApiConversationsRepliesRequest::new(channel_id.clone(), ts.clone())
+            .with_limit(200)
+            .opt_cursor(reply_cursor);
+
+        let response = with_rate_limit_retry(
+            || session.conversations_replies(&request),
+            callbacks,
+        ).await?;
+
+        // Skip the first message (parent) if it matches our message ts
+        replies.extend(response.messages.into_iter().filter(|m| &m.origin.ts != ts));
+
+        reply_cursor = get_next_cursor(&response.response_metadata);
+        if reply_cursor.is_none() {
+            return Ok(Some(replies));
+        }
+    }
+}
+
+pub(crate) fn write_json<T: Serialize>(path: &Path, data: &T) -> Result<()> {
     let file = File::create(path).map_err(|e| AppError::WriteFile {
         path: path.display().to_string(),
         source: e,
@@ -332,15 +777,20 @@ fn write_json<T: Serialize>(path: &Path, data: &T) -> Result<()> {
     Ok(())
 }
 
+fn json_bytes<T: Serialize>(data: &T) -> Result<Vec<u8>> {
+    serde_json::to_vec_pretty(data).map_err(|e| AppError::JsonSerialize(e.to_string()))
+}
+
 fn write_output<T: Serialize>(
     items: &[T],
-    output_path: &Path,
+    storage: &dyn Storage,
+    key: &str,
     format: OutputFormat,
-    write_parquet: impl Fn(&Path, &[serde_json::Value]) -> Result<()>,
+    write_parquet: impl Fn(&dyn Storage, &str, &[serde_json::Value]) -> Result<()>,
 ) -> Result<()> {
     match format {
         OutputFormat::Json => {
-            write_json(output_path, &items)?;
+            storage.put(key, &json_bytes(&items)?)?;
         }
         OutputFormat::Parquet => {
             let json_values: Vec<serde_json::Value> = items
@@ -348,7 +798,7 @@ fn write_output<T: Serialize>(
                 .map(serde_json::to_value)
                 .collect::<std::result::Result<_, _>>()
                 .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
-            write_parquet(output_path, &json_values)?;
+            write_parquet(storage, key, &json_values)?;
         }
     }
     Ok(())
@@ -361,6 +811,95 @@ pub struct FileInfo {
     pub name: String,
     pub filetype: Option<String>,
     pub url: Option<String>,
+    /// Size in bytes, when the export has it
+    pub size: Option<u64>,
+    /// When the file was shared, unix seconds
+    pub created: Option<i64>,
+}
+
+/// Which attachments to download; the default keeps all of them
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AttachmentFilter {
+    /// Lowercase file types (`png`, `pdf`), any type when empty
+    pub types: Vec<String>,
+    /// Largest size in bytes to download
+    pub max_size: Option<u64>,
+    /// First day a file may have been shared on
+    pub from: Option<NaiveDate>,
+    /// Last day a file may have been shared on
+    pub to: Option<NaiveDate>,
+}
+
+impl AttachmentFilter {
+    /// Build a filter from user input; empty strings leave that part unset.
+    /// `types` is a comma separated list, `max_size` a size like `25MB` and
+    /// the dates are YYYY-MM-DD.
+    pub fn parse(types: &str, max_size: &str, from: &str, to: &str) -> Result<Self> {
+        let optional_date = |s: &str| {
+            let s = s.trim();
+            (!s.is_empty()).then(|| crate::parse_date(s)).transpose()
+        };
+        let max_size = max_size.trim();
+        Ok(Self {
+            types: types
+                .split(',')
+                .map(|t| t.trim().trim_start_matches('.').to_lowercase())
+                .filter(|t| !t.is_empty())
+                .collect(),
+            max_size: (!max_size.is_empty()).then(|| parse_size(max_size)).transpose()?,
+            from: optional_date(from)?,
+            to: optional_date(to)?,
+        })
+    }
+
+    /// Whether `file` passes every part of the filter. Files without a known
+    /// size or date pass the size and date checks.
+    pub fn matches(&self, file: &FileInfo) -> bool {
+        let type_ok = self.types.is_empty()
+            || file
+                .filetype
+                .as_ref()
+                .is_some_and(|ft| self.types.contains(&ft.to_lowercase()));
+        let size_ok = match (self.max_size, file.size) {
+            (Some(max), Some(size)) => size <= max,
+            _ => true,
+        };
+        let day = file
+            .created
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|dt| dt.date_naive());
+        let date_ok = day.is_none_or(|day| {
+            self.from.is_none_or(|from| day >= from) && self.to.is_none_or(|to| day <= to)
+        });
+        type_ok && size_ok && date_ok
+    }
+}
+
+/// Parse a size like `500`, `200KB`, `25MB` or `1.5GB` (1024 based) into bytes
+pub fn parse_size(s: &str) -> Result<u64> {
+    let invalid = || AppError::InvalidSize(s.to_string());
+    let upper = s.trim().to_ascii_uppercase();
+    let split = upper
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(upper.len());
+    let (number, unit) = upper.split_at(split);
+    let multiplier: u64 = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KB" => 1 << 10,
+        "M" | "MB" => 1 << 20,
+        "G" | "GB" => 1 << 30,
+        _ => return Err(invalid()),
+    };
+    let number: f64 = number.parse().map_err(|_| invalid())?;
+    Ok((number * multiplier as f64) as u64)
+}
+
+/// An item a download task couldn't fetch, and why
+#[derive(Debug, Clone)]
+pub struct DownloadFailure {
+    /// File ID of an attachment, name of an emoji
+    pub item: String,
+    pub error: String,
 }
 
 /// Result of downloading attachments
@@ -369,23 +908,20 @@ pub struct DownloadResult {
     pub downloaded: usize,
     pub failed: usize,
     pub skipped: usize,
-    pub errors: Vec<String>,
+    /// Files whose content matched one downloaded before
+    pub duplicates: usize,
+    /// Files left out to stay within `max_total_size`
+    pub over_budget: usize,
+    pub failures: Vec<DownloadFailure>,
 }
 
-/// Extract file information from a conversations.json file
+/// Extract file information from a conversations.json file, read one
+/// channel at a time
 pub fn extract_files_from_conversations(conversations_path: &str) -> Result<Vec<FileInfo>> {
-    let file = File::open(conversations_path).map_err(|e| AppError::ReadFile {
-        path: conversations_path.to_string(),
-        source: e,
-    })?;
-    let reader = BufReader::new(file);
-    let conversations: Vec<serde_json::Value> =
-        serde_json::from_reader(reader).map_err(|e| AppError::JsonParse(e.to_string()))?;
-
     let mut files = Vec::new();
     let mut seen_ids = std::collections::HashSet::new();
 
-    for conv in conversations {
+    for_each_conversation(conversations_path, |conv| {
         if let Some(messages) = conv.get("messages").and_then(|m| m.as_array()) {
             for message in messages {
                 if let Some(msg_files) = message.get("files").and_then(|f| f.as_array()) {
@@ -420,37 +956,435 @@ pub fn extract_files_from_conversations(conversations_path: &str) -> Result<Vec<
                             .and_then(|u| u.as_str())
                             .map(|s| s.to_string());
 
+                        let size = file_obj.get("size").and_then(|s| s.as_u64());
+
+                        // Files shared in the message carry its timestamp
+                        let created = file_obj
+                            .get("created")
+                            .and_then(|c| c.as_i64())
+                            .or_else(|| {
+                                message
+                                    .get("ts")
+                                    .and_then(|ts| ts.as_str())
+                                    .and_then(|ts| ts.split('.').next())
+                                    .and_then(|secs| secs.parse().ok())
+                            });
+
                         files.push(FileInfo {
                             id,
                             name,
                             filetype,
                             url,
+                            size,
+                            created,
                         });
                     }
                 }
             }
         }
+        Ok(())
+    })?;
+
+    Ok(files)
+}
+
+/// Which attachments to download and how they are spread over connections
+#[derive(Debug, Clone, PartialEq)]
+pub struct DownloadOptions {
+    /// Files downloaded at the same time
+    pub concurrency: usize,
+    /// Minimum time between two requests to the same host
+    pub host_interval: Duration,
+    /// Extra attempts for a file after network errors, 5xx or 429 responses
+    pub retries: u32,
+    pub filter: AttachmentFilter,
+    /// What to do with files whose content was already downloaded
+    pub dedup: DedupMode,
+    /// Most bytes to download in one run, by the sizes in the file metadata
+    pub max_total_size: Option<u64>,
+    /// Which files to leave out when `max_total_size` is exceeded
+    pub over_budget: BudgetMode,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: 4,
+            host_interval: Duration::from_millis(100),
+            retries: 3,
+            filter: AttachmentFilter::default(),
+            dedup: DedupMode::Keep,
+            max_total_size: None,
+            over_budget: BudgetMode::Stop,
+        }
+    }
+}
+
+/// What happened to one attachment
+enum DownloadOutcome {
+    Downloaded(PathBuf),
+    /// Downloaded by an earlier run
+    Existing(PathBuf),
+    /// Nothing to download
+    Skipped,
+    Failed(String),
+}
+
+/// Minimum time between two byte progress reports
+const TRANSFER_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Bytes and files received by the downloads of one task, reported through
+/// `ProgressEvent::BytesDownloaded` at most every `TRANSFER_REPORT_INTERVAL`
+struct TransferMeter<'a> {
+    callbacks: SlackApiCallbacks<'a>,
+    start: Instant,
+    files_total: usize,
+    bytes_total: u64,
+    files_done: AtomicUsize,
+    bytes: AtomicU64,
+    last_report: Mutex<Option<Instant>>,
+}
+
+impl<'a> TransferMeter<'a> {
+    fn new(callbacks: SlackApiCallbacks<'a>, files_total: usize, bytes_total: u64) -> Self {
+        Self {
+            callbacks,
+            start: Instant::now(),
+            files_total,
+            bytes_total,
+            files_done: AtomicUsize::new(0),
+            bytes: AtomicU64::new(0),
+            last_report: Mutex::new(None),
+        }
+    }
+
+    fn add_bytes(&self, bytes: u64) {
+        self.bytes.fetch_add(bytes, Ordering::Relaxed);
+        self.report(false);
+    }
+
+    fn file_done(&self) {
+        self.files_done.fetch_add(1, Ordering::Relaxed);
+        self.report(true);
+    }
+
+    fn report(&self, force: bool) {
+        if !self.callbacks.has_events() {
+            return;
+        }
+        let now = Instant::now();
+        {
+            let mut last = self.last_report.lock().unwrap_or_else(|e| e.into_inner());
+            if !force && last.is_some_and(|t| now - t < TRANSFER_REPORT_INTERVAL) {
+                return;
+            }
+            *last = Some(now);
+        }
+        self.callbacks.report_transfer(&TransferProgress::new(
+            self.files_done.load(Ordering::Relaxed),
+            self.files_total,
+            self.bytes.load(Ordering::Relaxed),
+            self.bytes_total,
+            now - self.start,
+        ));
+    }
+}
+
+/// Wait until `host` may get another request, keeping requests to it at
+/// least `interval` apart across concurrent downloads
+async fn wait_for_host(next_slots: &Mutex<HashMap<String, Instant>>, host: &str, interval: Duration) {
+    let wait = {
+        let mut next_slots = next_slots.lock().unwrap_or_else(|e| e.into_inner());
+        let now = Instant::now();
+        let slot = next_slots.get(host).copied().filter(|t| *t > now).unwrap_or(now);
+        next_slots.insert(host.to_string(), slot + interval);
+        slot - now
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// Download one attachment into `output_dir/<first 3 chars of ID>/<ID>.<type>`
+async fn download_file(
+    client: &reqwest::Client,
+    token: &str,
+    file_info: &FileInfo,
+    output_dir: &Path,
+    options: &DownloadOptions,
+    next_slots: &Mutex<HashMap<String, Instant>>,
+    meter: &TransferMeter<'_>,
+) -> DownloadOutcome {
+    let Some(url) = &file_info.url else {
+        return DownloadOutcome::Skipped;
+    };
+
+    // Create folder based on first 3 characters of file ID
+    let folder_name = file_info.id.get(..3).unwrap_or("unk");
+    let id_dir = output_dir.join(folder_name);
+    if let Err(e) = std::fs::create_dir_all(&id_dir) {
+        return DownloadOutcome::Failed(format!("Failed to create directory {}: {}", id_dir.display(), e));
+    }
+
+    // Create filename using ID and filetype extension
+    let filename = match &file_info.filetype {
+        Some(ft) if !ft.is_empty() => format!("{}.{}", file_info.id, ft),
+        _ => file_info.id.clone(),
+    };
+    let file_path = id_dir.join(&filename);
+
+    // Skip if already exists
+    if file_path.exists() {
+        return DownloadOutcome::Existing(file_path);
+    }
+
+    let host = url::Url::parse(url)
+        .ok()
+        .and_then(|u| u.host_str().map(str::to_string))
+        .unwrap_or_default();
+    // Written next to the final file and renamed once complete, so an
+    // interrupted run leaves no truncated file and the next one resumes it
+    let partial_path = id_dir.join(format!("{}.partial", filename));
+
+    let mut attempt = 0u32;
+    loop {
+        wait_for_host(next_slots, &host, options.host_interval).await;
+        let error = match fetch_to_partial(client, token, url, &file_info.name, &partial_path, meter).await {
+            Ok(()) => {
+                return match std::fs::rename(&partial_path, &file_path) {
+                    Ok(()) => DownloadOutcome::Downloaded(file_path),
+                    Err(e) => DownloadOutcome::Failed(format!("Failed to write {}: {}", file_path.display(), e)),
+                };
+            }
+            Err(error) => error,
+        };
+        attempt += 1;
+        match error {
+            AttemptError::Retryable { retry_after, message } if attempt <= options.retries => {
+                tracing::debug!(file = %file_info.name, attempt, error = %message, "retrying download");
+                tokio::time::sleep(retry_delay(attempt, retry_after)).await;
+            }
+            AttemptError::Retryable { message, .. } if attempt > 1 => {
+                tracing::warn!(file = %file_info.name, attempts = attempt, error = %message, "download failed");
+                return DownloadOutcome::Failed(format!("{} (after {} attempts)", message, attempt));
+            }
+            AttemptError::Retryable { message, .. } | AttemptError::Fatal(message) => {
+                tracing::warn!(file = %file_info.name, error = %message, "download failed");
+                return DownloadOutcome::Failed(message);
+            }
+        }
+    }
+}
+
+/// Wait before retry `attempt` (1 for the first): the server's Retry-After
+/// when it sent one, doubling from `RETRY_BASE_DELAY` otherwise, and never
+/// more than `MAX_RETRY_DELAY`
+fn retry_delay(attempt: u32, retry_after: Option<Duration>) -> Duration {
+    let backoff = RETRY_BASE_DELAY.saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+    retry_after.unwrap_or(backoff).min(MAX_RETRY_DELAY)
+}
+
+/// Why one download attempt failed
+#[derive(Debug)]
+enum AttemptError {
+    /// Worth trying again, after the server's Retry-After when it sent one
+    Retryable {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    /// Trying again won't help (missing file, no access, disk errors)
+    Fatal(String),
+}
+
+/// How a response with the unsuccessful `status` fails a download attempt
+fn status_error(status: reqwest::StatusCode, retry_after: Option<&str>, name: &str, url: &str) -> AttemptError {
+    if status == reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return AttemptError::Retryable {
+            message: format!("Rate limited downloading {}", name),
+            retry_after: retry_after
+                .and_then(|s| s.trim().parse::<u64>().ok())
+                .map(Duration::from_secs),
+        };
+    }
+    let message = format!("HTTP {} for {}: {}", status, name, url);
+    if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE || status.is_server_error() {
+        AttemptError::Retryable {
+            message,
+            retry_after: None,
+        }
+    } else {
+        AttemptError::Fatal(message)
+    }
+}
+
+/// First byte of a `Content-Range: bytes START-END/TOTAL` header
+fn content_range_start(value: &str) -> Option<u64> {
+    value.strip_prefix("bytes ")?.split('-').next()?.trim().parse().ok()
+}
+
+/// Download `url` into `partial_path`, continuing from what an earlier
+/// attempt left there when the server supports ranges
+async fn fetch_to_partial(
+    client: &reqwest::Client,
+    token: &str,
+    url: &str,
+    name: &str,
+    partial_path: &Path,
+    meter: &TransferMeter<'_>,
+) -> std::result::Result<(), AttemptError> {
+    let mut resume_from = std::fs::metadata(partial_path).map(|m| m.len()).unwrap_or(0);
+    let mut response = loop {
+        let mut request = client
+            .get(url)
+            .header("Authorization", format!("Bearer {}", token));
+        if resume_from > 0 {
+            request = request.header("Range", format!("bytes={}-", resume_from));
+        }
+
+        let response = request.send().await.map_err(|e| AttemptError::Retryable {
+            message: format!("Failed to download {}: {}", name, e),
+            retry_after: None,
+        })?;
+        let status = response.status();
+        if !status.is_success() {
+            if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE {
+                // The partial file doesn't match what the server has, start over
+                let _ = std::fs::remove_file(partial_path);
+            }
+            let retry_after = response.headers().get("Retry-After").and_then(|v| v.to_str().ok());
+            return Err(status_error(status, retry_after, name, url));
+        }
+
+        // Appending a range that doesn't start where the partial file ends
+        // would corrupt it, so start over from the first byte instead
+        let range_start = response
+            .headers()
+            .get(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .and_then(content_range_start);
+        if status == reqwest::StatusCode::PARTIAL_CONTENT && range_start != Some(resume_from) {
+            if resume_from == 0 {
+                return Err(AttemptError::Retryable {
+                    message: format!("Unexpected partial content for {}: {}", name, url),
+                    retry_after: None,
+                });
+            }
+            tracing::debug!(file = %name, resume_from, ?range_start, "range mismatch, restarting download");
+            let _ = std::fs::remove_file(partial_path);
+            resume_from = 0;
+            continue;
+        }
+        break response;
+    };
+
+    // Servers that ignore the range send the whole file again
+    let append = response.status() == reqwest::StatusCode::PARTIAL_CONTENT;
+    let write_error =
+        |e: std::io::Error| AttemptError::Fatal(format!("Failed to write {}: {}", partial_path.display(), e));
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(append)
+        .truncate(!append)
+        .open(partial_path)
+        .map_err(write_error)?;
+    while let Some(chunk) = response.chunk().await.map_err(|e| AttemptError::Retryable {
+        message: format!("Failed to read response for {}: {}", name, e),
+        retry_after: None,
+    })? {
+        file.write_all(&chunk).map_err(write_error)?;
+        meter.add_bytes(chunk.len() as u64);
     }
+    Ok(())
+}
 
-    Ok(files)
+/// The attachments a download works on, before anything is downloaded
+#[derive(Debug)]
+pub struct DownloadSelection {
+    /// Files passing the filter and within the budget, downloaded before or not
+    pub files: Vec<FileInfo>,
+    /// Files downloaded before and still on disk, by ID
+    pub known: HashMap<String, PathBuf>,
+    /// Files left out to stay within `max_total_size`
+    pub over_budget: usize,
+    manifest: attachments::Manifest,
+}
+
+impl DownloadSelection {
+    /// Files still to download
+    pub fn pending(&self) -> impl Iterator<Item = &FileInfo> {
+        self.files.iter().filter(|f| !self.known.contains_key(&f.id))
+    }
+
+    /// Bytes still to download, by the sizes the export has
+    pub fn pending_bytes(&self) -> u64 {
+        self.pending().filter_map(|f| f.size).sum()
+    }
+}
+
+/// Pick the attachments of a conversations.json file that
+/// `download_attachments` would work on, without downloading or writing
+/// anything
+pub fn select_downloads(
+    conversations_path: &str,
+    output_dir: &Path,
+    only_ids: Option<&HashSet<String>>,
+    options: &DownloadOptions,
+) -> Result<DownloadSelection> {
+    let mut files = extract_files_from_conversations(conversations_path)?;
+    files.retain(|f| options.filter.matches(f) && only_ids.is_none_or(|ids| ids.contains(&f.id)));
+
+    let manifest = attachments::load_manifest(output_dir)?;
+    // Duplicates removed by earlier runs only exist in the manifest
+    let known: HashMap<String, PathBuf> = manifest
+        .iter()
+        .map(|(id, entry)| (id.clone(), output_dir.join(&entry.path)))
+        .filter(|(_, path)| path.is_file())
+        .collect();
+
+    // Files already on disk don't count against the budget
+    let mut over_budget = 0;
+    if let Some(budget) = options.max_total_size {
+        let (existing, pending): (Vec<_>, Vec<_>) = files.into_iter().partition(|f| known.contains_key(&f.id));
+        let (pending, left_out) = attachments::apply_budget(pending, budget, options.over_budget);
+        over_budget = left_out.len();
+        files = existing.into_iter().chain(pending).collect();
+    }
+    Ok(DownloadSelection {
+        files,
+        known,
+        over_budget,
+        manifest,
+    })
 }
 
-/// Download attachments from a conversations.json file
-pub fn download_attachments(
+/// Download attachments from a conversations.json file, `options.concurrency`
+/// at a time, keeping `manifest.json` in `output_dir` up to date
+pub async fn download_attachments(
     token: &str,
     conversations_path: &str,
     output_dir: &Path,
-    progress_callback: ProgressCallback,
+    only_ids: Option<&HashSet<String>>,
+    options: &DownloadOptions,
+    callbacks: SlackApiCallbacks<'_>,
 ) -> Result<DownloadResult> {
-    let files = extract_files_from_conversations(conversations_path)?;
-    let total = files.len();
-
-    if total == 0 {
+    let selection = select_downloads(conversations_path, output_dir, only_ids, options)?;
+    let bytes_total = selection.pending_bytes();
+    let DownloadSelection {
+        files,
+        known,
+        over_budget,
+        mut manifest,
+    } = selection;
+
+    if files.is_empty() {
         return Ok(DownloadResult {
             downloaded: 0,
             failed: 0,
             skipped: 0,
-            errors: Vec::new(),
+            duplicates: 0,
+            over_budget,
+            failures: Vec::new(),
         });
     }
 
@@ -460,92 +1394,97 @@ pub fn download_attachments(
         source: e,
     })?;
 
-    let client = reqwest::blocking::Client::new();
+    let total = files.len();
+    attachments::check_disk_space(output_dir, bytes_total)?;
+
+    let meter = TransferMeter::new(callbacks, total, bytes_total);
+    let client = reqwest::Client::new();
+    let next_slots = Mutex::new(HashMap::new());
     let mut downloaded = 0;
+    let mut duplicates = 0;
     let mut failed = 0;
     let mut skipped = 0;
-    let mut errors = Vec::new();
-
-    for (idx, file_info) in files.iter().enumerate() {
-        if let Some(cb) = progress_callback {
-            cb(idx + 1, total, &file_info.name);
-        }
-
-        let Some(url) = &file_info.url else {
-            skipped += 1;
-            continue;
-        };
-
-        // Create folder based on first 3 characters of file ID
-        let folder_name = if file_info.id.len() >= 3 {
-            &file_info.id[..3]
-        } else {
-            "unk"
-        };
-        let id_dir = output_dir.join(folder_name);
-        if let Err(e) = std::fs::create_dir_all(&id_dir) {
-            errors.push(format!("Failed to create directory {}: {}", id_dir.display(), e));
-            failed += 1;
-            continue;
-        }
-
-        // Create filename using ID and filetype extension
-        let filename = match &file_info.filetype {
-            Some(ft) if !ft.is_empty() => format!("{}.{}", file_info.id, ft),
-            _ => file_info.id.clone(),
-        };
-        let file_path = id_dir.join(&filename);
-
-        // Skip if already exists
-        if file_path.exists() {
-            skipped += 1;
-            continue;
-        }
-
-        // Download file
-        match client
-            .get(url)
-            .header("Authorization", format!("Bearer {}", token))
-            .send()
-        {
-            Ok(response) => {
-                if response.status().is_success() {
-                    match response.bytes() {
-                        Ok(bytes) => {
-                            if let Err(e) = std::fs::write(&file_path, &bytes) {
-                                errors.push(format!("Failed to write {}: {}", file_path.display(), e));
-                                failed += 1;
-                            } else {
-                                downloaded += 1;
-                            }
-                        }
-                        Err(e) => {
-                            errors.push(format!("Failed to read response for {}: {}", file_info.name, e));
-                            failed += 1;
-                        }
-                    }
-                } else {
-                    errors.push(format!(
-                        "HTTP {} for {}: {}",
-                        response.status(),
-                        file_info.name,
-                        url
-                    ));
-                    failed += 1;
-                }
+    let mut failures = Vec::new();
+
+    let mut downloads = futures::stream::iter(&files)
+        .map(|file_info| {
+            let (client, next_slots, known, meter) = (&client, &next_slots, &known, &meter);
+            async move {
+                let outcome = match known.get(&file_info.id) {
+                    Some(path) => DownloadOutcome::Existing(path.clone()),
+                    None => download_file(client, token, file_info, output_dir, options, next_slots, meter).await,
+                };
+                (file_info, outcome)
             }
-            Err(e) => {
-                errors.push(format!("Failed to download {}: {}", file_info.name, e));
+        })
+        .buffer_unordered(options.concurrency.max(1));
+
+    let mut done = 0;
+    // Dropping the stream aborts the downloads in flight
+    while let Some((file_info, outcome)) = tokio::select! {
+        biased;
+        () = callbacks.cancelled() => None,
+        next = downloads.next() => next,
+    } {
+        done += 1;
+        callbacks.report_progress(done, total, &file_info.name);
+        callbacks.log(&file_info.name);
+        meter.file_done();
+        let saved = match outcome {
+            DownloadOutcome::Downloaded(path) => {
+                downloaded += 1;
+                Some(path)
+            }
+            DownloadOutcome::Existing(path) => {
+                skipped += 1;
+                (!manifest.contains_key(&file_info.id)).then_some(path)
+            }
+            DownloadOutcome::Skipped => {
+                skipped += 1;
+                None
+            }
+            DownloadOutcome::Failed(error) => {
                 failed += 1;
+                failures.push(DownloadFailure {
+                    item: file_info.id.clone(),
+                    error,
+                });
+                None
+            }
+        };
+        if let Some(path) = saved {
+            let added = attachments::add_to_manifest(
+                &mut manifest,
+                output_dir,
+                &file_info.id,
+                &file_info.name,
+                &path,
+                options.dedup,
+            );
+            match added {
+                Ok(duplicate) => duplicates += usize::from(duplicate),
+                Err(e) => failures.push(DownloadFailure {
+                    item: file_info.id.clone(),
+                    error: e.to_string(),
+                }),
             }
         }
     }
+    attachments::save_manifest(output_dir, &manifest)?;
+    if done < total && callbacks.is_cancelled() {
+        return Err(AppError::Cancelled(format!(
+            "{} of {} files processed ({} downloaded, {} skipped, {} failed)",
+            done, total, downloaded, skipped, failed
+        )));
+    }
 
     Ok(DownloadResult {
         downloaded,
         failed,
         skipped,
-        errors,
+        duplicates,
+        over_budget,
+        failures,
     })
 }
 
@@ -556,7 +1495,7 @@ pub struct EmojiResult {
     pub downloaded: usize,
     pub failed: usize,
     pub skipped: usize,
-    pub errors: Vec<String>,
+    pub failures: Vec<DownloadFailure>,
 }
 
 /// Fetch custom emojis from Slack and optionally download images
@@ -564,13 +1503,10 @@ pub async fn fetch_emojis(
     token: &str,
     output_path: &Path,
     emojis_folder: &Path,
-    progress_callback: ProgressCallback<'_>,
+    only_names: Option<&HashSet<String>>,
+    callbacks: SlackApiCallbacks<'_>,
 ) -> Result<EmojiResult> {
-    let report_progress = |current: usize, total: usize, msg: &str| {
-        if let Some(cb) = progress_callback {
-            cb(current, total, msg);
-        }
-    };
+    let report_progress = |current: usize, total: usize, msg: &str| callbacks.report_progress(current, total, msg);
 
     report_progress(0, 0, "Fetching emoji list...");
 
@@ -600,8 +1536,13 @@ pub async fn fetch_emojis(
                 .and_then(|v| v.to_str().ok())
                 .and_then(|s| s.parse::<u64>().ok())
                 .unwrap_or(60);
-            report_progress(0, 0, &format!("Rate limited, waiting {}s...", retry_after));
-            tokio::time::sleep(Duration::from_secs(retry_after)).await;
+            callbacks.report_rate_limit(retry_after, retries, MAX_RATE_LIMIT_RETRIES);
+            tokio::select! {
+                () = tokio::time::sleep(Duration::from_secs(retry_after)) => {}
+                () = callbacks.cancelled() => {
+                    return Err(AppError::Cancelled("stopped while fetching the emoji list, nothing written".to_string()));
+                }
+            }
             continue;
         }
 
@@ -668,24 +1609,32 @@ pub async fn fetch_emojis(
     let mut failed = 0;
     let mut skipped = 0;
     let mut copied = 0;
-    let mut errors = Vec::new();
+    let mut failures = Vec::new();
     let mut emoji_extensions: std::collections::HashMap<&str, String> =
         std::collections::HashMap::new();
 
     let real_count = real_emojis.len();
+    let meter = TransferMeter::new(callbacks, real_count, 0);
     for (idx, (name, url)) in real_emojis.iter().enumerate() {
+        if callbacks.is_cancelled() {
+            return Err(AppError::Cancelled(format!(
+                "emoji list saved, {} of {} images processed ({} downloaded, {} skipped)",
+                idx, real_count, downloaded, skipped
+            )));
+        }
         report_progress(idx + 1, total, name);
+        callbacks.log(name);
+        meter.file_done();
 
-        // Extract file extension from URL
-        let ext = url
-            .split('.')
-            .next_back()
-            .and_then(|s: &str| s.split('?').next())
-            .unwrap_or("png")
-            .to_string();
+        let ext = crate::emojis::emoji_extension(url);
 
         emoji_extensions.insert(name.as_str(), ext.clone());
 
+        // Retries still need the extension of every image their aliases point to
+        if only_names.is_some_and(|names| !names.contains(name.as_str())) {
+            continue;
+        }
+
         let filename = format!("{}.{}", name, ext);
         let file_path = emojis_folder.join(&filename);
 
@@ -706,25 +1655,38 @@ pub async fn fetch_emojis(
                 if response.status().is_success() {
                     match response.bytes().await {
                         Ok(bytes) => {
+                            meter.add_bytes(bytes.len() as u64);
                             if let Err(e) = std::fs::write(&file_path, &bytes) {
-                                errors.push(format!("Failed to write {}: {}", filename, e));
+                                failures.push(DownloadFailure {
+                                    item: name.to_string(),
+                                    error: format!("Failed to write {}: {}", filename, e),
+                                });
                                 failed += 1;
                             } else {
                                 downloaded += 1;
                             }
                         }
                         Err(e) => {
-                            errors.push(format!("Failed to read emoji {}: {}", name, e));
+                            failures.push(DownloadFailure {
+                                item: name.to_string(),
+                                error: format!("Failed to read emoji {}: {}", name, e),
+                            });
                             failed += 1;
                         }
                     }
                 } else {
-                    errors.push(format!("HTTP {} for emoji {}", response.status(), name));
+                    failures.push(DownloadFailure {
+                        item: name.to_string(),
+                        error: format!("HTTP {} for emoji {}", response.status(), name),
+                    });
                     failed += 1;
                 }
             }
             Err(e) => {
-                errors.push(format!("Failed to download emoji {}: {}", name, e));
+                failures.push(DownloadFailure {
+                    item: name.to_string(),
+                    error: format!("Failed to download emoji {}: {}", name, e),
+                });
                 failed += 1;
             }
         }
@@ -732,26 +1694,19 @@ pub async fn fetch_emojis(
 
     // Copy files for aliases
     for (idx, (alias_name, target_name)) in aliases.iter().enumerate() {
-        report_progress(real_count + idx + 1, total, &format!("{} -> {}", alias_name, target_name));
-
-        // Resolve the target (follow alias chains)
-        let mut current_target = *target_name;
-        let mut visited = std::collections::HashSet::new();
-        while let Some(url) = emojis.get(current_target).and_then(|v| v.as_str()) {
-            if let Some(next_target) = url.strip_prefix("alias:") {
-                if visited.contains(next_target) {
-                    break; // Circular alias, stop
-                }
-                visited.insert(current_target);
-                current_target = next_target;
-            } else {
-                break; // Found the real emoji
-            }
+        if only_names.is_some_and(|names| !names.contains(alias_name.as_str())) {
+            continue;
         }
+        report_progress(real_count + idx + 1, total, &format!("{} -> {}", alias_name, target_name));
 
-        // Get the extension of the target emoji
-        let Some(ext) = emoji_extensions.get(current_target) else {
-            errors.push(format!("Alias {} target {} not found", alias_name, current_target));
+        // Follow alias chains to the real emoji and get its extension
+        let resolved = crate::emojis::resolve_alias(emojis, alias_name)
+            .and_then(|target| emoji_extensions.get(target).map(|ext| (target, ext)));
+        let Some((current_target, ext)) = resolved else {
+            failures.push(DownloadFailure {
+                item: alias_name.to_string(),
+                error: format!("Alias {} target {} not found", alias_name, target_name),
+            });
             failed += 1;
             continue;
         };
@@ -770,13 +1725,19 @@ pub async fn fetch_emojis(
         // Copy the file
         if source_path.exists() {
             if let Err(e) = std::fs::copy(&source_path, &dest_path) {
-                errors.push(format!("Failed to copy {} to {}: {}", source_filename, dest_filename, e));
+                failures.push(DownloadFailure {
+                    item: alias_name.to_string(),
+                    error: format!("Failed to copy {} to {}: {}", source_filename, dest_filename, e),
+                });
                 failed += 1;
             } else {
                 copied += 1;
             }
         } else {
-            errors.push(format!("Source file {} not found for alias {}", source_filename, alias_name));
+            failures.push(DownloadFailure {
+                item: alias_name.to_string(),
+                error: format!("Source file {} not found for alias {}", source_filename, alias_name),
+            });
             failed += 1;
         }
     }
@@ -786,7 +1747,7 @@ pub async fn fetch_emojis(
         downloaded: downloaded + copied,
         failed,
         skipped,
-        errors,
+        failures,
     })
 }
 
@@ -796,10 +1757,28 @@ pub struct ArchiveRangeResult {
     pub total_messages: usize,
     pub weeks_processed: usize,
     pub weeks_skipped: usize,
+    /// Weeks written without some of their channels, fetched again by the
+    /// next run
+    pub weeks_incomplete: usize,
+    /// The channels that failed, across weeks
+    pub failed_channels: Vec<ChannelReport>,
+}
+
+impl ArchiveRangeResult {
+    /// `AppError::ArchiveIncomplete` when some weeks miss channels
+    pub fn check_complete(&self) -> Result<()> {
+        match self.weeks_incomplete {
+            0 => Ok(()),
+            weeks => Err(AppError::ArchiveIncomplete {
+                weeks,
+                total: self.weeks_processed,
+            }),
+        }
+    }
 }
 
 /// Generate all ISO weeks in a range (inclusive)
-fn generate_weeks_in_range(
+pub(crate) fn generate_weeks_in_range(
     from_year: i32,
     from_week: u32,
     to_year: i32,
@@ -855,7 +1834,56 @@ fn slack_error_to_app_error(error: SlackClientError) -> AppError {
     }
 }
 
+/// Report of the channels of a week that failed, kept in its partition
+fn week_report_key(prefix: &str, (year, week): (i32, u32)) -> String {
+    join_key(prefix, &format!("year={}/week={:02}/report.json", year, week))
+}
+
+/// The `weeks` whose partition exists under `prefix` in `storage` with all
+/// of its channels
+fn archived_weeks(weeks: &[(i32, u32)], storage: &dyn Storage, prefix: &str) -> Result<HashSet<(i32, u32)>> {
+    let mut archived = HashSet::new();
+    for (year, week) in weeks {
+        let week_prefix = join_key(prefix, &format!("year={}/week={:02}/", year, week));
+        let keys = storage.list(&week_prefix)?;
+        if !keys.iter().any(|key| key.ends_with("/threads.parquet")) {
+            continue;
+        }
+        let report = storage
+            .get(&week_report_key(prefix, (*year, *week)))?
+            .and_then(|data| serde_json::from_slice::<ExportReport>(&data).ok());
+        if report.is_none_or(|report| report.failed().next().is_none()) {
+            archived.insert((*year, *week));
+        }
+    }
+    Ok(archived)
+}
+
+/// What `archive_weeks_to` with `skip_existing` would fetch, from the
+/// channel list and the partitions already written
+pub async fn plan_archive_weeks(
+    token: &str,
+    weeks: &[(i32, u32)],
+    storage: &dyn Storage,
+    prefix: &str,
+) -> Result<ArchivePlan> {
+    let existing = archived_weeks(weeks, storage, prefix)?;
+    let channels = fetch_channel_list(token).await?;
+    ArchivePlan::new(&channels, weeks, &existing)
+}
+
+fn log_merge(callbacks: SlackApiCallbacks<'_>, label: &str, merged: &OverflowMerge) {
+    if merged.moved + merged.duplicates > 0 {
+        tracing::info!(label, moved = merged.moved, duplicates = merged.duplicates, "thread replies merged");
+        callbacks.log(&format!(
+            "{}: {} replies moved to their thread's week, {} duplicates dropped",
+            label, merged.moved, merged.duplicates
+        ));
+    }
+}
+
 /// Archive conversations for a range of ISO weeks to parquet format
+#[cfg(any(feature = "tui", feature = "server"))]
 pub async fn archive_range(
     token: &str,
     from_year: i32,
@@ -865,30 +1893,66 @@ pub async fn archive_range(
     output_path: &Path,
     callbacks: SlackApiCallbacks<'_>,
 ) -> Result<ArchiveRangeResult> {
-
     let weeks = generate_weeks_in_range(from_year, from_week, to_year, to_week);
+    archive_weeks(token, &weeks, output_path, true, callbacks).await
+}
+
+/// Archive conversations for the given ISO weeks to parquet format. With
+/// `skip_existing`, weeks whose partition already exists are left as they
+/// are; otherwise they are fetched again and replaced, which keeps a week
+/// still in progress current.
+pub async fn archive_weeks(
+    token: &str,
+    weeks: &[(i32, u32)],
+    output_path: &Path,
+    skip_existing: bool,
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<ArchiveRangeResult> {
+    let prefix = output_path.to_string_lossy();
+    archive_weeks_to(token, weeks, &LocalStorage::default(), &prefix, skip_existing, (None, None), callbacks).await
+}
+
+/// `archive_weeks` writing the partitions under `prefix` in `storage`, laid
+/// out as `partition_by` asks and, for `TableFormat::Delta`, committing each
+/// week to the archive's Delta log. Either left `None` stays as the archive
+/// already is.
+pub async fn archive_weeks_to(
+    token: &str,
+    weeks: &[(i32, u32)],
+    storage: &dyn Storage,
+    prefix: &str,
+    skip_existing: bool,
+    (partition_by, table_format): (Option<PartitionBy>, Option<TableFormat>),
+    callbacks: SlackApiCallbacks<'_>,
+) -> Result<ArchiveRangeResult> {
     let total_weeks = weeks.len();
+    let partition_by = PartitionBy::resolve(partition_by, storage, prefix)?;
+    let table = match TableFormat::resolve(table_format, storage, prefix)? {
+        TableFormat::Delta => Some(DeltaTable::new(storage, prefix)),
+        TableFormat::Files => None,
+    };
+    // A new table starts with the weeks already archived
+    if let Some(table) = &table
+        && table.version()?.is_none()
+    {
+        table.commit(&[], "CREATE TABLE")?;
+    }
 
-    // Capture which parquet files exist BEFORE we start processing.
-    // This prevents skipping weeks that only have "overflow" messages from
-    // thread replies written during this run.
-    let pre_existing_files: HashSet<_> = weeks
-        .iter()
-        .filter_map(|(year, week)| {
-            let parquet_file = output_path
-                .join(format!("year={}/week={:02}", year, week))
-                .join("threads.parquet");
-            if parquet_file.exists() {
-                Some((*year, *week))
-            } else {
-                None
-            }
-        })
-        .collect();
+    // Capture which parquet files exist BEFORE we start processing, so
+    // weeks that only got replies merged into them during this run are
+    // still fetched.
+    let pre_existing_files = if skip_existing {
+        archived_weeks(weeks, storage, prefix)?
+    } else {
+        HashSet::new()
+    };
 
     let mut total_messages = 0usize;
     let mut weeks_processed = 0usize;
+    let mut processed = BTreeSet::new();
     let mut weeks_skipped = 0usize;
+    let mut weeks_incomplete = 0usize;
+    let mut failed_channels = Vec::new();
 
     callbacks.report_progress(
         0,
@@ -896,45 +1960,89 @@ pub async fn archive_range(
         &format!("Archiving {} weeks...", total_weeks),
     );
 
-    // Create callbacks for export_conversations without progress (we report at week level)
-    // but with rate limit callback
-    let export_callbacks = SlackApiCallbacks::new()
-        .with_rate_limit(callbacks.on_rate_limit.unwrap_or(&|_, _, _| {}));
+    // Status is reported at week level; the export's other events go through
+    let export_callbacks = callbacks.nested();
 
     for (idx, (year, week)) in weeks.iter().enumerate() {
         let week_label = format!("{}-W{:02}", year, week);
+        // The week in flight is written with the channels it got and marked
+        // incomplete, so the next run fetches it again
+        let cancelled = || {
+            AppError::Cancelled(format!(
+                "{} of {} weeks archived ({} messages, {} skipped)",
+                weeks_processed, total_weeks, total_messages, weeks_skipped
+            ))
+        };
+        if callbacks.is_cancelled() {
+            return Err(cancelled());
+        }
 
         // Only skip if file existed BEFORE this run started
-        // (not created by overflow messages during this run)
         if pre_existing_files.contains(&(*year, *week)) {
             callbacks.report_progress(
                 idx + 1,
                 total_weeks,
                 &format!("{} - already exists, skipping", week_label),
             );
+            callbacks.log(&format!("{}: already archived, skipped", week_label));
             weeks_skipped += 1;
             continue;
         }
 
         callbacks.report_progress(idx + 1, total_weeks, &format!("{} - fetching...", week_label));
 
+        // Replies stored here instead of with their thread would be lost
+        // when the week is replaced
+        let mut merged = merge_overflow(storage, prefix, partition_by, (*year, *week))?;
+
         // Convert week to date range
         let (from_date, to_date) = week_to_date_range(*year, *week)?;
 
         // Export conversations (rate limits handled at individual API call level)
-        let count = export_conversations(
+        let (count, report, mut keys) = write_conversations(
             token,
-            from_date,
-            to_date,
-            output_path,
+            (from_date, to_date),
+            storage,
+            prefix,
             None, // All channels
             export_callbacks,
-            OutputFormat::Parquet,
+            (OutputFormat::Parquet, partition_by),
         )
+        .instrument(tracing::info_span!("week", week = %week_label))
         .await?;
+        // Thread broadcasts come with the history of the week they were
+        // sent in, replies to a thread of an earlier week go to it
+        merged.add(merge_overflow(storage, prefix, partition_by, (*year, *week))?);
+        log_merge(callbacks, &week_label, &merged);
+        processed.insert((*year, *week));
+        keys.extend(merged.keys);
+        // The files written, earlier weeks' given replies included, are one commit
+        if let Some(table) = &table
+            && let Some(version) = table.commit(&keys, "WRITE")?
+        {
+            callbacks.log(&format!("{}: delta table version {}", week_label, version));
+        }
 
+        // A report with failures keeps the week from being skipped; one from
+        // an earlier run is replaced once the week is complete
+        let report_key = week_report_key(prefix, (*year, *week));
+        let failed: Vec<ChannelReport> = report.failed().cloned().collect();
+        if !failed.is_empty() || storage.exists(&report_key)? {
+            storage.put(&report_key, &json_bytes(&report)?)?;
+        }
+        if report.cancelled() {
+            return Err(cancelled());
+        }
         total_messages += count;
         weeks_processed += 1;
+        if !failed.is_empty() {
+            weeks_incomplete += 1;
+            tracing::warn!(week = %week_label, failed = failed.len(), "week archived without some channels");
+            callbacks.log(&format!("{}: {} channels failed, see {}", week_label, failed.len(), report_key));
+            failed_channels.extend(failed);
+        }
+        tracing::info!(week = %week_label, messages = count, "week archived");
+        callbacks.log(&format!("{}: {} messages archived", week_label, count));
         callbacks.report_progress(
             idx + 1,
             total_weeks,
@@ -942,10 +2050,32 @@ pub async fn archive_range(
         );
     }
 
+    // Weeks after the ones fetched may still hold replies to their threads
+    if let Some(first) = processed.iter().next().copied() {
+        let mut merged = OverflowMerge::default();
+        for (later_year, later_week) in archived_week_keys(storage, prefix)?.into_keys() {
+            let Ok(later_week) = u32::try_from(later_week) else {
+                continue;
+            };
+            let later = (later_year, later_week);
+            if later > first && !processed.contains(&later) {
+                merged.add(merge_overflow(storage, prefix, partition_by, later)?);
+            }
+        }
+        log_merge(callbacks, "later weeks", &merged);
+        if let Some(table) = &table
+            && let Some(version) = table.commit(&merged.keys, "MERGE")?
+        {
+            callbacks.log(&format!("later weeks: delta table version {}", version));
+        }
+    }
+
     Ok(ArchiveRangeResult {
         total_messages,
         weeks_processed,
         weeks_skipped,
+        weeks_incomplete,
+        failed_channels,
     })
 }
 
@@ -953,14 +2083,14 @@ pub async fn archive_range(
 #[cfg(feature = "tui")]
 mod tui_support {
     use std::fs::File;
-    use std::io::{BufReader, BufWriter};
+    use std::io::BufWriter;
     use std::path::Path;
 
     use serde::{Deserialize, Serialize};
     use slack_morphism::prelude::*;
 
     use crate::{AppError, Result};
-    use super::{create_slack_client, get_next_cursor, with_rate_limit_retry};
+    use super::fetch_channel_list;
 
     /// Type alias for loaded conversation data: (channel_id, channel_name, messages)
     pub type LoadedConversations = (
@@ -973,88 +2103,71 @@ mod tui_support {
     pub struct ChannelInfo {
         pub id: String,
         pub name: String,
+        #[serde(default)]
+        pub num_members: Option<u64>,
+        #[serde(default)]
+        pub is_archived: bool,
+        /// Unix seconds of the channel's creation
+        #[serde(default)]
+        pub created: Option<i64>,
     }
 
-    pub fn load_channels_from_file(path: &Path) -> Result<Vec<ChannelInfo>> {
-        let channels: Vec<serde_json::Value> = crate::load_json_file(&path.display().to_string())?;
-
-        let channel_infos = channels
-            .into_iter()
-            .filter_map(|c| {
-                let id = c.get("id")?.as_str()?.to_string();
-                let name = c
-                    .get("name")
-                    .and_then(|n| n.as_str())
-                    .unwrap_or("unknown")
-                    .to_string();
-                Some(ChannelInfo { id, name })
+    impl ChannelInfo {
+        /// From a channel object as written by `export-channels`
+        fn from_json(c: &serde_json::Value) -> Option<Self> {
+            let id = c.get("id")?.as_str()?.to_string();
+            let name = c
+                .get("name")
+                .and_then(|n| n.as_str())
+                .unwrap_or("unknown")
+                .to_string();
+            Some(ChannelInfo {
+                id,
+                name,
+                num_members: c.get("num_members").and_then(|n| n.as_u64()),
+                is_archived: c.get("is_archived").and_then(|a| a.as_bool()).unwrap_or(false),
+                created: c.get("created").and_then(|t| t.as_i64()),
             })
-            .collect();
-
-        Ok(channel_infos)
+        }
     }
 
-    pub async fn fetch_channels(token: &str) -> Result<Vec<ChannelInfo>> {
-        let (client, token_obj) = create_slack_client(token)?;
-        let session = client.open_session(&token_obj);
-
-        let mut all_channels = Vec::new();
-        let mut cursor: Option<SlackCursorId> = None;
-
-        loop {
-            let request = SlackApiConversationsListRequest::new()
-                .with_limit(200)
-                .with_types(vec![SlackConversationType::Public])
-                .opt_cursor(cursor);
+    impl From<&SlackChannelInfo> for ChannelInfo {
+        fn from(channel: &SlackChannelInfo) -> Self {
+            ChannelInfo {
+                id: channel.id.0.clone(),
+                name: channel.name.clone().unwrap_or_else(|| "unknown".to_string()),
+                num_members: channel.num_members,
+                is_archived: channel.flags.is_archived.unwrap_or(false),
+                created: Some(channel.created.0.timestamp()),
+            }
+        }
+    }
 
-            let response = with_rate_limit_retry(|| session.conversations_list(&request), None).await?;
+    /// Channels of a channels JSON export or `channels.parquet`
+    pub fn load_channels_from_file(path: &Path) -> Result<Vec<ChannelInfo>> {
+        let channels = crate::archive_input::load_channels(&path.display().to_string())?;
 
-            for channel in response.channels {
-                all_channels.push(ChannelInfo {
-                    id: channel.id.0.clone(),
-                    name: channel.name.clone().unwrap_or_else(|| "unknown".to_string()),
-                });
-            }
+        let channel_infos = channels.iter().filter_map(ChannelInfo::from_json).collect();
 
-            cursor = get_next_cursor(&response.response_metadata);
-            if cursor.is_none() {
-                break;
-            }
-        }
+        Ok(channel_infos)
+    }
 
-        Ok(all_channels)
+    pub async fn fetch_channels(token: &str) -> Result<Vec<ChannelInfo>> {
+        let channels = fetch_channel_list(token).await?;
+        Ok(channels.iter().map(ChannelInfo::from).collect())
     }
 
-    /// Load conversations from local JSON files for editing.
+    /// Load conversations for editing, from a JSON file or an `archive:`
+    /// week, with users and channels from their JSON or parquet files.
     /// Returns (channels with messages, users data, channels metadata).
     pub fn load_conversations_for_editing(
         conversations_path: &str,
         users_path: &str,
         channels_path: &str,
     ) -> Result<LoadedConversations> {
-        let conv_file = File::open(conversations_path).map_err(|e| AppError::ReadFile {
-            path: conversations_path.to_string(),
-            source: e,
-        })?;
-        let conv_reader = BufReader::new(conv_file);
-        let conversations: Vec<serde_json::Value> =
-            serde_json::from_reader(conv_reader).map_err(|e| AppError::JsonParse(e.to_string()))?;
-
-        let users_file = File::open(users_path).map_err(|e| AppError::ReadFile {
-            path: users_path.to_string(),
-            source: e,
-        })?;
-        let users_reader = BufReader::new(users_file);
-        let users: serde_json::Value =
-            serde_json::from_reader(users_reader).map_err(|e| AppError::JsonParse(e.to_string()))?;
-
-        let channels_file = File::open(channels_path).map_err(|e| AppError::ReadFile {
-            path: channels_path.to_string(),
-            source: e,
-        })?;
-        let channels_reader = BufReader::new(channels_file);
-        let channel_data: serde_json::Value =
-            serde_json::from_reader(channels_reader).map_err(|e| AppError::JsonParse(e.to_string()))?;
+        let conversations = crate::archive_input::load_conversations(conversations_path)?;
+        let users = serde_json::Value::Array(crate::archive_input::load_users(users_path)?);
+        let channel_data = serde_json::Value::Array(crate::archive_input::load_channels(channels_path)?);
 
         let channels: Vec<(String, String, Vec<serde_json::Value>)> = conversations
             .into_iter()
@@ -1073,29 +2186,31 @@ mod tui_support {
         Ok((channels, users, channel_data))
     }
 
-    /// Export edited conversations to a JSON file.
-    pub fn export_edited_conversations_to_file(
+    /// Edited conversations in the selected-conversations.json shape,
+    /// leaving out channels without messages
+    pub fn edited_conversations_to_values(
         channels: &[(String, String, Vec<serde_json::Value>)],
-        output_path: &str,
-    ) -> Result<usize> {
-        #[derive(Serialize)]
-        struct ExportedConversation {
-            channel_id: String,
-            channel_name: String,
-            messages: Vec<serde_json::Value>,
-        }
-
-        let exported: Vec<ExportedConversation> = channels
+    ) -> Vec<serde_json::Value> {
+        channels
             .iter()
             .filter(|(_, _, messages)| !messages.is_empty())
-            .map(|(id, name, messages)| ExportedConversation {
-                channel_id: id.clone(),
-                channel_name: name.clone(),
-                messages: messages.clone(),
+            .map(|(id, name, messages)| {
+                serde_json::json!({
+                    "channel_id": id,
+                    "channel_name": name,
+                    "messages": messages,
+                })
             })
-            .collect();
+            .collect()
+    }
 
-        let total_messages: usize = exported.iter().map(|c| c.messages.len()).sum();
+    /// Export edited conversations to a JSON file.
+    pub fn export_edited_conversations_to_file(
+        channels: &[(String, String, Vec<serde_json::Value>)],
+        output_path: &str,
+    ) -> Result<usize> {
+        let exported = edited_conversations_to_values(channels);
+        let total_messages: usize = channels.iter().map(|(_, _, messages)| messages.len()).sum();
 
         let file = File::create(output_path).map_err(|e| AppError::WriteFile {
             path: output_path.to_string(),
@@ -1111,3 +2226,258 @@ mod tui_support {
 
 #[cfg(feature = "tui")]
 pub use tui_support::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+
+    use crate::CancellationToken;
+
+    #[test]
+    fn test_retry_delay() {
+        assert_eq!(retry_delay(1, None), Duration::from_secs(1));
+        assert_eq!(retry_delay(2, None), Duration::from_secs(2));
+        assert_eq!(retry_delay(4, None), Duration::from_secs(8));
+        assert_eq!(retry_delay(40, None), MAX_RETRY_DELAY);
+        assert_eq!(retry_delay(1, Some(Duration::from_secs(5))), Duration::from_secs(5));
+        assert_eq!(retry_delay(1, Some(Duration::from_secs(86_400))), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn test_status_error() {
+        use reqwest::StatusCode;
+
+        let retry_after = |e: AttemptError| match e {
+            AttemptError::Retryable { retry_after, .. } => Some(retry_after),
+            AttemptError::Fatal(_) => None,
+        };
+        let classify = |status, header| retry_after(status_error(status, header, "f.txt", "http://x/f.txt"));
+
+        assert_eq!(
+            classify(StatusCode::TOO_MANY_REQUESTS, Some("30")),
+            Some(Some(Duration::from_secs(30)))
+        );
+        assert_eq!(classify(StatusCode::TOO_MANY_REQUESTS, Some("soon")), Some(None));
+        assert_eq!(classify(StatusCode::TOO_MANY_REQUESTS, None), Some(None));
+        assert_eq!(classify(StatusCode::SERVICE_UNAVAILABLE, Some("30")), Some(None));
+        assert_eq!(classify(StatusCode::RANGE_NOT_SATISFIABLE, None), Some(None));
+        assert_eq!(classify(StatusCode::NOT_FOUND, None), None);
+        assert_eq!(classify(StatusCode::FORBIDDEN, None), None);
+    }
+
+    #[test]
+    fn test_content_range_start() {
+        assert_eq!(content_range_start("bytes 100-199/200"), Some(100));
+        assert_eq!(content_range_start("bytes 0-9/*"), Some(0));
+        assert_eq!(content_range_start("bytes */200"), None);
+        assert_eq!(content_range_start("items 0-9/10"), None);
+    }
+
+    /// Serve `responses` to one connection each, returning the requests'
+    /// `Range` headers once they were all served
+    fn serve(responses: Vec<String>) -> (String, std::thread::JoinHandle<Vec<Option<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/file.txt", listener.local_addr().unwrap());
+        let handle = std::thread::spawn(move || {
+            let mut ranges = Vec::new();
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut range = None;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':')
+                        && name.eq_ignore_ascii_case("range")
+                    {
+                        range = Some(value.trim().to_string());
+                    }
+                }
+                ranges.push(range);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            ranges
+        });
+        (url, handle)
+    }
+
+    fn response(status: &str, headers: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n{}\r\n{}",
+            status,
+            body.len(),
+            headers,
+            body
+        )
+    }
+
+    async fn fetch(url: &str, partial_path: &Path) -> std::result::Result<(), AttemptError> {
+        let meter = TransferMeter::new(SlackApiCallbacks::new(), 1, 0);
+        fetch_to_partial(&reqwest::Client::new(), "xoxb", url, "file.txt", partial_path, &meter).await
+    }
+
+    #[tokio::test]
+    async fn test_fetch_resumes_partial_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("file.txt.partial");
+        std::fs::write(&partial, "hello ").unwrap();
+        let (url, server) = serve(vec![response(
+            "206 Partial Content",
+            "Content-Range: bytes 6-10/11\r\n",
+            "world",
+        )]);
+
+        fetch(&url, &partial).await.unwrap();
+
+        assert_eq!(server.join().unwrap(), vec![Some("bytes=6-".to_string())]);
+        assert_eq!(std::fs::read_to_string(&partial).unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_restarts_on_content_range_mismatch() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("file.txt.partial");
+        std::fs::write(&partial, "hello ").unwrap();
+        let (url, server) = serve(vec![
+            response("206 Partial Content", "Content-Range: bytes 2-10/11\r\n", "llo world"),
+            response("200 OK", "", "hello world"),
+        ]);
+
+        fetch(&url, &partial).await.unwrap();
+
+        assert_eq!(server.join().unwrap(), vec![Some("bytes=6-".to_string()), None]);
+        assert_eq!(std::fs::read_to_string(&partial).unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_replaces_partial_when_range_ignored() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("file.txt.partial");
+        std::fs::write(&partial, "stale").unwrap();
+        let (url, server) = serve(vec![response("200 OK", "", "hello world")]);
+
+        fetch(&url, &partial).await.unwrap();
+
+        assert_eq!(server.join().unwrap(), vec![Some("bytes=5-".to_string())]);
+        assert_eq!(std::fs::read_to_string(&partial).unwrap(), "hello world");
+    }
+
+    #[tokio::test]
+    async fn test_join_cancel_leave() {
+        let channel = |id: &str| PlannedChannel {
+            id: id.to_string(),
+            name: id.to_lowercase(),
+            num_members: None,
+            is_archived: false,
+            is_member: false,
+            created: 0,
+        };
+        let channels = [channel("C1"), channel("C2"), channel("C3")];
+        let to_join: Vec<&PlannedChannel> = channels.iter().collect();
+        let cancel = CancellationToken::new();
+        let callbacks = SlackApiCallbacks::new().with_cancel(&cancel);
+
+        // Cancelled while C2 is joined, so C3 never is
+        let stopped = join_each(&to_join, callbacks, |channel| {
+            if channel.id == "C2" {
+                cancel.cancel();
+            }
+            async { Ok(()) }
+        })
+        .await
+        .unwrap_err();
+        assert!(matches!(stopped.error, AppError::Cancelled(_)));
+        let joined: Vec<&str> = stopped.joined.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(joined, ["C1", "C2"]);
+
+        let left = std::sync::Mutex::new(Vec::new());
+        leave_each(&stopped.joined, callbacks, |channel, callbacks| {
+            assert!(!callbacks.is_cancelled());
+            left.lock().unwrap().push(channel.id.clone());
+            async { Ok(()) }
+        })
+        .await;
+        assert_eq!(*left.lock().unwrap(), ["C1", "C2"]);
+    }
+
+    #[tokio::test]
+    async fn test_cancel_stops_download_in_flight() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        let dir = tempfile::tempdir().unwrap();
+        let conversations = dir.path().join("conversations.json");
+        let file = |id: &str| serde_json::json!({"id": id, "name": id, "filetype": "txt", "url_private": format!("{}/{}", base, id)});
+        let json = serde_json::json!([{"messages": [{"files": [file("F01")]}, {"files": [file("F02")]}]}]);
+        std::fs::write(&conversations, json.to_string()).unwrap();
+
+        // The first file arrives whole, the second stalls halfway through
+        // and the task is cancelled while waiting for the rest
+        let cancel = CancellationToken::new();
+        let server_cancel = cancel.clone();
+        let server = std::thread::spawn(move || {
+            let mut streams = Vec::new();
+            for (body, length) in [("first", 5), ("sec", 6)] {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                while reader.read_line(&mut line).unwrap() > 2 {
+                    line.clear();
+                }
+                let head = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", length);
+                stream.write_all(head.as_bytes()).unwrap();
+                stream.write_all(body.as_bytes()).unwrap();
+                stream.flush().unwrap();
+                streams.push(stream);
+            }
+            std::thread::sleep(Duration::from_millis(200));
+            server_cancel.cancel();
+            // Keep the stalled response open until the download gave up on it
+            std::thread::sleep(Duration::from_secs(5));
+        });
+
+        let options = DownloadOptions {
+            concurrency: 1,
+            host_interval: Duration::ZERO,
+            ..DownloadOptions::default()
+        };
+        let output = dir.path().join("attachments");
+        let started = Instant::now();
+        let result = download_attachments(
+            "xoxb",
+            conversations.to_str().unwrap(),
+            &output,
+            None,
+            &options,
+            SlackApiCallbacks::new().with_cancel(&cancel),
+        )
+        .await;
+
+        assert!(matches!(result, Err(AppError::Cancelled(_))), "{:?}", result.err());
+        assert!(started.elapsed() < Duration::from_secs(3));
+        let manifest = attachments::load_manifest(&output).unwrap();
+        assert!(manifest.contains_key("F01"));
+        assert!(!manifest.contains_key("F02"));
+        assert_eq!(std::fs::read_to_string(output.join("F01/F01.txt")).unwrap(), "first");
+        assert_eq!(std::fs::read_to_string(output.join("F02/F02.txt.partial")).unwrap(), "sec");
+        drop(server);
+    }
+
+    #[tokio::test]
+    async fn test_fetch_range_not_satisfiable_discards_partial() {
+        let dir = tempfile::tempdir().unwrap();
+        let partial = dir.path().join("file.txt.partial");
+        std::fs::write(&partial, "too long already").unwrap();
+        let (url, server) = serve(vec![response("416 Range Not Satisfiable", "", "")]);
+
+        let result = fetch(&url, &partial).await;
+
+        server.join().unwrap();
+        assert!(matches!(result, Err(AttemptError::Retryable { .. })));
+        assert!(!partial.exists());
+    }
+}
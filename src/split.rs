@@ -0,0 +1,256 @@
+//! Split a conversations export into one file per channel or per month,
+//! streaming it so exports of several GB can be split
+
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use chrono::DateTime;
+use serde_json::Value;
+
+use crate::error::{AppError, Result};
+use crate::export_writer::JsonArrayWriter;
+use crate::json_stream::for_each_conversation;
+use crate::storage::LocalStorage;
+
+/// How `split` groups messages into files
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum SplitBy {
+    /// `<channel>.json` per channel
+    #[default]
+    Channel,
+    /// `YYYY-MM.json` per month (UTC) of the top-level message, replies
+    /// staying with their thread
+    Month,
+}
+
+impl std::str::FromStr for SplitBy {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "channel" => Ok(SplitBy::Channel),
+            "month" => Ok(SplitBy::Month),
+            _ => Err(AppError::InvalidFormat(format!("{} (expected channel or month)", s))),
+        }
+    }
+}
+
+/// One file `split` wrote and the top-level messages in it
+#[derive(Debug, Clone, PartialEq)]
+pub struct SplitPart {
+    pub path: PathBuf,
+    pub messages: usize,
+}
+
+/// Top-level messages of a conversation
+fn message_count(conversation: &Value) -> usize {
+    conversation.get("messages").and_then(|m| m.as_array()).map_or(0, |m| m.len())
+}
+
+/// A part being written, a conversations export of its own
+struct PartWriter<'a> {
+    writer: JsonArrayWriter<'a>,
+    file_name: String,
+    messages: usize,
+}
+
+impl<'a> PartWriter<'a> {
+    fn new(storage: &'a LocalStorage, file_name: String) -> Result<Self> {
+        Ok(PartWriter {
+            writer: JsonArrayWriter::new(storage, &file_name)?,
+            file_name,
+            messages: 0,
+        })
+    }
+
+    fn write(&mut self, conversation: &Value) -> Result<()> {
+        self.messages += message_count(conversation);
+        self.writer.write(conversation)
+    }
+
+    fn finish(self, output_dir: &Path) -> Result<SplitPart> {
+        self.writer.finish()?;
+        Ok(SplitPart {
+            path: output_dir.join(&self.file_name),
+            messages: self.messages,
+        })
+    }
+}
+
+/// `name` with anything but letters, digits, `-`, `_` and `.` replaced, so
+/// it's safe as a file name
+pub(crate) fn file_stem(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') { c } else { '_' })
+        .collect::<String>()
+        .trim_start_matches('.')
+        .to_string()
+}
+
+/// `YYYY-MM` (UTC) of a Slack timestamp
+fn ts_month(ts: &str) -> Option<String> {
+    let secs = ts.split('.').next()?.parse().ok()?;
+    DateTime::from_timestamp(secs, 0).map(|t| t.format("%Y-%m").to_string())
+}
+
+fn channel_export(channel_id: &str, channel_name: &str, messages: Vec<Value>) -> Value {
+    serde_json::json!({
+        "channel_id": channel_id,
+        "channel_name": channel_name,
+        "messages": messages,
+    })
+}
+
+/// Split the conversations file at `conversations_path` into parts under
+/// `output_dir`, each a conversations export of its own, reading one
+/// conversation at a time so files of several GB fit in memory. Channel
+/// parts are named after the channel (its ID when two channels share a
+/// name) and written as they are read; month parts are appended to as each
+/// channel's messages are read, and messages without a readable ts go to
+/// `unknown.json`. `on_written` is called with each part once it is
+/// complete; the parts are returned in the order written.
+pub fn split_conversations(
+    conversations_path: &str,
+    by: SplitBy,
+    output_dir: &Path,
+    on_written: &mut dyn FnMut(&SplitPart),
+) -> Result<Vec<SplitPart>> {
+    std::fs::create_dir_all(output_dir).map_err(|e| AppError::WriteFile {
+        path: output_dir.display().to_string(),
+        source: e,
+    })?;
+    let storage = LocalStorage::new(output_dir);
+    let mut written = Vec::new();
+    match by {
+        SplitBy::Channel => {
+            let mut used = HashSet::new();
+            for_each_conversation(conversations_path, |conversation| {
+                let text = |key: &str| conversation.get(key).and_then(|v| v.as_str()).map(file_stem);
+                let id = text("channel_id").unwrap_or_default();
+                let stem = match text("channel_name").filter(|n| !n.is_empty()) {
+                    Some(name) if !used.contains(&name) => name,
+                    Some(name) => format!("{}-{}", name, id),
+                    None => id,
+                };
+                used.insert(stem.clone());
+
+                let mut part = PartWriter::new(&storage, format!("{}.json", stem))?;
+                part.write(&conversation)?;
+                let part = part.finish(output_dir)?;
+                on_written(&part);
+                written.push(part);
+                Ok(())
+            })?;
+        }
+        SplitBy::Month => {
+            let mut months: BTreeMap<String, PartWriter> = BTreeMap::new();
+            for_each_conversation(conversations_path, |mut conversation| {
+                let text = |key: &str| conversation.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+                let (channel_id, channel_name) = (text("channel_id"), text("channel_name"));
+                let messages = match conversation.get_mut("messages").map(Value::take) {
+                    Some(Value::Array(messages)) => messages,
+                    _ => return Ok(()),
+                };
+
+                let mut by_month: BTreeMap<String, Vec<Value>> = BTreeMap::new();
+                for message in messages {
+                    let month = message
+                        .get("ts")
+                        .and_then(|v| v.as_str())
+                        .and_then(ts_month)
+                        .unwrap_or_else(|| "unknown".to_string());
+                    by_month.entry(month).or_default().push(message);
+                }
+                for (month, messages) in by_month {
+                    let part = match months.entry(month) {
+                        Entry::Occupied(part) => part.into_mut(),
+                        Entry::Vacant(slot) => {
+                            let file_name = format!("{}.json", slot.key());
+                            slot.insert(PartWriter::new(&storage, file_name)?)
+                        }
+                    };
+                    part.write(&channel_export(&channel_id, &channel_name, messages))?;
+                }
+                Ok(())
+            })?;
+            for part in months.into_values() {
+                let part = part.finish(output_dir)?;
+                on_written(&part);
+                written.push(part);
+            }
+        }
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A conversations file in `dir`
+    fn export(dir: &Path) -> String {
+        // 1704103200 is 2024-01-01, 1706781600 is 2024-02-01
+        let conversations = json!([
+            {
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1706781600.000100", "text": "february",
+                     "thread_replies": [{"ts": "1709287200.000100", "text": "march reply"}]},
+                    {"ts": "1704103200.000100", "text": "january"}
+                ]
+            },
+            {"channel_id": "C2", "channel_name": "general", "messages": [{"ts": "1704103300.000100"}]},
+            {"channel_id": "D3", "channel_name": "../dm with ana", "messages": [{"text": "no ts"}]}
+        ]);
+        let path = dir.join("conversations.json");
+        std::fs::write(&path, conversations.to_string()).unwrap();
+        path.to_string_lossy().to_string()
+    }
+
+    fn load(path: &Path) -> Vec<Value> {
+        crate::load_json_file(&path.to_string_lossy()).unwrap()
+    }
+
+    fn names(parts: &[SplitPart]) -> Vec<String> {
+        parts.iter().map(|p| p.path.file_name().unwrap().to_string_lossy().to_string()).collect()
+    }
+
+    #[test]
+    fn test_split_by_channel() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("parts");
+        let mut reported = 0;
+        let parts = split_conversations(&export(dir.path()), SplitBy::Channel, &output, &mut |_| reported += 1).unwrap();
+        assert_eq!(names(&parts), ["general.json", "general-C2.json", "_dm_with_ana.json"]);
+        assert_eq!(reported, 3);
+        assert_eq!(parts[0].messages, 2);
+        assert_eq!(parts[0].path, output.join("general.json"));
+        assert_eq!(load(&parts[1].path)[0]["channel_id"], "C2");
+    }
+
+    #[test]
+    fn test_split_by_month() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("parts");
+        let parts = split_conversations(&export(dir.path()), SplitBy::Month, &output, &mut |_| {}).unwrap();
+        assert_eq!(names(&parts), ["2024-01.json", "2024-02.json", "unknown.json"]);
+
+        let january = load(&parts[0].path);
+        assert_eq!(january.len(), 2);
+        assert_eq!(january[0]["channel_id"], "C1");
+        assert_eq!(parts[0].messages, 2);
+        // Replies stay with their parent's month
+        let february = load(&parts[1].path);
+        assert_eq!(february[0]["messages"][0]["thread_replies"][0]["text"], "march reply");
+        assert!(!output.join("2024-03.json").exists());
+    }
+
+    #[test]
+    fn test_split_by_from_str() {
+        assert_eq!("Month".parse::<SplitBy>().unwrap(), SplitBy::Month);
+        assert!("week".parse::<SplitBy>().is_err());
+    }
+}
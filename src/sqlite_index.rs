@@ -0,0 +1,164 @@
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{params, Connection};
+
+use crate::error::{AppError, Result};
+use crate::index::IndexEntry;
+use crate::ProgressCallback;
+
+/// FTS5 table: `text`, `channel_name` and `user_names` are full-text indexed,
+/// the remaining columns are stored metadata.
+const CREATE_FTS_TABLE: &str = "CREATE VIRTUAL TABLE messages USING fts5(
+    text,
+    channel_name,
+    user_names,
+    id UNINDEXED,
+    ts UNINDEXED,
+    date UNINDEXED,
+    timestamp UNINDEXED,
+    channel_id UNINDEXED,
+    user_ids UNINDEXED,
+    has_attachments UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+)";
+
+const INSERT_ENTRY: &str = "INSERT INTO messages
+    (text, channel_name, user_names, id, ts, date, timestamp, channel_id, user_ids, has_attachments)
+    VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)";
+
+fn sqlite_error(e: rusqlite::Error) -> AppError {
+    AppError::Sqlite(e.to_string())
+}
+
+/// Write index entries to a single SQLite file with an FTS5 table.
+///
+/// The file can be queried offline with any SQLite client, or loaded in the
+/// browser with sql.js, e.g. `SELECT channel_name, date,
+/// snippet(messages, 0, '<mark>', '</mark>', '…', 20) FROM messages
+/// WHERE messages MATCH 'deploy' ORDER BY rank`.
+/// An existing file at `output_path` is replaced.
+pub fn write_sqlite_fts_index(
+    entries: &[IndexEntry],
+    output_path: &str,
+    progress_callback: ProgressCallback,
+) -> Result<()> {
+    let report_progress = |current: usize, total: usize, msg: &str| {
+        if let Some(cb) = progress_callback {
+            cb(current, total, msg);
+        }
+    };
+
+    if Path::new(output_path).exists() {
+        fs::remove_file(output_path).map_err(|e| AppError::WriteFile {
+            path: output_path.to_string(),
+            source: e,
+        })?;
+    }
+
+    let mut conn = Connection::open(output_path).map_err(sqlite_error)?;
+    conn.execute(CREATE_FTS_TABLE, []).map_err(sqlite_error)?;
+
+    let total = entries.len();
+    let tx = conn.transaction().map_err(sqlite_error)?;
+    {
+        let mut stmt = tx.prepare(INSERT_ENTRY).map_err(sqlite_error)?;
+        for (i, entry) in entries.iter().enumerate() {
+            report_progress(i + 1, total, "Writing SQLite index...");
+
+            let user_names: Vec<&str> = entry.users.iter().map(|u| u.name.as_str()).collect();
+            let user_ids: Vec<&str> = entry.users.iter().map(|u| u.id.as_str()).collect();
+            stmt.execute(params![
+                entry.text,
+                entry.channel.name,
+                user_names.join(" "),
+                entry.id,
+                entry.ts,
+                entry.date,
+                entry.timestamp,
+                entry.channel.id,
+                user_ids.join(" "),
+                entry.has_attachments,
+            ])
+            .map_err(sqlite_error)?;
+        }
+    }
+    tx.commit().map_err(sqlite_error)?;
+
+    report_progress(total, total, "Optimizing SQLite index...");
+    conn.execute("INSERT INTO messages(messages) VALUES('optimize')", [])
+        .map_err(sqlite_error)?;
+    // Compact the file so it is smaller to ship to the browser
+    conn.execute("VACUUM", []).map_err(sqlite_error)?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{IndexChannel, IndexUser};
+
+    fn entry(ts: &str, channel: &str, user: &str, text: &str, has_attachments: bool) -> IndexEntry {
+        IndexEntry {
+            id: ts.replace('.', "_"),
+            ts: ts.to_string(),
+            date: "2024-01-01T00:00:00+00:00".to_string(),
+            text: text.to_string(),
+            users: vec![IndexUser {
+                id: format!("U_{}", user),
+                name: user.to_string(),
+            }],
+            channel: IndexChannel {
+                id: format!("C_{}", channel),
+                name: channel.to_string(),
+            },
+            timestamp: 1704067200,
+            has_attachments,
+        }
+    }
+
+    #[test]
+    fn test_write_sqlite_fts_index_is_searchable() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("index.sqlite");
+        let output = output.to_str().unwrap();
+        let entries = vec![
+            entry("1.1", "general", "alice", "The deploy failed on production", true),
+            entry("2.2", "random", "bob", "Lunch plans for friday", false),
+        ];
+
+        write_sqlite_fts_index(&entries, output, None).unwrap();
+
+        let conn = Connection::open(output).unwrap();
+        let (id, channel, has_attachments): (String, String, bool) = conn
+            .query_row(
+                "SELECT id, channel_name, has_attachments FROM messages WHERE messages MATCH 'deploy'",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )
+            .unwrap();
+        assert_eq!(id, "1_1");
+        assert_eq!(channel, "general");
+        assert!(has_attachments);
+
+        let count: i64 = conn
+            .query_row("SELECT COUNT(*) FROM messages WHERE messages MATCH 'user_names:bob'", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn test_write_sqlite_fts_index_replaces_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let output = dir.path().join("index.sqlite");
+        let output = output.to_str().unwrap();
+
+        write_sqlite_fts_index(&[entry("1.1", "general", "alice", "first", false)], output, None).unwrap();
+        write_sqlite_fts_index(&[entry("2.2", "general", "alice", "second", false)], output, None).unwrap();
+
+        let conn = Connection::open(output).unwrap();
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM messages", [], |row| row.get(0)).unwrap();
+        assert_eq!(count, 1);
+    }
+}
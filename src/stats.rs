@@ -0,0 +1,642 @@
+//! Workspace analytics computed from an exported conversations.json:
+//! message counts per channel, user, weekday and hour, thread participation
+//! and attachments, and the activity heatmap and weekly timeline
+
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::fs::File;
+use std::io::BufWriter;
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::Serialize;
+
+use crate::error::{AppError, Result};
+
+/// Weekday labels in the order of `WorkspaceStats::by_weekday`
+pub const WEEKDAYS: [&str; 7] = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+
+/// Output format for `stats`
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum StatsFormat {
+    #[default]
+    Json,
+    /// One CSV file per table: channels, users and activity
+    Csv,
+}
+
+impl std::str::FromStr for StatsFormat {
+    type Err = AppError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(StatsFormat::Json),
+            "csv" => Ok(StatsFormat::Csv),
+            _ => Err(AppError::InvalidFormat(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ChannelStats {
+    pub id: String,
+    pub name: String,
+    /// Top-level messages and replies
+    pub messages: usize,
+    pub replies: usize,
+    /// Messages with at least one reply
+    pub threads: usize,
+    pub attachments: usize,
+    /// Users who posted at least once
+    pub active_users: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct UserStats {
+    pub id: String,
+    pub name: String,
+    /// Top-level messages and replies
+    pub messages: usize,
+    pub replies: usize,
+    pub threads_started: usize,
+    /// Threads the user started or replied to
+    pub threads_joined: usize,
+    /// `threads_joined` over all threads in the export
+    pub thread_participation: f64,
+    pub attachments: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct WorkspaceStats {
+    /// Top-level messages and replies
+    pub messages: usize,
+    pub replies: usize,
+    pub threads: usize,
+    pub attachments: usize,
+    /// Share of top-level messages that got replies
+    pub thread_rate: f64,
+    /// Average distinct posters per thread, the starter included
+    pub avg_thread_participants: f64,
+    /// Messages per weekday in UTC, Monday first
+    pub by_weekday: [usize; 7],
+    /// Messages per hour of the day in UTC
+    pub by_hour: [usize; 24],
+    /// Sorted by messages, busiest first
+    pub channels: Vec<ChannelStats>,
+    /// Sorted by messages, busiest first
+    pub users: Vec<UserStats>,
+}
+
+fn ratio(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64
+    }
+}
+
+fn file_count(message: &serde_json::Value) -> usize {
+    message
+        .get("files")
+        .and_then(|f| f.as_array())
+        .map_or(0, |files| files.len())
+}
+
+/// When `post` was sent, from its Slack `ts`
+pub(crate) fn post_time(post: &serde_json::Value) -> Option<DateTime<Utc>> {
+    post.get("ts")
+        .and_then(|v| v.as_str())
+        .and_then(|ts| ts.split('.').next())
+        .and_then(|secs| secs.parse::<i64>().ok())
+        .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+}
+
+/// Compute the statistics of `conversations` (the contents of a
+/// conversations.json). `user_names` maps user IDs to display names; users
+/// missing from it are shown by ID.
+pub fn compute_stats(conversations: &[serde_json::Value], user_names: &HashMap<String, String>) -> WorkspaceStats {
+    let mut stats = WorkspaceStats::default();
+    let mut users: HashMap<String, UserStats> = HashMap::new();
+    let mut top_level = 0;
+    let mut thread_participants = 0;
+
+    for conversation in conversations {
+        let id = conversation
+            .get("channel_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let mut channel = ChannelStats {
+            id: id.to_string(),
+            name: conversation
+                .get("channel_name")
+                .and_then(|v| v.as_str())
+                .unwrap_or(id)
+                .to_string(),
+            ..ChannelStats::default()
+        };
+        let mut posters = HashSet::new();
+
+        let messages = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        for message in messages {
+            let replies = message
+                .get("thread_replies")
+                .and_then(|r| r.as_array())
+                .map(|a| a.as_slice())
+                .unwrap_or_default();
+            top_level += 1;
+            let mut participants = HashSet::new();
+
+            for (position, post) in std::iter::once(message).chain(replies).enumerate() {
+                let is_reply = position > 0;
+                let files = file_count(post);
+                channel.messages += 1;
+                channel.replies += usize::from(is_reply);
+                channel.attachments += files;
+
+                if let Some(time) = post_time(post) {
+                    let weekday = time.weekday().num_days_from_monday() as usize;
+                    if let Some(count) = stats.by_weekday.get_mut(weekday) {
+                        *count += 1;
+                    }
+                    if let Some(count) = stats.by_hour.get_mut(time.hour() as usize) {
+                        *count += 1;
+                    }
+                }
+
+                let Some(user_id) = post.get("user").and_then(|u| u.as_str()) else {
+                    continue;
+                };
+                posters.insert(user_id);
+                participants.insert(user_id);
+                let user = users.entry(user_id.to_string()).or_insert_with(|| UserStats {
+                    id: user_id.to_string(),
+                    name: user_names.get(user_id).cloned().unwrap_or_else(|| user_id.to_string()),
+                    ..UserStats::default()
+                });
+                user.messages += 1;
+                user.replies += usize::from(is_reply);
+                user.attachments += files;
+                user.threads_started += usize::from(!is_reply && !replies.is_empty());
+            }
+
+            if !replies.is_empty() {
+                channel.threads += 1;
+                thread_participants += participants.len();
+                for user_id in participants {
+                    if let Some(user) = users.get_mut(user_id) {
+                        user.threads_joined += 1;
+                    }
+                }
+            }
+        }
+
+        channel.active_users = posters.len();
+        stats.messages += channel.messages;
+        stats.replies += channel.replies;
+        stats.threads += channel.threads;
+        stats.attachments += channel.attachments;
+        stats.channels.push(channel);
+    }
+
+    stats.thread_rate = ratio(stats.threads, top_level);
+    stats.avg_thread_participants = ratio(thread_participants, stats.threads);
+    stats.channels.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.name.cmp(&b.name)));
+    stats.users = users
+        .into_values()
+        .map(|mut user| {
+            user.thread_participation = ratio(user.threads_joined, stats.threads);
+            user
+        })
+        .collect();
+    stats.users.sort_by(|a, b| b.messages.cmp(&a.messages).then_with(|| a.name.cmp(&b.name)));
+    stats
+}
+
+/// Display names by user ID from a users.json or users.parquet, empty
+/// without one
+pub(crate) fn load_user_names(users_path: Option<&str>) -> Result<HashMap<String, String>> {
+    let Some(path) = users_path else {
+        return Ok(HashMap::new());
+    };
+    let users = crate::archive_input::load_users(path)?;
+    Ok(users
+        .iter()
+        .filter_map(|user| {
+            let id = user.get("id")?.as_str()?;
+            let name = user
+                .get("profile")
+                .and_then(|p| p.get("display_name"))
+                .and_then(|n| n.as_str())
+                .filter(|s| !s.is_empty())
+                .or_else(|| user.get("name").and_then(|n| n.as_str()))
+                .unwrap_or(id);
+            Some((id.to_string(), name.to_string()))
+        })
+        .collect())
+}
+
+/// Load `conversations_path` and, when given, the user names from
+/// `users_path`, and compute their statistics
+pub fn load_stats(conversations_path: &str, users_path: Option<&str>) -> Result<WorkspaceStats> {
+    let conversations = crate::archive_input::load_conversations(conversations_path)?;
+    let user_names = load_user_names(users_path)?;
+    Ok(compute_stats(&conversations, &user_names))
+}
+
+/// Quote a CSV field when it holds a separator, quote or newline
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The three CSV tables of `stats`: channels, users and activity (one row
+/// per weekday and per hour)
+pub fn stats_csv_tables(stats: &WorkspaceStats) -> [(&'static str, String); 3] {
+    let mut channels = String::from("id,name,messages,replies,threads,attachments,active_users\n");
+    for c in &stats.channels {
+        let _ = writeln!(
+            channels,
+            "{},{},{},{},{},{},{}",
+            csv_field(&c.id),
+            csv_field(&c.name),
+            c.messages,
+            c.replies,
+            c.threads,
+            c.attachments,
+            c.active_users
+        );
+    }
+
+    let mut users =
+        String::from("id,name,messages,replies,threads_started,threads_joined,thread_participation,attachments\n");
+    for u in &stats.users {
+        let _ = writeln!(
+            users,
+            "{},{},{},{},{},{},{:.4},{}",
+            csv_field(&u.id),
+            csv_field(&u.name),
+            u.messages,
+            u.replies,
+            u.threads_started,
+            u.threads_joined,
+            u.thread_participation,
+            u.attachments
+        );
+    }
+
+    let mut activity = String::from("period,slot,messages\n");
+    for (day, count) in WEEKDAYS.iter().zip(stats.by_weekday) {
+        let _ = writeln!(activity, "weekday,{},{}", day, count);
+    }
+    for (hour, count) in stats.by_hour.iter().enumerate() {
+        let _ = writeln!(activity, "hour,{:02},{}", hour, count);
+    }
+
+    [("channels", channels), ("users", users), ("activity", activity)]
+}
+
+/// Write `stats` as `<output>.json`, or for CSV as `<output>-channels.csv`,
+/// `<output>-users.csv` and `<output>-activity.csv`. Returns the files written.
+pub fn write_stats(stats: &WorkspaceStats, output: &str, format: StatsFormat) -> Result<Vec<String>> {
+    match format {
+        StatsFormat::Json => {
+            let path = format!("{}.json", output);
+            let file = File::create(&path).map_err(|e| AppError::WriteFile {
+                path: path.clone(),
+                source: e,
+            })?;
+            serde_json::to_writer_pretty(BufWriter::new(file), stats)
+                .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+            Ok(vec![path])
+        }
+        StatsFormat::Csv => stats_csv_tables(stats)
+            .into_iter()
+            .map(|(table, content)| {
+                let path = format!("{}-{}.csv", output, table);
+                std::fs::write(&path, content).map_err(|e| AppError::WriteFile {
+                    path: path.clone(),
+                    source: e,
+                })?;
+                Ok(path)
+            })
+            .collect(),
+    }
+}
+
+/// Markdown report of `stats`, listing at most `top` channels and users
+pub fn stats_markdown_report(stats: &WorkspaceStats, top: usize) -> String {
+    let mut md = String::from("# Workspace statistics\n\n");
+    let _ = writeln!(md, "- Messages: {} ({} replies)", stats.messages, stats.replies);
+    let _ = writeln!(
+        md,
+        "- Threads: {} ({:.1}% of messages get replies, {:.1} participants on average)",
+        stats.threads,
+        stats.thread_rate * 100.0,
+        stats.avg_thread_participants
+    );
+    let _ = writeln!(md, "- Attachments: {}", stats.attachments);
+    let _ = writeln!(md, "- Channels: {}, active users: {}", stats.channels.len(), stats.users.len());
+
+    md.push_str("\n## Channels\n\n| Channel | Messages | Replies | Threads | Attachments | Active users |\n");
+    md.push_str("|---|---:|---:|---:|---:|---:|\n");
+    for c in stats.channels.iter().take(top) {
+        let _ = writeln!(
+            md,
+            "| #{} | {} | {} | {} | {} | {} |",
+            c.name, c.messages, c.replies, c.threads, c.attachments, c.active_users
+        );
+    }
+
+    md.push_str("\n## Users\n\n| User | Messages | Replies | Threads started | Thread participation | Attachments |\n");
+    md.push_str("|---|---:|---:|---:|---:|---:|\n");
+    for u in stats.users.iter().take(top) {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {} | {} | {:.1}% | {} |",
+            u.name,
+            u.messages,
+            u.replies,
+            u.threads_started,
+            u.thread_participation * 100.0,
+            u.attachments
+        );
+    }
+
+    md.push_str("\n## Activity (UTC)\n\n| Weekday | Messages |\n|---|---:|\n");
+    for (day, count) in WEEKDAYS.iter().zip(stats.by_weekday) {
+        let _ = writeln!(md, "| {} | {} |", day, count);
+    }
+    md.push_str("\n| Hour | Messages |\n|---|---:|\n");
+    for (hour, count) in stats.by_hour.iter().enumerate() {
+        let _ = writeln!(md, "| {:02}:00 | {} |", hour, count);
+    }
+    md
+}
+
+/// Messages of one channel per week of `ActivityReport::weeks`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChannelTimeline {
+    pub id: String,
+    pub name: String,
+    pub messages: Vec<usize>,
+}
+
+/// When a workspace is busy, ready for plotting: a weekday × hour heatmap and
+/// a weekly message series per channel
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ActivityReport {
+    /// Messages per weekday (Monday first) and hour, in UTC
+    pub heatmap: [[usize; 24]; 7],
+    /// Monday of every week from the first message to the last, YYYY-MM-DD
+    pub weeks: Vec<String>,
+    pub channels: Vec<ChannelTimeline>,
+}
+
+/// Aggregate the messages and replies of `conversations` into an
+/// `ActivityReport`. Weeks without messages are kept with a zero count.
+pub fn compute_activity(conversations: &[serde_json::Value]) -> ActivityReport {
+    let mut heatmap = [[0; 24]; 7];
+    let mut per_channel: Vec<(String, String, HashMap<NaiveDate, usize>)> = Vec::new();
+
+    for conversation in conversations {
+        let id = conversation
+            .get("channel_id")
+            .and_then(|v| v.as_str())
+            .unwrap_or_default();
+        let name = conversation
+            .get("channel_name")
+            .and_then(|v| v.as_str())
+            .unwrap_or(id);
+        let mut weekly: HashMap<NaiveDate, usize> = HashMap::new();
+
+        let messages = conversation
+            .get("messages")
+            .and_then(|m| m.as_array())
+            .map(|a| a.as_slice())
+            .unwrap_or_default();
+        let replies = messages
+            .iter()
+            .filter_map(|m| m.get("thread_replies").and_then(|r| r.as_array()))
+            .flatten();
+        for time in messages.iter().chain(replies).filter_map(post_time) {
+            let weekday = time.weekday().num_days_from_monday() as usize;
+            if let Some(count) = heatmap
+                .get_mut(weekday)
+                .and_then(|day| day.get_mut(time.hour() as usize))
+            {
+                *count += 1;
+            }
+            let monday = time.date_naive() - Duration::days(weekday as i64);
+            *weekly.entry(monday).or_default() += 1;
+        }
+        per_channel.push((id.to_string(), name.to_string(), weekly));
+    }
+
+    let mondays = per_channel.iter().flat_map(|(_, _, weekly)| weekly.keys());
+    let weeks: Vec<NaiveDate> = match (mondays.clone().min(), mondays.max()) {
+        (Some(&first), Some(&last)) => first
+            .iter_weeks()
+            .take_while(|monday| *monday <= last)
+            .collect(),
+        _ => Vec::new(),
+    };
+
+    ActivityReport {
+        heatmap,
+        weeks: weeks.iter().map(|monday| monday.to_string()).collect(),
+        channels: per_channel
+            .into_iter()
+            .map(|(id, name, weekly)| ChannelTimeline {
+                id,
+                name,
+                messages: weeks
+                    .iter()
+                    .map(|monday| weekly.get(monday).copied().unwrap_or(0))
+                    .collect(),
+            })
+            .collect(),
+    }
+}
+
+/// Render `heatmap` as an SVG grid, one row per weekday and one column per
+/// hour, darker for busier slots; each cell has a tooltip with its count
+pub fn heatmap_svg(heatmap: &[[usize; 24]; 7]) -> String {
+    const CELL: usize = 24;
+    const LEFT: usize = 40;
+    const TOP: usize = 20;
+    let max = heatmap.iter().flatten().copied().max().unwrap_or(0).max(1);
+    let width = LEFT + 24 * CELL;
+    let height = TOP + 7 * CELL;
+
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{width}\" height=\"{height}\" \
+         font-family=\"sans-serif\" font-size=\"10\">\n"
+    );
+    for hour in (0..24).step_by(3) {
+        let _ = writeln!(
+            svg,
+            "  <text x=\"{}\" y=\"{}\" text-anchor=\"middle\">{:02}</text>",
+            LEFT + hour * CELL + CELL / 2,
+            TOP - 6,
+            hour
+        );
+    }
+    for (row, (day, hours)) in WEEKDAYS.iter().zip(heatmap).enumerate() {
+        let y = TOP + row * CELL;
+        let _ = writeln!(svg, "  <text x=\"4\" y=\"{}\">{}</text>", y + CELL / 2 + 4, day);
+        for (hour, &count) in hours.iter().enumerate() {
+            // Empty slots stay visible, the busiest one is fully opaque
+            let opacity = 0.05 + 0.95 * count as f64 / max as f64;
+            let _ = writeln!(
+                svg,
+                "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#1f6feb\" \
+                 fill-opacity=\"{:.3}\"><title>{} {:02}:00 {}</title></rect>",
+                LEFT + hour * CELL,
+                y,
+                CELL - 2,
+                CELL - 2,
+                opacity,
+                day,
+                hour,
+                count
+            );
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Write `report` as JSON to `output_path` and, when given, its heatmap as
+/// SVG to `svg_path`
+pub fn write_activity(report: &ActivityReport, output_path: &str, svg_path: Option<&str>) -> Result<()> {
+    let file = File::create(output_path).map_err(|e| AppError::WriteFile {
+        path: output_path.to_string(),
+        source: e,
+    })?;
+    serde_json::to_writer_pretty(BufWriter::new(file), report)
+        .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+    if let Some(svg_path) = svg_path {
+        std::fs::write(svg_path, heatmap_svg(&report.heatmap)).map_err(|e| AppError::WriteFile {
+            path: svg_path.to_string(),
+            source: e,
+        })?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    // 2024-01-01 is a Monday; 1704103200 is 10:00 UTC that day
+    fn sample() -> Vec<serde_json::Value> {
+        vec![
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {
+                        "ts": "1704103200.000100",
+                        "user": "U1",
+                        "files": [{"id": "F1"}],
+                        "thread_replies": [
+                            {"ts": "1704103260.000100", "user": "U2"},
+                            {"ts": "1704103320.000100", "user": "U1"}
+                        ]
+                    },
+                    {"ts": "1704189600.000100", "user": "U2"}
+                ]
+            }),
+            json!({
+                "channel_id": "C2",
+                "messages": [{"ts": "1704103200.000200", "user": "U3", "files": [{"id": "F2"}, {"id": "F3"}]}]
+            }),
+        ]
+    }
+
+    #[test]
+    fn test_compute_stats() {
+        let names = HashMap::from([("U1".to_string(), "ana".to_string())]);
+        let stats = compute_stats(&sample(), &names);
+
+        assert_eq!(stats.messages, 5);
+        assert_eq!(stats.replies, 2);
+        assert_eq!(stats.threads, 1);
+        assert_eq!(stats.attachments, 3);
+        assert!((stats.thread_rate - 1.0 / 3.0).abs() < 1e-9);
+        assert!((stats.avg_thread_participants - 2.0).abs() < 1e-9);
+        assert_eq!(stats.by_weekday, [4, 1, 0, 0, 0, 0, 0]);
+        assert_eq!(stats.by_hour[10], 5);
+
+        assert_eq!(stats.channels[0].name, "general");
+        assert_eq!(stats.channels[0].messages, 4);
+        assert_eq!(stats.channels[0].active_users, 2);
+        assert_eq!(stats.channels[1].name, "C2");
+
+        let ana = stats.users.iter().find(|u| u.id == "U1").unwrap();
+        assert_eq!((ana.name.as_str(), ana.messages, ana.replies), ("ana", 2, 1));
+        assert_eq!((ana.threads_started, ana.threads_joined, ana.attachments), (1, 1, 1));
+        assert!((ana.thread_participation - 1.0).abs() < 1e-9);
+        let u3 = stats.users.iter().find(|u| u.id == "U3").unwrap();
+        assert_eq!((u3.name.as_str(), u3.threads_joined), ("U3", 0));
+    }
+
+    #[test]
+    fn test_stats_csv_tables() {
+        let mut stats = compute_stats(&sample(), &HashMap::new());
+        stats.channels[0].name = "a,b".to_string();
+        let [(_, channels), (_, users), (_, activity)] = stats_csv_tables(&stats);
+
+        assert_eq!(channels.lines().nth(1), Some("C1,\"a,b\",4,2,1,1,2"));
+        assert!(users.starts_with("id,name,messages"));
+        assert!(activity.contains("weekday,Mon,4\n"));
+        assert!(activity.contains("hour,10,5\n"));
+    }
+
+    #[test]
+    fn test_stats_format_from_str() {
+        assert_eq!("CSV".parse::<StatsFormat>().unwrap(), StatsFormat::Csv);
+        assert!(matches!("xml".parse::<StatsFormat>(), Err(AppError::InvalidFormat(_))));
+    }
+
+    #[test]
+    fn test_compute_activity() {
+        // 1705312800 is Monday 2024-01-15 10:00 UTC, two weeks after the sample
+        let mut conversations = sample();
+        conversations.push(json!({
+            "channel_id": "C3",
+            "channel_name": "late",
+            "messages": [{"ts": "1705312800.000100", "user": "U1"}]
+        }));
+        let report = compute_activity(&conversations);
+
+        assert_eq!(report.heatmap[0][10], 5);
+        assert_eq!(report.heatmap[1][10], 1);
+        assert_eq!(report.heatmap.iter().flatten().sum::<usize>(), 6);
+        assert_eq!(report.weeks, ["2024-01-01", "2024-01-08", "2024-01-15"]);
+        assert_eq!(report.channels[0].messages, [4, 0, 0]);
+        assert_eq!(report.channels[1].messages, [1, 0, 0]);
+        assert_eq!(report.channels[2].name, "late");
+        assert_eq!(report.channels[2].messages, [0, 0, 1]);
+
+        assert!(compute_activity(&[]).weeks.is_empty());
+    }
+
+    #[test]
+    fn test_heatmap_svg() {
+        let mut heatmap = [[0; 24]; 7];
+        heatmap[0][10] = 4;
+        let svg = heatmap_svg(&heatmap);
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), 7 * 24);
+        assert!(svg.contains("fill-opacity=\"1.000\"><title>Mon 10:00 4</title>"));
+        assert!(svg.contains("<title>Sun 23:00 0</title>"));
+    }
+}
@@ -0,0 +1,174 @@
+//! `-` as a file path, so file based commands can be chained in shell
+//! pipelines: standard input is copied to a temporary file for the commands
+//! that read a path (possibly more than once), and output written to a
+//! temporary file is copied to standard output once complete. Input paths
+//! can also name an archive week (`archive:2024/W07`), extracted the same
+//! way.
+
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+
+use tempfile::NamedTempFile;
+
+use crate::archive_input::ArchiveWeek;
+use crate::error::{AppError, Result};
+use crate::summary::Reporter;
+
+/// The path that stands for standard input or output
+pub const STDIO_PATH: &str = "-";
+
+pub fn is_stdio(path: &str) -> bool {
+    path == STDIO_PATH
+}
+
+/// A command's input path, with `-` replaced by a copy of standard input
+/// and an `archive:` week by its conversations, kept until this is dropped
+pub struct InputPath {
+    path: String,
+    _spool: Option<NamedTempFile>,
+}
+
+impl InputPath {
+    pub fn new(path: &str) -> Result<Self> {
+        if let Some(week) = ArchiveWeek::parse(path) {
+            let conversations = serde_json::to_vec(&week?.conversations()?)
+                .map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+            return Self::spooled(&conversations[..]);
+        }
+        if !is_stdio(path) {
+            return Ok(InputPath {
+                path: path.to_string(),
+                _spool: None,
+            });
+        }
+        Self::spooled(io::stdin().lock())
+    }
+
+    fn spooled(mut reader: impl Read) -> Result<Self> {
+        let mut spool = NamedTempFile::new().map_err(stdin_error)?;
+        io::copy(&mut reader, &mut spool).map_err(stdin_error)?;
+        spool.flush().map_err(stdin_error)?;
+        Ok(InputPath {
+            path: spool.path().to_string_lossy().to_string(),
+            _spool: Some(spool),
+        })
+    }
+
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+}
+
+fn stdin_error(source: io::Error) -> AppError {
+    AppError::ReadFile {
+        path: "<stdin>".to_string(),
+        source,
+    }
+}
+
+fn stdout_error(source: io::Error) -> AppError {
+    AppError::WriteFile {
+        path: "<stdout>".to_string(),
+        source,
+    }
+}
+
+/// A command's output path, with `-` replaced by a temporary file that
+/// `finish` copies to standard output. Messages go to stderr meanwhile.
+pub struct OutputPath {
+    path: String,
+    spool: Option<NamedTempFile>,
+}
+
+impl OutputPath {
+    pub fn new(path: &str, reporter: &mut Reporter) -> Result<Self> {
+        if !is_stdio(path) {
+            return Ok(OutputPath {
+                path: path.to_string(),
+                spool: None,
+            });
+        }
+        reporter.reserve_stdout();
+        let spool = NamedTempFile::new().map_err(stdout_error)?;
+        Ok(OutputPath {
+            path: spool.path().to_string_lossy().to_string(),
+            spool: Some(spool),
+        })
+    }
+
+    /// Where the command writes
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Copy the output to standard output when it was `-`
+    pub fn finish(self) -> Result<()> {
+        self.finish_to(&mut io::stdout().lock())
+    }
+
+    fn finish_to(self, out: &mut impl Write) -> Result<()> {
+        let Some(spool) = self.spool else {
+            return Ok(());
+        };
+        // Writers may have replaced the file, so it is opened again by path
+        let mut written = File::open(spool.path()).map_err(stdout_error)?;
+        io::copy(&mut written, out).map_err(stdout_error)?;
+        out.flush().map_err(stdout_error)
+    }
+}
+
+/// The text of a file, or of standard input for `-`
+pub fn read_input(path: &str) -> Result<String> {
+    let read = if is_stdio(path) {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content).map(|_| content)
+    } else {
+        fs::read_to_string(path)
+    };
+    read.map_err(|e| AppError::ReadFile {
+        path: path.to_string(),
+        source: e,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_paths_pass_through() {
+        let input = InputPath::new("conversations.json").unwrap();
+        assert_eq!(input.path(), "conversations.json");
+        let mut reporter = Reporter::default();
+        let output = OutputPath::new("index.json", &mut reporter).unwrap();
+        assert!(!reporter.messages_to_stderr());
+        assert_eq!(output.path(), "index.json");
+        let mut out = Vec::new();
+        output.finish_to(&mut out).unwrap();
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_spooled_input() {
+        let input = InputPath::spooled(&b"[{\"channel_id\": \"C1\"}]"[..]).unwrap();
+        let spooled = fs::read_to_string(input.path()).unwrap();
+        assert_eq!(spooled, "[{\"channel_id\": \"C1\"}]");
+        // Read as often as needed while the input lives
+        assert_eq!(fs::read_to_string(input.path()).unwrap(), spooled);
+        let path = input.path().to_string();
+        drop(input);
+        assert!(!std::path::Path::new(&path).exists());
+    }
+
+    #[test]
+    fn test_stdout_output() {
+        let mut reporter = Reporter::default();
+        let output = OutputPath::new(STDIO_PATH, &mut reporter).unwrap();
+        assert!(reporter.messages_to_stderr());
+        assert_ne!(output.path(), STDIO_PATH);
+        fs::write(output.path(), "# general\n").unwrap();
+        let mut out = Vec::new();
+        output.finish_to(&mut out).unwrap();
+        assert_eq!(out, b"# general\n");
+    }
+}
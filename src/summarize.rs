@@ -0,0 +1,358 @@
+//! Summaries of each channel's week of an archive by an external summarizer
+//! (a script or an HTTP endpoint, e.g. in front of an LLM), stored next to
+//! the week's parquet file
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::{Deserialize, Serialize};
+
+use crate::error::{AppError, Result};
+use crate::extract::{archived_weeks, extract_week};
+use crate::formatter::MarkdownExportOptions;
+use crate::markdown::render_conversation_values_to_markdown;
+use crate::ProgressCallback;
+
+/// Marks the summaries inserted at the top of a markdown file, so running
+/// again replaces them
+const SUMMARY_START: &str = "<!-- summaries -->";
+const SUMMARY_END: &str = "<!-- /summaries -->";
+
+/// Where the rendered markdown goes
+#[derive(Debug, Clone, PartialEq)]
+pub enum Summarizer {
+    /// Executable that gets the markdown on stdin, the channel and week in
+    /// `SUMMARY_CHANNEL_ID`, `SUMMARY_CHANNEL_NAME` and `SUMMARY_WEEK`, and
+    /// prints the summary
+    Script(String),
+    /// URL that gets a JSON `SummaryRequest` POSTed and answers
+    /// `{"summary": "..."}`
+    Endpoint(String),
+}
+
+/// What the summarizer gets for one channel's week
+#[derive(Debug, Clone, Serialize)]
+pub struct SummaryRequest<'a> {
+    pub channel_id: &'a str,
+    pub channel_name: &'a str,
+    /// `YYYY-Www`
+    pub week: &'a str,
+    pub markdown: &'a str,
+}
+
+#[derive(Debug, Deserialize)]
+struct SummaryResponse {
+    summary: String,
+}
+
+/// A channel's week summary, as stored in `summaries.json`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChannelSummary {
+    pub channel_id: String,
+    pub channel_name: String,
+    /// Top-level messages summarized
+    pub messages: usize,
+    pub summary: String,
+}
+
+/// What to summarize and where the results go
+#[derive(Debug, Clone)]
+pub struct SummarizeOptions {
+    /// Archive directory of year=*/week=* partitions
+    pub archive: String,
+    pub users: String,
+    pub channels: String,
+    pub summarizer: Summarizer,
+    /// Only weeks of this ISO year, and of this week when also given
+    pub year: Option<i32>,
+    pub week: Option<i32>,
+    /// Directory of `YYYY-Www.md` files to put each week's summaries at the
+    /// top of
+    pub markdown_dir: Option<String>,
+    /// Summarize channels that already have a summary again
+    pub force: bool,
+}
+
+/// What `summarize_archive` did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SummarizeResult {
+    pub weeks: usize,
+    pub summarized: usize,
+    /// Channels with a summary from an earlier run
+    pub skipped: usize,
+    /// Markdown files the summaries were put at the top of
+    pub markdown_files: usize,
+}
+
+/// `summaries.json` next to a week's `threads.parquet`
+pub fn summaries_path(week_file: &Path) -> PathBuf {
+    week_file.with_file_name("summaries.json")
+}
+
+fn load_summaries(path: &Path) -> Result<Vec<ChannelSummary>> {
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    crate::load_json_file(&path.to_string_lossy())
+}
+
+/// Run the summarizer on one channel's week
+pub async fn request_summary(summarizer: &Summarizer, request: &SummaryRequest<'_>) -> Result<String> {
+    let summary = match summarizer {
+        Summarizer::Script(script) => {
+            let mut child = Command::new(script)
+                .env("SUMMARY_CHANNEL_ID", request.channel_id)
+                .env("SUMMARY_CHANNEL_NAME", request.channel_name)
+                .env("SUMMARY_WEEK", request.week)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| AppError::Summarizer(format!("failed to spawn {}: {}", script, e)))?;
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin
+                    .write_all(request.markdown.as_bytes())
+                    .map_err(|e| AppError::Summarizer(format!("failed to write to stdin: {}", e)))?;
+            }
+            let output = child
+                .wait_with_output()
+                .map_err(|e| AppError::Summarizer(format!("failed to wait for {}: {}", script, e)))?;
+            if !output.status.success() {
+                return Err(AppError::Summarizer(format!(
+                    "{} exited with status {}: {}",
+                    script,
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                )));
+            }
+            String::from_utf8_lossy(&output.stdout).into_owned()
+        }
+        Summarizer::Endpoint(url) => {
+            let response = reqwest::Client::new()
+                .post(url)
+                .json(request)
+                .send()
+                .await
+                .map_err(|e| AppError::Summarizer(e.to_string()))?;
+            if !response.status().is_success() {
+                return Err(AppError::Summarizer(format!("{}: HTTP {}", url, response.status())));
+            }
+            let response: SummaryResponse = response
+                .json()
+                .await
+                .map_err(|e| AppError::Summarizer(format!("{}: invalid response: {}", url, e)))?;
+            response.summary
+        }
+    };
+    let summary = summary.trim();
+    if summary.is_empty() {
+        return Err(AppError::Summarizer(format!(
+            "empty summary for #{} {}",
+            request.channel_name, request.week
+        )));
+    }
+    Ok(summary.to_string())
+}
+
+/// The summaries block put at the top of a week's markdown
+pub fn summaries_markdown(summaries: &[ChannelSummary]) -> String {
+    let mut block = format!("{}\n## Summary\n", SUMMARY_START);
+    for summary in summaries {
+        block.push_str(&format!("\n### #{}\n\n{}\n", summary.channel_name, summary.summary));
+    }
+    block.push_str(&format!("\n{}\n\n", SUMMARY_END));
+    block
+}
+
+/// `markdown` with `block` at the top, replacing the block of an earlier run;
+/// a leading `# ` heading stays first
+pub fn insert_summaries(markdown: &str, block: &str) -> String {
+    let markdown = match (markdown.find(SUMMARY_START), markdown.find(SUMMARY_END)) {
+        (Some(start), Some(end)) if start < end => {
+            let after = markdown.get(end + SUMMARY_END.len()..).unwrap_or_default().trim_start_matches('\n');
+            format!("{}{}", markdown.get(..start).unwrap_or_default(), after)
+        }
+        _ => markdown.to_string(),
+    };
+    if markdown.starts_with("# ")
+        && let Some((heading, rest)) = markdown.split_once('\n')
+    {
+        return format!("{}\n\n{}{}", heading, block, rest.trim_start_matches('\n'));
+    }
+    format!("{}{}", block, markdown)
+}
+
+/// Summarize every channel of every archived week (or the weeks selected in
+/// `options`), storing the summaries in each week's `summaries.json` as they
+/// come in so an interrupted run keeps what it got
+pub async fn summarize_archive(options: &SummarizeOptions, progress_callback: ProgressCallback<'_>) -> Result<SummarizeResult> {
+    let weeks: Vec<(i32, i32, PathBuf)> = archived_weeks(Path::new(&options.archive))
+        .into_iter()
+        .filter(|(year, week, _)| options.year.is_none_or(|y| y == *year) && options.week.is_none_or(|w| w == *week))
+        .collect();
+    if weeks.is_empty() {
+        return Err(AppError::MissingInput(format!("{}/year=*/week=*/threads.parquet", options.archive)));
+    }
+
+    let markdown_options = MarkdownExportOptions::new();
+    let mut result = SummarizeResult {
+        weeks: weeks.len(),
+        ..SummarizeResult::default()
+    };
+    for (idx, (year, week, week_dir)) in weeks.iter().enumerate() {
+        let week_name = format!("{}-W{:02}", year, week);
+        let path = summaries_path(&week_dir.join("threads.parquet"));
+        let mut summaries = load_summaries(&path)?;
+        let extracted = extract_week(Path::new(&options.archive), *year, *week as u32)?;
+
+        for conversation in &extracted.conversations {
+            let text = |key: &str| conversation.get(key).and_then(|v| v.as_str()).unwrap_or_default();
+            let (channel_id, channel_name) = (text("channel_id"), text("channel_name"));
+            let existing = summaries.iter().position(|s| s.channel_id == channel_id);
+            if existing.is_some() && !options.force {
+                result.skipped += 1;
+                continue;
+            }
+            if let Some(cb) = progress_callback {
+                cb(idx + 1, weeks.len(), &format!("{} #{}", week_name, channel_name));
+            }
+
+            let markdown = render_conversation_values_to_markdown(
+                std::slice::from_ref(conversation),
+                &options.users,
+                &options.channels,
+                &markdown_options,
+            )?;
+            let request = SummaryRequest {
+                channel_id,
+                channel_name,
+                week: &week_name,
+                markdown: &markdown,
+            };
+            let summary = ChannelSummary {
+                channel_id: channel_id.to_string(),
+                channel_name: channel_name.to_string(),
+                messages: conversation.get("messages").and_then(|m| m.as_array()).map_or(0, |m| m.len()),
+                summary: request_summary(&options.summarizer, &request).await?,
+            };
+            match existing.and_then(|idx| summaries.get_mut(idx)) {
+                Some(slot) => *slot = summary,
+                None => summaries.push(summary),
+            }
+            crate::slack::write_json(&path, &summaries)?;
+            result.summarized += 1;
+        }
+
+        if let Some(dir) = &options.markdown_dir
+            && !summaries.is_empty()
+        {
+            let markdown_path = Path::new(dir).join(format!("{}.md", week_name));
+            if markdown_path.is_file() {
+                let markdown = std::fs::read_to_string(&markdown_path).map_err(|e| AppError::ReadFile {
+                    path: markdown_path.display().to_string(),
+                    source: e,
+                })?;
+                let updated = insert_summaries(&markdown, &summaries_markdown(&summaries));
+                std::fs::write(&markdown_path, updated).map_err(|e| AppError::WriteFile {
+                    path: markdown_path.display().to_string(),
+                    source: e,
+                })?;
+                result.markdown_files += 1;
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn summaries() -> Vec<ChannelSummary> {
+        vec![ChannelSummary {
+            channel_id: "C1".to_string(),
+            channel_name: "general".to_string(),
+            messages: 2,
+            summary: "Deploy went out.".to_string(),
+        }]
+    }
+
+    #[test]
+    fn test_insert_summaries() {
+        let block = summaries_markdown(&summaries());
+        assert_eq!(
+            block,
+            "<!-- summaries -->\n## Summary\n\n### #general\n\nDeploy went out.\n\n<!-- /summaries -->\n\n"
+        );
+
+        let markdown = "# 2024-W03\n\n# general\n\nhello\n";
+        let once = insert_summaries(markdown, &block);
+        assert!(once.starts_with("# 2024-W03\n\n<!-- summaries -->"));
+        assert!(once.ends_with("<!-- /summaries -->\n\n# general\n\nhello\n"));
+        // Running again replaces the block instead of adding another
+        assert_eq!(insert_summaries(&once, &block), once);
+
+        assert_eq!(insert_summaries("hello\n", &block), format!("{}hello\n", block));
+    }
+
+    #[test]
+    fn test_summaries_path() {
+        assert_eq!(
+            summaries_path(Path::new("archive/year=2024/week=03/threads.parquet")),
+            PathBuf::from("archive/year=2024/week=03/summaries.json")
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_summarize_archive_with_script() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let archive = dir.path().join("archive");
+        // 1705312800 is 2024-01-15 (W03)
+        let conversations = vec![serde_json::json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1705312800.000100", "user": "U1", "text": "deploy done"}]
+        })];
+        crate::parquet::write_conversations_parquet(&archive, &conversations).unwrap();
+        let users = dir.path().join("users.json");
+        std::fs::write(&users, r#"[{"id": "U1", "name": "ana"}]"#).unwrap();
+        let channels = dir.path().join("channels.json");
+        std::fs::write(&channels, r#"[{"id": "C1", "name": "general"}]"#).unwrap();
+        let script = dir.path().join("summarize.sh");
+        std::fs::write(&script, "#!/bin/sh\necho \"$SUMMARY_WEEK #$SUMMARY_CHANNEL_NAME: $(wc -l) lines\"\n").unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let markdown_dir = dir.path().join("weeks");
+        std::fs::create_dir_all(&markdown_dir).unwrap();
+        std::fs::write(markdown_dir.join("2024-W03.md"), "# 2024-W03\n\nbody\n").unwrap();
+
+        let mut options = SummarizeOptions {
+            archive: archive.to_string_lossy().to_string(),
+            users: users.to_string_lossy().to_string(),
+            channels: channels.to_string_lossy().to_string(),
+            summarizer: Summarizer::Script(script.to_string_lossy().to_string()),
+            year: Some(2024),
+            week: None,
+            markdown_dir: Some(markdown_dir.to_string_lossy().to_string()),
+            force: false,
+        };
+        let result = summarize_archive(&options, None).await.unwrap();
+        assert_eq!((result.weeks, result.summarized, result.skipped, result.markdown_files), (1, 1, 0, 1));
+
+        let stored: Vec<ChannelSummary> =
+            crate::load_json_file(&archive.join("year=2024/week=03/summaries.json").to_string_lossy()).unwrap();
+        assert!(stored[0].summary.starts_with("2024-W03 #general: "));
+        let markdown = std::fs::read_to_string(markdown_dir.join("2024-W03.md")).unwrap();
+        assert!(markdown.contains("### #general"));
+
+        // Already summarized channels are skipped unless forced
+        let again = summarize_archive(&options, None).await.unwrap();
+        assert_eq!((again.summarized, again.skipped), (0, 1));
+        options.force = true;
+        let forced = summarize_archive(&options, None).await.unwrap();
+        assert_eq!((forced.summarized, forced.skipped), (1, 0));
+    }
+}
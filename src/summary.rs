@@ -0,0 +1,177 @@
+//! Machine readable result of a CLI command (`--json`): what it wrote, how
+//! much, how long it took and what failed, printed to stdout as one JSON
+//! object once the command ends. The human messages go to stderr instead, so
+//! stdout holds nothing but the summary, as they do when a command writes
+//! its output to stdout (`-`).
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use serde::Serialize;
+
+use crate::error::Result;
+use crate::export_report::ChannelReport;
+
+/// Result of one command run
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct CommandSummary {
+    /// Subcommand name, e.g. `export-conversations`
+    pub command: String,
+    pub ok: bool,
+    pub duration_ms: u64,
+    /// Files, directories or URLs written
+    pub outputs: Vec<String>,
+    /// What the command processed, by name (`messages`, `channels`, ...)
+    pub counts: BTreeMap<String, usize>,
+    /// Channels that could not be exported
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failed_channels: Vec<ChannelReport>,
+    /// Other problems that did not stop the command (failed downloads,
+    /// validation issues)
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub failures: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Where a CLI command's messages go, and what it did for its `--json`
+/// summary. Created by `main` for each run and passed to the command.
+#[derive(Debug, Default)]
+pub struct Reporter {
+    json: bool,
+    /// The command writes its output to stdout, so nothing else can
+    stdout_reserved: bool,
+    summary: CommandSummary,
+}
+
+impl Reporter {
+    /// Human messages go to stderr under `json`, which prints a summary at the end
+    pub fn new(json: bool) -> Self {
+        Self {
+            json,
+            ..Self::default()
+        }
+    }
+
+    pub fn json_output(&self) -> bool {
+        self.json
+    }
+
+    /// Keep stdout for the command's output: messages and the summary go to
+    /// stderr from now on
+    pub(crate) fn reserve_stdout(&mut self) {
+        self.stdout_reserved = true;
+    }
+
+    /// Whether the CLI's messages go to stderr
+    pub(crate) fn messages_to_stderr(&self) -> bool {
+        self.json || self.stdout_reserved
+    }
+
+    /// `println!` for the CLI's messages, called as `say(format_args!(..))`
+    pub(crate) fn say(&self, args: std::fmt::Arguments) {
+        say(self.messages_to_stderr(), args);
+    }
+
+    /// Progress callback printing each step like `say`
+    pub(crate) fn progress(&self) -> impl Fn(usize, usize, &str) + use<> {
+        let to_stderr = self.messages_to_stderr();
+        move |current, total, name| crate::cli_progress(to_stderr, current, total, name)
+    }
+
+    pub(crate) fn record_output(&mut self, path: &str) {
+        self.summary.outputs.push(path.to_string());
+    }
+
+    /// Add `count` to the `name` count
+    pub(crate) fn record_count(&mut self, name: &str, count: usize) {
+        *self.summary.counts.entry(name.to_string()).or_default() += count;
+    }
+
+    pub(crate) fn record_failed_channels<'a>(&mut self, channels: impl IntoIterator<Item = &'a ChannelReport>) {
+        self.summary.failed_channels.extend(channels.into_iter().cloned());
+    }
+
+    pub(crate) fn record_failure(&mut self, failure: &str) {
+        self.summary.failures.push(failure.to_string());
+    }
+
+    /// The summary of what was recorded while `command` ran, ending with `result`
+    pub fn command_summary(self, command: &str, duration: Duration, result: &Result<()>) -> CommandSummary {
+        CommandSummary {
+            command: command.to_string(),
+            ok: result.is_ok(),
+            duration_ms: u64::try_from(duration.as_millis()).unwrap_or(u64::MAX),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            ..self.summary
+        }
+    }
+
+    /// Print the summary of `command` as one line of JSON, to stdout unless it
+    /// holds the command's output
+    pub fn print_summary(self, command: &str, duration: Duration, result: &Result<()>) {
+        let stdout_reserved = self.stdout_reserved;
+        let summary = self.command_summary(command, duration, result);
+        match serde_json::to_string(&summary) {
+            Ok(json) if stdout_reserved => eprintln!("{}", json),
+            Ok(json) => println!("{}", json),
+            Err(e) => eprintln!("Error: cannot write the JSON summary: {}", e),
+        }
+    }
+}
+
+/// `println!` for the CLI's messages: stderr under `--json` or when stdout
+/// holds the output, stdout otherwise
+pub(crate) fn say(to_stderr: bool, args: std::fmt::Arguments) {
+    if to_stderr {
+        eprintln!("{}", args);
+    } else {
+        println!("{}", args);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AppError;
+
+    #[test]
+    fn test_command_summary() {
+        let mut reporter = Reporter::new(true);
+        assert!(reporter.messages_to_stderr());
+        reporter.record_output("conversations.json");
+        reporter.record_count("messages", 3);
+        reporter.record_count("messages", 2);
+        reporter.record_failed_channels(&[ChannelReport::failed("C2", "random", "ratelimited".to_string())]);
+
+        let result = Err(AppError::ExportIncomplete {
+            failed: 1,
+            total: 2,
+            report: "conversations.json.report.json".to_string(),
+        });
+        let summary = reporter.command_summary("export-conversations", Duration::from_millis(1500), &result);
+        assert_eq!(summary.command, "export-conversations");
+        assert!(!summary.ok);
+        assert_eq!(summary.duration_ms, 1500);
+        assert_eq!(summary.outputs, vec!["conversations.json"]);
+        assert_eq!(summary.counts.get("messages"), Some(&5));
+        assert_eq!(summary.failed_channels.len(), 1);
+        assert!(summary.error.is_some());
+
+        let json = serde_json::to_value(&summary).unwrap();
+        assert_eq!(json["failed_channels"][0]["channel_id"], "C2");
+        assert!(json.get("failures").is_none());
+
+        let next = Reporter::new(false).command_summary("work-week", Duration::ZERO, &Ok(()));
+        assert!(next.ok && next.outputs.is_empty() && next.counts.is_empty());
+    }
+
+    #[test]
+    fn test_reserve_stdout() {
+        let mut reporter = Reporter::new(false);
+        assert!(!reporter.messages_to_stderr());
+        reporter.reserve_stdout();
+        assert!(reporter.messages_to_stderr());
+        assert!(!reporter.json_output());
+    }
+}
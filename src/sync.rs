@@ -0,0 +1,262 @@
+//! Upload a local archive to object storage, sending only the files that are
+//! new or changed since the last sync. What was uploaded is kept in a
+//! manifest of SHA-256 checksums next to the copy.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::attachments::sha256_file;
+use crate::error::{AppError, Result};
+use crate::storage::{join_key, Storage};
+use crate::ProgressCallback;
+
+/// Manifest of the copy, inside the destination prefix
+pub const SYNC_MANIFEST: &str = "sync-manifest.json";
+
+/// What to upload; each part is optional
+#[derive(Debug, Clone, Default)]
+pub struct SyncSources {
+    /// Archive directory of year=*/week=* partitions, copied to `conversations/`
+    pub conversations: Option<String>,
+    /// Users file, copied under its file name
+    pub users: Option<String>,
+    /// Channels file, copied under its file name
+    pub channels: Option<String>,
+    /// Folder written by download-attachments, copied to `attachments/`
+    pub attachments: Option<String>,
+}
+
+/// Checksum and size of an uploaded file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SyncedFile {
+    pub sha256: String,
+    pub size: u64,
+}
+
+/// Uploaded files by key, relative to the destination prefix
+pub type SyncManifest = BTreeMap<String, SyncedFile>;
+
+/// A local file and where it goes
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyncUpload {
+    pub path: PathBuf,
+    /// Key relative to the destination prefix
+    pub key: String,
+    pub file: SyncedFile,
+}
+
+/// Files to upload and how many are already up to date
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncPlan {
+    pub uploads: Vec<SyncUpload>,
+    pub unchanged: usize,
+}
+
+impl SyncPlan {
+    pub fn upload_bytes(&self) -> u64 {
+        self.uploads.iter().map(|u| u.file.size).sum()
+    }
+}
+
+/// Local files of `sources` with their destination keys, sorted by key
+fn source_files(sources: &SyncSources) -> Result<Vec<(PathBuf, String)>> {
+    let mut files = Vec::new();
+    for (dir, target, suffix) in [
+        (&sources.conversations, "conversations", Some(".parquet")),
+        (&sources.attachments, "attachments", None),
+    ] {
+        let Some(dir) = dir else { continue };
+        let dir = Path::new(dir);
+        if !dir.is_dir() {
+            return Err(AppError::MissingInput(dir.display().to_string()));
+        }
+        for entry in WalkDir::new(dir) {
+            let entry = entry.map_err(|e| AppError::ReadFile {
+                path: dir.display().to_string(),
+                source: e.into(),
+            })?;
+            let name = entry.file_name().to_string_lossy();
+            if !entry.file_type().is_file() || suffix.is_some_and(|s| !name.ends_with(s)) {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+            let key = join_key(target, &relative.to_string_lossy().replace(std::path::MAIN_SEPARATOR, "/"));
+            files.push((entry.path().to_path_buf(), key));
+        }
+    }
+    for file in [&sources.users, &sources.channels].into_iter().flatten() {
+        let path = Path::new(file);
+        if !path.is_file() {
+            return Err(AppError::MissingInput(file.clone()));
+        }
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        files.push((path.to_path_buf(), name));
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(files)
+}
+
+/// The manifest of the copy under `prefix`, empty before the first sync
+pub fn load_manifest(storage: &dyn Storage, prefix: &str) -> Result<SyncManifest> {
+    match storage.get(&join_key(prefix, SYNC_MANIFEST))? {
+        Some(data) => serde_json::from_slice(&data).map_err(|e| AppError::JsonParse(e.to_string())),
+        None => Ok(SyncManifest::new()),
+    }
+}
+
+/// Compare the local files with the manifest and the keys present under
+/// `prefix`: a file is uploaded when its checksum changed or its copy is
+/// missing
+pub fn plan_sync(
+    sources: &SyncSources,
+    storage: &dyn Storage,
+    prefix: &str,
+    progress_callback: ProgressCallback,
+) -> Result<SyncPlan> {
+    let manifest = load_manifest(storage, prefix)?;
+    let present: std::collections::HashSet<String> = storage.list(prefix)?.into_iter().collect();
+    let files = source_files(sources)?;
+
+    let mut plan = SyncPlan::default();
+    for (idx, (path, key)) in files.iter().enumerate() {
+        if let Some(cb) = progress_callback {
+            cb(idx + 1, files.len(), &format!("Checking {}", key));
+        }
+        let size = path.metadata().map(|m| m.len()).map_err(|e| AppError::ReadFile {
+            path: path.display().to_string(),
+            source: e,
+        })?;
+        let file = SyncedFile {
+            sha256: sha256_file(path)?,
+            size,
+        };
+        if manifest.get(key) == Some(&file) && present.contains(&join_key(prefix, key)) {
+            plan.unchanged += 1;
+        } else {
+            plan.uploads.push(SyncUpload {
+                path: path.clone(),
+                key: key.clone(),
+                file,
+            });
+        }
+    }
+    Ok(plan)
+}
+
+/// Upload the files of `plan` and record them in the manifest. The manifest
+/// is also written when an upload fails, so the files sent before it are not
+/// sent again.
+pub fn apply_sync(
+    plan: &SyncPlan,
+    storage: &dyn Storage,
+    prefix: &str,
+    progress_callback: ProgressCallback,
+) -> Result<usize> {
+    let mut manifest = load_manifest(storage, prefix)?;
+    let mut uploaded = 0;
+    let mut failure = None;
+    for (idx, upload) in plan.uploads.iter().enumerate() {
+        if let Some(cb) = progress_callback {
+            cb(idx + 1, plan.uploads.len(), &format!("Uploading {}", upload.key));
+        }
+        let sent = std::fs::read(&upload.path)
+            .map_err(|e| AppError::ReadFile {
+                path: upload.path.display().to_string(),
+                source: e,
+            })
+            .and_then(|data| storage.put(&join_key(prefix, &upload.key), &data));
+        if let Err(e) = sent {
+            failure = Some(e);
+            break;
+        }
+        manifest.insert(upload.key.clone(), upload.file.clone());
+        uploaded += 1;
+    }
+    if uploaded > 0 {
+        let data = serde_json::to_vec_pretty(&manifest).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+        storage.put(&join_key(prefix, SYNC_MANIFEST), &data)?;
+    }
+    match failure {
+        Some(e) => Err(e),
+        None => Ok(uploaded),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::LocalStorage;
+    use std::fs;
+
+    fn sources(dir: &Path) -> SyncSources {
+        let conversations = dir.join("conversations/year=2024/week=01");
+        fs::create_dir_all(&conversations).unwrap();
+        fs::write(conversations.join("threads.parquet"), "week one").unwrap();
+        fs::write(dir.join("conversations/notes.txt"), "not synced").unwrap();
+        fs::write(dir.join("users.parquet"), "users").unwrap();
+        fs::create_dir_all(dir.join("attachments/F1")).unwrap();
+        fs::write(dir.join("attachments/F1/photo.png"), "png").unwrap();
+        SyncSources {
+            conversations: Some(dir.join("conversations").to_string_lossy().to_string()),
+            users: Some(dir.join("users.parquet").to_string_lossy().to_string()),
+            channels: None,
+            attachments: Some(dir.join("attachments").to_string_lossy().to_string()),
+        }
+    }
+
+    #[test]
+    fn test_sync_uploads_new_and_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = sources(dir.path());
+        let storage = LocalStorage::new(dir.path().join("bucket"));
+
+        let plan = plan_sync(&sources, &storage, "archive", None).unwrap();
+        let keys: Vec<&str> = plan.uploads.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "attachments/F1/photo.png",
+                "conversations/year=2024/week=01/threads.parquet",
+                "users.parquet"
+            ]
+        );
+        assert_eq!(plan.upload_bytes(), 16);
+        assert_eq!(apply_sync(&plan, &storage, "archive", None).unwrap(), 3);
+        assert_eq!(storage.get("archive/users.parquet").unwrap(), Some(b"users".to_vec()));
+        assert_eq!(load_manifest(&storage, "archive").unwrap().len(), 3);
+
+        let plan = plan_sync(&sources, &storage, "archive", None).unwrap();
+        assert!(plan.uploads.is_empty());
+        assert_eq!(plan.unchanged, 3);
+
+        fs::write(dir.path().join("users.parquet"), "users v2").unwrap();
+        fs::remove_file(dir.path().join("bucket/archive/attachments/F1/photo.png")).unwrap();
+        let plan = plan_sync(&sources, &storage, "archive", None).unwrap();
+        let keys: Vec<&str> = plan.uploads.iter().map(|u| u.key.as_str()).collect();
+        assert_eq!(keys, vec!["attachments/F1/photo.png", "users.parquet"]);
+        assert_eq!(plan.unchanged, 1);
+    }
+
+    #[test]
+    fn test_dry_run_plan_writes_nothing() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = sources(dir.path());
+        let storage = LocalStorage::new(dir.path().join("bucket"));
+        plan_sync(&sources, &storage, "", None).unwrap();
+        assert!(storage.list("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_missing_source() {
+        let dir = tempfile::tempdir().unwrap();
+        let sources = SyncSources {
+            users: Some(dir.path().join("users.parquet").to_string_lossy().to_string()),
+            ..SyncSources::default()
+        };
+        let storage = LocalStorage::new(dir.path());
+        assert!(matches!(plan_sync(&sources, &storage, "", None), Err(AppError::MissingInput(_))));
+    }
+}
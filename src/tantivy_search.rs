@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::Path;
+
+use tantivy::collector::{Count, TopDocs};
+use tantivy::query::QueryParser;
+use tantivy::schema::{Field, Schema, Value, FAST, INDEXED, STORED, STRING, TEXT};
+use tantivy::snippet::{Snippet, SnippetGenerator};
+use tantivy::{doc, Index, IndexWriter, TantivyDocument};
+
+use crate::error::{AppError, Result};
+use crate::index::{IndexEntry, SearchHit, HIGHLIGHT_POST_TAG, HIGHLIGHT_PRE_TAG};
+use crate::ProgressCallback;
+
+/// Memory budget for the Tantivy index writer
+const WRITER_MEMORY_BYTES: usize = 50_000_000;
+
+/// Maximum snippet length in characters
+const SNIPPET_MAX_CHARS: usize = 200;
+
+/// Result of building a Tantivy index
+#[derive(Debug)]
+pub struct TantivyIndexResult {
+    pub total: usize,
+    pub index_dir: String,
+}
+
+/// Search result from a local Tantivy index
+#[derive(Debug)]
+pub struct TantivySearchResult {
+    pub hits: Vec<SearchHit>,
+    pub total_hits: usize,
+}
+
+/// Fields of the Tantivy schema
+struct IndexFields {
+    id: Field,
+    text: Field,
+    channel: Field,
+    users: Field,
+    timestamp: Field,
+    entry: Field,
+}
+
+impl IndexFields {
+    fn schema() -> (Schema, Self) {
+        let mut builder = Schema::builder();
+        let fields = Self {
+            id: builder.add_text_field("id", STRING | STORED),
+            text: builder.add_text_field("text", TEXT | STORED),
+            channel: builder.add_text_field("channel", TEXT),
+            users: builder.add_text_field("users", TEXT),
+            timestamp: builder.add_i64_field("timestamp", INDEXED | FAST),
+            // Full entry as JSON so hits round-trip to IndexEntry
+            entry: builder.add_text_field("entry", STORED),
+        };
+        (builder.build(), fields)
+    }
+
+    fn from_schema(schema: &Schema) -> Result<Self> {
+        let field = |name: &str| {
+            schema
+                .get_field(name)
+                .map_err(|e| AppError::Tantivy(format!("index is missing field '{}': {}", name, e)))
+        };
+        Ok(Self {
+            id: field("id")?,
+            text: field("text")?,
+            channel: field("channel")?,
+            users: field("users")?,
+            timestamp: field("timestamp")?,
+            entry: field("entry")?,
+        })
+    }
+}
+
+/// Build a Tantivy index on disk from a conversation index JSON file.
+///
+/// An existing Tantivy index in `index_dir` is replaced.
+pub fn build_tantivy_index(
+    input_path: &str,
+    index_dir: &str,
+    progress_callback: ProgressCallback,
+) -> Result<TantivyIndexResult> {
+    let report_progress = |current: usize, total: usize, msg: &str| {
+        if let Some(cb) = progress_callback {
+            cb(current, total, msg);
+        }
+    };
+
+    report_progress(0, 0, "Loading index file...");
+    let entries: Vec<IndexEntry> = crate::load_json_file(input_path)?;
+    let total = entries.len();
+
+    report_progress(0, total, "Creating Tantivy index...");
+    let dir = Path::new(index_dir);
+    if dir.join("meta.json").exists() {
+        fs::remove_dir_all(dir).map_err(|e| AppError::WriteFile {
+            path: index_dir.to_string(),
+            source: e,
+        })?;
+    }
+    fs::create_dir_all(dir).map_err(|e| AppError::WriteFile {
+        path: index_dir.to_string(),
+        source: e,
+    })?;
+
+    let (schema, fields) = IndexFields::schema();
+    let index = Index::create_in_dir(dir, schema).map_err(|e| AppError::Tantivy(e.to_string()))?;
+    let mut writer: IndexWriter = index
+        .writer(WRITER_MEMORY_BYTES)
+        .map_err(|e| AppError::Tantivy(e.to_string()))?;
+
+    for (i, entry) in entries.iter().enumerate() {
+        report_progress(i + 1, total, "Indexing messages...");
+
+        let users: Vec<&str> = entry.users.iter().map(|u| u.name.as_str()).collect();
+        let json = serde_json::to_string(entry).map_err(|e| AppError::JsonSerialize(e.to_string()))?;
+        writer
+            .add_document(doc!(
+                fields.id => entry.id.as_str(),
+                fields.text => entry.text.as_str(),
+                fields.channel => entry.channel.name.as_str(),
+                fields.users => users.join(" "),
+                fields.timestamp => entry.timestamp,
+                fields.entry => json,
+            ))
+            .map_err(|e| AppError::Tantivy(e.to_string()))?;
+    }
+
+    report_progress(total, total, "Committing index...");
+    writer.commit().map_err(|e| AppError::Tantivy(e.to_string()))?;
+
+    Ok(TantivyIndexResult {
+        total,
+        index_dir: index_dir.to_string(),
+    })
+}
+
+/// Query a Tantivy index built with [`build_tantivy_index`].
+///
+/// Uses Tantivy's query syntax: all terms must match by default, and
+/// `channel:general`, `users:alice` and `"exact phrase"` are supported.
+pub fn query_tantivy(index_dir: &str, query: &str, limit: usize) -> Result<TantivySearchResult> {
+    let index = Index::open_in_dir(index_dir).map_err(|e| AppError::Tantivy(e.to_string()))?;
+    let fields = IndexFields::from_schema(&index.schema())?;
+
+    let reader = index.reader().map_err(|e| AppError::Tantivy(e.to_string()))?;
+    let searcher = reader.searcher();
+
+    let mut query_parser = QueryParser::for_index(&index, vec![fields.text, fields.channel, fields.users]);
+    query_parser.set_conjunction_by_default();
+    let parsed = query_parser
+        .parse_query(query)
+        .map_err(|e| AppError::Tantivy(format!("invalid query: {}", e)))?;
+
+    let (top_docs, total_hits) = searcher
+        .search(&parsed, &(TopDocs::with_limit(limit), Count))
+        .map_err(|e| AppError::Tantivy(e.to_string()))?;
+
+    let mut snippet_generator = SnippetGenerator::create(&searcher, &*parsed, fields.text)
+        .map_err(|e| AppError::Tantivy(e.to_string()))?;
+    snippet_generator.set_max_num_chars(SNIPPET_MAX_CHARS);
+
+    let mut hits = Vec::with_capacity(top_docs.len());
+    for (_score, address) in top_docs {
+        let doc: TantivyDocument = searcher.doc(address).map_err(|e| AppError::Tantivy(e.to_string()))?;
+        let json = doc
+            .get_first(fields.entry)
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| AppError::Tantivy("document is missing stored entry".to_string()))?;
+        let entry: IndexEntry = serde_json::from_str(json).map_err(|e| AppError::JsonParse(e.to_string()))?;
+
+        hits.push(SearchHit {
+            snippet: snippet_with_markers(&snippet_generator.snippet_from_doc(&doc)),
+            entry,
+        });
+    }
+
+    Ok(TantivySearchResult { hits, total_hits })
+}
+
+/// Convert a Tantivy snippet to text with highlight markers around matches
+fn snippet_with_markers(snippet: &Snippet) -> Option<String> {
+    let fragment = snippet.fragment();
+    if fragment.is_empty() {
+        return None;
+    }
+
+    let mut output = String::with_capacity(fragment.len());
+    let mut last = 0;
+    for range in snippet.highlighted() {
+        output.push_str(fragment.get(last..range.start).unwrap_or_default());
+        output.push_str(HIGHLIGHT_PRE_TAG);
+        output.push_str(fragment.get(range.clone()).unwrap_or_default());
+        output.push_str(HIGHLIGHT_POST_TAG);
+        last = range.end;
+    }
+    output.push_str(fragment.get(last..).unwrap_or_default());
+
+    Some(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::index::{IndexChannel, IndexUser};
+
+    fn entry(ts: &str, channel: &str, user: &str, text: &str) -> IndexEntry {
+        IndexEntry {
+            id: ts.replace('.', "_"),
+            ts: ts.to_string(),
+            date: "2024-01-01T00:00:00+00:00".to_string(),
+            text: text.to_string(),
+            users: vec![IndexUser {
+                id: format!("U_{}", user),
+                name: user.to_string(),
+            }],
+            channel: IndexChannel {
+                id: format!("C_{}", channel),
+                name: channel.to_string(),
+            },
+            timestamp: 1704067200,
+            has_attachments: false,
+        }
+    }
+
+    fn build_fixture(dir: &Path) -> String {
+        let input = dir.join("index.json");
+        let entries = vec![
+            entry("1.1", "general", "alice", "The deploy failed on production"),
+            entry("2.2", "random", "bob", "Lunch plans for friday"),
+            entry("3.3", "ops", "bob", "Rolling back the deploy"),
+        ];
+        fs::write(&input, serde_json::to_string(&entries).unwrap()).unwrap();
+
+        let index_dir = dir.join("tantivy");
+        let result = build_tantivy_index(input.to_str().unwrap(), index_dir.to_str().unwrap(), None).unwrap();
+        assert_eq!(result.total, 3);
+        index_dir.to_string_lossy().to_string()
+    }
+
+    #[test]
+    fn test_query_tantivy_full_text() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = build_fixture(dir.path());
+
+        let result = query_tantivy(&index_dir, "deploy", 10).unwrap();
+
+        assert_eq!(result.total_hits, 2);
+        assert_eq!(result.hits.len(), 2);
+        let snippet = result.hits[0].snippet.as_deref().unwrap();
+        assert!(snippet.contains("<mark>deploy</mark>"));
+    }
+
+    #[test]
+    fn test_query_tantivy_field_filter() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = build_fixture(dir.path());
+
+        let result = query_tantivy(&index_dir, "deploy channel:ops", 10).unwrap();
+
+        assert_eq!(result.hits.len(), 1);
+        assert_eq!(result.hits[0].entry.channel.name, "ops");
+        assert_eq!(result.hits[0].entry.users[0].name, "bob");
+    }
+
+    #[test]
+    fn test_query_tantivy_limit() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = build_fixture(dir.path());
+
+        let result = query_tantivy(&index_dir, "deploy", 1).unwrap();
+
+        assert_eq!(result.total_hits, 2);
+        assert_eq!(result.hits.len(), 1);
+    }
+
+    #[test]
+    fn test_build_tantivy_index_replaces_existing() {
+        let dir = tempfile::tempdir().unwrap();
+        let index_dir = build_fixture(dir.path());
+        // Rebuilding into the same directory must not fail
+        let index_dir_again = build_fixture(dir.path());
+
+        assert_eq!(index_dir, index_dir_again);
+        assert_eq!(query_tantivy(&index_dir, "lunch", 10).unwrap().hits.len(), 1);
+    }
+
+    #[test]
+    fn test_query_tantivy_missing_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let missing = dir.path().join("missing");
+
+        let result = query_tantivy(missing.to_str().unwrap(), "deploy", 10);
+
+        assert!(matches!(result, Err(AppError::Tantivy(_))));
+    }
+}
@@ -26,7 +26,15 @@ pub fn render(
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    let [
+        from_year_area,
+        from_week_area,
+        to_year_area,
+        to_week_area,
+        output_area,
+        info_area,
+        help_area,
+    ] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
@@ -38,7 +46,7 @@ pub fn render(
             Constraint::Min(2),    // Info/Help
             Constraint::Length(1), // Help text
         ])
-        .split(inner);
+        .areas(inner);
 
     // From Year field
     render_text_field(
@@ -46,7 +54,7 @@ pub fn render(
         from_year,
         "From Year",
         active_field == ArchiveRangeField::FromYear,
-        chunks[0],
+        from_year_area,
     );
 
     // From Week field
@@ -55,7 +63,7 @@ pub fn render(
         from_week,
         "From Week (1-53)",
         active_field == ArchiveRangeField::FromWeek,
-        chunks[1],
+        from_week_area,
     );
 
     // To Year field
@@ -64,7 +72,7 @@ pub fn render(
         to_year,
         "To Year",
         active_field == ArchiveRangeField::ToYear,
-        chunks[2],
+        to_year_area,
     );
 
     // To Week field
@@ -73,7 +81,7 @@ pub fn render(
         to_week,
         "To Week (1-53)",
         active_field == ArchiveRangeField::ToWeek,
-        chunks[3],
+        to_week_area,
     );
 
     // Output Path field
@@ -82,7 +90,7 @@ pub fn render(
         output_path,
         "Output Directory",
         active_field == ArchiveRangeField::OutputPath,
-        chunks[4],
+        output_area,
     );
 
     // Info text
@@ -93,7 +101,12 @@ pub fn render(
     .style(Style::default().fg(Color::DarkGray))
     .alignment(Alignment::Center)
     .block(Block::default());
-    f.render_widget(info_text, chunks[5]);
+    f.render_widget(info_text, info_area);
 
-    render_help_text(f, "Tab: Next Field | Enter: Start Archive | Esc: Back", chunks[6]);
+    let help_text = if active_field == ArchiveRangeField::OutputPath {
+        "Tab: Next Field | Enter: Start Archive | Esc: Back"
+    } else {
+        "Tab: Next Field | Ctrl+P: Calendar | Enter: Start Archive | Esc: Back"
+    };
+    render_help_text(f, help_text, help_area);
 }
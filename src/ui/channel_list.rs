@@ -1,12 +1,16 @@
 use ratatui::{
-    layout::{Alignment, Rect},
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::Line,
-    widgets::{Block, Borders, List, ListItem, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
 
-use super::types::ChannelSelection;
+use super::centered_rect;
+use super::types::{ChannelSelection, ListNavigation, SelectionPicker};
+
+/// Longer channel names push the member and creation date columns out of line
+const MAX_NAME_WIDTH: usize = 30;
 
 /// Renders a channel list with selection state.
 ///
@@ -43,17 +47,57 @@ pub fn render(
     } else if let Some(sel) = channel_selection {
         let selected_count = sel.selected.len();
         let total_count = sel.channels.len();
+        let mut title = format!(
+            "Channels ({}/{}) by {}",
+            selected_count,
+            total_count,
+            sel.sort.label()
+        );
+        if sel.filtering || !sel.filter.text().is_empty() {
+            let cursor = if sel.filtering { "_" } else { "" };
+            title = format!(
+                "{} /{}{} [{} shown]",
+                title,
+                sel.filter.text(),
+                cursor,
+                sel.items_len()
+            );
+        }
 
+        let name_width = sel
+            .visible_channels()
+            .map(|ch| ch.name.chars().count())
+            .max()
+            .unwrap_or(0)
+            .min(MAX_NAME_WIDTH);
         let items: Vec<ListItem> = sel
-            .channels
-            .iter()
+            .visible_channels()
             .map(|ch| {
                 let checkbox = if sel.selected.contains(&ch.id) {
                     "[x]"
                 } else {
                     "[ ]"
                 };
-                ListItem::new(Line::from(format!("{} #{}", checkbox, ch.name)))
+                let members = ch.num_members.map_or("-".to_string(), |n| n.to_string());
+                let created = ch
+                    .created
+                    .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+                    .map_or("-".to_string(), |t| t.format("%Y-%m-%d").to_string());
+                let line = format!(
+                    "{} #{:<width$}  {:>6}  {:<10}{}",
+                    checkbox,
+                    ch.name,
+                    members,
+                    created,
+                    if ch.is_archived { "  archived" } else { "" },
+                    width = name_width
+                );
+                let style = if ch.is_archived {
+                    Style::default().fg(Color::DarkGray)
+                } else {
+                    Style::default()
+                };
+                ListItem::new(Line::styled(line, style))
             })
             .collect();
 
@@ -61,7 +105,7 @@ pub fn render(
             .block(
                 Block::default()
                     .borders(Borders::ALL)
-                    .title(format!("Channels ({}/{})", selected_count, total_count))
+                    .title(title)
                     .border_style(block_style),
             )
             .highlight_style(
@@ -85,3 +129,57 @@ pub fn render(
         f.render_widget(no_channels, area);
     }
 }
+
+/// Popup with the saved channel selections and the name to save the
+/// current one as
+pub fn render_selection_picker(f: &mut Frame, picker: &mut SelectionPicker, area: Rect) {
+    let popup = centered_rect(50, 50, area);
+    f.render_widget(Clear, popup);
+
+    let title = match &picker.profile {
+        Some(profile) => format!("Saved Selections ({})", profile),
+        None => "Saved Selections".to_string(),
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Yellow));
+    let inner = block.inner(popup);
+    f.render_widget(block, popup);
+
+    let [list_area, name_field, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(1), Constraint::Length(3), Constraint::Length(1)])
+        .areas(inner);
+
+    if picker.entries.is_empty() {
+        let empty = Paragraph::new("No saved selections")
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center);
+        f.render_widget(empty, list_area);
+    } else {
+        let items: Vec<ListItem> = picker
+            .entries
+            .iter()
+            .map(|(name, count)| ListItem::new(format!("{}  ({} channels)", name, count)))
+            .collect();
+        let list = List::new(items)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        f.render_stateful_widget(list, list_area, &mut picker.list_state);
+    }
+
+    let name_block = Block::default().borders(Borders::ALL).title("Save current as");
+    let name_area = name_block.inner(name_field);
+    f.render_widget(name_block, name_field);
+    picker.name.render(f, name_area, Style::default().fg(Color::Yellow));
+
+    let help = Paragraph::new("Enter: Load (Save when named) | Del: Delete | Esc: Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(help, help_area);
+}
@@ -4,15 +4,29 @@ use ratatui::{
     Frame,
 };
 
-use super::{render_help_text, render_static_field, types::DownloadAttachmentsField};
+use super::{render_help_text, render_static_field, render_text_field, types::DownloadAttachmentsField};
+use crate::widgets::TextInput;
 
-pub fn render(
-    f: &mut Frame,
-    conversations_path: &str,
-    output_path: &str,
-    active_field: DownloadAttachmentsField,
-    area: Rect,
-) {
+pub struct DownloadAttachmentsProps<'a> {
+    pub conversations_path: &'a str,
+    pub output_path: &'a str,
+    pub types: &'a TextInput,
+    pub max_size: &'a TextInput,
+    pub from_date: &'a TextInput,
+    pub to_date: &'a TextInput,
+    pub active_field: DownloadAttachmentsField,
+}
+
+pub fn render(f: &mut Frame, props: DownloadAttachmentsProps, area: Rect) {
+    let DownloadAttachmentsProps {
+        conversations_path,
+        output_path,
+        types,
+        max_size,
+        from_date,
+        to_date,
+        active_field,
+    } = props;
     let block = Block::default()
         .borders(Borders::ALL)
         .title("Download Attachments");
@@ -20,17 +34,33 @@ pub fn render(
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    let [
+        conversations_area,
+        output_area,
+        types_area,
+        max_size_area,
+        from_area,
+        to_area,
+        help_area,
+    ] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Length(3),
             Constraint::Min(1),
         ])
-        .split(inner);
+        .areas(inner);
 
-    render_static_field(f, conversations_path, "Conversations File", active_field == DownloadAttachmentsField::ConversationsPath, chunks[0]);
-    render_static_field(f, output_path, "Output Directory", active_field == DownloadAttachmentsField::OutputPath, chunks[1]);
-    render_help_text(f, "Tab: Next Field | Enter: Download | Esc: Back", chunks[2]);
+    render_static_field(f, conversations_path, "Conversations File", active_field == DownloadAttachmentsField::ConversationsPath, conversations_area);
+    render_static_field(f, output_path, "Output Directory", active_field == DownloadAttachmentsField::OutputPath, output_area);
+    render_text_field(f, types, "File Types (optional, e.g. png,jpg,pdf)", active_field == DownloadAttachmentsField::Types, types_area);
+    render_text_field(f, max_size, "Max Size (optional, e.g. 25MB)", active_field == DownloadAttachmentsField::MaxSize, max_size_area);
+    render_text_field(f, from_date, "Shared From (optional, YYYY-MM-DD)", active_field == DownloadAttachmentsField::FromDate, from_area);
+    render_text_field(f, to_date, "Shared To (optional, YYYY-MM-DD)", active_field == DownloadAttachmentsField::ToDate, to_area);
+    render_help_text(f, "Tab: Next Field | Ctrl+P: Calendar | Enter: Download | Esc: Back", help_area);
 }
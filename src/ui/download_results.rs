@@ -0,0 +1,67 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+
+use super::render_help_text;
+use crate::slack::DownloadFailure;
+
+pub fn render(
+    f: &mut Frame,
+    message: &str,
+    failures: &[DownloadFailure],
+    table_state: &mut TableState,
+    area: Rect,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Download Results")
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [summary_area, failures_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(2), // Summary
+            Constraint::Min(1),    // Failures
+            Constraint::Length(1), // Help
+        ])
+        .areas(inner);
+
+    let summary = Paragraph::new(message)
+        .style(Style::default().fg(Color::White))
+        .wrap(Wrap { trim: true });
+    f.render_widget(summary, summary_area);
+
+    let rows = failures
+        .iter()
+        .map(|failure| Row::new(vec![failure.item.clone(), failure.error.clone()]));
+    let table = Table::new(rows, [Constraint::Length(24), Constraint::Min(10)])
+        .header(
+            Row::new(vec!["Item", "Error"])
+                .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+        )
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Failures ({})", failures.len())),
+        )
+        .row_highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(table, failures_area, table_state);
+
+    render_help_text(
+        f,
+        "↑/↓: Scroll | r: Retry failed items | Enter/Esc: Main menu",
+        help_area,
+    );
+}
@@ -7,8 +7,10 @@ use ratatui::{
 };
 
 use super::centered_rect;
-use super::types::{EditConvPathField, EditableChannelList};
-use crate::widgets::TextInput;
+use super::types::{
+    BulkToggle, ChannelNameAction, ChannelNamePrompt, EditConvPathField, EditableChannelList, ListNavigation,
+};
+use crate::widgets::{TextArea, TextInput};
 
 pub fn render_path_input(
     f: &mut Frame,
@@ -80,6 +82,7 @@ pub fn render_channel_list(
     f: &mut Frame,
     channels: &mut EditableChannelList,
     editing_export_path: bool,
+    name_prompt: Option<&ChannelNamePrompt>,
     area: Rect,
 ) {
     let block = Block::default()
@@ -105,9 +108,15 @@ pub fn render_channel_list(
         .map(|ch| {
             let enabled = ch.enabled_count();
             let total = ch.messages.len();
+            let mark = if ch.marked { "[m] " } else { "" };
+            let merged = if ch.merged_from.is_empty() {
+                String::new()
+            } else {
+                format!(" merged from #{}", ch.merged_from.join(", #"))
+            };
             ListItem::new(Line::from(format!(
-                "#{} ({}/{} messages enabled)",
-                ch.name, enabled, total
+                "{}#{} ({}/{} messages enabled){}",
+                mark, ch.name, enabled, total, merged
             )))
         })
         .collect();
@@ -140,12 +149,53 @@ pub fn render_channel_list(
     let help_text = if editing_export_path {
         "Enter: Confirm Path | Esc: Cancel"
     } else {
-        "↑/↓: Navigate | Alt+↑/↓: Reorder | Enter: Edit Messages | p: Edit Path | e: Export | Esc: Back"
+        "↑/↓: Navigate | Alt+↑/↓: Reorder | Enter: Edit Messages | Ctrl+Z/Y: Undo/Redo | +/-: Enable/Disable Matching | r: Rename | x: Mark | g: Merge Marked | p: Edit Path | e: Export | m: Markdown | Esc: Back"
     };
     let help = Paragraph::new(help_text)
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
     f.render_widget(help, chunks[2]);
+
+    if let Some(prompt) = name_prompt {
+        render_name_prompt(f, prompt, area);
+    }
+}
+
+fn render_name_prompt(f: &mut Frame, prompt: &ChannelNamePrompt, area: Rect) {
+    let editing_area = centered_rect(60, 25, area);
+    f.render_widget(Clear, editing_area);
+
+    let title = match prompt.action {
+        ChannelNameAction::Rename => "Rename Channel",
+        ChannelNameAction::Merge => "Merge Marked Channels Into",
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let inner = block.inner(editing_area);
+    f.render_widget(block, editing_area);
+
+    let [input_area, status_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1)])
+        .areas(inner);
+
+    prompt
+        .input
+        .render(f, input_area, Style::default().fg(Color::Yellow));
+
+    if let Some(error) = &prompt.error {
+        let status = Paragraph::new(error.as_str()).style(Style::default().fg(Color::Red));
+        f.render_widget(status, status_area);
+    }
+
+    let edit_help = Paragraph::new("Enter: Apply | Esc: Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(edit_help, help_area);
 }
 
 pub fn render_message_list(
@@ -174,8 +224,7 @@ pub fn render_message_list(
         .split(inner);
 
     let items: Vec<ListItem> = channel
-        .messages
-        .iter()
+        .visible_messages()
         .map(|msg| {
             let checkbox = if msg.enabled { "[x]" } else { "[ ]" };
             let collapse_icon = if msg.collapsed { "▶" } else { "▼" };
@@ -239,13 +288,23 @@ pub fn render_message_list(
 
     let enabled_count = channel.enabled_count();
     let total_count = channel.messages.len();
+    let mut title = format!("Messages ({}/{})", enabled_count, total_count);
+    if channel.filtering || !channel.filter.text().is_empty() {
+        let cursor = if channel.filtering { "_" } else { "" };
+        title = format!(
+            "{} /{}{} [{} shown]",
+            title,
+            channel.filter.text(),
+            cursor,
+            channel.items_len()
+        );
+    }
+    if let Some(error) = &channel.filter_error {
+        title = format!("{} ({})", title, error);
+    }
 
     let list = List::new(items)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title(format!("Messages ({}/{})", enabled_count, total_count)),
-        )
+        .block(Block::default().borders(Borders::ALL).title(title))
         .highlight_style(
             Style::default()
                 .bg(Color::DarkGray)
@@ -256,7 +315,7 @@ pub fn render_message_list(
     f.render_stateful_widget(list, chunks[0], &mut channel.list_state);
 
     let help = Paragraph::new(
-        "↑/↓: Navigate | Space: Toggle | Tab: Expand/Collapse | Alt+↑/↓: Reorder | a/n: All/None | Enter: Details | Esc: Back",
+        "↑/↓: Navigate | Space: Toggle | Tab: Expand/Collapse | Alt+↑/↓: Reorder | a/n: All/None | +/-: Enable/Disable Matching | Ctrl+Z/Y: Undo/Redo | /: Filter | Enter: Details | Esc: Back",
     )
     .style(Style::default().fg(Color::DarkGray))
     .alignment(Alignment::Center);
@@ -268,37 +327,42 @@ pub fn render_message_detail(
     f: &mut Frame,
     channel_idx: usize,
     message_idx: usize,
-    channels: &EditableChannelList,
+    channels: &mut EditableChannelList,
     users: &serde_json::Value,
     attachment_list_state: &mut ListState,
     editing_title: Option<&(usize, TextInput)>,
+    editing_text: Option<&mut TextArea>,
+    replies_focused: bool,
     area: Rect,
 ) {
-    let channel = match channels.channels.get(channel_idx) {
+    let channel = match channels.channels.get_mut(channel_idx) {
         Some(ch) => ch,
         None => return,
     };
-    let msg = match channel.messages.get(message_idx) {
+    let msg = match channel.messages.get_mut(message_idx) {
         Some(m) => m,
         None => return,
     };
 
-    let user_name = msg
-        .user_id()
-        .and_then(|uid| {
-            users.as_array().and_then(|arr| {
-                arr.iter().find_map(|u| {
-                    if u.get("id").and_then(|i| i.as_str()) == Some(uid) {
-                        u.get("name")
-                            .and_then(|n| n.as_str())
-                            .or_else(|| u.get("real_name").and_then(|n| n.as_str()))
-                    } else {
-                        None
-                    }
+    let find_user_name = |user_id: Option<&str>| -> String {
+        user_id
+            .and_then(|uid| {
+                users.as_array().and_then(|arr| {
+                    arr.iter().find_map(|u| {
+                        if u.get("id").and_then(|i| i.as_str()) == Some(uid) {
+                            u.get("name")
+                                .and_then(|n| n.as_str())
+                                .or_else(|| u.get("real_name").and_then(|n| n.as_str()))
+                        } else {
+                            None
+                        }
+                    })
                 })
             })
-        })
-        .unwrap_or("unknown");
+            .unwrap_or("unknown")
+            .to_string()
+    };
+    let user_name = find_user_name(msg.user_id());
 
     let block = Block::default()
         .borders(Borders::ALL)
@@ -307,20 +371,27 @@ pub fn render_message_detail(
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    let has_replies = !msg.replies.is_empty();
+    let [message_area, attachments_area, replies_area, help_area] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
             Constraint::Length(5),
             Constraint::Min(6),
+            Constraint::Length(if has_replies { 8 } else { 0 }),
             Constraint::Length(1),
         ])
-        .split(inner);
+        .areas(inner);
 
+    let message_title = if msg.edited_text.is_some() {
+        "Message (edited)"
+    } else {
+        "Message"
+    };
     let message_text = Paragraph::new(msg.text())
-        .block(Block::default().borders(Borders::ALL).title("Message"))
+        .block(Block::default().borders(Borders::ALL).title(message_title))
         .wrap(ratatui::widgets::Wrap { trim: false });
-    f.render_widget(message_text, chunks[0]);
+    f.render_widget(message_text, message_area);
 
     let files = msg.files();
     let links = msg.links();
@@ -372,6 +443,14 @@ pub fn render_message_detail(
     let total_files = files.len();
     let total_links = links.len();
 
+    let focused_style = |focused: bool| {
+        if focused {
+            Style::default().fg(Color::Yellow)
+        } else {
+            Style::default()
+        }
+    };
+
     let list = List::new(items)
         .block(
             Block::default()
@@ -379,7 +458,8 @@ pub fn render_message_detail(
                 .title(format!(
                     "Files & Links ({}/{} files, {}/{} links)",
                     selected_files, total_files, selected_links, total_links
-                )),
+                ))
+                .border_style(focused_style(has_replies && !replies_focused)),
         )
         .highlight_style(
             Style::default()
@@ -388,7 +468,50 @@ pub fn render_message_detail(
         )
         .highlight_symbol("> ");
 
-    f.render_stateful_widget(list, chunks[1], attachment_list_state);
+    f.render_stateful_widget(list, attachments_area, attachment_list_state);
+
+    let mut reply_offset = None;
+    if has_replies {
+        let reply_items: Vec<ListItem> = msg
+            .replies
+            .iter()
+            .map(|reply| {
+                let checkbox = if reply.enabled { "[x]" } else { "[ ]" };
+                let text: String = reply
+                    .text()
+                    .chars()
+                    .map(|c| if c == '\n' { ' ' } else { c })
+                    .collect();
+                let style = if reply.enabled {
+                    Style::default()
+                } else {
+                    Style::default().fg(Color::DarkGray)
+                };
+                ListItem::new(Line::from(vec![
+                    Span::raw(format!("{} ", checkbox)),
+                    Span::styled(format!("@{}: {}", find_user_name(reply.user_id()), text), style),
+                ]))
+            })
+            .collect();
+        let enabled_replies = msg.replies.iter().filter(|r| r.enabled).count();
+        let reply_list = List::new(reply_items)
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!("Replies ({}/{})", enabled_replies, msg.replies.len()))
+                    .border_style(focused_style(replies_focused)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("> ");
+        // Rendered from a copy, `files` and `links` still borrow the message
+        let mut reply_list_state = msg.reply_list_state;
+        f.render_stateful_widget(reply_list, replies_area, &mut reply_list_state);
+        reply_offset = Some(reply_list_state.offset());
+    }
 
     // Render title editing input if active
     if let Some((link_idx, text_input)) = editing_title {
@@ -439,10 +562,85 @@ pub fn render_message_detail(
         attachment_list_state.select(Some(files_count + *link_idx));
     }
 
-    let help = Paragraph::new(
-        "↑/↓: Navigate | Space: Toggle | m: Main | e: Edit Title | f: Fetch Title | a/n: All/None | Esc: Back",
-    )
-    .style(Style::default().fg(Color::DarkGray))
-    .alignment(Alignment::Center);
-    f.render_widget(help, chunks[2]);
+    // Render message text editor if active
+    if let Some(text_area) = editing_text {
+        let editing_area = centered_rect(80, 60, area);
+        f.render_widget(Clear, editing_area);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title("Edit Message Text")
+            .border_style(Style::default().fg(Color::Yellow));
+
+        let inner = block.inner(editing_area);
+        f.render_widget(block, editing_area);
+
+        let [input_area, help_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(1), Constraint::Length(1)])
+            .areas(inner);
+
+        text_area.render(f, input_area, Style::default().fg(Color::Yellow));
+
+        let edit_help = Paragraph::new("Ctrl+S: Save | Esc: Cancel | Enter: New Line")
+            .style(Style::default().fg(Color::DarkGray))
+            .alignment(Alignment::Center);
+        f.render_widget(edit_help, help_area);
+    }
+
+    let help_text = if replies_focused {
+        "↑/↓: Navigate | Space: Toggle | d: Delete | Alt+↑/↓: Reorder | a/n: All/None | t: Edit Text | Tab: Files & Links | Ctrl+Z/Y: Undo/Redo | Esc: Back"
+    } else if has_replies {
+        "↑/↓: Navigate | Space: Toggle | t: Edit Text | m: Main | e: Edit Title | f: Fetch Title | a/n: All/None | Tab: Replies | Ctrl+Z/Y: Undo/Redo | Esc: Back"
+    } else {
+        "↑/↓: Navigate | Space: Toggle | t: Edit Text | m: Main | e: Edit Title | f: Fetch Title | a/n: All/None | Ctrl+Z/Y: Undo/Redo | Esc: Back"
+    };
+    let help = Paragraph::new(help_text)
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(help, help_area);
+
+    if let Some(offset) = reply_offset {
+        *msg.reply_list_state.offset_mut() = offset;
+    }
+}
+
+/// Prompt for the pattern of a bulk enable/disable. `global` when it applies
+/// to every channel rather than the open one.
+pub fn render_bulk_toggle(f: &mut Frame, prompt: &BulkToggle, global: bool, area: Rect) {
+    let editing_area = centered_rect(60, 25, area);
+    f.render_widget(Clear, editing_area);
+
+    let action = if prompt.enable { "Enable" } else { "Disable" };
+    let scope = if global { "all channels" } else { "this channel" };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("{} Matching Messages ({})", action, scope))
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let inner = block.inner(editing_area);
+    f.render_widget(block, editing_area);
+
+    let [input_area, status_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Length(1)])
+        .areas(inner);
+
+    prompt
+        .input
+        .render(f, input_area, Style::default().fg(Color::Yellow));
+
+    let status = match &prompt.error {
+        Some(error) => Paragraph::new(error.as_str()).style(Style::default().fg(Color::Red)),
+        None => Paragraph::new("Regex over the text, from:@user or from:bot")
+            .style(Style::default().fg(Color::DarkGray)),
+    };
+    f.render_widget(status, status_area);
+
+    let edit_help = Paragraph::new("Enter: Apply | Esc: Cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(edit_help, help_area);
 }
@@ -0,0 +1,111 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
+    Frame,
+};
+
+use super::{render_help_text, render_text_field, types::EmojiBrowser};
+use crate::emojis::{EmojiEntry, EmojiKind};
+
+pub fn render(f: &mut Frame, browser: &mut EmojiBrowser, source: &str, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Browse Emojis ({})", source));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [filter_area, body_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3), // Filter
+            Constraint::Min(1),    // List and details
+            Constraint::Length(1), // Help
+        ])
+        .areas(inner);
+
+    render_text_field(f, &browser.filter, "Filter (name or alias target)", true, filter_area);
+
+    let [list_area, details_area] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)])
+        .areas(body_area);
+
+    let items: Vec<ListItem> = browser
+        .visible_entries()
+        .map(|entry| {
+            let (kind, style) = match (&entry.kind, &entry.problem) {
+                (_, Some(_)) => ("!", Style::default().fg(Color::Red)),
+                (EmojiKind::Alias { .. }, None) => ("=", Style::default().fg(Color::Cyan)),
+                (EmojiKind::Image { .. }, None) => (" ", Style::default()),
+            };
+            let target = match &entry.kind {
+                EmojiKind::Alias { target } => format!(" -> :{}:", target),
+                EmojiKind::Image { .. } => String::new(),
+            };
+            ListItem::new(Line::styled(format!("{} :{}:{}", kind, entry.name, target), style))
+        })
+        .collect();
+
+    let title = format!(
+        "{} of {} emojis, {} with problems{}",
+        items.len(),
+        browser.entries.len(),
+        browser.problem_count(),
+        if browser.only_problems { " [problems only]" } else { "" }
+    );
+    let list = List::new(items)
+        .block(Block::default().borders(Borders::ALL).title(title))
+        .highlight_style(
+            Style::default()
+                .bg(Color::DarkGray)
+                .add_modifier(Modifier::BOLD),
+        )
+        .highlight_symbol("> ");
+    f.render_stateful_widget(list, list_area, &mut browser.list_state);
+
+    let details = Paragraph::new(browser.current().map(detail_lines).unwrap_or_default())
+        .wrap(Wrap { trim: false })
+        .block(Block::default().borders(Borders::ALL).title("Details"));
+    f.render_widget(details, details_area);
+
+    render_help_text(
+        f,
+        "Type to filter | ↑/↓: Navigate | Tab: Problems only | Esc: Back",
+        help_area,
+    );
+}
+
+fn detail_lines(entry: &EmojiEntry) -> Vec<Line<'static>> {
+    let label = |text: &str| Span::styled(format!("{:<10}", text), Style::default().fg(Color::DarkGray));
+
+    let mut lines = vec![Line::from(vec![label("Name"), Span::raw(format!(":{}:", entry.name))])];
+    match &entry.kind {
+        EmojiKind::Image { extension } => {
+            lines.push(Line::from(vec![label("Type"), Span::raw(format!("image ({})", extension))]));
+        }
+        EmojiKind::Alias { target } => {
+            lines.push(Line::from(vec![label("Type"), Span::raw("alias")]));
+            lines.push(Line::from(vec![label("Target"), Span::raw(format!(":{}:", target))]));
+        }
+    }
+    lines.push(Line::from(vec![
+        label("File"),
+        Span::raw(entry.file_name.clone().unwrap_or_else(|| "-".to_string())),
+    ]));
+    let size = match entry.file_size {
+        Some(bytes) if bytes >= 1024 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        Some(bytes) => format!("{} bytes", bytes),
+        None => "-".to_string(),
+    };
+    lines.push(Line::from(vec![label("Size"), Span::raw(size)]));
+    let status = match &entry.problem {
+        Some(problem) => Span::styled(problem.clone(), Style::default().fg(Color::Red)),
+        None => Span::styled("ok", Style::default().fg(Color::Green)),
+    };
+    lines.push(Line::from(vec![label("Status"), status]));
+    lines
+}
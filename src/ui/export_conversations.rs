@@ -64,6 +64,8 @@ pub fn render(
         chunks[2],
     );
 
+    let filtering = channel_selection.as_ref().is_some_and(|s| s.filtering);
+
     // Channel list
     channel_list::render(
         f,
@@ -73,10 +75,14 @@ pub fn render(
         chunks[3],
     );
 
-    let help_text = if active_field == ConvExportField::Channels {
-        "↑/↓: Navigate | Space: Toggle | a: All | n: None | r: Refresh | Tab: Next | Enter: Export | Esc: Back"
-    } else {
+    let help_text = if filtering {
+        "Type to filter | ↑/↓: Navigate | Space: Toggle | Enter: Done | Esc: Clear filter"
+    } else if active_field == ConvExportField::Channels {
+        "↑/↓: Navigate | Space: Toggle | /: Filter | a: All | n: None | s: Sort | p: Saved | r: Refresh | Tab: Next | Enter: Export | Esc: Back"
+    } else if active_field == ConvExportField::OutputPath {
         "Tab: Next Field | Enter: Export | Esc: Back"
+    } else {
+        "Tab: Next Field | Ctrl+P: Calendar | Enter: Export | Esc: Back"
     };
     render_help_text(f, help_text, chunks[4]);
 }
@@ -4,7 +4,7 @@ use ratatui::{
     Frame,
 };
 
-use super::{render_checkbox_field, render_help_text, render_static_field, types::ImportMeilisearchField};
+use super::{mask_secret, render_checkbox_field, render_help_text, render_static_field, types::ImportMeilisearchField};
 
 pub struct ImportMeilisearchProps<'a> {
     pub input_path: &'a str,
@@ -23,7 +23,7 @@ pub fn render(f: &mut Frame, props: ImportMeilisearchProps, area: Rect) {
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    let [input_area, url_area, api_key_area, index_area, clear_area, help_area] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
@@ -34,20 +34,14 @@ pub fn render(f: &mut Frame, props: ImportMeilisearchProps, area: Rect) {
             Constraint::Length(3),
             Constraint::Min(1),
         ])
-        .split(inner);
-
-    render_static_field(f, props.input_path, "Index File", props.active_field == ImportMeilisearchField::Input, chunks[0]);
-    render_static_field(f, props.url, "Meilisearch URL", props.active_field == ImportMeilisearchField::Url, chunks[1]);
-
-    // API Key field (masked)
-    let masked_key = if props.api_key.is_empty() {
-        "(none)".to_string()
-    } else {
-        "*".repeat(props.api_key.len().min(20))
-    };
-    render_static_field(f, &masked_key, "API Key", props.active_field == ImportMeilisearchField::ApiKey, chunks[2]);
-
-    render_static_field(f, props.index_name, "Index Name", props.active_field == ImportMeilisearchField::IndexName, chunks[3]);
-    render_checkbox_field(f, props.clear, "Clear index", "Options (Space to toggle)", props.active_field == ImportMeilisearchField::Clear, chunks[4]);
-    render_help_text(f, "Tab: Next Field | Space: Toggle Clear | Enter: Import | Esc: Back", chunks[5]);
+        .areas(inner);
+
+    render_static_field(f, props.input_path, "Index File", props.active_field == ImportMeilisearchField::Input, input_area);
+    render_static_field(f, props.url, "Meilisearch URL", props.active_field == ImportMeilisearchField::Url, url_area);
+
+    render_static_field(f, mask_secret(props.api_key), "API Key", props.active_field == ImportMeilisearchField::ApiKey, api_key_area);
+
+    render_static_field(f, props.index_name, "Index Name", props.active_field == ImportMeilisearchField::IndexName, index_area);
+    render_checkbox_field(f, props.clear, "Clear index", "Options (Space to toggle)", props.active_field == ImportMeilisearchField::Clear, clear_area);
+    render_help_text(f, "Tab: Next Field | Space: Toggle Clear | Enter: Import | Esc: Back", help_area);
 }
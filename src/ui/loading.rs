@@ -7,13 +7,24 @@ use ratatui::{
 };
 
 use super::centered_rect;
+use crate::{ExportProgress, TransferProgress};
 
 pub fn render_loading(
     f: &mut Frame,
     message: &str,
     progress: Option<&(usize, usize, String)>,
+    export_progress: Option<&ExportProgress>,
+    transfer_progress: Option<&TransferProgress>,
+    cancellable: bool,
     area: Rect,
 ) {
+    if let Some(transfer) = transfer_progress {
+        return render_transfer_loading(f, message, progress, transfer, cancellable, area);
+    }
+    if let Some(detail) = export_progress {
+        return render_export_loading(f, message, progress, detail, cancellable, area);
+    }
+
     let block = Block::default().borders(Borders::ALL).title("Processing");
 
     let popup_area = centered_rect(60, 30, area);
@@ -21,19 +32,19 @@ pub fn render_loading(
     f.render_widget(block.clone(), popup_area);
 
     let inner = block.inner(popup_area);
-    let chunks = Layout::default()
+    let [message_area, progress_area, help_area] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
             Constraint::Percentage(30),
             Constraint::Percentage(30),
             Constraint::Percentage(40),
         ])
-        .split(inner);
+        .areas(inner);
 
     let loading = Paragraph::new(message)
         .style(Style::default().fg(Color::Cyan))
         .alignment(Alignment::Center);
-    f.render_widget(loading, chunks[0]);
+    f.render_widget(loading, message_area);
 
     // Always show progress gauge, with default values if not yet set
     let (current, total, item_name) = match progress {
@@ -41,15 +52,158 @@ pub fn render_loading(
         None => (0, 1, "Starting..."),
     };
 
+    let progress_text = format!("{}/{} - {}", current, total, item_name);
+    f.render_widget(progress_gauge("Progress", current, total, progress_text), progress_area);
+
+    if cancellable {
+        render_cancel_help(f, help_area);
+    }
+}
+
+/// Loading popup for downloads: file gauge, byte gauge when the sizes are
+/// known, and the transfer rate and ETA
+fn render_transfer_loading(
+    f: &mut Frame,
+    message: &str,
+    progress: Option<&(usize, usize, String)>,
+    transfer: &TransferProgress,
+    cancellable: bool,
+    area: Rect,
+) {
+    let block = Block::default().borders(Borders::ALL).title("Processing");
+
+    let popup_area = centered_rect(70, 50, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block.clone(), popup_area);
+
+    let inner = block.inner(popup_area);
+    let [message_area, files_area, bytes_area, counters_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Message
+            Constraint::Length(3), // Files
+            Constraint::Length(3), // Bytes
+            Constraint::Length(2), // Rate and current file
+            Constraint::Min(1),    // Help
+        ])
+        .areas(inner);
+
+    let loading = Paragraph::new(message)
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center);
+    f.render_widget(loading, message_area);
+
+    let files_text = format!("{}/{} files", transfer.files_done, transfer.files_total);
+    f.render_widget(
+        progress_gauge("Files", transfer.files_done, transfer.files_total, files_text),
+        files_area,
+    );
+
+    if transfer.bytes_total > 0 {
+        let bytes_text = format!(
+            "{} / {}",
+            crate::format_bytes(transfer.bytes),
+            crate::format_bytes(transfer.bytes_total)
+        );
+        // Gauge ratios are computed from counts; KB keeps them within usize
+        f.render_widget(
+            progress_gauge(
+                "Bytes",
+                (transfer.bytes / 1024) as usize,
+                (transfer.bytes_total / 1024).max(1) as usize,
+                bytes_text,
+            ),
+            bytes_area,
+        );
+    }
+
+    let mut counters = transfer.summary();
+    if let Some((_, _, item_name)) = progress {
+        counters.push_str(&format!("\n{}", item_name));
+    }
+    let counters = Paragraph::new(counters)
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center);
+    f.render_widget(counters, counters_area);
+
+    if cancellable {
+        render_cancel_help(f, help_area);
+    }
+}
+
+/// Loading popup for conversation exports: overall channel gauge, a gauge
+/// for the threads of the current channel and running page/message counters
+fn render_export_loading(
+    f: &mut Frame,
+    message: &str,
+    progress: Option<&(usize, usize, String)>,
+    detail: &ExportProgress,
+    cancellable: bool,
+    area: Rect,
+) {
+    let block = Block::default().borders(Borders::ALL).title("Processing");
+
+    let popup_area = centered_rect(70, 50, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block.clone(), popup_area);
+
+    let inner = block.inner(popup_area);
+    let [message_area, channels_area, threads_area, counters_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(2), // Message
+            Constraint::Length(3), // Channels
+            Constraint::Length(3), // Threads
+            Constraint::Length(2), // Counters
+            Constraint::Min(1),    // Help
+        ])
+        .areas(inner);
+
+    let loading = Paragraph::new(message)
+        .style(Style::default().fg(Color::Cyan))
+        .alignment(Alignment::Center);
+    f.render_widget(loading, message_area);
+
+    let channels_text = format!("{}/{} channels", detail.channels_done, detail.channels_total);
+    f.render_widget(
+        progress_gauge("Channels", detail.channels_done, detail.channels_total, channels_text),
+        channels_area,
+    );
+
+    let threads_title = if detail.channel.is_empty() {
+        "Threads".to_string()
+    } else {
+        format!("Threads in #{}", detail.channel)
+    };
+    let threads_text = format!("{}/{} threads", detail.threads_done, detail.threads_total);
+    f.render_widget(
+        progress_gauge(&threads_title, detail.threads_done, detail.threads_total, threads_text),
+        threads_area,
+    );
+
+    let mut counters = format!("Pages: {}   Messages: {}", detail.pages, detail.messages);
+    if let Some((_, _, item_name)) = progress {
+        counters.push_str(&format!("\n{}", item_name));
+    }
+    let counters = Paragraph::new(counters)
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center);
+    f.render_widget(counters, counters_area);
+
+    if cancellable {
+        render_cancel_help(f, help_area);
+    }
+}
+
+fn progress_gauge<'a>(title: &'a str, current: usize, total: usize, label: String) -> Gauge<'a> {
     let percentage = if total > 0 {
-        (current as f64 / total as f64 * 100.0) as u16
+        (current.min(total) as f64 / total as f64 * 100.0) as u16
     } else {
         0
     };
 
-    let progress_text = format!("{}/{} - {}", current, total, item_name);
-    let gauge = Gauge::default()
-        .block(Block::default().borders(Borders::ALL).title("Progress"))
+    Gauge::default()
+        .block(Block::default().borders(Borders::ALL).title(title))
         .gauge_style(
             Style::default()
                 .fg(Color::Cyan)
@@ -57,15 +211,34 @@ pub fn render_loading(
         )
         .percent(percentage)
         .label(Span::styled(
-            progress_text,
+            label,
             Style::default()
                 .fg(Color::White)
                 .add_modifier(Modifier::BOLD),
-        ));
-    f.render_widget(gauge, chunks[1]);
+        ))
+}
+
+fn render_cancel_help(f: &mut Frame, area: Rect) {
+    let help = Paragraph::new("Press Esc to cancel")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(help, area);
 }
 
-pub fn render_success(f: &mut Frame, message: &str, details: Option<&str>, details_scroll: usize, area: Rect) {
+pub fn render_success(
+    f: &mut Frame,
+    message: &str,
+    details: Option<&str>,
+    details_scroll: usize,
+    can_preview: bool,
+    area: Rect,
+) {
+    let help_text = if can_preview {
+        "Press Enter to continue | p: Preview markdown"
+    } else {
+        "Press Enter to continue"
+    };
+
     let block = Block::default()
         .borders(Borders::ALL)
         .title("Success")
@@ -128,12 +301,12 @@ pub fn render_success(f: &mut Frame, message: &str, details: Option<&str>, detai
             .scroll((details_scroll as u16, 0));
         f.render_widget(details_widget, chunks[2]);
 
-        let help = Paragraph::new("Press Enter to continue")
+        let help = Paragraph::new(help_text)
             .style(Style::default().fg(Color::DarkGray))
             .alignment(Alignment::Center);
         f.render_widget(help, chunks[3]);
     } else {
-        let help = Paragraph::new("Press Enter to continue")
+        let help = Paragraph::new(help_text)
             .style(Style::default().fg(Color::DarkGray))
             .alignment(Alignment::Center);
         f.render_widget(help, chunks[2]);
@@ -179,3 +352,44 @@ pub fn render_error(f: &mut Frame, message: &str, area: Rect) {
         .alignment(Alignment::Center);
     f.render_widget(help, chunks[2]);
 }
+
+pub fn render_confirm_overwrite(f: &mut Frame, existing: &[String], area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Overwrite?")
+        .border_style(Style::default().fg(Color::Yellow));
+
+    let popup_area = centered_rect(70, 40, area);
+    f.render_widget(Clear, popup_area);
+    f.render_widget(block.clone(), popup_area);
+
+    let inner = block.inner(popup_area);
+    let [title_area, files_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(2), Constraint::Min(0), Constraint::Length(1)])
+        .areas(inner);
+
+    let title = if existing.len() == 1 {
+        "This file already exists:"
+    } else {
+        "These files already exist:"
+    };
+    let title = Paragraph::new(title)
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .alignment(Alignment::Center);
+    f.render_widget(title, title_area);
+
+    let files = Paragraph::new(existing.join("\n"))
+        .style(Style::default().fg(Color::White))
+        .alignment(Alignment::Center);
+    f.render_widget(files, files_area);
+
+    let help = Paragraph::new("y: Overwrite | t: Add timestamp to name | n/Esc: Back")
+        .style(Style::default().fg(Color::DarkGray))
+        .alignment(Alignment::Center);
+    f.render_widget(help, help_area);
+}
@@ -0,0 +1,38 @@
+use ratatui::{
+    layout::Rect,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::types::TaskLog;
+
+/// Renders the newest log lines that fit, offset by the log's scroll
+pub fn render(f: &mut Frame, log: &TaskLog, area: Rect) {
+    let title = if log.scroll > 0 {
+        format!("Log (-{} lines) | l: Hide | PgUp/PgDn: Scroll", log.scroll)
+    } else {
+        "Log | l: Hide | PgUp/PgDn: Scroll".to_string()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(Style::default().fg(Color::DarkGray));
+
+    let height = block.inner(area).height as usize;
+    let mut lines: Vec<Line> = log
+        .lines()
+        .rev()
+        .skip(log.scroll)
+        .take(height)
+        .map(Line::from)
+        .collect();
+    lines.reverse();
+
+    if lines.is_empty() {
+        lines.push(Line::styled("No activity yet", Style::default().fg(Color::DarkGray)));
+    }
+
+    f.render_widget(Paragraph::new(lines).block(block), area);
+}
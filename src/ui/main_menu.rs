@@ -25,7 +25,7 @@ pub fn render(f: &mut Frame, menu_state: &mut ListState, area: Rect) {
 
     f.render_stateful_widget(list, area, menu_state);
 
-    let help = Paragraph::new("↑/↓: Navigate | Enter: Select | q: Quit")
+    let help = Paragraph::new("↑/↓: Navigate | Enter: Select | l: Log | q: Quit")
         .style(Style::default().fg(Color::DarkGray))
         .alignment(Alignment::Center);
 
@@ -7,6 +7,8 @@ use ratatui::{
 use super::{render_checkbox_field, render_help_text, render_static_field, types::MarkdownExportField};
 
 pub struct MarkdownExportProps<'a> {
+    /// Exporting the conversation editor's messages instead of a file
+    pub from_editor: bool,
     pub conversations_path: &'a str,
     pub users_path: &'a str,
     pub channels_path: &'a str,
@@ -18,6 +20,7 @@ pub struct MarkdownExportProps<'a> {
 
 pub fn render(f: &mut Frame, props: MarkdownExportProps, area: Rect) {
     let MarkdownExportProps {
+        from_editor,
         conversations_path,
         users_path,
         channels_path,
@@ -26,14 +29,24 @@ pub fn render(f: &mut Frame, props: MarkdownExportProps, area: Rect) {
         backslash_line_breaks,
         active_field,
     } = props;
-    let block = Block::default()
-        .borders(Borders::ALL)
-        .title("Selected Conversations to Markdown");
+    let block = Block::default().borders(Borders::ALL).title(if from_editor {
+        "Edited Conversations to Markdown"
+    } else {
+        "Selected Conversations to Markdown"
+    });
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    let [
+        conversations_area,
+        users_area,
+        channels_area,
+        output_area,
+        formatter_area,
+        checkbox_area,
+        help_area,
+    ] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
@@ -45,19 +58,23 @@ pub fn render(f: &mut Frame, props: MarkdownExportProps, area: Rect) {
             Constraint::Length(3),
             Constraint::Min(1),
         ])
-        .split(inner);
+        .areas(inner);
 
-    render_static_field(f, conversations_path, "Selected Conversations File", active_field == MarkdownExportField::Conversations, chunks[0]);
-    render_static_field(f, users_path, "Users File", active_field == MarkdownExportField::Users, chunks[1]);
-    render_static_field(f, channels_path, "Channels File", active_field == MarkdownExportField::Channels, chunks[2]);
-    render_static_field(f, output_path, "Output File", active_field == MarkdownExportField::Output, chunks[3]);
-    render_static_field(f, formatter_script, "Formatter Script (optional)", active_field == MarkdownExportField::FormatterScript, chunks[4]);
+    if from_editor {
+        render_static_field(f, "enabled messages in the conversation editor", "Selected Conversations", false, conversations_area);
+    } else {
+        render_static_field(f, conversations_path, "Selected Conversations File", active_field == MarkdownExportField::Conversations, conversations_area);
+    }
+    render_static_field(f, users_path, "Users File", active_field == MarkdownExportField::Users, users_area);
+    render_static_field(f, channels_path, "Channels File", active_field == MarkdownExportField::Channels, channels_area);
+    render_static_field(f, output_path, "Output File", active_field == MarkdownExportField::Output, output_area);
+    render_static_field(f, formatter_script, "Formatter Script (optional)", active_field == MarkdownExportField::FormatterScript, formatter_area);
     render_checkbox_field(
         f, backslash_line_breaks,
         "Backslash Line Breaks (adds \\ before newlines)",
         "Options",
         active_field == MarkdownExportField::BackslashLineBreaks,
-        chunks[5],
+        checkbox_area,
     );
-    render_help_text(f, "Tab: Next Field | Space: Toggle Checkbox | Enter: Export | Esc: Back", chunks[6]);
+    render_help_text(f, "Tab: Next Field | Space: Toggle Checkbox | F5: Preview Output | Enter: Export | Esc: Back", help_area);
 }
@@ -0,0 +1,146 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph, Wrap},
+    Frame,
+};
+
+use super::render_help_text;
+
+pub fn render(f: &mut Frame, path: &str, content: &str, scroll: u16, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title(format!("Markdown Preview ({})", path));
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [content_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Min(1),    // Content
+            Constraint::Length(1), // Help
+        ])
+        .areas(inner);
+
+    let preview = Paragraph::new(markdown_lines(content))
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0));
+    f.render_widget(preview, content_area);
+
+    render_help_text(
+        f,
+        "↑/↓: Scroll | PgUp/PgDn: Page | Home: Top | Esc: Back",
+        help_area,
+    );
+}
+
+/// Style the block-level markdown the exporter writes: headings, quotes,
+/// bullets, rules and fenced code, with inline styling inside them
+fn markdown_lines(content: &str) -> Vec<Line<'static>> {
+    let code_style = Style::default().fg(Color::Yellow);
+    let mut in_code = false;
+    let mut lines = Vec::new();
+
+    for line in content.lines() {
+        if line.trim_start().starts_with("```") {
+            in_code = !in_code;
+            lines.push(Line::styled(line.to_string(), Style::default().fg(Color::DarkGray)));
+            continue;
+        }
+        if in_code {
+            lines.push(Line::styled(line.to_string(), code_style));
+            continue;
+        }
+
+        let trimmed = line.trim_start();
+        let level = trimmed.chars().take_while(|c| *c == '#').count();
+        if (1..=6).contains(&level)
+            && let Some(title) = trimmed.get(level..).and_then(|t| t.strip_prefix(' '))
+        {
+            let mut style = Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD);
+            if level == 1 {
+                style = style.add_modifier(Modifier::UNDERLINED);
+            }
+            lines.push(Line::styled(title.to_string(), style));
+        } else if let Some(quote) = trimmed.strip_prefix('>') {
+            let quote = quote.strip_prefix(' ').unwrap_or(quote);
+            let mut spans = vec![Span::styled("│ ", Style::default().fg(Color::DarkGray))];
+            spans.extend(
+                inline_spans(quote)
+                    .into_iter()
+                    .map(|s| s.patch_style(Style::default().add_modifier(Modifier::ITALIC))),
+            );
+            lines.push(Line::from(spans));
+        } else if matches!(trimmed, "---" | "***" | "___") {
+            lines.push(Line::styled("─".repeat(40), Style::default().fg(Color::DarkGray)));
+        } else if let Some(item) = trimmed.strip_prefix("- ").or_else(|| trimmed.strip_prefix("* ")) {
+            let indent = line.strip_suffix(trimmed).unwrap_or_default();
+            let mut spans = vec![Span::raw(format!("{}• ", indent))];
+            spans.extend(inline_spans(item));
+            lines.push(Line::from(spans));
+        } else {
+            lines.push(Line::from(inline_spans(line)));
+        }
+    }
+
+    lines
+}
+
+/// Split a line into spans for `**bold**`, `` `code` `` and `[text](url)`.
+/// Unclosed markers are kept as plain text.
+fn inline_spans(text: &str) -> Vec<Span<'static>> {
+    let mut spans = Vec::new();
+    let mut plain = String::new();
+    let mut rest = text;
+
+    while let Some(c) = rest.chars().next() {
+        let styled = if let Some(after) = rest.strip_prefix("**") {
+            after.split_once("**").map(|(bold, tail)| {
+                (
+                    Span::styled(bold.to_string(), Style::default().add_modifier(Modifier::BOLD)),
+                    tail,
+                )
+            })
+        } else if let Some(after) = rest.strip_prefix('`') {
+            after
+                .split_once('`')
+                .map(|(code, tail)| (Span::styled(code.to_string(), Style::default().fg(Color::Yellow)), tail))
+        } else if let Some(after) = rest.strip_prefix('[') {
+            after.split_once("](").and_then(|(label, tail)| {
+                tail.split_once(')').map(|(_, tail)| {
+                    (
+                        Span::styled(
+                            label.to_string(),
+                            Style::default().fg(Color::Blue).add_modifier(Modifier::UNDERLINED),
+                        ),
+                        tail,
+                    )
+                })
+            })
+        } else {
+            None
+        };
+
+        match styled {
+            Some((span, tail)) => {
+                if !plain.is_empty() {
+                    spans.push(Span::raw(std::mem::take(&mut plain)));
+                }
+                spans.push(span);
+                rest = tail;
+            }
+            None => {
+                plain.push(c);
+                rest = rest.get(c.len_utf8()..).unwrap_or_default();
+            }
+        }
+    }
+
+    if !plain.is_empty() {
+        spans.push(Span::raw(plain));
+    }
+    spans
+}
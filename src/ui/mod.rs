@@ -3,7 +3,9 @@ pub mod types;
 mod archive_range;
 mod channel_list;
 mod download_attachments;
+mod download_results;
 mod edit_conversations;
+mod emoji_browser;
 mod export_conversations;
 mod export_conversations_week;
 mod export_emojis;
@@ -11,16 +13,20 @@ mod export_index;
 mod export_simple;
 mod import_meilisearch;
 mod loading;
+mod log_pane;
 mod main_menu;
 mod markdown_export;
+mod markdown_preview;
 mod md_to_html;
+mod pipeline;
 mod query_meilisearch;
+mod search_local;
 
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Paragraph},
+    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
     Frame,
 };
 pub use types::*;
@@ -84,6 +90,11 @@ pub fn render_checkbox_field(
     f.render_widget(widget, area);
 }
 
+/// A secret as shown in a form: whether it is set, never its length
+pub fn mask_secret(value: &str) -> &'static str {
+    if value.is_empty() { "(none)" } else { "********" }
+}
+
 /// Renders a help text line centered with DarkGray styling.
 pub fn render_help_text(f: &mut Frame, text: &str, area: Rect) {
     let help = Paragraph::new(text)
@@ -93,10 +104,10 @@ pub fn render_help_text(f: &mut Frame, text: &str, area: Rect) {
 }
 
 pub fn ui(f: &mut Frame, app: &mut App) {
-    let chunks = Layout::default()
+    let [banner_area, body_area] = Layout::default()
         .direction(Direction::Vertical)
         .constraints([Constraint::Length(3), Constraint::Min(0)])
-        .split(f.area());
+        .areas(f.area());
 
     let (iso_year, iso_week) = crate::current_iso_week();
     let today = chrono::Local::now().date_naive();
@@ -115,10 +126,21 @@ pub fn ui(f: &mut Frame, app: &mut App) {
     .alignment(Alignment::Center)
     .block(Block::default().borders(Borders::ALL));
 
-    f.render_widget(banner, chunks[0]);
+    f.render_widget(banner, banner_area);
+
+    let body = if app.log.visible {
+        let [screen_area, log_area] = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Percentage(35)])
+            .areas(body_area);
+        log_pane::render(f, &app.log, log_area);
+        screen_area
+    } else {
+        body_area
+    };
 
     match &mut app.screen {
-        Screen::MainMenu => main_menu::render(f, &mut app.menu_state, chunks[1]),
+        Screen::MainMenu => main_menu::render(f, &mut app.menu_state, body),
         Screen::ExportConversations {
             from_date,
             to_date,
@@ -134,7 +156,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             *active_field,
             channel_selection.as_mut(),
             *loading_channels,
-            chunks[1],
+            body,
         ),
         Screen::ExportConversationsWeek {
             year,
@@ -151,7 +173,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             *active_field,
             channel_selection.as_mut(),
             *loading_channels,
-            chunks[1],
+            body,
         ),
         Screen::ArchiveRange {
             from_year,
@@ -168,24 +190,34 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             to_week,
             output_path,
             *active_field,
-            chunks[1],
+            body,
         ),
         Screen::ExportUsers { output_path } => {
-            export_simple::render(f, "Export Users", output_path, chunks[1])
+            export_simple::render(f, "Export Users", output_path, body)
         }
         Screen::ExportChannels { output_path } => {
-            export_simple::render(f, "Export Channels", output_path, chunks[1])
+            export_simple::render(f, "Export Channels", output_path, body)
         }
         Screen::DownloadAttachments {
             conversations_path,
             output_path,
+            types,
+            max_size,
+            from_date,
+            to_date,
             active_field,
         } => download_attachments::render(
             f,
-            conversations_path,
-            output_path,
-            *active_field,
-            chunks[1],
+            download_attachments::DownloadAttachmentsProps {
+                conversations_path,
+                output_path,
+                types,
+                max_size,
+                from_date,
+                to_date,
+                active_field: *active_field,
+            },
+            body,
         ),
         Screen::MarkdownExport {
             conversations_path,
@@ -195,9 +227,11 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             formatter_script,
             backslash_line_breaks,
             active_field,
+            editor,
         } => markdown_export::render(
             f,
             markdown_export::MarkdownExportProps {
+                from_editor: editor.is_some(),
                 conversations_path,
                 users_path,
                 channels_path,
@@ -206,13 +240,13 @@ pub fn ui(f: &mut Frame, app: &mut App) {
                 backslash_line_breaks: *backslash_line_breaks,
                 active_field: *active_field,
             },
-            chunks[1],
+            body,
         ),
         Screen::ExportEmojis {
             output_path,
             emojis_folder,
             active_field,
-        } => export_emojis::render(f, output_path, emojis_folder, *active_field, chunks[1]),
+        } => export_emojis::render(f, output_path, emojis_folder, *active_field, body),
         Screen::ExportIndex {
             conversations_path,
             users_path,
@@ -226,7 +260,7 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             channels_path,
             output_path,
             *active_field,
-            chunks[1],
+            body,
         ),
         Screen::ImportMeilisearch {
             input_path,
@@ -245,13 +279,15 @@ pub fn ui(f: &mut Frame, app: &mut App) {
                 clear: *clear,
                 active_field: *active_field,
             },
-            chunks[1],
+            body,
         ),
         Screen::QueryMeilisearch {
             query,
+            backend,
             url,
             api_key,
             index_name,
+            tantivy_dir,
             active_field,
             results,
             result_state,
@@ -260,22 +296,62 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             f,
             query_meilisearch::QueryMeilisearchProps {
                 query,
+                backend: *backend,
                 url,
                 api_key,
                 index_name,
+                tantivy_dir,
                 active_field: *active_field,
                 results: results.as_ref(),
                 result_state,
                 error: error.as_deref(),
             },
-            chunks[1],
+            body,
+        ),
+        Screen::Pipeline {
+            from_date,
+            to_date,
+            active_field,
+            targets,
+            channel_selection,
+        } => pipeline::render(
+            f,
+            from_date,
+            to_date,
+            *active_field,
+            targets,
+            channel_selection.as_mut(),
+            body,
+        ),
+        Screen::SearchLocal {
+            index_path,
+            query,
+            active_field,
+            source,
+            loading,
+            results,
+            result_state,
+            error,
+        } => search_local::render(
+            f,
+            search_local::SearchLocalProps {
+                index_path,
+                query,
+                active_field: *active_field,
+                source: source.as_ref(),
+                loading: *loading,
+                results,
+                result_state,
+                error: error.as_deref(),
+            },
+            body,
         ),
         Screen::MdToHtml {
             input_path,
             output_path,
             gfm,
             active_field,
-        } => md_to_html::render(f, input_path, output_path, *gfm, *active_field, chunks[1]),
+        } => md_to_html::render(f, input_path, output_path, *gfm, *active_field, body),
         Screen::EditConversationsPathInput {
             conversations_path,
             users_path,
@@ -287,20 +363,27 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             users_path,
             channels_path,
             *active_field,
-            chunks[1],
+            body,
         ),
         Screen::EditConversationsChannelList {
             channels,
             users: _,
             channel_data: _,
             editing_export_path,
-        } => edit_conversations::render_channel_list(f, channels, *editing_export_path, chunks[1]),
+            name_prompt,
+        } => edit_conversations::render_channel_list(
+            f,
+            channels,
+            *editing_export_path,
+            name_prompt.as_ref(),
+            body,
+        ),
         Screen::EditConversationsMessageList {
             channel_idx,
             channels,
             users,
             channel_data: _,
-        } => edit_conversations::render_message_list(f, *channel_idx, channels, users, chunks[1]),
+        } => edit_conversations::render_message_list(f, *channel_idx, channels, users, body),
         Screen::EditConversationsMessageDetail {
             channel_idx,
             message_idx,
@@ -309,6 +392,8 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             channel_data: _,
             attachment_list_state,
             editing_title,
+            editing_text,
+            replies_focused,
         } => edit_conversations::render_message_detail(
             f,
             *channel_idx,
@@ -317,16 +402,81 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             users,
             attachment_list_state,
             editing_title.as_ref(),
-            chunks[1],
+            editing_text.as_mut(),
+            *replies_focused,
+            body,
+        ),
+        Screen::Loading { message, progress } => loading::render_loading(
+            f,
+            message,
+            progress.as_ref(),
+            app.export_progress.as_ref(),
+            app.transfer_progress.as_ref(),
+            app.cancel.is_some(),
+            body,
         ),
-        Screen::Loading { message, progress } => {
-            loading::render_loading(f, message, progress.as_ref(), chunks[1])
-        }
         Screen::Success { message, details, details_scroll } => {
-            loading::render_success(f, message, details.as_deref(), *details_scroll, chunks[1])
+            loading::render_success(
+                f,
+                message,
+                details.as_deref(),
+                *details_scroll,
+                app.markdown_output.is_some(),
+                body,
+            )
         }
-        Screen::Error { message } => loading::render_error(f, message, chunks[1]),
+        Screen::Error { message } => loading::render_error(f, message, body),
+        Screen::MarkdownPreview {
+            path,
+            content,
+            scroll,
+            ..
+        } => markdown_preview::render(f, path, content, *scroll, body),
+        Screen::DownloadResults {
+            message,
+            failures,
+            table_state,
+            ..
+        } => download_results::render(f, message, failures, table_state, body),
+        Screen::EmojiBrowser { browser, source } => emoji_browser::render(f, browser, source, body),
+        Screen::ConfirmOverwrite { existing, .. } => loading::render_confirm_overwrite(f, existing, body),
+    }
+
+    if let Some(picker) = &app.date_picker {
+        picker.render(f, body);
+    }
+
+    if let Some(prompt) = &app.bulk_toggle {
+        let global = matches!(app.screen, Screen::EditConversationsChannelList { .. });
+        edit_conversations::render_bulk_toggle(f, prompt, global, body);
     }
+
+    if let Some(picker) = &mut app.selection_picker {
+        channel_list::render_selection_picker(f, picker, body);
+    }
+
+    if !app.path_candidates.is_empty() {
+        render_path_candidates(f, &app.path_candidates, body);
+    }
+}
+
+/// Lists the candidates of an ambiguous path completion at the bottom of `area`
+fn render_path_candidates(f: &mut Frame, candidates: &[String], area: Rect) {
+    let height = (candidates.len() as u16 + 2).min(area.height / 2).max(3);
+    let popup = Rect {
+        y: area.y + area.height.saturating_sub(height),
+        height: height.min(area.height),
+        ..area
+    };
+    let items: Vec<ListItem> = candidates.iter().map(|c| ListItem::new(c.as_str())).collect();
+    let list = List::new(items).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Completions ({})", candidates.len()))
+            .border_style(Style::default().fg(Color::Yellow)),
+    );
+    f.render_widget(Clear, popup);
+    f.render_widget(list, popup);
 }
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
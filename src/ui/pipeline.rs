@@ -0,0 +1,91 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Borders, Paragraph},
+    Frame,
+};
+
+use super::{
+    active_style, channel_list, render_help_text, render_text_field,
+    types::{ChannelSelection, PipelineField, PipelineTarget, PipelineTargets},
+};
+use crate::widgets::TextInput;
+
+pub fn render(
+    f: &mut Frame,
+    from_date: &TextInput,
+    to_date: &TextInput,
+    active_field: PipelineField,
+    targets: &PipelineTargets,
+    channel_selection: Option<&mut ChannelSelection>,
+    area: Rect,
+) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Full Pipeline");
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [dates_area, steps_area, channels_area, help_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(PipelineTarget::all().len() as u16 + 3),
+            Constraint::Min(6),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+    let [from_area, to_area] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(50), Constraint::Percentage(50)])
+        .areas(dates_area);
+    render_text_field(f, from_date, "From Date (YYYY-MM-DD)", active_field == PipelineField::FromDate, from_area);
+    render_text_field(f, to_date, "To Date (YYYY-MM-DD)", active_field == PipelineField::ToDate, to_area);
+
+    // Export conversations always runs; the rest are optional
+    let focused = active_field == PipelineField::Targets;
+    let mut lines = vec![Line::styled("[x] Export conversations", Style::default().fg(Color::DarkGray))];
+    lines.extend(PipelineTarget::all().iter().enumerate().map(|(i, target)| {
+        let checkbox = if targets.is_enabled(*target) { "[x]" } else { "[ ]" };
+        let style = if focused && i == targets.cursor {
+            Style::default().fg(Color::Black).bg(Color::Yellow)
+        } else {
+            Style::default()
+        };
+        Line::styled(format!("{} {}", checkbox, target.label()), style)
+    }));
+    let targets_widget = Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Steps (paths from each screen's settings)")
+            .border_style(active_style(focused)),
+    );
+    f.render_widget(targets_widget, steps_area);
+
+    let filtering = channel_selection.as_ref().is_some_and(|s| s.filtering);
+
+    channel_list::render(
+        f,
+        channel_selection,
+        false,
+        active_field == PipelineField::Channels,
+        channels_area,
+    );
+
+    let help_text = if filtering {
+        "Type to filter | ^/v: Navigate | Space: Toggle | Enter: Done | Esc: Clear filter"
+    } else {
+        match active_field {
+            PipelineField::Targets => "^/v: Navigate | Space: Toggle step | Tab: Next | Enter: Run | Esc: Back",
+            PipelineField::Channels => {
+                "^/v: Navigate | Space: Toggle | /: Filter | a: All | n: None | s: Sort | p: Saved | Tab: Next | Enter: Run | Esc: Back"
+            }
+            _ => "Tab: Next Field | Ctrl+P: Calendar | Enter: Run | Esc: Back",
+        }
+    };
+    render_help_text(f, help_text, help_area);
+}
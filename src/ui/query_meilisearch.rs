@@ -6,16 +6,21 @@ use ratatui::{
     Frame,
 };
 
-use super::{render_help_text, render_static_field, types::QueryMeilisearchField};
-use crate::index::IndexEntry;
+use super::{
+    mask_secret, render_help_text, render_static_field,
+    types::{QueryMeilisearchField, SearchBackend},
+};
+use crate::index::{split_highlights, SearchHit};
 
 pub struct QueryMeilisearchProps<'a> {
     pub query: &'a str,
+    pub backend: SearchBackend,
     pub url: &'a str,
     pub api_key: &'a str,
     pub index_name: &'a str,
+    pub tantivy_dir: &'a str,
     pub active_field: QueryMeilisearchField,
-    pub results: Option<&'a Vec<IndexEntry>>,
+    pub results: Option<&'a Vec<SearchHit>>,
     pub result_state: &'a mut ListState,
     pub error: Option<&'a str>,
 }
@@ -23,37 +28,67 @@ pub struct QueryMeilisearchProps<'a> {
 pub fn render(f: &mut Frame, props: QueryMeilisearchProps, area: Rect) {
     let block = Block::default()
         .borders(Borders::ALL)
-        .title("Search Meilisearch");
+        .title("Search Index");
 
     let inner = block.inner(area);
     f.render_widget(block, area);
 
-    let chunks = Layout::default()
+    // Meilisearch needs URL, API key and index name; Tantivy only the index directory
+    let backend_rows: u16 = match props.backend {
+        SearchBackend::Meilisearch => 9,
+        SearchBackend::Tantivy => 3,
+    };
+
+    let [query_area, backend_area, settings_area, help_area, results_area] = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
         .constraints([
-            Constraint::Length(3), // Query
-            Constraint::Length(3), // URL
-            Constraint::Length(3), // API Key
-            Constraint::Length(3), // Index name
-            Constraint::Length(2), // Help text
-            Constraint::Min(1),   // Results
+            Constraint::Length(3),            // Query
+            Constraint::Length(3),            // Backend
+            Constraint::Length(backend_rows), // Backend settings
+            Constraint::Length(2),            // Help text
+            Constraint::Min(1),               // Results
         ])
-        .split(inner);
+        .areas(inner);
 
-    render_static_field(f, props.query, "Search Query", props.active_field == QueryMeilisearchField::Query, chunks[0]);
-    render_static_field(f, props.url, "Meilisearch URL", props.active_field == QueryMeilisearchField::Url, chunks[1]);
-
-    // API Key field (masked)
-    let masked_key = if props.api_key.is_empty() {
-        "(none)".to_string()
-    } else {
-        "*".repeat(props.api_key.len().min(20))
+    let query_title = match props.backend {
+        SearchBackend::Meilisearch => "Search Query (from:@user in:#channel before:/after:/on:DATE)",
+        SearchBackend::Tantivy => "Search Query (channel:NAME users:NAME \"phrase\")",
     };
-    render_static_field(f, &masked_key, "API Key", props.active_field == QueryMeilisearchField::ApiKey, chunks[2]);
+    render_static_field(f, props.query, query_title, props.active_field == QueryMeilisearchField::Query, query_area);
+    render_static_field(
+        f,
+        props.backend.label(),
+        "Backend (Space to toggle)",
+        props.active_field == QueryMeilisearchField::Backend,
+        backend_area,
+    );
+
+    match props.backend {
+        SearchBackend::Meilisearch => {
+            let [url_area, api_key_area, index_area] = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(3), Constraint::Length(3), Constraint::Length(3)])
+                .areas(settings_area);
+
+            render_static_field(f, props.url, "Meilisearch URL", props.active_field == QueryMeilisearchField::Url, url_area);
+
+            render_static_field(f, mask_secret(props.api_key), "API Key", props.active_field == QueryMeilisearchField::ApiKey, api_key_area);
+
+            render_static_field(f, props.index_name, "Index Name", props.active_field == QueryMeilisearchField::IndexName, index_area);
+        }
+        SearchBackend::Tantivy => {
+            render_static_field(
+                f,
+                props.tantivy_dir,
+                "Tantivy Index Directory",
+                props.active_field == QueryMeilisearchField::TantivyDir,
+                settings_area,
+            );
+        }
+    }
 
-    render_static_field(f, props.index_name, "Index Name", props.active_field == QueryMeilisearchField::IndexName, chunks[3]);
-    render_help_text(f, "Tab: Next Field | Enter: Search | ↑↓: Navigate Results | Esc: Back", chunks[4]);
+    render_help_text(f, "Tab: Next Field | Enter: Search | ↑↓: Navigate Results | Esc: Back", help_area);
 
     // Results area
     let results_block = Block::default()
@@ -65,18 +100,19 @@ pub fn render(f: &mut Frame, props: QueryMeilisearchProps, area: Rect) {
             .style(Style::default().fg(Color::Red))
             .wrap(Wrap { trim: true })
             .block(results_block);
-        f.render_widget(error_widget, chunks[5]);
+        f.render_widget(error_widget, results_area);
     } else if let Some(results) = props.results {
         if results.is_empty() {
             let no_results = Paragraph::new("No results found.")
                 .style(Style::default().fg(Color::DarkGray))
                 .block(results_block);
-            f.render_widget(no_results, chunks[5]);
+            f.render_widget(no_results, results_area);
         } else {
             let items: Vec<ListItem> = results
                 .iter()
                 .enumerate()
-                .map(|(i, entry)| {
+                .map(|(i, hit)| {
+                    let entry = &hit.entry;
                     let users: String = entry
                         .users
                         .iter()
@@ -84,13 +120,24 @@ pub fn render(f: &mut Frame, props: QueryMeilisearchProps, area: Rect) {
                         .collect::<Vec<_>>()
                         .join(", ");
 
-                    // Truncate text preview
-                    let preview: String = entry
-                        .text
-                        .chars()
-                        .take(100)
-                        .collect::<String>()
-                        .replace('\n', " ");
+                    let mut preview_spans = vec![Span::styled("   ", Style::default())];
+                    match &hit.snippet {
+                        Some(snippet) => preview_spans.extend(highlighted_spans(snippet)),
+                        None => {
+                            // Truncate text preview
+                            let preview: String = entry
+                                .text
+                                .chars()
+                                .take(100)
+                                .collect::<String>()
+                                .replace('\n', " ");
+                            preview_spans.push(Span::raw(if preview.len() >= 100 {
+                                format!("{}...", preview)
+                            } else {
+                                preview
+                            }));
+                        }
+                    }
 
                     let lines = vec![
                         Line::from(vec![
@@ -111,14 +158,7 @@ pub fn render(f: &mut Frame, props: QueryMeilisearchProps, area: Rect) {
                             Span::styled("   Users: ", Style::default().fg(Color::DarkGray)),
                             Span::raw(users),
                         ]),
-                        Line::from(vec![
-                            Span::styled("   ", Style::default()),
-                            Span::raw(if preview.len() >= 100 {
-                                format!("{}...", preview)
-                            } else {
-                                preview
-                            }),
-                        ]),
+                        Line::from(preview_spans),
                     ];
 
                     ListItem::new(lines)
@@ -133,12 +173,30 @@ pub fn render(f: &mut Frame, props: QueryMeilisearchProps, area: Rect) {
                         .add_modifier(Modifier::BOLD),
                 );
 
-            f.render_stateful_widget(list, chunks[5], props.result_state);
+            f.render_stateful_widget(list, results_area, props.result_state);
         }
     } else {
         let placeholder = Paragraph::new("Enter a search query and press Enter to search.")
             .style(Style::default().fg(Color::DarkGray))
             .block(results_block);
-        f.render_widget(placeholder, chunks[5]);
+        f.render_widget(placeholder, results_area);
     }
 }
+
+/// Spans for a highlighted snippet with matched terms emphasized
+pub(super) fn highlighted_spans(snippet: &str) -> Vec<Span<'static>> {
+    let match_style = Style::default()
+        .fg(Color::Yellow)
+        .add_modifier(Modifier::BOLD);
+
+    split_highlights(&snippet.replace('\n', " "))
+        .into_iter()
+        .map(|(segment, is_match)| {
+            if is_match {
+                Span::styled(segment.to_string(), match_style)
+            } else {
+                Span::raw(segment.to_string())
+            }
+        })
+        .collect()
+}
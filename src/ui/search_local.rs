@@ -0,0 +1,158 @@
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
+    Frame,
+};
+
+use super::{
+    query_meilisearch::highlighted_spans,
+    render_help_text, render_text_field,
+    types::{LocalSearchSource, SearchLocalField},
+};
+use crate::index::SearchHit;
+use crate::widgets::TextInput;
+
+pub struct SearchLocalProps<'a> {
+    pub index_path: &'a TextInput,
+    pub query: &'a TextInput,
+    pub active_field: SearchLocalField,
+    pub source: Option<&'a LocalSearchSource>,
+    pub loading: bool,
+    pub results: &'a [SearchHit],
+    pub result_state: &'a mut ListState,
+    pub error: Option<&'a str>,
+}
+
+pub fn render(f: &mut Frame, props: SearchLocalProps, area: Rect) {
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .title("Search (local)");
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let [index_area, query_area, status_area, results_area] = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3), // Index path
+            Constraint::Length(3), // Query
+            Constraint::Length(2), // Status / help
+            Constraint::Min(1),    // Results and preview
+        ])
+        .areas(inner);
+
+    render_text_field(
+        f,
+        props.index_path,
+        "Index (conversation-index.json or Tantivy directory, Enter to load)",
+        props.active_field == SearchLocalField::IndexPath,
+        index_area,
+    );
+    render_text_field(
+        f,
+        props.query,
+        "Search (all terms must match text, channel or user)",
+        props.active_field == SearchLocalField::Query,
+        query_area,
+    );
+
+    let status = if props.loading {
+        "Loading index...".to_string()
+    } else {
+        match props.source {
+            Some(source) => format!(
+                "{} | {} results | Tab: Next Field | ↑↓: Navigate Results | Esc: Back",
+                source.describe(),
+                props.results.len()
+            ),
+            None => "No index loaded | Tab: Next Field | Esc: Back".to_string(),
+        }
+    };
+    render_help_text(f, &status, status_area);
+
+    let [list_pane, preview_pane] = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(45), Constraint::Percentage(55)])
+        .areas(results_area);
+
+    let results_block = Block::default().borders(Borders::ALL).title("Results");
+
+    if let Some(err) = props.error {
+        let error_widget = Paragraph::new(err)
+            .style(Style::default().fg(Color::Red))
+            .wrap(Wrap { trim: true })
+            .block(results_block);
+        f.render_widget(error_widget, list_pane);
+    } else if props.results.is_empty() {
+        let text = if props.query.text().trim().is_empty() {
+            "Start typing to search."
+        } else {
+            "No results found."
+        };
+        let placeholder = Paragraph::new(text)
+            .style(Style::default().fg(Color::DarkGray))
+            .block(results_block);
+        f.render_widget(placeholder, list_pane);
+    } else {
+        let items: Vec<ListItem> = props.results.iter().map(result_item).collect();
+        let list = List::new(items)
+            .block(results_block)
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            );
+        f.render_stateful_widget(list, list_pane, props.result_state);
+    }
+
+    let selected = props
+        .result_state
+        .selected()
+        .and_then(|i| props.results.get(i));
+    let preview_block = Block::default().borders(Borders::ALL).title("Preview");
+    let preview = match selected {
+        Some(hit) => {
+            let users: Vec<&str> = hit.entry.users.iter().map(|u| u.name.as_str()).collect();
+            let mut lines = vec![
+                Line::from(Span::styled(
+                    format!("#{}  {}", hit.entry.channel.name, hit.entry.date),
+                    Style::default().fg(Color::Cyan),
+                )),
+                Line::from(Span::styled(
+                    format!("Users: {}", users.join(", ")),
+                    Style::default().fg(Color::DarkGray),
+                )),
+                Line::default(),
+            ];
+            lines.extend(hit.entry.text.lines().map(|l| Line::from(l.to_string())));
+            Paragraph::new(lines)
+        }
+        None => Paragraph::new("").style(Style::default().fg(Color::DarkGray)),
+    };
+    f.render_widget(preview.wrap(Wrap { trim: false }).block(preview_block), preview_pane);
+}
+
+fn result_item(hit: &SearchHit) -> ListItem<'static> {
+    let entry = &hit.entry;
+    let mut preview_spans = vec![Span::raw("  ")];
+    match &hit.snippet {
+        Some(snippet) => preview_spans.extend(highlighted_spans(snippet)),
+        None => preview_spans.push(Span::raw(
+            entry.text.chars().take(80).collect::<String>().replace('\n', " "),
+        )),
+    }
+
+    ListItem::new(vec![
+        Line::from(vec![
+            Span::styled(
+                format!("[{}] ", entry.date.chars().take(10).collect::<String>()),
+                Style::default().fg(Color::Cyan),
+            ),
+            Span::styled(format!("#{}", entry.channel.name), Style::default().fg(Color::Green)),
+        ]),
+        Line::from(preview_spans),
+    ])
+}
@@ -1,8 +1,10 @@
-use std::collections::HashSet;
-use ratatui::widgets::ListState;
+use std::collections::{HashSet, VecDeque};
+use ratatui::widgets::{ListState, TableState};
 
-use crate::slack::ChannelInfo;
-use crate::widgets::TextInput;
+use crate::emojis::{EmojiEntry, EmojiKind};
+use crate::meilisearch::{parse_search_query, ParsedQuery};
+use crate::slack::{ChannelInfo, DownloadFailure, DownloadOptions};
+use crate::widgets::{DatePicker, PickerMode, TextArea, TextInput};
 use crate::OutputFormat;
 
 /// Trait for types that support list navigation with wrapping.
@@ -88,6 +90,34 @@ pub enum EditConvPathField {
 pub enum DownloadAttachmentsField {
     ConversationsPath,
     OutputPath,
+    Types,
+    MaxSize,
+    FromDate,
+    ToDate,
+}
+
+impl DownloadAttachmentsField {
+    pub fn next(self) -> Self {
+        match self {
+            Self::ConversationsPath => Self::OutputPath,
+            Self::OutputPath => Self::Types,
+            Self::Types => Self::MaxSize,
+            Self::MaxSize => Self::FromDate,
+            Self::FromDate => Self::ToDate,
+            Self::ToDate => Self::ConversationsPath,
+        }
+    }
+
+    pub fn prev(self) -> Self {
+        match self {
+            Self::ConversationsPath => Self::ToDate,
+            Self::OutputPath => Self::ConversationsPath,
+            Self::Types => Self::OutputPath,
+            Self::MaxSize => Self::Types,
+            Self::FromDate => Self::MaxSize,
+            Self::ToDate => Self::FromDate,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -126,9 +156,179 @@ pub enum ImportMeilisearchField {
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum QueryMeilisearchField {
     Query,
+    Backend,
     Url,
     ApiKey,
     IndexName,
+    TantivyDir,
+}
+
+impl QueryMeilisearchField {
+    /// Next field, skipping fields that don't apply to the backend
+    pub fn next(self, backend: SearchBackend) -> Self {
+        match (self, backend) {
+            (Self::Query, _) => Self::Backend,
+            (Self::Backend, SearchBackend::Meilisearch) => Self::Url,
+            (Self::Backend, SearchBackend::Tantivy) => Self::TantivyDir,
+            (Self::Url, _) => Self::ApiKey,
+            (Self::ApiKey, _) => Self::IndexName,
+            (Self::IndexName, _) | (Self::TantivyDir, _) => Self::Query,
+        }
+    }
+
+    /// Previous field, skipping fields that don't apply to the backend
+    pub fn prev(self, backend: SearchBackend) -> Self {
+        match (self, backend) {
+            (Self::Query, SearchBackend::Meilisearch) => Self::IndexName,
+            (Self::Query, SearchBackend::Tantivy) => Self::TantivyDir,
+            (Self::Backend, _) => Self::Query,
+            (Self::Url, _) | (Self::TantivyDir, _) => Self::Backend,
+            (Self::ApiKey, _) => Self::Url,
+            (Self::IndexName, _) => Self::ApiKey,
+        }
+    }
+}
+
+/// Search backend used by the query screen
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchBackend {
+    Meilisearch,
+    Tantivy,
+}
+
+impl SearchBackend {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SearchBackend::Meilisearch => "Meilisearch (server)",
+            SearchBackend::Tantivy => "Tantivy (local index)",
+        }
+    }
+
+    pub fn toggle(self) -> Self {
+        match self {
+            SearchBackend::Meilisearch => SearchBackend::Tantivy,
+            SearchBackend::Tantivy => SearchBackend::Meilisearch,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PipelineField {
+    FromDate,
+    ToDate,
+    Targets,
+    Channels,
+}
+
+/// Optional steps of the full pipeline, run after exporting conversations
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PipelineTarget {
+    Attachments,
+    Markdown,
+    Index,
+    Meilisearch,
+}
+
+impl PipelineTarget {
+    pub fn all() -> [PipelineTarget; 4] {
+        [
+            PipelineTarget::Attachments,
+            PipelineTarget::Markdown,
+            PipelineTarget::Index,
+            PipelineTarget::Meilisearch,
+        ]
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PipelineTarget::Attachments => "Download attachments",
+            PipelineTarget::Markdown => "Export markdown",
+            PipelineTarget::Index => "Export index",
+            PipelineTarget::Meilisearch => "Import index to Meilisearch",
+        }
+    }
+}
+
+/// Targets of the pipeline screen with the one under the cursor
+#[derive(Debug, Clone)]
+pub struct PipelineTargets {
+    pub enabled: Vec<PipelineTarget>,
+    pub cursor: usize,
+}
+
+impl PipelineTargets {
+    pub fn is_enabled(&self, target: PipelineTarget) -> bool {
+        self.enabled.contains(&target)
+    }
+
+    /// Toggle the target under the cursor; Meilisearch imports the index, so
+    /// enabling it enables the index and disabling the index disables it
+    pub fn toggle_current(&mut self) {
+        let Some(target) = PipelineTarget::all().get(self.cursor).copied() else {
+            return;
+        };
+        if self.is_enabled(target) {
+            self.enabled.retain(|t| *t != target);
+            if target == PipelineTarget::Index {
+                self.enabled.retain(|t| *t != PipelineTarget::Meilisearch);
+            }
+        } else {
+            self.enabled.push(target);
+            if target == PipelineTarget::Meilisearch && !self.is_enabled(PipelineTarget::Index) {
+                self.enabled.push(PipelineTarget::Index);
+            }
+        }
+    }
+
+    pub fn previous(&mut self) {
+        self.cursor = self.cursor.checked_sub(1).unwrap_or(PipelineTarget::all().len() - 1);
+    }
+
+    pub fn next(&mut self) {
+        self.cursor = (self.cursor + 1) % PipelineTarget::all().len();
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SearchLocalField {
+    IndexPath,
+    Query,
+}
+
+/// Index searched by the local search screen
+#[derive(Debug, Clone)]
+pub enum LocalSearchSource {
+    /// `conversation-index.json` held in memory
+    Json(crate::index::LocalIndex),
+    /// Tantivy index directory, queried on every keystroke
+    Tantivy(String),
+}
+
+impl LocalSearchSource {
+    /// A directory is opened as a Tantivy index, anything else is loaded as JSON
+    pub fn load(path: &str) -> crate::Result<Self> {
+        if std::path::Path::new(path).is_dir() {
+            crate::tantivy_search::query_tantivy(path, "*", 1)?;
+            Ok(Self::Tantivy(path.to_string()))
+        } else {
+            crate::index::LocalIndex::load(path).map(Self::Json)
+        }
+    }
+
+    pub fn search(&self, query: &str, limit: usize) -> crate::Result<Vec<crate::index::SearchHit>> {
+        match self {
+            Self::Json(index) => Ok(index.search(query, limit)),
+            Self::Tantivy(_) if query.trim().is_empty() => Ok(Vec::new()),
+            Self::Tantivy(dir) => crate::tantivy_search::query_tantivy(dir, query, limit).map(|r| r.hits),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Self::Json(index) => format!("{} entries in memory", index.len()),
+            Self::Tantivy(dir) => format!("Tantivy index {}", dir),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -152,7 +352,10 @@ pub enum MenuItem {
     ExportIndex,
     ImportIndexMeilisearch,
     QueryMeilisearch,
+    SearchLocal,
+    Pipeline,
     ExportEmojis,
+    BrowseEmojis,
     MdToHtml,
     Exit,
 }
@@ -165,13 +368,16 @@ impl MenuItem {
             MenuItem::ExportConversations,
             MenuItem::ExportConversationsWeek,
             MenuItem::ArchiveRange,
+            MenuItem::Pipeline,
             MenuItem::DownloadAttachments,
             MenuItem::EditConversations,
             MenuItem::SelectedConversationsToMarkdown,
             MenuItem::ExportIndex,
             MenuItem::ImportIndexMeilisearch,
             MenuItem::QueryMeilisearch,
+            MenuItem::SearchLocal,
             MenuItem::ExportEmojis,
+            MenuItem::BrowseEmojis,
             MenuItem::MdToHtml,
             MenuItem::Exit,
         ]
@@ -189,8 +395,11 @@ impl MenuItem {
             MenuItem::SelectedConversationsToMarkdown => "Export Conversations to Markdown",
             MenuItem::ExportIndex => "Export Index",
             MenuItem::ImportIndexMeilisearch => "Import Index to Meilisearch",
-            MenuItem::QueryMeilisearch => "Search Meilisearch",
+            MenuItem::QueryMeilisearch => "Search Index (Meilisearch / Tantivy)",
+            MenuItem::SearchLocal => "Search (local)",
+            MenuItem::Pipeline => "Full Pipeline (export -> markdown -> index -> import)",
             MenuItem::ExportEmojis => "Export Custom Emojis",
+            MenuItem::BrowseEmojis => "Browse Exported Emojis",
             MenuItem::MdToHtml => "Convert Markdown to HTML",
             MenuItem::Exit => "Exit",
         }
@@ -204,6 +413,26 @@ pub struct ExtractedLink {
     pub title: String,
 }
 
+// Editable thread reply type
+#[derive(Debug, Clone)]
+pub struct EditableReply {
+    pub original: serde_json::Value,
+    pub enabled: bool,
+}
+
+impl EditableReply {
+    pub fn text(&self) -> &str {
+        self.original
+            .get("text")
+            .and_then(|t| t.as_str())
+            .unwrap_or("")
+    }
+
+    pub fn user_id(&self) -> Option<&str> {
+        self.original.get("user").and_then(|u| u.as_str())
+    }
+}
+
 // Editable message type
 #[derive(Debug, Clone)]
 pub struct EditableMessage {
@@ -215,6 +444,11 @@ pub struct EditableMessage {
     pub main_link: Option<usize>,
     pub custom_link_titles: std::collections::HashMap<usize, String>,
     pub custom_file_titles: std::collections::HashMap<usize, String>,
+    /// Replacement for the message text, written to `text` on export
+    pub edited_text: Option<String>,
+    /// Thread replies in export order, deleted ones are removed
+    pub replies: Vec<EditableReply>,
+    pub reply_list_state: ListState,
 }
 
 impl EditableMessage {
@@ -229,6 +463,23 @@ impl EditableMessage {
         let links_count = links.len();
         let main_link = if links_count > 0 { Some(0) } else { None };
 
+        let replies: Vec<EditableReply> = original
+            .get("thread_replies")
+            .and_then(|r| r.as_array())
+            .map(|a| {
+                a.iter()
+                    .map(|reply| EditableReply {
+                        original: reply.clone(),
+                        enabled: true,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let mut reply_list_state = ListState::default();
+        if !replies.is_empty() {
+            reply_list_state.select(Some(0));
+        }
+
         Self {
             original,
             enabled: true,
@@ -238,14 +489,31 @@ impl EditableMessage {
             main_link,
             custom_link_titles: std::collections::HashMap::new(),
             custom_file_titles: std::collections::HashMap::new(),
+            edited_text: None,
+            replies,
+            reply_list_state,
         }
     }
 
     pub fn text(&self) -> &str {
-        self.original
-            .get("text")
-            .and_then(|t| t.as_str())
-            .unwrap_or("")
+        match &self.edited_text {
+            Some(text) => text,
+            None => self
+                .original
+                .get("text")
+                .and_then(|t| t.as_str())
+                .unwrap_or(""),
+        }
+    }
+
+    /// Replace the message text, an unchanged text clears the edit
+    pub fn set_text(&mut self, text: String) {
+        let original = self.original.get("text").and_then(|t| t.as_str());
+        self.edited_text = if original == Some(text.as_str()) {
+            None
+        } else {
+            Some(text)
+        };
     }
 
     pub fn user_id(&self) -> Option<&str> {
@@ -365,6 +633,57 @@ impl EditableMessage {
     pub fn set_file_title(&mut self, idx: usize, title: String) {
         self.custom_file_titles.insert(idx, title);
     }
+
+    pub fn toggle_current_reply(&mut self) {
+        if let Some(idx) = self.reply_list_state.selected()
+            && let Some(reply) = self.replies.get_mut(idx)
+        {
+            reply.enabled = !reply.enabled;
+        }
+    }
+
+    pub fn delete_current_reply(&mut self) {
+        if let Some(idx) = self.reply_list_state.selected()
+            && idx < self.replies.len()
+        {
+            self.replies.remove(idx);
+            let selected = if self.replies.is_empty() {
+                None
+            } else {
+                Some(idx.min(self.replies.len() - 1))
+            };
+            self.reply_list_state.select(selected);
+        }
+    }
+
+    pub fn move_current_reply_up(&mut self) {
+        if let Some(idx) = self.reply_list_state.selected()
+            && idx > 0
+            && idx < self.replies.len()
+        {
+            self.replies.swap(idx, idx - 1);
+            self.reply_list_state.select(Some(idx - 1));
+        }
+    }
+
+    pub fn move_current_reply_down(&mut self) {
+        if let Some(idx) = self.reply_list_state.selected()
+            && idx + 1 < self.replies.len()
+        {
+            self.replies.swap(idx, idx + 1);
+            self.reply_list_state.select(Some(idx + 1));
+        }
+    }
+}
+
+impl ListNavigation for EditableMessage {
+    fn items_len(&self) -> usize {
+        self.replies.len()
+    }
+
+    fn list_state_mut(&mut self) -> &mut ListState {
+        &mut self.reply_list_state
+    }
 }
 
 // Editable channel type
@@ -373,13 +692,31 @@ pub struct EditableChannel {
     pub id: String,
     pub name: String,
     pub messages: Vec<EditableMessage>,
+    /// Highlighted row among the visible messages
     pub list_state: ListState,
+    /// Filter typed after `/`, with the search modifiers `from:`, `before:`,
+    /// `after:`, `on:` and `has:attachment`; empty shows every message
+    pub filter: TextInput,
+    /// Whether typed keys go to the filter
+    pub filtering: bool,
+    /// Why the filter text couldn't be parsed, the previous matches stay shown
+    pub filter_error: Option<String>,
+    /// Indices into `messages` matching the filter, in message order
+    visible: Vec<usize>,
+    /// Picked to be merged with the other marked channels
+    pub marked: bool,
+    /// Names of the channels merged into this one, empty when it wasn't merged
+    pub merged_from: Vec<String>,
 }
 
 impl EditableChannel {
     pub fn new(id: String, name: String, messages: Vec<serde_json::Value>) -> Self {
         let messages: Vec<EditableMessage> =
             messages.into_iter().map(EditableMessage::new).collect();
+        Self::from_messages(id, name, messages)
+    }
+
+    fn from_messages(id: String, name: String, messages: Vec<EditableMessage>) -> Self {
         let mut list_state = ListState::default();
         if !messages.is_empty() {
             list_state.select(Some(0));
@@ -387,8 +724,14 @@ impl EditableChannel {
         Self {
             id,
             name,
+            visible: (0..messages.len()).collect(),
             messages,
             list_state,
+            filter: TextInput::default(),
+            filtering: false,
+            filter_error: None,
+            marked: false,
+            merged_from: Vec::new(),
         }
     }
 
@@ -396,8 +739,18 @@ impl EditableChannel {
         self.messages.iter().filter(|m| m.enabled).count()
     }
 
+    /// Messages matching the filter, in display order
+    pub fn visible_messages(&self) -> impl Iterator<Item = &EditableMessage> {
+        self.visible.iter().filter_map(|&idx| self.messages.get(idx))
+    }
+
+    /// Index into `messages` of the highlighted message
+    pub fn current_index(&self) -> Option<usize> {
+        self.visible.get(self.list_state.selected()?).copied()
+    }
+
     pub fn toggle_current(&mut self) {
-        if let Some(idx) = self.list_state.selected()
+        if let Some(idx) = self.current_index()
             && let Some(msg) = self.messages.get_mut(idx)
         {
             msg.enabled = !msg.enabled;
@@ -405,35 +758,243 @@ impl EditableChannel {
     }
 
     pub fn toggle_collapse_current(&mut self) {
-        if let Some(idx) = self.list_state.selected()
+        if let Some(idx) = self.current_index()
             && let Some(msg) = self.messages.get_mut(idx)
         {
             msg.collapsed = !msg.collapsed;
         }
     }
 
+    /// Enable or disable every visible message, leaving hidden ones as they are
+    pub fn set_visible_enabled(&mut self, enabled: bool) {
+        for &idx in &self.visible {
+            if let Some(msg) = self.messages.get_mut(idx) {
+                msg.enabled = enabled;
+            }
+        }
+    }
+
+    /// Enable or disable the messages `matcher` selects, filtered out ones
+    /// included. Returns how many changed.
+    pub fn set_enabled_matching(&mut self, matcher: &BulkMatcher, users: &serde_json::Value, enabled: bool) -> usize {
+        let mut changed = 0;
+        for msg in &mut self.messages {
+            if msg.enabled != enabled && matcher.matches(msg, users) {
+                msg.enabled = enabled;
+                changed += 1;
+            }
+        }
+        changed
+    }
+
+    /// Swap the highlighted message with the visible one above it
     pub fn move_current_up(&mut self) {
-        if let Some(idx) = self.list_state.selected()
-            && idx > 0
+        if let Some(pos) = self.list_state.selected()
+            && pos > 0
+            && let (Some(&a), Some(&b)) = (self.visible.get(pos - 1), self.visible.get(pos))
         {
-            self.messages.swap(idx, idx - 1);
-            self.list_state.select(Some(idx - 1));
+            self.messages.swap(a, b);
+            self.list_state.select(Some(pos - 1));
         }
     }
 
+    /// Swap the highlighted message with the visible one below it
     pub fn move_current_down(&mut self) {
-        if let Some(idx) = self.list_state.selected()
-            && idx < self.messages.len() - 1
+        if let Some(pos) = self.list_state.selected()
+            && let (Some(&a), Some(&b)) = (self.visible.get(pos), self.visible.get(pos + 1))
         {
-            self.messages.swap(idx, idx + 1);
-            self.list_state.select(Some(idx + 1));
+            self.messages.swap(a, b);
+            self.list_state.select(Some(pos + 1));
+        }
+    }
+
+    /// Recompute the visible messages after the filter text changed, keeping
+    /// the highlighted message when it still matches. `users` resolves
+    /// `from:` names.
+    pub fn apply_filter(&mut self, users: &serde_json::Value) {
+        let query = match parse_search_query(self.filter.text()) {
+            Ok(query) => query,
+            Err(e) => {
+                self.filter_error = Some(e.to_string());
+                return;
+            }
+        };
+        self.filter_error = None;
+
+        let current = self.current_index();
+        self.visible = self
+            .messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| message_matches(msg, &query, users))
+            .map(|(idx, _)| idx)
+            .collect();
+
+        let position = current
+            .and_then(|idx| self.visible.iter().position(|&v| v == idx))
+            .or((!self.visible.is_empty()).then_some(0));
+        self.list_state.select(position);
+    }
+
+    /// Stop filtering and show every message again
+    pub fn clear_filter(&mut self, users: &serde_json::Value) {
+        self.filter = TextInput::default();
+        self.filtering = false;
+        self.apply_filter(users);
+    }
+}
+
+/// Entry of `users` with the given ID
+fn find_user<'a>(users: &'a serde_json::Value, uid: &str) -> Option<&'a serde_json::Value> {
+    users
+        .as_array()?
+        .iter()
+        .find(|u| u.get("id").and_then(|i| i.as_str()) == Some(uid))
+}
+
+/// Whether `msg` was posted by the user with this name or ID, ignoring case
+fn is_author(msg: &EditableMessage, wanted: &str, users: &serde_json::Value) -> bool {
+    let wanted = wanted.to_lowercase();
+    let Some(uid) = msg.user_id() else {
+        return false;
+    };
+    let user = find_user(users, uid);
+    let names = ["name", "real_name"]
+        .iter()
+        .filter_map(|key| user?.get(*key)?.as_str());
+    uid.to_lowercase() == wanted || names.into_iter().any(|n| n.to_lowercase() == wanted)
+}
+
+/// Messages a bulk toggle applies to, parsed from its prompt
+#[derive(Debug, Clone)]
+pub enum BulkMatcher {
+    /// `from:@name`, by user name or ID
+    Author(String),
+    /// `from:bot`, messages posted by bots and integrations
+    Bots,
+    /// Anything else: a case-insensitive regex over the message text
+    Text(regex::Regex),
+}
+
+impl BulkMatcher {
+    pub fn parse(input: &str) -> std::result::Result<Self, String> {
+        let input = input.trim();
+        if input.is_empty() {
+            return Err("enter a regex, from:@user or from:bot".to_string());
+        }
+        match input.strip_prefix("from:").map(|name| name.trim_start_matches('@')) {
+            Some("bot" | "bots") => Ok(BulkMatcher::Bots),
+            Some(name) if !name.is_empty() => Ok(BulkMatcher::Author(name.to_string())),
+            _ => regex::RegexBuilder::new(input)
+                .case_insensitive(true)
+                .build()
+                .map(BulkMatcher::Text)
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    pub fn matches(&self, msg: &EditableMessage, users: &serde_json::Value) -> bool {
+        match self {
+            BulkMatcher::Author(name) => is_author(msg, name, users),
+            BulkMatcher::Bots => {
+                msg.original.get("bot_id").is_some()
+                    || msg.original.get("subtype").and_then(|s| s.as_str()) == Some("bot_message")
+                    || msg
+                        .user_id()
+                        .and_then(|uid| find_user(users, uid)?.get("is_bot")?.as_bool())
+                        .unwrap_or(false)
+            }
+            BulkMatcher::Text(regex) => regex.is_match(msg.text()),
         }
     }
 }
 
+/// What the conversation editor's channel name prompt does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ChannelNameAction {
+    Rename,
+    Merge,
+}
+
+/// Prompt for a channel name in the conversation editor's channel list
+#[derive(Debug, Clone)]
+pub struct ChannelNamePrompt {
+    pub action: ChannelNameAction,
+    pub input: TextInput,
+    /// Why the action couldn't be applied
+    pub error: Option<String>,
+}
+
+/// Prompt of the conversation editor's bulk enable/disable, over the
+/// channel list (all channels) or a message list (that channel)
+#[derive(Debug, Clone)]
+pub struct BulkToggle {
+    /// Enable the matching messages, otherwise disable them
+    pub enable: bool,
+    pub input: TextInput,
+    /// Why the pattern was rejected
+    pub error: Option<String>,
+}
+
+impl BulkToggle {
+    pub fn new(enable: bool) -> Self {
+        Self {
+            enable,
+            input: TextInput::default(),
+            error: None,
+        }
+    }
+}
+
+/// Whether `msg` passes the author, date, attachment and text parts of a
+/// parsed filter
+fn message_matches(msg: &EditableMessage, query: &ParsedQuery, users: &serde_json::Value) -> bool {
+    let filters = &query.filters;
+
+    if let Some(wanted) = &filters.user
+        && !is_author(msg, wanted, users)
+    {
+        return false;
+    }
+
+    if filters.from.is_some() || filters.to.is_some() {
+        let date = msg
+            .original
+            .get("ts")
+            .and_then(|t| t.as_str())
+            .and_then(|ts| ts.split('.').next()?.parse::<i64>().ok())
+            .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0))
+            .map(|t| t.format("%Y-%m-%d").to_string());
+        let Some(date) = date else {
+            return false;
+        };
+        // YYYY-MM-DD strings compare like the dates they hold
+        if filters.from.as_ref().is_some_and(|from| date < *from)
+            || filters.to.as_ref().is_some_and(|to| date > *to)
+        {
+            return false;
+        }
+    }
+
+    if filters.has_attachments == Some(true) && msg.files().is_empty() {
+        return false;
+    }
+
+    // Odd parts of the split are quoted phrases, matched as a whole
+    let text = msg.text().to_lowercase();
+    query.text.split('"').enumerate().all(|(i, part)| {
+        let part = part.to_lowercase();
+        if i % 2 == 1 {
+            text.contains(&part)
+        } else {
+            part.split_whitespace().all(|word| text.contains(word))
+        }
+    })
+}
+
 impl ListNavigation for EditableChannel {
     fn items_len(&self) -> usize {
-        self.messages.len()
+        self.visible.len()
     }
 
     fn list_state_mut(&mut self) -> &mut ListState {
@@ -441,6 +1002,54 @@ impl ListNavigation for EditableChannel {
     }
 }
 
+/// Snapshots kept by an `UndoStack`, older ones are dropped
+const UNDO_LIMIT: usize = 50;
+
+/// Undo and redo history of whole-state snapshots
+#[derive(Debug, Clone)]
+pub struct UndoStack<T> {
+    undo: Vec<T>,
+    redo: Vec<T>,
+}
+
+impl<T> UndoStack<T> {
+    pub fn new() -> Self {
+        Self {
+            undo: Vec::new(),
+            redo: Vec::new(),
+        }
+    }
+
+    /// Save the state from before an edit; a new edit drops the redo history
+    pub fn record(&mut self, state: T) {
+        if self.undo.len() >= UNDO_LIMIT {
+            self.undo.remove(0);
+        }
+        self.undo.push(state);
+        self.redo.clear();
+    }
+
+    /// State before the last edit, `current` becomes redoable
+    pub fn undo(&mut self, current: T) -> Option<T> {
+        let previous = self.undo.pop()?;
+        self.redo.push(current);
+        Some(previous)
+    }
+
+    /// State of the last undone edit, `current` becomes undoable again
+    pub fn redo(&mut self, current: T) -> Option<T> {
+        let next = self.redo.pop()?;
+        self.undo.push(current);
+        Some(next)
+    }
+}
+
+impl<T> Default for UndoStack<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 // Editable channel list type
 #[derive(Debug, Clone)]
 pub struct EditableChannelList {
@@ -467,35 +1076,126 @@ impl EditableChannelList {
         }
     }
 
-    pub fn move_current_up(&mut self) {
+    pub fn toggle_mark_current(&mut self) {
         if let Some(idx) = self.list_state.selected()
-            && idx > 0
+            && let Some(channel) = self.channels.get_mut(idx)
         {
-            self.channels.swap(idx, idx - 1);
-            self.list_state.select(Some(idx - 1));
+            channel.marked = !channel.marked;
         }
     }
 
-    pub fn move_current_down(&mut self) {
+    /// Name the highlighted channel gets in the export
+    pub fn rename_current(&mut self, name: &str) {
         if let Some(idx) = self.list_state.selected()
-            && idx < self.channels.len() - 1
+            && let Some(channel) = self.channels.get_mut(idx)
         {
-            self.channels.swap(idx, idx + 1);
-            self.list_state.select(Some(idx + 1));
+            channel.name = name.to_string();
         }
     }
 
-    pub fn to_export_data(&self) -> Vec<(String, String, Vec<serde_json::Value>)> {
-        self.channels
+    /// Replace the marked channels with a single channel called `name`,
+    /// holding all their messages in timestamp order. It takes the place
+    /// and ID of the first marked channel.
+    pub fn merge_marked(&mut self, name: &str) -> std::result::Result<(), String> {
+        let marked: Vec<usize> = self
+            .channels
             .iter()
-            .map(|ch| {
-                let filtered_messages: Vec<serde_json::Value> = ch
-                    .messages
-                    .iter()
-                    .filter(|msg| msg.enabled)
-                    .map(|msg| {
+            .enumerate()
+            .filter(|(_, c)| c.marked)
+            .map(|(idx, _)| idx)
+            .collect();
+        let (Some(&first), true) = (marked.first(), marked.len() >= 2) else {
+            return Err("mark at least two channels to merge with x".to_string());
+        };
+        let id = self.channels.get(first).map(|c| c.id.clone()).unwrap_or_default();
+
+        // Removed from the back so the indices stay valid, then taken in list order
+        let mut removed: Vec<EditableChannel> = marked.iter().rev().map(|&idx| self.channels.remove(idx)).collect();
+        removed.reverse();
+        let mut merged_from = Vec::new();
+        let mut messages = Vec::new();
+        for channel in removed {
+            if channel.merged_from.is_empty() {
+                merged_from.push(channel.name);
+            } else {
+                merged_from.extend(channel.merged_from);
+            }
+            messages.extend(channel.messages);
+        }
+
+        let ts = |msg: &EditableMessage| {
+            msg.original
+                .get("ts")
+                .and_then(|t| t.as_str())
+                .and_then(|t| t.parse::<f64>().ok())
+                .unwrap_or(0.0)
+        };
+        messages.sort_by(|a, b| ts(a).total_cmp(&ts(b)));
+
+        let mut channel = EditableChannel::from_messages(id, name.to_string(), messages);
+        channel.merged_from = merged_from;
+        self.channels.insert(first, channel);
+        self.list_state.select(Some(first));
+        Ok(())
+    }
+
+    pub fn move_current_up(&mut self) {
+        if let Some(idx) = self.list_state.selected()
+            && idx > 0
+        {
+            self.channels.swap(idx, idx - 1);
+            self.list_state.select(Some(idx - 1));
+        }
+    }
+
+    pub fn move_current_down(&mut self) {
+        if let Some(idx) = self.list_state.selected()
+            && idx < self.channels.len() - 1
+        {
+            self.channels.swap(idx, idx + 1);
+            self.list_state.select(Some(idx + 1));
+        }
+    }
+
+    pub fn to_export_data(&self) -> Vec<(String, String, Vec<serde_json::Value>)> {
+        self.channels
+            .iter()
+            .map(|ch| {
+                let filtered_messages: Vec<serde_json::Value> = ch
+                    .messages
+                    .iter()
+                    .filter(|msg| msg.enabled)
+                    .map(|msg| {
                         let mut exported = msg.original.clone();
 
+                        // Markdown and index rendering prefer the rich text
+                        // blocks, which still hold the old text
+                        if let Some(text) = &msg.edited_text
+                            && let Some(object) = exported.as_object_mut()
+                        {
+                            object.insert("text".to_string(), serde_json::Value::String(text.clone()));
+                            object.remove("blocks");
+                        }
+
+                        if exported.get("thread_replies").is_some() {
+                            let replies: Vec<serde_json::Value> = msg
+                                .replies
+                                .iter()
+                                .filter(|r| r.enabled)
+                                .map(|r| r.original.clone())
+                                .collect();
+                            if let Some(object) = exported.as_object_mut() {
+                                if replies.is_empty() {
+                                    object.remove("thread_replies");
+                                } else {
+                                    object.insert(
+                                        "thread_replies".to_string(),
+                                        serde_json::Value::Array(replies),
+                                    );
+                                }
+                            }
+                        }
+
                         if let Some(files) = exported.get("files").and_then(|f| f.as_array()) {
                             let filtered_files: Vec<serde_json::Value> = files
                                 .iter()
@@ -565,18 +1265,55 @@ impl ListNavigation for EditableChannelList {
     }
 }
 
+/// Order of the channel picker
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ChannelSort {
+    #[default]
+    Name,
+    /// Most members first
+    Members,
+    /// Most recently created first
+    Newest,
+}
+
+impl ChannelSort {
+    pub fn next(self) -> Self {
+        match self {
+            ChannelSort::Name => ChannelSort::Members,
+            ChannelSort::Members => ChannelSort::Newest,
+            ChannelSort::Newest => ChannelSort::Name,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ChannelSort::Name => "name",
+            ChannelSort::Members => "members",
+            ChannelSort::Newest => "newest",
+        }
+    }
+}
+
 // Channel selection type
 #[derive(Debug, Clone)]
 pub struct ChannelSelection {
     pub channels: Vec<ChannelInfo>,
     pub selected: HashSet<String>,
+    pub sort: ChannelSort,
+    /// Highlighted row among the visible channels
     pub list_state: ListState,
+    /// Fuzzy filter typed after `/`; empty shows every channel
+    pub filter: TextInput,
+    /// Whether typed keys go to the filter
+    pub filtering: bool,
+    /// Indices into `channels` matching the filter, best match first
+    visible: Vec<usize>,
 }
 
 impl ChannelSelection {
     pub fn new(channels: Vec<ChannelInfo>, saved_selection: Option<HashSet<String>>) -> Self {
         let mut channels = channels;
-        channels.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
+        sort_channels(&mut channels, ChannelSort::Name);
 
         let selected = match saved_selection {
             Some(saved) if !saved.is_empty() => {
@@ -595,30 +1332,90 @@ impl ChannelSelection {
         }
 
         Self {
+            visible: (0..channels.len()).collect(),
             channels,
             selected,
+            sort: ChannelSort::Name,
             list_state,
+            filter: TextInput::default(),
+            filtering: false,
         }
     }
 
+    /// Switch to the next sort order, keeping the highlighted channel
+    pub fn cycle_sort(&mut self) {
+        // `visible` indexes the old order, so look the channel up first
+        let current = self.current().map(|c| c.id.clone());
+        self.sort = self.sort.next();
+        sort_channels(&mut self.channels, self.sort);
+        self.apply_filter();
+        if let Some(position) = current.and_then(|id| self.visible_channels().position(|c| c.id == id)) {
+            self.list_state.select(Some(position));
+        }
+    }
+
+    /// Channels matching the filter, in display order
+    pub fn visible_channels(&self) -> impl Iterator<Item = &ChannelInfo> {
+        self.visible.iter().filter_map(|&idx| self.channels.get(idx))
+    }
+
+    fn current(&self) -> Option<&ChannelInfo> {
+        let idx = self.visible.get(self.list_state.selected()?)?;
+        self.channels.get(*idx)
+    }
+
     pub fn toggle_current(&mut self) {
-        if let Some(idx) = self.list_state.selected()
-            && let Some(channel) = self.channels.get(idx)
+        if let Some(id) = self.current().map(|c| c.id.clone())
+            && !self.selected.remove(&id)
         {
-            if self.selected.contains(&channel.id) {
-                self.selected.remove(&channel.id);
-            } else {
-                self.selected.insert(channel.id.clone());
-            }
+            self.selected.insert(id);
         }
     }
 
+    /// Select every visible channel, keeping hidden selections
     pub fn select_all(&mut self) {
-        self.selected = self.channels.iter().map(|c| c.id.clone()).collect();
+        let ids: Vec<String> = self.visible_channels().map(|c| c.id.clone()).collect();
+        self.selected.extend(ids);
     }
 
+    /// Deselect every visible channel, keeping hidden selections
     pub fn select_none(&mut self) {
-        self.selected.clear();
+        let ids: HashSet<String> = self.visible_channels().map(|c| c.id.clone()).collect();
+        self.selected.retain(|id| !ids.contains(id));
+    }
+
+    /// Replace the selection with the listed channels among `ids`
+    pub fn select_only(&mut self, ids: &HashSet<String>) {
+        self.selected = self.channels.iter().filter(|c| ids.contains(&c.id)).map(|c| c.id.clone()).collect();
+    }
+
+    /// Recompute the visible channels after the filter text changed, keeping
+    /// the highlighted channel when it still matches
+    pub fn apply_filter(&mut self) {
+        let current = self.current().map(|c| c.id.clone());
+        let query = self.filter.text();
+
+        let mut matches: Vec<(i32, usize)> = self
+            .channels
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, c)| fuzzy_score(query, &c.name).map(|score| (score, idx)))
+            .collect();
+        // Stable sort keeps alphabetical order between equal scores
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        self.visible = matches.into_iter().map(|(_, idx)| idx).collect();
+
+        let position = current
+            .and_then(|id| self.visible_channels().position(|c| c.id == id))
+            .or((!self.visible.is_empty()).then_some(0));
+        self.list_state.select(position);
+    }
+
+    /// Stop filtering and show every channel again
+    pub fn clear_filter(&mut self) {
+        self.filter = TextInput::default();
+        self.filtering = false;
+        self.apply_filter();
     }
 
     pub fn selected_ids(&self) -> Vec<String> {
@@ -626,9 +1423,169 @@ impl ChannelSelection {
     }
 }
 
+/// Sort `channels` by `sort`, archived channels last and by name among equals
+fn sort_channels(channels: &mut [ChannelInfo], sort: ChannelSort) {
+    let by_name = |c: &ChannelInfo| c.name.to_lowercase();
+    match sort {
+        ChannelSort::Name => channels.sort_by_key(|c| (c.is_archived, by_name(c))),
+        ChannelSort::Members => {
+            channels.sort_by_key(|c| (c.is_archived, std::cmp::Reverse(c.num_members), by_name(c)))
+        }
+        ChannelSort::Newest => channels.sort_by_key(|c| (c.is_archived, std::cmp::Reverse(c.created), by_name(c))),
+    }
+}
+
+/// Case-insensitive subsequence match of `query` in `text`; consecutive
+/// characters and matches at word starts score higher, skipped ones lower
+fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut previous: Option<usize> = None;
+    for q in query.to_lowercase().chars() {
+        let skipped = text.get(pos..)?.iter().position(|&c| c == q)?;
+        let idx = pos + skipped;
+        score += if previous.is_some_and(|p| p + 1 == idx) { 5 } else { 1 };
+        let word_start = idx == 0
+            || text
+                .get(idx - 1)
+                .is_some_and(|c| matches!(c, '-' | '_' | '.'));
+        if word_start {
+            score += 3;
+        }
+        score -= skipped.min(10) as i32;
+        previous = Some(idx);
+        pos = idx + 1;
+    }
+    Some(score)
+}
+
 impl ListNavigation for ChannelSelection {
     fn items_len(&self) -> usize {
-        self.channels.len()
+        self.visible.len()
+    }
+
+    fn list_state_mut(&mut self) -> &mut ListState {
+        &mut self.list_state
+    }
+}
+
+/// Popup over a channel list with the selections saved in the settings
+/// profile: Enter loads the highlighted one, or saves the current selection
+/// when a name was typed
+#[derive(Debug, Clone)]
+pub struct SelectionPicker {
+    /// Saved selection names with their channel counts, sorted by name
+    pub entries: Vec<(String, usize)>,
+    /// Name to save the current selection as
+    pub name: TextInput,
+    /// `SLACK_PROFILE` the selections belong to, for the title
+    pub profile: Option<String>,
+    pub list_state: ListState,
+}
+
+impl SelectionPicker {
+    pub fn new(entries: Vec<(String, usize)>, profile: Option<String>) -> Self {
+        let mut list_state = ListState::default();
+        if !entries.is_empty() {
+            list_state.select(Some(0));
+        }
+        Self {
+            entries,
+            name: TextInput::default(),
+            profile,
+            list_state,
+        }
+    }
+
+    /// Replace the entries after one was deleted, keeping the highlight in range
+    pub fn set_entries(&mut self, entries: Vec<(String, usize)>) {
+        let selected = self.list_state.selected().unwrap_or(0).min(entries.len().saturating_sub(1));
+        self.list_state.select((!entries.is_empty()).then_some(selected));
+        self.entries = entries;
+    }
+
+    /// Name of the highlighted selection
+    pub fn current(&self) -> Option<&str> {
+        let (name, _) = self.entries.get(self.list_state.selected()?)?;
+        Some(name)
+    }
+}
+
+impl ListNavigation for SelectionPicker {
+    fn items_len(&self) -> usize {
+        self.entries.len()
+    }
+
+    fn list_state_mut(&mut self) -> &mut ListState {
+        &mut self.list_state
+    }
+}
+
+/// Exported custom emojis with a filter, for auditing an emoji export
+#[derive(Debug, Clone)]
+pub struct EmojiBrowser {
+    pub entries: Vec<EmojiEntry>,
+    /// Matches names and alias targets; always receives typed keys
+    pub filter: TextInput,
+    /// Show only entries with a problem
+    pub only_problems: bool,
+    pub list_state: ListState,
+    /// Indices into `entries` passing the filter
+    visible: Vec<usize>,
+}
+
+impl EmojiBrowser {
+    pub fn new(entries: Vec<EmojiEntry>) -> Self {
+        let mut browser = Self {
+            entries,
+            filter: TextInput::default(),
+            only_problems: false,
+            list_state: ListState::default(),
+            visible: Vec::new(),
+        };
+        browser.apply_filter();
+        browser
+    }
+
+    pub fn visible_entries(&self) -> impl Iterator<Item = &EmojiEntry> {
+        self.visible.iter().filter_map(|&idx| self.entries.get(idx))
+    }
+
+    pub fn current(&self) -> Option<&EmojiEntry> {
+        let idx = self.visible.get(self.list_state.selected()?)?;
+        self.entries.get(*idx)
+    }
+
+    pub fn problem_count(&self) -> usize {
+        self.entries.iter().filter(|e| e.problem.is_some()).count()
+    }
+
+    /// Recompute the visible entries after the filter changed
+    pub fn apply_filter(&mut self) {
+        let query = self.filter.text().to_lowercase();
+        self.visible = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| !self.only_problems || e.problem.is_some())
+            .filter(|(_, e)| {
+                let target = match &e.kind {
+                    EmojiKind::Alias { target } => target.as_str(),
+                    EmojiKind::Image { .. } => "",
+                };
+                e.name.to_lowercase().contains(&query) || target.to_lowercase().contains(&query)
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        self.list_state
+            .select((!self.visible.is_empty()).then_some(0));
+    }
+}
+
+impl ListNavigation for EmojiBrowser {
+    fn items_len(&self) -> usize {
+        self.visible.len()
     }
 
     fn list_state_mut(&mut self) -> &mut ListState {
@@ -636,6 +1593,14 @@ impl ListNavigation for ChannelSelection {
     }
 }
 
+/// Answers to the overwrite confirmation
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverwriteChoice {
+    Overwrite,
+    Timestamp,
+    Cancel,
+}
+
 // Export task enum
 #[derive(Debug, Clone)]
 pub enum ExportTask {
@@ -671,9 +1636,14 @@ pub enum ExportTask {
     DownloadAttachments {
         conversations_path: String,
         output_path: String,
+        /// File IDs to download, for retrying failures; all when `None`
+        only: Option<HashSet<String>>,
+        options: DownloadOptions,
     },
     MarkdownExport {
         conversations_path: String,
+        /// Conversations to export instead of reading `conversations_path`
+        conversations: Option<Vec<serde_json::Value>>,
         users_path: String,
         channels_path: String,
         output_path: String,
@@ -683,6 +1653,8 @@ pub enum ExportTask {
     ExportEmojis {
         output_path: String,
         emojis_folder: String,
+        /// Emoji names to download, for retrying failures; all when `None`
+        only: Option<HashSet<String>>,
     },
     ExportIndex {
         conversations_path: String,
@@ -697,6 +1669,171 @@ pub enum ExportTask {
         index_name: String,
         clear: bool,
     },
+    Pipeline(crate::pipeline::PipelineOptions),
+}
+
+impl ExportTask {
+    /// Files the task reads, which must exist before it starts
+    pub fn input_paths(&self) -> Vec<&str> {
+        match self {
+            ExportTask::DownloadAttachments { conversations_path, .. } => vec![conversations_path],
+            ExportTask::MarkdownExport {
+                conversations_path,
+                conversations,
+                users_path,
+                channels_path,
+                formatter_script,
+                ..
+            } => {
+                let mut paths = vec![users_path.as_str(), channels_path];
+                if conversations.is_none() {
+                    paths.insert(0, conversations_path);
+                }
+                paths.extend(formatter_script.as_deref().filter(|s| !s.is_empty()));
+                paths
+            }
+            ExportTask::ExportIndex {
+                conversations_path,
+                users_path,
+                channels_path,
+                ..
+            } => vec![conversations_path, users_path, channels_path],
+            ExportTask::ImportMeilisearch { input_path, .. } => vec![input_path],
+            // Conversations come from the first step; users and channels are
+            // only read when rendering markdown or building the index
+            ExportTask::Pipeline(options) if options.markdown_path.is_some() || options.index_path.is_some() => {
+                vec![&options.users_path, &options.channels_path]
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Files the task writes over. Output directories are left out: the
+    /// tasks filling them skip what is already there.
+    pub fn output_paths_mut(&mut self) -> Vec<&mut String> {
+        match self {
+            ExportTask::Conversations { output_path, .. }
+            | ExportTask::ConversationsWeek { output_path, .. }
+            | ExportTask::Users { output_path, .. }
+            | ExportTask::Channels { output_path, .. }
+            | ExportTask::MarkdownExport { output_path, .. }
+            | ExportTask::ExportEmojis { output_path, .. }
+            | ExportTask::ExportIndex { output_path, .. } => vec![output_path],
+            ExportTask::Pipeline(options) => std::iter::once(&mut options.conversations_path)
+                .chain(options.markdown_path.as_mut())
+                .chain(options.index_path.as_mut())
+                .collect(),
+            ExportTask::ArchiveRange { .. }
+            | ExportTask::DownloadAttachments { .. }
+            | ExportTask::ImportMeilisearch { .. } => Vec::new(),
+        }
+    }
+
+    /// Values the task was started with, by the history key of the field
+    /// they were typed in
+    pub fn history_values(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ExportTask::Conversations { from_date, to_date, output_path, .. } => vec![
+                ("date", from_date.clone()),
+                ("date", to_date.clone()),
+                ("output-path", output_path.clone()),
+            ],
+            ExportTask::ConversationsWeek { year, week, output_path, .. } => vec![
+                ("year", year.to_string()),
+                ("week", week.to_string()),
+                ("output-path", output_path.clone()),
+            ],
+            ExportTask::ArchiveRange {
+                from_year,
+                from_week,
+                to_year,
+                to_week,
+                output_path,
+            } => vec![
+                ("year", from_year.to_string()),
+                ("week", from_week.to_string()),
+                ("year", to_year.to_string()),
+                ("week", to_week.to_string()),
+                ("directory-path", output_path.clone()),
+            ],
+            ExportTask::Users { output_path, .. } | ExportTask::Channels { output_path, .. } => {
+                vec![("output-path", output_path.clone())]
+            }
+            ExportTask::DownloadAttachments {
+                conversations_path,
+                output_path,
+                options,
+                ..
+            } => {
+                let filter = &options.filter;
+                let day = |d: Option<chrono::NaiveDate>| d.map(|d| d.format("%Y-%m-%d").to_string()).unwrap_or_default();
+                vec![
+                    ("conversations-path", conversations_path.clone()),
+                    ("directory-path", output_path.clone()),
+                    ("file-types", filter.types.join(",")),
+                    ("date", day(filter.from)),
+                    ("date", day(filter.to)),
+                ]
+            }
+            ExportTask::MarkdownExport {
+                conversations_path,
+                conversations,
+                users_path,
+                channels_path,
+                output_path,
+                formatter_script,
+                ..
+            } => {
+                let mut values = vec![
+                    ("users-path", users_path.clone()),
+                    ("channels-path", channels_path.clone()),
+                    ("output-path", output_path.clone()),
+                ];
+                if conversations.is_none() {
+                    values.insert(0, ("conversations-path", conversations_path.clone()));
+                }
+                values.extend(formatter_script.clone().map(|s| ("script-path", s)));
+                values
+            }
+            ExportTask::ExportEmojis { output_path, emojis_folder, .. } => vec![
+                ("output-path", output_path.clone()),
+                ("directory-path", emojis_folder.clone()),
+            ],
+            ExportTask::ExportIndex {
+                conversations_path,
+                users_path,
+                channels_path,
+                output_path,
+            } => vec![
+                ("conversations-path", conversations_path.clone()),
+                ("users-path", users_path.clone()),
+                ("channels-path", channels_path.clone()),
+                ("output-path", output_path.clone()),
+            ],
+            ExportTask::ImportMeilisearch { input_path, url, index_name, .. } => vec![
+                ("index-path", input_path.clone()),
+                ("url", url.clone()),
+                ("index-name", index_name.clone()),
+            ],
+            ExportTask::Pipeline(options) => vec![
+                ("date", options.from_date.to_string()),
+                ("date", options.to_date.to_string()),
+            ],
+        }
+    }
+
+    /// Tasks that check the cancel flag between Slack API calls
+    pub fn is_cancellable(&self) -> bool {
+        matches!(
+            self,
+            ExportTask::Conversations { .. }
+                | ExportTask::ConversationsWeek { .. }
+                | ExportTask::ArchiveRange { .. }
+                | ExportTask::DownloadAttachments { .. }
+                | ExportTask::ExportEmojis { .. }
+                | ExportTask::Pipeline(_)
+        )
+    }
 }
 
 // Screen enum
@@ -744,6 +1881,8 @@ pub enum Screen {
         users: serde_json::Value,
         channel_data: serde_json::Value,
         editing_export_path: bool,
+        /// Open rename or merge prompt
+        name_prompt: Option<ChannelNamePrompt>,
     },
     EditConversationsMessageList {
         channel_idx: usize,
@@ -759,10 +1898,18 @@ pub enum Screen {
         channel_data: serde_json::Value,
         attachment_list_state: ListState,
         editing_title: Option<(usize, TextInput)>,
+        editing_text: Option<TextArea>,
+        /// Keys act on the thread replies instead of the files and links
+        replies_focused: bool,
     },
     DownloadAttachments {
         conversations_path: String,
         output_path: String,
+        /// Comma separated file types, all when empty
+        types: TextInput,
+        max_size: TextInput,
+        from_date: TextInput,
+        to_date: TextInput,
         active_field: DownloadAttachmentsField,
     },
     MarkdownExport {
@@ -773,6 +1920,10 @@ pub enum Screen {
         formatter_script: String,
         backslash_line_breaks: bool,
         active_field: MarkdownExportField,
+        /// Conversation editor the export was opened from: its enabled
+        /// messages are exported instead of `conversations_path`, and Esc
+        /// goes back to it
+        editor: Option<Box<Screen>>,
     },
     ExportEmojis {
         output_path: String,
@@ -796,11 +1947,13 @@ pub enum Screen {
     },
     QueryMeilisearch {
         query: String,
+        backend: SearchBackend,
         url: String,
         api_key: String,
         index_name: String,
+        tantivy_dir: String,
         active_field: QueryMeilisearchField,
-        results: Option<Vec<crate::index::IndexEntry>>,
+        results: Option<Vec<crate::index::SearchHit>>,
         result_state: ListState,
         error: Option<String>,
     },
@@ -810,6 +1963,23 @@ pub enum Screen {
         gfm: bool,
         active_field: MdToHtmlField,
     },
+    Pipeline {
+        from_date: TextInput,
+        to_date: TextInput,
+        active_field: PipelineField,
+        targets: PipelineTargets,
+        channel_selection: Option<ChannelSelection>,
+    },
+    SearchLocal {
+        index_path: TextInput,
+        query: TextInput,
+        active_field: SearchLocalField,
+        source: Option<LocalSearchSource>,
+        loading: bool,
+        results: Vec<crate::index::SearchHit>,
+        result_state: ListState,
+        error: Option<String>,
+    },
     Loading {
         message: String,
         progress: Option<(usize, usize, String)>,
@@ -822,6 +1992,362 @@ pub enum Screen {
     Error {
         message: String,
     },
+    /// Styled view of a markdown file
+    MarkdownPreview {
+        path: String,
+        content: String,
+        scroll: u16,
+        /// Screen to go back to, the main menu when `None`
+        previous: Option<Box<Screen>>,
+    },
+    /// Outcome of a download task that had failures
+    DownloadResults {
+        message: String,
+        failures: Vec<DownloadFailure>,
+        retry: ExportTask,
+        table_state: TableState,
+    },
+    EmojiBrowser {
+        browser: EmojiBrowser,
+        /// Emoji JSON and folder the catalog was read from
+        source: String,
+    },
+    /// Asks before a task writes over existing files
+    ConfirmOverwrite {
+        task: ExportTask,
+        message: String,
+        existing: Vec<String>,
+        previous: Box<Screen>,
+    },
+}
+
+/// Activity log of background tasks, shown in the `l` pane
+#[derive(Debug, Default)]
+pub struct TaskLog {
+    lines: VecDeque<String>,
+    /// Lines scrolled up from the newest one
+    pub scroll: usize,
+    pub visible: bool,
+}
+
+impl TaskLog {
+    /// Oldest lines are dropped past this many
+    const MAX_LINES: usize = 1000;
+
+    pub fn push(&mut self, line: &str) {
+        if self.lines.len() == Self::MAX_LINES {
+            self.lines.pop_front();
+        }
+        let time = chrono::Local::now().format("%H:%M:%S");
+        self.lines.push_back(format!("{} {}", time, line));
+    }
+
+    pub fn lines(&self) -> impl DoubleEndedIterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.lines.len().saturating_sub(1));
+    }
+
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+}
+
+/// A free-text field of the current screen
+pub enum TextField<'a> {
+    Input(&'a mut TextInput),
+    Text(&'a mut String),
+}
+
+impl TextField<'_> {
+    pub fn text(&self) -> &str {
+        match self {
+            TextField::Input(input) => input.text(),
+            TextField::Text(text) => text,
+        }
+    }
+
+    pub fn set_text(self, value: String) {
+        match self {
+            TextField::Input(input) => input.set_text(value),
+            TextField::Text(text) => *text = value,
+        }
+    }
+
+    /// Complete the field in place, returning the candidates left to choose from
+    pub fn complete(self) -> Vec<String> {
+        match self {
+            TextField::Input(input) => input.complete_path(),
+            TextField::Text(text) => {
+                let completion = crate::widgets::complete_path(text);
+                *text = completion.text;
+                completion.candidates
+            }
+        }
+    }
+}
+
+/// Fields of the current screen that the date picker can fill
+pub enum DateTarget<'a> {
+    Date(&'a mut TextInput),
+    Week {
+        year: &'a mut TextInput,
+        week: &'a mut TextInput,
+    },
+}
+
+impl DateTarget<'_> {
+    /// A picker starting at the field's current value, or today when it
+    /// doesn't parse
+    pub fn picker(&self) -> DatePicker {
+        let today = chrono::Local::now().date_naive();
+        match self {
+            DateTarget::Date(input) => {
+                DatePicker::new(PickerMode::Date, crate::parse_date(input.text()).unwrap_or(today))
+            }
+            DateTarget::Week { year, week } => {
+                let monday = year
+                    .text()
+                    .parse()
+                    .ok()
+                    .zip(week.text().parse().ok())
+                    .and_then(|(year, week)| crate::week_to_date_range(year, week).ok())
+                    .map_or(today, |(monday, _)| monday);
+                DatePicker::new(PickerMode::Week, monday)
+            }
+        }
+    }
+
+    /// Write the picked date or week into the fields
+    pub fn set(self, picker: &DatePicker) {
+        match self {
+            DateTarget::Date(input) => input.set_text(picker.selected.format("%Y-%m-%d").to_string()),
+            DateTarget::Week { year, week } => {
+                let (iso_year, iso_week) = picker.iso_week();
+                year.set_text(iso_year.to_string());
+                week.set_text(iso_week.to_string());
+            }
+        }
+    }
+}
+
+/// Whether values remembered under `key` are filesystem paths
+pub fn is_path_history_key(key: &str) -> bool {
+    key.ends_with("-path")
+}
+
+impl Screen {
+    /// The focused free-text field, with the key its recent values are
+    /// remembered under. Fields of the same kind share a key across screens.
+    pub fn active_text_field(&mut self) -> Option<(&'static str, TextField<'_>)> {
+        use TextField::{Input, Text};
+
+        match self {
+            Screen::ExportConversations { from_date, to_date, output_path, active_field, .. } => match active_field {
+                ConvExportField::FromDate => Some(("date", Input(from_date))),
+                ConvExportField::ToDate => Some(("date", Input(to_date))),
+                ConvExportField::OutputPath => Some(("output-path", Input(output_path))),
+                ConvExportField::Channels => None,
+            },
+            Screen::ExportConversationsWeek { year, week, output_path, active_field, .. } => match active_field {
+                ConvExportWeekField::Year => Some(("year", Input(year))),
+                ConvExportWeekField::Week => Some(("week", Input(week))),
+                ConvExportWeekField::OutputPath => Some(("output-path", Input(output_path))),
+                ConvExportWeekField::Channels => None,
+            },
+            Screen::ArchiveRange {
+                from_year,
+                from_week,
+                to_year,
+                to_week,
+                output_path,
+                active_field,
+            } => Some(match active_field {
+                ArchiveRangeField::FromYear => ("year", Input(from_year)),
+                ArchiveRangeField::FromWeek => ("week", Input(from_week)),
+                ArchiveRangeField::ToYear => ("year", Input(to_year)),
+                ArchiveRangeField::ToWeek => ("week", Input(to_week)),
+                ArchiveRangeField::OutputPath => ("directory-path", Input(output_path)),
+            }),
+            Screen::Pipeline { from_date, to_date, active_field, .. } => match active_field {
+                PipelineField::FromDate => Some(("date", Input(from_date))),
+                PipelineField::ToDate => Some(("date", Input(to_date))),
+                PipelineField::Targets | PipelineField::Channels => None,
+            },
+            Screen::SearchLocal { index_path, active_field: SearchLocalField::IndexPath, .. } => {
+                Some(("index-path", Input(index_path)))
+            }
+            Screen::ExportUsers { output_path } | Screen::ExportChannels { output_path } => {
+                Some(("output-path", Text(output_path)))
+            }
+            Screen::EditConversationsPathInput {
+                conversations_path,
+                users_path,
+                channels_path,
+                active_field,
+            } => Some(match active_field {
+                EditConvPathField::Conversations => ("conversations-path", Text(conversations_path)),
+                EditConvPathField::Users => ("users-path", Text(users_path)),
+                EditConvPathField::Channels => ("channels-path", Text(channels_path)),
+            }),
+            Screen::DownloadAttachments {
+                conversations_path,
+                output_path,
+                types,
+                max_size,
+                from_date,
+                to_date,
+                active_field,
+            } => Some(match active_field {
+                DownloadAttachmentsField::ConversationsPath => ("conversations-path", Text(conversations_path)),
+                DownloadAttachmentsField::OutputPath => ("directory-path", Text(output_path)),
+                DownloadAttachmentsField::Types => ("file-types", Input(types)),
+                DownloadAttachmentsField::MaxSize => ("max-size", Input(max_size)),
+                DownloadAttachmentsField::FromDate => ("date", Input(from_date)),
+                DownloadAttachmentsField::ToDate => ("date", Input(to_date)),
+            }),
+            Screen::MarkdownExport {
+                conversations_path,
+                users_path,
+                channels_path,
+                output_path,
+                formatter_script,
+                active_field,
+                ..
+            } => match active_field {
+                MarkdownExportField::Conversations => Some(("conversations-path", Text(conversations_path))),
+                MarkdownExportField::Users => Some(("users-path", Text(users_path))),
+                MarkdownExportField::Channels => Some(("channels-path", Text(channels_path))),
+                MarkdownExportField::Output => Some(("output-path", Text(output_path))),
+                MarkdownExportField::FormatterScript => Some(("script-path", Text(formatter_script))),
+                MarkdownExportField::BackslashLineBreaks => None,
+            },
+            Screen::ExportEmojis { output_path, emojis_folder, active_field } => Some(match active_field {
+                ExportEmojisField::OutputPath => ("output-path", Text(output_path)),
+                ExportEmojisField::EmojisFolder => ("directory-path", Text(emojis_folder)),
+            }),
+            Screen::ExportIndex {
+                conversations_path,
+                users_path,
+                channels_path,
+                output_path,
+                active_field,
+            } => Some(match active_field {
+                ExportIndexField::Conversations => ("conversations-path", Text(conversations_path)),
+                ExportIndexField::Users => ("users-path", Text(users_path)),
+                ExportIndexField::Channels => ("channels-path", Text(channels_path)),
+                ExportIndexField::Output => ("output-path", Text(output_path)),
+            }),
+            // The API key is left out so it never lands in the history
+            Screen::ImportMeilisearch { input_path, url, index_name, active_field, .. } => match active_field {
+                ImportMeilisearchField::Input => Some(("index-path", Text(input_path))),
+                ImportMeilisearchField::Url => Some(("url", Text(url))),
+                ImportMeilisearchField::IndexName => Some(("index-name", Text(index_name))),
+                ImportMeilisearchField::ApiKey | ImportMeilisearchField::Clear => None,
+            },
+            Screen::QueryMeilisearch { url, index_name, tantivy_dir, active_field, .. } => match active_field {
+                QueryMeilisearchField::Url => Some(("url", Text(url))),
+                QueryMeilisearchField::IndexName => Some(("index-name", Text(index_name))),
+                QueryMeilisearchField::TantivyDir => Some(("index-path", Text(tantivy_dir))),
+                QueryMeilisearchField::Query | QueryMeilisearchField::Backend | QueryMeilisearchField::ApiKey => {
+                    None
+                }
+            },
+            Screen::MdToHtml { input_path, output_path, active_field, .. } => match active_field {
+                MdToHtmlField::InputPath => Some(("input-path", Text(input_path))),
+                MdToHtmlField::OutputPath => Some(("output-path", Text(output_path))),
+                MdToHtmlField::Gfm => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The focused date field, or both fields of the focused year/week pair
+    pub fn active_date_target(&mut self) -> Option<DateTarget<'_>> {
+        match self {
+            Screen::ExportConversations { from_date, to_date, active_field, .. } => match active_field {
+                ConvExportField::FromDate => Some(DateTarget::Date(from_date)),
+                ConvExportField::ToDate => Some(DateTarget::Date(to_date)),
+                ConvExportField::OutputPath | ConvExportField::Channels => None,
+            },
+            Screen::DownloadAttachments { from_date, to_date, active_field, .. } => match active_field {
+                DownloadAttachmentsField::FromDate => Some(DateTarget::Date(from_date)),
+                DownloadAttachmentsField::ToDate => Some(DateTarget::Date(to_date)),
+                _ => None,
+            },
+            Screen::Pipeline { from_date, to_date, active_field, .. } => match active_field {
+                PipelineField::FromDate => Some(DateTarget::Date(from_date)),
+                PipelineField::ToDate => Some(DateTarget::Date(to_date)),
+                PipelineField::Targets | PipelineField::Channels => None,
+            },
+            Screen::ExportConversationsWeek {
+                year,
+                week,
+                active_field: ConvExportWeekField::Year | ConvExportWeekField::Week,
+                ..
+            } => Some(DateTarget::Week { year, week }),
+            Screen::ArchiveRange {
+                from_year,
+                from_week,
+                to_year,
+                to_week,
+                active_field,
+                ..
+            } => match active_field {
+                ArchiveRangeField::FromYear | ArchiveRangeField::FromWeek => Some(DateTarget::Week {
+                    year: from_year,
+                    week: from_week,
+                }),
+                ArchiveRangeField::ToYear | ArchiveRangeField::ToWeek => Some(DateTarget::Week {
+                    year: to_year,
+                    week: to_week,
+                }),
+                ArchiveRangeField::OutputPath => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// The focused field when it holds a filesystem path
+    pub fn active_path_field(&mut self) -> Option<TextField<'_>> {
+        self.active_text_field()
+            .filter(|(key, _)| is_path_history_key(key))
+            .map(|(_, field)| field)
+    }
+
+    /// Whether Up/Down may cycle the focused field through its recent
+    /// values; screens showing results use them to move the selection
+    /// The channel picker of the screen when its channels field is focused
+    pub fn active_channel_selection(&mut self) -> Option<&mut ChannelSelection> {
+        match self {
+            Screen::ExportConversations {
+                active_field: ConvExportField::Channels,
+                channel_selection,
+                ..
+            }
+            | Screen::ExportConversationsWeek {
+                active_field: ConvExportWeekField::Channels,
+                channel_selection,
+                ..
+            }
+            | Screen::Pipeline {
+                active_field: PipelineField::Channels,
+                channel_selection,
+                ..
+            } => channel_selection.as_mut(),
+            _ => None,
+        }
+    }
+
+    pub fn cycles_history(&self) -> bool {
+        match self {
+            Screen::SearchLocal { .. } => false,
+            Screen::QueryMeilisearch { results, .. } => results.is_none(),
+            _ => true,
+        }
+    }
 }
 
 /// Result of an export operation with optional details (e.g., formatter stderr)
@@ -830,10 +2356,293 @@ pub struct ExportResult {
     pub details: Option<String>,
 }
 
+/// Result of a download task, with the items that failed
+pub struct DownloadReport {
+    pub message: String,
+    pub failures: Vec<DownloadFailure>,
+    /// The same task restricted to the failed items
+    pub retry: ExportTask,
+}
+
 // Async result enum
 pub enum AsyncResult {
     ExportComplete(std::result::Result<ExportResult, String>),
+    DownloadComplete(std::result::Result<Box<DownloadReport>, String>),
+    /// Task stopped by the user, with a summary of the work done
+    ExportCancelled(String),
     ChannelsLoaded(std::result::Result<Vec<ChannelInfo>, String>),
-    QueryResult(std::result::Result<Vec<crate::index::IndexEntry>, String>),
+    QueryResult(std::result::Result<Vec<crate::index::SearchHit>, String>),
     MdToHtmlResult(std::result::Result<(), String>),
+    LocalIndexLoaded(std::result::Result<LocalSearchSource, String>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn channel(id: &str, name: &str) -> ChannelInfo {
+        ChannelInfo {
+            id: id.to_string(),
+            name: name.to_string(),
+            num_members: None,
+            is_archived: false,
+            created: None,
+        }
+    }
+
+    fn visible_names(selection: &ChannelSelection) -> Vec<&str> {
+        selection.visible_channels().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_fuzzy_score() {
+        // Consecutive characters from a word start beat scattered ones
+        let general = fuzzy_score("gen", "general").unwrap();
+        let engineering = fuzzy_score("gen", "engineering").unwrap();
+        assert!(general > engineering);
+        assert!(fuzzy_score("ai", "gen-ai").unwrap() > fuzzy_score("ai", "random-chair").unwrap());
+
+        // Characters must appear in order
+        assert_eq!(fuzzy_score("neg", "general"), None);
+        assert_eq!(fuzzy_score("xyz", "general"), None);
+        assert_eq!(fuzzy_score("generals", "general"), None);
+
+        assert_eq!(fuzzy_score("GEN", "General"), Some(general));
+        assert_eq!(fuzzy_score("", "general"), Some(0));
+    }
+
+    #[test]
+    fn test_apply_filter() {
+        let mut selection = ChannelSelection::new(
+            vec![
+                channel("C1", "engineering"),
+                channel("C2", "general"),
+                channel("C3", "random"),
+                channel("C4", "gen-ai"),
+            ],
+            None,
+        );
+        assert_eq!(visible_names(&selection), ["engineering", "gen-ai", "general", "random"]);
+
+        // Best match first, equal scores stay alphabetical
+        selection.list_state.select(Some(2));
+        selection.filter = TextInput::new("GEN".to_string());
+        selection.apply_filter();
+        assert_eq!(visible_names(&selection), ["gen-ai", "general", "engineering"]);
+        // The highlighted channel still matches, so it stays highlighted
+        assert_eq!(selection.current().map(|c| c.id.as_str()), Some("C2"));
+
+        selection.filter = TextInput::new("zzz".to_string());
+        selection.apply_filter();
+        assert!(visible_names(&selection).is_empty());
+        assert_eq!(selection.list_state.selected(), None);
+        // Filtering hides channels without deselecting them
+        assert_eq!(selection.selected.len(), 4);
+
+        selection.clear_filter();
+        assert_eq!(visible_names(&selection).len(), 4);
+        assert_eq!(selection.list_state.selected(), Some(0));
+    }
+
+    #[test]
+    fn test_cycle_sort() {
+        let mut old = channel("C1", "archive-me");
+        old.is_archived = true;
+        old.num_members = Some(50);
+        old.created = Some(300);
+        let mut big = channel("C2", "big");
+        big.num_members = Some(40);
+        big.created = Some(100);
+        let mut new = channel("C3", "new");
+        new.num_members = Some(2);
+        new.created = Some(200);
+        let mut selection = ChannelSelection::new(vec![new, old, big], None);
+        assert_eq!(visible_names(&selection), ["big", "new", "archive-me"]);
+
+        // The highlighted channel stays highlighted wherever it moves
+        selection.list_state.select(Some(1));
+        selection.cycle_sort();
+        assert_eq!(selection.sort, ChannelSort::Members);
+        assert_eq!(visible_names(&selection), ["big", "new", "archive-me"]);
+        selection.cycle_sort();
+        assert_eq!(selection.sort, ChannelSort::Newest);
+        assert_eq!(visible_names(&selection), ["new", "big", "archive-me"]);
+        assert_eq!(selection.current().map(|c| c.id.as_str()), Some("C3"));
+        selection.cycle_sort();
+        assert_eq!(selection.sort, ChannelSort::Name);
+        assert_eq!(selection.current().map(|c| c.id.as_str()), Some("C3"));
+    }
+
+    #[test]
+    fn test_undo_stack() {
+        let mut stack = UndoStack::new();
+        assert_eq!(stack.undo(0), None);
+        assert_eq!(stack.redo(0), None);
+
+        // Edits 0 -> 1 -> 2, then back to 0 and forward to 1
+        stack.record(0);
+        stack.record(1);
+        assert_eq!(stack.undo(2), Some(1));
+        assert_eq!(stack.undo(1), Some(0));
+        assert_eq!(stack.undo(0), None);
+        assert_eq!(stack.redo(0), Some(1));
+
+        // A new edit drops what was left to redo
+        stack.record(1);
+        assert_eq!(stack.redo(3), None);
+        assert_eq!(stack.undo(3), Some(1));
+        assert_eq!(stack.undo(1), Some(0));
+        assert_eq!(stack.undo(0), None);
+    }
+
+    #[test]
+    fn test_undo_stack_limit() {
+        let mut stack = UndoStack::new();
+        for state in 0..UNDO_LIMIT + 5 {
+            stack.record(state);
+        }
+
+        // The oldest states are dropped first
+        let mut current = UNDO_LIMIT + 5;
+        let mut undone = 0;
+        while let Some(previous) = stack.undo(current) {
+            current = previous;
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+        assert_eq!(current, 5);
+    }
+
+    fn editor_channel() -> (EditableChannel, serde_json::Value) {
+        let messages = vec![
+            serde_json::json!({"ts": "1705312800.000100", "user": "U1", "text": "Deploy done"}),
+            serde_json::json!({"ts": "1705917600.000100", "user": "U2", "text": "lunch?"}),
+            serde_json::json!({"ts": "1705917700.000100", "bot_id": "B1", "text": "Build #12 failed"}),
+        ];
+        let users = serde_json::json!([
+            {"id": "U1", "name": "alice", "real_name": "Alice Smith"},
+            {"id": "U2", "name": "bob"},
+        ]);
+        (EditableChannel::new("C1".to_string(), "general".to_string(), messages), users)
+    }
+
+    fn enabled_texts(channel: &EditableChannel) -> Vec<&str> {
+        channel.messages.iter().filter(|m| m.enabled).map(|m| m.text()).collect()
+    }
+
+    #[test]
+    fn test_bulk_toggle_matching() {
+        let (mut channel, users) = editor_channel();
+
+        let matcher = BulkMatcher::parse("^(deploy|build)").unwrap();
+        assert_eq!(channel.set_enabled_matching(&matcher, &users, false), 2);
+        assert_eq!(enabled_texts(&channel), ["lunch?"]);
+        // Already disabled messages don't count as changed
+        assert_eq!(channel.set_enabled_matching(&matcher, &users, false), 0);
+
+        let matcher = BulkMatcher::parse("from:@Alice Smith").unwrap();
+        assert_eq!(channel.set_enabled_matching(&matcher, &users, true), 1);
+        assert_eq!(enabled_texts(&channel), ["Deploy done", "lunch?"]);
+
+        let matcher = BulkMatcher::parse("from:bot").unwrap();
+        assert_eq!(channel.set_enabled_matching(&matcher, &users, true), 1);
+        let matcher = BulkMatcher::parse("from:U2").unwrap();
+        assert_eq!(channel.set_enabled_matching(&matcher, &users, false), 1);
+        assert_eq!(enabled_texts(&channel), ["Deploy done", "Build #12 failed"]);
+    }
+
+    #[test]
+    fn test_bulk_matcher_rejects_bad_patterns() {
+        // An invalid regex is reported instead of matching nothing
+        assert!(BulkMatcher::parse("deploy(").unwrap_err().contains("unclosed group"));
+        assert!(BulkMatcher::parse("[a-").is_err());
+        assert!(BulkMatcher::parse("   ").is_err());
+        // `from:` alone is a regex, not an empty author
+        assert!(matches!(BulkMatcher::parse("from:"), Ok(BulkMatcher::Text(_))));
+    }
+
+    #[test]
+    fn test_editor_filter() {
+        let (mut channel, users) = editor_channel();
+        let visible = |channel: &EditableChannel| -> Vec<String> {
+            channel.visible_messages().map(|m| m.text().to_string()).collect()
+        };
+
+        channel.filter = TextInput::new("from:alice".to_string());
+        channel.apply_filter(&users);
+        assert_eq!(visible(&channel), ["Deploy done"]);
+
+        channel.filter = TextInput::new("after:2024-01-20 FAILED".to_string());
+        channel.apply_filter(&users);
+        assert_eq!(visible(&channel), ["Build #12 failed"]);
+        assert_eq!(channel.filter_error, None);
+
+        // A filter that can't be parsed is reported and keeps the last matches
+        channel.filter = TextInput::new("before:someday".to_string());
+        channel.apply_filter(&users);
+        assert!(channel.filter_error.is_some());
+        assert_eq!(visible(&channel), ["Build #12 failed"]);
+
+        channel.clear_filter(&users);
+        assert_eq!(channel.filter_error, None);
+        assert_eq!(visible(&channel).len(), 3);
+    }
+
+    fn channel_list() -> EditableChannelList {
+        let message = |ts: &str| serde_json::json!({"ts": ts, "text": ts});
+        EditableChannelList::new(vec![
+            ("C1".to_string(), "alpha".to_string(), vec![message("3.0"), message("5.0")]),
+            ("C2".to_string(), "beta".to_string(), vec![message("1.0")]),
+            ("C3".to_string(), "gamma".to_string(), vec![message("4.0"), message("2.0")]),
+            ("C4".to_string(), "delta".to_string(), vec![message("6.0")]),
+        ])
+    }
+
+    fn mark(list: &mut EditableChannelList, names: &[&str]) {
+        for channel in &mut list.channels {
+            channel.marked = names.contains(&channel.name.as_str());
+        }
+    }
+
+    fn channel_names(list: &EditableChannelList) -> Vec<&str> {
+        list.channels.iter().map(|c| c.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_rename_current() {
+        let mut list = channel_list();
+        list.list_state.select(Some(1));
+        list.rename_current("bravo");
+        assert_eq!(channel_names(&list), ["alpha", "bravo", "delta", "gamma"]);
+
+        list.list_state.select(None);
+        list.rename_current("nobody");
+        assert_eq!(channel_names(&list), ["alpha", "bravo", "delta", "gamma"]);
+    }
+
+    #[test]
+    fn test_merge_marked() {
+        let mut list = channel_list();
+        mark(&mut list, &["delta"]);
+        assert!(list.merge_marked("all").is_err());
+        assert_eq!(list.channels.len(), 4);
+
+        mark(&mut list, &["beta", "gamma", "alpha"]);
+        list.merge_marked("abg").unwrap();
+        assert_eq!(channel_names(&list), ["abg", "delta"]);
+        let merged = &list.channels[0];
+        assert_eq!(merged.id, "C1");
+        assert_eq!(merged.merged_from, ["alpha", "beta", "gamma"]);
+        assert!(!merged.marked);
+        let ts: Vec<&str> = merged.messages.iter().map(|m| m.text()).collect();
+        assert_eq!(ts, ["1.0", "2.0", "3.0", "4.0", "5.0"]);
+        assert_eq!(list.list_state.selected(), Some(0));
+
+        // Merging a merged channel again keeps its sources in order
+        mark(&mut list, &["abg", "delta"]);
+        list.merge_marked("everything").unwrap();
+        assert_eq!(channel_names(&list), ["everything"]);
+        assert_eq!(list.channels[0].merged_from, ["alpha", "beta", "gamma", "delta"]);
+        assert_eq!(list.channels[0].messages.len(), 6);
+    }
 }
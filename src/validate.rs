@@ -0,0 +1,469 @@
+//! Archive validation: checks exported JSON files and parquet trees against
+//! the shape the exporters write, for CI of archive pipelines
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::path::Path;
+
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use serde::Serialize;
+use walkdir::WalkDir;
+
+use crate::parquet::{record_batch_to_json, ts_partition};
+
+/// What is wrong with an archive file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IssueKind {
+    /// The file can't be opened, parsed or decoded
+    Unreadable,
+    /// A value has the wrong type, e.g. a timestamp that isn't a number
+    Schema,
+    MissingField,
+    /// Two users or channels share an ID
+    DuplicateId,
+    /// Two messages of one channel share a ts
+    DuplicateTs,
+    /// A reply that points at a parent the archive doesn't have
+    BrokenThread,
+}
+
+impl fmt::Display for IssueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            IssueKind::Unreadable => "unreadable",
+            IssueKind::Schema => "schema",
+            IssueKind::MissingField => "missing field",
+            IssueKind::DuplicateId => "duplicate id",
+            IssueKind::DuplicateTs => "duplicate ts",
+            IssueKind::BrokenThread => "broken thread",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    pub file: String,
+    pub kind: IssueKind,
+    pub detail: String,
+}
+
+impl fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.file, self.kind, self.detail)
+    }
+}
+
+/// Files checked and the issues found in them
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ValidationReport {
+    pub files: usize,
+    pub issues: Vec<ValidationIssue>,
+}
+
+impl ValidationReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+
+    fn push(&mut self, file: &str, kind: IssueKind, detail: String) {
+        self.issues.push(ValidationIssue {
+            file: file.to_string(),
+            kind,
+            detail,
+        });
+    }
+}
+
+/// Which export a JSON file holds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonKind {
+    Users,
+    Channels,
+    Conversations,
+}
+
+fn str_field<'a>(value: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    value.get(key).and_then(|v| v.as_str())
+}
+
+/// Users and channels: objects with a string `id` and `name`, IDs unique
+fn validate_entities(file: &str, label: &str, entities: &[serde_json::Value], report: &mut ValidationReport) {
+    let mut seen = HashSet::new();
+    for (i, entity) in entities.iter().enumerate() {
+        if !entity.is_object() {
+            report.push(file, IssueKind::Schema, format!("{} {} is not an object", label, i));
+            continue;
+        }
+        for key in ["id", "name"] {
+            if str_field(entity, key).is_none() {
+                report.push(file, IssueKind::MissingField, format!("{} {} has no {}", label, i, key));
+            }
+        }
+        if let Some(id) = str_field(entity, "id")
+            && !seen.insert(id)
+        {
+            report.push(file, IssueKind::DuplicateId, format!("{} {} appears more than once", label, id));
+        }
+    }
+}
+
+/// Check a message's `ts` and record it, returning it when valid
+fn check_ts<'a>(
+    file: &str,
+    channel: &str,
+    message: &'a serde_json::Value,
+    seen: &mut HashSet<&'a str>,
+    report: &mut ValidationReport,
+) -> Option<&'a str> {
+    let Some(ts) = str_field(message, "ts") else {
+        report.push(file, IssueKind::MissingField, format!("#{}: message without ts", channel));
+        return None;
+    };
+    if ts.parse::<f64>().is_err() {
+        report.push(file, IssueKind::Schema, format!("#{}: ts {} is not a Slack timestamp", channel, ts));
+    }
+    if !seen.insert(ts) {
+        report.push(file, IssueKind::DuplicateTs, format!("#{}: ts {} appears more than once", channel, ts));
+    }
+    Some(ts)
+}
+
+/// Conversations: channel objects with messages whose ts are unique per
+/// channel, and replies nested under the parent they belong to
+fn validate_conversations(file: &str, conversations: &[serde_json::Value], report: &mut ValidationReport) {
+    for (i, conversation) in conversations.iter().enumerate() {
+        let channel_id = str_field(conversation, "channel_id");
+        let channel = str_field(conversation, "channel_name")
+            .or(channel_id)
+            .map_or_else(|| format!("conversation {}", i), str::to_string);
+        for key in ["channel_id", "channel_name"] {
+            if str_field(conversation, key).is_none() {
+                report.push(file, IssueKind::MissingField, format!("{} has no {}", channel, key));
+            }
+        }
+        let Some(messages) = conversation.get("messages").and_then(|m| m.as_array()) else {
+            report.push(file, IssueKind::MissingField, format!("{} has no messages array", channel));
+            continue;
+        };
+
+        let mut seen = HashSet::new();
+        for message in messages {
+            let parent_ts = check_ts(file, &channel, message, &mut seen, report);
+            let Some(replies) = message.get("thread_replies").and_then(|r| r.as_array()) else {
+                continue;
+            };
+            if let (Some(ts), Some(thread_ts)) = (parent_ts, str_field(message, "thread_ts"))
+                && ts != thread_ts
+                && !replies.is_empty()
+            {
+                let detail = format!("#{}: reply {} has replies of its own", channel, ts);
+                report.push(file, IssueKind::BrokenThread, detail);
+            }
+            for reply in replies {
+                let Some(ts) = check_ts(file, &channel, reply, &mut seen, report) else {
+                    continue;
+                };
+                if let (Some(parent), Some(thread_ts)) = (parent_ts, str_field(reply, "thread_ts"))
+                    && parent != thread_ts
+                {
+                    let detail = format!("#{}: reply {} points at {} but is nested under {}", channel, ts, thread_ts, parent);
+                    report.push(file, IssueKind::BrokenThread, detail);
+                }
+            }
+        }
+    }
+}
+
+/// Validate one exported JSON file
+pub fn validate_json_file(path: &str, kind: JsonKind, report: &mut ValidationReport) {
+    report.files += 1;
+    let values: Vec<serde_json::Value> = match crate::load_json_file::<serde_json::Value>(path) {
+        Ok(serde_json::Value::Array(values)) => values,
+        Ok(_) => {
+            report.push(path, IssueKind::Schema, "expected a JSON array".to_string());
+            return;
+        }
+        Err(e) => {
+            report.push(path, IssueKind::Unreadable, e.to_string());
+            return;
+        }
+    };
+    match kind {
+        JsonKind::Users => validate_entities(path, "user", &values, report),
+        JsonKind::Channels => validate_entities(path, "channel", &values, report),
+        JsonKind::Conversations => validate_conversations(path, &values, report),
+    }
+}
+
+/// Columns every file of a kind must have
+fn required_columns(path: &Path) -> &'static [&'static str] {
+    match path.file_name().and_then(|n| n.to_str()) {
+        Some("users.parquet") | Some("channels.parquet") => &["id", "name"],
+        _ => &["ts", "channel_id", "channel_name", "thread_ts", "is_reply"],
+    }
+}
+
+/// `year=YYYY/week=WW` of a partitioned file, from its parent folders
+fn path_partition(path: &Path) -> Option<(i32, i32)> {
+    let value = |dir: Option<&Path>, prefix: &str| {
+        dir.and_then(|d| d.file_name())
+            .and_then(|n| n.to_str())
+            .and_then(|n| n.strip_prefix(prefix))
+            .and_then(|v| v.parse().ok())
+    };
+    let mut week_dir = path.parent();
+    // Archives partitioned by channel have a channel=ID folder in each week
+    let in_channel_dir = |dir: &Path| dir.file_name().and_then(|n| n.to_str()).is_some_and(|n| n.starts_with("channel="));
+    if week_dir.is_some_and(in_channel_dir) {
+        week_dir = week_dir.and_then(Path::parent);
+    }
+    Some((value(week_dir.and_then(Path::parent), "year=")?, value(week_dir, "week=")?))
+}
+
+/// Rows of a parquet file as JSON, after checking its columns
+fn read_parquet_rows(path: &Path, file: &str, report: &mut ValidationReport) -> Option<Vec<crate::parquet::JsonRow>> {
+    let builder = File::open(path)
+        .map_err(|e| e.to_string())
+        .and_then(|f| ParquetRecordBatchReaderBuilder::try_new(f).map_err(|e| e.to_string()));
+    let builder = match builder {
+        Ok(builder) => builder,
+        Err(e) => {
+            report.push(file, IssueKind::Unreadable, e);
+            return None;
+        }
+    };
+    let missing: Vec<&str> = required_columns(path)
+        .iter()
+        .copied()
+        .filter(|c| builder.schema().field_with_name(c).is_err())
+        .collect();
+    if !missing.is_empty() {
+        report.push(file, IssueKind::MissingField, format!("no {} column", missing.join(", ")));
+        return None;
+    }
+
+    let rows = builder
+        .build()
+        .map_err(|e| e.to_string())
+        .and_then(|reader| {
+            let mut rows = Vec::new();
+            for batch in reader {
+                let batch = batch.map_err(|e| e.to_string())?;
+                rows.extend(record_batch_to_json(&batch).map_err(|e| e.to_string())?);
+            }
+            Ok(rows)
+        });
+    rows.map_err(|e| report.push(file, IssueKind::Unreadable, e)).ok()
+}
+
+/// Validate every parquet file under `root`: each must decode and have the
+/// expected columns, message ts must be unique per channel across the tree,
+/// and replies must have their parent whenever the parent's week is archived.
+/// The Delta log and the `part-*.parquet` copies it points at are skipped.
+pub fn validate_parquet_tree(root: &Path, report: &mut ValidationReport) {
+    let mut files: Vec<_> = WalkDir::new(root)
+        .into_iter()
+        .filter_entry(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name != "_delta_log" && !name.starts_with("part-")
+        })
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file() && entry.path().extension().is_some_and(|e| e == "parquet"))
+        .map(|entry| entry.into_path())
+        .collect();
+    if files.is_empty() {
+        report.push(&root.display().to_string(), IssueKind::Unreadable, "no parquet files".to_string());
+        return;
+    }
+    files.sort();
+
+    let mut weeks = HashSet::new();
+    let mut seen: HashMap<(String, String), String> = HashMap::new();
+    let mut parents = HashSet::new();
+    let mut replies = Vec::new();
+    for path in &files {
+        report.files += 1;
+        let file = path.display().to_string();
+        let Some(rows) = read_parquet_rows(path, &file, report) else {
+            continue;
+        };
+        let Some(week) = path_partition(path) else {
+            continue;
+        };
+        weeks.insert(week);
+
+        for row in rows {
+            let text = |key: &str| row.get(key).and_then(|v| v.as_str()).map(str::to_string);
+            let (Some(channel_id), Some(ts)) = (text("channel_id"), text("ts")) else {
+                report.push(&file, IssueKind::MissingField, "row without channel_id or ts".to_string());
+                continue;
+            };
+            let is_reply = row.get("is_reply").and_then(|v| v.as_bool()).unwrap_or(false);
+            if is_reply {
+                match text("thread_ts") {
+                    Some(thread_ts) => replies.push((file.clone(), channel_id.clone(), ts.clone(), thread_ts)),
+                    None => {
+                        let detail = format!("reply {} in {} has no thread_ts", ts, channel_id);
+                        report.push(&file, IssueKind::BrokenThread, detail);
+                    }
+                }
+            } else {
+                parents.insert((channel_id.clone(), ts.clone()));
+            }
+            if let Some(first) = seen.insert((channel_id.clone(), ts.clone()), file.clone()) {
+                let detail = format!("ts {} in {} also in {}", ts, channel_id, first);
+                report.push(&file, IssueKind::DuplicateTs, detail);
+            }
+        }
+    }
+
+    for (file, channel_id, ts, thread_ts) in replies {
+        let archived_week = ts_partition(&thread_ts).is_some_and(|(_, year, week)| weeks.contains(&(year, week)));
+        if archived_week && !parents.contains(&(channel_id.clone(), thread_ts.clone())) {
+            let detail = format!("reply {} in {} points at missing parent {}", ts, channel_id, thread_ts);
+            report.push(&file, IssueKind::BrokenThread, detail);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn kinds(report: &ValidationReport) -> Vec<IssueKind> {
+        report.issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_validate_entities() {
+        let users = [
+            json!({"id": "U1", "name": "ana"}),
+            json!({"id": "U1", "name": "ana2"}),
+            json!({"name": "nobody"}),
+            json!("U3"),
+        ];
+        let mut report = ValidationReport::default();
+        validate_entities("users.json", "user", &users, &mut report);
+        assert_eq!(
+            kinds(&report),
+            vec![IssueKind::DuplicateId, IssueKind::MissingField, IssueKind::Schema]
+        );
+        assert_eq!(report.issues[1].to_string(), "users.json: missing field: user 2 has no id");
+    }
+
+    #[test]
+    fn test_validate_conversations() {
+        let conversations = [
+            json!({
+                "channel_id": "C1",
+                "channel_name": "general",
+                "messages": [
+                    {"ts": "1.0", "thread_ts": "1.0", "thread_replies": [
+                        {"ts": "1.5", "thread_ts": "1.0"},
+                        {"ts": "1.6", "thread_ts": "9.0"}
+                    ]},
+                    {"ts": "1.5"},
+                    {"ts": "later"},
+                    {"text": "no ts"}
+                ]
+            }),
+            json!({"channel_id": "C2"}),
+        ];
+        let mut report = ValidationReport::default();
+        validate_conversations("conversations.json", &conversations, &mut report);
+        assert_eq!(
+            kinds(&report),
+            vec![
+                IssueKind::BrokenThread,
+                IssueKind::DuplicateTs,
+                IssueKind::Schema,
+                IssueKind::MissingField,
+                IssueKind::MissingField,
+                IssueKind::MissingField,
+            ]
+        );
+        assert!(report.issues[0].detail.contains("reply 1.6 points at 9.0"));
+    }
+
+    #[test]
+    fn test_validate_json_file_unreadable() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("channels.json");
+        std::fs::write(&path, "{\"id\": \"C1\"}").unwrap();
+        let mut report = ValidationReport::default();
+        validate_json_file(&path.to_string_lossy(), JsonKind::Channels, &mut report);
+        validate_json_file("missing.json", JsonKind::Users, &mut report);
+        assert_eq!(report.files, 2);
+        assert_eq!(kinds(&report), vec![IssueKind::Schema, IssueKind::Unreadable]);
+    }
+
+    #[test]
+    fn test_validate_parquet_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let conversations = [json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [
+                {"ts": "1705312800.000100", "thread_replies": [{"ts": "1705312860.000100"}]},
+                {"ts": "1705399200.000100"}
+            ]
+        })];
+        crate::parquet::write_conversations_parquet(dir.path(), &conversations).unwrap();
+        crate::parquet::write_users_parquet(&dir.path().join("users.parquet"), &[json!({"id": "U1"})]).unwrap();
+
+        let mut report = ValidationReport::default();
+        validate_parquet_tree(dir.path(), &mut report);
+        assert_eq!(report.files, 2);
+        assert!(report.is_ok(), "{:?}", report.issues);
+
+        // A reply whose parent's week is archived but the parent is gone
+        let orphan = [json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1705312700.000100", "thread_replies": [{"ts": "1705312900.000100"}]}]
+        })];
+        let other = tempfile::tempdir().unwrap();
+        crate::parquet::write_conversations_parquet(other.path(), &orphan).unwrap();
+        let rows = crate::parquet::read_parquet_as_json(&other.path().join("year=2024/week=03/threads.parquet")).unwrap();
+        let replies: Vec<_> = rows.into_iter().filter(|r| r["is_reply"] == json!(true)).collect();
+        let week = dir.path().join("year=2024/week=03/extra.parquet");
+        crate::parquet::write_message_rows(&week, &replies, None).unwrap();
+        std::fs::write(dir.path().join("year=2024/broken.parquet"), b"not parquet").unwrap();
+
+        let mut report = ValidationReport::default();
+        validate_parquet_tree(dir.path(), &mut report);
+        assert_eq!(report.files, 4);
+        assert_eq!(kinds(&report), vec![IssueKind::Unreadable, IssueKind::BrokenThread]);
+    }
+
+    #[test]
+    fn test_validate_parquet_tree_delta() {
+        let dir = tempfile::tempdir().unwrap();
+        let conversations = [json!({
+            "channel_id": "C1",
+            "channel_name": "general",
+            "messages": [{"ts": "1705312800.000100"}, {"ts": "1705399200.000100"}]
+        })];
+        crate::parquet::write_conversations_parquet(dir.path(), &conversations).unwrap();
+        let storage = crate::storage::LocalStorage::new(dir.path());
+        crate::delta::DeltaTable::new(&storage, "").commit(&[], "WRITE").unwrap();
+        assert!(dir.path().join("_delta_log").is_dir());
+
+        let mut report = ValidationReport::default();
+        validate_parquet_tree(dir.path(), &mut report);
+        assert_eq!(report.files, 1);
+        assert!(report.is_ok(), "{:?}", report.issues);
+    }
+
+    #[test]
+    fn test_validate_parquet_tree_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut report = ValidationReport::default();
+        validate_parquet_tree(dir.path(), &mut report);
+        assert!(!report.is_ok());
+    }
+}
@@ -1,8 +1,10 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
 use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::{
     layout::Rect,
-    style::Style,
-    widgets::Paragraph,
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
     Frame,
 };
 
@@ -153,6 +155,20 @@ impl TextInput {
         self.cursor = pos;
     }
 
+    /// Replace the text, moving the cursor to the end
+    pub fn set_text(&mut self, text: String) {
+        self.cursor = text.chars().count();
+        self.text = text;
+    }
+
+    /// Complete the text as a filesystem path, returning the candidates left
+    /// to choose from
+    pub fn complete_path(&mut self) -> Vec<String> {
+        let completion = complete_path(&self.text);
+        self.set_text(completion.text);
+        completion.candidates
+    }
+
     /// Render the input and return cursor screen position
     pub fn render(&self, f: &mut Frame, area: Rect, style: Style) {
         let paragraph = Paragraph::new(self.text.as_str()).style(style);
@@ -167,8 +183,414 @@ impl TextInput {
     }
 }
 
+/// Outcome of completing a filesystem path
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathCompletion {
+    pub text: String,
+    /// Entries matching the typed prefix when it is still ambiguous
+    pub candidates: Vec<String>,
+}
+
+/// Complete the last component of `text` against the filesystem. A single
+/// match is filled in (directories get a trailing `/`); several matches are
+/// extended to their longest common prefix and returned as candidates.
+pub fn complete_path(text: &str) -> PathCompletion {
+    let (dir, prefix) = match text.rfind('/') {
+        Some(i) => text.split_at(i + 1),
+        None => ("", text),
+    };
+    let read_dir = if dir.is_empty() { "." } else { dir };
+
+    let mut matches: Vec<String> = std::fs::read_dir(read_dir)
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| {
+                    let name = entry.file_name().into_string().ok()?;
+                    // Hidden entries only when asked for
+                    if !name.starts_with(prefix) || (name.starts_with('.') && !prefix.starts_with('.')) {
+                        return None;
+                    }
+                    let is_dir = entry.file_type().is_ok_and(|t| t.is_dir());
+                    Some(if is_dir { format!("{}/", name) } else { name })
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    matches.sort();
+
+    let common = matches.split_first().map(|(first, rest)| {
+        rest.iter().fold(first.as_str(), |common, name| {
+            let len = common
+                .char_indices()
+                .zip(name.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((i, c), _)| i + c.len_utf8());
+            common.get(..len).unwrap_or_default()
+        })
+    });
+
+    match (common, matches.len()) {
+        (Some(common), 1) => PathCompletion {
+            text: format!("{}{}", dir, common),
+            candidates: Vec::new(),
+        },
+        (Some(common), _) => PathCompletion {
+            text: format!("{}{}", dir, common),
+            candidates: matches,
+        },
+        (None, _) => PathCompletion {
+            text: text.to_string(),
+            candidates: Vec::new(),
+        },
+    }
+}
+
 impl Default for TextInput {
     fn default() -> Self {
         Self::new(String::new())
     }
 }
+
+/// A multi-line text editor, cursor positions are in characters
+#[derive(Debug, Clone)]
+pub struct TextArea {
+    lines: Vec<String>,
+    row: usize,
+    col: usize,
+    /// First visible line, kept so the cursor stays on screen
+    scroll: usize,
+}
+
+impl TextArea {
+    pub fn new(text: &str) -> Self {
+        let lines: Vec<String> = text.split('\n').map(str::to_string).collect();
+        let row = lines.len().saturating_sub(1);
+        let col = lines.last().map_or(0, |l| l.chars().count());
+        Self {
+            lines,
+            row,
+            col,
+            scroll: 0,
+        }
+    }
+
+    pub fn text(&self) -> String {
+        self.lines.join("\n")
+    }
+
+    fn line_len(&self, row: usize) -> usize {
+        self.lines.get(row).map_or(0, |l| l.chars().count())
+    }
+
+    fn byte_pos(&self, row: usize, col: usize) -> usize {
+        self.lines.get(row).map_or(0, |line| {
+            line.char_indices()
+                .nth(col)
+                .map(|(i, _)| i)
+                .unwrap_or(line.len())
+        })
+    }
+
+    /// Handle a key event, returns true if the event was handled
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        match (key.code, key.modifiers) {
+            (KeyCode::Left, _) => {
+                if self.col > 0 {
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                }
+                true
+            }
+            (KeyCode::Right, _) => {
+                if self.col < self.line_len(self.row) {
+                    self.col += 1;
+                } else if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = 0;
+                }
+                true
+            }
+            (KeyCode::Up, _) => {
+                if self.row > 0 {
+                    self.row -= 1;
+                    self.col = self.col.min(self.line_len(self.row));
+                }
+                true
+            }
+            (KeyCode::Down, _) => {
+                if self.row + 1 < self.lines.len() {
+                    self.row += 1;
+                    self.col = self.col.min(self.line_len(self.row));
+                }
+                true
+            }
+            (KeyCode::Home, _) | (KeyCode::Char('a'), KeyModifiers::CONTROL) => {
+                self.col = 0;
+                true
+            }
+            (KeyCode::End, _) | (KeyCode::Char('e'), KeyModifiers::CONTROL) => {
+                self.col = self.line_len(self.row);
+                true
+            }
+            (KeyCode::Enter, _) => {
+                let pos = self.byte_pos(self.row, self.col);
+                if let Some(line) = self.lines.get_mut(self.row) {
+                    let rest = line.split_off(pos);
+                    self.lines.insert(self.row + 1, rest);
+                    self.row += 1;
+                    self.col = 0;
+                }
+                true
+            }
+            (KeyCode::Backspace, _) => {
+                if self.col > 0 {
+                    let start = self.byte_pos(self.row, self.col - 1);
+                    let end = self.byte_pos(self.row, self.col);
+                    if let Some(line) = self.lines.get_mut(self.row) {
+                        line.replace_range(start..end, "");
+                    }
+                    self.col -= 1;
+                } else if self.row > 0 {
+                    let line = self.lines.remove(self.row);
+                    self.row -= 1;
+                    self.col = self.line_len(self.row);
+                    if let Some(previous) = self.lines.get_mut(self.row) {
+                        previous.push_str(&line);
+                    }
+                }
+                true
+            }
+            (KeyCode::Delete, _) => {
+                if self.col < self.line_len(self.row) {
+                    let start = self.byte_pos(self.row, self.col);
+                    let end = self.byte_pos(self.row, self.col + 1);
+                    if let Some(line) = self.lines.get_mut(self.row) {
+                        line.replace_range(start..end, "");
+                    }
+                } else if self.row + 1 < self.lines.len() {
+                    let next = self.lines.remove(self.row + 1);
+                    if let Some(line) = self.lines.get_mut(self.row) {
+                        line.push_str(&next);
+                    }
+                }
+                true
+            }
+            (KeyCode::Char(c), KeyModifiers::NONE | KeyModifiers::SHIFT) => {
+                let pos = self.byte_pos(self.row, self.col);
+                if let Some(line) = self.lines.get_mut(self.row) {
+                    line.insert(pos, c);
+                    self.col += 1;
+                }
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Render the visible lines and place the cursor, scrolling to keep it
+    /// in view. Lines longer than the area are cut, not wrapped.
+    pub fn render(&mut self, f: &mut Frame, area: Rect, style: Style) {
+        let height = usize::from(area.height.max(1));
+        if self.row < self.scroll {
+            self.scroll = self.row;
+        } else if self.row >= self.scroll + height {
+            self.scroll = self.row + 1 - height;
+        }
+
+        let lines: Vec<Line> = self
+            .lines
+            .iter()
+            .skip(self.scroll)
+            .take(height)
+            .map(|l| Line::raw(l.as_str()))
+            .collect();
+        f.render_widget(Paragraph::new(lines).style(style), area);
+
+        let cursor_x = area.x + self.col as u16;
+        let cursor_y = area.y + (self.row - self.scroll) as u16;
+        if cursor_x < area.x + area.width {
+            f.set_cursor_position((cursor_x, cursor_y));
+        }
+    }
+}
+
+/// What a `DatePicker` selects: a single day or a whole ISO week
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PickerMode {
+    Date,
+    Week,
+}
+
+/// Popup month calendar with ISO week numbers, for picking a valid date or week
+#[derive(Debug, Clone)]
+pub struct DatePicker {
+    pub mode: PickerMode,
+    pub selected: NaiveDate,
+}
+
+impl DatePicker {
+    pub fn new(mode: PickerMode, selected: NaiveDate) -> Self {
+        Self { mode, selected }
+    }
+
+    /// ISO year and week of the selection
+    pub fn iso_week(&self) -> (i32, u32) {
+        let week = self.selected.iso_week();
+        (week.year(), week.week())
+    }
+
+    /// Move the selection. Returns false for keys the picker doesn't handle
+    pub fn handle_key(&mut self, key: KeyEvent) -> bool {
+        let step = match self.mode {
+            PickerMode::Date => 1,
+            PickerMode::Week => 7,
+        };
+        let moved = match key.code {
+            KeyCode::Left | KeyCode::Char('h') => self.selected.checked_sub_days(Days::new(step)),
+            KeyCode::Right | KeyCode::Char('l') => self.selected.checked_add_days(Days::new(step)),
+            KeyCode::Up | KeyCode::Char('k') => self.selected.checked_sub_days(Days::new(7)),
+            KeyCode::Down | KeyCode::Char('j') => self.selected.checked_add_days(Days::new(7)),
+            KeyCode::PageUp => self.selected.checked_sub_months(Months::new(1)),
+            KeyCode::PageDown => self.selected.checked_add_months(Months::new(1)),
+            KeyCode::Home => Some(chrono::Local::now().date_naive()),
+            _ => return false,
+        };
+        if let Some(date) = moved {
+            self.selected = date;
+        }
+        true
+    }
+
+    fn is_selected(&self, date: NaiveDate) -> bool {
+        match self.mode {
+            PickerMode::Date => date == self.selected,
+            PickerMode::Week => date.iso_week() == self.selected.iso_week(),
+        }
+    }
+
+    /// Render the calendar of the selected month centered in `area`
+    pub fn render(&self, f: &mut Frame, area: Rect) {
+        let width = 44.min(area.width);
+        let height = 12.min(area.height);
+        let popup = Rect {
+            x: area.x + (area.width - width) / 2,
+            y: area.y + (area.height - height) / 2,
+            width,
+            height,
+        };
+
+        let title = match self.mode {
+            PickerMode::Date => format!("{}", self.selected.format("%B %Y")),
+            PickerMode::Week => {
+                let (year, week) = self.iso_week();
+                format!("{}  W{:02} ({})", self.selected.format("%B"), week, year)
+            }
+        };
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(title)
+            .border_style(Style::default().fg(Color::Yellow));
+
+        let dim = Style::default().fg(Color::DarkGray);
+        let mut lines = vec![Line::from(Span::styled("Wk   Mo Tu We Th Fr Sa Su", dim))];
+
+        let first = self.selected.with_day(1).unwrap_or(self.selected);
+        let mut monday = first - Days::new(u64::from(first.weekday().num_days_from_monday()));
+        let today = chrono::Local::now().date_naive();
+        while monday.month() == first.month() || monday <= first {
+            let mut spans = vec![Span::styled(format!("{:>2}  ", monday.iso_week().week()), dim)];
+            for offset in 0..7 {
+                let date = monday + Days::new(offset);
+                let mut style = if date.month() == first.month() {
+                    Style::default()
+                } else {
+                    dim
+                };
+                if date == today {
+                    style = style.add_modifier(Modifier::BOLD);
+                }
+                if self.is_selected(date) {
+                    style = style.bg(Color::Yellow).fg(Color::Black);
+                }
+                spans.push(Span::raw(" "));
+                spans.push(Span::styled(format!("{:>2}", date.day()), style));
+            }
+            lines.push(Line::from(spans));
+            monday = monday + Days::new(7);
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("PgUp/PgDn: Month  Enter: Pick  Esc: Close", dim)));
+
+        f.render_widget(Clear, popup);
+        f.render_widget(Paragraph::new(lines).block(block), popup);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("archive-2023")).unwrap();
+        std::fs::create_dir(dir.path().join("archive-2024")).unwrap();
+        std::fs::create_dir(dir.path().join("channels")).unwrap();
+        std::fs::write(dir.path().join("users.json"), "[]").unwrap();
+        std::fs::write(dir.path().join(".env"), "").unwrap();
+        std::fs::write(dir.path().join(".envrc"), "").unwrap();
+        dir
+    }
+
+    fn base(dir: &tempfile::TempDir) -> String {
+        format!("{}/", dir.path().display())
+    }
+
+    #[test]
+    fn test_complete_path_common_prefix() {
+        let dir = fixture();
+        let base = base(&dir);
+
+        let completion = complete_path(&format!("{}arc", base));
+        assert_eq!(completion.text, format!("{}archive-202", base));
+        assert_eq!(completion.candidates, ["archive-2023/", "archive-2024/"]);
+
+        // Nothing matches: the text is left alone
+        let completion = complete_path(&format!("{}zzz", base));
+        assert_eq!(completion.text, format!("{}zzz", base));
+        assert!(completion.candidates.is_empty());
+    }
+
+    #[test]
+    fn test_complete_path_single_match() {
+        let dir = fixture();
+        let base = base(&dir);
+
+        // Directories get a trailing slash, files don't
+        let completion = complete_path(&format!("{}ch", base));
+        assert_eq!(completion.text, format!("{}channels/", base));
+        assert!(completion.candidates.is_empty());
+        assert_eq!(complete_path(&format!("{}u", base)).text, format!("{}users.json", base));
+
+        let mut input = TextInput::new(format!("{}archive-2024", base));
+        assert!(input.complete_path().is_empty());
+        assert_eq!(input.text, format!("{}archive-2024/", base));
+    }
+
+    #[test]
+    fn test_complete_path_hidden_entries() {
+        let dir = fixture();
+        let base = base(&dir);
+
+        let completion = complete_path(&base);
+        assert_eq!(completion.candidates, ["archive-2023/", "archive-2024/", "channels/", "users.json"]);
+
+        let completion = complete_path(&format!("{}.", base));
+        assert_eq!(completion.text, format!("{}.env", base));
+        assert_eq!(completion.candidates, [".env", ".envrc"]);
+    }
+}
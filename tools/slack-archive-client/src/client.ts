@@ -2,11 +2,25 @@ import type {
   SlackArchiveClientOptions,
   ClientMode,
   ThreadsInRangeResponse,
+  RowsResponse,
+  MessagesResponse,
+  ArchiveStats,
+  ArchivesResponse,
+  QueryRequest,
+  QueryResponse,
+  ArchiveRangeRequest,
+  JobStatus,
+  ThreadsJsonOptions,
+  ThreadsPageOptions,
+  SearchFilters,
   SearchResponse,
   ErrorResponse,
   YearWeek,
 } from "./types.js";
 
+/** Query string parameters for API requests */
+type QueryParams = Record<string, string | number | string[] | undefined>;
+
 /**
  * Error thrown when the server returns an error response
  */
@@ -22,6 +36,17 @@ export class SlackArchiveError extends Error {
   }
 }
 
+/**
+ * Wrap a fetch implementation so every request carries a bearer token
+ */
+function withBearerToken(fetchFn: typeof fetch, token: string): typeof fetch {
+  return (input, init) => {
+    const headers = new Headers(init?.headers);
+    headers.set("Authorization", `Bearer ${token}`);
+    return fetchFn(input, { ...init, headers });
+  };
+}
+
 /**
  * Client for interacting with Slack archive parquet files.
  *
@@ -37,7 +62,10 @@ export class SlackArchiveClient {
   constructor(options: SlackArchiveClientOptions) {
     this.baseUrl = options.baseUrl.replace(/\/$/, "");
     this.mode = options.mode ?? "api";
-    this.fetchFn = options.fetch ?? ((...args) => fetch(...args));
+    const fetchFn = options.fetch ?? ((...args) => fetch(...args));
+    this.fetchFn = options.apiKey
+      ? withBearerToken(fetchFn, options.apiKey)
+      : fetchFn;
   }
 
   /**
@@ -86,31 +114,229 @@ export class SlackArchiveClient {
    * Fetch threads.parquet file for a specific year and week
    * @param year The year (e.g., 2024)
    * @param week The ISO week number (1-53)
+   * @param page Optional channel filter and offset/limit (API mode only);
+   *   the response then holds only the selected messages
    */
-  async getThreads(year: number, week: number): Promise<ArrayBuffer> {
+  async getThreads(
+    year: number,
+    week: number,
+    page: ThreadsPageOptions = {}
+  ): Promise<ArrayBuffer> {
+    const query = this.buildQuery({ year, week, ...page });
     if (this.mode === "static") {
+      if (page.channel !== undefined || page.offset !== undefined || page.limit !== undefined) {
+        throw new SlackArchiveError(
+          "Channel filtering and pagination are not available in static mode",
+          501
+        );
+      }
       const weekStr = String(week).padStart(2, "0");
       return this.fetchStaticParquet(
         `/conversations/year=${year}/week=${weekStr}/threads.parquet`
       );
     }
-    return this.fetchParquet(`/archive/threads?year=${year}&week=${week}`);
+    return this.fetchParquet(`/archive/threads?${query}`);
+  }
+
+  /**
+   * Fetch users as JSON rows (server reads the parquet).
+   * Only available in "api" mode.
+   * @param fields Columns to return (default: all)
+   */
+  async getUsersJson(fields?: string[]): Promise<RowsResponse> {
+    return this.fetchRows("/api/users", { fields });
+  }
+
+  /**
+   * Fetch channels as JSON rows (server reads the parquet).
+   * Only available in "api" mode.
+   * @param fields Columns to return (default: all)
+   */
+  async getChannelsJson(fields?: string[]): Promise<RowsResponse> {
+    return this.fetchRows("/api/channels", { fields });
+  }
+
+  /**
+   * Fetch the messages of a week as JSON rows (server reads the parquet).
+   * Only available in "api" mode.
+   * @param year The year (e.g., 2024)
+   * @param week The ISO week number (1-53)
+   * @param options Optional channel filter and field selection
+   */
+  async getThreadsJson(
+    year: number,
+    week: number,
+    options: ThreadsJsonOptions = {}
+  ): Promise<RowsResponse> {
+    return this.fetchRows("/api/threads", { year, week, ...options });
+  }
+
+  /**
+   * Fetch the messages of a week rendered to HTML by the server.
+   * Only available in "api" mode.
+   * @param year The year (e.g., 2024)
+   * @param week The ISO week number (1-53)
+   * @param page Optional channel filter and offset/limit
+   */
+  async getThreadsHtml(
+    year: number,
+    week: number,
+    page: ThreadsPageOptions = {}
+  ): Promise<MessagesResponse> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError(
+        "Rendered messages are not available in static mode",
+        501
+      );
+    }
+    const query = this.buildQuery({ year, week, ...page });
+    const response = await this.fetchFn(`${this.baseUrl}/api/threads/html?${query}`);
+    return this.handleJsonResponse<MessagesResponse>(response);
+  }
+
+  /**
+   * Fetch message counts per week and channel for the whole archive.
+   * Only available in "api" mode.
+   */
+  async getStats(): Promise<ArchiveStats> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError(
+        "Archive statistics are not available in static mode",
+        501
+      );
+    }
+    const response = await this.fetchFn(`${this.baseUrl}/api/stats`);
+    return this.handleJsonResponse<ArchiveStats>(response);
+  }
+
+  /**
+   * URL of a downloaded attachment, usable as an `<img>`/`<a>` target.
+   * Only available in "api" mode with `[media] attachments` configured.
+   * @param id Slack file ID (e.g., "F0123ABCD")
+   */
+  fileUrl(id: string): string {
+    return `${this.baseUrl}/archive/files/${encodeURIComponent(id)}`;
+  }
+
+  /**
+   * URL of an exported custom emoji image.
+   * Only available in "api" mode with `[media] emojis` configured.
+   * @param name Emoji name without colons (e.g., "party-parrot")
+   */
+  emojiUrl(name: string): string {
+    return `${this.baseUrl}/archive/emojis/${encodeURIComponent(name)}`;
+  }
+
+  /**
+   * WebSocket URL of the live tail; each text frame is a JSON `LiveMessage`.
+   * Only available in "api" mode with `[live]` configured.
+   * @param channel Optional channel ID to follow
+   */
+  liveUrl(channel?: string): string {
+    // Relative base URLs resolve against the page in browsers
+    const url = new URL(
+      `${this.baseUrl}/archive/live`,
+      globalThis.location?.href ?? "http://localhost"
+    );
+    url.protocol = url.protocol === "https:" ? "wss:" : "ws:";
+    if (channel) {
+      url.searchParams.set("channel", channel);
+    }
+    return url.toString();
+  }
+
+  /**
+   * List the archives hosted by the server.
+   * Only available in "api" mode, on a client whose baseUrl is the server root;
+   * create another client with `baseUrl + prefix` to browse a named archive.
+   */
+  async listArchives(): Promise<ArchivesResponse> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError(
+        "Archive listing is not available in static mode",
+        501
+      );
+    }
+    const response = await this.fetchFn(`${this.baseUrl}/api/archives`);
+    return this.handleJsonResponse<ArchivesResponse>(response);
+  }
+
+  /**
+   * Run a read-only SQL query on the server (DuckDB).
+   * Only available in "api" mode on servers with `[sql]` configured.
+   * @param request `{ name }` of an allowlisted query, or `{ sql }` when ad-hoc queries are enabled
+   */
+  async query(request: QueryRequest): Promise<QueryResponse> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError("SQL queries are not available in static mode", 501);
+    }
+    const response = await this.fetchFn(`${this.baseUrl}/api/query`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify(request),
+    });
+    return this.handleJsonResponse<QueryResponse>(response);
+  }
+
+  /**
+   * Start an archive-range job on the server (requires an admin apiKey).
+   * Only available in "api" mode.
+   * @param range Weeks to fetch from Slack
+   * @returns The running job; poll it with getJob
+   */
+  async startArchiveRange(range: ArchiveRangeRequest): Promise<JobStatus> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError("Admin jobs are not available in static mode", 501);
+    }
+    const response = await this.fetchFn(`${this.baseUrl}/admin/archive-range`, {
+      method: "POST",
+      headers: { "Content-Type": "application/json" },
+      body: JSON.stringify(range),
+    });
+    return this.handleJsonResponse<JobStatus>(response);
+  }
+
+  /**
+   * Get the progress and outcome of an admin job (requires an admin apiKey).
+   * Only available in "api" mode.
+   * @param id Job ID returned by startArchiveRange
+   */
+  async getJob(id: string): Promise<JobStatus> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError("Admin jobs are not available in static mode", 501);
+    }
+    const response = await this.fetchFn(
+      `${this.baseUrl}/admin/jobs/${encodeURIComponent(id)}`
+    );
+    return this.handleJsonResponse<JobStatus>(response);
   }
 
   /**
    * Search messages via Meilisearch.
    * Only available in "api" mode.
-   * @param query Search query string
+   * @param query Search query string; supports Slack-like modifiers
+   *   (`from:@alice in:#general before:2024-02-01 "exact phrase"`)
    * @param limit Maximum number of results (default: 20)
+   * @param filters Optional channel, user and date range filters
    */
-  async search(query: string, limit: number = 20): Promise<SearchResponse> {
+  async search(
+    query: string,
+    limit: number = 20,
+    filters: SearchFilters = {}
+  ): Promise<SearchResponse> {
     if (this.mode === "static") {
       throw new SlackArchiveError(
         "Search is not available in static mode",
         501
       );
     }
-    const url = `${this.baseUrl}/archive/search?query=${encodeURIComponent(query)}&limit=${limit}`;
+    const params = new URLSearchParams({ query, limit: String(limit) });
+    for (const [key, value] of Object.entries(filters)) {
+      if (value !== undefined) {
+        params.set(key, value);
+      }
+    }
+    const url = `${this.baseUrl}/archive/search?${params.toString()}`;
     const response = await this.fetchFn(url, { method: "POST" });
     return this.handleJsonResponse<SearchResponse>(response);
   }
@@ -157,6 +383,32 @@ export class SlackArchiveClient {
     return response.arrayBuffer();
   }
 
+  private async fetchRows(path: string, params: QueryParams): Promise<RowsResponse> {
+    if (this.mode === "static") {
+      throw new SlackArchiveError(
+        "JSON endpoints are not available in static mode",
+        501
+      );
+    }
+    const qs = this.buildQuery(params);
+    const url = `${this.baseUrl}${path}${qs ? `?${qs}` : ""}`;
+    const response = await this.fetchFn(url);
+    return this.handleJsonResponse<RowsResponse>(response);
+  }
+
+  /** Build a query string, skipping undefined values and joining arrays with commas */
+  private buildQuery(params: QueryParams): string {
+    const query = new URLSearchParams();
+    for (const [key, value] of Object.entries(params)) {
+      if (Array.isArray(value)) {
+        query.set(key, value.join(","));
+      } else if (value !== undefined) {
+        query.set(key, String(value));
+      }
+    }
+    return query.toString();
+  }
+
   private async handleJsonResponse<T>(response: Response): Promise<T> {
     if (!response.ok) {
       await this.throwError(response);
@@ -7,6 +7,22 @@ export type {
   IndexChannel,
   IndexEntry,
   ThreadsInRangeResponse,
+  JsonRow,
+  RowsResponse,
+  RenderedMessage,
+  MessagesResponse,
+  LiveMessage,
+  WeekStats,
+  ArchiveStats,
+  ArchiveSummary,
+  QueryRequest,
+  QueryResponse,
+  ArchiveRangeRequest,
+  JobStatus,
+  ArchivesResponse,
+  ThreadsJsonOptions,
+  ThreadsPageOptions,
+  SearchFilters,
   SearchResponse,
   ErrorResponse,
 } from "./types.js";
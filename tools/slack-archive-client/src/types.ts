@@ -38,11 +38,193 @@ export interface IndexEntry {
   users: IndexUser[];
   /** Channel information */
   channel: IndexChannel;
+  /** Unix timestamp of the message in seconds */
+  timestamp: number;
+  /** Whether the message or any of its thread replies has file attachments */
+  has_attachments: boolean;
 }
 
 /**
- * Response from the threads-in-range endpoint
+ * Optional filters for the search endpoint
+ */
+export interface SearchFilters {
+  /** Channel name or ID */
+  channel?: string;
+  /** Name or ID of a user involved in the thread */
+  user?: string;
+  /** Only threads on or after this date (YYYY-MM-DD) */
+  from?: string;
+  /** Only threads on or before this date (YYYY-MM-DD) */
+  to?: string;
+}
+
+/**
+ * A parquet row returned by the JSON endpoints, keyed by column name
+ */
+export type JsonRow = Record<string, string | number | boolean | null>;
+
+/**
+ * Response from the JSON endpoints (/api/users, /api/channels, /api/threads)
+ */
+export interface RowsResponse {
+  rows: JsonRow[];
+  /** Number of matching rows before offset/limit */
+  total: number;
+}
+
+/**
+ * A message rendered to HTML by the server (/api/threads/html)
  */
+export interface RenderedMessage {
+  ts: string;
+  user: string | null;
+  /** Display name from users.parquet, or the user ID */
+  user_name: string | null;
+  channel_name: string | null;
+  date: string | null;
+  thread_ts: string | null;
+  is_reply: boolean;
+  /** Message rendered through the server's markdown pipeline */
+  html: string;
+}
+
+/**
+ * Response from the rendered messages endpoint
+ */
+export interface MessagesResponse {
+  messages: RenderedMessage[];
+  /** Number of matching messages before offset/limit */
+  total: number;
+}
+
+/**
+ * A new message pushed by the live tail WebSocket (/archive/live)
+ */
+export interface LiveMessage {
+  /** Channel ID */
+  channel: string;
+  ts: string;
+  user: string | null;
+  thread_ts: string | null;
+  text: string;
+}
+
+/**
+ * Message counts of one week partition
+ */
+export interface WeekStats {
+  year: number;
+  week: number;
+  messages: number;
+  /** Size of the week's threads.parquet */
+  bytes: number;
+  /** Messages per channel name */
+  channels: Record<string, number>;
+}
+
+/**
+ * Response from the archive statistics endpoint (/api/stats)
+ */
+export interface ArchiveStats {
+  total_messages: number;
+  /** Size of all parquet files, including users and channels */
+  total_bytes: number;
+  first_week: YearWeek | null;
+  last_week: YearWeek | null;
+  /** Messages per channel name across all weeks */
+  channels: Record<string, number>;
+  /** One entry per partition, oldest first */
+  weeks: WeekStats[];
+}
+
+/**
+ * Body of a SQL query: an allowlisted query name or ad-hoc SQL
+ */
+export type QueryRequest = { name: string } | { sql: string };
+
+/**
+ * Response from the SQL query endpoint
+ */
+export interface QueryResponse {
+  columns: string[];
+  rows: unknown[][];
+  /** More rows matched than the server's max-rows */
+  truncated: boolean;
+}
+
+/**
+ * Weeks to fetch from Slack with an admin archive-range job
+ */
+export interface ArchiveRangeRequest {
+  from_year: number;
+  from_week: number;
+  /** Defaults to from_year */
+  to_year?: number;
+  /** Defaults to from_week */
+  to_week?: number;
+}
+
+/**
+ * Progress and outcome of an admin job
+ */
+export interface JobStatus {
+  id: string;
+  /** What the job does (e.g. "archive-range") */
+  kind: string;
+  state: "running" | "completed" | "failed";
+  current: number;
+  total: number;
+  message: string;
+  /** Summary of a completed job */
+  result: Record<string, unknown> | null;
+  /** Error of a failed job */
+  error: string | null;
+  /** RFC 3339 timestamps */
+  started_at: string;
+  finished_at: string | null;
+}
+
+/**
+ * An archive hosted by the server
+ */
+export interface ArchiveSummary {
+  /** Name from `[[archives]]`; null for the default `[slack-archive]` */
+  name: string | null;
+  /** Prefix of the archive's routes ("" or "/{name}"); append to baseUrl */
+  prefix: string;
+  /** Whether search is configured for this archive */
+  search: boolean;
+}
+
+/**
+ * Response from the archives index endpoint
+ */
+export interface ArchivesResponse {
+  archives: ArchiveSummary[];
+}
+
+/**
+ * Channel filter and pagination for the threads endpoints
+ */
+export interface ThreadsPageOptions {
+  /** Only messages in this channel (name or ID) */
+  channel?: string;
+  /** Number of matching messages to skip */
+  offset?: number;
+  /** Maximum number of messages to return */
+  limit?: number;
+}
+
+/**
+ * Options for the JSON threads endpoint
+ */
+export interface ThreadsJsonOptions extends ThreadsPageOptions {
+  /** Columns to return (default: all) */
+  fields?: string[];
+}
+
+/**
+ * Response from the threads-in-range endpoint
 export interface ThreadsInRangeResponse {
   available: YearWeek[];
 }
@@ -82,4 +264,6 @@ export interface SlackArchiveClientOptions {
   mode?: ClientMode;
   /** Optional custom fetch implementation */
   fetch?: typeof fetch;
+  /** API key sent as `Authorization: Bearer <key>` when the server has `[auth]` configured */
+  apiKey?: string;
 }